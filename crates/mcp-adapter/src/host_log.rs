@@ -0,0 +1,209 @@
+//! Structured invoke events for Greentic's telemetry logger.
+//!
+//! Built with the `host-logging` feature, the adapter imports
+//! `greentic:telemetry/logger-api@1.0.0` and reports every invoke there: its
+//! operation, tool, correlation id, duration, and outcome. The span context
+//! carries the invoke's tenant, flow, and node, and its start and end times.
+//! Without the feature, or when the host import panics, events are dropped
+//! and the invoke proceeds as usual.
+
+use std::cell::RefCell;
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+use crate::AdapterResult;
+use crate::bindings::exports::greentic::component::node::ExecCtx;
+
+/// `provider` of every span the adapter logs under.
+const PROVIDER: &str = "greentic-mcp-adapter";
+
+/// Flow position of the invoke in flight, for its span context.
+#[derive(Clone, Debug, Default)]
+struct Span {
+    tenant: String,
+    flow_id: String,
+    node_id: Option<String>,
+}
+
+thread_local! {
+    static SPAN: RefCell<Option<Span>> = const { RefCell::new(None) };
+}
+
+/// Run `f`, logging its invokes under the span of `ctx`.
+pub(crate) fn in_span<T>(ctx: &ExecCtx, f: impl FnOnce() -> T) -> T {
+    let span = Span {
+        tenant: ctx.tenant.tenant.clone(),
+        flow_id: ctx.flow_id.clone(),
+        node_id: ctx.node_id.clone(),
+    };
+    let previous = SPAN.with(|current| current.replace(Some(span)));
+    let result = f();
+    SPAN.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+/// Level, message, and fields of the event reporting one invoke.
+///
+/// Error messages are left out: they may carry tool output, and the error
+/// code and status are enough to find the failing call by correlation id.
+pub(crate) fn invoke_event<T>(
+    operation: &str,
+    tool: Option<&str>,
+    correlation_id: Option<&str>,
+    elapsed: Duration,
+    result: &AdapterResult<T>,
+) -> (Level, String, Value) {
+    let mut fields = json!({
+        "operation": operation,
+        "tool": tool,
+        "correlation_id": correlation_id,
+        "duration_ms": elapsed.as_millis() as u64,
+    });
+    match result {
+        Ok(_) => {
+            fields["outcome"] = json!("ok");
+            (Level::Info, format!("{operation} succeeded"), fields)
+        }
+        Err(err) => {
+            fields["outcome"] = json!("error");
+            fields["error_code"] = json!(err.error.code);
+            fields["status"] = json!(err.error.status);
+            fields["category"] = json!(err.error.category);
+            let level = if err.error.status >= 500 {
+                Level::Error
+            } else {
+                Level::Warn
+            };
+            let message = format!("{operation} failed with {}", err.error.code);
+            (level, message, fields)
+        }
+    }
+}
+
+/// Report one invoke to the host log.
+pub(crate) fn invoke<T>(
+    operation: &str,
+    tool: Option<&str>,
+    correlation_id: Option<&str>,
+    elapsed: Duration,
+    result: &AdapterResult<T>,
+) {
+    if cfg!(feature = "host-logging") {
+        let (level, message, fields) =
+            invoke_event(operation, tool, correlation_id, elapsed, result);
+        emit(elapsed, &log_fields(level, &message, &fields));
+    }
+}
+
+/// Flatten an event into the logger's string pairs: `level` and `message`
+/// first, then every non-null field, strings unquoted.
+pub(crate) fn log_fields(level: Level, message: &str, fields: &Value) -> Vec<(String, String)> {
+    let level = match level {
+        Level::Info => "info",
+        Level::Warn => "warn",
+        Level::Error => "error",
+    };
+    let mut pairs = vec![
+        ("level".to_owned(), level.to_owned()),
+        ("message".to_owned(), message.to_owned()),
+    ];
+    if let Value::Object(fields) = fields {
+        for (key, value) in fields {
+            let value = match value {
+                Value::Null => continue,
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            pairs.push((key.clone(), value));
+        }
+    }
+    pairs
+}
+
+#[cfg(feature = "host-logging")]
+fn emit(elapsed: Duration, fields: &[(String, String)]) {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use crate::log_bindings::greentic::telemetry::logger_api as log;
+
+    let span = SPAN
+        .with(|current| current.borrow().clone())
+        .unwrap_or_default();
+    let end_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_millis() as i64);
+    let span = log::SpanContext {
+        tenant: span.tenant,
+        session_id: None,
+        flow_id: span.flow_id,
+        node_id: span.node_id,
+        provider: PROVIDER.to_owned(),
+        start_ms: Some(end_ms - elapsed.as_millis() as i64),
+        end_ms: Some(end_ms),
+    };
+    // Logging must never fail the invoke it reports on.
+    let _ = crate::contain_panic("log", || log::log(&span, fields, None));
+}
+
+#[cfg(not(feature = "host-logging"))]
+fn emit(_elapsed: Duration, _fields: &[(String, String)]) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ErrorEnvelope, Rendered};
+
+    #[test]
+    fn events_report_outcome_without_error_messages() {
+        let ok: AdapterResult<Rendered> = Ok(Rendered::Value(json!({ "ok": true })));
+        let (level, message, fields) = invoke_event(
+            "call",
+            Some("echo"),
+            Some("req-1"),
+            Duration::from_millis(42),
+            &ok,
+        );
+        assert_eq!(level, Level::Info);
+        assert_eq!(message, "call succeeded");
+        assert_eq!(
+            fields,
+            json!({
+                "operation": "call",
+                "tool": "echo",
+                "correlation_id": "req-1",
+                "duration_ms": 42,
+                "outcome": "ok",
+            })
+        );
+
+        let failed: AdapterResult<Rendered> = Err(Box::new(ErrorEnvelope::new(
+            "MCP_ROUTER_ERROR",
+            502,
+            "secret upstream detail".into(),
+            Some("echo".into()),
+            Value::Null,
+        )));
+        let (level, message, fields) =
+            invoke_event("call", Some("echo"), None, Duration::ZERO, &failed);
+        assert_eq!(level, Level::Error);
+        assert_eq!(message, "call failed with MCP_ROUTER_ERROR");
+        assert_eq!(fields["status"], json!(502));
+        assert_eq!(fields["category"], json!("transport"));
+        assert!(!fields.to_string().contains("secret upstream detail"));
+
+        let pairs = log_fields(level, &message, &fields);
+        assert_eq!(pairs[0], ("level".into(), "error".into()));
+        assert_eq!(pairs[1], ("message".into(), message.clone()));
+        assert!(pairs.contains(&("tool".into(), "echo".into())));
+        assert!(pairs.contains(&("status".into(), "502".into())));
+        assert!(!pairs.iter().any(|(key, _)| key == "correlation_id"));
+    }
+}
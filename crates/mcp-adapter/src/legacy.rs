@@ -0,0 +1,472 @@
+//! Router shim for `wasix:mcp@25.3.26`.
+//!
+//! Built with the `legacy-router` feature, the adapter imports the previous
+//! router revision and lifts its types to the 25.06.18 surface the rest of the
+//! adapter speaks:
+//!
+//! - `describe-server` is assembled from `name` and `capabilities`.
+//! - JSON travels in `value` records instead of plain strings.
+//! - A tool's `tool-meta` becomes meta entries: `output_hint`, `config`,
+//!   `secret_requirements`, then its `config-meta` and `extra` entries.
+//! - `call-tool-result` becomes a completed response, since 25.3.26 routers
+//!   cannot elicit.
+//! - An `embedded` block's resource contents become the embedded resource's
+//!   `data`: the text of a text resource, or the base64 of a blob.
+//!
+//! Fields the older revision lacks (titles, structured content, server
+//! resources and meta, resource metadata) come back as `None`. The
+//! revision's `complete` is not used, as the adapter has no completion
+//! operation.
+
+use serde_json::{Value, json};
+
+use crate::legacy_bindings::wasix::mcp25_3_26::router as legacy;
+use crate::{
+    CallFailure, McpRouter, PromptFailure, ReadFailure, RouterError, contain_panic, router,
+};
+
+pub(crate) struct LegacyRouter;
+
+impl McpRouter for LegacyRouter {
+    fn describe_server(&self) -> Result<router::ServerDescription, RouterError> {
+        let name = contain_panic("name", legacy::name)?;
+        let capabilities = contain_panic("capabilities", legacy::capabilities)?;
+        Ok(map_server_description(name, capabilities))
+    }
+
+    fn instructions(&self) -> Result<String, RouterError> {
+        contain_panic("instructions", legacy::instructions)
+    }
+
+    fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+        contain_panic("list-tools", legacy::list_tools)
+            .map(|tools| tools.into_iter().map(map_tool).collect())
+    }
+
+    fn call_tool(&self, tool: &str, arguments: &Value) -> Result<router::Response, CallFailure> {
+        let args_json = serde_json::to_string(arguments)
+            .map_err(|err| CallFailure::Transport(err.to_string()))?;
+
+        let arguments = legacy::Value { json: args_json };
+        match contain_panic("call-tool", || legacy::call_tool(tool, &arguments))? {
+            Ok(result) => Ok(router::Response::Completed(map_tool_result(result))),
+            Err(err) => Err(CallFailure::Tool(map_tool_error(err))),
+        }
+    }
+
+    fn list_resources(&self) -> Result<Vec<router::McpResource>, RouterError> {
+        contain_panic("list-resources", legacy::list_resources)
+            .map(|resources| resources.into_iter().map(map_resource).collect())
+    }
+
+    fn read_resource(&self, uri: &str) -> Result<router::ReadResourceResult, ReadFailure> {
+        match contain_panic("read-resource", || legacy::read_resource(uri))? {
+            Ok(result) => Ok(map_read_resource_result(result)),
+            Err(err) => Err(ReadFailure::Resource(map_resource_error(err))),
+        }
+    }
+
+    fn list_prompts(&self) -> Result<Vec<router::Prompt>, RouterError> {
+        contain_panic("list-prompts", legacy::list_prompts)
+            .map(|prompts| prompts.into_iter().map(map_prompt).collect())
+    }
+
+    fn get_prompt(&self, name: &str) -> Result<router::GetPromptResult, PromptFailure> {
+        match contain_panic("get-prompt", || legacy::get_prompt(name))? {
+            Ok(result) => Ok(map_get_prompt_result(result)),
+            Err(err) => Err(PromptFailure::Prompt(map_prompt_error(err))),
+        }
+    }
+}
+
+fn map_annotations(ann: Option<legacy::Annotations>) -> Option<router::Annotations> {
+    ann.map(|ann| router::Annotations {
+        audience: ann.audience.map(|roles| {
+            roles
+                .into_iter()
+                .map(|role| match role {
+                    legacy::Role::User => router::Role::User,
+                    legacy::Role::Assistant => router::Role::Assistant,
+                })
+                .collect()
+        }),
+        priority: ann.priority,
+        timestamp: ann.timestamp,
+    })
+}
+
+fn map_tool(tool: legacy::Tool) -> router::Tool {
+    router::Tool {
+        name: tool.name,
+        title: None,
+        description: tool.description,
+        input_schema: tool.input_schema.json,
+        output_schema: tool.output_schema.map(|schema| schema.json),
+        annotations: tool.annotations.map(|ann| router::ToolAnnotations {
+            read_only: ann.read_only,
+            destructive: ann.destructive,
+            streaming: ann.streaming,
+            experimental: ann.experimental,
+        }),
+        meta: tool.meta.map(map_tool_meta),
+    }
+}
+
+fn map_tool_meta(meta: legacy::ToolMeta) -> Vec<router::MetaEntry> {
+    let entry = |key: &str, value: Value| router::MetaEntry {
+        key: key.into(),
+        value: value.to_string(),
+    };
+    let mut entries = Vec::new();
+    if let Some(hint) = meta.output_hint {
+        entries.push(entry("output_hint", json!(hint)));
+    }
+    if let Some(config) = meta.config {
+        let config: Vec<Value> = config
+            .into_iter()
+            .map(|desc| {
+                json!({
+                    "name": desc.name,
+                    "description": desc.description,
+                    "required": desc.required,
+                })
+            })
+            .collect();
+        entries.push(entry("config", json!(config)));
+    }
+    if let Some(secrets) = meta.secrets {
+        let secrets: Vec<Value> = secrets
+            .into_iter()
+            .map(|desc| {
+                json!({
+                    "key": desc.name,
+                    "description": desc.description,
+                    "required": desc.required,
+                })
+            })
+            .collect();
+        entries.push(entry("secret_requirements", json!(secrets)));
+    }
+    entries.extend(
+        meta.config_meta
+            .into_iter()
+            .chain(meta.extra)
+            .flatten()
+            .map(map_meta_entry),
+    );
+    entries
+}
+
+/// 25.3.26 meta values are plain strings; the adapter reads values that are
+/// not JSON as strings.
+fn map_meta_entry(entry: legacy::MetaEntry) -> router::MetaEntry {
+    router::MetaEntry {
+        key: entry.key,
+        value: entry.value,
+    }
+}
+
+fn map_server_description(
+    name: String,
+    caps: legacy::ServerCapabilities,
+) -> router::ServerDescription {
+    router::ServerDescription {
+        name,
+        title: None,
+        capabilities: router::ServerCapabilities {
+            prompts: caps.prompts.map(|cap| router::PromptsCapability {
+                list_changed: cap.list_changed,
+            }),
+            resources: caps.resources.map(|cap| router::ResourcesCapability {
+                subscribe: cap.subscribe,
+                list_changed: cap.list_changed,
+            }),
+            tools: caps.tools.map(|cap| router::ToolsCapability {
+                list_changed: cap.list_changed,
+            }),
+            completions: caps.completions.map(|cap| router::CompletionsCapability {
+                enabled: cap.enabled,
+            }),
+        },
+        resources: None,
+        resource_metadata: None,
+        meta: None,
+    }
+}
+
+fn map_text(text: legacy::TextContent) -> router::TextContent {
+    router::TextContent {
+        text: text.text,
+        annotations: map_annotations(text.annotations),
+    }
+}
+
+fn map_image(image: legacy::ImageContent) -> router::ImageContent {
+    router::ImageContent {
+        data: image.data,
+        mime_type: image.mime_type,
+        annotations: map_annotations(image.annotations),
+    }
+}
+
+fn map_embedded_resource(resource: legacy::EmbeddedResource) -> router::EmbeddedResource {
+    let (uri, mime_type, data) = match resource.resource_contents {
+        legacy::ResourceContents::Text(text) => (text.uri, text.mime_type, text.text),
+        legacy::ResourceContents::Blob(blob) => (blob.uri, blob.mime_type, blob.blob),
+    };
+    router::EmbeddedResource {
+        uri,
+        title: None,
+        description: None,
+        mime_type,
+        data,
+        annotations: map_annotations(resource.annotations),
+    }
+}
+
+fn map_content(content: legacy::Content) -> router::ContentBlock {
+    match content {
+        legacy::Content::Text(text) => router::ContentBlock::Text(map_text(text)),
+        legacy::Content::Image(image) => router::ContentBlock::Image(map_image(image)),
+        legacy::Content::Audio(audio) => router::ContentBlock::Audio(router::AudioContent {
+            data: audio.data,
+            mime_type: audio.mime_type,
+            annotations: map_annotations(audio.annotations),
+        }),
+        legacy::Content::Embedded(resource) => {
+            router::ContentBlock::EmbeddedResource(map_embedded_resource(resource))
+        }
+    }
+}
+
+fn map_tool_result(result: legacy::CallToolResult) -> router::ToolResult {
+    router::ToolResult {
+        content: result.content.into_iter().map(map_content).collect(),
+        structured_content: None,
+        progress: result.progress.map(|items| {
+            items
+                .into_iter()
+                .map(|item| router::ProgressNotification {
+                    progress: item.progress,
+                    message: item.message,
+                    annotations: map_annotations(item.annotations),
+                })
+                .collect()
+        }),
+        meta: result
+            .meta
+            .map(|entries| entries.into_iter().map(map_meta_entry).collect()),
+        is_error: result.is_error,
+    }
+}
+
+fn map_tool_error(err: legacy::ToolError) -> router::ToolError {
+    match err {
+        legacy::ToolError::InvalidParameters(msg) => router::ToolError::InvalidParameters(msg),
+        legacy::ToolError::ExecutionError(msg) => router::ToolError::ExecutionError(msg),
+        legacy::ToolError::SchemaError(msg) => router::ToolError::SchemaError(msg),
+        legacy::ToolError::NotFound(msg) => router::ToolError::NotFound(msg),
+    }
+}
+
+fn map_resource(resource: legacy::McpResource) -> router::McpResource {
+    router::McpResource {
+        uri: resource.uri,
+        name: resource.name,
+        title: None,
+        description: resource.description,
+        mime_type: resource.mime_type,
+        annotations: map_annotations(resource.annotations),
+    }
+}
+
+fn map_read_resource_result(result: legacy::ReadResourceResult) -> router::ReadResourceResult {
+    router::ReadResourceResult {
+        contents: result
+            .contents
+            .into_iter()
+            .map(|contents| match contents {
+                legacy::ResourceContents::Text(text) => {
+                    router::ResourceContents::Text(router::TextResourceContents {
+                        uri: text.uri,
+                        mime_type: text.mime_type,
+                        text: text.text,
+                    })
+                }
+                legacy::ResourceContents::Blob(blob) => {
+                    router::ResourceContents::Blob(router::BlobResourceContents {
+                        uri: blob.uri,
+                        mime_type: blob.mime_type,
+                        blob: blob.blob,
+                    })
+                }
+            })
+            .collect(),
+    }
+}
+
+fn map_resource_error(err: legacy::ResourceError) -> router::ResourceError {
+    match err {
+        legacy::ResourceError::ExecutionError(msg) => router::ResourceError::ExecutionError(msg),
+        legacy::ResourceError::NotFound(msg) => router::ResourceError::NotFound(msg),
+    }
+}
+
+fn map_prompt(prompt: legacy::Prompt) -> router::Prompt {
+    router::Prompt {
+        name: prompt.name,
+        description: prompt.description,
+        arguments: prompt.arguments.map(|args| {
+            args.into_iter()
+                .map(|arg| router::PromptArgument {
+                    name: arg.name,
+                    description: arg.description,
+                    required: arg.required,
+                })
+                .collect()
+        }),
+    }
+}
+
+fn map_get_prompt_result(result: legacy::GetPromptResult) -> router::GetPromptResult {
+    router::GetPromptResult {
+        description: result.description,
+        messages: result
+            .messages
+            .into_iter()
+            .map(|message| router::PromptMessage {
+                role: match message.role {
+                    legacy::PromptMessageRole::User => router::PromptMessageRole::User,
+                    legacy::PromptMessageRole::Assistant => router::PromptMessageRole::Assistant,
+                },
+                content: match message.content {
+                    legacy::PromptMessageContent::Text(text) => {
+                        router::PromptMessageContent::Text(map_text(text))
+                    }
+                    legacy::PromptMessageContent::Image(image) => {
+                        router::PromptMessageContent::Image(map_image(image))
+                    }
+                    legacy::PromptMessageContent::McpResource(resource) => {
+                        router::PromptMessageContent::McpResource(map_embedded_resource(resource))
+                    }
+                },
+            })
+            .collect(),
+    }
+}
+
+fn map_prompt_error(err: legacy::PromptError) -> router::PromptError {
+    match err {
+        legacy::PromptError::InvalidParameters(msg) => router::PromptError::InvalidParameters(msg),
+        legacy::PromptError::InternalError(msg) => router::PromptError::InternalError(msg),
+        legacy::PromptError::NotFound(msg) => router::PromptError::NotFound(msg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_results_are_lifted_to_completed_responses() {
+        let result = map_tool_result(legacy::CallToolResult {
+            content: vec![
+                legacy::Content::Text(legacy::TextContent {
+                    text: "hi".into(),
+                    annotations: Some(legacy::Annotations {
+                        audience: Some(vec![legacy::Role::Assistant]),
+                        priority: None,
+                        timestamp: None,
+                    }),
+                }),
+                legacy::Content::Embedded(legacy::EmbeddedResource {
+                    resource_contents: legacy::ResourceContents::Blob(
+                        legacy::BlobResourceContents {
+                            uri: "file:///a.txt".into(),
+                            mime_type: Some("text/plain".into()),
+                            blob: "aGk=".into(),
+                        },
+                    ),
+                    annotations: None,
+                }),
+            ],
+            progress: None,
+            meta: Some(vec![legacy::MetaEntry {
+                key: "trace".into(),
+                value: "abc".into(),
+            }]),
+            is_error: Some(false),
+        });
+        assert!(result.structured_content.is_none());
+        assert_eq!(
+            result.meta.as_ref().map(|meta| meta[0].key.as_str()),
+            Some("trace")
+        );
+        assert!(matches!(
+            &result.content[0],
+            router::ContentBlock::Text(text)
+                if text.annotations.as_ref().and_then(|a| a.audience.as_deref())
+                    == Some(&[router::Role::Assistant][..])
+        ));
+        assert!(matches!(
+            &result.content[1],
+            router::ContentBlock::EmbeddedResource(res)
+                if res.uri == "file:///a.txt" && res.data == "aGk=" && res.title.is_none()
+        ));
+
+        let tool = map_tool(legacy::Tool {
+            name: "echo".into(),
+            description: "Echo".into(),
+            input_schema: legacy::Value { json: "{}".into() },
+            output_schema: Some(legacy::Value {
+                json: r#"{"type":"string"}"#.into(),
+            }),
+            annotations: None,
+            meta: Some(legacy::ToolMeta {
+                output_hint: None,
+                config: None,
+                secrets: Some(vec![legacy::SecretDescriptor {
+                    name: "API_KEY".into(),
+                    description: "Upstream key".into(),
+                    required: true,
+                }]),
+                config_meta: None,
+                extra: Some(vec![legacy::MetaEntry {
+                    key: "cache_ttl_seconds".into(),
+                    value: "60".into(),
+                }]),
+            }),
+        });
+        assert_eq!(tool.name, "echo");
+        assert_eq!(tool.input_schema, "{}");
+        assert_eq!(tool.output_schema.as_deref(), Some(r#"{"type":"string"}"#));
+        let meta = tool.meta.expect("tool meta");
+        assert_eq!(meta[0].key, "secret_requirements");
+        assert_eq!(
+            serde_json::from_str::<Value>(&meta[0].value).expect("secrets json")[0]["key"],
+            "API_KEY"
+        );
+        assert_eq!(
+            (meta[1].key.as_str(), meta[1].value.as_str()),
+            ("cache_ttl_seconds", "60")
+        );
+
+        let desc = map_server_description(
+            "legacy".into(),
+            legacy::ServerCapabilities {
+                prompts: None,
+                resources: None,
+                tools: Some(legacy::ToolsCapability {
+                    list_changed: Some(true),
+                }),
+                completions: Some(legacy::CompletionsCapability { enabled: true }),
+            },
+        );
+        assert_eq!(desc.name, "legacy");
+        assert!(desc.capabilities.completions.is_some_and(|cap| cap.enabled));
+        assert!(matches!(
+            map_tool_error(legacy::ToolError::NotFound("nope".into())),
+            router::ToolError::NotFound(msg) if msg == "nope"
+        ));
+    }
+}
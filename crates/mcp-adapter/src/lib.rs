@@ -1,21 +1,58 @@
 #![allow(dead_code)]
 
 mod bindings {
+    #[cfg(not(feature = "kv-handoff"))]
     wit_bindgen::generate!({
         path: "wit",
         world: "adapter",
         generate_unused_types: true,
         generate_all,
     });
+    #[cfg(feature = "kv-handoff")]
+    wit_bindgen::generate!({
+        path: "wit",
+        world: "adapter-kv",
+        generate_unused_types: true,
+        generate_all,
+    });
+}
+
+#[cfg(feature = "legacy-router")]
+mod legacy_bindings {
+    wit_bindgen::generate!({
+        path: "wit",
+        world: "legacy-router",
+        generate_all,
+    });
+}
+
+#[cfg(feature = "host-logging")]
+mod log_bindings {
+    wit_bindgen::generate!({
+        path: "wit",
+        world: "host-log",
+        generate_all,
+    });
 }
 
+mod host_log;
+#[cfg(feature = "legacy-router")]
+mod legacy;
+mod schema;
+
 use bindings::exports::greentic::component::node::{
     ExecCtx, Guest, InvokeResult, LifecycleStatus, NodeError, StreamEvent,
 };
-use bindings::wasix::mcp::router;
-use serde::{Deserialize, Serialize};
+use bindings::wasix::mcp25_6_18::router;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::value::RawValue;
 use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
 use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
 const PROTOCOL: &str = "25.06.18";
@@ -26,14 +63,65 @@ type AdapterResult<T> = Result<T, Box<ErrorEnvelope>>;
 struct AdapterRequest {
     operation: Option<String>,
     tool: Option<String>,
+    prompt: Option<String>,
     #[serde(default = "default_arguments")]
     arguments: Value,
+    #[serde(rename = "timeout_ms")]
+    timeout_ms: Option<u64>,
+    #[serde(default)]
+    render: Render,
+    #[serde(rename = "correlation_id")]
+    correlation_id: Option<String>,
+}
+
+/// Argument key carrying call metadata to the router, which has no separate
+/// meta parameter.
+const ARGUMENTS_META: &str = "_meta";
+
+/// Key of the caller's correlation id in call metadata and envelopes.
+const CORRELATION_ID: &str = "correlation_id";
+
+/// How the `messages` of an envelope are rendered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Render {
+    /// One card per content block.
+    #[default]
+    Cards,
+    /// A single markdown text card, for channels that show one message per turn.
+    Markdown,
 }
 
 #[derive(Debug)]
 enum Operation {
     List,
+    CheckChanged,
     Call,
+    ResourcesList,
+    ResourcesRead,
+    PromptsList,
+    PromptsGet,
+    Describe,
+    Metrics,
+    DebugEcho,
+}
+
+impl Operation {
+    /// The name [`parse_operation`] accepts for this operation.
+    fn as_str(&self) -> &'static str {
+        match self {
+            Operation::List => "list",
+            Operation::CheckChanged => "list.check_changed",
+            Operation::Call => "call",
+            Operation::ResourcesList => "resources.list",
+            Operation::ResourcesRead => "resources.read",
+            Operation::PromptsList => "prompts.list",
+            Operation::PromptsGet => "prompts.get",
+            Operation::Describe => "describe",
+            Operation::Metrics => "metrics",
+            Operation::DebugEcho => "debug.echo",
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -41,750 +129,5190 @@ struct ErrorBody {
     code: &'static str,
     message: String,
     status: u16,
+    /// JSON-RPC error code matching `code` and `status`.
+    jsonrpc_code: i32,
+    category: ErrorCategory,
     tool: Option<String>,
     protocol: &'static str,
     details: Value,
+    /// Suggested wait before the host retries, once the adapter's own retries
+    /// are exhausted; also carried as the node error's `backoff-ms`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_after_ms: Option<u64>,
+    /// The router answered after the caller stopped waiting, and the answer
+    /// was discarded. The call may have taken effect, so the error is never
+    /// retryable.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    late: bool,
+}
+
+/// Stable classification of a failure for orchestrators.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ErrorCategory {
+    /// The adapter request itself is invalid.
+    Config,
+    /// The tool, resource, or prompt reported a failure or is unavailable.
+    Tool,
+    /// The router could not be reached, panicked, or ran out of time, or the
+    /// host cancelled the call.
+    Transport,
+    /// Arguments were rejected as not matching the tool's schema.
+    Schema,
+}
+
+/// JSON-RPC codes for the adapter's errors, as MCP servers report them.
+mod jsonrpc {
+    pub const INVALID_REQUEST: i32 = -32600;
+    pub const INVALID_PARAMS: i32 = -32602;
+    pub const INTERNAL_ERROR: i32 = -32603;
+    /// Start of the implementation-defined server error range.
+    pub const SERVER_ERROR: i32 = -32000;
+    pub const REQUEST_TIMEOUT: i32 = -32001;
+    pub const RESOURCE_NOT_FOUND: i32 = -32002;
+    pub const FORBIDDEN: i32 = -32003;
+    /// As LSP's `RequestCancelled`; JSON-RPC itself reserves no code for it.
+    pub const REQUEST_CANCELLED: i32 = -32800;
+}
+
+/// JSON-RPC code and category for an adapter error code and HTTP status.
+fn classify(code: &str, status: u16) -> (i32, ErrorCategory) {
+    match (code, status) {
+        ("MCP_CONFIG_ERROR", _) => (jsonrpc::INVALID_REQUEST, ErrorCategory::Config),
+        ("MCP_TOOL_ERROR", 400 | 422) => (jsonrpc::INVALID_PARAMS, ErrorCategory::Schema),
+        ("MCP_TOOL_ERROR" | "MCP_PROMPT_ERROR", 400 | 404) => {
+            (jsonrpc::INVALID_PARAMS, ErrorCategory::Tool)
+        }
+        ("MCP_RESOURCE_ERROR", 404) => (jsonrpc::RESOURCE_NOT_FOUND, ErrorCategory::Tool),
+        ("MCP_TOOL_FORBIDDEN", _) => (jsonrpc::FORBIDDEN, ErrorCategory::Tool),
+        ("MCP_TIMEOUT", _) => (jsonrpc::REQUEST_TIMEOUT, ErrorCategory::Transport),
+        ("MCP_CANCELLED", _) => (jsonrpc::REQUEST_CANCELLED, ErrorCategory::Transport),
+        ("MCP_ROUTER_ERROR", _) => (jsonrpc::SERVER_ERROR, ErrorCategory::Transport),
+        ("MCP_ROUTER_PANIC", _) => (jsonrpc::INTERNAL_ERROR, ErrorCategory::Transport),
+        _ => (jsonrpc::INTERNAL_ERROR, ErrorCategory::Tool),
+    }
 }
 
 #[derive(Debug, Serialize)]
 struct ErrorEnvelope {
     ok: bool,
     error: ErrorBody,
+    /// The request's `correlation_id`, echoed for log stitching.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    correlation_id: Option<String>,
 }
 
 impl ErrorEnvelope {
+    fn new(
+        code: &'static str,
+        status: u16,
+        message: String,
+        tool: Option<String>,
+        details: Value,
+    ) -> Self {
+        let (jsonrpc_code, category) = classify(code, status);
+        ErrorEnvelope {
+            ok: false,
+            error: ErrorBody {
+                code,
+                message,
+                status,
+                jsonrpc_code,
+                category,
+                tool,
+                protocol: PROTOCOL,
+                details,
+                retry_after_ms: None,
+                late: false,
+            },
+            correlation_id: None,
+        }
+    }
+
+    /// Mark an error raised after the router answered; see [`ErrorBody::late`].
+    fn late(mut self) -> Self {
+        self.error.late = true;
+        self
+    }
+
+    /// Apply the configured detail level before the envelope leaves the adapter.
+    fn redact(mut self, detail: ErrorDetail) -> Self {
+        if detail == ErrorDetail::Terse {
+            self.error.message = terse_message(self.error.code).to_string();
+            self.error.details = Value::Null;
+        }
+        self
+    }
+
     fn node_error(&self) -> NodeError {
-        let retryable = self.error.status >= 500;
+        let retryable = self.error.status >= 500 && !self.error.late;
         let details = serde_json::to_string(self).unwrap_or_else(|_| self.error.message.clone());
         NodeError {
             code: self.error.code.to_string(),
             message: self.error.message.clone(),
             retryable,
-            backoff_ms: None,
+            backoff_ms: self.error.retry_after_ms,
             details: Some(details),
         }
     }
 }
 
+/// Environment variable selecting how much detail error envelopes carry.
+const ERROR_DETAIL_ENV: &str = "GREENTIC_MCP_ADAPTER_ERROR_DETAIL";
+
+/// How much of the underlying failure error envelopes expose.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum ErrorDetail {
+    /// Router messages and raw payloads are embedded (development).
+    #[default]
+    Full,
+    /// Only stable codes, statuses, and generic messages (production).
+    Terse,
+}
+
+static ERROR_DETAIL: AtomicU8 = AtomicU8::new(ErrorDetail::Full as u8);
+
+impl ErrorDetail {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "full" | "debug" => Some(ErrorDetail::Full),
+            "terse" | "minimal" => Some(ErrorDetail::Terse),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            ErrorDetail::Full => "full",
+            ErrorDetail::Terse => "terse",
+        }
+    }
+
+    fn current() -> Self {
+        if ERROR_DETAIL.load(Ordering::Relaxed) == ErrorDetail::Terse as u8 {
+            ErrorDetail::Terse
+        } else {
+            ErrorDetail::Full
+        }
+    }
+
+    fn install(self) {
+        ERROR_DETAIL.store(self as u8, Ordering::Relaxed);
+    }
+}
+
+/// Environment variable selecting how `call` treats assistant-only content.
+const AUDIENCE_ENV: &str = "GREENTIC_MCP_ADAPTER_AUDIENCE";
+
+/// What `call` does with content blocks whose `annotations.audience` names
+/// only the assistant.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Audience {
+    /// Every block is rendered into `messages` alike.
+    #[default]
+    All,
+    /// Assistant-only blocks are carded under `assistant_content` instead of
+    /// `messages`.
+    Split,
+    /// Assistant-only blocks are left out of the envelope.
+    User,
+}
+
+static AUDIENCE: AtomicU8 = AtomicU8::new(Audience::All as u8);
+
+impl Audience {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "all" => Some(Audience::All),
+            "split" => Some(Audience::Split),
+            "user" => Some(Audience::User),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Audience::All => "all",
+            Audience::Split => "split",
+            Audience::User => "user",
+        }
+    }
+
+    fn current() -> Self {
+        match AUDIENCE.load(Ordering::Relaxed) {
+            mode if mode == Audience::Split as u8 => Audience::Split,
+            mode if mode == Audience::User as u8 => Audience::User,
+            _ => Audience::All,
+        }
+    }
+
+    fn install(self) {
+        AUDIENCE.store(self as u8, Ordering::Relaxed);
+    }
+}
+
+/// Environment variable selecting the router probe run by `on-start`.
+const STARTUP_PROBE_ENV: &str = "GREENTIC_MCP_ADAPTER_STARTUP_PROBE";
+
+/// Router call `on-start` makes to fail fast on a missing or broken router.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum StartupProbe {
+    /// The router is first called by the first `invoke`.
+    #[default]
+    None,
+    /// `list-tools` through the composed router.
+    ListTools,
+    /// `describe-server`, for routers whose listing is expensive.
+    Describe,
+}
+
+impl StartupProbe {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "none" => Some(StartupProbe::None),
+            "list_tools" | "list-tools" => Some(StartupProbe::ListTools),
+            "describe" => Some(StartupProbe::Describe),
+            _ => None,
+        }
+    }
+
+    /// Make the probe call, describing the failure if the router is unusable.
+    /// The envelope's details, such as a panic message, follow unless redacted.
+    fn run<R: McpRouter>(self, router: &R) -> Result<(), String> {
+        let (operation, outcome) = match self {
+            StartupProbe::None => return Ok(()),
+            StartupProbe::ListTools => ("list-tools", router.list_tools().map(drop)),
+            StartupProbe::Describe => ("describe-server", router.describe_server().map(drop)),
+        };
+        outcome.map_err(|err| {
+            let envelope = transport_error(err, None).redact(ErrorDetail::current());
+            let mut message = format!(
+                "router health check failed: {operation} returned {}: {}",
+                envelope.error.code, envelope.error.message
+            );
+            if !envelope.error.details.is_null() {
+                message.push_str(&format!(" ({})", envelope.error.details));
+            }
+            message
+        })
+    }
+}
+
+/// Environment variable capping the size of a single content payload.
+const MAX_CHUNK_ENV: &str = "GREENTIC_MCP_ADAPTER_MAX_CHUNK_BYTES";
+
+/// Smallest accepted cap: one base64 quantum, and room for any UTF-8 char.
+const MIN_CHUNK_BYTES: usize = 4;
+
+/// Largest text/image/audio payload emitted in one piece; 0 means unlimited.
+static MAX_CHUNK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// Environment variable capping the size of a whole `invoke` body.
+const MAX_RESULT_ENV: &str = "GREENTIC_MCP_ADAPTER_MAX_RESULT_BYTES";
+
+/// Largest `invoke` body before content blocks give way to placeholders; 0
+/// means unlimited.
+static MAX_RESULT_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// Environment variable naming the host KV namespace for oversized payloads.
+const KV_HANDOFF_ENV: &str = "GREENTIC_MCP_ADAPTER_KV_HANDOFF_NS";
+
+/// KV namespace receiving oversized payloads in `invoke`; empty disables it.
+static KV_HANDOFF_NS: RwLock<String> = RwLock::new(String::new());
+
+/// Host KV store receiving the full payloads that `invoke` truncates, so the
+/// host can fetch them out-of-band.
+struct KvHandoff {
+    namespace: String,
+    put: fn(&str, &str, &str),
+}
+
+impl KvHandoff {
+    fn current() -> Option<Self> {
+        let namespace = KV_HANDOFF_NS
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        (!namespace.is_empty()).then_some(Self {
+            namespace,
+            put: host_kv_put,
+        })
+    }
+
+    /// Store `payload` under a key derived from its content and return the
+    /// handle the host uses to fetch it.
+    fn stash(&self, payload: &str) -> Value {
+        let key = format!(
+            "mcp-adapter/{}-{}",
+            fingerprint(payload.as_bytes()),
+            payload.len()
+        );
+        (self.put)(&self.namespace, &key, payload);
+        json!({
+            "type": "kv_handle",
+            "ns": self.namespace,
+            "key": key,
+            "bytes": payload.len(),
+        })
+    }
+}
+
+#[cfg(feature = "kv-handoff")]
+fn host_kv_put(ns: &str, key: &str, val: &str) {
+    bindings::greentic::host::kv_v1::put(ns, key, val);
+}
+
+/// Never called: `on-start` rejects a namespace without the `kv-handoff` feature.
+#[cfg(not(feature = "kv-handoff"))]
+fn host_kv_put(_ns: &str, _key: &str, _val: &str) {}
+
+/// Environment variable enabling schema-driven coercion of `call` arguments.
+const COERCE_ARGUMENTS_ENV: &str = "GREENTIC_MCP_ADAPTER_COERCE_ARGUMENTS";
+
+/// Whether string arguments are coerced to the types their tool schema expects.
+static COERCE_ARGUMENTS: AtomicBool = AtomicBool::new(false);
+
+/// Environment variables configuring retries of transient `call` failures.
+const RETRY_ATTEMPTS_ENV: &str = "GREENTIC_MCP_ADAPTER_RETRY_ATTEMPTS";
+const RETRY_BACKOFF_ENV: &str = "GREENTIC_MCP_ADAPTER_RETRY_BACKOFF_MS";
+
+static RETRY_ATTEMPTS: AtomicU32 = AtomicU32::new(1);
+static RETRY_BACKOFF_MS: AtomicU64 = AtomicU64::new(100);
+
+/// Retries of transient router failures during `call`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct RetryPolicy {
+    /// Attempts including the first; 1 disables retries.
+    max_attempts: u32,
+    /// Delay before the first retry, doubled before each further one.
+    base_backoff_ms: u64,
+}
+
+impl RetryPolicy {
+    fn current() -> Self {
+        Self {
+            max_attempts: RETRY_ATTEMPTS.load(Ordering::Relaxed),
+            base_backoff_ms: RETRY_BACKOFF_MS.load(Ordering::Relaxed),
+        }
+    }
+
+    fn install(self) {
+        RETRY_ATTEMPTS.store(self.max_attempts, Ordering::Relaxed);
+        RETRY_BACKOFF_MS.store(self.base_backoff_ms, Ordering::Relaxed);
+    }
+
+    /// Delay after failed attempt number `attempt`, counting from 1.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(16);
+        Duration::from_millis(self.base_backoff_ms.saturating_mul(factor))
+    }
+}
+
+fn max_chunk_bytes() -> Option<usize> {
+    match MAX_CHUNK_BYTES.load(Ordering::Relaxed) {
+        0 => None,
+        limit => Some(limit),
+    }
+}
+
+fn max_result_bytes() -> Option<usize> {
+    match MAX_RESULT_BYTES.load(Ordering::Relaxed) {
+        0 => None,
+        limit => Some(limit),
+    }
+}
+
+/// Stable, non-leaky message used for each error code in terse mode.
+fn terse_message(code: &str) -> &'static str {
+    match code {
+        "MCP_TOOL_ERROR" => "tool reported an error",
+        "MCP_RESOURCE_ERROR" => "resource unavailable",
+        "MCP_PROMPT_ERROR" => "prompt unavailable",
+        "MCP_ROUTER_PANIC" => "router panicked",
+        "MCP_CONFIG_ERROR" => "invalid adapter request",
+        "MCP_TIMEOUT" => "router call timed out",
+        "MCP_CANCELLED" => "router call cancelled",
+        "MCP_TOOL_FORBIDDEN" => "tool not permitted",
+        _ => "router unavailable",
+    }
+}
+
 #[derive(Debug, Error)]
 enum RouterError {
     #[error("{0}")]
     Transport(String),
+    #[error("router panicked during {operation}: {message}")]
+    Panic {
+        operation: &'static str,
+        message: String,
+    },
 }
 
 #[derive(Debug, Error)]
 enum CallFailure {
     #[error("tool")]
     Tool(router::ToolError),
+    #[error("tool is not permitted by the adapter's tool filter")]
+    Forbidden,
     #[error("{0}")]
     Transport(String),
+    #[error("router panicked during {operation}: {message}")]
+    Panic {
+        operation: &'static str,
+        message: String,
+    },
+}
+
+#[derive(Debug, Error)]
+enum ReadFailure {
+    #[error("resource")]
+    Resource(router::ResourceError),
+    #[error(transparent)]
+    Router(#[from] RouterError),
+}
+
+#[derive(Debug, Error)]
+enum PromptFailure {
+    #[error("prompt")]
+    Prompt(router::PromptError),
+    #[error(transparent)]
+    Router(#[from] RouterError),
+}
+
+impl From<RouterError> for CallFailure {
+    fn from(err: RouterError) -> Self {
+        match err {
+            RouterError::Transport(msg) => CallFailure::Transport(msg),
+            RouterError::Panic { operation, message } => CallFailure::Panic { operation, message },
+        }
+    }
+}
+
+/// Run a router entry point, turning a panic into [`RouterError::Panic`].
+///
+/// Every router import must go through this so a misbehaving router yields an
+/// `MCP_ROUTER_PANIC` envelope instead of aborting the adapter.
+fn contain_panic<T>(operation: &'static str, f: impl FnOnce() -> T) -> Result<T, RouterError> {
+    catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|msg| msg.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".into());
+        RouterError::Panic { operation, message }
+    })
 }
 
 trait McpRouter {
+    /// Routers that cannot describe themselves report a transport error.
+    fn describe_server(&self) -> Result<router::ServerDescription, RouterError> {
+        Err(RouterError::Transport(
+            "router does not implement describe-server".into(),
+        ))
+    }
+
+    fn instructions(&self) -> Result<String, RouterError> {
+        Ok(String::new())
+    }
+
     fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError>;
     fn call_tool(&self, tool: &str, arguments: &Value) -> Result<router::Response, CallFailure>;
+
+    /// Routers without a resources capability expose an empty list.
+    fn list_resources(&self) -> Result<Vec<router::McpResource>, RouterError> {
+        Ok(Vec::new())
+    }
+
+    fn read_resource(&self, uri: &str) -> Result<router::ReadResourceResult, ReadFailure> {
+        Err(ReadFailure::Resource(router::ResourceError::NotFound(
+            uri.to_string(),
+        )))
+    }
+
+    /// Routers without a prompts capability expose an empty list.
+    fn list_prompts(&self) -> Result<Vec<router::Prompt>, RouterError> {
+        Ok(Vec::new())
+    }
+
+    fn get_prompt(&self, name: &str) -> Result<router::GetPromptResult, PromptFailure> {
+        Err(PromptFailure::Prompt(router::PromptError::NotFound(
+            name.to_string(),
+        )))
+    }
 }
 
+#[cfg(not(feature = "legacy-router"))]
 struct WitRouter;
 
+/// The router import the adapter is built against.
+#[cfg(not(feature = "legacy-router"))]
+type BaseRouter = WitRouter;
+#[cfg(feature = "legacy-router")]
+type BaseRouter = legacy::LegacyRouter;
+
+#[cfg(not(feature = "legacy-router"))]
 impl McpRouter for WitRouter {
+    fn describe_server(&self) -> Result<router::ServerDescription, RouterError> {
+        contain_panic("describe-server", router::describe_server)
+    }
+
+    fn instructions(&self) -> Result<String, RouterError> {
+        contain_panic("instructions", router::instructions)
+    }
+
     fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
-        catch_unwind(router::list_tools)
-            .map_err(|_| RouterError::Transport("router panicked".into()))
+        contain_panic("list-tools", router::list_tools)
     }
 
     fn call_tool(&self, tool: &str, arguments: &Value) -> Result<router::Response, CallFailure> {
         let args_json = serde_json::to_string(arguments)
             .map_err(|err| CallFailure::Transport(err.to_string()))?;
 
-        let call = catch_unwind(AssertUnwindSafe(|| router::call_tool(tool, &args_json)));
-
-        match call {
-            Ok(Ok(response)) => Ok(response),
-            Ok(Err(err)) => Err(CallFailure::Tool(err)),
-            Err(_) => Err(CallFailure::Transport("router panicked".into())),
+        match contain_panic("call-tool", || router::call_tool(tool, &args_json))? {
+            Ok(response) => Ok(response),
+            Err(err) => Err(CallFailure::Tool(err)),
         }
     }
-}
 
-struct Adapter;
+    fn list_resources(&self) -> Result<Vec<router::McpResource>, RouterError> {
+        contain_panic("list-resources", router::list_resources)
+    }
 
-impl Guest for Adapter {
-    fn get_manifest() -> String {
-        serde_json::to_string(&json!({
-            "name": "greentic-mcp-adapter",
-            "version": env!("CARGO_PKG_VERSION"),
-            "protocol": PROTOCOL,
-            "operations": ["list", "call"],
-            "description": "MCP adapter template exporting greentic:component/node@0.5.0 and importing wasix:mcp@25.06.18.",
-        }))
-        .unwrap_or_else(|_| "{}".into())
+    fn read_resource(&self, uri: &str) -> Result<router::ReadResourceResult, ReadFailure> {
+        contain_panic("read-resource", || router::read_resource(uri))?
+            .map_err(ReadFailure::Resource)
     }
 
-    fn on_start(_ctx: ExecCtx) -> Result<LifecycleStatus, String> {
-        Ok(LifecycleStatus::Ok)
+    fn list_prompts(&self) -> Result<Vec<router::Prompt>, RouterError> {
+        contain_panic("list-prompts", router::list_prompts)
     }
 
-    fn on_stop(_ctx: ExecCtx, _reason: String) -> Result<LifecycleStatus, String> {
-        Ok(LifecycleStatus::Ok)
+    fn get_prompt(&self, name: &str) -> Result<router::GetPromptResult, PromptFailure> {
+        contain_panic("get-prompt", || router::get_prompt(name))?.map_err(PromptFailure::Prompt)
     }
+}
 
-    fn invoke(_ctx: ExecCtx, op: String, input: String) -> InvokeResult {
-        match handle_invoke(&WitRouter, &op, &input) {
-            Ok(value) => {
-                let rendered =
-                    serde_json::to_string(&value).unwrap_or_else(|_| "{\"ok\":true}".into());
-                InvokeResult::Ok(rendered)
-            }
-            Err(err) => InvokeResult::Err(err.node_error()),
-        }
+/// Environment variable holding the tool allow/deny document.
+const TOOL_FILTER_ENV: &str = "GREENTIC_MCP_ADAPTER_TOOL_FILTER";
+
+/// Operator restrictions on which router tools the adapter exposes.
+///
+/// Patterns match tool names, with `*` standing for any run of characters.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ToolFilter {
+    /// Tools to expose; every tool when empty.
+    #[serde(default)]
+    allowed_tools: Vec<String>,
+    /// Tools to hide and refuse, even when also allowed.
+    #[serde(default)]
+    denied_tools: Vec<String>,
+}
+
+static TOOL_FILTER: RwLock<Option<ToolFilter>> = RwLock::new(None);
+
+impl ToolFilter {
+    fn current() -> Self {
+        TOOL_FILTER
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+            .unwrap_or_default()
     }
 
-    fn invoke_stream(ctx: ExecCtx, op: String, input: String) -> Vec<StreamEvent> {
-        match Self::invoke(ctx, op, input) {
-            InvokeResult::Ok(body) => vec![StreamEvent::Data(body), StreamEvent::Done],
-            InvokeResult::Err(err) => {
-                let payload = err.details.clone().unwrap_or_else(|| err.message.clone());
-                vec![StreamEvent::Error(payload)]
-            }
+    fn install(self) {
+        *TOOL_FILTER
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(self);
+    }
+
+    fn permits(&self, tool: &str) -> bool {
+        (self.allowed_tools.is_empty()
+            || self
+                .allowed_tools
+                .iter()
+                .any(|pattern| glob_match(pattern, tool)))
+            && !self
+                .denied_tools
+                .iter()
+                .any(|pattern| glob_match(pattern, tool))
+    }
+}
+
+/// Whether `name` matches `pattern`, where `*` stands for any run of characters.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = name.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
         }
     }
+    rest.len() >= last.len() && rest.ends_with(last)
 }
 
-#[cfg(target_arch = "wasm32")]
-bindings::exports::greentic::component::node::__export_greentic_component_node_0_5_0_cabi!(
-    Adapter with_types_in bindings::exports::greentic::component::node
-);
+/// Router view that hides the tools a [`ToolFilter`] excludes and refuses
+/// calls to them, so every operation sees the same restricted catalog.
+struct Filtered<R> {
+    inner: R,
+    tools: ToolFilter,
+}
 
-fn handle_invoke<R: McpRouter>(router: &R, op: &str, input: &str) -> AdapterResult<Value> {
-    let request = parse_request(op, input)?;
+impl<R: McpRouter> McpRouter for Filtered<R> {
+    fn describe_server(&self) -> Result<router::ServerDescription, RouterError> {
+        self.inner.describe_server()
+    }
 
-    match request.operation {
-        Operation::List => {
-            let tools = router
-                .list_tools()
-                .map_err(|err| Box::new(transport_error(err, None)))?;
-            Ok(render_tool_list(&tools))
-        }
-        Operation::Call => {
-            let tool_name = request.tool.clone().unwrap_or_default();
-            let response = router
-                .call_tool(&tool_name, &request.arguments)
-                .map_err(|err| Box::new(map_call_error(err, &tool_name)))?;
+    fn instructions(&self) -> Result<String, RouterError> {
+        self.inner.instructions()
+    }
 
-            match response {
-                router::Response::Completed(result) => Ok(render_tool_result(&result)),
-                router::Response::Elicit(req) => Ok(render_elicitation(&req)),
-            }
+    fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+        let mut tools = self.inner.list_tools()?;
+        tools.retain(|tool| self.tools.permits(&tool.name));
+        Ok(tools)
+    }
+
+    fn call_tool(&self, tool: &str, arguments: &Value) -> Result<router::Response, CallFailure> {
+        if !self.tools.permits(tool) {
+            return Err(CallFailure::Forbidden);
         }
+        self.inner.call_tool(tool, arguments)
+    }
+
+    fn list_resources(&self) -> Result<Vec<router::McpResource>, RouterError> {
+        self.inner.list_resources()
+    }
+
+    fn read_resource(&self, uri: &str) -> Result<router::ReadResourceResult, ReadFailure> {
+        self.inner.read_resource(uri)
+    }
+
+    fn list_prompts(&self) -> Result<Vec<router::Prompt>, RouterError> {
+        self.inner.list_prompts()
+    }
+
+    fn get_prompt(&self, name: &str) -> Result<router::GetPromptResult, PromptFailure> {
+        self.inner.get_prompt(name)
     }
 }
 
-fn parse_request(op: &str, input: &str) -> AdapterResult<ParsedRequest> {
-    let parsed: AdapterRequest = serde_json::from_str(input).map_err(|err| {
-        Box::new(config_error(
-            format!("invalid request payload: {err}"),
-            None,
-            json!({"raw": input}),
-        ))
-    })?;
+/// Environment variable holding the namespace prepended to tool names.
+const TOOL_PREFIX_ENV: &str = "GREENTIC_MCP_ADAPTER_TOOL_PREFIX";
 
-    let operation = resolve_operation(parsed.operation.as_deref(), op, parsed.tool.as_deref())?;
-    let arguments_value = parsed.arguments.clone();
-    let arguments = ensure_object(parsed.arguments).map_err(|err| {
-        Box::new(config_error(
-            err,
-            parsed.tool.clone(),
-            json!({"arguments": arguments_value}),
-        ))
-    })?;
+/// Namespace for exposed tool names; empty leaves names unchanged.
+static TOOL_PREFIX: RwLock<String> = RwLock::new(String::new());
 
-    Ok(ParsedRequest {
-        operation,
-        tool: parsed.tool,
-        arguments,
-    })
+fn tool_prefix() -> String {
+    TOOL_PREFIX
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
 }
 
-struct ParsedRequest {
-    operation: Operation,
-    tool: Option<String>,
-    arguments: Value,
+/// Router view that exposes every tool as `prefix.name` and strips the prefix
+/// before forwarding calls, so several adapters can share a flow without their
+/// tool names colliding.
+struct Prefixed<R> {
+    inner: R,
+    prefix: String,
 }
 
-fn ensure_object(value: Value) -> Result<Value, String> {
-    match value {
-        Value::Null => Ok(json!({})),
-        Value::Object(_) => Ok(value),
-        other => Err(format!("arguments must be an object, got {other:?}")),
+impl<R: McpRouter> McpRouter for Prefixed<R> {
+    fn describe_server(&self) -> Result<router::ServerDescription, RouterError> {
+        self.inner.describe_server()
     }
-}
 
-fn resolve_operation(
-    from_payload: Option<&str>,
-    from_op: &str,
-    tool: Option<&str>,
-) -> AdapterResult<Operation> {
-    let parsed_payload = match from_payload {
-        Some(raw) => {
-            let op = parse_operation(raw).ok_or_else(|| {
-                Box::new(config_error(
-                    format!("unsupported operation value: {raw}"),
-                    tool.map(|t| t.to_string()),
-                    Value::Null,
-                ))
-            })?;
-            Some(op)
+    fn instructions(&self) -> Result<String, RouterError> {
+        self.inner.instructions()
+    }
+
+    fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+        let mut tools = self.inner.list_tools()?;
+        if !self.prefix.is_empty() {
+            for tool in &mut tools {
+                tool.name = format!("{}.{}", self.prefix, tool.name);
+            }
         }
-        None => None,
-    };
-    let parsed_op = parse_operation(from_op);
+        Ok(tools)
+    }
 
-    let op = parsed_payload.or(parsed_op).unwrap_or_else(|| {
-        if tool.is_some() {
-            Operation::Call
-        } else {
-            Operation::List
+    fn call_tool(&self, tool: &str, arguments: &Value) -> Result<router::Response, CallFailure> {
+        if self.prefix.is_empty() {
+            return self.inner.call_tool(tool, arguments);
         }
-    });
+        let name = tool
+            .strip_prefix(self.prefix.as_str())
+            .and_then(|rest| rest.strip_prefix('.'))
+            .ok_or_else(|| {
+                CallFailure::Tool(router::ToolError::NotFound(format!(
+                    "tool `{tool}` is not under the `{}.` prefix",
+                    self.prefix
+                )))
+            })?;
+        self.inner.call_tool(name, arguments)
+    }
 
-    if matches!(op, Operation::Call) && tool.is_none() {
-        return Err(Box::new(config_error(
-            "tool is required for operation=call".into(),
-            None,
-            Value::Null,
-        )));
+    fn list_resources(&self) -> Result<Vec<router::McpResource>, RouterError> {
+        self.inner.list_resources()
     }
 
-    Ok(op)
-}
+    fn read_resource(&self, uri: &str) -> Result<router::ReadResourceResult, ReadFailure> {
+        self.inner.read_resource(uri)
+    }
 
-fn parse_operation(raw: &str) -> Option<Operation> {
-    match raw.trim().to_ascii_lowercase().as_str() {
-        "list" => Some(Operation::List),
-        "call" => Some(Operation::Call),
-        _ => None,
+    fn list_prompts(&self) -> Result<Vec<router::Prompt>, RouterError> {
+        self.inner.list_prompts()
     }
-}
 
-fn render_tool_list(tools: &[router::Tool]) -> Value {
-    let rendered_tools: Vec<Value> = tools.iter().map(render_tool).collect();
-    json!({
-        "ok": true,
-        "result": {
-            "tools": rendered_tools,
-            "protocol": PROTOCOL,
-        }
-    })
+    fn get_prompt(&self, name: &str) -> Result<router::GetPromptResult, PromptFailure> {
+        self.inner.get_prompt(name)
+    }
 }
 
-fn render_tool(tool: &router::Tool) -> Value {
-    json!({
-        "name": tool.name,
-        "title": tool.title,
-        "description": tool.description,
-        "input_schema": parse_json_string(&tool.input_schema),
-        "output_schema": tool.output_schema.as_ref().map(|s| parse_json_string(s)),
-        "annotations": tool.annotations.as_ref().map(render_tool_annotations),
-        "meta": meta_to_value(tool.meta.as_ref()),
-    })
-}
+/// Environment variable holding the key patterns whose values are redacted
+/// from `call` results.
+const REDACT_KEYS_ENV: &str = "GREENTIC_MCP_ADAPTER_REDACT_KEYS";
 
-fn render_tool_annotations(ann: &router::ToolAnnotations) -> Value {
-    json!({
-        "read_only": ann.read_only,
-        "destructive": ann.destructive,
-        "streaming": ann.streaming,
-        "experimental": ann.experimental,
-    })
+/// Replacement for redacted values.
+const REDACTED: &str = "[REDACTED]";
+
+/// Lowercase key patterns redacted from results; empty disables redaction.
+static REDACT_KEYS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+fn redact_keys() -> Vec<String> {
+    REDACT_KEYS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
 }
 
-fn render_tool_result(result: &router::ToolResult) -> Value {
-    let mut messages = Vec::new();
-    let mut result_annotations: Option<Value> = None;
-    let content: Vec<Value> = result
-        .content
-        .iter()
-        .map(|block| {
-            let (payload, message, annotations) = render_content_block(block);
-            if let Some(message) = message {
-                messages.push(message);
-            }
-            if result_annotations.is_none() {
-                result_annotations = annotations;
-            }
-            payload
-        })
-        .collect();
+/// Environment variable holding per-tool default arguments.
+const TOOL_DEFAULTS_ENV: &str = "GREENTIC_MCP_ADAPTER_TOOL_DEFAULTS";
 
-    let payload = json!({
-        "ok": true,
-        "result": {
-            "content": content,
-            "structured_content": result.structured_content.as_ref().map(|s| parse_json_string(s)),
-            "progress": result.progress.as_deref().map(render_progress),
-            "meta": meta_to_value(result.meta.as_ref()),
-            "is_error": result.is_error,
-            "annotations": result_annotations,
-        },
-        "messages": Value::Array(messages),
-        "protocol": PROTOCOL,
-    });
+/// Default arguments per router tool name, each a JSON object.
+static TOOL_DEFAULTS: RwLock<BTreeMap<String, Value>> = RwLock::new(BTreeMap::new());
 
-    payload
+fn tool_defaults() -> BTreeMap<String, Value> {
+    TOOL_DEFAULTS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
 }
 
-fn render_progress(progress: &[router::ProgressNotification]) -> Value {
-    Value::Array(
-        progress
-            .iter()
-            .map(|p| {
-                json!({
-                    "progress": p.progress,
-                    "message": p.message,
-                    "annotations": p.annotations.as_ref().map(render_annotations),
-                })
-            })
-            .collect(),
-    )
+/// Router view that deep-merges each tool's configured defaults under the
+/// caller's arguments, so deployments can pin values such as a region or
+/// workspace id without changing every flow.
+struct Defaulted<R> {
+    inner: R,
+    defaults: BTreeMap<String, Value>,
 }
 
-fn render_elicitation(req: &router::ElicitationRequest) -> Value {
-    json!({
-        "ok": true,
-        "elicitation": {
-            "title": req.title,
-            "message": req.message,
-            "schema": parse_json_string(&req.schema),
-            "annotations": req.annotations.as_ref().map(render_annotations),
-            "meta": meta_to_value(req.meta.as_ref()),
-        },
-        "messages": [{
-            "type": "text",
-            "text": req.message,
-        }],
-        "protocol": PROTOCOL,
-    })
-}
+impl<R: McpRouter> McpRouter for Defaulted<R> {
+    fn describe_server(&self) -> Result<router::ServerDescription, RouterError> {
+        self.inner.describe_server()
+    }
 
-fn render_content_block(block: &router::ContentBlock) -> (Value, Option<Value>, Option<Value>) {
-    match block {
-        router::ContentBlock::Text(text) => {
-            let payload = json!({
-                "type": "text",
-                "text": text.text,
-                "annotations": text.annotations.as_ref().map(render_annotations),
-            });
-            let message = json!({
-                "type": "text",
-                "text": text.text,
-            });
-            (
-                payload,
-                Some(message),
-                text.annotations.as_ref().map(render_annotations),
-            )
-        }
-        router::ContentBlock::Image(image) => {
-            let payload = json!({
-                "type": "image",
-                "data": image.data,
-                "mime_type": image.mime_type,
-                "annotations": image.annotations.as_ref().map(render_annotations),
-            });
-            let message = json!({
-                "type": "image",
-                "mime_type": image.mime_type,
-                "data": image.data,
-            });
-            (
-                payload,
-                Some(message),
-                image.annotations.as_ref().map(render_annotations),
-            )
-        }
-        router::ContentBlock::Audio(audio) => {
-            let payload = json!({
-                "type": "audio",
-                "data": audio.data,
-                "mime_type": audio.mime_type,
-                "annotations": audio.annotations.as_ref().map(render_annotations),
-            });
-            let message = json!({
-                "type": "audio",
-                "mime_type": audio.mime_type,
-                "data": audio.data,
-            });
-            (
-                payload,
-                Some(message),
-                audio.annotations.as_ref().map(render_annotations),
-            )
-        }
-        router::ContentBlock::ResourceLink(link) => {
-            let payload = json!({
-                "type": "resource_link",
-                "uri": link.uri,
-                "title": link.title,
-                "description": link.description,
-                "mime_type": link.mime_type,
-                "annotations": link.annotations.as_ref().map(render_annotations),
-            });
-            let message = json!({
-                "type": "resource_link",
-                "uri": link.uri,
-                "title": link.title,
-                "description": link.description,
-            });
-            (
-                payload,
-                Some(message),
-                link.annotations.as_ref().map(render_annotations),
-            )
-        }
-        router::ContentBlock::EmbeddedResource(res) => {
-            let payload = json!({
-                "type": "resource",
-                "uri": res.uri,
-                "title": res.title,
-                "description": res.description,
-                "mime_type": res.mime_type,
-                "data": res.data,
-                "annotations": res.annotations.as_ref().map(render_annotations),
-            });
-            let message = json!({
-                "type": "resource",
-                "uri": res.uri,
-                "title": res.title,
-                "description": res.description,
-                "mime_type": res.mime_type,
-            });
-            (
-                payload,
-                Some(message),
-                res.annotations.as_ref().map(render_annotations),
-            )
+    fn instructions(&self) -> Result<String, RouterError> {
+        self.inner.instructions()
+    }
+
+    fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+        self.inner.list_tools()
+    }
+
+    fn call_tool(&self, tool: &str, arguments: &Value) -> Result<router::Response, CallFailure> {
+        match self.defaults.get(tool) {
+            Some(defaults) => self
+                .inner
+                .call_tool(tool, &merge_defaults(defaults, arguments.clone())),
+            None => self.inner.call_tool(tool, arguments),
         }
     }
-}
 
-fn render_annotations(ann: &router::Annotations) -> Value {
-    json!({
-        "audience": ann.audience.as_ref().map(|roles| {
-            roles.iter().map(|role| match role {
-                router::Role::User => "user",
-                router::Role::Assistant => "assistant",
-            }).collect::<Vec<_>>()
-        }),
-        "priority": ann.priority,
-        "timestamp": ann.timestamp,
-    })
+    fn list_resources(&self) -> Result<Vec<router::McpResource>, RouterError> {
+        self.inner.list_resources()
+    }
+
+    fn read_resource(&self, uri: &str) -> Result<router::ReadResourceResult, ReadFailure> {
+        self.inner.read_resource(uri)
+    }
+
+    fn list_prompts(&self) -> Result<Vec<router::Prompt>, RouterError> {
+        self.inner.list_prompts()
+    }
+
+    fn get_prompt(&self, name: &str) -> Result<router::GetPromptResult, PromptFailure> {
+        self.inner.get_prompt(name)
+    }
 }
 
-fn meta_to_value(meta: Option<&Vec<router::MetaEntry>>) -> Option<Value> {
-    meta.map(|entries| {
-        let mut map = Map::new();
-        for entry in entries {
-            map.insert(entry.key.clone(), parse_json_string(&entry.value));
+/// `arguments` with every field of `defaults` it lacks filled in. Nested
+/// objects are merged field by field; any other caller value wins.
+fn merge_defaults(defaults: &Value, arguments: Value) -> Value {
+    match (defaults, arguments) {
+        (Value::Object(defaults), Value::Object(mut fields)) => {
+            for (name, default) in defaults {
+                let merged = match fields.remove(name) {
+                    Some(value) => merge_defaults(default, value),
+                    None => default.clone(),
+                };
+                fields.insert(name.clone(), merged);
+            }
+            Value::Object(fields)
         }
-        Value::Object(map)
-    })
+        (_, arguments) => arguments,
+    }
 }
 
-fn parse_json_string(raw: &str) -> Value {
-    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+/// Environment variable setting how long a `list-tools` result is reused.
+const TOOLS_CACHE_TTL_ENV: &str = "GREENTIC_MCP_ADAPTER_TOOLS_CACHE_TTL_MS";
+
+/// `list-tools` results shared by every dispatch of this instance.
+static TOOLS_CACHE: ToolsCache = ToolsCache::new();
+
+/// The router's tool catalog as last listed, reused for a configured time.
+struct ToolsCache {
+    /// How long a listing stays fresh; 0 disables caching.
+    ttl_ms: AtomicU64,
+    entry: Mutex<Option<(Instant, Vec<router::Tool>)>>,
 }
 
-fn transport_error(err: RouterError, tool: Option<String>) -> ErrorEnvelope {
-    ErrorEnvelope {
-        ok: false,
-        error: ErrorBody {
-            code: "MCP_ROUTER_ERROR",
-            message: err.to_string(),
-            status: 502,
-            tool,
-            protocol: PROTOCOL,
-            details: Value::Null,
-        },
+impl ToolsCache {
+    const fn new() -> Self {
+        Self {
+            ttl_ms: AtomicU64::new(0),
+            entry: Mutex::new(None),
+        }
+    }
+
+    /// Set the TTL and drop any cached listing.
+    fn configure(&self, ttl_ms: u64) {
+        self.ttl_ms.store(ttl_ms, Ordering::Relaxed);
+        self.invalidate();
+    }
+
+    fn invalidate(&self) {
+        *self.lock() = None;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<(Instant, Vec<router::Tool>)>> {
+        self.entry
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
     }
 }
 
-fn map_call_error(err: CallFailure, tool: &str) -> ErrorEnvelope {
-    match err {
-        CallFailure::Tool(tool_err) => match tool_err {
-            router::ToolError::InvalidParameters(msg) => tool_error(400, msg, tool),
-            router::ToolError::ExecutionError(msg) => tool_error(500, msg, tool),
-            router::ToolError::SchemaError(msg) => tool_error(422, msg, tool),
-            router::ToolError::NotFound(msg) => tool_error(404, msg, tool),
-        },
-        CallFailure::Transport(msg) => {
-            transport_error(RouterError::Transport(msg), Some(tool.to_string()))
+/// Router view that serves `list-tools` from a [`ToolsCache`] while it is
+/// fresh, so hosts that list before every call reach the router once per TTL.
+/// Any failed listing or call drops the cached catalog.
+struct Cached<'a, R> {
+    inner: R,
+    cache: &'a ToolsCache,
+}
+
+impl<R: McpRouter> McpRouter for Cached<'_, R> {
+    fn describe_server(&self) -> Result<router::ServerDescription, RouterError> {
+        self.inner.describe_server()
+    }
+
+    fn instructions(&self) -> Result<String, RouterError> {
+        self.inner.instructions()
+    }
+
+    fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+        let ttl = Duration::from_millis(self.cache.ttl_ms.load(Ordering::Relaxed));
+        if ttl.is_zero() {
+            return self.inner.list_tools();
+        }
+        if let Some((listed, tools)) = self.cache.lock().as_ref()
+            && listed.elapsed() < ttl
+        {
+            return Ok(tools.clone());
+        }
+        match self.inner.list_tools() {
+            Ok(tools) => {
+                *self.cache.lock() = Some((Instant::now(), tools.clone()));
+                Ok(tools)
+            }
+            Err(err) => {
+                self.cache.invalidate();
+                Err(err)
+            }
+        }
+    }
+
+    fn call_tool(&self, tool: &str, arguments: &Value) -> Result<router::Response, CallFailure> {
+        let result = self.inner.call_tool(tool, arguments);
+        if result.is_err() {
+            self.cache.invalidate();
         }
+        result
+    }
+
+    fn list_resources(&self) -> Result<Vec<router::McpResource>, RouterError> {
+        self.inner.list_resources()
+    }
+
+    fn read_resource(&self, uri: &str) -> Result<router::ReadResourceResult, ReadFailure> {
+        self.inner.read_resource(uri)
+    }
+
+    fn list_prompts(&self) -> Result<Vec<router::Prompt>, RouterError> {
+        self.inner.list_prompts()
+    }
+
+    fn get_prompt(&self, name: &str) -> Result<router::GetPromptResult, PromptFailure> {
+        self.inner.get_prompt(name)
     }
 }
 
-fn tool_error(status: u16, message: String, tool: &str) -> ErrorEnvelope {
-    ErrorEnvelope {
-        ok: false,
-        error: ErrorBody {
-            code: "MCP_TOOL_ERROR",
-            message,
-            status,
-            tool: Some(tool.to_string()),
-            protocol: PROTOCOL,
-            details: Value::Null,
+/// The composed router, restricted by the installed [`ToolFilter`],
+/// namespaced by the installed tool prefix, and with the installed tool
+/// defaults applied. Filter patterns and defaults use the router's own tool
+/// names. Tool listings are cached per the installed TTL.
+fn composed_router() -> Prefixed<Filtered<Defaulted<Cached<'static, BaseRouter>>>> {
+    Prefixed {
+        inner: Filtered {
+            inner: Defaulted {
+                inner: Cached {
+                    inner: BaseRouter {},
+                    cache: &TOOLS_CACHE,
+                },
+                defaults: tool_defaults(),
+            },
+            tools: ToolFilter::current(),
         },
+        prefix: tool_prefix(),
     }
 }
 
-fn config_error(message: String, tool: Option<String>, details: Value) -> ErrorEnvelope {
-    ErrorEnvelope {
-        ok: false,
-        error: ErrorBody {
-            code: "MCP_CONFIG_ERROR",
-            message,
-            status: 400,
-            tool,
-            protocol: PROTOCOL,
-            details,
+/// Upper bounds, in milliseconds, of the invoke latency histogram buckets; a
+/// final bucket catches everything slower.
+const LATENCY_BUCKETS_MS: [u64; 10] = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000];
+
+/// Counters of this adapter instance since it was instantiated, served by
+/// the `metrics` operation. Scrapes are not counted.
+struct Metrics {
+    calls: AtomicU64,
+    /// Failures by [`ErrorCategory`], in declaration order.
+    errors: [AtomicU64; 4],
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS_MS.len() + 1],
+    latency_ms_sum: AtomicU64,
+    bytes_rendered: AtomicU64,
+}
+
+static METRICS: Metrics = Metrics::new();
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            calls: AtomicU64::new(0),
+            errors: [const { AtomicU64::new(0) }; 4],
+            latency_buckets: [const { AtomicU64::new(0) }; LATENCY_BUCKETS_MS.len() + 1],
+            latency_ms_sum: AtomicU64::new(0),
+            bytes_rendered: AtomicU64::new(0),
+        }
+    }
+
+    /// Count one invoke that took `elapsed` and failed with `error`, if any.
+    fn record(&self, elapsed: Duration, error: Option<ErrorCategory>) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        if let Some(category) = error {
+            self.errors[category as usize].fetch_add(1, Ordering::Relaxed);
+        }
+        let ms = elapsed.as_millis() as u64;
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.latency_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.latency_ms_sum.fetch_add(ms, Ordering::Relaxed);
+    }
+
+    fn record_rendered(&self, bytes: usize) {
+        self.bytes_rendered
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// The `metrics` envelope. Histogram buckets are cumulative, as in
+    /// Prometheus; the last one has no upper bound.
+    fn render(&self) -> Value {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let categories = [
+            ErrorCategory::Config,
+            ErrorCategory::Tool,
+            ErrorCategory::Transport,
+            ErrorCategory::Schema,
+        ];
+        let errors: Map<String, Value> = categories
+            .iter()
+            .map(|category| {
+                let name = serde_json::to_value(category).unwrap_or_default();
+                (
+                    name.as_str().unwrap_or_default().to_string(),
+                    json!(load(&self.errors[*category as usize])),
+                )
+            })
+            .collect();
+        let mut cumulative = 0;
+        let buckets: Vec<Value> = self
+            .latency_buckets
+            .iter()
+            .enumerate()
+            .map(|(index, count)| {
+                cumulative += load(count);
+                json!({ "le_ms": LATENCY_BUCKETS_MS.get(index), "count": cumulative })
+            })
+            .collect();
+        json!({
+            "ok": true,
+            "result": {
+                "calls": load(&self.calls),
+                "errors": errors,
+                "latency_ms": { "buckets": buckets, "sum": load(&self.latency_ms_sum) },
+                "bytes_rendered": load(&self.bytes_rendered),
+                "protocol": PROTOCOL,
+            }
+        })
+    }
+}
+
+struct Adapter;
+
+impl Guest for Adapter {
+    fn get_manifest() -> String {
+        serde_json::to_string(&build_manifest(&composed_router())).unwrap_or_else(|_| "{}".into())
+    }
+
+    fn on_start(_ctx: ExecCtx) -> Result<LifecycleStatus, String> {
+        let detail = match std::env::var(ERROR_DETAIL_ENV) {
+            Ok(raw) => ErrorDetail::parse(&raw).ok_or_else(|| {
+                format!("{ERROR_DETAIL_ENV} must be `full` or `terse`, got `{raw}`")
+            })?,
+            Err(_) => ErrorDetail::default(),
+        };
+        detail.install();
+
+        let audience = match std::env::var(AUDIENCE_ENV) {
+            Ok(raw) => Audience::parse(&raw).ok_or_else(|| {
+                format!("{AUDIENCE_ENV} must be `all`, `split`, or `user`, got `{raw}`")
+            })?,
+            Err(_) => Audience::default(),
+        };
+        audience.install();
+
+        let max_chunk = match std::env::var(MAX_CHUNK_ENV) {
+            Ok(raw) => raw
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|limit| *limit >= MIN_CHUNK_BYTES)
+                .ok_or_else(|| {
+                    format!(
+                        "{MAX_CHUNK_ENV} must be a byte count of at least {MIN_CHUNK_BYTES}, got `{raw}`"
+                    )
+                })?,
+            Err(_) => 0,
+        };
+        MAX_CHUNK_BYTES.store(max_chunk, Ordering::Relaxed);
+
+        let max_result = match std::env::var(MAX_RESULT_ENV) {
+            Ok(raw) => raw
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|limit| *limit > 0)
+                .ok_or_else(|| {
+                    format!("{MAX_RESULT_ENV} must be a positive byte count, got `{raw}`")
+                })?,
+            Err(_) => 0,
+        };
+        MAX_RESULT_BYTES.store(max_result, Ordering::Relaxed);
+
+        let handoff_ns = match std::env::var(KV_HANDOFF_ENV) {
+            Ok(raw) if !cfg!(feature = "kv-handoff") => {
+                return Err(format!(
+                    "{KV_HANDOFF_ENV} is set to `{raw}`, but the adapter was built without the `kv-handoff` feature"
+                ));
+            }
+            Ok(raw) if raw.trim().is_empty() => {
+                return Err(format!("{KV_HANDOFF_ENV} must be a non-empty namespace"));
+            }
+            Ok(raw) => raw.trim().to_string(),
+            Err(_) => String::new(),
+        };
+        *KV_HANDOFF_NS
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = handoff_ns;
+
+        let coerce = match std::env::var(COERCE_ARGUMENTS_ENV) {
+            Ok(raw) => match raw.trim() {
+                "true" => true,
+                "false" => false,
+                _ => {
+                    return Err(format!(
+                        "{COERCE_ARGUMENTS_ENV} must be `true` or `false`, got `{raw}`"
+                    ));
+                }
+            },
+            Err(_) => false,
+        };
+        COERCE_ARGUMENTS.store(coerce, Ordering::Relaxed);
+
+        let max_attempts = match std::env::var(RETRY_ATTEMPTS_ENV) {
+            Ok(raw) => raw
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|attempts| *attempts >= 1)
+                .ok_or_else(|| {
+                    format!("{RETRY_ATTEMPTS_ENV} must be a positive attempt count, got `{raw}`")
+                })?,
+            Err(_) => 1,
+        };
+        let base_backoff_ms = match std::env::var(RETRY_BACKOFF_ENV) {
+            Ok(raw) => raw.trim().parse::<u64>().map_err(|_| {
+                format!("{RETRY_BACKOFF_ENV} must be a delay in milliseconds, got `{raw}`")
+            })?,
+            Err(_) => 100,
+        };
+        RetryPolicy {
+            max_attempts,
+            base_backoff_ms,
+        }
+        .install();
+
+        let filter = match std::env::var(TOOL_FILTER_ENV) {
+            Ok(raw) => serde_json::from_str::<ToolFilter>(&raw).map_err(|err| {
+                format!(
+                    "{TOOL_FILTER_ENV} must be a JSON object with `allowed_tools`/`denied_tools` pattern lists: {err}"
+                )
+            })?,
+            Err(_) => ToolFilter::default(),
+        };
+        filter.install();
+
+        let prefix = match std::env::var(TOOL_PREFIX_ENV) {
+            Ok(raw) => {
+                let prefix = raw.trim();
+                if prefix.is_empty()
+                    || prefix.ends_with('.')
+                    || prefix.contains(char::is_whitespace)
+                {
+                    return Err(format!(
+                        "{TOOL_PREFIX_ENV} must be a non-empty name without whitespace or a trailing `.`, got `{raw}`"
+                    ));
+                }
+                prefix.to_string()
+            }
+            Err(_) => String::new(),
+        };
+        *TOOL_PREFIX
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = prefix;
+
+        let defaults = match std::env::var(TOOL_DEFAULTS_ENV) {
+            Ok(raw) => serde_json::from_str::<BTreeMap<String, Map<String, Value>>>(&raw)
+                .map_err(|err| {
+                    format!(
+                        "{TOOL_DEFAULTS_ENV} must be a JSON object mapping tool names to default argument objects: {err}"
+                    )
+                })?
+                .into_iter()
+                .map(|(tool, defaults)| (tool, Value::Object(defaults)))
+                .collect(),
+            Err(_) => BTreeMap::new(),
+        };
+        *TOOL_DEFAULTS
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = defaults;
+
+        let redact = match std::env::var(REDACT_KEYS_ENV) {
+            Ok(raw) => serde_json::from_str::<Vec<String>>(&raw)
+                .map_err(|err| {
+                    format!("{REDACT_KEYS_ENV} must be a JSON array of key patterns: {err}")
+                })?
+                .into_iter()
+                .map(|pattern| pattern.to_lowercase())
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        *REDACT_KEYS
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = redact;
+
+        let tools_ttl_ms = match std::env::var(TOOLS_CACHE_TTL_ENV) {
+            Ok(raw) => raw.trim().parse::<u64>().map_err(|_| {
+                format!("{TOOLS_CACHE_TTL_ENV} must be a duration in milliseconds, got `{raw}`")
+            })?,
+            Err(_) => 0,
+        };
+        TOOLS_CACHE.configure(tools_ttl_ms);
+
+        // Last, so the probe goes through the router as configured above.
+        let probe = match std::env::var(STARTUP_PROBE_ENV) {
+            Ok(raw) => StartupProbe::parse(&raw).ok_or_else(|| {
+                format!(
+                    "{STARTUP_PROBE_ENV} must be `none`, `list_tools`, or `describe`, got `{raw}`"
+                )
+            })?,
+            Err(_) => StartupProbe::default(),
+        };
+        probe.run(&composed_router())?;
+        Ok(LifecycleStatus::Ok)
+    }
+
+    fn on_stop(_ctx: ExecCtx, _reason: String) -> Result<LifecycleStatus, String> {
+        Ok(LifecycleStatus::Ok)
+    }
+
+    fn invoke(ctx: ExecCtx, op: String, input: String) -> InvokeResult {
+        match dispatch(&ctx, &op, &input) {
+            Ok(rendered) => {
+                let body = match rendered {
+                    Rendered::Value(mut value) => {
+                        if let Some(limit) = max_chunk_bytes() {
+                            truncate_oversized(&mut value, limit, KvHandoff::current().as_ref());
+                        }
+                        if let Some(limit) = max_result_bytes() {
+                            fit_result_budget(&mut value, limit);
+                        }
+                        render_body(&value)
+                    }
+                    Rendered::Tools(page) => page.body(),
+                };
+                METRICS.record_rendered(body.len());
+                InvokeResult::Ok(body)
+            }
+            Err(err) => InvokeResult::Err(err),
+        }
+    }
+
+    fn invoke_stream(ctx: ExecCtx, op: String, input: String) -> Vec<StreamEvent> {
+        match dispatch(&ctx, &op, &input) {
+            Ok(rendered) => {
+                let bodies = match rendered {
+                    Rendered::Value(value) => stream_parts(value, max_chunk_bytes())
+                        .iter()
+                        .map(render_body)
+                        .collect(),
+                    Rendered::Tools(page) => vec![page.body()],
+                };
+                bodies
+                    .into_iter()
+                    .map(|body| {
+                        METRICS.record_rendered(body.len());
+                        StreamEvent::Data(body)
+                    })
+                    .chain(std::iter::once(StreamEvent::Done))
+                    .collect()
+            }
+            Err(err) => {
+                let payload = err.details.clone().unwrap_or_else(|| err.message.clone());
+                vec![StreamEvent::Error(payload)]
+            }
+        }
+    }
+}
+
+/// Run an operation against the composed router, containing panics and
+/// redacting errors per the configured detail level.
+fn dispatch(ctx: &ExecCtx, op: &str, input: &str) -> Result<Rendered, NodeError> {
+    let deadline = ctx.tenant.deadline_unix_ms;
+    let started = Instant::now();
+    let result = contain_panic("invoke", || {
+        host_log::in_span(ctx, || {
+            handle_invoke_rendered(&composed_router(), op, input, deadline, &host_cancelled)
+        })
+    })
+    .unwrap_or_else(|err| Err(Box::new(transport_error(err, None))));
+    if !matches!(parse_operation(op), Some(Operation::Metrics)) {
+        let error = result.as_ref().err().map(|err| err.error.category);
+        METRICS.record(started.elapsed(), error);
+    }
+    result.map_err(|err| err.redact(ErrorDetail::current()).node_error())
+}
+
+fn render_body(value: &Value) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "{\"ok\":true}".into())
+}
+
+#[cfg(target_arch = "wasm32")]
+bindings::exports::greentic::component::node::__export_greentic_component_node_0_5_0_cabi!(
+    Adapter with_types_in bindings::exports::greentic::component::node
+);
+
+/// Adapter manifest, embedding the router's live tool catalog and server
+/// description.
+///
+/// A router that fails or panics while listing tools must not break manifest
+/// retrieval, so the failure is reported under `tools_error` with `tools: null`.
+/// Likewise a failed `describe-server` yields `server: null` and
+/// `server_error`, with no secret requirements.
+fn build_manifest<R: McpRouter>(router: &R) -> Value {
+    let mut manifest = json!({
+        "name": "greentic-mcp-adapter",
+        "version": env!("CARGO_PKG_VERSION"),
+        "protocol": PROTOCOL,
+        "operations": schema::OPERATIONS,
+        "description": "MCP adapter template exporting greentic:component/node@0.5.0 and importing wasix:mcp@25.06.18.",
+        "schemas": {
+            "request": schema::request_schema(),
+            "response": schema::response_schema(),
         },
+    });
+    match router.list_tools() {
+        Ok(tools) => {
+            manifest["tools"] = Value::Array(tools.iter().map(render_tool).collect());
+        }
+        Err(err) => {
+            let envelope = transport_error(err, None).redact(ErrorDetail::current());
+            manifest["tools"] = Value::Null;
+            manifest["tools_error"] = json!({
+                "code": envelope.error.code,
+                "message": envelope.error.message,
+            });
+        }
+    }
+    match router.describe_server() {
+        Ok(desc) => {
+            manifest["server"] = json!({
+                "name": desc.name,
+                "title": desc.title,
+                "capabilities": render_capabilities(&desc.capabilities),
+                "resources": desc.resources.as_deref().map(render_resources),
+            });
+            manifest["secret_requirements"] = Value::Array(secret_requirements(&desc));
+        }
+        Err(err) => {
+            let envelope = transport_error(err, None).redact(ErrorDetail::current());
+            manifest["server"] = Value::Null;
+            manifest["server_error"] = json!({
+                "code": envelope.error.code,
+                "message": envelope.error.message,
+            });
+            manifest["secret_requirements"] = json!([]);
+        }
     }
+    manifest
 }
 
-fn default_arguments() -> Value {
-    json!({})
+fn handle_invoke<R: McpRouter>(router: &R, op: &str, input: &str) -> AdapterResult<Value> {
+    handle_invoke_within(router, op, input, None, &|| false)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::cell::RefCell;
-    use std::path::PathBuf;
-    use std::process::Command;
-    use wasmtime::component::Linker;
-    use wasmtime::{Engine, Store};
-    use wasmtime_wasi::{
-        ResourceTable, WasiCtx, WasiCtxBuilder, WasiCtxView, WasiView, p2::add_to_linker_sync,
+/// Whether the host asked, through `greentic:component/control`, to cancel the
+/// in-flight invoke. A panicking host import counts as no cancellation.
+fn host_cancelled() -> bool {
+    contain_panic(
+        "should-cancel",
+        bindings::greentic::component::control::should_cancel,
+    )
+    .unwrap_or(false)
+}
+
+/// [`handle_invoke`] bounded by the host's `deadline-unix-ms`, if any, and
+/// abandoned once `cancelled` reports that the host gave up on it.
+fn handle_invoke_within<R: McpRouter>(
+    router: &R,
+    op: &str,
+    input: &str,
+    deadline_unix_ms: Option<u64>,
+    cancelled: &dyn Fn() -> bool,
+) -> AdapterResult<Value> {
+    handle_invoke_rendered(router, op, input, deadline_unix_ms, cancelled).map(Rendered::into_value)
+}
+
+/// Successful outcome of an operation, before it is encoded as the body.
+enum Rendered {
+    Value(Value),
+    /// `list` pages, encoded without a [`Value`] round-trip; see [`ToolPage`].
+    Tools(ToolPage),
+}
+
+impl Rendered {
+    fn into_value(self) -> Value {
+        match self {
+            Rendered::Value(value) => value,
+            Rendered::Tools(page) => page.into_value(),
+        }
+    }
+}
+
+/// [`handle_invoke_within`], leaving `list` pages unencoded.
+///
+/// The request's `correlation_id`, if any, is echoed in the envelope whether
+/// the operation succeeds or fails. Every invoke is reported to the host log.
+fn handle_invoke_rendered<R: McpRouter>(
+    router: &R,
+    op: &str,
+    input: &str,
+    deadline_unix_ms: Option<u64>,
+    cancelled: &dyn Fn() -> bool,
+) -> AdapterResult<Rendered> {
+    let started = Instant::now();
+    let request = match parse_request(op, input) {
+        Ok(request) => request,
+        Err(err) => {
+            let err: AdapterResult<Rendered> = Err(err);
+            host_log::invoke(op, None, None, started.elapsed(), &err);
+            return err;
+        }
     };
+    let operation = request.operation.as_str();
+    let tool = request.tool.clone();
+    let correlation_id = request.correlation_id.clone();
+    let result = correlate_and_run(router, op, request, deadline_unix_ms, cancelled);
+    host_log::invoke(
+        operation,
+        tool.as_deref(),
+        correlation_id.as_deref(),
+        started.elapsed(),
+        &result,
+    );
+    result
+}
+
+/// [`run_request`], with the request's `correlation_id` added to the outcome.
+fn correlate_and_run<R: McpRouter>(
+    router: &R,
+    op: &str,
+    request: ParsedRequest,
+    deadline_unix_ms: Option<u64>,
+    cancelled: &dyn Fn() -> bool,
+) -> AdapterResult<Rendered> {
+    let Some(correlation_id) = request.correlation_id.clone() else {
+        return run_request(router, op, request, deadline_unix_ms, cancelled);
+    };
+    match run_request(router, op, request, deadline_unix_ms, cancelled) {
+        Ok(Rendered::Value(mut payload)) => {
+            if let Some(fields) = payload.as_object_mut() {
+                fields.insert(CORRELATION_ID.into(), Value::String(correlation_id));
+            }
+            Ok(Rendered::Value(payload))
+        }
+        Ok(Rendered::Tools(page)) => Ok(Rendered::Tools(ToolPage {
+            correlation_id: Some(correlation_id),
+            ..page
+        })),
+        Err(err) => Err(correlated(err, Some(correlation_id))),
+    }
+}
+
+fn correlated(mut err: Box<ErrorEnvelope>, correlation_id: Option<String>) -> Box<ErrorEnvelope> {
+    err.correlation_id = correlation_id;
+    err
+}
+
+/// Run a parsed request against `router`.
+fn run_request<R: McpRouter>(
+    router: &R,
+    op: &str,
+    request: ParsedRequest,
+    deadline_unix_ms: Option<u64>,
+    cancelled: &dyn Fn() -> bool,
+) -> AdapterResult<Rendered> {
+    let render = request.render;
+
+    let mut payload = match request.operation {
+        Operation::List => {
+            let page = ListPage::from_arguments(&request.arguments)?;
+            let tools = router
+                .list_tools()
+                .map_err(|err| Box::new(transport_error(err, None)))?;
+            return page.render(tools).map(Rendered::Tools);
+        }
+        Operation::CheckChanged => {
+            let tools = router
+                .list_tools()
+                .map_err(|err| Box::new(transport_error(err, None)))?;
+            let check = ChangeCheck::from_arguments(&request.arguments)?;
+            Ok::<_, Box<ErrorEnvelope>>(render_change_check(&tools, &check))
+        }
+        Operation::Call => {
+            let tool_name = request.tool.clone().unwrap_or_default();
+            let budget = call_budget(request.timeout_ms, deadline_unix_ms);
+            if let Some(budget) = budget
+                && budget.is_zero()
+            {
+                return Err(Box::new(timeout_error(&tool_name, budget, Duration::ZERO)));
+            }
+            // Coercion needs the tool's schema; the catalog entry is kept for
+            // the cache hint below.
+            let mut catalog_entry = None;
+            let mut arguments = request.arguments;
+            if COERCE_ARGUMENTS.load(Ordering::Relaxed) {
+                catalog_entry = find_tool(router, &tool_name);
+                if let Some(tool) = &catalog_entry {
+                    arguments = coerce_arguments(&tool.input_schema, arguments);
+                }
+            }
+            if let Some(correlation_id) = &request.correlation_id {
+                attach_correlation_id(&mut arguments, correlation_id);
+            }
+            if cancelled() {
+                return Err(Box::new(cancelled_error(&tool_name, Duration::ZERO)));
+            }
+            let started = Instant::now();
+            let retry = RetryPolicy::current();
+            let (response, attempts) =
+                call_with_retries(router, &tool_name, &arguments, retry, budget, cancelled);
+            // The router import cannot be interrupted from inside the guest:
+            // the budget and cancellation are only checked once it returns. A
+            // late or cancelled answer is discarded rather than handed to a
+            // caller that has already given up on it, and flagged as late,
+            // since the call may have taken effect.
+            if cancelled() {
+                return Err(Box::new(
+                    cancelled_error(&tool_name, started.elapsed()).late(),
+                ));
+            }
+            if let Some(budget) = budget
+                && started.elapsed() > budget
+            {
+                return Err(Box::new(
+                    timeout_error(&tool_name, budget, started.elapsed()).late(),
+                ));
+            }
+            let response =
+                response.map_err(|err| Box::new(call_failure(err, &tool_name, retry, attempts)))?;
+
+            match response {
+                router::Response::Completed(result) => {
+                    let mut payload = render_tool_result(&result, Audience::current());
+                    if result.is_error != Some(true) {
+                        // Best effort: a catalog failure only costs the hint.
+                        let tool = catalog_entry.or_else(|| find_tool(router, &tool_name));
+                        if let Some(hint) = tool.and_then(|tool| cache_hint(&tool, &result)) {
+                            payload["cache"] = hint;
+                        }
+                    }
+                    let patterns = redact_keys();
+                    if !patterns.is_empty() {
+                        redact_fields(&mut payload["result"], &patterns);
+                        redact_fields(&mut payload["messages"], &patterns);
+                        if let Some(cards) = payload.get_mut("assistant_content") {
+                            redact_fields(cards, &patterns);
+                        }
+                    }
+                    Ok(payload)
+                }
+                #[cfg(feature = "elicitation")]
+                router::Response::Elicit(req) => Ok(render_elicitation(&req)),
+                #[cfg(not(feature = "elicitation"))]
+                router::Response::Elicit(_) => Err(Box::new(tool_error(
+                    501,
+                    "tool asked for input through elicitation, which this adapter build does not support".into(),
+                    &tool_name,
+                ))),
+            }
+        }
+        Operation::ResourcesList => {
+            let resources = router
+                .list_resources()
+                .map_err(|err| Box::new(transport_error(err, None)))?;
+            Ok(render_resource_list(&resources))
+        }
+        Operation::ResourcesRead => {
+            let uri = resource_uri(&request.arguments)?;
+            let result = router
+                .read_resource(&uri)
+                .map_err(|err| Box::new(map_read_error(err, &uri)))?;
+            Ok(render_read_resource(&uri, &result))
+        }
+        Operation::Describe => {
+            let description = router
+                .describe_server()
+                .map_err(|err| Box::new(transport_error(err, None)))?;
+            let instructions = router
+                .instructions()
+                .map_err(|err| Box::new(transport_error(err, None)))?;
+            Ok(render_server_description(&description, &instructions))
+        }
+        Operation::PromptsList => {
+            let prompts = router
+                .list_prompts()
+                .map_err(|err| Box::new(transport_error(err, None)))?;
+            Ok(render_prompt_list(&prompts))
+        }
+        Operation::PromptsGet => {
+            let name = request.prompt.clone().unwrap_or_default();
+            let result = router
+                .get_prompt(&name)
+                .map_err(|err| Box::new(map_prompt_error(err, &name)))?;
+            Ok(render_prompt(&name, &result))
+        }
+        Operation::Metrics => Ok(METRICS.render()),
+        Operation::DebugEcho => Ok(render_debug_echo(op, &request)),
+    }?;
+    if render == Render::Markdown
+        && let Some(Value::Array(cards)) = payload.get("messages")
+    {
+        let markdown = render_markdown(cards);
+        payload["messages"] = json!([{ "type": "text", "format": "markdown", "text": markdown }]);
+    }
+    Ok(Rendered::Value(payload))
+}
+
+fn parse_request(op: &str, input: &str) -> AdapterResult<ParsedRequest> {
+    let parsed: AdapterRequest = serde_json::from_str(input).map_err(|err| {
+        Box::new(config_error(
+            format!("invalid request payload: {err}"),
+            None,
+            json!({"raw": input}),
+        ))
+    })?;
+    let correlation_id = parsed.correlation_id.clone();
+    check_request(op, parsed).map_err(|err| correlated(err, correlation_id))
+}
+
+/// Resolve the operation of a decoded request and validate its fields.
+fn check_request(op: &str, parsed: AdapterRequest) -> AdapterResult<ParsedRequest> {
+    let operation = resolve_operation(parsed.operation.as_deref(), op, parsed.tool.as_deref())?;
+    if matches!(operation, Operation::PromptsGet) && parsed.prompt.is_none() {
+        return Err(Box::new(config_error(
+            "prompt is required for operation=prompts.get".into(),
+            None,
+            Value::Null,
+        )));
+    }
+    let arguments_value = parsed.arguments.clone();
+    let arguments = ensure_object(parsed.arguments).map_err(|err| {
+        Box::new(config_error(
+            err,
+            parsed.tool.clone(),
+            json!({"arguments": arguments_value}),
+        ))
+    })?;
+
+    if parsed.timeout_ms == Some(0) {
+        return Err(Box::new(config_error(
+            "timeout_ms must be positive".into(),
+            parsed.tool,
+            Value::Null,
+        )));
+    }
+
+    Ok(ParsedRequest {
+        operation,
+        tool: parsed.tool,
+        prompt: parsed.prompt,
+        arguments,
+        timeout_ms: parsed.timeout_ms,
+        render: parsed.render,
+        correlation_id: parsed.correlation_id,
+    })
+}
+
+struct ParsedRequest {
+    operation: Operation,
+    tool: Option<String>,
+    prompt: Option<String>,
+    arguments: Value,
+    timeout_ms: Option<u64>,
+    render: Render,
+    correlation_id: Option<String>,
+}
+
+/// Record `correlation_id` in the call's `_meta` argument, creating it if
+/// needed. A `_meta` that is not an object is the caller's and left alone.
+fn attach_correlation_id(arguments: &mut Value, correlation_id: &str) {
+    let Some(fields) = arguments.as_object_mut() else {
+        return;
+    };
+    let meta = fields
+        .entry(ARGUMENTS_META)
+        .or_insert_with(|| Value::Object(Map::new()));
+    if let Some(meta) = meta.as_object_mut() {
+        meta.insert(CORRELATION_ID.into(), Value::String(correlation_id.into()));
+    }
+}
+
+/// Time left for a router call: the payload's `timeout_ms` or the host
+/// deadline, whichever ends first. `None` when neither is set.
+fn call_budget(timeout_ms: Option<u64>, deadline_unix_ms: Option<u64>) -> Option<Duration> {
+    let until_deadline = deadline_unix_ms.map(|deadline| {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_millis() as u64)
+            .unwrap_or(0);
+        Duration::from_millis(deadline.saturating_sub(now))
+    });
+    [timeout_ms.map(Duration::from_millis), until_deadline]
+        .into_iter()
+        .flatten()
+        .min()
+}
+
+fn ensure_object(value: Value) -> Result<Value, String> {
+    match value {
+        Value::Null => Ok(json!({})),
+        Value::Object(_) => Ok(value),
+        other => Err(format!("arguments must be an object, got {other:?}")),
+    }
+}
+
+fn resolve_operation(
+    from_payload: Option<&str>,
+    from_op: &str,
+    tool: Option<&str>,
+) -> AdapterResult<Operation> {
+    let parsed_payload = match from_payload {
+        Some(raw) => {
+            let op = parse_operation(raw).ok_or_else(|| {
+                Box::new(config_error(
+                    format!("unsupported operation value: {raw}"),
+                    tool.map(|t| t.to_string()),
+                    Value::Null,
+                ))
+            })?;
+            Some(op)
+        }
+        None => None,
+    };
+    let parsed_op = parse_operation(from_op);
+
+    let op = parsed_payload.or(parsed_op).unwrap_or_else(|| {
+        if tool.is_some() {
+            Operation::Call
+        } else {
+            Operation::List
+        }
+    });
+
+    if matches!(op, Operation::Call) && tool.is_none() {
+        return Err(Box::new(config_error(
+            "tool is required for operation=call".into(),
+            None,
+            Value::Null,
+        )));
+    }
+
+    Ok(op)
+}
+
+fn parse_operation(raw: &str) -> Option<Operation> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "list" => Some(Operation::List),
+        "list.check_changed" => Some(Operation::CheckChanged),
+        "call" => Some(Operation::Call),
+        "resources.list" => Some(Operation::ResourcesList),
+        "resources.read" => Some(Operation::ResourcesRead),
+        "prompts.list" => Some(Operation::PromptsList),
+        "prompts.get" => Some(Operation::PromptsGet),
+        "describe" => Some(Operation::Describe),
+        "metrics" => Some(Operation::Metrics),
+        "debug.echo" => Some(Operation::DebugEcho),
+        _ => None,
+    }
+}
+
+/// What the adapter made of a request, and the configuration it would run it
+/// under, without calling the router. Echoed arguments and tool defaults are
+/// redacted like `call` results.
+fn render_debug_echo(op: &str, request: &ParsedRequest) -> Value {
+    let patterns = redact_keys();
+    let mut arguments = request.arguments.clone();
+    let mut defaults = Value::Object(tool_defaults().into_iter().collect());
+    if !patterns.is_empty() {
+        redact_fields(&mut arguments, &patterns);
+        redact_fields(&mut defaults, &patterns);
+    }
+    let filter = ToolFilter::current();
+    let retry = RetryPolicy::current();
+    let kv_handoff_ns = KvHandoff::current().map(|handoff| handoff.namespace);
+    let prefix = tool_prefix();
+    json!({
+        "ok": true,
+        "result": {
+            "op": op,
+            "operation": request.operation.as_str(),
+            "request": {
+                "tool": request.tool,
+                "prompt": request.prompt,
+                "arguments": arguments,
+                "timeout_ms": request.timeout_ms,
+                "render": request.render,
+                "correlation_id": request.correlation_id,
+            },
+            "config": {
+                "error_detail": ErrorDetail::current().as_str(),
+                "audience": Audience::current().as_str(),
+                "max_chunk_bytes": max_chunk_bytes(),
+                "max_result_bytes": max_result_bytes(),
+                "kv_handoff_ns": kv_handoff_ns,
+                "coerce_arguments": COERCE_ARGUMENTS.load(Ordering::Relaxed),
+                "retry": {
+                    "max_attempts": retry.max_attempts,
+                    "base_backoff_ms": retry.base_backoff_ms,
+                },
+                "tool_filter": {
+                    "allowed_tools": filter.allowed_tools,
+                    "denied_tools": filter.denied_tools,
+                },
+                "tool_prefix": (!prefix.is_empty()).then_some(prefix),
+                "tool_defaults": defaults,
+                "redact_keys": patterns,
+                "tools_cache_ttl_ms": TOOLS_CACHE.ttl_ms.load(Ordering::Relaxed),
+            },
+            "protocol": PROTOCOL,
+        },
+    })
+}
+
+fn render_capabilities(caps: &router::ServerCapabilities) -> Value {
+    json!({
+        "prompts": caps.prompts.as_ref().map(|p| json!({"list_changed": p.list_changed})),
+        "resources": caps.resources.as_ref().map(|r| json!({
+            "subscribe": r.subscribe,
+            "list_changed": r.list_changed,
+        })),
+        "tools": caps.tools.as_ref().map(|t| json!({"list_changed": t.list_changed})),
+        "completions": caps.completions.as_ref().map(|c| json!({"enabled": c.enabled})),
+    })
+}
+
+/// Meta key on the server description listing the secrets its tools need.
+const SECRET_REQUIREMENTS_META: &str = "secret_requirements";
+
+/// Secret requirements declared in the server's meta, as
+/// `{ key, required, description }` objects.
+///
+/// Entries may be bare secret names, which are required, or objects with a
+/// string `key`; anything else is skipped.
+fn secret_requirements(desc: &router::ServerDescription) -> Vec<Value> {
+    let declared = desc
+        .meta
+        .iter()
+        .flatten()
+        .find(|entry| entry.key == SECRET_REQUIREMENTS_META)
+        .map(|entry| parse_json_string(&entry.value));
+    let Some(Value::Array(entries)) = declared else {
+        return Vec::new();
+    };
+    entries
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Value::String(key) => Some(json!({
+                "key": key,
+                "required": true,
+                "description": null,
+            })),
+            Value::Object(fields) => {
+                let key = fields.get("key")?.as_str()?;
+                Some(json!({
+                    "key": key,
+                    "required": fields.get("required").and_then(Value::as_bool).unwrap_or(true),
+                    "description": fields.get("description").and_then(Value::as_str),
+                }))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn render_server_description(desc: &router::ServerDescription, instructions: &str) -> Value {
+    json!({
+        "ok": true,
+        "result": {
+            "name": desc.name,
+            "title": desc.title,
+            "instructions": instructions,
+            "capabilities": render_capabilities(&desc.capabilities),
+            "resources": desc.resources.as_deref().map(render_resources),
+            "resource_metadata": desc.resource_metadata.as_ref().map(|entries| {
+                entries
+                    .iter()
+                    .map(|entry| {
+                        json!({
+                            "authorization_servers": entry
+                                .authorization_servers
+                                .iter()
+                                .map(|server| json!({
+                                    "issuer": server.issuer,
+                                    "token_endpoint": server.token_endpoint,
+                                }))
+                                .collect::<Vec<_>>(),
+                            "resource_indicator": entry.resource_indicator,
+                            "default_scopes": entry.default_scopes,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            }),
+            "meta": meta_to_value(desc.meta.as_ref()),
+            "protocol": PROTOCOL,
+        }
+    })
+}
+
+/// A page of the catalog returned by `list`.
+///
+/// Encoded straight into the response body, so tool schemas, which can be
+/// large, are copied through verbatim instead of being parsed into a [`Value`]
+/// and encoded again on every listing.
+struct ToolPage {
+    tools: Vec<router::Tool>,
+    next_cursor: Option<String>,
+    correlation_id: Option<String>,
+}
+
+impl ToolPage {
+    fn body(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| "{\"ok\":true}".into())
+    }
+
+    fn into_value(self) -> Value {
+        serde_json::to_value(&self).unwrap_or(Value::Null)
+    }
+}
+
+impl Serialize for ToolPage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct PageResult<'a>(&'a ToolPage);
+
+        impl Serialize for PageResult<'_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let page = self.0;
+                let tools: Vec<ToolView<'_>> = page.tools.iter().map(ToolView).collect();
+                // Keys in the order a `Value` would encode them.
+                let mut map = serializer.serialize_map(None)?;
+                if let Some(cursor) = &page.next_cursor {
+                    map.serialize_entry("next_cursor", cursor)?;
+                }
+                map.serialize_entry("protocol", PROTOCOL)?;
+                map.serialize_entry("tools", &tools)?;
+                map.end()
+            }
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        if let Some(correlation_id) = &self.correlation_id {
+            map.serialize_entry(CORRELATION_ID, correlation_id)?;
+        }
+        map.serialize_entry("ok", &true)?;
+        map.serialize_entry("result", &PageResult(self))?;
+        map.end()
+    }
+}
+
+/// A tool as rendered by `list`, the manifest, and `list.check_changed`.
+struct ToolView<'a>(&'a router::Tool);
+
+impl Serialize for ToolView<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let tool = self.0;
+        let mut map = serializer.serialize_map(Some(7))?;
+        map.serialize_entry(
+            "annotations",
+            &tool.annotations.as_ref().map(render_tool_annotations),
+        )?;
+        map.serialize_entry("description", &tool.description)?;
+        map.serialize_entry("input_schema", &JsonText(&tool.input_schema))?;
+        map.serialize_entry("meta", &meta_to_value(tool.meta.as_ref()))?;
+        map.serialize_entry("name", &tool.name)?;
+        map.serialize_entry(
+            "output_schema",
+            &tool.output_schema.as_deref().map(JsonText),
+        )?;
+        map.serialize_entry("title", &tool.title)?;
+        map.end()
+    }
+}
+
+/// JSON text from the router, emitted verbatim when it is valid JSON and as a
+/// string otherwise: [`parse_json_string`] without building a [`Value`].
+struct JsonText<'a>(&'a str);
+
+impl Serialize for JsonText<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match serde_json::from_str::<&RawValue>(self.0) {
+            Ok(raw) => raw.serialize(serializer),
+            Err(_) => serializer.serialize_str(self.0),
+        }
+    }
+}
+
+fn render_tool(tool: &router::Tool) -> Value {
+    serde_json::to_value(ToolView(tool)).unwrap_or(Value::Null)
+}
+
+fn render_tool_annotations(ann: &router::ToolAnnotations) -> Value {
+    json!({
+        "read_only": ann.read_only,
+        "destructive": ann.destructive,
+        "streaming": ann.streaming,
+        "experimental": ann.experimental,
+    })
+}
+
+/// Page of the catalog requested by `list`: `limit` tools starting at the
+/// opaque `cursor` returned by the previous page. Without either, the whole
+/// catalog is returned.
+#[derive(Debug, Default, Deserialize)]
+struct ListPage {
+    #[serde(default)]
+    cursor: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+impl ListPage {
+    fn from_arguments(arguments: &Value) -> AdapterResult<Self> {
+        let page: Self = serde_json::from_value(arguments.clone()).map_err(|err| {
+            Box::new(config_error(
+                format!("invalid list arguments: {err}"),
+                None,
+                json!({"arguments": arguments}),
+            ))
+        })?;
+        if page.limit == Some(0) {
+            return Err(Box::new(config_error(
+                "limit must be positive".into(),
+                None,
+                json!({"arguments": arguments}),
+            )));
+        }
+        Ok(page)
+    }
+
+    /// The requested slice of `tools`, with `next_cursor` when more follow.
+    fn render(&self, mut tools: Vec<router::Tool>) -> AdapterResult<ToolPage> {
+        let start = match &self.cursor {
+            Some(cursor) => cursor
+                .parse::<usize>()
+                .ok()
+                .filter(|start| *start <= tools.len())
+                .ok_or_else(|| {
+                    Box::new(config_error(
+                        format!("invalid list cursor `{cursor}`"),
+                        None,
+                        json!({"cursor": cursor}),
+                    ))
+                })?,
+            None => 0,
+        };
+        let end = self.limit.map_or(tools.len(), |limit| {
+            start.saturating_add(limit).min(tools.len())
+        });
+        let next_cursor = (end < tools.len()).then(|| end.to_string());
+        tools.truncate(end);
+        tools.drain(..start);
+        Ok(ToolPage {
+            tools,
+            next_cursor,
+            correlation_id: None,
+        })
+    }
+}
+
+/// Caller state for `list.check_changed`: the catalog hash it last saw and,
+/// optionally, the per-tool hashes needed to compute a delta.
+#[derive(Debug, Default, Deserialize)]
+struct ChangeCheck {
+    #[serde(default)]
+    hash: Option<String>,
+    #[serde(default)]
+    known: Option<BTreeMap<String, String>>,
+}
+
+impl ChangeCheck {
+    fn from_arguments(arguments: &Value) -> AdapterResult<Self> {
+        serde_json::from_value(arguments.clone()).map_err(|err| {
+            Box::new(config_error(
+                format!("invalid list.check_changed arguments: {err}"),
+                None,
+                json!({"arguments": arguments}),
+            ))
+        })
+    }
+}
+
+/// Compare the current catalog against the caller's hash and report the delta.
+///
+/// `changed` is computed from the catalog hash alone. A delta (`added`,
+/// `removed`, `updated`) is only possible when the caller echoes back the
+/// `tool_hashes` of an earlier response as `known`; otherwise a changed
+/// catalog is returned in full under `tools`.
+fn render_change_check(tools: &[router::Tool], check: &ChangeCheck) -> Value {
+    let rendered: BTreeMap<&str, Value> = tools
+        .iter()
+        .map(|tool| (tool.name.as_str(), render_tool(tool)))
+        .collect();
+    let tool_hashes: BTreeMap<&str, String> = rendered
+        .iter()
+        .map(|(name, tool)| (*name, fingerprint(tool.to_string().as_bytes())))
+        .collect();
+    let catalog = tool_hashes
+        .iter()
+        .map(|(name, hash)| format!("{name}={hash}\n"))
+        .collect::<String>();
+    let hash = fingerprint(catalog.as_bytes());
+    let changed = check.hash.as_deref() != Some(hash.as_str());
+
+    let mut result = json!({
+        "changed": changed,
+        "hash": hash,
+        "tool_hashes": tool_hashes,
+        "protocol": PROTOCOL,
+    });
+    if changed {
+        match &check.known {
+            Some(known) => {
+                let added: Vec<&Value> = rendered
+                    .iter()
+                    .filter(|(name, _)| !known.contains_key(**name))
+                    .map(|(_, tool)| tool)
+                    .collect();
+                let updated: Vec<&Value> = rendered
+                    .iter()
+                    .filter(|(name, _)| {
+                        known
+                            .get(**name)
+                            .is_some_and(|previous| *previous != tool_hashes[**name])
+                    })
+                    .map(|(_, tool)| tool)
+                    .collect();
+                let removed: Vec<&String> = known
+                    .keys()
+                    .filter(|name| !rendered.contains_key(name.as_str()))
+                    .collect();
+                result["delta"] = json!({
+                    "added": added,
+                    "removed": removed,
+                    "updated": updated,
+                });
+            }
+            None => result["tools"] = json!(rendered.values().collect::<Vec<_>>()),
+        }
+    }
+
+    json!({ "ok": true, "result": result })
+}
+
+/// 64-bit FNV-1a digest rendered as hex; stable across adapter builds.
+fn fingerprint(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+fn render_tool_result(result: &router::ToolResult, audience: Audience) -> Value {
+    let mut messages = Vec::new();
+    let mut assistant_content = Vec::new();
+    let mut result_annotations: Option<Value> = None;
+    let content: Vec<Value> = result
+        .content
+        .iter()
+        .filter(|block| audience != Audience::User || !is_assistant_only(block))
+        .map(|block| {
+            let (payload, message, annotations) = render_content_block(block);
+            if let Some(message) = message {
+                if audience == Audience::Split && is_assistant_only(block) {
+                    assistant_content.push(message);
+                } else {
+                    messages.push(message);
+                }
+            }
+            if result_annotations.is_none() {
+                result_annotations = annotations;
+            }
+            payload
+        })
+        .collect();
+
+    let mut payload = json!({
+        "ok": true,
+        "result": {
+            "content": content,
+            "structured_content": result.structured_content.as_ref().map(|s| parse_json_string(s)),
+            "progress": result.progress.as_deref().map(render_progress),
+            "meta": meta_to_value(result.meta.as_ref()),
+            "is_error": result.is_error,
+            "annotations": result_annotations,
+        },
+        "messages": Value::Array(messages),
+        "protocol": PROTOCOL,
+    });
+    if audience == Audience::Split {
+        payload["assistant_content"] = Value::Array(assistant_content);
+    }
+
+    payload
+}
+
+/// Whether the block's `annotations.audience` names the assistant and nobody else.
+fn is_assistant_only(block: &router::ContentBlock) -> bool {
+    let annotations = match block {
+        router::ContentBlock::Text(text) => text.annotations.as_ref(),
+        router::ContentBlock::Image(image) => image.annotations.as_ref(),
+        router::ContentBlock::Audio(audio) => audio.annotations.as_ref(),
+        router::ContentBlock::ResourceLink(link) => link.annotations.as_ref(),
+        router::ContentBlock::EmbeddedResource(res) => res.annotations.as_ref(),
+    };
+    annotations
+        .and_then(|ann| ann.audience.as_deref())
+        .is_some_and(|roles| {
+            !roles.is_empty()
+                && roles
+                    .iter()
+                    .all(|role| matches!(role, router::Role::Assistant))
+        })
+}
+
+/// Meta key on a tool, or on its result, suggesting how long a read-only
+/// result stays fresh.
+const CACHE_TTL_META: &str = "cache_ttl_seconds";
+
+/// Cache-control hint for a successful result of a tool annotated read-only;
+/// a TTL in the result's meta overrides the tool's.
+fn cache_hint(tool: &router::Tool, result: &router::ToolResult) -> Option<Value> {
+    let read_only = tool
+        .annotations
+        .as_ref()
+        .and_then(|annotations| annotations.read_only)
+        .unwrap_or(false);
+    if !read_only {
+        return None;
+    }
+    let ttl = [result.meta.as_ref(), tool.meta.as_ref()]
+        .into_iter()
+        .flatten()
+        .find_map(|entries| entries.iter().find(|entry| entry.key == CACHE_TTL_META))
+        .and_then(|entry| parse_json_string(&entry.value).as_u64());
+    Some(json!({ "cacheable": true, "ttl_seconds": ttl }))
+}
+
+/// Replace the value of every object field whose key matches one of the
+/// lowercase `patterns`, at any depth, with [`REDACTED`]. Keys are compared
+/// case-insensitively. Returns the number of fields redacted.
+fn redact_fields(value: &mut Value, patterns: &[String]) -> usize {
+    match value {
+        Value::Object(fields) => fields
+            .iter_mut()
+            .map(|(key, field)| {
+                let key = key.to_lowercase();
+                if patterns.iter().any(|pattern| glob_match(pattern, &key)) {
+                    *field = Value::String(REDACTED.into());
+                    1
+                } else {
+                    redact_fields(field, patterns)
+                }
+            })
+            .sum(),
+        Value::Array(items) => items
+            .iter_mut()
+            .map(|item| redact_fields(item, patterns))
+            .sum(),
+        _ => 0,
+    }
+}
+
+/// Catalog entry for `name`, or `None` when it is missing or listing fails.
+fn find_tool<R: McpRouter>(router: &R, name: &str) -> Option<router::Tool> {
+    router
+        .list_tools()
+        .ok()
+        .and_then(|tools| tools.into_iter().find(|tool| tool.name == name))
+}
+
+/// Convert string arguments to the types `input_schema` declares, for hosts
+/// that pass every value as a string: `"3"` becomes `3`, `"true"` becomes
+/// `true`, and JSON text becomes an object or array. Values the schema allows
+/// as strings, and strings that do not parse as the declared type, are left
+/// for the router to validate.
+fn coerce_arguments(input_schema: &str, arguments: Value) -> Value {
+    match serde_json::from_str::<Value>(input_schema) {
+        Ok(schema) => coerce_value(&schema, arguments),
+        Err(_) => arguments,
+    }
+}
+
+fn coerce_value(schema: &Value, value: Value) -> Value {
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(kind)) => vec![kind.as_str()],
+        Some(Value::Array(kinds)) => kinds.iter().filter_map(Value::as_str).collect(),
+        _ if schema.get("properties").is_some() => vec!["object"],
+        _ => Vec::new(),
+    };
+    match value {
+        Value::String(text) if !types.contains(&"string") => types
+            .iter()
+            .find_map(|kind| parse_as(kind, &text))
+            .map(|parsed| coerce_value(schema, parsed))
+            .unwrap_or(Value::String(text)),
+        Value::Object(mut fields) => {
+            if let Some(Value::Object(properties)) = schema.get("properties") {
+                for (name, property) in properties {
+                    if let Some(field) = fields.remove(name) {
+                        fields.insert(name.clone(), coerce_value(property, field));
+                    }
+                }
+            }
+            Value::Object(fields)
+        }
+        Value::Array(items) => match schema.get("items") {
+            Some(item_schema) => Value::Array(
+                items
+                    .into_iter()
+                    .map(|item| coerce_value(item_schema, item))
+                    .collect(),
+            ),
+            None => Value::Array(items),
+        },
+        other => other,
+    }
+}
+
+/// `text` as a JSON value of schema type `kind`, if it is one.
+fn parse_as(kind: &str, text: &str) -> Option<Value> {
+    let parsed: Value = match kind {
+        "boolean" => match text.trim() {
+            "true" => return Some(Value::Bool(true)),
+            "false" => return Some(Value::Bool(false)),
+            _ => return None,
+        },
+        "integer" | "number" | "object" | "array" | "null" => {
+            serde_json::from_str(text.trim()).ok()?
+        }
+        _ => return None,
+    };
+    let matches = match kind {
+        "integer" => parsed.is_i64() || parsed.is_u64(),
+        "number" => parsed.is_number(),
+        "object" => parsed.is_object(),
+        "array" => parsed.is_array(),
+        _ => parsed.is_null(),
+    };
+    matches.then_some(parsed)
+}
+
+fn resource_uri(arguments: &Value) -> AdapterResult<String> {
+    match arguments.get("uri").and_then(Value::as_str) {
+        Some(uri) if !uri.trim().is_empty() => Ok(uri.to_string()),
+        _ => Err(Box::new(config_error(
+            "arguments.uri is required for operation=resources.read".into(),
+            None,
+            json!({"arguments": arguments}),
+        ))),
+    }
+}
+
+fn render_resource_list(resources: &[router::McpResource]) -> Value {
+    json!({
+        "ok": true,
+        "result": {
+            "resources": render_resources(resources),
+            "protocol": PROTOCOL,
+        }
+    })
+}
+
+fn render_resources(resources: &[router::McpResource]) -> Vec<Value> {
+    resources
+        .iter()
+        .map(|res| {
+            json!({
+                "uri": res.uri,
+                "name": res.name,
+                "title": res.title,
+                "description": res.description,
+                "mime_type": res.mime_type,
+                "annotations": res.annotations.as_ref().map(render_annotations),
+            })
+        })
+        .collect()
+}
+
+/// Render resource contents as embedded-resource content blocks, the same
+/// shape `call` uses for resources embedded in tool results.
+fn render_read_resource(uri: &str, result: &router::ReadResourceResult) -> Value {
+    let mut messages = Vec::new();
+    let content: Vec<Value> = result
+        .contents
+        .iter()
+        .map(|contents| {
+            let embedded = match contents {
+                router::ResourceContents::Text(text) => router::EmbeddedResource {
+                    uri: text.uri.clone(),
+                    title: None,
+                    description: None,
+                    mime_type: text.mime_type.clone(),
+                    data: text.text.clone(),
+                    annotations: None,
+                },
+                router::ResourceContents::Blob(blob) => router::EmbeddedResource {
+                    uri: blob.uri.clone(),
+                    title: None,
+                    description: None,
+                    mime_type: blob.mime_type.clone(),
+                    data: blob.blob.clone(),
+                    annotations: None,
+                },
+            };
+            let (mut payload, message, _) = render_embedded_resource(&embedded);
+            if let Some(message) = message {
+                messages.push(message);
+            }
+            payload["encoding"] = json!(match contents {
+                router::ResourceContents::Text(_) => "text",
+                router::ResourceContents::Blob(_) => "base64",
+            });
+            payload
+        })
+        .collect();
+
+    json!({
+        "ok": true,
+        "result": {
+            "uri": uri,
+            "content": content,
+        },
+        "messages": Value::Array(messages),
+        "protocol": PROTOCOL,
+    })
+}
+
+fn render_prompt_list(prompts: &[router::Prompt]) -> Value {
+    let rendered: Vec<Value> = prompts
+        .iter()
+        .map(|prompt| {
+            json!({
+                "name": prompt.name,
+                "description": prompt.description,
+                "arguments": prompt.arguments.as_ref().map(|args| {
+                    args.iter()
+                        .map(|arg| {
+                            json!({
+                                "name": arg.name,
+                                "description": arg.description,
+                                "required": arg.required,
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                }),
+            })
+        })
+        .collect();
+    json!({
+        "ok": true,
+        "result": {
+            "prompts": rendered,
+            "protocol": PROTOCOL,
+        }
+    })
+}
+
+/// Render prompt messages through the tool-result content mapping, tagging
+/// each content block and message card with the speaker's role.
+fn render_prompt(name: &str, result: &router::GetPromptResult) -> Value {
+    let mut messages = Vec::new();
+    let content: Vec<Value> = result
+        .messages
+        .iter()
+        .map(|message| {
+            let role = match message.role {
+                router::PromptMessageRole::User => "user",
+                router::PromptMessageRole::Assistant => "assistant",
+            };
+            let block = match &message.content {
+                router::PromptMessageContent::Text(text) => {
+                    router::ContentBlock::Text(text.clone())
+                }
+                router::PromptMessageContent::Image(image) => {
+                    router::ContentBlock::Image(image.clone())
+                }
+                router::PromptMessageContent::McpResource(res) => {
+                    router::ContentBlock::EmbeddedResource(res.clone())
+                }
+            };
+            let (mut payload, message, _) = render_content_block(&block);
+            if let Some(mut message) = message {
+                message["role"] = json!(role);
+                messages.push(message);
+            }
+            payload["role"] = json!(role);
+            payload
+        })
+        .collect();
+
+    json!({
+        "ok": true,
+        "result": {
+            "name": name,
+            "description": result.description,
+            "content": content,
+        },
+        "messages": Value::Array(messages),
+        "protocol": PROTOCOL,
+    })
+}
+
+fn render_progress(progress: &[router::ProgressNotification]) -> Value {
+    Value::Array(
+        progress
+            .iter()
+            .map(|p| {
+                json!({
+                    "progress": p.progress,
+                    "message": p.message,
+                    "annotations": p.annotations.as_ref().map(render_annotations),
+                })
+            })
+            .collect(),
+    )
+}
+
+#[cfg(feature = "elicitation")]
+fn render_elicitation(req: &router::ElicitationRequest) -> Value {
+    json!({
+        "ok": true,
+        "elicitation": {
+            "title": req.title,
+            "message": req.message,
+            "schema": parse_json_string(&req.schema),
+            "annotations": req.annotations.as_ref().map(render_annotations),
+            "meta": meta_to_value(req.meta.as_ref()),
+        },
+        "messages": [{
+            "type": "text",
+            "text": req.message,
+        }],
+        "protocol": PROTOCOL,
+    })
+}
+
+fn render_content_block(block: &router::ContentBlock) -> (Value, Option<Value>, Option<Value>) {
+    match block {
+        router::ContentBlock::Text(text) => {
+            let payload = json!({
+                "type": "text",
+                "text": text.text,
+                "annotations": text.annotations.as_ref().map(render_annotations),
+            });
+            let message = json!({
+                "type": "text",
+                "text": text.text,
+            });
+            (
+                payload,
+                Some(message),
+                text.annotations.as_ref().map(render_annotations),
+            )
+        }
+        #[cfg(feature = "media")]
+        router::ContentBlock::Image(image) => {
+            let payload = json!({
+                "type": "image",
+                "data": image.data,
+                "mime_type": image.mime_type,
+                "annotations": image.annotations.as_ref().map(render_annotations),
+            });
+            let message = json!({
+                "type": "image",
+                "mime_type": image.mime_type,
+                "data": image.data,
+            });
+            (
+                payload,
+                Some(message),
+                image.annotations.as_ref().map(render_annotations),
+            )
+        }
+        #[cfg(not(feature = "media"))]
+        router::ContentBlock::Image(image) => omitted_media(
+            "image",
+            &image.mime_type,
+            &image.data,
+            image.annotations.as_ref(),
+        ),
+        #[cfg(feature = "media")]
+        router::ContentBlock::Audio(audio) => {
+            let payload = json!({
+                "type": "audio",
+                "data": audio.data,
+                "mime_type": audio.mime_type,
+                "annotations": audio.annotations.as_ref().map(render_annotations),
+            });
+            let message = json!({
+                "type": "audio",
+                "mime_type": audio.mime_type,
+                "data": audio.data,
+            });
+            (
+                payload,
+                Some(message),
+                audio.annotations.as_ref().map(render_annotations),
+            )
+        }
+        #[cfg(not(feature = "media"))]
+        router::ContentBlock::Audio(audio) => omitted_media(
+            "audio",
+            &audio.mime_type,
+            &audio.data,
+            audio.annotations.as_ref(),
+        ),
+        router::ContentBlock::ResourceLink(link) => {
+            let payload = json!({
+                "type": "resource_link",
+                "uri": link.uri,
+                "title": link.title,
+                "description": link.description,
+                "mime_type": link.mime_type,
+                "annotations": link.annotations.as_ref().map(render_annotations),
+            });
+            let message = json!({
+                "type": "resource_link",
+                "uri": link.uri,
+                "title": link.title,
+                "description": link.description,
+            });
+            (
+                payload,
+                Some(message),
+                link.annotations.as_ref().map(render_annotations),
+            )
+        }
+        #[cfg(feature = "embedded-resources")]
+        router::ContentBlock::EmbeddedResource(res) => render_embedded_resource(res),
+        // Without embedded resources, point at the resource instead; its
+        // contents stay available through `resources.read`.
+        #[cfg(not(feature = "embedded-resources"))]
+        router::ContentBlock::EmbeddedResource(res) => render_content_block(
+            &router::ContentBlock::ResourceLink(router::ResourceLinkContent {
+                uri: res.uri.clone(),
+                title: res.title.clone(),
+                description: res.description.clone(),
+                mime_type: res.mime_type.clone(),
+                annotations: res.annotations.clone(),
+            }),
+        ),
+    }
+}
+
+fn render_embedded_resource(
+    res: &router::EmbeddedResource,
+) -> (Value, Option<Value>, Option<Value>) {
+    let payload = json!({
+        "type": "resource",
+        "uri": res.uri,
+        "title": res.title,
+        "description": res.description,
+        "mime_type": res.mime_type,
+        "data": res.data,
+        "annotations": res.annotations.as_ref().map(render_annotations),
+    });
+    let message = json!({
+        "type": "resource",
+        "uri": res.uri,
+        "title": res.title,
+        "description": res.description,
+        "mime_type": res.mime_type,
+    });
+    (
+        payload,
+        Some(message),
+        res.annotations.as_ref().map(render_annotations),
+    )
+}
+
+/// Placeholder for image or audio content in builds without the `media`
+/// feature, shaped like the result budget's omitted blocks.
+#[cfg(not(feature = "media"))]
+fn omitted_media(
+    kind: &str,
+    mime_type: &str,
+    data: &str,
+    annotations: Option<&router::Annotations>,
+) -> (Value, Option<Value>, Option<Value>) {
+    let description = format!("{kind} content is not rendered by this adapter build");
+    let uri = format!("urn:greentic:mcp-adapter:omitted:{kind}");
+    let payload = json!({
+        "type": "resource_link",
+        "uri": uri,
+        "description": description,
+        "mime_type": mime_type,
+        "truncated": true,
+        "original_type": kind,
+        "original_bytes": data.len(),
+        "annotations": annotations.map(render_annotations),
+    });
+    let message = json!({
+        "type": "resource_link",
+        "uri": uri,
+        "title": null,
+        "description": description,
+    });
+    (payload, Some(message), annotations.map(render_annotations))
+}
+
+/// Join message cards into one markdown document, a paragraph per card.
+///
+/// Images become inline images and resources become links; image and audio
+/// data is embedded as a data URI. Cards carrying a `role` are prefixed with
+/// the speaker.
+fn render_markdown(cards: &[Value]) -> String {
+    let field = |card: &Value, key: &str| card.get(key).and_then(Value::as_str).map(str::to_owned);
+    cards
+        .iter()
+        .filter_map(|card| {
+            let data_uri = || {
+                let mime = field(card, "mime_type").unwrap_or_default();
+                format!(
+                    "data:{mime};base64,{}",
+                    field(card, "data").unwrap_or_default()
+                )
+            };
+            let body = match card.get("type")?.as_str()? {
+                "text" => field(card, "text")?,
+                "image" => format!("![image]({})", data_uri()),
+                "audio" => format!("[audio]({})", data_uri()),
+                "resource_link" | "resource" => {
+                    let uri = field(card, "uri")?;
+                    let label = field(card, "title").unwrap_or_else(|| uri.clone());
+                    match field(card, "description") {
+                        Some(description) => format!("[{label}]({uri}): {description}"),
+                        None => format!("[{label}]({uri})"),
+                    }
+                }
+                _ => return None,
+            };
+            Some(match field(card, "role") {
+                Some(role) => format!("**{role}:** {body}"),
+                None => body,
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn render_annotations(ann: &router::Annotations) -> Value {
+    json!({
+        "audience": ann.audience.as_ref().map(|roles| {
+            roles.iter().map(|role| match role {
+                router::Role::User => "user",
+                router::Role::Assistant => "assistant",
+            }).collect::<Vec<_>>()
+        }),
+        "priority": ann.priority,
+        "timestamp": ann.timestamp,
+    })
+}
+
+fn meta_to_value(meta: Option<&Vec<router::MetaEntry>>) -> Option<Value> {
+    meta.map(|entries| {
+        let mut map = Map::new();
+        for entry in entries {
+            map.insert(entry.key.clone(), parse_json_string(&entry.value));
+        }
+        Value::Object(map)
+    })
+}
+
+/// Field of a content block or message card carrying a payload that can grow
+/// without bound.
+fn chunkable_field(block: &Value) -> Option<&'static str> {
+    match block.get("type")?.as_str()? {
+        "text" => Some("text"),
+        "image" | "audio" => Some("data"),
+        _ => None,
+    }
+}
+
+/// Split `payload` into pieces of at most `limit` bytes, each ending on a
+/// char boundary and, for base64 `data`, on a whole quantum.
+fn split_payload<'a>(payload: &'a str, field: &str, limit: usize) -> Vec<&'a str> {
+    let limit = limit.max(MIN_CHUNK_BYTES);
+    let step = if field == "data" {
+        limit - limit % 4
+    } else {
+        limit
+    };
+    let mut pieces = Vec::new();
+    let mut rest = payload;
+    while !rest.is_empty() {
+        let mut end = step.min(rest.len());
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (piece, tail) = rest.split_at(end);
+        pieces.push(piece);
+        rest = tail;
+    }
+    pieces
+}
+
+/// Lists of content blocks and message cards in an envelope.
+const BLOCK_LISTS: [&str; 3] = ["/result/content", "/messages", "/assistant_content"];
+
+/// Oversized payloads in `result.content`, `messages`, and `assistant_content`,
+/// as (pointer, field).
+fn oversized_blocks(envelope: &Value, limit: usize) -> Vec<(String, &'static str)> {
+    let mut found = Vec::new();
+    for list in BLOCK_LISTS {
+        let Some(blocks) = envelope.pointer(list).and_then(Value::as_array) else {
+            continue;
+        };
+        for (index, block) in blocks.iter().enumerate() {
+            if let Some(field) = chunkable_field(block)
+                && block
+                    .get(field)
+                    .and_then(Value::as_str)
+                    .is_some_and(|payload| payload.len() > limit)
+            {
+                found.push((format!("{list}/{index}"), field));
+            }
+        }
+    }
+    found
+}
+
+/// Cut a block's payload down to its first piece and mark it truncated.
+fn truncate_block(block: &mut Value, field: &str, limit: usize, link: Option<Value>) {
+    let Some(payload) = block.get(field).and_then(Value::as_str) else {
+        return;
+    };
+    let original = payload.len();
+    let kept = split_payload(payload, field, limit)
+        .first()
+        .copied()
+        .unwrap_or_default()
+        .to_string();
+    block[field] = json!(kept);
+    block["truncated"] = json!(true);
+    block["original_bytes"] = json!(original);
+    if let Some(link) = link {
+        block["full_content"] = link;
+    }
+}
+
+/// Non-streaming mode: truncate oversized payloads so hosts never buffer more
+/// than `limit` bytes per block. Content blocks point at `invoke-stream` for
+/// the rest, or at the full payload stashed through `handoff`.
+fn truncate_oversized(envelope: &mut Value, limit: usize, handoff: Option<&KvHandoff>) {
+    for (pointer, field) in oversized_blocks(envelope, limit) {
+        let Some(block) = envelope.pointer_mut(&pointer) else {
+            continue;
+        };
+        let link = pointer
+            .strip_prefix("/result/content/")
+            .map(|index| match handoff {
+                Some(handoff) => handoff.stash(block[field].as_str().unwrap_or_default()),
+                None => json!({
+                    "type": "resource_link",
+                    "uri": format!("urn:greentic:mcp-adapter:invoke-stream:content:{index}"),
+                    "description": "Truncated; invoke-stream with the same input delivers the full payload in chunks.",
+                }),
+            });
+        truncate_block(block, field, limit, link);
+    }
+}
+
+/// Non-streaming mode: replace the largest content blocks and message cards
+/// with `resource_link` placeholders until the rendered envelope fits in
+/// `limit` bytes. Placeholders keep the block's type and size, and
+/// `result.meta` gains `truncated: true` and the envelope's `original_bytes`.
+/// An envelope that still does not fit once no block can shrink is sent as is.
+fn fit_result_budget(envelope: &mut Value, limit: usize) {
+    let original = render_body(envelope).len();
+    if original <= limit {
+        return;
+    }
+    let mut blocks: Vec<(usize, String)> = BLOCK_LISTS
+        .iter()
+        .flat_map(|list| {
+            envelope
+                .pointer(list)
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .enumerate()
+                .map(move |(index, block)| (render_body(block).len(), format!("{list}/{index}")))
+        })
+        .collect();
+    blocks.sort_by(|a, b| b.0.cmp(&a.0));
+
+    for (bytes, pointer) in blocks {
+        if render_body(envelope).len() <= limit {
+            break;
+        }
+        let Some(block) = envelope.pointer_mut(&pointer) else {
+            continue;
+        };
+        let placeholder = omitted_block(block, &pointer, bytes);
+        if render_body(&placeholder).len() >= bytes {
+            continue;
+        }
+        *block = placeholder;
+        if let Some(result) = envelope.get_mut("result").and_then(Value::as_object_mut) {
+            let meta = result.entry("meta").or_insert(Value::Null);
+            if !meta.is_object() {
+                *meta = json!({});
+            }
+            meta["truncated"] = json!(true);
+            meta["original_bytes"] = json!(original);
+        }
+    }
+}
+
+/// Placeholder for the block at `pointer`, `bytes` long once rendered, that
+/// was left out of an `invoke` body.
+fn omitted_block(block: &Value, pointer: &str, bytes: usize) -> Value {
+    json!({
+        "type": "resource_link",
+        "uri": format!(
+            "urn:greentic:mcp-adapter:omitted:{}",
+            pointer.trim_start_matches('/').replace('/', ":")
+        ),
+        "description": "Omitted to fit the result budget; invoke-stream with the same input delivers it.",
+        "mime_type": block.get("mime_type"),
+        "truncated": true,
+        "original_type": block.get("type"),
+        "original_bytes": bytes,
+    })
+}
+
+/// Streaming mode: the envelope is emitted first with oversized content
+/// payloads emptied and marked `chunked`, followed by one event per chunk.
+/// Message cards are only previews and are truncated instead.
+fn chunk_oversized(mut envelope: Value, limit: usize) -> Vec<Value> {
+    let mut chunks = Vec::new();
+    for (pointer, field) in oversized_blocks(&envelope, limit) {
+        let Some(block) = envelope.pointer_mut(&pointer) else {
+            continue;
+        };
+        let Some(index) = pointer.strip_prefix("/result/content/") else {
+            truncate_block(block, field, limit, None);
+            continue;
+        };
+        let payload = block[field].as_str().unwrap_or_default().to_string();
+        let pieces = split_payload(&payload, field, limit);
+        let count = pieces.len();
+        chunks.extend(pieces.into_iter().enumerate().map(|(seq, piece)| {
+            json!({
+                "chunk": {
+                    "block": index.parse::<usize>().unwrap_or_default(),
+                    "field": field,
+                    "seq": seq,
+                    "last": seq + 1 == count,
+                    "data": piece,
+                }
+            })
+        }));
+        block[field] = json!("");
+        block["chunked"] = json!({ "chunks": count, "bytes": payload.len() });
+    }
+    let mut parts = vec![envelope];
+    parts.extend(chunks);
+    parts
+}
+
+/// The `data` events of `invoke-stream` for a successful envelope: one
+/// `progress` event per notification in `result.progress`, in order, then
+/// the envelope, then its chunks when payloads exceed `chunk_limit`.
+///
+/// Progress is reported before the tool completed, so it precedes the
+/// content it led up to. Every event carries the envelope's `correlation_id`.
+fn stream_parts(envelope: Value, chunk_limit: Option<usize>) -> Vec<Value> {
+    let correlation_id = envelope.get(CORRELATION_ID).cloned();
+    let mut parts: Vec<Value> = envelope
+        .pointer("/result/progress")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(|(seq, notification)| {
+            json!({
+                "type": "progress",
+                "seq": seq,
+                "progress": notification["progress"],
+                "message": notification["message"],
+                "annotations": notification["annotations"],
+            })
+        })
+        .collect();
+    match chunk_limit {
+        Some(limit) => parts.extend(chunk_oversized(envelope, limit)),
+        None => parts.push(envelope),
+    }
+    if let Some(correlation_id) = correlation_id {
+        for part in &mut parts {
+            part[CORRELATION_ID] = correlation_id.clone();
+        }
+    }
+    parts
+}
+
+fn parse_json_string(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+fn transport_error(err: RouterError, tool: Option<String>) -> ErrorEnvelope {
+    if let RouterError::Panic { operation, message } = err {
+        return panic_error(operation, message, tool);
+    }
+    ErrorEnvelope::new("MCP_ROUTER_ERROR", 502, err.to_string(), tool, Value::Null)
+}
+
+/// Call a tool, retrying transient failures per `policy` as long as the next
+/// attempt can start within `budget`. Returns the last outcome and the number
+/// of attempts made.
+fn call_with_retries<R: McpRouter>(
+    router: &R,
+    tool: &str,
+    arguments: &Value,
+    policy: RetryPolicy,
+    budget: Option<Duration>,
+    cancelled: &dyn Fn() -> bool,
+) -> (Result<router::Response, CallFailure>, u32) {
+    let started = Instant::now();
+    let mut attempt = 1;
+    loop {
+        let response = router.call_tool(tool, arguments);
+        let retry = match &response {
+            Err(err) if is_transient(err) && attempt < policy.max_attempts => {
+                let delay = policy.backoff(attempt);
+                budget
+                    .is_none_or(|budget| started.elapsed() + delay < budget)
+                    .then_some(delay)
+            }
+            _ => None,
+        };
+        let Some(delay) = retry else {
+            return (response, attempt);
+        };
+        if !sleep_unless_cancelled(delay, cancelled) {
+            return (response, attempt);
+        }
+        attempt += 1;
+    }
+}
+
+/// How often a backoff wait checks for cancellation.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Sleep for `delay`, waking early when `cancelled` turns true. Returns
+/// whether the full delay elapsed.
+fn sleep_unless_cancelled(delay: Duration, cancelled: &dyn Fn() -> bool) -> bool {
+    let until = Instant::now() + delay;
+    loop {
+        if cancelled() {
+            return false;
+        }
+        let left = until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        std::thread::sleep(left.min(CANCEL_POLL_INTERVAL));
+    }
+}
+
+/// Transport failures and tool execution errors may pass on another attempt;
+/// invalid parameters, unknown tools, and router panics will not.
+fn is_transient(err: &CallFailure) -> bool {
+    matches!(
+        err,
+        CallFailure::Transport(_) | CallFailure::Tool(router::ToolError::ExecutionError(_))
+    )
+}
+
+/// Error envelope for a failed call, suggesting a backoff to the host when
+/// the adapter gave up retrying a transient failure.
+fn call_failure(err: CallFailure, tool: &str, policy: RetryPolicy, attempts: u32) -> ErrorEnvelope {
+    let backoff = (policy.max_attempts > 1 && is_transient(&err))
+        .then(|| policy.backoff(attempts).as_millis() as u64);
+    let mut envelope = map_call_error(err, tool);
+    envelope.error.retry_after_ms = backoff;
+    envelope
+}
+
+fn map_call_error(err: CallFailure, tool: &str) -> ErrorEnvelope {
+    match err {
+        CallFailure::Tool(tool_err) => match tool_err {
+            router::ToolError::InvalidParameters(msg) => tool_error(400, msg, tool),
+            router::ToolError::ExecutionError(msg) => tool_error(500, msg, tool),
+            router::ToolError::SchemaError(msg) => tool_error(422, msg, tool),
+            router::ToolError::NotFound(msg) => tool_error(404, msg, tool),
+        },
+        CallFailure::Forbidden => forbidden_error(tool),
+        CallFailure::Transport(msg) => {
+            transport_error(RouterError::Transport(msg), Some(tool.to_string()))
+        }
+        CallFailure::Panic { operation, message } => {
+            panic_error(operation, message, Some(tool.to_string()))
+        }
+    }
+}
+
+fn map_read_error(err: ReadFailure, uri: &str) -> ErrorEnvelope {
+    let (status, message) = match err {
+        ReadFailure::Resource(router::ResourceError::NotFound(msg)) => (404, msg),
+        ReadFailure::Resource(router::ResourceError::ExecutionError(msg)) => (500, msg),
+        ReadFailure::Router(err) => return transport_error(err, None),
+    };
+    ErrorEnvelope::new(
+        "MCP_RESOURCE_ERROR",
+        status,
+        message,
+        None,
+        json!({"uri": uri}),
+    )
+}
+
+fn map_prompt_error(err: PromptFailure, name: &str) -> ErrorEnvelope {
+    let (status, message) = match err {
+        PromptFailure::Prompt(router::PromptError::InvalidParameters(msg)) => (400, msg),
+        PromptFailure::Prompt(router::PromptError::NotFound(msg)) => (404, msg),
+        PromptFailure::Prompt(router::PromptError::InternalError(msg)) => (500, msg),
+        PromptFailure::Router(err) => return transport_error(err, None),
+    };
+    ErrorEnvelope::new(
+        "MCP_PROMPT_ERROR",
+        status,
+        message,
+        None,
+        json!({"prompt": name}),
+    )
+}
+
+fn panic_error(operation: &'static str, message: String, tool: Option<String>) -> ErrorEnvelope {
+    ErrorEnvelope::new(
+        "MCP_ROUTER_PANIC",
+        502,
+        format!("router panicked during {operation}"),
+        tool,
+        json!({"operation": operation, "panic": message}),
+    )
+}
+
+fn tool_error(status: u16, message: String, tool: &str) -> ErrorEnvelope {
+    ErrorEnvelope::new(
+        "MCP_TOOL_ERROR",
+        status,
+        message,
+        Some(tool.to_string()),
+        Value::Null,
+    )
+}
+
+fn forbidden_error(tool: &str) -> ErrorEnvelope {
+    ErrorEnvelope::new(
+        "MCP_TOOL_FORBIDDEN",
+        403,
+        format!("tool `{tool}` is not permitted by the adapter's tool filter"),
+        Some(tool.to_string()),
+        Value::Null,
+    )
+}
+
+fn timeout_error(tool: &str, budget: Duration, elapsed: Duration) -> ErrorEnvelope {
+    ErrorEnvelope::new(
+        "MCP_TIMEOUT",
+        504,
+        format!("router call exceeded its {} ms budget", budget.as_millis()),
+        Some(tool.to_string()),
+        json!({
+            "timeout_ms": budget.as_millis() as u64,
+            "elapsed_ms": elapsed.as_millis() as u64,
+        }),
+    )
+}
+
+fn cancelled_error(tool: &str, elapsed: Duration) -> ErrorEnvelope {
+    ErrorEnvelope::new(
+        "MCP_CANCELLED",
+        499,
+        "router call was cancelled by the host".into(),
+        Some(tool.to_string()),
+        json!({ "elapsed_ms": elapsed.as_millis() as u64 }),
+    )
+}
+
+fn config_error(message: String, tool: Option<String>, details: Value) -> ErrorEnvelope {
+    ErrorEnvelope::new("MCP_CONFIG_ERROR", 400, message, tool, details)
+}
+
+fn default_arguments() -> Value {
+    json!({})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+    use std::path::PathBuf;
+    use std::process::Command;
+    use wasmtime::component::Linker;
+    use wasmtime::{Engine, Store};
+    use wasmtime_wasi::{
+        ResourceTable, WasiCtx, WasiCtxBuilder, WasiCtxView, WasiView, p2::add_to_linker_sync,
+    };
+
+    struct MockRouter {
+        tools: Vec<router::Tool>,
+        response: Option<router::Response>,
+    }
+
+    impl McpRouter for MockRouter {
+        fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+            Ok(self.tools.clone())
+        }
+
+        fn call_tool(
+            &self,
+            _tool: &str,
+            _arguments: &Value,
+        ) -> Result<router::Response, CallFailure> {
+            self.response
+                .clone()
+                .ok_or_else(|| CallFailure::Transport("no response".into()))
+        }
+    }
+
+    fn sample_tool() -> router::Tool {
+        router::Tool {
+            name: "demo".into(),
+            title: Some("Demo".into()),
+            description: "Example".into(),
+            input_schema: r#"{\"type\":\"object\"}"#.into(),
+            output_schema: Some(
+                r#"{"type":"object","properties":{"result":{"type":"string"}}}"#.into(),
+            ),
+            annotations: None,
+            meta: None,
+        }
+    }
+
+    #[test]
+    fn list_operation_defaults_without_tool() {
+        let router = MockRouter {
+            tools: vec![sample_tool()],
+            response: None,
+        };
+
+        let result =
+            handle_invoke(&router, "", r#"{"arguments": {}}"#).expect("list should succeed");
+
+        assert_eq!(result.get("ok"), Some(&Value::Bool(true)));
+        let tools = result
+            .get("result")
+            .and_then(|r| r.get("tools"))
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        assert_eq!(tools.len(), 1);
+    }
+
+    #[test]
+    fn list_pages_through_the_catalog_with_cursors() {
+        let router = MockRouter {
+            tools: ["a", "b", "c", "d", "e"]
+                .into_iter()
+                .map(sample_tool_named)
+                .collect(),
+            response: None,
+        };
+        let names = |result: &Value| -> Vec<String> {
+            result["result"]["tools"]
+                .as_array()
+                .expect("tools")
+                .iter()
+                .map(|tool| tool["name"].as_str().expect("name").to_string())
+                .collect()
+        };
+
+        let mut pages = Vec::new();
+        let mut cursor = Value::Null;
+        loop {
+            let input = json!({"operation": "list", "arguments": {"limit": 2, "cursor": cursor}});
+            let result = handle_invoke(&router, "", &input.to_string()).expect("page");
+            pages.push(names(&result));
+            match result["result"].get("next_cursor") {
+                Some(next) => cursor = next.clone(),
+                None => break,
+            }
+        }
+        assert_eq!(pages, [vec!["a", "b"], vec!["c", "d"], vec!["e"]]);
+
+        let all = handle_invoke(&router, "", r#"{"operation":"list"}"#).expect("list");
+        assert_eq!(names(&all).len(), 5);
+        assert!(all["result"].get("next_cursor").is_none());
+
+        for bad in [
+            r#"{"operation":"list","arguments":{"cursor":"9"}}"#,
+            r#"{"operation":"list","arguments":{"cursor":"x"}}"#,
+            r#"{"operation":"list","arguments":{"limit":0}}"#,
+        ] {
+            let err = handle_invoke(&router, "", bad).expect_err("invalid page");
+            assert_eq!(err.error.code, "MCP_CONFIG_ERROR", "{bad}");
+        }
+    }
+
+    #[test]
+    fn list_bodies_carry_schemas_verbatim() {
+        let schema = r#"{ "type": "object", "properties": { "q": { "type": "string" } } }"#;
+        let tool = router::Tool {
+            input_schema: schema.into(),
+            ..sample_tool()
+        };
+        let broken = router::Tool {
+            input_schema: "{not json".into(),
+            output_schema: None,
+            ..sample_tool_named("broken")
+        };
+        let page = ListPage {
+            cursor: None,
+            limit: Some(1),
+        }
+        .render(vec![tool.clone(), broken.clone()])
+        .expect("page");
+
+        let body = page.body();
+        assert!(body.contains(schema), "{body}");
+        let value = page.into_value();
+        assert_eq!(serde_json::from_str::<Value>(&body).expect("body"), value);
+        assert_eq!(value["result"]["tools"][0], render_tool(&tool));
+        assert_eq!(
+            value["result"]["tools"][0]["input_schema"]["properties"]["q"]["type"],
+            json!("string")
+        );
+        assert_eq!(value["result"]["next_cursor"], json!("1"));
+
+        // Text that is not JSON is passed through as a string, as before.
+        assert_eq!(render_tool(&broken)["input_schema"], json!("{not json"));
+    }
+
+    #[test]
+    fn check_changed_reports_delta_against_known_hashes() {
+        let router = MockRouter {
+            tools: vec![sample_tool(), sample_tool_named("other")],
+            response: None,
+        };
+
+        let first = handle_invoke(&router, "list.check_changed", "{}").expect("check");
+        let first = &first["result"];
+        assert_eq!(first["changed"], Value::Bool(true));
+        assert_eq!(first["tools"].as_array().map(Vec::len), Some(2));
+
+        let unchanged = handle_invoke(
+            &router,
+            "",
+            &json!({
+                "operation": "list.check_changed",
+                "arguments": {"hash": first["hash"]},
+            })
+            .to_string(),
+        )
+        .expect("check");
+        assert_eq!(unchanged["result"]["changed"], Value::Bool(false));
+        assert!(unchanged["result"].get("tools").is_none());
+
+        let mut updated = sample_tool();
+        updated.description = "Changed".into();
+        let router = MockRouter {
+            tools: vec![updated, sample_tool_named("fresh")],
+            response: None,
+        };
+        let delta = handle_invoke(
+            &router,
+            "list.check_changed",
+            &json!({"arguments": {"hash": first["hash"], "known": first["tool_hashes"]}})
+                .to_string(),
+        )
+        .expect("check");
+        let delta = &delta["result"]["delta"];
+        assert_eq!(delta["added"][0]["name"], json!("fresh"));
+        assert_eq!(delta["updated"][0]["name"], json!("demo"));
+        assert_eq!(delta["removed"], json!(["other"]));
+    }
+
+    fn sample_tool_named(name: &str) -> router::Tool {
+        let mut tool = sample_tool();
+        tool.name = name.into();
+        tool
+    }
+
+    #[test]
+    fn router_panics_map_to_panic_envelope() {
+        struct PanickingRouter;
+
+        impl McpRouter for PanickingRouter {
+            fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+                contain_panic("list-tools", || panic!("list exploded"))
+            }
+
+            fn call_tool(
+                &self,
+                _tool: &str,
+                _arguments: &Value,
+            ) -> Result<router::Response, CallFailure> {
+                Ok(contain_panic("call-tool", || -> router::Response {
+                    panic!("call exploded")
+                })?)
+            }
+        }
+
+        let err = handle_invoke(&PanickingRouter, "list", "{}").expect_err("list panics");
+        assert_eq!(err.error.code, "MCP_ROUTER_PANIC");
+        assert_eq!(err.error.details["operation"], json!("list-tools"));
+        assert_eq!(err.error.details["panic"], json!("list exploded"));
+
+        let err =
+            handle_invoke(&PanickingRouter, "", r#"{"tool":"demo"}"#).expect_err("call panics");
+        assert_eq!(err.error.code, "MCP_ROUTER_PANIC");
+        assert_eq!(err.error.status, 502);
+        assert_eq!(err.error.tool.as_deref(), Some("demo"));
+        assert_eq!(err.error.details["operation"], json!("call-tool"));
+    }
+
+    #[test]
+    fn terse_detail_strips_messages_and_payloads() {
+        let err = handle_invoke(
+            &MockRouter {
+                tools: vec![],
+                response: None,
+            },
+            "",
+            "not json",
+        )
+        .expect_err("invalid payload");
+        assert!(err.error.details.get("raw").is_some());
+
+        let full = err.redact(ErrorDetail::Full);
+        assert!(full.error.message.contains("invalid request payload"));
+
+        let terse = full.redact(ErrorDetail::Terse);
+        assert_eq!(terse.error.code, "MCP_CONFIG_ERROR");
+        assert_eq!(terse.error.status, 400);
+        assert_eq!(terse.error.message, "invalid adapter request");
+        assert_eq!(terse.error.details, Value::Null);
+        assert!(
+            !terse
+                .node_error()
+                .details
+                .unwrap_or_default()
+                .contains("not json")
+        );
+
+        assert_eq!(ErrorDetail::parse(" TERSE "), Some(ErrorDetail::Terse));
+        assert_eq!(ErrorDetail::parse("verbose"), None);
+    }
+
+    #[test]
+    fn manifest_publishes_envelope_schemas() {
+        let manifest = build_manifest(&MockRouter {
+            tools: vec![sample_tool()],
+            response: None,
+        });
+        let request = &manifest["schemas"]["request"];
+        let operations = request["properties"]["operation"]["enum"]
+            .as_array()
+            .expect("operation enum");
+        for op in operations {
+            let op = op.as_str().expect("operation name");
+            let parsed = parse_operation(op);
+            assert!(parsed.is_some(), "{op} is not accepted");
+            assert_eq!(parsed.map(|parsed| parsed.as_str()), Some(op));
+        }
+
+        let defs = &manifest["schemas"]["response"]["$defs"];
+        for def in [
+            "list",
+            "check_changed",
+            "call",
+            "elicitation",
+            "resources",
+            "resource_read",
+            "prompts",
+            "prompt",
+            "describe",
+            "metrics",
+            "debug_echo",
+            "error",
+            "tool",
+        ] {
+            assert!(defs.get(def).is_some(), "missing response def {def}");
+        }
+        let responses = manifest["schemas"]["response"]["anyOf"]
+            .as_array()
+            .expect("response alternatives");
+        assert_eq!(responses.len(), 12);
+    }
+
+    #[test]
+    fn manifest_embeds_live_tools_and_survives_router_panics() {
+        let manifest = build_manifest(&MockRouter {
+            tools: vec![sample_tool(), sample_tool_named("other")],
+            response: None,
+        });
+        assert_eq!(manifest["tools"][0]["name"], json!("demo"));
+        assert_eq!(manifest["tools"][1]["name"], json!("other"));
+        assert_eq!(
+            manifest["tools"][0]["output_schema"]["properties"]["result"]["type"],
+            json!("string")
+        );
+        assert!(manifest.get("tools_error").is_none());
+
+        struct PanickingRouter;
+
+        impl McpRouter for PanickingRouter {
+            fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+                contain_panic("list-tools", || panic!("list exploded"))
+            }
+
+            fn call_tool(
+                &self,
+                _tool: &str,
+                _arguments: &Value,
+            ) -> Result<router::Response, CallFailure> {
+                Err(CallFailure::Transport("unused".into()))
+            }
+        }
+
+        let manifest = build_manifest(&PanickingRouter);
+        assert_eq!(manifest["tools"], Value::Null);
+        assert_eq!(manifest["tools_error"]["code"], json!("MCP_ROUTER_PANIC"));
+        assert_eq!(manifest["protocol"], json!(PROTOCOL));
+        assert!(manifest["schemas"]["request"].is_object());
+    }
+
+    #[test]
+    fn startup_probe_reports_broken_routers() {
+        struct PanickingRouter;
+
+        impl McpRouter for PanickingRouter {
+            fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+                contain_panic("list-tools", || panic!("router not linked"))
+            }
+
+            fn call_tool(
+                &self,
+                _tool: &str,
+                _arguments: &Value,
+            ) -> Result<router::Response, CallFailure> {
+                Err(CallFailure::Transport("unused".into()))
+            }
+        }
+
+        let healthy = MockRouter {
+            tools: vec![sample_tool()],
+            response: None,
+        };
+        assert_eq!(StartupProbe::ListTools.run(&healthy), Ok(()));
+        assert_eq!(StartupProbe::None.run(&PanickingRouter), Ok(()));
+
+        let err = StartupProbe::ListTools
+            .run(&PanickingRouter)
+            .expect_err("panicking router");
+        assert!(
+            err.contains("list-tools returned MCP_ROUTER_PANIC"),
+            "{err}"
+        );
+        assert!(err.contains("router not linked"), "{err}");
+
+        // MockRouter cannot describe itself.
+        let err = StartupProbe::Describe
+            .run(&healthy)
+            .expect_err("undescribed router");
+        assert!(
+            err.contains("describe-server returned MCP_ROUTER_ERROR"),
+            "{err}"
+        );
+
+        assert_eq!(
+            StartupProbe::parse(" List-Tools "),
+            Some(StartupProbe::ListTools)
+        );
+        assert_eq!(StartupProbe::parse("ping"), None);
+    }
+
+    #[test]
+    fn manifest_advertises_server_description_and_secret_requirements() {
+        struct SecretiveRouter;
+
+        impl McpRouter for SecretiveRouter {
+            fn describe_server(&self) -> Result<router::ServerDescription, RouterError> {
+                Ok(router::ServerDescription {
+                    name: "crm".into(),
+                    title: None,
+                    capabilities: router::ServerCapabilities {
+                        prompts: None,
+                        resources: None,
+                        tools: Some(router::ToolsCapability { list_changed: None }),
+                        completions: None,
+                    },
+                    resources: None,
+                    resource_metadata: None,
+                    meta: Some(vec![router::MetaEntry {
+                        key: SECRET_REQUIREMENTS_META.into(),
+                        value: json!([
+                            "CRM_TOKEN",
+                            {"key": "CRM_WEBHOOK", "required": false, "description": "Webhook signing key"},
+                            42,
+                        ])
+                        .to_string(),
+                    }]),
+                })
+            }
+
+            fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+                Ok(vec![sample_tool()])
+            }
+
+            fn call_tool(
+                &self,
+                _tool: &str,
+                _arguments: &Value,
+            ) -> Result<router::Response, CallFailure> {
+                Err(CallFailure::Transport("unused".into()))
+            }
+        }
+
+        let manifest = build_manifest(&SecretiveRouter);
+        assert_eq!(manifest["server"]["name"], json!("crm"));
+        assert_eq!(
+            manifest["server"]["capabilities"]["tools"],
+            json!({"list_changed": null})
+        );
+        assert_eq!(
+            manifest["secret_requirements"],
+            json!([
+                {"key": "CRM_TOKEN", "required": true, "description": null},
+                {"key": "CRM_WEBHOOK", "required": false, "description": "Webhook signing key"},
+            ])
+        );
+        assert!(manifest.get("server_error").is_none());
+
+        // Routers that cannot describe themselves still get a manifest.
+        let manifest = build_manifest(&MockRouter {
+            tools: vec![sample_tool()],
+            response: None,
+        });
+        assert_eq!(manifest["server"], Value::Null);
+        assert_eq!(manifest["server_error"]["code"], json!("MCP_ROUTER_ERROR"));
+        assert_eq!(manifest["secret_requirements"], json!([]));
+        assert_eq!(manifest["tools"][0]["name"], json!("demo"));
+    }
+
+    #[test]
+    fn call_operation_routes_arguments() {
+        let router = MockRouter {
+            tools: vec![],
+            response: Some(router::Response::Completed(router::ToolResult {
+                content: vec![router::ContentBlock::Text(router::TextContent {
+                    text: "hi".into(),
+                    annotations: None,
+                })],
+                structured_content: None,
+                progress: None,
+                meta: None,
+                is_error: None,
+            })),
+        };
+
+        let result = handle_invoke(
+            &router,
+            "",
+            r#"{"operation":"call","tool":"demo","arguments":{"foo":"bar"}}"#,
+        )
+        .expect("call should succeed");
+
+        assert_eq!(result.get("ok"), Some(&Value::Bool(true)));
+        let messages = result
+            .get("messages")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        assert_eq!(messages.len(), 1);
+    }
+
+    #[cfg(not(any(
+        feature = "media",
+        feature = "embedded-resources",
+        feature = "elicitation"
+    )))]
+    #[test]
+    fn slim_builds_link_to_content_they_do_not_render() {
+        let router = MockRouter {
+            tools: vec![],
+            response: Some(router::Response::Completed(router::ToolResult {
+                content: vec![
+                    router::ContentBlock::Image(router::ImageContent {
+                        data: "iVBORw==".into(),
+                        mime_type: "image/png".into(),
+                        annotations: None,
+                    }),
+                    router::ContentBlock::EmbeddedResource(router::EmbeddedResource {
+                        uri: "file:///notes.txt".into(),
+                        title: Some("Notes".into()),
+                        description: None,
+                        mime_type: Some("text/plain".into()),
+                        data: "remember the milk".into(),
+                        annotations: None,
+                    }),
+                ],
+                structured_content: None,
+                progress: None,
+                meta: None,
+                is_error: None,
+            })),
+        };
+        let result = handle_invoke(&router, "", r#"{"tool":"demo"}"#).expect("call");
+        let content = &result["result"]["content"];
+        assert_eq!(content[0]["type"], json!("resource_link"));
+        assert_eq!(
+            content[0]["uri"],
+            json!("urn:greentic:mcp-adapter:omitted:image")
+        );
+        assert_eq!(content[0]["original_type"], json!("image"));
+        assert_eq!(content[0]["original_bytes"], json!(8));
+        assert_eq!(content[1]["type"], json!("resource_link"));
+        assert_eq!(content[1]["uri"], json!("file:///notes.txt"));
+        assert!(content[1].get("data").is_none());
+
+        let router = MockRouter {
+            tools: vec![],
+            response: Some(router::Response::Elicit(router::ElicitationRequest {
+                title: None,
+                message: "Which account?".into(),
+                schema: "{}".into(),
+                annotations: None,
+                meta: None,
+            })),
+        };
+        let err = handle_invoke(&router, "", r#"{"tool":"demo"}"#).expect_err("unsupported");
+        assert_eq!(err.error.code, "MCP_TOOL_ERROR");
+        assert_eq!(err.error.status, 501);
+    }
+
+    #[cfg(feature = "media")]
+    #[test]
+    fn markdown_render_joins_messages_into_one_card() {
+        let router = MockRouter {
+            tools: vec![],
+            response: Some(router::Response::Completed(router::ToolResult {
+                content: vec![
+                    router::ContentBlock::Text(router::TextContent {
+                        text: "Here is the chart:".into(),
+                        annotations: None,
+                    }),
+                    router::ContentBlock::Image(router::ImageContent {
+                        data: "iVBORw==".into(),
+                        mime_type: "image/png".into(),
+                        annotations: None,
+                    }),
+                    router::ContentBlock::ResourceLink(router::ResourceLinkContent {
+                        uri: "https://example.com/report".into(),
+                        title: Some("Full report".into()),
+                        description: None,
+                        mime_type: None,
+                        annotations: None,
+                    }),
+                ],
+                structured_content: None,
+                progress: None,
+                meta: None,
+                is_error: None,
+            })),
+        };
+
+        let result = handle_invoke(
+            &router,
+            "",
+            r#"{"operation":"call","tool":"demo","render":"markdown"}"#,
+        )
+        .expect("call should succeed");
+        assert_eq!(
+            result["messages"],
+            json!([{
+                "type": "text",
+                "format": "markdown",
+                "text": "Here is the chart:\n\n![image](data:image/png;base64,iVBORw==)\n\n[Full report](https://example.com/report)",
+            }])
+        );
+        assert_eq!(
+            result["result"]["content"].as_array().map(Vec::len),
+            Some(3)
+        );
+
+        let err = handle_invoke(&router, "", r#"{"tool":"demo","render":"html"}"#)
+            .expect_err("unknown render mode");
+        assert_eq!(err.error.code, "MCP_CONFIG_ERROR");
+    }
+
+    #[test]
+    fn call_operation_preserves_typed_arguments() {
+        struct AssertArgsRouter {
+            expected: Value,
+        }
+
+        impl McpRouter for AssertArgsRouter {
+            fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+                Ok(vec![])
+            }
+
+            fn call_tool(
+                &self,
+                _tool: &str,
+                arguments: &Value,
+            ) -> Result<router::Response, CallFailure> {
+                if arguments != &self.expected {
+                    return Err(CallFailure::Transport(format!(
+                        "unexpected arguments: {arguments}"
+                    )));
+                }
+
+                Ok(router::Response::Completed(router::ToolResult {
+                    content: vec![],
+                    structured_content: None,
+                    progress: None,
+                    meta: None,
+                    is_error: None,
+                }))
+            }
+        }
+
+        let router = AssertArgsRouter {
+            expected: json!({
+                "count": 3,
+                "active": true,
+                "items": ["a", "b"],
+                "meta": {"score": 9.5},
+            }),
+        };
+
+        let result = handle_invoke(
+            &router,
+            "",
+            r#"{"operation":"call","tool":"demo","arguments":{"count":3,"active":true,"items":["a","b"],"meta":{"score":9.5}}}"#,
+        )
+        .expect("call should succeed");
+
+        assert_eq!(result.get("ok"), Some(&Value::Bool(true)));
+    }
+
+    #[test]
+    fn tool_error_maps_to_envelope() {
+        let _router = MockRouter {
+            tools: vec![],
+            response: Some(router::Response::Completed(router::ToolResult {
+                content: vec![],
+                structured_content: None,
+                progress: None,
+                meta: None,
+                is_error: Some(true),
+            })),
+        };
+
+        let err = map_call_error(
+            CallFailure::Tool(router::ToolError::InvalidParameters("bad".into())),
+            "demo",
+        );
+        assert_eq!(err.error.code, "MCP_TOOL_ERROR");
+        assert_eq!(err.error.status, 400);
+    }
+
+    #[test]
+    fn structured_content_and_resource_link_round_trip() {
+        let router = MockRouter {
+            tools: vec![],
+            response: Some(router::Response::Completed(router::ToolResult {
+                content: vec![router::ContentBlock::ResourceLink(
+                    router::ResourceLinkContent {
+                        uri: "https://example.com/doc".into(),
+                        title: Some("Doc".into()),
+                        description: Some("desc".into()),
+                        mime_type: Some("text/html".into()),
+                        annotations: None,
+                    },
+                )],
+                structured_content: Some(r#"{"result":42}"#.into()),
+                progress: None,
+                meta: Some(vec![router::MetaEntry {
+                    key: "source".into(),
+                    value: r#""demo-router""#.into(),
+                }]),
+                is_error: None,
+            })),
+        };
+
+        let result = handle_invoke(
+            &router,
+            "",
+            r#"{"operation":"call","tool":"demo","arguments":{"foo":"bar"}}"#,
+        )
+        .expect("call should succeed");
+
+        assert_eq!(result.get("ok"), Some(&Value::Bool(true)));
+        let structured = result
+            .pointer("/result/structured_content/result")
+            .cloned()
+            .unwrap();
+        assert_eq!(structured, json!(42));
+
+        let content = result
+            .get("result")
+            .and_then(|r| r.get("content"))
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        assert_eq!(content.len(), 1);
+        assert_eq!(
+            content[0].get("type"),
+            Some(&Value::String("resource_link".into()))
+        );
+
+        let messages = result
+            .get("messages")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        assert_eq!(
+            messages.first().and_then(|m| m.get("type")),
+            Some(&Value::String("resource_link".into()))
+        );
+    }
+
+    #[test]
+    fn resource_operations_list_and_read_contents() {
+        struct ResourceRouter;
+
+        impl McpRouter for ResourceRouter {
+            fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+                Ok(vec![])
+            }
+
+            fn call_tool(
+                &self,
+                _tool: &str,
+                _arguments: &Value,
+            ) -> Result<router::Response, CallFailure> {
+                Err(CallFailure::Transport("no tools".into()))
+            }
+
+            fn list_resources(&self) -> Result<Vec<router::McpResource>, RouterError> {
+                Ok(vec![router::McpResource {
+                    uri: "file:///readme.md".into(),
+                    name: "readme".into(),
+                    title: Some("Readme".into()),
+                    description: None,
+                    mime_type: "text/markdown".into(),
+                    annotations: None,
+                }])
+            }
+
+            fn read_resource(&self, uri: &str) -> Result<router::ReadResourceResult, ReadFailure> {
+                if uri != "file:///readme.md" {
+                    return Err(ReadFailure::Resource(router::ResourceError::NotFound(
+                        format!("no resource at {uri}"),
+                    )));
+                }
+                Ok(router::ReadResourceResult {
+                    contents: vec![
+                        router::ResourceContents::Text(router::TextResourceContents {
+                            uri: uri.into(),
+                            mime_type: Some("text/markdown".into()),
+                            text: "# Hello".into(),
+                        }),
+                        router::ResourceContents::Blob(router::BlobResourceContents {
+                            uri: "file:///logo.png".into(),
+                            mime_type: Some("image/png".into()),
+                            blob: "iVBORw0KGgo=".into(),
+                        }),
+                    ],
+                })
+            }
+        }
+
+        let list = handle_invoke(&ResourceRouter, "resources.list", "{}").expect("list");
+        assert_eq!(list["result"]["resources"][0]["name"], json!("readme"));
+        assert_eq!(
+            list["result"]["resources"][0]["mime_type"],
+            json!("text/markdown")
+        );
+
+        let read = handle_invoke(
+            &ResourceRouter,
+            "",
+            r#"{"operation":"resources.read","arguments":{"uri":"file:///readme.md"}}"#,
+        )
+        .expect("read");
+        let content = &read["result"]["content"];
+        assert_eq!(content[0]["type"], json!("resource"));
+        assert_eq!(content[0]["data"], json!("# Hello"));
+        assert_eq!(content[0]["encoding"], json!("text"));
+        assert_eq!(content[1]["encoding"], json!("base64"));
+        assert_eq!(read["messages"].as_array().map(Vec::len), Some(2));
+
+        let err = handle_invoke(
+            &ResourceRouter,
+            "resources.read",
+            r#"{"arguments":{"uri":"file:///missing"}}"#,
+        )
+        .expect_err("missing resource");
+        assert_eq!(err.error.code, "MCP_RESOURCE_ERROR");
+        assert_eq!(err.error.status, 404);
+        assert_eq!(err.error.details["uri"], json!("file:///missing"));
+
+        let err = handle_invoke(&ResourceRouter, "resources.read", "{}").expect_err("no uri");
+        assert_eq!(err.error.code, "MCP_CONFIG_ERROR");
+    }
+
+    #[cfg(feature = "embedded-resources")]
+    #[test]
+    fn prompt_operations_render_messages() {
+        struct PromptRouter;
+
+        impl McpRouter for PromptRouter {
+            fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+                Ok(vec![])
+            }
+
+            fn call_tool(
+                &self,
+                _tool: &str,
+                _arguments: &Value,
+            ) -> Result<router::Response, CallFailure> {
+                Err(CallFailure::Transport("no tools".into()))
+            }
+
+            fn list_prompts(&self) -> Result<Vec<router::Prompt>, RouterError> {
+                Ok(vec![router::Prompt {
+                    name: "summarize".into(),
+                    description: Some("Summarize a document".into()),
+                    arguments: Some(vec![router::PromptArgument {
+                        name: "uri".into(),
+                        description: None,
+                        required: Some(true),
+                    }]),
+                }])
+            }
+
+            fn get_prompt(&self, name: &str) -> Result<router::GetPromptResult, PromptFailure> {
+                if name != "summarize" {
+                    return Err(PromptFailure::Prompt(router::PromptError::NotFound(
+                        format!("unknown prompt {name}"),
+                    )));
+                }
+                Ok(router::GetPromptResult {
+                    description: Some("Summarize a document".into()),
+                    messages: vec![
+                        router::PromptMessage {
+                            role: router::PromptMessageRole::User,
+                            content: router::PromptMessageContent::Text(router::TextContent {
+                                text: "Summarize this".into(),
+                                annotations: None,
+                            }),
+                        },
+                        router::PromptMessage {
+                            role: router::PromptMessageRole::Assistant,
+                            content: router::PromptMessageContent::McpResource(
+                                router::EmbeddedResource {
+                                    uri: "file:///doc.md".into(),
+                                    title: None,
+                                    description: None,
+                                    mime_type: Some("text/markdown".into()),
+                                    data: "# Doc".into(),
+                                    annotations: None,
+                                },
+                            ),
+                        },
+                    ],
+                })
+            }
+        }
+
+        let list = handle_invoke(&PromptRouter, "prompts.list", "{}").expect("list");
+        assert_eq!(list["result"]["prompts"][0]["name"], json!("summarize"));
+        assert_eq!(
+            list["result"]["prompts"][0]["arguments"][0]["required"],
+            json!(true)
+        );
+
+        let prompt = handle_invoke(
+            &PromptRouter,
+            "",
+            r#"{"operation":"prompts.get","prompt":"summarize"}"#,
+        )
+        .expect("get");
+        let messages = prompt["messages"].as_array().expect("messages");
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["role"], json!("user"));
+        assert_eq!(messages[0]["text"], json!("Summarize this"));
+        assert_eq!(messages[1]["role"], json!("assistant"));
+        assert_eq!(messages[1]["type"], json!("resource"));
+        assert_eq!(prompt["result"]["content"][1]["data"], json!("# Doc"));
+
+        let err = handle_invoke(
+            &PromptRouter,
+            "",
+            r#"{"operation":"prompts.get","prompt":"missing"}"#,
+        )
+        .expect_err("unknown prompt");
+        assert_eq!(err.error.code, "MCP_PROMPT_ERROR");
+        assert_eq!(err.error.status, 404);
+
+        let err = handle_invoke(&PromptRouter, "prompts.get", "{}").expect_err("no prompt");
+        assert_eq!(err.error.code, "MCP_CONFIG_ERROR");
+    }
+
+    struct SlowRouter {
+        delay: Duration,
+        calls: RefCell<u32>,
+    }
+
+    impl McpRouter for SlowRouter {
+        fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+            Ok(vec![sample_tool()])
+        }
+
+        fn call_tool(
+            &self,
+            _tool: &str,
+            _arguments: &Value,
+        ) -> Result<router::Response, CallFailure> {
+            *self.calls.borrow_mut() += 1;
+            std::thread::sleep(self.delay);
+            Ok(router::Response::Completed(router::ToolResult {
+                content: Vec::new(),
+                structured_content: None,
+                progress: None,
+                meta: None,
+                is_error: None,
+            }))
+        }
+    }
+
+    struct FlakyRouter {
+        failures: RefCell<u32>,
+        error: fn() -> CallFailure,
+    }
+
+    impl McpRouter for FlakyRouter {
+        fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+            Ok(vec![sample_tool()])
+        }
+
+        fn call_tool(
+            &self,
+            _tool: &str,
+            _arguments: &Value,
+        ) -> Result<router::Response, CallFailure> {
+            let mut failures = self.failures.borrow_mut();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err((self.error)());
+            }
+            Ok(router::Response::Completed(router::ToolResult {
+                content: Vec::new(),
+                structured_content: None,
+                progress: None,
+                meta: None,
+                is_error: None,
+            }))
+        }
+    }
+
+    #[test]
+    fn tool_filter_hides_and_refuses_excluded_tools() {
+        assert!(glob_match("read_*", "read_file"));
+        assert!(glob_match("*_file", "read_file"));
+        assert!(glob_match("r*d*e", "read_file"));
+        assert!(!glob_match("read", "read_file"));
+        assert!(!glob_match("ab*b", "ab"));
+
+        let tools: ToolFilter = serde_json::from_str(
+            r#"{"allowed_tools": ["demo", "delete_*"], "denied_tools": ["delete_all"]}"#,
+        )
+        .expect("filter document");
+        let router = Filtered {
+            inner: MockRouter {
+                tools: vec![
+                    sample_tool(),
+                    sample_tool_named("other"),
+                    sample_tool_named("delete_one"),
+                    sample_tool_named("delete_all"),
+                ],
+                response: Some(router::Response::Completed(router::ToolResult {
+                    content: Vec::new(),
+                    structured_content: None,
+                    progress: None,
+                    meta: None,
+                    is_error: None,
+                })),
+            },
+            tools,
+        };
+
+        let list = handle_invoke(&router, "list", "{}").expect("list");
+        let names: Vec<_> = list["result"]["tools"]
+            .as_array()
+            .expect("tools")
+            .iter()
+            .map(|tool| tool["name"].clone())
+            .collect();
+        assert_eq!(names, [json!("demo"), json!("delete_one")]);
+
+        handle_invoke(&router, "", r#"{"tool":"demo"}"#).expect("allowed call");
+        for denied in ["delete_all", "other"] {
+            let input = json!({ "tool": denied }).to_string();
+            let err = handle_invoke(&router, "", &input).expect_err("refused call");
+            assert_eq!(err.error.code, "MCP_TOOL_FORBIDDEN");
+            assert_eq!(err.error.status, 403);
+            assert!(!err.node_error().retryable);
+        }
+        assert!(serde_json::from_str::<ToolFilter>(r#"{"allow": []}"#).is_err());
+    }
+
+    #[test]
+    fn tool_prefix_namespaces_listed_and_called_tools() {
+        let router = Prefixed {
+            inner: Filtered {
+                inner: MockRouter {
+                    tools: vec![sample_tool(), sample_tool_named("other")],
+                    response: Some(router::Response::Completed(router::ToolResult {
+                        content: Vec::new(),
+                        structured_content: None,
+                        progress: None,
+                        meta: None,
+                        is_error: None,
+                    })),
+                },
+                tools: ToolFilter {
+                    denied_tools: vec!["other".into()],
+                    ..ToolFilter::default()
+                },
+            },
+            prefix: "crm".into(),
+        };
+
+        let list = handle_invoke(&router, "list", "{}").expect("list");
+        assert_eq!(list["result"]["tools"][0]["name"], "crm.demo");
+        assert_eq!(list["result"]["tools"].as_array().map(Vec::len), Some(1));
+
+        handle_invoke(&router, "", r#"{"tool":"crm.demo"}"#).expect("prefixed call");
+        let err = handle_invoke(&router, "", r#"{"tool":"demo"}"#).expect_err("bare name");
+        assert_eq!((err.error.code, err.error.status), ("MCP_TOOL_ERROR", 404));
+        let err = handle_invoke(&router, "", r#"{"tool":"crm.other"}"#).expect_err("denied");
+        assert_eq!(err.error.code, "MCP_TOOL_FORBIDDEN");
+        let err = handle_invoke(&router, "", r#"{"tool":"crmdemo"}"#).expect_err("no separator");
+        assert_eq!(err.error.status, 404);
+    }
+
+    /// Records the arguments of every call it receives.
+    struct RecordingRouter {
+        calls: RefCell<Vec<Value>>,
+    }
+
+    impl McpRouter for RecordingRouter {
+        fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+            Ok(vec![sample_tool()])
+        }
+
+        fn call_tool(
+            &self,
+            _tool: &str,
+            arguments: &Value,
+        ) -> Result<router::Response, CallFailure> {
+            self.calls.borrow_mut().push(arguments.clone());
+            Ok(router::Response::Completed(router::ToolResult {
+                content: Vec::new(),
+                structured_content: None,
+                progress: None,
+                meta: None,
+                is_error: None,
+            }))
+        }
+    }
+
+    #[test]
+    fn tool_defaults_are_merged_under_caller_arguments() {
+        let router = Defaulted {
+            inner: RecordingRouter {
+                calls: RefCell::new(Vec::new()),
+            },
+            defaults: BTreeMap::from([(
+                "demo".to_string(),
+                json!({ "region": "eu-west-1", "scope": { "workspace": "w1", "team": "core" } }),
+            )]),
+        };
+
+        let input = json!({
+            "tool": "demo",
+            "arguments": { "region": "us-east-1", "scope": { "team": "ops" }, "q": "x" }
+        });
+        handle_invoke(&router, "", &input.to_string()).expect("call");
+        handle_invoke(&router, "", r#"{"tool":"demo"}"#).expect("call without arguments");
+        handle_invoke(&router, "", r#"{"tool":"other"}"#).expect("tool without defaults");
+
+        assert_eq!(
+            *router.inner.calls.borrow(),
+            [
+                json!({
+                    "region": "us-east-1",
+                    "scope": { "workspace": "w1", "team": "ops" },
+                    "q": "x"
+                }),
+                json!({ "region": "eu-west-1", "scope": { "workspace": "w1", "team": "core" } }),
+                json!({}),
+            ]
+        );
+        assert_eq!(
+            merge_defaults(&json!({ "scope": { "a": 1 } }), json!({ "scope": "flat" })),
+            json!({ "scope": "flat" })
+        );
+    }
+
+    #[test]
+    fn correlation_ids_reach_the_router_and_every_envelope() {
+        let router = RecordingRouter {
+            calls: RefCell::new(Vec::new()),
+        };
+        let envelope = handle_invoke(
+            &router,
+            "",
+            r#"{"tool":"demo","arguments":{"q":"x"},"correlation_id":"flow-7"}"#,
+        )
+        .expect("call");
+        assert_eq!(envelope["correlation_id"], json!("flow-7"));
+        assert_eq!(
+            router.calls.borrow()[0],
+            json!({ "q": "x", "_meta": { "correlation_id": "flow-7" } })
+        );
+
+        let listed =
+            handle_invoke(&router, "list", r#"{"correlation_id":"flow-7"}"#).expect("list");
+        assert_eq!(listed["correlation_id"], json!("flow-7"));
+        let uncorrelated = handle_invoke(&router, "list", "{}").expect("list");
+        assert!(uncorrelated.get("correlation_id").is_none());
+
+        let err = handle_invoke(
+            &router,
+            "",
+            r#"{"operation":"prompts.get","correlation_id":"flow-7"}"#,
+        )
+        .expect_err("prompt is required");
+        let body = serde_json::to_value(&err).expect("encode");
+        assert_eq!(body["correlation_id"], json!("flow-7"));
+        assert_eq!(body["error"]["code"], json!("MCP_CONFIG_ERROR"));
+
+        let parts = stream_parts(
+            json!({
+                "ok": true,
+                "correlation_id": "flow-7",
+                "result": { "progress": [{ "progress": 0.5 }] },
+            }),
+            None,
+        );
+        assert!(
+            parts
+                .iter()
+                .all(|part| part["correlation_id"] == json!("flow-7"))
+        );
+    }
+
+    /// Counts listings; fails listings and calls while `failing` is set.
+    struct CountingRouter {
+        listings: Cell<u32>,
+        failing: Cell<bool>,
+    }
+
+    impl McpRouter for CountingRouter {
+        fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+            self.listings.set(self.listings.get() + 1);
+            if self.failing.get() {
+                return Err(RouterError::Transport("router down".into()));
+            }
+            Ok(vec![sample_tool()])
+        }
+
+        fn call_tool(
+            &self,
+            _tool: &str,
+            _arguments: &Value,
+        ) -> Result<router::Response, CallFailure> {
+            Err(CallFailure::Transport("router down".into()))
+        }
+    }
+
+    #[test]
+    fn tool_listings_are_cached_until_the_ttl_or_an_error() {
+        let cache = ToolsCache::new();
+        let router = Cached {
+            inner: CountingRouter {
+                listings: Cell::new(0),
+                failing: Cell::new(false),
+            },
+            cache: &cache,
+        };
+
+        // A zero TTL disables caching.
+        router.list_tools().expect("list");
+        router.list_tools().expect("list");
+        assert_eq!(router.inner.listings.get(), 2);
+
+        cache.configure(60_000);
+        handle_invoke(&router, "", r#"{"operation":"list"}"#).expect("list");
+        handle_invoke(&router, "", r#"{"operation":"list"}"#).expect("list");
+        assert_eq!(router.inner.listings.get(), 3);
+
+        // A failed call drops the catalog; a failed listing is not cached.
+        router
+            .call_tool("demo", &json!({}))
+            .expect_err("call fails");
+        router.inner.failing.set(true);
+        router.list_tools().expect_err("listing fails");
+        router.inner.failing.set(false);
+        router.list_tools().expect("list");
+        router.list_tools().expect("list");
+        assert_eq!(router.inner.listings.get(), 5);
+
+        cache.configure(1);
+        router.list_tools().expect("list");
+        std::thread::sleep(Duration::from_millis(5));
+        router.list_tools().expect("list");
+        assert_eq!(router.inner.listings.get(), 7);
+    }
+
+    #[test]
+    fn assistant_only_content_is_split_or_dropped() {
+        let text = |text: &str, audience: Option<Vec<router::Role>>| {
+            router::ContentBlock::Text(router::TextContent {
+                text: text.into(),
+                annotations: Some(router::Annotations {
+                    audience,
+                    priority: None,
+                    timestamp: None,
+                }),
+            })
+        };
+        let result = router::ToolResult {
+            content: vec![
+                text("for everyone", None),
+                text("for the model", Some(vec![router::Role::Assistant])),
+                text(
+                    "for both",
+                    Some(vec![router::Role::User, router::Role::Assistant]),
+                ),
+            ],
+            structured_content: None,
+            progress: None,
+            meta: None,
+            is_error: None,
+        };
+        let texts = |cards: &Value| -> Vec<String> {
+            cards
+                .as_array()
+                .expect("cards")
+                .iter()
+                .map(|card| card["text"].as_str().expect("text").to_string())
+                .collect()
+        };
+
+        let all = render_tool_result(&result, Audience::All);
+        assert_eq!(texts(&all["messages"]).len(), 3);
+        assert!(all.get("assistant_content").is_none());
+
+        let split = render_tool_result(&result, Audience::Split);
+        assert_eq!(texts(&split["messages"]), ["for everyone", "for both"]);
+        assert_eq!(texts(&split["assistant_content"]), ["for the model"]);
+        assert_eq!(texts(&split["result"]["content"]).len(), 3);
+
+        let user = render_tool_result(&result, Audience::User);
+        assert_eq!(texts(&user["messages"]), ["for everyone", "for both"]);
+        assert_eq!(
+            texts(&user["result"]["content"]),
+            ["for everyone", "for both"]
+        );
+    }
+
+    #[test]
+    fn redaction_masks_matching_keys_at_any_depth() {
+        let patterns = vec!["*token*".to_string(), "*password*".to_string()];
+        let mut payload = json!({
+            "content": [{ "type": "text", "text": "done", "meta": { "AccessToken": "t1" } }],
+            "structured_content": {
+                "user": "ada",
+                "db": { "password": "hunter2", "hosts": [{ "refresh_token": "r1" }] }
+            },
+            "meta": { "token_count": 12, "trace": "x" }
+        });
+        assert_eq!(redact_fields(&mut payload, &patterns), 4);
+        assert_eq!(
+            payload,
+            json!({
+                "content": [{ "type": "text", "text": "done", "meta": { "AccessToken": REDACTED } }],
+                "structured_content": {
+                    "user": "ada",
+                    "db": { "password": REDACTED, "hosts": [{ "refresh_token": REDACTED }] }
+                },
+                "meta": { "token_count": REDACTED, "trace": "x" }
+            })
+        );
+        assert_eq!(redact_fields(&mut json!({"a": 1}), &[]), 0);
+    }
+
+    #[test]
+    fn coercion_follows_the_input_schema() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "count": { "type": "integer" },
+                "ratio": { "type": "number" },
+                "dry_run": { "type": "boolean" },
+                "label": { "type": "string" },
+                "id": { "type": ["integer", "string"] },
+                "filter": {
+                    "type": "object",
+                    "properties": { "limit": { "type": "integer" } }
+                },
+                "tags": { "type": "array", "items": { "type": "integer" } },
+                "page": { "type": ["integer", "null"] }
+            }
+        })
+        .to_string();
+        let coerced = coerce_arguments(
+            &schema,
+            json!({
+                "count": "3",
+                "ratio": "0.5",
+                "dry_run": "true",
+                "label": "42",
+                "id": "7",
+                "filter": r#"{"limit": "10"}"#,
+                "tags": ["1", "2"],
+                "page": "null",
+                "extra": "5"
+            }),
+        );
+        assert_eq!(
+            coerced,
+            json!({
+                "count": 3,
+                "ratio": 0.5,
+                "dry_run": true,
+                "label": "42",
+                "id": "7",
+                "filter": { "limit": 10 },
+                "tags": [1, 2],
+                "page": null,
+                "extra": "5"
+            })
+        );
+
+        let untouched = json!({ "count": "three", "dry_run": "yes", "ratio": 2 });
+        assert_eq!(coerce_arguments(&schema, untouched.clone()), untouched);
+        assert_eq!(
+            coerce_arguments("not json", json!({"count": "3"})),
+            json!({"count": "3"})
+        );
+    }
+
+    #[test]
+    fn transient_call_failures_are_retried_with_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_backoff_ms: 1,
+        };
+        let args = json!({});
+        let flaky = |failures, error| FlakyRouter {
+            failures: RefCell::new(failures),
+            error,
+        };
+        let transport: fn() -> CallFailure = || CallFailure::Transport("reset".into());
+        let execution: fn() -> CallFailure =
+            || CallFailure::Tool(router::ToolError::ExecutionError("busy".into()));
+        let invalid: fn() -> CallFailure =
+            || CallFailure::Tool(router::ToolError::InvalidParameters("bad".into()));
+
+        let (response, attempts) =
+            call_with_retries(&flaky(2, transport), "demo", &args, policy, None, &|| false);
+        assert!(response.is_ok());
+        assert_eq!(attempts, 3);
+
+        let (response, attempts) =
+            call_with_retries(&flaky(5, execution), "demo", &args, policy, None, &|| false);
+        assert_eq!(attempts, 3);
+        let err = call_failure(response.expect_err("exhausted"), "demo", policy, attempts);
+        let node = err.node_error();
+        assert!(node.retryable);
+        assert_eq!(node.backoff_ms, Some(4));
+
+        let (response, attempts) =
+            call_with_retries(&flaky(1, invalid), "demo", &args, policy, None, &|| false);
+        assert_eq!(attempts, 1, "invalid parameters are not retried");
+        let err = call_failure(response.expect_err("invalid"), "demo", policy, attempts);
+        assert_eq!(err.node_error().backoff_ms, None);
+        assert_eq!(err.error.category, ErrorCategory::Schema);
+
+        let slow = RetryPolicy {
+            max_attempts: 3,
+            base_backoff_ms: 50,
+        };
+        let budget = Some(Duration::from_millis(20));
+        let (_, attempts) =
+            call_with_retries(&flaky(5, transport), "demo", &args, slow, budget, &|| false);
+        assert_eq!(attempts, 1, "no retry that would overrun the budget");
+    }
+
+    #[test]
+    fn error_envelopes_carry_jsonrpc_codes_and_categories() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_backoff_ms: 10,
+        };
+        let exhausted = call_failure(CallFailure::Transport("reset".into()), "demo", policy, 3);
+        let encoded = serde_json::to_value(&exhausted).expect("serialize");
+        assert_eq!(encoded["error"]["jsonrpc_code"], json!(-32000));
+        assert_eq!(encoded["error"]["category"], json!("transport"));
+        assert_eq!(encoded["error"]["retry_after_ms"], json!(40));
+        assert_eq!(exhausted.node_error().backoff_ms, Some(40));
+
+        let cases = [
+            (
+                config_error("bad".into(), None, Value::Null),
+                -32600,
+                "config",
+            ),
+            (tool_error(422, "schema".into(), "demo"), -32602, "schema"),
+            (tool_error(404, "missing".into(), "demo"), -32602, "tool"),
+            (tool_error(500, "boom".into(), "demo"), -32603, "tool"),
+            (forbidden_error("demo"), -32003, "tool"),
+            (
+                map_read_error(
+                    ReadFailure::Resource(router::ResourceError::NotFound("gone".into())),
+                    "file:///x",
+                ),
+                -32002,
+                "tool",
+            ),
+            (
+                timeout_error("demo", Duration::from_millis(5), Duration::from_millis(6)),
+                -32001,
+                "transport",
+            ),
+        ];
+        for (envelope, code, category) in cases {
+            let encoded =
+                serde_json::to_value(envelope.redact(ErrorDetail::Terse)).expect("serialize");
+            assert_eq!(encoded["error"]["jsonrpc_code"], json!(code));
+            assert_eq!(encoded["error"]["category"], json!(category));
+            assert!(encoded["error"].get("retry_after_ms").is_none());
+        }
+    }
+
+    #[test]
+    fn metrics_count_calls_errors_latency_and_bytes() {
+        let metrics = Metrics::new();
+        metrics.record(Duration::from_millis(3), None);
+        metrics.record(Duration::from_millis(40), Some(ErrorCategory::Schema));
+        metrics.record(Duration::from_secs(9), Some(ErrorCategory::Transport));
+        metrics.record_rendered(120);
+
+        let rendered = metrics.render();
+        let result = &rendered["result"];
+        assert_eq!(result["calls"], json!(3));
+        assert_eq!(
+            result["errors"],
+            json!({"config": 0, "tool": 0, "transport": 1, "schema": 1})
+        );
+        let buckets = result["latency_ms"]["buckets"].as_array().expect("buckets");
+        assert_eq!(buckets.len(), LATENCY_BUCKETS_MS.len() + 1);
+        assert_eq!(buckets[0], json!({"le_ms": 5, "count": 1}));
+        assert_eq!(buckets[3], json!({"le_ms": 50, "count": 2}));
+        assert_eq!(
+            buckets[LATENCY_BUCKETS_MS.len()],
+            json!({"le_ms": null, "count": 3})
+        );
+        assert_eq!(result["latency_ms"]["sum"], json!(9043));
+        assert_eq!(result["bytes_rendered"], json!(120));
+
+        let scraped = handle_invoke(
+            &MockRouter {
+                tools: vec![],
+                response: None,
+            },
+            "metrics",
+            "{}",
+        )
+        .expect("metrics");
+        assert!(scraped["result"]["calls"].is_u64());
+    }
+
+    #[test]
+    fn debug_echo_reports_the_parsed_request_without_the_router() {
+        struct UntouchedRouter;
+
+        impl McpRouter for UntouchedRouter {
+            fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+                panic!("debug.echo must not list tools")
+            }
+
+            fn call_tool(
+                &self,
+                _tool: &str,
+                _arguments: &Value,
+            ) -> Result<router::Response, CallFailure> {
+                panic!("debug.echo must not call tools")
+            }
+        }
+
+        let echoed = handle_invoke(
+            &UntouchedRouter,
+            "debug.echo",
+            r#"{"tool":"demo","arguments":{"q":"x"},"render":"markdown"}"#,
+        )
+        .expect("echo");
+        let result = &echoed["result"];
+        assert_eq!(result["op"], json!("debug.echo"));
+        assert_eq!(result["operation"], json!("debug.echo"));
+        assert_eq!(
+            result["request"],
+            json!({
+                "tool": "demo",
+                "prompt": null,
+                "arguments": { "q": "x" },
+                "timeout_ms": null,
+                "render": "markdown",
+                "correlation_id": null,
+            })
+        );
+        assert_eq!(result["config"]["error_detail"], json!("full"));
+        assert_eq!(result["config"]["retry"]["max_attempts"], json!(1));
+        assert_eq!(result["protocol"], json!(PROTOCOL));
+
+        let echoed =
+            handle_invoke(&UntouchedRouter, "call", r#"{"operation":"debug.echo"}"#).expect("echo");
+        assert_eq!(echoed["result"]["op"], json!("call"));
+        assert_eq!(echoed["result"]["operation"], json!("debug.echo"));
+    }
+
+    #[test]
+    fn calls_past_their_budget_time_out() {
+        let router = SlowRouter {
+            delay: Duration::from_millis(30),
+            calls: RefCell::new(0),
+        };
+
+        let err = handle_invoke(&router, "", r#"{"tool":"demo","timeout_ms":5}"#)
+            .expect_err("late answer");
+        assert_eq!(err.error.code, "MCP_TIMEOUT");
+        assert_eq!(err.error.status, 504);
+        assert_eq!(err.error.details["timeout_ms"], json!(5));
+        // The router ran the call; retrying could repeat it.
+        assert!(err.error.late);
+        assert!(!err.node_error().retryable);
+        assert_eq!(
+            serde_json::to_value(&*err).expect("envelope")["error"]["late"],
+            json!(true)
+        );
 
-    struct MockRouter {
-        tools: Vec<router::Tool>,
-        response: Option<router::Response>,
-    }
+        handle_invoke(&router, "", r#"{"tool":"demo","timeout_ms":5000}"#).expect("in time");
 
-    impl McpRouter for MockRouter {
-        fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
-            Ok(self.tools.clone())
-        }
+        let expired = handle_invoke_within(&router, "", r#"{"tool":"demo"}"#, Some(1), &|| false)
+            .expect_err("deadline already passed");
+        assert_eq!(expired.error.code, "MCP_TIMEOUT");
+        assert!(!expired.error.late && expired.node_error().retryable);
+        assert_eq!(
+            *router.calls.borrow(),
+            2,
+            "expired calls never reach the router"
+        );
 
-        fn call_tool(
-            &self,
-            _tool: &str,
-            _arguments: &Value,
-        ) -> Result<router::Response, CallFailure> {
-            self.response
-                .clone()
-                .ok_or_else(|| CallFailure::Transport("no response".into()))
-        }
+        let err = handle_invoke(&router, "", r#"{"tool":"demo","timeout_ms":0}"#)
+            .expect_err("zero timeout");
+        assert_eq!(err.error.code, "MCP_CONFIG_ERROR");
     }
 
-    fn sample_tool() -> router::Tool {
-        router::Tool {
-            name: "demo".into(),
-            title: Some("Demo".into()),
-            description: "Example".into(),
-            input_schema: r#"{\"type\":\"object\"}"#.into(),
-            output_schema: Some(
-                r#"{"type":"object","properties":{"result":{"type":"string"}}}"#.into(),
-            ),
-            annotations: None,
-            meta: None,
-        }
+    #[test]
+    fn cancelled_calls_stop_retrying_and_are_not_retryable() {
+        let router = FlakyRouter {
+            failures: RefCell::new(5),
+            error: || CallFailure::Transport("reset".into()),
+        };
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_backoff_ms: 10_000,
+        };
+        let checks = Cell::new(0);
+        // Cancelled once the first attempt has failed and the backoff began.
+        let cancelled = || {
+            checks.set(checks.get() + 1);
+            checks.get() > 1
+        };
+        let started = Instant::now();
+        let (response, attempts) =
+            call_with_retries(&router, "demo", &json!({}), policy, None, &cancelled);
+        assert!(response.is_err());
+        assert_eq!(attempts, 1);
+        assert!(
+            started.elapsed() < Duration::from_secs(5),
+            "backoff cut short"
+        );
+
+        let err = handle_invoke_within(&router, "", r#"{"tool":"demo"}"#, None, &|| true)
+            .expect_err("cancelled");
+        assert_eq!(err.error.code, "MCP_CANCELLED");
+        assert_eq!(err.error.jsonrpc_code, -32800);
+        assert!(!err.node_error().retryable);
+        assert_eq!(
+            *router.failures.borrow(),
+            4,
+            "cancelled calls never reach the router"
+        );
+        assert!(!err.error.late);
+
+        // Cancelled while the router was answering: the call may have landed.
+        let router = FlakyRouter {
+            failures: RefCell::new(1),
+            error: || CallFailure::Transport("reset".into()),
+        };
+        let answered = || *router.failures.borrow() == 0;
+        let err = handle_invoke_within(&router, "", r#"{"tool":"demo"}"#, None, &answered)
+            .expect_err("cancelled after the call");
+        assert_eq!(err.error.code, "MCP_CANCELLED");
+        assert!(err.error.late);
     }
 
     #[test]
-    fn list_operation_defaults_without_tool() {
+    fn read_only_results_carry_cache_hints() {
+        let completed = |meta: Option<Vec<router::MetaEntry>>| {
+            Some(router::Response::Completed(router::ToolResult {
+                content: Vec::new(),
+                structured_content: None,
+                progress: None,
+                meta,
+                is_error: None,
+            }))
+        };
+        let mut tool = sample_tool();
+        tool.annotations = Some(router::ToolAnnotations {
+            read_only: Some(true),
+            destructive: None,
+            streaming: None,
+            experimental: None,
+        });
+        tool.meta = Some(vec![router::MetaEntry {
+            key: CACHE_TTL_META.into(),
+            value: "120".into(),
+        }]);
+        let call = r#"{"tool":"demo"}"#;
+
         let router = MockRouter {
-            tools: vec![sample_tool()],
-            response: None,
+            tools: vec![tool.clone()],
+            response: completed(None),
         };
+        let result = handle_invoke(&router, "", call).expect("call");
+        assert_eq!(
+            result["cache"],
+            json!({ "cacheable": true, "ttl_seconds": 120 })
+        );
 
-        let result =
-            handle_invoke(&router, "", r#"{"arguments": {}}"#).expect("list should succeed");
+        let router = MockRouter {
+            tools: vec![tool.clone()],
+            response: completed(Some(vec![router::MetaEntry {
+                key: CACHE_TTL_META.into(),
+                value: "5".into(),
+            }])),
+        };
+        let result = handle_invoke(&router, "", call).expect("call");
+        assert_eq!(result["cache"]["ttl_seconds"], json!(5));
 
-        assert_eq!(result.get("ok"), Some(&Value::Bool(true)));
-        let tools = result
-            .get("result")
-            .and_then(|r| r.get("tools"))
-            .and_then(Value::as_array)
-            .cloned()
-            .unwrap_or_default();
-        assert_eq!(tools.len(), 1);
+        let router = MockRouter {
+            tools: vec![sample_tool()],
+            response: completed(None),
+        };
+        let result = handle_invoke(&router, "", call).expect("call");
+        assert!(result.get("cache").is_none());
     }
 
+    #[cfg(feature = "media")]
     #[test]
-    fn call_operation_routes_arguments() {
+    fn oversized_blocks_are_truncated_or_chunked() {
         let router = MockRouter {
-            tools: vec![],
+            tools: vec![sample_tool()],
             response: Some(router::Response::Completed(router::ToolResult {
-                content: vec![router::ContentBlock::Text(router::TextContent {
-                    text: "hi".into(),
-                    annotations: None,
-                })],
+                content: vec![
+                    router::ContentBlock::Text(router::TextContent {
+                        text: "héllo wörld".into(),
+                        annotations: None,
+                    }),
+                    router::ContentBlock::Audio(router::AudioContent {
+                        data: "QUJDREVGR0hJSktM".into(),
+                        mime_type: "audio/wav".into(),
+                        annotations: None,
+                    }),
+                    router::ContentBlock::Text(router::TextContent {
+                        text: "ok".into(),
+                        annotations: None,
+                    }),
+                ],
                 structured_content: None,
                 progress: None,
                 meta: None,
                 is_error: None,
             })),
         };
+        let envelope = handle_invoke(&router, "", r#"{"tool":"demo"}"#).expect("call");
 
-        let result = handle_invoke(
-            &router,
-            "",
-            r#"{"operation":"call","tool":"demo","arguments":{"foo":"bar"}}"#,
-        )
-        .expect("call should succeed");
-
-        assert_eq!(result.get("ok"), Some(&Value::Bool(true)));
-        let messages = result
-            .get("messages")
-            .and_then(Value::as_array)
-            .cloned()
-            .unwrap_or_default();
-        assert_eq!(messages.len(), 1);
-    }
-
-    #[test]
-    fn call_operation_preserves_typed_arguments() {
-        struct AssertArgsRouter {
-            expected: Value,
-        }
-
-        impl McpRouter for AssertArgsRouter {
-            fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
-                Ok(vec![])
-            }
-
-            fn call_tool(
-                &self,
-                _tool: &str,
-                arguments: &Value,
-            ) -> Result<router::Response, CallFailure> {
-                if arguments != &self.expected {
-                    return Err(CallFailure::Transport(format!(
-                        "unexpected arguments: {arguments}"
-                    )));
-                }
+        let mut truncated = envelope.clone();
+        truncate_oversized(&mut truncated, 6, None);
+        let content = &truncated["result"]["content"];
+        assert_eq!(content[0]["text"], json!("héllo"));
+        assert_eq!(content[0]["truncated"], json!(true));
+        assert_eq!(content[0]["original_bytes"], json!(13));
+        assert_eq!(
+            content[0]["full_content"]["uri"],
+            json!("urn:greentic:mcp-adapter:invoke-stream:content:0")
+        );
+        assert_eq!(content[1]["data"], json!("QUJD"));
+        assert!(content[2].get("truncated").is_none());
+        assert_eq!(truncated["messages"][1]["truncated"], json!(true));
 
-                Ok(router::Response::Completed(router::ToolResult {
-                    content: vec![],
-                    structured_content: None,
-                    progress: None,
-                    meta: None,
-                    is_error: None,
-                }))
-            }
-        }
+        let parts = chunk_oversized(envelope, 6);
+        let head = &parts[0]["result"]["content"];
+        assert_eq!(head[0]["text"], json!(""));
+        assert_eq!(head[0]["chunked"], json!({ "chunks": 3, "bytes": 13 }));
+        assert_eq!(head[1]["chunked"], json!({ "chunks": 4, "bytes": 16 }));
+        assert_eq!(parts.len(), 1 + 3 + 4);
 
-        let router = AssertArgsRouter {
-            expected: json!({
-                "count": 3,
-                "active": true,
-                "items": ["a", "b"],
-                "meta": {"score": 9.5},
-            }),
+        let reassembled = |block: usize| {
+            parts[1..]
+                .iter()
+                .filter(|part| part["chunk"]["block"] == json!(block))
+                .map(|part| part["chunk"]["data"].as_str().unwrap().to_string())
+                .collect::<String>()
         };
-
-        let result = handle_invoke(
-            &router,
-            "",
-            r#"{"operation":"call","tool":"demo","arguments":{"count":3,"active":true,"items":["a","b"],"meta":{"score":9.5}}}"#,
-        )
-        .expect("call should succeed");
-
-        assert_eq!(result.get("ok"), Some(&Value::Bool(true)));
+        assert_eq!(reassembled(0), "héllo wörld");
+        assert_eq!(reassembled(1), "QUJDREVGR0hJSktM");
+        assert_eq!(parts[3]["chunk"]["last"], json!(true));
     }
 
+    #[cfg(feature = "media")]
     #[test]
-    fn tool_error_maps_to_envelope() {
-        let _router = MockRouter {
-            tools: vec![],
+    fn result_budget_swaps_the_largest_blocks_for_placeholders() {
+        let router = MockRouter {
+            tools: vec![sample_tool()],
             response: Some(router::Response::Completed(router::ToolResult {
-                content: vec![],
+                content: vec![
+                    router::ContentBlock::Text(router::TextContent {
+                        text: "ok".into(),
+                        annotations: None,
+                    }),
+                    router::ContentBlock::Image(router::ImageContent {
+                        data: "A".repeat(2000),
+                        mime_type: "image/png".into(),
+                        annotations: None,
+                    }),
+                    router::ContentBlock::Text(router::TextContent {
+                        text: "t".repeat(500),
+                        annotations: None,
+                    }),
+                ],
                 structured_content: None,
                 progress: None,
                 meta: None,
-                is_error: Some(true),
+                is_error: None,
             })),
         };
+        let envelope = handle_invoke(&router, "", r#"{"tool":"demo"}"#).expect("call");
+        let original = render_body(&envelope).len();
 
-        let err = map_call_error(
-            CallFailure::Tool(router::ToolError::InvalidParameters("bad".into())),
-            "demo",
+        let mut untouched = envelope.clone();
+        fit_result_budget(&mut untouched, original);
+        assert_eq!(untouched, envelope);
+
+        // Only dropping the image from both content and its message card fits.
+        let limit = original - 3000;
+        let mut fitted = envelope;
+        fit_result_budget(&mut fitted, limit);
+        assert!(render_body(&fitted).len() <= limit);
+        let content = &fitted["result"]["content"];
+        assert_eq!(content[0]["text"], json!("ok"));
+        assert_eq!(content[1]["type"], json!("resource_link"));
+        assert_eq!(content[1]["original_type"], json!("image"));
+        assert_eq!(content[1]["mime_type"], json!("image/png"));
+        assert_eq!(
+            content[1]["uri"],
+            json!("urn:greentic:mcp-adapter:omitted:result:content:1")
         );
-        assert_eq!(err.error.code, "MCP_TOOL_ERROR");
-        assert_eq!(err.error.status, 400);
+        assert!(content[1]["original_bytes"].as_u64().unwrap() > 2000);
+        assert_eq!(content[2]["text"].as_str().map(str::len), Some(500));
+        assert_eq!(fitted["messages"][1]["original_type"], json!("image"));
+        assert_eq!(fitted["result"]["meta"]["truncated"], json!(true));
+        assert_eq!(fitted["result"]["meta"]["original_bytes"], json!(original));
     }
 
     #[test]
-    fn structured_content_and_resource_link_round_trip() {
+    fn progress_notifications_stream_ahead_of_content() {
+        let notification = |progress: f32, message: &str| router::ProgressNotification {
+            progress: Some(progress),
+            message: Some(message.into()),
+            annotations: None,
+        };
         let router = MockRouter {
-            tools: vec![],
+            tools: vec![sample_tool()],
             response: Some(router::Response::Completed(router::ToolResult {
-                content: vec![router::ContentBlock::ResourceLink(
-                    router::ResourceLinkContent {
-                        uri: "https://example.com/doc".into(),
-                        title: Some("Doc".into()),
-                        description: Some("desc".into()),
-                        mime_type: Some("text/html".into()),
-                        annotations: None,
-                    },
-                )],
-                structured_content: Some(r#"{"result":42}"#.into()),
-                progress: None,
-                meta: Some(vec![router::MetaEntry {
-                    key: "source".into(),
-                    value: r#""demo-router""#.into(),
-                }]),
+                content: vec![router::ContentBlock::Text(router::TextContent {
+                    text: "héllo wörld".into(),
+                    annotations: None,
+                })],
+                structured_content: None,
+                progress: Some(vec![
+                    notification(0.25, "fetching"),
+                    notification(1.0, "done"),
+                ]),
+                meta: None,
                 is_error: None,
             })),
         };
+        let envelope = handle_invoke(&router, "", r#"{"tool":"demo"}"#).expect("call");
 
-        let result = handle_invoke(
-            &router,
-            "",
-            r#"{"operation":"call","tool":"demo","arguments":{"foo":"bar"}}"#,
-        )
-        .expect("call should succeed");
+        let parts = stream_parts(envelope.clone(), None);
+        assert_eq!(parts.len(), 3);
+        assert_eq!(
+            parts[0],
+            json!({
+                "type": "progress",
+                "seq": 0,
+                "progress": 0.25,
+                "message": "fetching",
+                "annotations": null,
+            })
+        );
+        assert_eq!(parts[1]["seq"], json!(1));
+        assert_eq!(parts[1]["message"], json!("done"));
+        assert_eq!(parts[2], envelope);
 
-        assert_eq!(result.get("ok"), Some(&Value::Bool(true)));
-        let structured = result
-            .pointer("/result/structured_content/result")
-            .cloned()
-            .unwrap();
-        assert_eq!(structured, json!(42));
+        let chunked = stream_parts(envelope, Some(6));
+        assert_eq!(chunked[1]["type"], json!("progress"));
+        assert_eq!(
+            chunked[2]["result"]["content"][0]["chunked"]["chunks"],
+            json!(3)
+        );
+        assert_eq!(chunked.len(), 2 + 1 + 3);
+    }
 
-        let content = result
-            .get("result")
-            .and_then(|r| r.get("content"))
-            .and_then(Value::as_array)
-            .cloned()
-            .unwrap_or_default();
-        assert_eq!(content.len(), 1);
+    thread_local! {
+        static STASHED: RefCell<Vec<(String, String, String)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn record_put(ns: &str, key: &str, val: &str) {
+        STASHED.with(|stashed| {
+            stashed
+                .borrow_mut()
+                .push((ns.into(), key.into(), val.into()))
+        });
+    }
+
+    #[test]
+    fn oversized_content_is_handed_off_to_host_kv() {
+        let mut envelope = json!({
+            "result": { "content": [{ "type": "text", "text": "a long payload" }] },
+            "messages": [{ "type": "text", "text": "a long payload" }],
+        });
+        let handoff = KvHandoff {
+            namespace: "flows".into(),
+            put: record_put,
+        };
+        truncate_oversized(&mut envelope, 6, Some(&handoff));
+
+        let block = &envelope["result"]["content"][0];
+        assert_eq!(block["text"], json!("a long"));
+        assert_eq!(block["truncated"], json!(true));
+        let handle = &block["full_content"];
+        assert_eq!(handle["type"], json!("kv_handle"));
+        assert_eq!(handle["ns"], json!("flows"));
+        assert_eq!(handle["bytes"], json!(14));
+        // Message cards are previews and are not handed off.
+        assert!(envelope["messages"][0].get("full_content").is_none());
+
+        let stashed = STASHED.with(|stashed| stashed.take());
         assert_eq!(
-            content[0].get("type"),
-            Some(&Value::String("resource_link".into()))
+            stashed,
+            [(
+                "flows".to_string(),
+                handle["key"].as_str().expect("key").to_string(),
+                "a long payload".to_string(),
+            )]
         );
+    }
 
-        let messages = result
-            .get("messages")
-            .and_then(Value::as_array)
-            .cloned()
-            .unwrap_or_default();
+    #[test]
+    fn describe_reports_server_capabilities() {
+        struct DescribedRouter;
+
+        impl McpRouter for DescribedRouter {
+            fn describe_server(&self) -> Result<router::ServerDescription, RouterError> {
+                Ok(router::ServerDescription {
+                    name: "weather".into(),
+                    title: Some("Weather".into()),
+                    capabilities: router::ServerCapabilities {
+                        prompts: None,
+                        resources: Some(router::ResourcesCapability {
+                            subscribe: Some(false),
+                            list_changed: Some(true),
+                        }),
+                        tools: Some(router::ToolsCapability {
+                            list_changed: Some(true),
+                        }),
+                        completions: None,
+                    },
+                    resources: None,
+                    resource_metadata: Some(vec![router::ResourceMetadata {
+                        authorization_servers: vec![router::AuthorizationServer {
+                            issuer: "https://auth.example.com".into(),
+                            token_endpoint: None,
+                        }],
+                        resource_indicator: "https://weather.example.com".into(),
+                        default_scopes: vec!["forecast:read".into()],
+                    }]),
+                    meta: None,
+                })
+            }
+
+            fn instructions(&self) -> Result<String, RouterError> {
+                Ok("Ask for a city.".into())
+            }
+
+            fn list_tools(&self) -> Result<Vec<router::Tool>, RouterError> {
+                Ok(vec![])
+            }
+
+            fn call_tool(
+                &self,
+                _tool: &str,
+                _arguments: &Value,
+            ) -> Result<router::Response, CallFailure> {
+                Err(CallFailure::Transport("no tools".into()))
+            }
+        }
+
+        let described = handle_invoke(&DescribedRouter, "describe", "{}").expect("describe");
+        let result = &described["result"];
+        assert_eq!(result["name"], json!("weather"));
+        assert_eq!(result["instructions"], json!("Ask for a city."));
+        assert_eq!(result["capabilities"]["tools"]["list_changed"], json!(true));
+        assert_eq!(result["capabilities"]["prompts"], Value::Null);
         assert_eq!(
-            messages.first().and_then(|m| m.get("type")),
-            Some(&Value::String("resource_link".into()))
+            result["resource_metadata"][0]["default_scopes"],
+            json!(["forecast:read"])
         );
+
+        let router = MockRouter {
+            tools: vec![],
+            response: None,
+        };
+        let err = handle_invoke(&router, "describe", "{}").expect_err("undescribed router");
+        assert_eq!(err.error.code, "MCP_ROUTER_ERROR");
     }
 
     mod router_bindings {
@@ -0,0 +1,548 @@
+//! JSON Schemas for the adapter's own request and response envelopes,
+//! published through `get-manifest` so flow builders can validate node
+//! configurations without invoking the component.
+
+use serde_json::{Map, Value, json};
+
+use crate::PROTOCOL;
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Operations accepted in the request `operation` field (or the node `op`).
+pub(crate) const OPERATIONS: &[&str] = &[
+    "list",
+    "list.check_changed",
+    "call",
+    "resources.list",
+    "resources.read",
+    "prompts.list",
+    "prompts.get",
+    "describe",
+    "metrics",
+    "debug.echo",
+];
+
+/// Schema for the JSON payload passed to `invoke`.
+pub(crate) fn request_schema() -> Value {
+    json!({
+        "$schema": DRAFT,
+        "title": "MCP adapter request",
+        "type": "object",
+        "properties": {
+            "operation": {
+                "type": "string",
+                "enum": OPERATIONS,
+                "description": "Defaults to `call` when `tool` is set, otherwise `list`."
+            },
+            "tool": {
+                "type": "string",
+                "description": "Tool to invoke; required for `call`."
+            },
+            "prompt": {
+                "type": "string",
+                "description": "Prompt to fetch; required for `prompts.get`."
+            },
+            "arguments": {
+                "type": ["object", "null"],
+                "default": {},
+                "description": "Tool arguments for `call`; `cursor`/`limit` for `list`; `hash`/`known` for `list.check_changed`; `uri` for `resources.read`."
+            },
+            "timeout_ms": {
+                "type": "integer",
+                "minimum": 1,
+                "description": "Budget for the router call in `call`; the host deadline applies too."
+            },
+            "render": {
+                "type": "string",
+                "enum": ["cards", "markdown"],
+                "default": "cards",
+                "description": "`markdown` joins `messages` into a single markdown text card."
+            },
+            "correlation_id": {
+                "type": "string",
+                "description": "Echoed in the response envelope and stream events, and passed to the router as `arguments._meta.correlation_id` in `call`."
+            }
+        },
+        "allOf": [
+            {
+                "if": {
+                    "properties": { "operation": { "const": "call" } },
+                    "required": ["operation"]
+                },
+                "then": { "required": ["tool"] }
+            },
+            {
+                "if": {
+                    "properties": { "operation": { "const": "prompts.get" } },
+                    "required": ["operation"]
+                },
+                "then": { "required": ["prompt"] }
+            },
+            {
+                "if": {
+                    "properties": { "operation": { "const": "resources.read" } },
+                    "required": ["operation"]
+                },
+                "then": {
+                    "required": ["arguments"],
+                    "properties": {
+                        "arguments": { "type": "object", "required": ["uri"] }
+                    }
+                }
+            }
+        ]
+    })
+}
+
+/// Schema for the JSON returned by `invoke`, success or error.
+///
+/// Each definition is built by its own function: as one `json!` literal the
+/// schema is deep enough to hit the macro recursion limit. The responses are
+/// alternatives under `anyOf`, not `oneOf`: they carry no discriminator, and a
+/// `list.check_changed` result that includes `tools` also matches `list`.
+pub(crate) fn response_schema() -> Value {
+    let defs: Map<String, Value> = [
+        ("list", list_schema()),
+        ("check_changed", check_changed_schema()),
+        ("call", call_schema()),
+        ("elicitation", elicitation_schema()),
+        ("resources", resources_schema()),
+        ("resource_read", resource_read_schema()),
+        ("prompts", prompts_schema()),
+        ("prompt", prompt_schema()),
+        ("describe", describe_schema()),
+        ("metrics", metrics_schema()),
+        ("debug_echo", debug_echo_schema()),
+        ("error", error_schema()),
+        ("tool", tool_schema()),
+    ]
+    .into_iter()
+    .map(|(name, schema)| (name.to_string(), schema))
+    .collect();
+    let responses: Vec<Value> = RESPONSES
+        .iter()
+        .map(|name| json!({ "$ref": format!("#/$defs/{name}") }))
+        .collect();
+    json!({
+        "$schema": DRAFT,
+        "title": "MCP adapter response",
+        "anyOf": responses,
+        "$defs": defs
+    })
+}
+
+/// Definitions in `$defs` that describe a whole response.
+const RESPONSES: &[&str] = &[
+    "list",
+    "check_changed",
+    "call",
+    "elicitation",
+    "resources",
+    "resource_read",
+    "prompts",
+    "prompt",
+    "describe",
+    "metrics",
+    "debug_echo",
+    "error",
+];
+
+fn list_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["ok", "result"],
+        "properties": {
+            "ok": { "const": true },
+            "result": {
+                "type": "object",
+                "required": ["tools", "protocol"],
+                "properties": {
+                    "tools": { "type": "array", "items": { "$ref": "#/$defs/tool" } },
+                    "next_cursor": { "type": "string" },
+                    "protocol": { "const": PROTOCOL }
+                }
+            }
+        }
+    })
+}
+
+fn check_changed_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["ok", "result"],
+        "properties": {
+            "ok": { "const": true },
+            "result": {
+                "type": "object",
+                "required": ["changed", "hash", "tool_hashes", "protocol"],
+                "properties": {
+                    "changed": { "type": "boolean" },
+                    "hash": { "type": "string" },
+                    "tool_hashes": {
+                        "type": "object",
+                        "additionalProperties": { "type": "string" }
+                    },
+                    "tools": { "type": "array", "items": { "$ref": "#/$defs/tool" } },
+                    "delta": {
+                        "type": "object",
+                        "properties": {
+                            "added": { "type": "array", "items": { "$ref": "#/$defs/tool" } },
+                            "removed": { "type": "array", "items": { "type": "string" } },
+                            "updated": { "type": "array", "items": { "$ref": "#/$defs/tool" } }
+                        }
+                    },
+                    "protocol": { "const": PROTOCOL }
+                }
+            }
+        }
+    })
+}
+
+fn call_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["ok", "result", "messages", "protocol"],
+        "properties": {
+            "ok": { "const": true },
+            "result": {
+                "type": "object",
+                "required": ["content"],
+                "properties": {
+                    "content": { "type": "array", "items": { "type": "object" } },
+                    "structured_content": {},
+                    "progress": { "type": ["array", "null"] },
+                    "meta": { "type": ["object", "null"] },
+                    "is_error": { "type": ["boolean", "null"] },
+                    "annotations": { "type": ["object", "null"] }
+                }
+            },
+            "messages": { "type": "array", "items": { "type": "object" } },
+            "assistant_content": { "type": "array", "items": { "type": "object" } },
+            "cache": {
+                "type": "object",
+                "required": ["cacheable"],
+                "properties": {
+                    "cacheable": { "type": "boolean" },
+                    "ttl_seconds": { "type": ["integer", "null"] }
+                }
+            },
+            "protocol": { "const": PROTOCOL }
+        }
+    })
+}
+
+fn elicitation_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["ok", "elicitation", "messages", "protocol"],
+        "properties": {
+            "ok": { "const": true },
+            "elicitation": {
+                "type": "object",
+                "required": ["message", "schema"],
+                "properties": {
+                    "title": { "type": ["string", "null"] },
+                    "message": { "type": "string" },
+                    "schema": {},
+                    "annotations": { "type": ["object", "null"] },
+                    "meta": { "type": ["object", "null"] }
+                }
+            },
+            "messages": { "type": "array", "items": { "type": "object" } },
+            "protocol": { "const": PROTOCOL }
+        }
+    })
+}
+
+fn resources_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["ok", "result"],
+        "properties": {
+            "ok": { "const": true },
+            "result": {
+                "type": "object",
+                "required": ["resources", "protocol"],
+                "properties": {
+                    "resources": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["uri", "name", "mime_type"],
+                            "properties": {
+                                "uri": { "type": "string" },
+                                "name": { "type": "string" },
+                                "title": { "type": ["string", "null"] },
+                                "description": { "type": ["string", "null"] },
+                                "mime_type": { "type": "string" },
+                                "annotations": { "type": ["object", "null"] }
+                            }
+                        }
+                    },
+                    "protocol": { "const": PROTOCOL }
+                }
+            }
+        }
+    })
+}
+
+fn resource_read_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["ok", "result", "messages", "protocol"],
+        "properties": {
+            "ok": { "const": true },
+            "result": {
+                "type": "object",
+                "required": ["uri", "content"],
+                "properties": {
+                    "uri": { "type": "string" },
+                    "content": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["type", "uri", "data", "encoding"],
+                            "properties": {
+                                "type": { "const": "resource" },
+                                "uri": { "type": "string" },
+                                "mime_type": { "type": ["string", "null"] },
+                                "data": { "type": "string" },
+                                "encoding": { "enum": ["text", "base64"] }
+                            }
+                        }
+                    }
+                }
+            },
+            "messages": { "type": "array", "items": { "type": "object" } },
+            "protocol": { "const": PROTOCOL }
+        }
+    })
+}
+
+fn prompts_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["ok", "result"],
+        "properties": {
+            "ok": { "const": true },
+            "result": {
+                "type": "object",
+                "required": ["prompts", "protocol"],
+                "properties": {
+                    "prompts": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["name"],
+                            "properties": {
+                                "name": { "type": "string" },
+                                "description": { "type": ["string", "null"] },
+                                "arguments": {
+                                    "type": ["array", "null"],
+                                    "items": {
+                                        "type": "object",
+                                        "required": ["name"],
+                                        "properties": {
+                                            "name": { "type": "string" },
+                                            "description": { "type": ["string", "null"] },
+                                            "required": { "type": ["boolean", "null"] }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "protocol": { "const": PROTOCOL }
+                }
+            }
+        }
+    })
+}
+
+fn prompt_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["ok", "result", "messages", "protocol"],
+        "properties": {
+            "ok": { "const": true },
+            "result": {
+                "type": "object",
+                "required": ["name", "content"],
+                "properties": {
+                    "name": { "type": "string" },
+                    "description": { "type": ["string", "null"] },
+                    "content": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["type", "role"],
+                            "properties": {
+                                "role": { "enum": ["user", "assistant"] }
+                            }
+                        }
+                    }
+                }
+            },
+            "messages": { "type": "array", "items": { "type": "object" } },
+            "protocol": { "const": PROTOCOL }
+        }
+    })
+}
+
+fn describe_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["ok", "result"],
+        "properties": {
+            "ok": { "const": true },
+            "result": {
+                "type": "object",
+                "required": ["name", "instructions", "capabilities", "protocol"],
+                "properties": {
+                    "name": { "type": "string" },
+                    "title": { "type": ["string", "null"] },
+                    "instructions": { "type": "string" },
+                    "capabilities": {
+                        "type": "object",
+                        "properties": {
+                            "prompts": { "type": ["object", "null"] },
+                            "resources": { "type": ["object", "null"] },
+                            "tools": { "type": ["object", "null"] },
+                            "completions": { "type": ["object", "null"] }
+                        }
+                    },
+                    "resources": { "type": ["array", "null"] },
+                    "resource_metadata": { "type": ["array", "null"] },
+                    "meta": { "type": ["object", "null"] },
+                    "protocol": { "const": PROTOCOL }
+                }
+            }
+        }
+    })
+}
+
+fn metrics_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["ok", "result"],
+        "properties": {
+            "ok": { "const": true },
+            "result": {
+                "type": "object",
+                "required": ["calls", "errors", "latency_ms", "bytes_rendered", "protocol"],
+                "properties": {
+                    "calls": { "type": "integer", "minimum": 0 },
+                    "errors": {
+                        "type": "object",
+                        "additionalProperties": { "type": "integer", "minimum": 0 }
+                    },
+                    "latency_ms": {
+                        "type": "object",
+                        "required": ["buckets", "sum"],
+                        "properties": {
+                            "buckets": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "required": ["le_ms", "count"],
+                                    "properties": {
+                                        "le_ms": { "type": ["integer", "null"] },
+                                        "count": { "type": "integer", "minimum": 0 }
+                                    }
+                                }
+                            },
+                            "sum": { "type": "integer", "minimum": 0 }
+                        }
+                    },
+                    "bytes_rendered": { "type": "integer", "minimum": 0 },
+                    "protocol": { "const": PROTOCOL }
+                }
+            }
+        }
+    })
+}
+
+fn debug_echo_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["ok", "result"],
+        "properties": {
+            "ok": { "const": true },
+            "result": {
+                "type": "object",
+                "required": ["op", "operation", "request", "config", "protocol"],
+                "properties": {
+                    "op": { "type": "string" },
+                    "operation": { "enum": OPERATIONS },
+                    "request": {
+                        "type": "object",
+                        "properties": {
+                            "tool": { "type": ["string", "null"] },
+                            "prompt": { "type": ["string", "null"] },
+                            "arguments": { "type": "object" },
+                            "timeout_ms": { "type": ["integer", "null"] },
+                            "render": { "enum": ["cards", "markdown"] },
+                            "correlation_id": { "type": ["string", "null"] }
+                        }
+                    },
+                    "config": { "type": "object" },
+                    "protocol": { "const": PROTOCOL }
+                }
+            }
+        }
+    })
+}
+
+fn error_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["ok", "error"],
+        "properties": {
+            "ok": { "const": false },
+            "error": {
+                "type": "object",
+                "required": ["code", "message", "status", "jsonrpc_code", "category", "protocol"],
+                "properties": {
+                    "code": {
+                        "enum": [
+                            "MCP_TOOL_ERROR",
+                            "MCP_RESOURCE_ERROR",
+                            "MCP_PROMPT_ERROR",
+                            "MCP_ROUTER_ERROR",
+                            "MCP_ROUTER_PANIC",
+                            "MCP_CONFIG_ERROR",
+                            "MCP_TIMEOUT",
+                            "MCP_CANCELLED",
+                            "MCP_TOOL_FORBIDDEN"
+                        ]
+                    },
+                    "message": { "type": "string" },
+                    "status": { "type": "integer" },
+                    "jsonrpc_code": { "type": "integer" },
+                    "category": { "enum": ["config", "tool", "transport", "schema"] },
+                    "tool": { "type": ["string", "null"] },
+                    "protocol": { "const": PROTOCOL },
+                    "details": {},
+                    "retry_after_ms": { "type": "integer", "minimum": 0 },
+                    "late": { "const": true }
+                }
+            }
+        }
+    })
+}
+
+fn tool_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["name", "description", "input_schema"],
+        "properties": {
+            "name": { "type": "string" },
+            "title": { "type": ["string", "null"] },
+            "description": { "type": "string" },
+            "input_schema": {},
+            "output_schema": {},
+            "annotations": { "type": ["object", "null"] },
+            "meta": { "type": ["object", "null"] }
+        }
+    })
+}
@@ -0,0 +1,177 @@
+//! Instance actors: one thread per warm instance, owning its store.
+//!
+//! A cold call instantiates and runs a component on the same worker thread,
+//! but warm instances are created ahead of time and served later, from
+//! whichever thread the call arrives on. Rather than moving the store between
+//! threads, [`InstanceActor`] instantiates the component on a dedicated thread
+//! and keeps the store there; callers only hold a channel to it. The store is
+//! created, called, and dropped on that one thread.
+
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+
+use serde_json::Value;
+use wasmtime::Engine;
+use wasmtime::component::InstancePre;
+
+use crate::ExecRequest;
+use crate::config::{DynSecretsStore, RuntimePolicy};
+use crate::error::RunnerError;
+use crate::resolve::ResolvedArtifact;
+use crate::runner::{self, ReadyInstance, StoreState};
+
+/// Requests an [`InstanceActor`] serves.
+enum Command {
+    /// Run `request`, dumping the call of `artifact` if the runtime asks for it.
+    Call {
+        request: ExecRequest,
+        artifact: ResolvedArtifact,
+        runtime: RuntimePolicy,
+        reply: Sender<Result<Value, RunnerError>>,
+    },
+}
+
+/// Handle to an instantiated component living on its own thread.
+///
+/// Dropping the handle stops the thread, which drops the store with it.
+pub(crate) struct InstanceActor {
+    commands: Sender<Command>,
+}
+
+impl InstanceActor {
+    /// Instantiate `pre` on a new thread with fresh host state, returning once
+    /// the instance is ready.
+    pub(crate) fn spawn(
+        engine: Engine,
+        pre: InstancePre<StoreState>,
+        runtime: RuntimePolicy,
+        http_enabled: bool,
+        secrets_store: Option<DynSecretsStore>,
+    ) -> Result<Self, RunnerError> {
+        let (commands, inbox) = mpsc::channel();
+        let (started, ready) = mpsc::channel();
+        thread::Builder::new()
+            .name("mcp-exec-instance".into())
+            .spawn(move || {
+                let instantiated =
+                    runner::instantiate(&engine, &pre, &runtime, http_enabled, secrets_store, None);
+                match instantiated {
+                    Ok(instance) => {
+                        let _ = started.send(Ok(()));
+                        serve(instance, inbox);
+                    }
+                    Err(err) => {
+                        let _ = started.send(Err(err));
+                    }
+                }
+            })
+            .map_err(|err| RunnerError::Internal(format!("spawning instance actor: {err}")))?;
+
+        ready
+            .recv()
+            .unwrap_or_else(|_| Err(RunnerError::Internal("instance actor exited".into())))?;
+        Ok(Self { commands })
+    }
+
+    /// Run `request` on the instance, consuming it, and wait at most
+    /// [`RuntimePolicy::per_call_timeout`] for the outcome.
+    pub(crate) fn call(
+        self,
+        request: ExecRequest,
+        artifact: ResolvedArtifact,
+        runtime: RuntimePolicy,
+    ) -> Result<Value, RunnerError> {
+        let timeout = runtime.per_call_timeout;
+        let (reply, outcome) = mpsc::channel();
+        self.commands
+            .send(Command::Call {
+                request,
+                artifact,
+                runtime,
+                reply,
+            })
+            .map_err(|_| RunnerError::Internal("instance actor exited".into()))?;
+
+        match outcome.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => Err(RunnerError::Timeout { elapsed: timeout }),
+            Err(RecvTimeoutError::Disconnected) => {
+                Err(RunnerError::Internal("instance actor failed".into()))
+            }
+        }
+    }
+}
+
+/// The actor's command loop; returns once every handle is gone.
+fn serve(instance: ReadyInstance, inbox: Receiver<Command>) {
+    // Each instance serves exactly one call so no guest state leaks between
+    // requests.
+    let mut instance = Some(instance);
+    for command in inbox {
+        match command {
+            Command::Call {
+                request,
+                artifact,
+                runtime,
+                reply,
+            } => {
+                let result = match instance.take() {
+                    Some(ready) => ready
+                        .with_debug_dump(&artifact, &runtime)
+                        .call(&request, &runtime),
+                    None => Err(RunnerError::Internal(
+                        "instance already served a call".into(),
+                    )),
+                };
+                let _ = reply.send(result);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stress::echo_component;
+    use serde_json::json;
+    use std::sync::Arc;
+    use wasmtime::component::Component;
+
+    #[test]
+    fn actors_run_their_call_on_their_own_thread() {
+        let runtime = RuntimePolicy::default();
+        let engine = runner::build_engine(&runtime).expect("engine");
+        let linker = runner::build_linker(&engine).expect("linker");
+        let bytes = echo_component(0);
+        let component = Component::new(&engine, &bytes).expect("component");
+        let pre = runner::prepare_instance(&engine, &linker, &component, &runtime).expect("pre");
+        let artifact = ResolvedArtifact {
+            info: crate::store::ToolInfo {
+                name: "echo".into(),
+                path: "echo.wasm".into(),
+                sha256: None,
+            },
+            bytes: Arc::from(bytes),
+            digest: "sha256:echo".into(),
+        };
+
+        // The handle moves to another thread; the store stays on the actor.
+        let actor =
+            InstanceActor::spawn(engine, pre, runtime.clone(), false, None).expect("instantiate");
+        let request = ExecRequest {
+            component: "echo".into(),
+            action: "echo".into(),
+            args: json!({ "n": 1 }),
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Default::default(),
+        };
+        let value = thread::spawn(move || actor.call(request, artifact, runtime))
+            .join()
+            .expect("caller thread")
+            .expect("call");
+        assert_eq!(value["version"], json!(0));
+        assert_eq!(value["args"], json!({ "n": 1 }));
+    }
+}
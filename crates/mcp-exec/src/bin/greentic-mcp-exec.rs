@@ -1,12 +1,19 @@
 use std::fs;
 use std::io::{self, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, anyhow};
 use clap::{Parser, Subcommand};
 use greentic_interfaces_wasmtime::host_helpers::v1::{runner_host_http, runner_host_kv};
+use greentic_mcp_exec::catalog_diff::diff_catalogs;
+use greentic_mcp_exec::debug_dump::{self, DebugDump, DumpOutcome};
+use greentic_mcp_exec::preflight::check_imports;
 use greentic_mcp_exec::router;
-use greentic_mcp_exec::runner::{StoreState, add_secrets_to_linker};
+use greentic_mcp_exec::runner::{StoreState, add_secrets_to_linker, capability_report};
+use greentic_mcp_exec::schema;
+use greentic_mcp_exec::{RunnerError, RuntimePolicy};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
 use wasmtime::component::{Component, Linker};
 use wasmtime::{Config, Engine, Store};
 use wasmtime_wasi::p2::add_to_linker_sync as add_wasi_to_linker;
@@ -31,6 +38,48 @@ struct Cli {
 enum Commands {
     /// Invoke a router component export (wasix:mcp/router@25.6.18).
     Router(RouterCommand),
+    /// Print example invocations synthesized from a router's input schemas.
+    Examples(ExamplesCommand),
+    /// Compare the tool catalogs of two router components.
+    Diff(DiffCommand),
+    /// Re-execute a debug dump, answering host calls from its transcript.
+    Replay(ReplayCommand),
+}
+
+#[derive(Parser)]
+struct ReplayCommand {
+    /// Directory written by `router --debug-dump` or `RuntimePolicy::debug_dump`.
+    #[arg(value_name = "DIR")]
+    dir: PathBuf,
+    /// Pretty-print the report.
+    #[arg(long)]
+    pretty: bool,
+}
+
+#[derive(Parser)]
+struct DiffCommand {
+    /// Currently deployed router component (.wasm).
+    #[arg(value_name = "OLD")]
+    old: PathBuf,
+    /// Candidate router component (.wasm).
+    #[arg(value_name = "NEW")]
+    new: PathBuf,
+    /// Exit with status 2 when the diff contains breaking changes.
+    #[arg(long)]
+    fail_on_breaking: bool,
+    /// Pretty-print the report.
+    #[arg(long)]
+    pretty: bool,
+}
+
+#[derive(Parser)]
+struct ExamplesCommand {
+    /// Path to the router component (.wasm).
+    #[arg(long, value_name = "PATH")]
+    router: PathBuf,
+    /// Only generate examples for this tool (default: every tool).
+    #[arg(long, value_name = "NAME")]
+    tool: Option<String>,
 }
 
 #[derive(Parser)]
@@ -56,15 +105,25 @@ struct RouterCommand {
     /// Read JSON arguments from file.
     #[arg(long, value_name = "FILE")]
     input_file: Option<PathBuf>,
+    /// Prompt for each argument of the tool's input schema instead of reading JSON.
+    #[arg(long, conflicts_with_all = ["input", "input_file", "list_tools"])]
+    interactive: bool,
     /// Pretty-print the response.
     #[arg(long)]
     pretty: bool,
+    /// Write the component, arguments, host calls, and result into DIR for
+    /// `greentic-mcp-exec replay`.
+    #[arg(long, value_name = "DIR", conflicts_with = "list_tools")]
+    debug_dump: Option<PathBuf>,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
     match cli.command {
         Commands::Router(cmd) => run_router(cmd, cli.verbose),
+        Commands::Examples(cmd) => run_examples(cmd, cli.verbose),
+        Commands::Diff(cmd) => run_diff(cmd, cli.verbose),
+        Commands::Replay(cmd) => run_replay(cmd),
     }
 }
 
@@ -75,8 +134,9 @@ fn run_router(cmd: RouterCommand, verbose: bool) -> Result<()> {
             cmd.list_tools, cmd.enable_http
         );
     }
-    // Avoid blocking on stdin when we're only listing tools.
-    let args_json = if cmd.list_tools {
+    // Avoid blocking on stdin when we're only listing tools; interactive
+    // arguments are collected once the router is instantiated.
+    let args_json = if cmd.list_tools || cmd.interactive {
         "{}".to_string()
     } else {
         load_input(cmd.input.clone(), cmd.input_file.clone())?
@@ -95,7 +155,12 @@ fn run_router(cmd: RouterCommand, verbose: bool) -> Result<()> {
     }
 
     // Offload instantiation/invocation to a worker so we can enforce a wallclock timeout.
-    let timeout = cmd.timeout_ms.map(std::time::Duration::from_millis);
+    // The timeout would otherwise run while waiting on prompts, so it is not
+    // applied in interactive mode.
+    let timeout = cmd
+        .timeout_ms
+        .filter(|_| !cmd.interactive)
+        .map(std::time::Duration::from_millis);
     let (tx, rx) = std::sync::mpsc::channel();
     std::thread::spawn(move || {
         let res = invoke_router(cmd, args_json, engine, component, verbose);
@@ -125,40 +190,12 @@ fn invoke_router(
     component: Component,
     verbose: bool,
 ) -> Result<()> {
-    if verbose {
-        eprintln!("creating linker and wiring wasi/hosts");
-    }
-    let mut linker = Linker::new(&engine);
-    linker.allow_shadowing(true);
-    add_wasi_to_linker(&mut linker)
-        .map_err(|err| anyhow!("linking wasi preview2 imports: {}", err))?;
-
-    // Mirror runtime linker setup so router components importing wasi:http/types
-    // and wasi:tls types can instantiate in this direct CLI path.
-    let mut opts = LinkOptions::default();
-    opts.tls(true);
-    wasmtime_wasi_tls::add_to_linker(&mut linker, &mut opts, |h: &mut StoreState| h.wasi_tls())
-        .map_err(|err| anyhow!("linking wasi tls imports: {}", err))?;
-    wasmtime_wasi_http::add_only_http_to_linker_sync(&mut linker)
-        .map_err(|err| anyhow!("linking wasi http imports: {}", err))?;
-
-    runner_host_http::add_runner_host_http_to_linker(&mut linker, |state: &mut StoreState| state)
-        .map_err(|err| anyhow!("linking runner host http: {}", err))?;
-    runner_host_kv::add_runner_host_kv_to_linker(&mut linker, |state: &mut StoreState| state)
-        .map_err(|err| anyhow!("linking runner host kv: {}", err))?;
-    add_secrets_to_linker(&mut linker).map_err(|err| anyhow!("linking secrets host: {}", err))?;
-
     let http_enabled = cmd.enable_http && !cmd.list_tools;
-    if verbose {
-        eprintln!("building store (http_enabled={})", http_enabled);
+    let (mut store, router) =
+        instantiate_router(&engine, &component, &cmd.router, http_enabled, verbose)?;
+    if cmd.debug_dump.is_some() {
+        store.data_mut().record_host_calls();
     }
-    let mut store = Store::new(&engine, StoreState::new(http_enabled, None, None));
-
-    if verbose {
-        eprintln!("instantiating router component {}", cmd.router.display());
-    }
-    let router = router::McpRouter::instantiate(&mut store, &component, &linker)
-        .map_err(|err| anyhow!("component missing wasix:mcp/router@25.6.18 exports: {err}"))?;
 
     if verbose {
         let tool = cmd.tool.as_deref().unwrap_or("<list-tools>");
@@ -195,14 +232,42 @@ fn invoke_router(
         .as_deref()
         .ok_or_else(|| anyhow!("--tool/--operation is required unless --list-tools is set"))?;
 
+    let args_json = if cmd.interactive {
+        let tools = router_iface
+            .call_list_tools(&mut store)
+            .map_err(|err| anyhow!(err.to_string()))?;
+        let schema = tools
+            .iter()
+            .find(|t| t.name == tool)
+            .map(|t| serde_json::from_str(&t.input_schema))
+            .ok_or_else(|| anyhow!("router does not list tool `{tool}`"))?
+            .with_context(|| format!("parsing input schema of `{tool}`"))?;
+        let args = schema::prompt_arguments(&schema, &mut io::stdin().lock(), &mut io::stderr())
+            .context("prompting for arguments")?;
+        if verbose {
+            eprintln!("collected arguments: {args}");
+        }
+        serde_json::to_string(&args)?
+    } else {
+        args_json
+    };
+
     let result = router_iface
         .call_call_tool(&mut store, tool, &args_json)
-        .map_err(|err| anyhow!(err.to_string()))?;
+        .map(|result| match result {
+            Ok(resp) => router::render_response(&resp),
+            Err(err) => router::tool_error_to_value(tool, err),
+        })
+        .map_err(|err| err.to_string());
 
-    let json = match result {
-        Ok(resp) => router::render_response(&resp),
-        Err(err) => router::tool_error_to_value(tool, err),
-    };
+    if let Some(dir) = &cmd.debug_dump {
+        let host_calls = store.data_mut().take_host_calls();
+        write_debug_dump(dir, &cmd.router, tool, &args_json, host_calls, &result)?;
+        if verbose {
+            eprintln!("debug dump written to {}", dir.display());
+        }
+    }
+    let json = result.map_err(|err| anyhow!(err))?;
 
     if cmd.pretty {
         println!("{}", serde_json::to_string_pretty(&json)?);
@@ -213,6 +278,182 @@ fn invoke_router(
     Ok(())
 }
 
+fn run_examples(cmd: ExamplesCommand, verbose: bool) -> Result<()> {
+    let engine = build_engine()?;
+    let tools = list_router_tools(&engine, &cmd.router, verbose)?;
+    let selected: Vec<_> = tools
+        .iter()
+        .filter(|tool| cmd.tool.as_deref().is_none_or(|name| tool.name == name))
+        .collect();
+    if let Some(name) = &cmd.tool
+        && selected.is_empty()
+    {
+        return Err(anyhow!("router does not list tool `{name}`"));
+    }
+
+    for (index, tool) in selected.into_iter().enumerate() {
+        let input_schema = serde_json::from_str(&tool.input_schema)
+            .with_context(|| format!("parsing input schema of `{}`", tool.name))?;
+        let arguments = schema::example_value(&input_schema);
+        if index > 0 {
+            println!();
+        }
+        if tool.description.is_empty() {
+            println!("# {}", tool.name);
+        } else {
+            println!("# {}: {}", tool.name, tool.description);
+        }
+        println!(
+            "greentic-mcp-exec router --router {} --tool {} --input {}",
+            shell_quote(&cmd.router.display().to_string()),
+            shell_quote(&tool.name),
+            shell_quote(&serde_json::to_string(&arguments)?),
+        );
+    }
+    Ok(())
+}
+
+fn run_diff(cmd: DiffCommand, verbose: bool) -> Result<()> {
+    let engine = build_engine()?;
+    let old = list_router_tools(&engine, &cmd.old, verbose)?;
+    let new = list_router_tools(&engine, &cmd.new, verbose)?;
+    let diff = diff_catalogs(&old, &new);
+
+    if cmd.pretty {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+    } else {
+        println!("{}", serde_json::to_string(&diff)?);
+    }
+    if cmd.fail_on_breaking && diff.breaking {
+        std::process::exit(2);
+    }
+    Ok(())
+}
+
+fn run_replay(cmd: ReplayCommand) -> Result<()> {
+    let report = debug_dump::replay(&cmd.dir, &RuntimePolicy::default())
+        .map_err(|err| anyhow!("replaying {}: {err}", cmd.dir.display()))?;
+    if cmd.pretty {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("{}", serde_json::to_string(&report)?);
+    }
+    if !report.reproduced() {
+        std::process::exit(3);
+    }
+    Ok(())
+}
+
+/// Dump a router call in the layout `debug_dump::replay` reads.
+fn write_debug_dump(
+    dir: &Path,
+    path: &Path,
+    tool: &str,
+    args_json: &str,
+    host_calls: Vec<debug_dump::HostCall>,
+    result: &std::result::Result<Value, String>,
+) -> Result<()> {
+    let bytes = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    let component = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let dump = DebugDump {
+        component,
+        digest: hex::encode(Sha256::digest(&bytes)),
+        action: tool.to_string(),
+        args: serde_json::from_str(args_json)
+            .unwrap_or_else(|_| Value::String(args_json.to_string())),
+        tenant: None,
+        host_calls,
+        // Traps surface as internal runner errors, as they would through the library.
+        outcome: DumpOutcome::of(&result.clone().map_err(RunnerError::Internal)),
+    };
+    dump.write(dir, &bytes)
+        .with_context(|| format!("writing debug dump to {}", dir.display()))
+}
+
+/// Quote `raw` for POSIX shells when it contains anything beyond safe characters.
+fn shell_quote(raw: &str) -> String {
+    let safe = !raw.is_empty()
+        && raw
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/' | ':' | '='));
+    if safe {
+        raw.to_string()
+    } else {
+        format!("'{}'", raw.replace('\'', "'\\''"))
+    }
+}
+
+/// Link host imports and instantiate a router component.
+fn instantiate_router(
+    engine: &Engine,
+    component: &Component,
+    path: &Path,
+    http_enabled: bool,
+    verbose: bool,
+) -> Result<(Store<StoreState>, router::McpRouter)> {
+    if verbose {
+        eprintln!("creating linker and wiring wasi/hosts");
+    }
+    let mut linker = Linker::new(engine);
+    linker.allow_shadowing(true);
+    add_wasi_to_linker(&mut linker)
+        .map_err(|err| anyhow!("linking wasi preview2 imports: {}", err))?;
+
+    // Mirror runtime linker setup so router components importing wasi:http/types
+    // and wasi:tls types can instantiate in this direct CLI path.
+    let mut opts = LinkOptions::default();
+    opts.tls(true);
+    wasmtime_wasi_tls::add_to_linker(&mut linker, &mut opts, |h: &mut StoreState| h.wasi_tls())
+        .map_err(|err| anyhow!("linking wasi tls imports: {}", err))?;
+    wasmtime_wasi_http::add_only_http_to_linker_sync(&mut linker)
+        .map_err(|err| anyhow!("linking wasi http imports: {}", err))?;
+
+    runner_host_http::add_runner_host_http_to_linker(&mut linker, |state: &mut StoreState| state)
+        .map_err(|err| anyhow!("linking runner host http: {}", err))?;
+    runner_host_kv::add_runner_host_kv_to_linker(&mut linker, |state: &mut StoreState| state)
+        .map_err(|err| anyhow!("linking runner host kv: {}", err))?;
+    add_secrets_to_linker(&mut linker).map_err(|err| anyhow!("linking secrets host: {}", err))?;
+
+    if verbose {
+        eprintln!("building store (http_enabled={})", http_enabled);
+    }
+    let state = StoreState::new(http_enabled, None, None);
+    if verbose {
+        let report = capability_report(engine, component, &state);
+        eprintln!("capabilities: {}", serde_json::to_string(&report)?);
+    }
+    let mut store = Store::new(engine, state);
+
+    if let Err(err) = linker.instantiate_pre(component) {
+        let report = check_imports(&linker, component);
+        if report.is_empty() {
+            return Err(anyhow!("linking {}: {err}", path.display()));
+        }
+        return Err(anyhow!("{report}"));
+    }
+
+    if verbose {
+        eprintln!("instantiating router component {}", path.display());
+    }
+    let router = router::McpRouter::instantiate(&mut store, component, &linker)
+        .map_err(|err| anyhow!("component missing wasix:mcp/router@25.6.18 exports: {err}"))?;
+    Ok((store, router))
+}
+
+/// Load a router component and return its tool catalog.
+fn list_router_tools(engine: &Engine, path: &Path, verbose: bool) -> Result<Vec<router::Tool>> {
+    let component = Component::from_file(engine, path)
+        .map_err(|err| anyhow!("loading component {}: {}", path.display(), err))?;
+    let (mut store, router) = instantiate_router(engine, &component, path, false, verbose)?;
+    router
+        .wasix_mcp_router()
+        .call_list_tools(&mut store)
+        .map_err(|err| anyhow!(err.to_string()))
+}
+
 fn load_input(inline: Option<String>, file: Option<PathBuf>) -> Result<String> {
     if let Some(path) = file {
         let contents =
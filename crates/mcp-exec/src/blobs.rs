@@ -0,0 +1,210 @@
+//! Content-addressed blob exchange for components.
+//!
+//! Components that import `greentic:blobs/blob-store@1.0.0` can hand large
+//! binary artifacts to each other, or to the host, by hash instead of inlining
+//! them as base64 in JSON envelopes:
+//!
+//! ```wit
+//! interface blob-store {
+//!   /// Store `bytes`, returning their hash (`sha256:<hex>`).
+//!   put: func(bytes: list<u8>, ttl-secs: option<u64>) -> result<string, string>;
+//!   /// Fetch the bytes stored under `hash` for the calling tenant.
+//!   get: func(hash: string) -> result<option<list<u8>>, string>;
+//! }
+//! ```
+//!
+//! Blobs are scoped to the tenant of the call and persisted by a pluggable
+//! [`BlobStore`]; [`BlobPolicy`] on [`crate::RuntimePolicy::blobs`] sets the
+//! size quotas and expiry the host enforces.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use sha2::{Digest, Sha256};
+
+/// Prefix of every blob hash.
+pub const HASH_PREFIX: &str = "sha256:";
+
+/// The content address of `bytes`.
+pub fn blob_hash(bytes: &[u8]) -> String {
+    format!("{HASH_PREFIX}{}", hex::encode(Sha256::digest(bytes)))
+}
+
+/// Whether `hash` has the shape [`blob_hash`] produces.
+pub fn is_blob_hash(hash: &str) -> bool {
+    hash.strip_prefix(HASH_PREFIX).is_some_and(|hex| {
+        hex.len() == 64 && hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    })
+}
+
+/// Persistence behind the blob-store import.
+///
+/// The host hashes, scopes, and checks quotas; stores only keep bytes per
+/// tenant and hash, and must stop returning blobs once they expire.
+pub trait BlobStore: Send + Sync {
+    /// Store `bytes` under `hash` for `tenant` until `expires_at`. Putting an
+    /// existing blob again replaces its expiry.
+    fn put(
+        &self,
+        tenant: &str,
+        hash: &str,
+        bytes: &[u8],
+        expires_at: Option<SystemTime>,
+    ) -> Result<(), String>;
+
+    /// The live blob under `hash` for `tenant`, if any.
+    fn get(&self, tenant: &str, hash: &str) -> Result<Option<Vec<u8>>, String>;
+
+    /// Whether a live blob exists under `hash` for `tenant`.
+    fn contains(&self, tenant: &str, hash: &str) -> Result<bool, String> {
+        self.get(tenant, hash).map(|blob| blob.is_some())
+    }
+
+    /// Bytes held by the live blobs of `tenant`.
+    fn usage(&self, tenant: &str) -> Result<u64, String>;
+}
+
+/// Shared blob-store handle.
+pub type DynBlobStore = Arc<dyn BlobStore>;
+
+/// Limits the host enforces on the blob-store import.
+#[derive(Clone)]
+pub struct BlobPolicy {
+    pub store: DynBlobStore,
+    /// Largest single blob a component may put.
+    pub max_blob_bytes: u64,
+    /// Total bytes of live blobs per tenant; puts beyond it fail.
+    pub tenant_quota_bytes: u64,
+    /// Expiry of blobs put without a TTL; `None` keeps them until the store
+    /// evicts them.
+    pub default_ttl: Option<Duration>,
+    /// Longest TTL a component may request.
+    pub max_ttl: Option<Duration>,
+}
+
+impl BlobPolicy {
+    /// Default limits over `store`: 16 MiB blobs, 256 MiB per tenant, and a
+    /// one hour TTL that components cannot extend past a day.
+    pub fn new(store: DynBlobStore) -> Self {
+        Self {
+            store,
+            max_blob_bytes: 16 * 1024 * 1024,
+            tenant_quota_bytes: 256 * 1024 * 1024,
+            default_ttl: Some(Duration::from_secs(60 * 60)),
+            max_ttl: Some(Duration::from_secs(24 * 60 * 60)),
+        }
+    }
+
+    /// TTL of a put that asked for `requested`, capped at [`Self::max_ttl`].
+    pub(crate) fn ttl(&self, requested: Option<Duration>) -> Option<Duration> {
+        match (requested.or(self.default_ttl), self.max_ttl) {
+            (Some(ttl), Some(max)) => Some(ttl.min(max)),
+            (None, max) => max,
+            (ttl, None) => ttl,
+        }
+    }
+}
+
+impl fmt::Debug for BlobPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlobPolicy")
+            .field("store", &"<dyn BlobStore>")
+            .field("max_blob_bytes", &self.max_blob_bytes)
+            .field("tenant_quota_bytes", &self.tenant_quota_bytes)
+            .field("default_ttl", &self.default_ttl)
+            .field("max_ttl", &self.max_ttl)
+            .finish()
+    }
+}
+
+type BlobKey = (String, String);
+type BlobEntry = (Arc<[u8]>, Option<SystemTime>);
+
+/// Process-local [`BlobStore`] that drops expired blobs as it goes.
+#[derive(Debug, Default)]
+pub struct InMemoryBlobStore {
+    blobs: Mutex<HashMap<BlobKey, BlobEntry>>,
+}
+
+impl InMemoryBlobStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn live(&self) -> std::sync::MutexGuard<'_, HashMap<BlobKey, BlobEntry>> {
+        let mut blobs = self
+            .blobs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = SystemTime::now();
+        blobs.retain(|_, (_, expires_at)| expires_at.is_none_or(|at| at > now));
+        blobs
+    }
+}
+
+impl BlobStore for InMemoryBlobStore {
+    fn put(
+        &self,
+        tenant: &str,
+        hash: &str,
+        bytes: &[u8],
+        expires_at: Option<SystemTime>,
+    ) -> Result<(), String> {
+        self.live().insert(
+            (tenant.to_string(), hash.to_string()),
+            (bytes.into(), expires_at),
+        );
+        Ok(())
+    }
+
+    fn get(&self, tenant: &str, hash: &str) -> Result<Option<Vec<u8>>, String> {
+        Ok(self
+            .live()
+            .get(&(tenant.to_string(), hash.to_string()))
+            .map(|(bytes, _)| bytes.to_vec()))
+    }
+
+    fn contains(&self, tenant: &str, hash: &str) -> Result<bool, String> {
+        Ok(self
+            .live()
+            .contains_key(&(tenant.to_string(), hash.to_string())))
+    }
+
+    fn usage(&self, tenant: &str) -> Result<u64, String> {
+        Ok(self
+            .live()
+            .iter()
+            .filter(|((owner, _), _)| owner == tenant)
+            .map(|(_, (bytes, _))| bytes.len() as u64)
+            .sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expired_blobs_are_dropped_and_ttls_capped() {
+        let store = InMemoryBlobStore::new();
+        let past = SystemTime::now() - Duration::from_secs(1);
+        store
+            .put("acme", "sha256:a", b"old", Some(past))
+            .expect("put");
+        store.put("acme", "sha256:b", b"fresh", None).expect("put");
+        assert_eq!(store.get("acme", "sha256:a").expect("get"), None);
+        assert_eq!(store.usage("acme").expect("usage"), 5);
+        assert_eq!(store.usage("globex").expect("usage"), 0);
+
+        let policy = BlobPolicy::new(Arc::new(InMemoryBlobStore::new()));
+        assert_eq!(policy.ttl(None), Some(Duration::from_secs(3600)));
+        assert_eq!(
+            policy.ttl(Some(Duration::from_secs(7 * 24 * 3600))),
+            Some(Duration::from_secs(24 * 3600))
+        );
+        assert!(is_blob_hash(&blob_hash(b"x")));
+        assert!(!is_blob_hash("sha256:XYZ"));
+    }
+}
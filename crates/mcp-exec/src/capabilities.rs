@@ -0,0 +1,140 @@
+//! Per-execution report of a component's sandbox posture.
+//!
+//! A [`CapabilityReport`] lists the interfaces a component imports next to the
+//! capabilities its store was actually granted for one run, so sandbox posture
+//! can be audited call by call instead of inferred from configuration. Every
+//! wasm execution logs its report at debug level under the
+//! `greentic_mcp_exec::capabilities` tracing target. With
+//! [`crate::RuntimePolicy::capability_report`] set, the report is also attached
+//! to the result under `capabilities`, and
+//! [`crate::Executor::inspect_capabilities`] returns it without running the
+//! component.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+use wasmtime::Engine;
+use wasmtime::component::Component;
+
+/// Tracing target of the per-execution capability log.
+pub(crate) const TRACE_TARGET: &str = "greentic_mcp_exec::capabilities";
+
+/// Imports of a component and the capabilities granted to one execution.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityReport {
+    /// Every interface the component imports, sorted, e.g.
+    /// `wasi:filesystem/preopens@0.2.0`.
+    pub imports: Vec<String>,
+    pub granted: GrantedCapabilities,
+}
+
+impl CapabilityReport {
+    /// The WASI interfaces among [`Self::imports`].
+    pub fn wasi_imports(&self) -> impl Iterator<Item = &str> {
+        self.imports
+            .iter()
+            .map(String::as_str)
+            .filter(|name| name.starts_with("wasi:"))
+    }
+
+    /// Read the report attached to an execution result, if any.
+    pub fn from_result(value: &Value) -> Option<Self> {
+        serde_json::from_value(value.get("capabilities")?.clone()).ok()
+    }
+}
+
+/// What the WASI context and host imports allow during one execution.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GrantedCapabilities {
+    /// TCP and UDP sockets may reach host networks.
+    pub sockets: bool,
+    pub ip_name_lookup: bool,
+    /// Requests through the runner-host HTTP import are served.
+    pub http: bool,
+    /// Guest paths of preopened directories.
+    pub preopened_dirs: Vec<String>,
+    /// The host's environment variables are visible to the guest.
+    pub env: bool,
+    /// Guest stdout is captured rather than written to the host's stdout.
+    pub stdout_captured: bool,
+    /// A secrets store is reachable through the secrets host import.
+    pub secrets: bool,
+}
+
+/// Sorted names of the interfaces `component` imports.
+pub(crate) fn imports(engine: &Engine, component: &Component) -> Vec<String> {
+    let mut names: Vec<String> = component
+        .component_type()
+        .imports(engine)
+        .map(|(name, _)| name.to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Log the report of an execution of `component`.
+pub(crate) fn log(component: &str, report: &CapabilityReport) {
+    debug!(
+        target: TRACE_TARGET,
+        component,
+        imports = ?report.imports,
+        granted = ?report.granted,
+        "component capabilities"
+    );
+}
+
+/// Attach `report` to a result value under `capabilities`.
+pub(crate) fn attach(value: &mut Value, report: &CapabilityReport) {
+    if let (Value::Object(map), Ok(report)) = (value, serde_json::to_value(report)) {
+        map.insert("capabilities".to_string(), report);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NetworkProfile;
+    use crate::runner::StoreState;
+    use serde_json::json;
+
+    #[test]
+    fn reports_granted_capabilities_per_profile() {
+        let standard = StoreState::new(true, None, None).granted_capabilities();
+        assert!(standard.sockets && standard.ip_name_lookup && standard.http);
+        assert!(standard.env && !standard.secrets && !standard.stdout_captured);
+        assert!(standard.preopened_dirs.is_empty());
+
+        let hardened = StoreState::new(true, None, None)
+            .with_network_profile(NetworkProfile::Hardened)
+            .expect("hardened store")
+            .granted_capabilities();
+        assert!(!hardened.sockets && !hardened.ip_name_lookup && !hardened.http);
+
+        let scratch = tempfile::tempdir().expect("tempdir");
+        let offline = StoreState::new(false, None, None)
+            .with_scratch_dir(scratch.path())
+            .expect("scratch store")
+            .granted_capabilities();
+        assert!(!offline.sockets && !offline.http);
+        assert_eq!(offline.preopened_dirs, ["/tmp"]);
+
+        let report = CapabilityReport {
+            imports: vec![
+                "greentic:host/secrets@1.0.0".into(),
+                "wasi:cli/environment@0.2.0".into(),
+            ],
+            granted: offline,
+        };
+        let mut value = json!({"ok": true});
+        attach(&mut value, &report);
+        assert_eq!(
+            value["capabilities"]["granted"]["preopened_dirs"],
+            json!(["/tmp"])
+        );
+        assert_eq!(CapabilityReport::from_result(&value), Some(report.clone()));
+        assert_eq!(
+            report.wasi_imports().collect::<Vec<_>>(),
+            ["wasi:cli/environment@0.2.0"]
+        );
+    }
+}
@@ -0,0 +1,568 @@
+//! Structured comparison of two router tool catalogs.
+//!
+//! Changes are classified from a caller's point of view: anything that can
+//! make an existing call fail or an existing consumer misread a result is
+//! breaking. Input schemas break when they demand more (new required fields,
+//! narrower types or enums); output schemas break when they promise less.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::router::Tool;
+
+/// Whether a change can break existing callers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Impact {
+    Breaking,
+    NonBreaking,
+}
+
+/// A single difference within a tool.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Change {
+    /// Location of the change, e.g. `input.city` or `output.items[]`.
+    pub path: String,
+    pub impact: Impact,
+    pub description: String,
+}
+
+/// All differences for a tool present in both catalogs.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ToolChanges {
+    pub tool: String,
+    pub changes: Vec<Change>,
+}
+
+/// Differences between an old and a new tool catalog.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CatalogDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<ToolChanges>,
+    pub breaking: bool,
+}
+
+impl CatalogDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// One-line rendering of the breaking changes, for logs and errors.
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = self
+            .removed
+            .iter()
+            .map(|tool| format!("{tool}: tool removed"))
+            .collect();
+        for tool in &self.changed {
+            for change in &tool.changes {
+                if change.impact == Impact::Breaking {
+                    parts.push(format!(
+                        "{}: {} at {}",
+                        tool.tool, change.description, change.path
+                    ));
+                }
+            }
+        }
+        if parts.is_empty() {
+            "no breaking changes".into()
+        } else {
+            parts.join("; ")
+        }
+    }
+}
+
+/// A recorded tool entry in MCP `tools/list` shape, used as the expected
+/// catalog in [`crate::VerifyPolicy::catalog_snapshots`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotTool {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "inputSchema", default = "object_schema")]
+    pub input_schema: Value,
+    #[serde(
+        rename = "outputSchema",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_schema: Option<Value>,
+}
+
+impl SnapshotTool {
+    /// Record a router tool.
+    pub fn from_tool(tool: &Tool) -> Self {
+        Self {
+            name: tool.name.clone(),
+            title: tool.title.clone(),
+            description: Some(tool.description.clone()),
+            input_schema: parse_schema(&tool.input_schema),
+            output_schema: tool.output_schema.as_deref().map(parse_schema),
+        }
+    }
+}
+
+fn object_schema() -> Value {
+    serde_json::json!({ "type": "object" })
+}
+
+/// A tool as seen by the diff: anything with a name and JSON schemas.
+///
+/// Implemented for router tools here; other catalog representations (e.g.
+/// MCP `tools/list` results) implement it to share the same rules.
+pub trait CatalogTool {
+    fn name(&self) -> &str;
+    fn title(&self) -> Option<&str>;
+    fn description(&self) -> Option<&str>;
+    /// Annotations rendered to a comparable form, if any.
+    fn annotations(&self) -> Option<String>;
+    fn input_schema(&self) -> Value;
+    fn output_schema(&self) -> Option<Value>;
+}
+
+impl CatalogTool for Tool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some(&self.description)
+    }
+
+    fn annotations(&self) -> Option<String> {
+        self.annotations
+            .as_ref()
+            .map(|annotations| format!("{annotations:?}"))
+    }
+
+    fn input_schema(&self) -> Value {
+        parse_schema(&self.input_schema)
+    }
+
+    fn output_schema(&self) -> Option<Value> {
+        self.output_schema.as_deref().map(parse_schema)
+    }
+}
+
+impl CatalogTool for SnapshotTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    // Snapshots pin schemas; annotations are hints and are not recorded.
+    fn annotations(&self) -> Option<String> {
+        None
+    }
+
+    fn input_schema(&self) -> Value {
+        self.input_schema.clone()
+    }
+
+    fn output_schema(&self) -> Option<Value> {
+        self.output_schema.clone()
+    }
+}
+
+/// Which side of the call a schema describes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Input,
+    Output,
+}
+
+/// Compare two catalogs, keyed by tool name.
+pub fn diff_catalogs<T: CatalogTool>(old: &[T], new: &[T]) -> CatalogDiff {
+    let old: BTreeMap<&str, &T> = old.iter().map(|tool| (tool.name(), tool)).collect();
+    let new: BTreeMap<&str, &T> = new.iter().map(|tool| (tool.name(), tool)).collect();
+
+    let mut diff = CatalogDiff {
+        added: new
+            .keys()
+            .filter(|name| !old.contains_key(*name))
+            .map(|name| name.to_string())
+            .collect(),
+        removed: old
+            .keys()
+            .filter(|name| !new.contains_key(*name))
+            .map(|name| name.to_string())
+            .collect(),
+        ..Default::default()
+    };
+
+    for (name, old_tool) in &old {
+        let Some(new_tool) = new.get(name) else {
+            continue;
+        };
+        let changes = diff_tool(*old_tool, *new_tool);
+        if !changes.is_empty() {
+            diff.changed.push(ToolChanges {
+                tool: name.to_string(),
+                changes,
+            });
+        }
+    }
+
+    diff.breaking = !diff.removed.is_empty()
+        || diff
+            .changed
+            .iter()
+            .flat_map(|tool| &tool.changes)
+            .any(|change| change.impact == Impact::Breaking);
+    diff
+}
+
+fn diff_tool<T: CatalogTool>(old: &T, new: &T) -> Vec<Change> {
+    let mut changes = Vec::new();
+    if old.title() != new.title() {
+        changes.push(non_breaking("title", "title changed"));
+    }
+    if old.description() != new.description() {
+        changes.push(non_breaking("description", "description changed"));
+    }
+    if old.annotations() != new.annotations() {
+        changes.push(non_breaking("annotations", "annotations changed"));
+    }
+
+    diff_schema(
+        "input",
+        &old.input_schema(),
+        &new.input_schema(),
+        Direction::Input,
+        &mut changes,
+    );
+
+    match (old.output_schema(), new.output_schema()) {
+        (Some(old_output), Some(new_output)) => diff_schema(
+            "output",
+            &old_output,
+            &new_output,
+            Direction::Output,
+            &mut changes,
+        ),
+        (Some(_), None) => changes.push(breaking("output", "output schema removed")),
+        (None, Some(_)) => changes.push(non_breaking("output", "output schema added")),
+        (None, None) => {}
+    }
+    changes
+}
+
+fn diff_schema(path: &str, old: &Value, new: &Value, dir: Direction, changes: &mut Vec<Change>) {
+    let old_types = types(old);
+    let new_types = types(new);
+    if old_types != new_types {
+        // Accepting more input types, or returning fewer output types, is safe.
+        let widened = match dir {
+            Direction::Input => !old_types.is_empty() && old_types.is_subset(&new_types),
+            Direction::Output => !new_types.is_empty() && new_types.is_subset(&old_types),
+        };
+        let description = format!(
+            "type changed from {} to {}",
+            render_types(&old_types),
+            render_types(&new_types)
+        );
+        changes.push(change(path, widened, description));
+    }
+
+    diff_enum(path, old, new, dir, changes);
+
+    let old_required = required(old);
+    let new_required = required(new);
+    let old_props = properties(old);
+    let new_props = properties(new);
+
+    for (name, old_prop) in &old_props {
+        let prop_path = format!("{path}.{name}");
+        match new_props.get(name) {
+            Some(new_prop) => diff_schema(&prop_path, old_prop, new_prop, dir, changes),
+            None => {
+                // Dropping an input field breaks callers that still send it
+                // under strict schemas; dropping an output field breaks readers.
+                changes.push(breaking(&prop_path, "property removed"));
+            }
+        }
+    }
+    for name in new_props
+        .keys()
+        .filter(|name| !old_props.contains_key(*name))
+    {
+        let prop_path = format!("{path}.{name}");
+        let safe = dir == Direction::Output || !new_required.contains(name);
+        let description = if new_required.contains(name) {
+            "required property added"
+        } else {
+            "optional property added"
+        };
+        changes.push(change(&prop_path, safe, description.into()));
+    }
+    for name in new_required
+        .difference(&old_required)
+        .filter(|name| old_props.contains_key(*name))
+    {
+        changes.push(change(
+            &format!("{path}.{name}"),
+            dir == Direction::Output,
+            "property became required".into(),
+        ));
+    }
+    for name in old_required
+        .difference(&new_required)
+        .filter(|name| new_props.contains_key(*name))
+    {
+        changes.push(change(
+            &format!("{path}.{name}"),
+            dir == Direction::Input,
+            "property became optional".into(),
+        ));
+    }
+
+    if let (Some(old_items), Some(new_items)) = (old.get("items"), new.get("items")) {
+        diff_schema(&format!("{path}[]"), old_items, new_items, dir, changes);
+    }
+}
+
+fn diff_enum(path: &str, old: &Value, new: &Value, dir: Direction, changes: &mut Vec<Change>) {
+    let old_values = enum_values(old);
+    let new_values = enum_values(new);
+    if old_values == new_values {
+        return;
+    }
+    match (old_values, new_values) {
+        (Some(old_values), Some(new_values)) => {
+            let removed: Vec<&String> = old_values.difference(&new_values).collect();
+            let added: Vec<&String> = new_values.difference(&old_values).collect();
+            if !removed.is_empty() {
+                changes.push(change(
+                    path,
+                    dir == Direction::Output,
+                    format!("enum values removed: {}", join(&removed)),
+                ));
+            }
+            if !added.is_empty() {
+                changes.push(change(
+                    path,
+                    dir == Direction::Input,
+                    format!("enum values added: {}", join(&added)),
+                ));
+            }
+        }
+        (None, Some(_)) => {
+            changes.push(change(
+                path,
+                dir == Direction::Output,
+                "values restricted to an enum".into(),
+            ));
+        }
+        (Some(_), None) => {
+            changes.push(change(
+                path,
+                dir == Direction::Input,
+                "enum restriction removed".into(),
+            ));
+        }
+        (None, None) => {}
+    }
+}
+
+fn parse_schema(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or(Value::Null)
+}
+
+fn types(schema: &Value) -> BTreeSet<String> {
+    match schema.get("type") {
+        Some(Value::String(ty)) => BTreeSet::from([ty.clone()]),
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_owned)
+            .collect(),
+        _ => BTreeSet::new(),
+    }
+}
+
+fn render_types(types: &BTreeSet<String>) -> String {
+    if types.is_empty() {
+        "any".into()
+    } else {
+        types.iter().cloned().collect::<Vec<_>>().join("|")
+    }
+}
+
+fn required(schema: &Value) -> BTreeSet<String> {
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_owned)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn properties(schema: &Value) -> BTreeMap<String, Value> {
+    schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .map(|props| {
+            props
+                .iter()
+                .map(|(name, prop)| (name.clone(), prop.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn enum_values(schema: &Value) -> Option<BTreeSet<String>> {
+    schema
+        .get("enum")
+        .and_then(Value::as_array)
+        .map(|values| values.iter().map(Value::to_string).collect())
+}
+
+fn join(values: &[&String]) -> String {
+    values
+        .iter()
+        .map(|value| value.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn change(path: &str, safe: bool, description: String) -> Change {
+    Change {
+        path: path.to_string(),
+        impact: if safe {
+            Impact::NonBreaking
+        } else {
+            Impact::Breaking
+        },
+        description,
+    }
+}
+
+fn breaking(path: &str, description: &str) -> Change {
+    change(path, false, description.to_string())
+}
+
+fn non_breaking(path: &str, description: &str) -> Change {
+    change(path, true, description.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool(name: &str, input: Value, output: Option<Value>) -> Tool {
+        Tool {
+            name: name.into(),
+            title: None,
+            description: format!("{name} tool"),
+            input_schema: input.to_string(),
+            output_schema: output.map(|schema| schema.to_string()),
+            annotations: None,
+            meta: None,
+        }
+    }
+
+    fn find<'a>(diff: &'a CatalogDiff, path: &str) -> &'a Change {
+        diff.changed
+            .iter()
+            .flat_map(|tool| &tool.changes)
+            .find(|change| change.path == path)
+            .unwrap_or_else(|| panic!("no change at {path}"))
+    }
+
+    #[test]
+    fn reports_added_and_removed_tools() {
+        let schema = json!({"type": "object"});
+        let old = [
+            tool("a", schema.clone(), None),
+            tool("b", schema.clone(), None),
+        ];
+        let new = [tool("b", schema.clone(), None), tool("c", schema, None)];
+
+        let diff = diff_catalogs(&old, &new);
+        assert_eq!(diff.added, ["c"]);
+        assert_eq!(diff.removed, ["a"]);
+        assert!(diff.changed.is_empty());
+        assert!(diff.breaking);
+    }
+
+    #[test]
+    fn classifies_input_and_output_schema_changes() {
+        let old = [tool(
+            "forecast",
+            json!({
+                "type": "object",
+                "required": ["city"],
+                "properties": {
+                    "city": {"type": "string"},
+                    "units": {"type": "string", "enum": ["metric", "imperial"]},
+                    "days": {"type": "integer"}
+                }
+            }),
+            Some(json!({
+                "type": "object",
+                "properties": {"temp": {"type": "number"}, "summary": {"type": "string"}}
+            })),
+        )];
+        let new = [tool(
+            "forecast",
+            json!({
+                "type": "object",
+                "required": ["city", "country"],
+                "properties": {
+                    "city": {"type": "string"},
+                    "country": {"type": "string"},
+                    "units": {"type": "string", "enum": ["metric", "imperial", "kelvin"]},
+                    "days": {"type": ["integer", "string"]},
+                    "hourly": {"type": "boolean"}
+                }
+            }),
+            Some(json!({
+                "type": "object",
+                "properties": {"temp": {"type": "number"}, "wind": {"type": "number"}}
+            })),
+        )];
+
+        let diff = diff_catalogs(&old, &new);
+        assert!(diff.breaking);
+        assert_eq!(find(&diff, "input.country").impact, Impact::Breaking);
+        assert_eq!(find(&diff, "input.hourly").impact, Impact::NonBreaking);
+        assert_eq!(find(&diff, "input.units").impact, Impact::NonBreaking);
+        assert_eq!(find(&diff, "input.days").impact, Impact::NonBreaking);
+        assert_eq!(find(&diff, "output.summary").impact, Impact::Breaking);
+        assert_eq!(find(&diff, "output.wind").impact, Impact::NonBreaking);
+    }
+
+    #[test]
+    fn description_only_changes_are_not_breaking() {
+        let schema = json!({"type": "object"});
+        let old = [tool("a", schema.clone(), None)];
+        let mut updated = tool("a", schema, None);
+        updated.description = "clearer words".into();
+
+        let diff = diff_catalogs(&old, &[updated]);
+        assert!(!diff.breaking);
+        assert_eq!(find(&diff, "description").impact, Impact::NonBreaking);
+    }
+}
@@ -0,0 +1,262 @@
+//! Tool catalog compatibility gate enforced at execution setup.
+//!
+//! When [`VerifyPolicy::catalog_snapshots`](crate::VerifyPolicy) holds an
+//! expected catalog for a component, its live catalog is listed and diffed
+//! against the snapshot before any call, so an upstream tool change that would
+//! break a flow is caught before it runs. Artifacts that are not wasm, such
+//! as JSON mocks, have no catalog to list and are not gated.
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+
+use tracing::warn;
+use wasmtime::Engine;
+use wasmtime::component::InstancePre;
+
+use crate::catalog_diff::{SnapshotTool, diff_catalogs};
+use crate::config::{CatalogGate, ExecConfig};
+use crate::error::{ExecError, RunnerError, VerificationError};
+use crate::preview1;
+use crate::router::Tool;
+use crate::runner::{self, StoreState};
+use crate::verify::VerifiedArtifact;
+
+/// Live catalogs listed by [`check_artifact`], by artifact digest, so each
+/// artifact is compiled for the gate only once per process.
+static CATALOGS: Mutex<BTreeMap<String, Vec<Tool>>> = Mutex::new(BTreeMap::new());
+
+/// Whether `verified` is gated: its component has a snapshot and the artifact
+/// is wasm.
+pub(crate) fn applies(component: &str, verified: &VerifiedArtifact, cfg: &ExecConfig) -> bool {
+    cfg.security.catalog_snapshots.contains_key(component)
+        && verified.resolved.bytes.starts_with(b"\0asm")
+}
+
+/// Check an artifact, for callers without a prepared instance. The artifact is
+/// compiled and its catalog listed on the first check of its digest only.
+pub(crate) fn check_artifact(
+    component: &str,
+    verified: &VerifiedArtifact,
+    cfg: &ExecConfig,
+) -> Result<(), ExecError> {
+    let Some(snapshot) = cfg.security.catalog_snapshots.get(component) else {
+        return Ok(());
+    };
+    if !applies(component, verified, cfg) {
+        return Ok(());
+    }
+
+    let digest = &verified.resolved.digest;
+    let cached = lock_catalogs().get(digest).cloned();
+    let tools = match cached {
+        Some(tools) => tools,
+        None => {
+            let prepare = || -> Result<_, RunnerError> {
+                let engine = runner::build_engine(&cfg.runtime)?;
+                let compiled =
+                    preview1::compile(&engine, verified.resolved.bytes.as_ref(), &cfg.runtime)?;
+                let linker = runner::build_linker(&engine)?;
+                let pre = runner::prepare_instance(&engine, &linker, &compiled, &cfg.runtime)?;
+                Ok((engine, pre))
+            };
+            let (engine, pre) = prepare().map_err(|err| ExecError::runner(component, err))?;
+            let tools = list_tools(&engine, &pre, component, cfg)?;
+            lock_catalogs().insert(digest.clone(), tools.clone());
+            tools
+        }
+    };
+    evaluate(component, snapshot, &tools, cfg.security.catalog_gate)
+}
+
+/// Check a prepared component against its snapshot, if one is configured.
+pub(crate) fn check(
+    engine: &Engine,
+    pre: &InstancePre<StoreState>,
+    component: &str,
+    cfg: &ExecConfig,
+) -> Result<(), ExecError> {
+    let Some(snapshot) = cfg.security.catalog_snapshots.get(component) else {
+        return Ok(());
+    };
+    let tools = list_tools(engine, pre, component, cfg)?;
+    evaluate(component, snapshot, &tools, cfg.security.catalog_gate)
+}
+
+/// The component's live catalog, listed from a throwaway instance with HTTP
+/// disabled. Components that are not routers are treated as exposing no
+/// tools.
+fn list_tools(
+    engine: &Engine,
+    pre: &InstancePre<StoreState>,
+    component: &str,
+    cfg: &ExecConfig,
+) -> Result<Vec<Tool>, ExecError> {
+    Ok(
+        runner::instantiate(engine, pre, &cfg.runtime, false, None, None)
+            .and_then(|ready| ready.list_tools())
+            .map_err(|err| ExecError::runner(component, err))?
+            .unwrap_or_default(),
+    )
+}
+
+fn lock_catalogs() -> MutexGuard<'static, BTreeMap<String, Vec<Tool>>> {
+    CATALOGS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn evaluate(
+    component: &str,
+    snapshot: &[SnapshotTool],
+    tools: &[Tool],
+    gate: CatalogGate,
+) -> Result<(), ExecError> {
+    let current: Vec<SnapshotTool> = tools.iter().map(SnapshotTool::from_tool).collect();
+    let diff = diff_catalogs(snapshot, &current);
+    if !diff.breaking {
+        return Ok(());
+    }
+
+    match gate {
+        CatalogGate::Reject => Err(ExecError::verification(
+            component,
+            VerificationError::IncompatibleCatalog(diff),
+        )),
+        CatalogGate::Warn => {
+            warn!(
+                component,
+                changes = %diff.summary(),
+                "tool catalog deviates incompatibly from the recorded snapshot"
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::config::{RuntimePolicy, VerifyPolicy};
+    use crate::resolve::ResolvedArtifact;
+    use crate::store::{ToolInfo, ToolStore};
+    use serde_json::json;
+
+    fn artifact(bytes: &[u8], digest: &str) -> VerifiedArtifact {
+        VerifiedArtifact {
+            resolved: ResolvedArtifact {
+                info: ToolInfo {
+                    name: "weather".into(),
+                    path: "weather.wasm".into(),
+                    sha256: None,
+                },
+                bytes: Arc::from(bytes),
+                digest: digest.into(),
+            },
+            verified_digest: None,
+            verified_signer: None,
+        }
+    }
+
+    fn gated_config() -> ExecConfig {
+        let mut security = VerifyPolicy::default();
+        security
+            .catalog_snapshots
+            .insert("weather".into(), Vec::new());
+        ExecConfig {
+            store: ToolStore::LocalDir(PathBuf::from(".")),
+            security,
+            runtime: RuntimePolicy::default(),
+            http_enabled: false,
+            secrets_store: None,
+            cost_sink: None,
+            transforms: None,
+            hooks: None,
+        }
+    }
+
+    #[test]
+    fn catalogs_are_listed_once_per_digest() {
+        let cfg = gated_config();
+        let component = wat::parse_str("(component)").expect("component");
+        let digest = "sha256:catalog-gate-cache";
+        check_artifact("weather", &artifact(&component, digest), &cfg).expect("first check");
+        assert!(lock_catalogs().contains_key(digest));
+
+        // Same digest: the cached catalog is used, so nothing is compiled.
+        let garbage = b"\0asm\x0d\0\x01\0not a component";
+        check_artifact("weather", &artifact(garbage, digest), &cfg).expect("cached check");
+        check_artifact("weather", &artifact(garbage, "sha256:uncached"), &cfg)
+            .expect_err("uncached artifacts are compiled");
+    }
+
+    #[test]
+    fn json_mocks_are_not_gated() {
+        let cfg = gated_config();
+        let mock = br#"{"_mock_mcp_exec": true, "responses": {"ping": {"pong": 1}}}"#;
+        let mock = artifact(mock, "sha256:catalog-gate-mock");
+        assert!(!applies("weather", &mock, &cfg));
+        check_artifact("weather", &mock, &cfg).expect("mocks pass");
+        assert!(!lock_catalogs().contains_key("sha256:catalog-gate-mock"));
+    }
+
+    fn tool(required: &[&str]) -> Tool {
+        Tool {
+            name: "forecast".into(),
+            title: None,
+            description: "Forecast".into(),
+            input_schema: json!({
+                "type": "object",
+                "required": required,
+                "properties": {
+                    "city": { "type": "string" },
+                    "country": { "type": "string" }
+                }
+            })
+            .to_string(),
+            output_schema: None,
+            annotations: None,
+            meta: None,
+        }
+    }
+
+    #[test]
+    fn breaking_deviation_is_rejected_or_warned() {
+        let snapshot = vec![SnapshotTool::from_tool(&tool(&["city"]))];
+        let stricter = [tool(&["city", "country"])];
+
+        let err = evaluate("weather", &snapshot, &stricter, CatalogGate::Reject)
+            .expect_err("new required field breaks callers");
+        assert!(matches!(
+            err,
+            ExecError::Verification {
+                source: VerificationError::IncompatibleCatalog(_),
+                ..
+            }
+        ));
+        assert!(err.to_string().contains("weather"));
+
+        evaluate("weather", &snapshot, &stricter, CatalogGate::Warn).expect("warn only");
+    }
+
+    #[test]
+    fn compatible_changes_pass() {
+        let snapshot = vec![SnapshotTool::from_tool(&tool(&["city", "country"]))];
+        evaluate(
+            "weather",
+            &snapshot,
+            &[tool(&["city"])],
+            CatalogGate::Reject,
+        )
+        .expect("relaxing a requirement is compatible");
+
+        let snapshot: Vec<SnapshotTool> = serde_json::from_value(json!([
+            { "name": "forecast", "description": "Forecast" }
+        ]))
+        .expect("snapshot");
+        evaluate("weather", &snapshot, &[tool(&[])], CatalogGate::Reject)
+            .expect("adding optional fields is compatible");
+    }
+}
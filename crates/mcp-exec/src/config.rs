@@ -3,12 +3,20 @@
 
 use std::collections::HashMap;
 use std::fmt;
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
-use greentic_types::TenantCtx;
+use greentic_types::{SecretRequirement, TenantCtx};
 
+use crate::blobs::BlobPolicy;
+use crate::catalog_diff::SnapshotTool;
+use crate::cost::DynCostSink;
+use crate::hooks::ExecHooks;
+use crate::permissions::PermissionGate;
 use crate::store::ToolStore;
+use crate::stream::StreamCapture;
+use crate::transform::TransformHooks;
 
 /// Configuration for a single executor invocation.
 #[derive(Clone)]
@@ -20,6 +28,12 @@ pub struct ExecConfig {
     /// Optional secrets-store binding implementing greentic:secrets/store@1.0.0.
     /// When absent, secrets imports will return a host error.
     pub secrets_store: Option<DynSecretsStore>,
+    /// Receives the cost of every wasm execution, keyed by tenant.
+    pub cost_sink: Option<DynCostSink>,
+    /// Per-component rewriting of call arguments and results.
+    pub transforms: Option<TransformHooks>,
+    /// Callbacks that approve each call before it runs and observe its outcome.
+    pub hooks: Option<ExecHooks>,
 }
 
 /// Policy describing how artifacts must be verified prior to execution.
@@ -31,6 +45,26 @@ pub struct VerifyPolicy {
     pub required_digests: HashMap<String, String>,
     /// Signers that are trusted to vouch for artifacts.
     pub trusted_signers: Vec<String>,
+    /// Trust-on-first-use record file. Components without a required digest are
+    /// accepted on first sight, their digest is persisted here, and any later
+    /// change is rejected.
+    pub tofu_store: Option<PathBuf>,
+    /// Expected tool catalogs keyed by component identifier. Router components
+    /// with a snapshot have their live catalog diffed against it before they
+    /// are called; see [`VerifyPolicy::catalog_gate`].
+    pub catalog_snapshots: HashMap<String, Vec<SnapshotTool>>,
+    /// What to do when a catalog deviates incompatibly from its snapshot.
+    pub catalog_gate: CatalogGate,
+}
+
+/// Outcome of a breaking deviation from a recorded tool catalog.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CatalogGate {
+    /// Fail the execution with [`crate::ExecError::Verification`].
+    #[default]
+    Reject,
+    /// Log the breaking changes and execute anyway.
+    Warn,
 }
 
 /// Runtime resource limits applied to the Wasm execution.
@@ -42,6 +76,52 @@ pub struct RuntimePolicy {
     pub per_call_timeout: Duration,
     pub max_attempts: u32,
     pub base_backoff: Duration,
+    /// Caps on host-side state a component can accumulate through host calls.
+    pub host_limits: HostLimits,
+    /// Network exposure granted to the WASI context.
+    pub network: NetworkProfile,
+    /// When set, each execution gets a fresh host temp directory preopened as
+    /// `/tmp`, removed after the call; usage above this many bytes fails the call.
+    pub scratch_quota_bytes: Option<u64>,
+    /// Files, directories, and links the scratch directory may hold under
+    /// [`Self::scratch_quota_bytes`]; creating more fails the call.
+    pub scratch_max_entries: u64,
+    /// Capture guest stdout: streamed to the callback of [`crate::exec_stream`],
+    /// otherwise attached to the result as chunked content under
+    /// [`crate::stream::STREAM_FIELD`].
+    pub stream_capture: Option<StreamCapture>,
+    /// Crash-loop protection for [`crate::Executor`]; disabled when `None`.
+    pub quarantine: Option<QuarantinePolicy>,
+    /// Result cache for deterministic tools on [`crate::Executor`]; disabled
+    /// when `None`.
+    pub result_cache: Option<ResultCachePolicy>,
+    /// Restricts which secret names each component may access per tenant.
+    /// When `None`, components can reach every secret of the call's tenant.
+    pub secrets_access: Option<SecretsAccessPolicy>,
+    /// Tool permissions every call must satisfy; the action is checked as the
+    /// tool name. Unrestricted when `None`.
+    pub tool_permissions: Option<Arc<PermissionGate>>,
+    /// Secrets each component needs, keyed by component identifier, e.g. the
+    /// `secret_requirements` from [`crate::describe::describe_tool`]. Calls to
+    /// a listed component first check that its tenant has every required key;
+    /// see [`crate::secrets_preflight`]. Unchecked when empty.
+    pub required_secrets: HashMap<String, Vec<SecretRequirement>>,
+    /// Attach each execution's [`crate::capabilities::CapabilityReport`] to
+    /// its result under `capabilities`.
+    pub capability_report: bool,
+    /// Leave each execution's [`crate::ExecutionCost`] on its result under
+    /// [`crate::cost::COST_FIELD`]. Costs reach [`ExecConfig::cost_sink`]
+    /// either way; results served from the result cache carry none.
+    pub cost_report: bool,
+    /// Adapter used to run `wasm32-wasip1` core modules as components; core
+    /// modules are rejected when `None` or without the `wasip1` feature.
+    pub preview1_adapter: Option<Preview1Adapter>,
+    /// Write a [`crate::debug_dump`] of every execution into a fresh
+    /// subdirectory of this directory, for replaying it later.
+    pub debug_dump: Option<PathBuf>,
+    /// Store and limits behind the `greentic:blobs` import; the import fails
+    /// with `blobs-unavailable` when `None`.
+    pub blobs: Option<BlobPolicy>,
 }
 
 impl Default for RuntimePolicy {
@@ -53,10 +133,200 @@ impl Default for RuntimePolicy {
             per_call_timeout: Duration::from_secs(10),
             max_attempts: 1,
             base_backoff: Duration::from_millis(100),
+            host_limits: HostLimits::default(),
+            network: NetworkProfile::default(),
+            scratch_quota_bytes: None,
+            scratch_max_entries: 4096,
+            stream_capture: None,
+            quarantine: None,
+            result_cache: None,
+            secrets_access: None,
+            tool_permissions: None,
+            required_secrets: HashMap::new(),
+            capability_report: false,
+            cost_report: false,
+            preview1_adapter: None,
+            debug_dump: None,
+            blobs: None,
+        }
+    }
+}
+
+/// The `wasi_snapshot_preview1` adapter module, e.g. the
+/// `wasi_snapshot_preview1.reactor.wasm` published with each wasmtime release.
+#[derive(Clone)]
+pub struct Preview1Adapter {
+    bytes: Arc<[u8]>,
+}
+
+impl Preview1Adapter {
+    pub fn new(bytes: impl Into<Arc<[u8]>>) -> Self {
+        Self {
+            bytes: bytes.into(),
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        std::fs::read(path).map(Self::new)
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl fmt::Debug for Preview1Adapter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Preview1Adapter")
+            .field("len", &self.bytes.len())
+            .finish()
+    }
+}
+
+/// When an [`crate::Executor`] stops calling a component that keeps crashing.
+///
+/// Traps, timeouts, and runner panics extend a per-digest failure streak; any
+/// call that completes, including one returning a tool error, resets it. Once
+/// the streak reaches `failure_threshold` the digest is quarantined for
+/// `cooldown`, during which calls fail fast with
+/// [`crate::ExecError::Quarantined`]. The first call after the cooldown is a
+/// probe: if it crashes too, the digest is quarantined again straight away.
+#[derive(Clone, Debug)]
+pub struct QuarantinePolicy {
+    pub failure_threshold: u32,
+    pub cooldown: Duration,
+}
+
+impl Default for QuarantinePolicy {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Which results an [`crate::Executor`] may serve from memory instead of
+/// executing the component again.
+///
+/// Only successful results are cached, keyed by artifact digest, tenant, tool,
+/// and arguments. Hits bypass instantiation, cost reporting, and
+/// [`crate::Executor::stats`]; they are counted in
+/// [`crate::Executor::result_cache_stats`] instead.
+#[derive(Clone, Debug)]
+pub struct ResultCachePolicy {
+    pub ttl: Duration,
+    pub max_entries: usize,
+    /// Results whose JSON encoding is larger than this are not stored.
+    pub max_entry_bytes: usize,
+    /// Per-component overrides of [`ComponentCaching::ReadOnly`].
+    pub components: HashMap<String, ComponentCaching>,
+}
+
+impl Default for ResultCachePolicy {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(60),
+            max_entries: 1024,
+            max_entry_bytes: 256 * 1024,
+            components: HashMap::new(),
+        }
+    }
+}
+
+/// How calls to one component are cached.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ComponentCaching {
+    /// Cache the tools the router annotates `read-only`. The catalog is listed
+    /// once per digest; components that are not routers, or whose catalog
+    /// cannot be listed, are never cached.
+    #[default]
+    ReadOnly,
+    /// Cache every tool, e.g. for pure converters without annotations.
+    Always,
+    /// Never cache, e.g. for tools that read the clock.
+    Never,
+}
+
+/// Socket-level network profile applied to each execution.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NetworkProfile {
+    /// Network access follows `ExecConfig::http_enabled`.
+    #[default]
+    Standard,
+    /// Never grant network or name lookup, and reject components that import
+    /// socket or outgoing HTTP interfaces before they are instantiated.
+    Hardened,
+}
+
+/// Per-execution limits on resources the host allocates on behalf of a component.
+///
+/// Wasm memory caps do not cover data the host buffers for the guest, so these
+/// limits bound HTTP bodies, KV values, and secrets handed across the boundary.
+#[derive(Clone, Debug)]
+pub struct HostLimits {
+    /// Maximum number of host resources (HTTP responses, KV entries, secrets) per execution.
+    pub max_resources: u32,
+    /// Maximum number of bytes buffered by the host across all host calls.
+    pub max_buffered_bytes: u64,
+    /// Maximum number of live entries in the store's resource table: WASI
+    /// descriptors, streams, pollables, and HTTP handles. A host call that
+    /// would add one more traps, and the call fails with
+    /// [`RunnerError::HostResourceLimit`](crate::RunnerError::HostResourceLimit)
+    /// for `table-entries`.
+    pub max_table_entries: u32,
+}
+
+impl Default for HostLimits {
+    fn default() -> Self {
+        Self {
+            max_resources: 1024,
+            max_buffered_bytes: 64 * 1024 * 1024,
+            max_table_entries: 4096,
         }
     }
 }
 
+/// Allowlist of secret names per component and tenant.
+///
+/// Secrets imports for names no grant covers fail with `secrets-forbidden`,
+/// so a compromised component cannot probe the rest of the tenant's secrets.
+#[derive(Clone, Debug, Default)]
+pub struct SecretsAccessPolicy {
+    pub grants: Vec<SecretsGrant>,
+}
+
+/// Secret names one component may access.
+#[derive(Clone, Debug)]
+pub struct SecretsGrant {
+    /// Component identifier as passed in [`crate::ExecRequest::component`].
+    pub component: String,
+    /// Tenant id the grant is limited to; `None` applies to every tenant.
+    pub tenant: Option<String>,
+    /// Secret names; an entry ending in `*` covers every name with that prefix.
+    pub names: Vec<String>,
+    /// Whether the names may also be written and deleted, not just read.
+    pub write: bool,
+}
+
+impl SecretsAccessPolicy {
+    /// Whether `component` may access `name` for `tenant`.
+    pub fn allows(&self, component: &str, tenant: &str, name: &str, write: bool) -> bool {
+        self.grants.iter().any(|grant| {
+            grant.component == component
+                && grant.tenant.as_deref().is_none_or(|only| only == tenant)
+                && (grant.write || !write)
+                && grant
+                    .names
+                    .iter()
+                    .any(|pattern| match pattern.strip_suffix('*') {
+                        Some(prefix) => name.starts_with(prefix),
+                        None => pattern == name,
+                    })
+        })
+    }
+}
+
 /// Host-facing secrets-store trait mirroring greentic:secrets/store@1.0.0.
 pub trait SecretsStore: Send + Sync {
     /// Read bytes for the scoped secret name.
@@ -73,6 +343,68 @@ pub trait SecretsStore: Send + Sync {
         let _ = (scope, name);
         Err("delete-not-implemented".into())
     }
+
+    /// Write a secret on behalf of a component, returning the new version for
+    /// stores that keep history. Defaults to an unversioned [`Self::write`].
+    fn write_revision(
+        &self,
+        scope: &TenantCtx,
+        name: &str,
+        bytes: &[u8],
+    ) -> Result<Option<u64>, String> {
+        self.write(scope, name, bytes).map(|()| None)
+    }
+
+    /// Delete a secret on behalf of a component, returning `true` when the
+    /// value was retained and can be restored. Defaults to a hard
+    /// [`Self::delete`].
+    fn soft_delete(&self, scope: &TenantCtx, name: &str) -> Result<bool, String> {
+        self.delete(scope, name).map(|()| false)
+    }
+
+    /// Receive the audit record of every write or delete a component attempts.
+    /// The host also logs each event under the `greentic_mcp_exec::secrets`
+    /// tracing target.
+    fn audit(&self, event: &SecretAuditEvent) {
+        let _ = event;
+    }
+}
+
+/// Kind of mutation recorded in a [`SecretAuditEvent`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecretAction {
+    Write,
+    Delete,
+    /// Delete that the store retained for recovery.
+    SoftDelete,
+}
+
+impl SecretAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecretAction::Write => "write",
+            SecretAction::Delete => "delete",
+            SecretAction::SoftDelete => "soft-delete",
+        }
+    }
+}
+
+/// Audit record of a component mutating a secret.
+#[derive(Clone, Debug)]
+pub struct SecretAuditEvent {
+    /// Component that made the call.
+    pub component: String,
+    pub tenant: TenantCtx,
+    pub name: String,
+    pub action: SecretAction,
+    /// Hex SHA-256 of the written value, so changes can be correlated without
+    /// logging the secret itself. `None` for deletes.
+    pub value_digest: Option<String>,
+    /// Version assigned by a versioning store.
+    pub version: Option<u64>,
+    /// Why the mutation was rejected, by the access policy or the store.
+    pub error: Option<String>,
+    pub at: SystemTime,
 }
 
 /// Shared secrets-store handle.
@@ -89,6 +421,10 @@ impl fmt::Debug for ExecConfig {
                 "secrets_store",
                 &self.secrets_store.as_ref().map(|_| "<dyn SecretsStore>"),
             )
+            .field(
+                "cost_sink",
+                &self.cost_sink.as_ref().map(|_| "<dyn CostSink>"),
+            )
             .finish()
     }
 }
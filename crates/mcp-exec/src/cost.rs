@@ -0,0 +1,238 @@
+//! Per-execution cost accounting for chargeback and budgeting.
+//!
+//! Every wasm call records the fuel it burned (when [`crate::RuntimePolicy::fuel`]
+//! is set), its wallclock time, and the bytes it moved through the runner-host
+//! HTTP import; native router calls record their wallclock time only. The
+//! runner carries the figures out of the call under
+//! [`COST_FIELD`], a reserved key that never overwrites the tool's own output.
+//! [`report`] takes them off the result again and, when
+//! [`crate::ExecConfig::cost_sink`] is set, hands them to the sink together with
+//! the tenant the call was made for. Only with
+//! [`crate::RuntimePolicy::cost_report`] set does the result keep them. Each call
+//! gets the full fuel budget; one that burns through it fails with
+//! [`RunnerError::FuelExhausted`].
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use greentic_types::TenantCtx;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use wasmtime::Store;
+
+use crate::ExecRequest;
+use crate::config::{ExecConfig, RuntimePolicy};
+use crate::error::RunnerError;
+
+/// Result key carrying an execution's [`ExecutionCost`].
+pub const COST_FIELD: &str = "_exec_cost";
+
+/// Resources consumed by a single execution.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionCost {
+    /// Fuel consumed, or `None` when fuel metering is disabled.
+    pub fuel: Option<u64>,
+    #[serde(rename = "wallclock_ms", with = "millis")]
+    pub wallclock: Duration,
+    /// Request bytes (headers and body) sent through host HTTP.
+    pub http_bytes_sent: u64,
+    /// Response body bytes received through host HTTP.
+    pub http_bytes_received: u64,
+}
+
+impl ExecutionCost {
+    /// Read the cost attached to an execution result under [`COST_FIELD`],
+    /// if any.
+    pub fn from_result(value: &Value) -> Option<Self> {
+        serde_json::from_value(value.get(COST_FIELD)?.clone()).ok()
+    }
+
+    fn add(&mut self, other: &ExecutionCost) {
+        self.fuel = match (self.fuel, other.fuel) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0).saturating_add(b.unwrap_or(0))),
+        };
+        self.wallclock = self.wallclock.saturating_add(other.wallclock);
+        self.http_bytes_sent = self.http_bytes_sent.saturating_add(other.http_bytes_sent);
+        self.http_bytes_received = self
+            .http_bytes_received
+            .saturating_add(other.http_bytes_received);
+    }
+}
+
+/// Receiver of per-execution costs, e.g. a metrics exporter or billing queue.
+pub trait CostSink: Send + Sync {
+    fn record(
+        &self,
+        tenant: Option<&TenantCtx>,
+        component: &str,
+        action: &str,
+        cost: &ExecutionCost,
+    );
+}
+
+/// Shared cost-sink handle.
+pub type DynCostSink = Arc<dyn CostSink>;
+
+/// Running totals for one tenant.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TenantCost {
+    pub calls: u64,
+    pub total: ExecutionCost,
+}
+
+/// In-memory [`CostSink`] accumulating totals per tenant id.
+///
+/// Calls made without a tenant are accounted under `None`.
+#[derive(Default)]
+pub struct CostLedger {
+    totals: Mutex<HashMap<Option<String>, TenantCost>>,
+}
+
+impl CostLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Totals keyed by tenant id.
+    pub fn snapshot(&self) -> HashMap<Option<String>, TenantCost> {
+        self.lock().clone()
+    }
+
+    /// Return the totals and start a new accounting period.
+    pub fn drain(&self) -> HashMap<Option<String>, TenantCost> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Option<String>, TenantCost>> {
+        self.totals
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl CostSink for CostLedger {
+    fn record(
+        &self,
+        tenant: Option<&TenantCtx>,
+        _component: &str,
+        _action: &str,
+        cost: &ExecutionCost,
+    ) {
+        let key = tenant.map(|tenant| tenant.tenant.0.clone());
+        let mut totals = self.lock();
+        let entry = totals.entry(key).or_default();
+        entry.calls += 1;
+        entry.total.add(cost);
+    }
+}
+
+/// Take the cost off a runner result and forward it to the configured sink,
+/// leaving it on the result only if [`RuntimePolicy::cost_report`] is set.
+///
+/// Results carrying a tool-reported error are charged like any other; calls
+/// that failed in the runtime have no cost attached and are not reported.
+pub(crate) fn report(cfg: &ExecConfig, req: &ExecRequest, result: &mut Result<Value, RunnerError>) {
+    let Ok(value) = result else {
+        return;
+    };
+    let Some(cost) = take(value) else {
+        return;
+    };
+    if let Some(sink) = &cfg.cost_sink {
+        sink.record(req.tenant.as_ref(), &req.component, &req.action, &cost);
+    }
+    if cfg.runtime.cost_report {
+        attach(value, &cost);
+    }
+}
+
+/// Remove the cost attached to `value`, returning it.
+pub(crate) fn take(value: &mut Value) -> Option<ExecutionCost> {
+    let cost = value.as_object_mut()?.remove(COST_FIELD)?;
+    serde_json::from_value(cost).ok()
+}
+
+/// Give `store` the full fuel budget of `runtime`, if metering is enabled.
+pub(crate) fn refuel<T>(store: &mut Store<T>, runtime: &RuntimePolicy) -> Result<(), RunnerError> {
+    if let Some(fuel) = runtime.fuel {
+        store.set_fuel(fuel)?;
+    }
+    Ok(())
+}
+
+/// Fuel burned since [`refuel`], or `None` when metering is disabled.
+pub(crate) fn fuel_used<T>(store: &Store<T>, runtime: &RuntimePolicy) -> Option<u64> {
+    runtime
+        .fuel
+        .zip(store.get_fuel().ok())
+        .map(|(budget, left)| budget.saturating_sub(left))
+}
+
+/// The error for a call on `store` that failed, if it failed by running out
+/// of fuel.
+pub(crate) fn fuel_exhausted<T>(store: &Store<T>, runtime: &RuntimePolicy) -> Option<RunnerError> {
+    let budget = runtime.fuel?;
+    (store.get_fuel().ok()? == 0).then_some(RunnerError::FuelExhausted { budget })
+}
+
+/// Attach `cost` to a result value under [`COST_FIELD`].
+pub(crate) fn attach(value: &mut Value, cost: &ExecutionCost) {
+    if let (Value::Object(map), Ok(cost)) = (value, serde_json::to_value(cost)) {
+        map.insert(COST_FIELD.to_string(), cost);
+    }
+}
+
+mod millis {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        value: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(u64::try_from(value.as_millis()).unwrap_or(u64::MAX))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn costs_round_trip_through_results_and_ledger() {
+        let cost = ExecutionCost {
+            fuel: Some(1_500),
+            wallclock: Duration::from_millis(42),
+            http_bytes_sent: 10,
+            http_bytes_received: 200,
+        };
+        let mut value = json!({"ok": true, "cost": "the tool's own"});
+        attach(&mut value, &cost);
+        assert_eq!(value[COST_FIELD]["wallclock_ms"], json!(42));
+        assert_eq!(value["cost"], json!("the tool's own"));
+        assert_eq!(ExecutionCost::from_result(&value), Some(cost));
+        assert_eq!(take(&mut value), Some(cost));
+        assert_eq!(value, json!({"ok": true, "cost": "the tool's own"}));
+
+        let ledger = CostLedger::new();
+        ledger.record(None, "weather", "forecast", &cost);
+        ledger.record(None, "weather", "forecast", &ExecutionCost::default());
+
+        let totals = ledger.drain();
+        let anonymous = totals[&None];
+        assert_eq!(anonymous.calls, 2);
+        assert_eq!(anonymous.total.fuel, Some(1_500));
+        assert_eq!(anonymous.total.http_bytes_received, 200);
+        assert!(ledger.snapshot().is_empty());
+    }
+}
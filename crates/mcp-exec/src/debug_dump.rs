@@ -0,0 +1,293 @@
+//! Self-contained dumps of single executions, for reproducible bug reports.
+//!
+//! With [`crate::RuntimePolicy::debug_dump`] set, every execution writes a
+//! directory holding the artifact it ran ([`COMPONENT_FILE`]) and a
+//! [`DUMP_FILE`] with the artifact digest, the request, every runner-host and
+//! secrets host call the guest made together with its response, and the final
+//! result or error. [`replay`] runs the artifact again with those host calls
+//! answered from the transcript instead of the network or the secrets store,
+//! and reports whether the outcome matches. WASI imports (clocks, random,
+//! `wasi:http`) are not transcribed and run live during a replay.
+//!
+//! Secret values never reach a dump: recorded secret reads answer with a
+//! [`REDACTED_SECRET`] error on replay, written values are kept as their
+//! SHA-256 digest, and credential-carrying HTTP header values are masked.
+
+use std::borrow::Cow;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::config::RuntimePolicy;
+use crate::error::RunnerError;
+use crate::resolve::ResolvedArtifact;
+use crate::runner;
+use crate::ExecRequest;
+
+/// Transcript and outcome of the execution, inside a dump directory.
+pub const DUMP_FILE: &str = "dump.json";
+/// The executed artifact, inside a dump directory.
+pub const COMPONENT_FILE: &str = "component.wasm";
+/// Wire error replayed in place of a recorded secret value.
+pub const REDACTED_SECRET: &str = "secrets-redacted:secret values are not recorded";
+
+/// Header names whose values are masked in recorded HTTP requests.
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+];
+
+/// Distinguishes dumps written within the same millisecond.
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// One host import invocation made by the guest.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HostCall {
+    pub interface: String,
+    pub function: String,
+    pub request: Value,
+    pub response: Value,
+}
+
+/// Final outcome of a dumped execution.
+///
+/// Results are kept without the per-run cost and `capabilities` keys so
+/// outcomes of different runs compare equal.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DumpOutcome {
+    Result(Value),
+    Error(String),
+}
+
+impl DumpOutcome {
+    pub fn of(result: &Result<Value, RunnerError>) -> Self {
+        match result {
+            Ok(value) => {
+                let mut value = value.clone();
+                if let Some(map) = value.as_object_mut() {
+                    map.remove(crate::cost::COST_FIELD);
+                    map.remove("capabilities");
+                }
+                Self::Result(value)
+            }
+            Err(err) => Self::Error(err.to_string()),
+        }
+    }
+}
+
+/// Contents of [`DUMP_FILE`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DebugDump {
+    pub component: String,
+    /// Hex SHA-256 digest of [`COMPONENT_FILE`].
+    pub digest: String,
+    pub action: String,
+    pub args: Value,
+    /// Tenant of the original call; informational, replays run without one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(default)]
+    pub host_calls: Vec<HostCall>,
+    pub outcome: DumpOutcome,
+}
+
+impl DebugDump {
+    /// Write the dump and the executed artifact into `dir`, creating it.
+    pub fn write(&self, dir: &Path, component: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(COMPONENT_FILE), component)?;
+        fs::write(dir.join(DUMP_FILE), serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Read a dump and its artifact back from `dir`.
+    pub fn read(dir: &Path) -> io::Result<(Self, Vec<u8>)> {
+        let dump = serde_json::from_slice(&fs::read(dir.join(DUMP_FILE))?)?;
+        Ok((dump, fs::read(dir.join(COMPONENT_FILE))?))
+    }
+}
+
+/// Outcome of re-executing a dump.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub recorded: DumpOutcome,
+    pub replayed: DumpOutcome,
+    /// First point where the guest's host calls departed from the transcript.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub divergence: Option<String>,
+}
+
+impl ReplayReport {
+    /// Whether the replay made the recorded host calls and ended the same way.
+    pub fn reproduced(&self) -> bool {
+        self.divergence.is_none() && self.recorded == self.replayed
+    }
+}
+
+/// Re-execute the dump in `dir` under `runtime`, answering host calls from
+/// its transcript.
+///
+/// HTTP and secrets stay disabled during the replay, so a guest departing from
+/// the transcript gets errors instead of reaching live systems.
+pub fn replay(dir: &Path, runtime: &RuntimePolicy) -> Result<ReplayReport, RunnerError> {
+    let (dump, component) = DebugDump::read(dir).map_err(|err| {
+        RunnerError::Internal(format!("reading debug dump {}: {err}", dir.display()))
+    })?;
+    let digest = hex::encode(Sha256::digest(&component));
+    if !digest.eq_ignore_ascii_case(dump.digest.trim_start_matches("sha256:")) {
+        return Err(RunnerError::Internal(format!(
+            "{COMPONENT_FILE} in {} does not match the recorded digest {}",
+            dir.display(),
+            dump.digest
+        )));
+    }
+
+    // Like HTTP and secrets, blobs are not reachable from a replay.
+    let runtime = RuntimePolicy {
+        debug_dump: None,
+        blobs: None,
+        ..runtime.clone()
+    };
+    let request = ExecRequest::new(dump.component, dump.action, dump.args);
+    let (result, divergence) = runner::replay(&component, &request, &runtime, dump.host_calls);
+    Ok(ReplayReport {
+        recorded: dump.outcome,
+        replayed: DumpOutcome::of(&result),
+        divergence,
+    })
+}
+
+/// Dump an execution of `artifact` when `runtime` asks for it.
+///
+/// Failing to write the dump is logged and never fails the execution.
+pub(crate) fn capture(
+    runtime: &RuntimePolicy,
+    artifact: &ResolvedArtifact,
+    request: &ExecRequest,
+    host_calls: Vec<HostCall>,
+    result: &Result<Value, RunnerError>,
+) {
+    let Some(root) = &runtime.debug_dump else {
+        return;
+    };
+    let dump = DebugDump {
+        component: request.component.clone(),
+        digest: artifact.digest.clone(),
+        action: request.action.clone(),
+        // Unparseable raw arguments are recorded as null.
+        args: request
+            .args_value()
+            .map(Cow::into_owned)
+            .unwrap_or(Value::Null),
+        tenant: request
+            .tenant
+            .as_ref()
+            .map(|tenant| tenant.tenant.0.clone()),
+        host_calls,
+        outcome: DumpOutcome::of(result),
+    };
+    let dir = execution_dir(root, &request.component);
+    if let Err(err) = dump.write(&dir, &artifact.bytes) {
+        warn!(component = %request.component, dir = %dir.display(), %err, "writing debug dump failed");
+    }
+}
+
+/// Fresh directory under `root` for one execution of `component`.
+fn execution_dir(root: &Path, component: &str) -> PathBuf {
+    let name: String = component
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis());
+    let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    root.join(format!("{name}-{millis}-{sequence}"))
+}
+
+/// `headers` with the values of credential-carrying headers masked.
+pub(crate) fn mask_headers(headers: &[String]) -> Vec<String> {
+    headers
+        .iter()
+        .map(|header| match header.split_once(':') {
+            Some((name, _))
+                if SENSITIVE_HEADERS
+                    .iter()
+                    .any(|sensitive| name.trim().eq_ignore_ascii_case(sensitive)) =>
+            {
+                format!("{name}: <redacted>")
+            }
+            _ => header.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn dumps_round_trip_and_replay_mock_artifacts() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let component = br#"{"_mock_mcp_exec": true, "responses": {"ping": {"pong": 1}}}"#;
+        let dump = DebugDump {
+            component: "mock".into(),
+            digest: hex::encode(Sha256::digest(component)),
+            action: "ping".into(),
+            args: json!({}),
+            tenant: Some("acme".into()),
+            host_calls: vec![HostCall {
+                interface: "runner-host-kv".into(),
+                function: "get".into(),
+                request: json!({"ns": "a", "key": "b"}),
+                response: Value::Null,
+            }],
+            outcome: DumpOutcome::of(&Ok(
+                json!({"pong": 1, crate::cost::COST_FIELD: {"fuel": 1}}),
+            )),
+        };
+        dump.write(dir.path(), component).expect("write");
+        let (read, bytes) = DebugDump::read(dir.path()).expect("read");
+        assert_eq!(bytes, component);
+        assert_eq!(read.host_calls, dump.host_calls);
+        assert_eq!(read.outcome, DumpOutcome::Result(json!({"pong": 1})));
+
+        let report = replay(dir.path(), &RuntimePolicy::default()).expect("replay");
+        assert_eq!(report.replayed, report.recorded);
+
+        fs::write(dir.path().join(COMPONENT_FILE), b"tampered").expect("write");
+        assert!(replay(dir.path(), &RuntimePolicy::default()).is_err());
+    }
+
+    #[test]
+    fn credential_headers_are_masked() {
+        let headers = vec![
+            "Authorization: Bearer abc".to_string(),
+            "accept: application/json".to_string(),
+        ];
+        assert_eq!(
+            mask_headers(&headers),
+            vec![
+                "Authorization: <redacted>".to_string(),
+                "accept: application/json".to_string()
+            ]
+        );
+    }
+}
@@ -48,12 +48,7 @@ pub fn describe_tool(name: &str, cfg: &ExecConfig) -> Result<ToolDescribe> {
     }
 
     fn try_action(name: &str, action: &str, cfg: &ExecConfig) -> Result<Maybe<Value>> {
-        let req = ExecRequest {
-            component: name.to_string(),
-            action: action.to_string(),
-            args: Value::Object(Default::default()),
-            tenant: None,
-        };
+        let req = ExecRequest::new(name, action, Value::Object(Default::default()));
 
         match exec(req, cfg) {
             Ok(v) => Ok(Maybe::Data(v)),
@@ -0,0 +1,74 @@
+//! Background epoch ticker enforcing wallclock timeouts on synchronous calls.
+//!
+//! Engines built by [`crate::runner::build_engine`] are registered with one
+//! process-wide thread that advances their epoch every [`EPOCH_TICK`]. Calls
+//! set an epoch deadline covering [`RuntimePolicy::wallclock_timeout`], so a
+//! guest that runs past it is interrupted with a trap instead of being caught
+//! only after it returns. Engines are held weakly and forgotten once dropped.
+//! Async engines are registered too; their stores yield at every tick instead
+//! of trapping, since a spinning guest never returns to the executor to
+//! advance the epoch itself.
+//!
+//! [`RuntimePolicy::wallclock_timeout`]: crate::RuntimePolicy::wallclock_timeout
+
+use std::sync::{Mutex, MutexGuard, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use wasmtime::{Engine, EngineWeak};
+
+use crate::error::RunnerError;
+
+/// Interval between epoch increments.
+pub(crate) const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+static ENGINES: Mutex<Vec<EngineWeak>> = Mutex::new(Vec::new());
+static TICKER: Once = Once::new();
+
+/// Advance the epoch of `engine` every [`EPOCH_TICK`] for as long as it lives.
+pub(crate) fn register(engine: &Engine) {
+    engines().push(engine.weak());
+    TICKER.call_once(|| {
+        thread::Builder::new()
+            .name("mcp-exec-epoch".into())
+            .spawn(tick)
+            .expect("spawn epoch ticker");
+    });
+}
+
+fn tick() {
+    loop {
+        thread::sleep(EPOCH_TICK);
+        engines().retain(|engine| match engine.upgrade() {
+            Some(engine) => {
+                engine.increment_epoch();
+                true
+            }
+            None => false,
+        });
+    }
+}
+
+fn engines() -> MutexGuard<'static, Vec<EngineWeak>> {
+    ENGINES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Epoch deadline, in ticks from now, that expires no earlier than `timeout`.
+pub(crate) fn deadline(timeout: Duration) -> u64 {
+    // One extra tick, since the next one may come right after the deadline is set.
+    let ticks = timeout.as_nanos().div_ceil(EPOCH_TICK.as_nanos()) + 1;
+    u64::try_from(ticks).map_or(u64::MAX / 2, |ticks| ticks.min(u64::MAX / 2))
+}
+
+/// The error for a guest call started at `started` that failed with
+/// `message`: a timeout if it ran into its wallclock deadline.
+pub(crate) fn call_error(started: Instant, timeout: Duration, message: String) -> RunnerError {
+    let elapsed = started.elapsed();
+    if elapsed >= timeout {
+        RunnerError::Timeout { elapsed }
+    } else {
+        RunnerError::Internal(message)
+    }
+}
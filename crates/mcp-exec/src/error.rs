@@ -36,6 +36,37 @@ pub enum ExecError {
         code: String,
         payload: Value,
     },
+    #[error("call to `{component}` refused by tool permissions: {source}")]
+    PermissionDenied {
+        component: String,
+        #[source]
+        source: crate::permissions::PermissionDenied,
+    },
+    #[error("call to `{component}` action `{action}` denied by an exec hook: {reason}")]
+    Denied {
+        component: String,
+        action: String,
+        reason: String,
+    },
+    #[error(
+        "component `{component}` lacks required secrets for tenant `{}`: {}",
+        .tenant.as_deref().unwrap_or("<none>"),
+        .missing.join(", ")
+    )]
+    MissingSecrets {
+        component: String,
+        /// Tenant of the call, `None` when it had no tenant context.
+        tenant: Option<String>,
+        missing: Vec<String>,
+    },
+    #[error(
+        "component `{component}` ({digest}) is quarantined after repeated crashes; retry after {retry_after:?}"
+    )]
+    Quarantined {
+        component: String,
+        digest: String,
+        retry_after: Duration,
+    },
 }
 
 impl ExecError {
@@ -96,14 +127,26 @@ pub enum ResolveError {
 pub enum VerificationError {
     #[error("digest mismatch: expected {expected}, got {actual}")]
     DigestMismatch { expected: String, actual: String },
+    #[error("digest pinned by the request does not match: expected {expected}, got {actual}")]
+    PinnedDigestMismatch { expected: String, actual: String },
+    #[error("digest changed since first use: recorded {recorded}, got {actual}")]
+    TofuMismatch { recorded: String, actual: String },
+    #[error("trust-on-first-use store error: {0}")]
+    TofuStore(String),
     #[error("artifact is unsigned and policy does not allow it")]
     UnsignedRejected,
+    #[error("tool catalog deviates incompatibly from the recorded snapshot: {}", .0.summary())]
+    IncompatibleCatalog(crate::catalog_diff::CatalogDiff),
 }
 
 #[derive(Debug, Error)]
 pub enum RunnerError {
     #[error("wasm execution timed out after {elapsed:?}")]
     Timeout { elapsed: Duration },
+    #[error("wasm execution ran out of fuel (budget {budget})")]
+    FuelExhausted { budget: u64 },
+    #[error("wasm memory growth to {requested} bytes exceeds the limit of {limit} bytes")]
+    MemoryLimit { limit: u64, requested: u64 },
     #[error("wasmtime error: {0}")]
     Wasmtime(#[from] wasmtime::Error),
     #[error("serde error: {0}")]
@@ -114,6 +157,14 @@ pub enum RunnerError {
     ToolTransient { component: String, message: String },
     #[error("internal runner error: {0}")]
     Internal(String),
+    #[error("component imports are not satisfied by the host: {0}")]
+    MissingImports(crate::preflight::ImportReport),
+    #[error("sandbox policy violation: {0}")]
+    PolicyViolation(String),
+    #[error("host resource limit exceeded for {resource} (limit {limit})")]
+    HostResourceLimit { resource: String, limit: u64 },
+    #[error("core wasm module cannot run as a component: {0}")]
+    CoreModule(String),
     #[error("runner is not implemented for this configuration")]
     NotImplemented,
 }
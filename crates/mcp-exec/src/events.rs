@@ -0,0 +1,116 @@
+//! Live lifecycle events of the calls an [`Executor`](crate::Executor) serves.
+//!
+//! [`Executor::subscribe`](crate::Executor::subscribe) hands out a receiver on
+//! a broadcast channel carrying one [`ExecEvent`] per step of every call:
+//! resolution, verification, compilation, the start of the guest, each host
+//! call it makes, and the final outcome. Events are meant for dashboards and
+//! debugging UIs, not accounting: they are only produced while someone is
+//! subscribed, and a receiver that falls more than [`EVENT_CAPACITY`] events
+//! behind skips the oldest ones. Statistics and costs keep going through
+//! [`Executor::stats`](crate::Executor::stats) and
+//! [`ExecConfig::cost_sink`](crate::ExecConfig::cost_sink).
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+
+/// Events a receiver can lag behind before it starts missing them.
+pub const EVENT_CAPACITY: usize = 1024;
+
+/// One step of one call.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecEvent {
+    /// Identifies the call, so events of concurrent calls can be told apart.
+    pub call_id: u64,
+    pub component: String,
+    pub kind: ExecEventKind,
+}
+
+/// What happened at an [`ExecEvent`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExecEventKind {
+    /// The artifact was located in the tool store.
+    Resolved { digest: String },
+    /// The artifact passed the verification policy.
+    Verified { digest: String },
+    /// The artifact was compiled, which happens once per digest until the
+    /// cache is cleared.
+    Compiled { digest: String, elapsed: Duration },
+    /// The guest, or native router, started serving `action`. Calls answered
+    /// from the result cache finish without starting.
+    Started { action: String, digest: String },
+    /// The guest made a host call, which took `elapsed` to answer.
+    HostCall {
+        interface: String,
+        function: String,
+        elapsed: Duration,
+    },
+    /// The call succeeded, `elapsed` after it was admitted.
+    Finished { action: String, elapsed: Duration },
+    /// The call failed at any step, `elapsed` after it was admitted.
+    Failed {
+        action: String,
+        elapsed: Duration,
+        error: String,
+    },
+}
+
+/// Sending side of an executor's events.
+pub(crate) struct EventBus {
+    sender: broadcast::Sender<ExecEvent>,
+    next_call: AtomicU64,
+}
+
+impl EventBus {
+    pub(crate) fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CAPACITY);
+        Self {
+            sender,
+            next_call: AtomicU64::new(1),
+        }
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<ExecEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Events of a new call to `component`, or `None` while nobody listens.
+    pub(crate) fn call(&self, component: &str) -> Option<CallEvents> {
+        if self.sender.receiver_count() == 0 {
+            return None;
+        }
+        Some(CallEvents {
+            sender: self.sender.clone(),
+            call_id: self.next_call.fetch_add(1, Ordering::Relaxed),
+            component: Arc::from(component),
+        })
+    }
+}
+
+/// Emits the events of one call; cloned into the store that serves it.
+#[derive(Clone)]
+pub(crate) struct CallEvents {
+    sender: broadcast::Sender<ExecEvent>,
+    call_id: u64,
+    component: Arc<str>,
+}
+
+impl CallEvents {
+    pub(crate) fn emit(&self, kind: ExecEventKind) {
+        // Sending only fails once every receiver is gone.
+        let _ = self.sender.send(ExecEvent {
+            call_id: self.call_id,
+            component: self.component.to_string(),
+            kind,
+        });
+    }
+}
+
+/// Emit the event built by `kind` if the call has listeners.
+pub(crate) fn emit(events: Option<&CallEvents>, kind: impl FnOnce() -> ExecEventKind) {
+    if let Some(events) = events {
+        events.emit(kind());
+    }
+}
@@ -0,0 +1,1076 @@
+//! Long-lived executor that owns the engine, linker, caches, worker threads,
+//! and statistics shared by every request it serves.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+use tokio::sync::broadcast;
+use wasmtime::component::{InstancePre, Linker};
+use wasmtime::{Engine, Module};
+
+use crate::capabilities::CapabilityReport;
+use crate::catalog_gate;
+use crate::config::{ComponentCaching, ExecConfig, RuntimePolicy};
+use crate::cost;
+use crate::debug_dump;
+use crate::error::{ExecError, RunnerError};
+use crate::events::{self, CallEvents, EventBus, ExecEvent, ExecEventKind};
+use crate::hooks;
+use crate::module_runner;
+use crate::native::{self, NativeRouter};
+use crate::parallel::{self, ParallelReport};
+use crate::preview1;
+use crate::quarantine::QuarantineTracker;
+use crate::result_cache::{self, ResultCache, ResultCacheStats};
+use crate::router::{Tool, ToolError};
+use crate::runner::{self, StoreState, WorkerPool};
+use crate::secrets_preflight;
+use crate::stats::{ComponentStats, StatsRecorder};
+use crate::transform;
+use crate::verify::VerifiedArtifact;
+use crate::{
+    ExecRequest, check_permissions, finish, resolve_and_verify, resolve_artifact, verify_artifact,
+};
+
+/// Service-style entry point for embedding the executor.
+///
+/// Unlike [`crate::exec`], which rebuilds the engine and linker on every call,
+/// an `Executor` is created once and reused: components are compiled and
+/// pre-linked once per digest, calls run on a fixed worker pool, and
+/// per-component statistics accumulate across requests. Native routers
+/// registered with [`Executor::register_native`] are served alongside wasm
+/// components. With [`crate::RuntimePolicy::quarantine`] set, components that
+/// keep crashing are quarantined rather than instantiated again, and with
+/// [`crate::RuntimePolicy::result_cache`] set, repeated calls of deterministic
+/// tools are answered from memory. [`Executor::subscribe`] streams the
+/// lifecycle of every call as [`ExecEvent`]s.
+pub struct Executor {
+    cfg: ExecConfig,
+    engine: Engine,
+    linker: Linker<StoreState>,
+    prepared: Mutex<HashMap<String, InstancePre<StoreState>>>,
+    /// Compiled legacy core modules, keyed by digest; see [`crate::module_runner`].
+    modules: Mutex<HashMap<String, Module>>,
+    /// `component@digest` pairs that passed the catalog gate.
+    gated: Mutex<HashSet<String>>,
+    workers: WorkerPool,
+    stats: StatsRecorder,
+    quarantine: Option<QuarantineTracker>,
+    results: Option<ResultCache>,
+    natives: RwLock<HashMap<String, Arc<dyn NativeRouter>>>,
+    events: EventBus,
+}
+
+impl Executor {
+    /// Create an executor with one worker per available CPU.
+    pub fn new(cfg: ExecConfig) -> Result<Self, RunnerError> {
+        let workers = thread::available_parallelism().map_or(1, usize::from);
+        Self::with_workers(cfg, workers)
+    }
+
+    /// Create an executor with a fixed number of worker threads.
+    pub fn with_workers(cfg: ExecConfig, workers: usize) -> Result<Self, RunnerError> {
+        let engine = runner::build_engine(&cfg.runtime)?;
+        let linker = runner::build_linker(&engine)?;
+        let quarantine = cfg.runtime.quarantine.clone().map(QuarantineTracker::new);
+        let results = cfg.runtime.result_cache.clone().map(ResultCache::new);
+        Ok(Self {
+            cfg,
+            engine,
+            linker,
+            prepared: Mutex::new(HashMap::new()),
+            modules: Mutex::new(HashMap::new()),
+            gated: Mutex::new(HashSet::new()),
+            workers: WorkerPool::new(workers),
+            stats: StatsRecorder::default(),
+            quarantine,
+            results,
+            natives: RwLock::new(HashMap::new()),
+            events: EventBus::new(),
+        })
+    }
+
+    /// Configuration this executor was created with.
+    pub fn config(&self) -> &ExecConfig {
+        &self.cfg
+    }
+
+    /// Execute a request with the executor's configuration.
+    pub fn exec(&self, req: ExecRequest) -> Result<Value, ExecError> {
+        transform::around(self.cfg.transforms.as_ref(), req, |req| {
+            hooks::around(self.cfg.hooks.as_ref(), req, |req| {
+                self.exec_transformed(req)
+            })
+        })
+    }
+
+    /// Receive the [`ExecEvent`]s of calls made from now on.
+    ///
+    /// Events are only produced while at least one receiver exists. A receiver
+    /// that falls more than [`events::EVENT_CAPACITY`] events behind gets
+    /// [`broadcast::error::RecvError::Lagged`] and skips the oldest ones.
+    pub fn subscribe(&self) -> broadcast::Receiver<ExecEvent> {
+        self.events.subscribe()
+    }
+
+    fn exec_transformed(&self, req: ExecRequest) -> Result<Value, ExecError> {
+        let Some(events) = self.events.call(&req.component) else {
+            return self.exec_observed(req, None);
+        };
+        let (action, started) = (req.action.clone(), Instant::now());
+        let result = self.exec_observed(req, Some(&events));
+        let elapsed = started.elapsed();
+        events.emit(match &result {
+            Ok(_) => ExecEventKind::Finished { action, elapsed },
+            Err(err) => ExecEventKind::Failed {
+                action,
+                elapsed,
+                error: err.to_string(),
+            },
+        });
+        result
+    }
+
+    fn exec_observed(
+        &self,
+        req: ExecRequest,
+        events: Option<&CallEvents>,
+    ) -> Result<Value, ExecError> {
+        check_permissions(&req, &self.cfg)?;
+        secrets_preflight::check(&req, &self.cfg)?;
+        if let Some(router) = self.native(&req.component) {
+            let key = format!("native:{}", req.component);
+            let cache_key = self.cache_key(&req, &key, || router.list_tools().ok());
+            if let Some(hit) = self.cached(cache_key.as_deref()) {
+                return Ok(hit);
+            }
+            events::emit(events, || ExecEventKind::Started {
+                action: req.action.clone(),
+                digest: key.clone(),
+            });
+            let started = Instant::now();
+            let mut result = req.args_json().map_err(RunnerError::from).and_then(|args| {
+                let (action, args) = (req.action.clone(), args.into_owned());
+                self.workers
+                    .run(req.priority, self.cfg.runtime.per_call_timeout, move || {
+                        native::call(router.as_ref(), &action, &args)
+                    })
+            });
+            cost::report(&self.cfg, &req, &mut result);
+            let result = finish(req.clone(), result);
+            self.stats
+                .record_call(&key, &req.component, started.elapsed(), result.is_ok());
+            self.store_result(cache_key, &result);
+            return result;
+        }
+
+        let resolved = resolve_artifact(&req, &self.cfg)?;
+        events::emit(events, || ExecEventKind::Resolved {
+            digest: resolved.digest.clone(),
+        });
+        let verified = verify_artifact(&req, resolved, &self.cfg)?;
+        let digest = verified.resolved.digest.clone();
+        events::emit(events, || ExecEventKind::Verified {
+            digest: digest.clone(),
+        });
+        let cache_key = self.cache_key(&req, &digest, || self.list_tools(&verified, events));
+        if let Some(hit) = self.cached(cache_key.as_deref()) {
+            return Ok(hit);
+        }
+        if let Some(retry_after) = self
+            .quarantine
+            .as_ref()
+            .and_then(|quarantine| quarantine.retry_after(&digest))
+        {
+            return Err(ExecError::Quarantined {
+                component: req.component.clone(),
+                digest,
+                retry_after,
+            });
+        }
+        self.check_catalog(&req.component, &verified, events)?;
+
+        let started = Instant::now();
+        let mut result = self.run(&req, &verified, events);
+        if let Some(quarantine) = &self.quarantine {
+            quarantine.record(&digest, &result);
+        }
+        cost::report(&self.cfg, &req, &mut result);
+        let result = finish(req.clone(), result);
+        self.stats
+            .record_call(&digest, &req.component, started.elapsed(), result.is_ok());
+        self.store_result(cache_key, &result);
+        result
+    }
+
+    /// Execute independent requests concurrently, at most `max_concurrency`
+    /// at a time, for fan-out patterns such as querying several sources.
+    ///
+    /// Each request goes through [`Executor::exec`] and gets its own entry in
+    /// the report, in request order, so one failing call does not affect the
+    /// others. Calls still share the executor's worker pool.
+    pub fn call_parallel(
+        &self,
+        requests: Vec<ExecRequest>,
+        max_concurrency: usize,
+    ) -> ParallelReport {
+        parallel::run(requests, max_concurrency, |req| self.exec(req))
+    }
+
+    /// Serve requests for `component` from an in-host router instead of wasm.
+    ///
+    /// Native routers are trusted: no resolution, digest verification, or
+    /// sandboxing applies to them.
+    pub fn register_native(&self, component: impl Into<String>, router: Arc<dyn NativeRouter>) {
+        self.natives
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(component.into(), router);
+    }
+
+    /// Tools exposed by the native router registered as `component`.
+    pub fn native_tools(&self, component: &str) -> Option<Result<Vec<Tool>, ToolError>> {
+        self.native(component).map(|router| router.list_tools())
+    }
+
+    /// Snapshot of rolling statistics keyed by component digest; native
+    /// routers are keyed as `native:<component>`.
+    pub fn stats(&self) -> HashMap<String, ComponentStats> {
+        self.stats.snapshot()
+    }
+
+    /// Quarantined component digests and the time left on each cooldown.
+    pub fn quarantined(&self) -> HashMap<String, Duration> {
+        self.quarantine
+            .as_ref()
+            .map(QuarantineTracker::snapshot)
+            .unwrap_or_default()
+    }
+
+    /// Release every quarantined component and reset failure streaks.
+    pub fn lift_quarantine(&self) {
+        if let Some(quarantine) = &self.quarantine {
+            quarantine.clear();
+        }
+    }
+
+    /// Counters of the result cache, if [`crate::RuntimePolicy::result_cache`]
+    /// is set.
+    pub fn result_cache_stats(&self) -> Option<ResultCacheStats> {
+        self.results.as_ref().map(ResultCache::stats)
+    }
+
+    /// Interfaces `component` imports and the capabilities a call to it would
+    /// be granted under this executor's configuration, without running it.
+    pub fn inspect_capabilities(&self, component: &str) -> Result<CapabilityReport, ExecError> {
+        let req = ExecRequest::new(component, "", Value::Null);
+        let verified = resolve_and_verify(&req, &self.cfg)?;
+        let pre = self
+            .prepare(&verified, None)
+            .map_err(|err| ExecError::runner(component, err))?;
+        runner::planned_capabilities(
+            &self.engine,
+            pre.component(),
+            &self.cfg.runtime,
+            self.cfg.http_enabled,
+            self.cfg.secrets_store.clone(),
+        )
+        .map_err(|err| ExecError::runner(component, err))
+    }
+
+    /// Drop cached compiled components and results, e.g. after tools were
+    /// republished.
+    pub fn clear_cache(&self) {
+        self.lock_prepared().clear();
+        self.lock_modules().clear();
+        self.lock_gated().clear();
+        if let Some(results) = &self.results {
+            results.clear();
+        }
+    }
+
+    /// Result-cache key for `req` when its result may be cached, listing the
+    /// catalog of `digest` first if eligibility depends on its annotations.
+    fn cache_key(
+        &self,
+        req: &ExecRequest,
+        digest: &str,
+        list_tools: impl FnOnce() -> Option<Vec<Tool>>,
+    ) -> Option<String> {
+        let results = self.results.as_ref()?;
+        if results.mode(&req.component) == ComponentCaching::ReadOnly
+            && results.needs_catalog(digest)
+        {
+            results.record_catalog(digest, &list_tools().unwrap_or_default());
+        }
+        if !results.eligible(&req.component, digest, &req.action) {
+            return None;
+        }
+        // Arguments that do not parse are never cached.
+        let args = req.args_value().ok()?;
+        let tenant = req.tenant.as_ref().map(|tenant| tenant.tenant.0.as_str());
+        Some(result_cache::key(digest, tenant, &req.action, &args))
+    }
+
+    fn cached(&self, key: Option<&str>) -> Option<Value> {
+        self.results.as_ref()?.get(key?)
+    }
+
+    /// Cache a successful result, without the cost of the call that produced
+    /// it: hits cost nothing and are not charged.
+    fn store_result(&self, key: Option<String>, result: &Result<Value, ExecError>) {
+        if let (Some(results), Some(key), Ok(value)) = (&self.results, key, result) {
+            let mut value = value.clone();
+            cost::take(&mut value);
+            results.insert(key, &value);
+        }
+    }
+
+    /// Router tools of a wasm artifact, listed from a throwaway instance.
+    fn list_tools(
+        &self,
+        verified: &VerifiedArtifact,
+        events: Option<&CallEvents>,
+    ) -> Option<Vec<Tool>> {
+        let pre = self.prepare(verified, events).ok()?;
+        runner::instantiate(&self.engine, &pre, &self.cfg.runtime, false, None, None)
+            .and_then(|ready| ready.list_tools())
+            .ok()
+            .flatten()
+    }
+
+    fn run(
+        &self,
+        req: &ExecRequest,
+        verified: &VerifiedArtifact,
+        events: Option<&CallEvents>,
+    ) -> Result<Value, RunnerError> {
+        let start = || {
+            events::emit(events, || ExecEventKind::Started {
+                action: req.action.clone(),
+                digest: verified.resolved.digest.clone(),
+            })
+        };
+        if let Some(module) = self.legacy_module(verified, events) {
+            start();
+            let engine = self.engine.clone();
+            let request = req.clone();
+            let runtime = self.worker_runtime();
+            let result =
+                self.workers
+                    .run(req.priority, self.cfg.runtime.per_call_timeout, move || {
+                        module_runner::call(&engine, &module, &request, &runtime)
+                    });
+            debug_dump::capture(
+                &self.cfg.runtime,
+                &verified.resolved,
+                req,
+                Vec::new(),
+                &result,
+            );
+            return result;
+        }
+        let pre = match self.prepare(verified, events) {
+            Ok(pre) => pre,
+            Err(err) => {
+                let bytes = verified.resolved.bytes.as_ref();
+                start();
+                return runner::try_mock_json(bytes, &req.action).unwrap_or(Err(err));
+            }
+        };
+        start();
+
+        let engine = self.engine.clone();
+        let request = req.clone();
+        let runtime = self.worker_runtime();
+        let http_enabled = self.cfg.http_enabled;
+        let secrets_store = self.cfg.secrets_store.clone();
+        let artifact = verified.resolved.clone();
+        let events = events.cloned();
+        self.workers
+            .run(req.priority, self.cfg.runtime.per_call_timeout, move || {
+                runner::instantiate(&engine, &pre, &runtime, http_enabled, secrets_store, None)?
+                    .with_debug_dump(&artifact, &runtime)
+                    .with_events(events)
+                    .call(&request, &runtime)
+            })
+    }
+
+    /// Runtime policy for a call on the worker pool. The store's epoch
+    /// deadline, like the pool's timeout, starts when a worker picks the call
+    /// up, so a guest still running at `per_call_timeout` is interrupted
+    /// instead of holding its worker.
+    fn worker_runtime(&self) -> RuntimePolicy {
+        let mut runtime = self.cfg.runtime.clone();
+        runtime.wallclock_timeout = runtime.wallclock_timeout.min(runtime.per_call_timeout);
+        runtime
+    }
+
+    /// Enforce the catalog gate once per component and digest.
+    fn check_catalog(
+        &self,
+        component: &str,
+        verified: &VerifiedArtifact,
+        events: Option<&CallEvents>,
+    ) -> Result<(), ExecError> {
+        if !catalog_gate::applies(component, verified, &self.cfg) {
+            return Ok(());
+        }
+        let key = format!("{component}@{}", verified.resolved.digest);
+        if self.lock_gated().contains(&key) {
+            return Ok(());
+        }
+
+        let pre = self
+            .prepare(verified, events)
+            .map_err(|err| ExecError::runner(component, err))?;
+        catalog_gate::check(&self.engine, &pre, component, &self.cfg)?;
+        self.lock_gated().insert(key);
+        Ok(())
+    }
+
+    /// The compiled artifact if it is a legacy core module, compiling it on a
+    /// cache miss.
+    fn legacy_module(
+        &self,
+        verified: &VerifiedArtifact,
+        events: Option<&CallEvents>,
+    ) -> Option<Module> {
+        let digest = &verified.resolved.digest;
+        if let Some(module) = self.lock_modules().get(digest) {
+            self.stats.record_cache(digest, true);
+            return Some(module.clone());
+        }
+        let started = Instant::now();
+        let module = module_runner::load(&self.engine, &verified.resolved.bytes)?;
+        self.stats.record_cache(digest, false);
+        events::emit(events, || ExecEventKind::Compiled {
+            digest: digest.clone(),
+            elapsed: started.elapsed(),
+        });
+        self.lock_modules().insert(digest.clone(), module.clone());
+        Some(module)
+    }
+
+    /// Fetch the pre-linked component for the artifact, compiling it on a cache miss.
+    fn prepare(
+        &self,
+        verified: &VerifiedArtifact,
+        events: Option<&CallEvents>,
+    ) -> Result<InstancePre<StoreState>, RunnerError> {
+        let digest = &verified.resolved.digest;
+        if let Some(pre) = self.lock_prepared().get(digest) {
+            self.stats.record_cache(digest, true);
+            return Ok(pre.clone());
+        }
+
+        let started = Instant::now();
+        let component =
+            preview1::compile(&self.engine, &verified.resolved.bytes, &self.cfg.runtime)?;
+        self.stats.record_cache(digest, false);
+        events::emit(events, || ExecEventKind::Compiled {
+            digest: digest.clone(),
+            elapsed: started.elapsed(),
+        });
+        let pre =
+            runner::prepare_instance(&self.engine, &self.linker, &component, &self.cfg.runtime)?;
+        self.lock_prepared().insert(digest.clone(), pre.clone());
+        Ok(pre)
+    }
+
+    fn native(&self, component: &str) -> Option<Arc<dyn NativeRouter>> {
+        self.natives
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(component)
+            .cloned()
+    }
+
+    fn lock_gated(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+        self.gated
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lock_modules(&self) -> std::sync::MutexGuard<'_, HashMap<String, Module>> {
+        self.modules
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lock_prepared(&self) -> std::sync::MutexGuard<'_, HashMap<String, InstancePre<StoreState>>> {
+        self.prepared
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::{ContentBlock, Response, TextContent, ToolError, ToolResult};
+    use crate::{CostLedger, Priority, ResultCachePolicy, ToolStore, VerifyPolicy};
+    use serde_json::json;
+
+    fn request(action: &str) -> ExecRequest {
+        ExecRequest {
+            component: "mock".into(),
+            action: action.into(),
+            args: json!({}),
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Priority::Interactive,
+        }
+    }
+
+    #[test]
+    fn records_calls_and_errors_per_digest() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            tmp.path().join("mock.wasm"),
+            br#"{"_mock_mcp_exec": true, "responses": {"ping": {"pong": true}}}"#,
+        )
+        .expect("write mock");
+
+        let executor = Executor::with_workers(
+            ExecConfig {
+                store: ToolStore::LocalDir(tmp.path().to_path_buf()),
+                security: VerifyPolicy {
+                    allow_unverified: true,
+                    ..Default::default()
+                },
+                runtime: RuntimePolicy::default(),
+                http_enabled: false,
+                secrets_store: None,
+                cost_sink: None,
+                transforms: None,
+                hooks: None,
+            },
+            2,
+        )
+        .expect("executor");
+
+        executor.exec(request("ping")).expect("ping");
+        executor.exec(request("ping")).expect("ping");
+        executor.exec(request("missing")).expect_err("not found");
+
+        let stats = executor.stats();
+        assert_eq!(stats.len(), 1);
+        let entry = stats.values().next().expect("entry");
+        assert_eq!(entry.component, "mock");
+        assert_eq!(entry.calls, 3);
+        assert_eq!(entry.errors, 1);
+        // JSON mocks never compile, so they never touch the component cache.
+        assert_eq!(entry.cache_hits + entry.cache_misses, 0);
+
+        let report = executor.call_parallel(
+            vec![request("ping"), request("missing"), request("ping")],
+            2,
+        );
+        assert_eq!((report.succeeded(), report.failed()), (2, 1));
+        let value = report.to_value();
+        assert_eq!(value["results"][0]["result"], json!({"pong": true}));
+        assert_eq!(value["results"][1]["status"], "error");
+        assert_eq!(value["results"][1]["error"]["kind"], "not-found");
+        assert_eq!(value["results"][2]["status"], "ok");
+    }
+
+    struct Greeter;
+
+    impl NativeRouter for Greeter {
+        fn name(&self) -> String {
+            "greeter".into()
+        }
+
+        fn list_tools(&self) -> Result<Vec<Tool>, ToolError> {
+            Ok(vec![Tool {
+                name: "greet".into(),
+                title: None,
+                description: "Greets by name".into(),
+                input_schema: r#"{"type":"object"}"#.into(),
+                output_schema: None,
+                annotations: None,
+                meta: None,
+            }])
+        }
+
+        fn call_tool(&self, tool_name: &str, arguments: &str) -> Result<Response, ToolError> {
+            if tool_name != "greet" {
+                return Err(ToolError::NotFound(tool_name.into()));
+            }
+            let args: Value = serde_json::from_str(arguments)
+                .map_err(|err| ToolError::InvalidParameters(err.to_string()))?;
+            let name = args["name"].as_str().unwrap_or("world");
+            Ok(Response::Completed(ToolResult {
+                content: vec![ContentBlock::Text(TextContent {
+                    text: format!("hello {name}"),
+                    annotations: None,
+                })],
+                structured_content: None,
+                progress: None,
+                meta: None,
+                is_error: None,
+            }))
+        }
+    }
+
+    #[test]
+    fn serves_native_routers_without_resolving() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let executor = Executor::with_workers(
+            ExecConfig {
+                store: ToolStore::LocalDir(tmp.path().to_path_buf()),
+                security: VerifyPolicy::default(),
+                runtime: RuntimePolicy::default(),
+                http_enabled: false,
+                secrets_store: None,
+                cost_sink: None,
+                transforms: None,
+                hooks: None,
+            },
+            1,
+        )
+        .expect("executor");
+        executor.register_native("greeter", Arc::new(Greeter));
+
+        let tools = executor
+            .native_tools("greeter")
+            .expect("registered")
+            .expect("tools");
+        assert_eq!(tools[0].name, "greet");
+
+        let mut req = request("greet");
+        req.component = "greeter".into();
+        req.args = json!({"name": "ada"});
+        let value = executor.exec(req.clone()).expect("greet");
+        assert_eq!(value["result"]["content"][0]["text"], json!("hello ada"));
+
+        req.action = "wave".into();
+        let err = executor.exec(req).expect_err("unknown tool");
+        assert!(matches!(err, ExecError::Tool { .. }));
+        assert_eq!(executor.stats()["native:greeter"].errors, 1);
+    }
+
+    struct Sleeper;
+
+    impl NativeRouter for Sleeper {
+        fn name(&self) -> String {
+            "sleeper".into()
+        }
+
+        fn list_tools(&self) -> Result<Vec<Tool>, ToolError> {
+            Err(ToolError::ExecutionError("catalog unavailable".into()))
+        }
+
+        fn call_tool(&self, _tool_name: &str, arguments: &str) -> Result<Response, ToolError> {
+            let args: Value = serde_json::from_str(arguments)
+                .map_err(|err| ToolError::InvalidParameters(err.to_string()))?;
+            thread::sleep(Duration::from_millis(args["ms"].as_u64().unwrap_or(0)));
+            Ok(Response::Completed(ToolResult {
+                content: Vec::new(),
+                structured_content: None,
+                progress: None,
+                meta: None,
+                is_error: None,
+            }))
+        }
+    }
+
+    #[test]
+    fn native_calls_run_on_workers_under_the_timeout_and_report_cost() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let ledger = Arc::new(CostLedger::new());
+        let executor = Executor::with_workers(
+            ExecConfig {
+                store: ToolStore::LocalDir(tmp.path().to_path_buf()),
+                security: VerifyPolicy::default(),
+                runtime: RuntimePolicy {
+                    per_call_timeout: Duration::from_millis(100),
+                    ..RuntimePolicy::default()
+                },
+                http_enabled: false,
+                secrets_store: None,
+                cost_sink: Some(ledger.clone()),
+                transforms: None,
+                hooks: None,
+            },
+            1,
+        )
+        .expect("executor");
+        executor.register_native("sleeper", Arc::new(Sleeper));
+        assert!(matches!(
+            executor.native_tools("sleeper"),
+            Some(Err(ToolError::ExecutionError(_)))
+        ));
+
+        let mut req = request("nap");
+        req.component = "sleeper".into();
+        req.args = json!({ "ms": 10 });
+        executor.exec(req.clone()).expect("short call");
+        let totals = ledger.snapshot()[&None];
+        assert_eq!(totals.calls, 1);
+        assert!(totals.total.wallclock >= Duration::from_millis(10));
+
+        req.args = json!({ "ms": 1000 });
+        let err = executor.exec(req).expect_err("past the timeout");
+        assert!(
+            matches!(
+                err,
+                ExecError::Runner {
+                    source: RunnerError::Timeout { .. },
+                    ..
+                }
+            ),
+            "got {err}"
+        );
+    }
+
+    #[test]
+    fn repeated_deterministic_calls_are_served_from_the_result_cache() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            tmp.path().join("mock.wasm"),
+            br#"{"_mock_mcp_exec": true, "responses": {"ping": {"pong": true}}}"#,
+        )
+        .expect("write mock");
+        let executor = Executor::with_workers(
+            ExecConfig {
+                store: ToolStore::LocalDir(tmp.path().to_path_buf()),
+                security: VerifyPolicy {
+                    allow_unverified: true,
+                    ..Default::default()
+                },
+                runtime: RuntimePolicy {
+                    result_cache: Some(ResultCachePolicy {
+                        components: HashMap::from([("mock".to_string(), ComponentCaching::Always)]),
+                        ..ResultCachePolicy::default()
+                    }),
+                    ..RuntimePolicy::default()
+                },
+                http_enabled: false,
+                secrets_store: None,
+                cost_sink: None,
+                transforms: None,
+                hooks: None,
+            },
+            1,
+        )
+        .expect("executor");
+        executor.register_native("greeter", Arc::new(Greeter));
+
+        executor.exec(request("ping")).expect("ping");
+        let cached = executor.exec(request("ping")).expect("cached ping");
+        assert_eq!(cached["pong"], json!(true));
+        let entry = executor.stats().into_values().next().expect("entry");
+        assert_eq!(entry.calls, 1, "the hit never reached the component");
+
+        // Greeter's tool carries no read-only annotation, so it always runs.
+        let mut greet = request("greet");
+        greet.component = "greeter".into();
+        executor.exec(greet.clone()).expect("greet");
+        executor.exec(greet).expect("greet again");
+        assert_eq!(executor.stats()["native:greeter"].calls, 2);
+
+        let stats = executor.result_cache_stats().expect("cache enabled");
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+        executor.clear_cache();
+        assert_eq!(executor.result_cache_stats().expect("stats").entries, 0);
+    }
+
+    #[test]
+    fn costs_are_charged_once_and_never_cached() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            tmp.path().join("echo.wasm"),
+            crate::stress::echo_component(1),
+        )
+        .expect("write echo");
+        let ledger = Arc::new(crate::CostLedger::new());
+        let config = |cost_report: bool| ExecConfig {
+            store: ToolStore::LocalDir(tmp.path().to_path_buf()),
+            security: VerifyPolicy {
+                allow_unverified: true,
+                ..Default::default()
+            },
+            runtime: RuntimePolicy {
+                result_cache: Some(ResultCachePolicy {
+                    components: HashMap::from([("echo".to_string(), ComponentCaching::Always)]),
+                    ..ResultCachePolicy::default()
+                }),
+                cost_report,
+                ..RuntimePolicy::default()
+            },
+            http_enabled: false,
+            secrets_store: None,
+            cost_sink: Some(ledger.clone()),
+            transforms: None,
+            hooks: None,
+        };
+        let mut call = request("run");
+        call.component = "echo".into();
+
+        let executor = Executor::with_workers(config(false), 1).expect("executor");
+        let fresh = executor.exec(call.clone()).expect("fresh call");
+        assert!(fresh.get(cost::COST_FIELD).is_none());
+        let cached = executor.exec(call.clone()).expect("cached call");
+        assert_eq!(cached, fresh);
+        assert_eq!(ledger.drain()[&None].calls, 1, "hits are not charged");
+
+        let executor = Executor::with_workers(config(true), 1).expect("executor");
+        let fresh = executor.exec(call.clone()).expect("fresh call");
+        assert!(crate::ExecutionCost::from_result(&fresh).is_some());
+        let cached = executor.exec(call).expect("cached call");
+        assert!(cached.get(cost::COST_FIELD).is_none());
+        assert_eq!(ledger.drain()[&None].calls, 1);
+    }
+
+    #[test]
+    fn subscribers_see_the_lifecycle_of_each_call() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            tmp.path().join("echo.wasm"),
+            crate::stress::echo_component(1),
+        )
+        .expect("write echo");
+        let executor = Executor::with_workers(
+            ExecConfig {
+                store: ToolStore::LocalDir(tmp.path().to_path_buf()),
+                security: VerifyPolicy {
+                    allow_unverified: true,
+                    ..Default::default()
+                },
+                runtime: RuntimePolicy::default(),
+                http_enabled: false,
+                secrets_store: None,
+                cost_sink: None,
+                transforms: None,
+                hooks: None,
+            },
+            1,
+        )
+        .expect("executor");
+        let call = |component: &str| ExecRequest {
+            component: component.into(),
+            action: "echo".into(),
+            args: json!({}),
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Priority::Interactive,
+        };
+
+        let mut events = executor.subscribe();
+        executor.exec(call("echo")).expect("first call");
+        executor.exec(call("echo")).expect("second call");
+        executor
+            .exec(call("missing"))
+            .expect_err("unknown component");
+
+        let mut steps = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            let step = match &event.kind {
+                ExecEventKind::Resolved { .. } => "resolved",
+                ExecEventKind::Verified { .. } => "verified",
+                ExecEventKind::Compiled { .. } => "compiled",
+                ExecEventKind::Started { .. } => "started",
+                ExecEventKind::HostCall { .. } => "host-call",
+                ExecEventKind::Finished { .. } => "finished",
+                ExecEventKind::Failed { .. } => "failed",
+            };
+            steps.push((event.call_id, event.component, step));
+        }
+        let echo = |call_id: u64, step: &'static str| (call_id, "echo".to_string(), step);
+        assert_eq!(
+            steps,
+            [
+                echo(1, "resolved"),
+                echo(1, "verified"),
+                echo(1, "compiled"),
+                echo(1, "started"),
+                echo(1, "finished"),
+                // Compiled once per digest.
+                echo(2, "resolved"),
+                echo(2, "verified"),
+                echo(2, "started"),
+                echo(2, "finished"),
+                (3, "missing".to_string(), "failed"),
+            ]
+        );
+    }
+
+    #[test]
+    fn worker_pool_survives_panicking_jobs() {
+        let workers = WorkerPool::new(1);
+        let timeout = std::time::Duration::from_secs(5);
+        let err = workers
+            .run(Priority::Interactive, timeout, || panic!("boom"))
+            .expect_err("panicked job");
+        assert!(matches!(err, RunnerError::Internal(_)));
+        let value = workers
+            .run(Priority::Interactive, timeout, || Ok(json!(1)))
+            .expect("next job");
+        assert_eq!(value, json!(1));
+    }
+
+    #[test]
+    fn worker_pool_timeout_starts_at_dequeue_and_drops_queued_jobs() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::time::Duration;
+
+        let workers = Arc::new(WorkerPool::new(1));
+        let hold = |millis| {
+            let workers = Arc::clone(&workers);
+            thread::spawn(move || {
+                workers.run(Priority::Interactive, Duration::from_secs(5), move || {
+                    thread::sleep(Duration::from_millis(millis));
+                    Ok(json!(null))
+                })
+            })
+        };
+
+        let blocker = hold(300);
+        thread::sleep(Duration::from_millis(50));
+        let ran = Arc::new(AtomicBool::new(false));
+        let err = {
+            let ran = Arc::clone(&ran);
+            workers.run(
+                Priority::Interactive,
+                Duration::from_millis(50),
+                move || {
+                    ran.store(true, Ordering::SeqCst);
+                    Ok(json!(null))
+                },
+            )
+        }
+        .expect_err("queued past its timeout");
+        assert!(matches!(err, RunnerError::Timeout { .. }), "got {err}");
+        blocker.join().expect("blocker").expect("blocker job");
+
+        // Queued for about 400ms and running for 300ms: over the 500ms
+        // timeout in total, but not from when the worker picked it up.
+        let blocker = hold(400);
+        thread::sleep(Duration::from_millis(50));
+        let value = workers
+            .run(Priority::Interactive, Duration::from_millis(500), || {
+                thread::sleep(Duration::from_millis(300));
+                Ok(json!(1))
+            })
+            .expect("timed from dequeue");
+        assert_eq!(value, json!(1));
+        blocker.join().expect("blocker").expect("blocker job");
+        assert!(!ran.load(Ordering::SeqCst), "dropped job never runs");
+    }
+
+    #[test]
+    fn guests_past_the_per_call_timeout_release_their_worker() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            tmp.path().join("echo.wasm"),
+            crate::stress::echo_component(1),
+        )
+        .expect("write echo");
+        std::fs::write(
+            tmp.path().join("spin.wasm"),
+            crate::stress::spinning_component(),
+        )
+        .expect("write spin");
+        let executor = Executor::with_workers(
+            ExecConfig {
+                store: ToolStore::LocalDir(tmp.path().to_path_buf()),
+                security: VerifyPolicy {
+                    allow_unverified: true,
+                    ..Default::default()
+                },
+                runtime: RuntimePolicy {
+                    per_call_timeout: std::time::Duration::from_millis(200),
+                    ..RuntimePolicy::default()
+                },
+                http_enabled: false,
+                secrets_store: None,
+                cost_sink: None,
+                transforms: None,
+                hooks: None,
+            },
+            1,
+        )
+        .expect("executor");
+        let call = |component: &str| ExecRequest {
+            component: component.into(),
+            action: "run".into(),
+            args: json!({}),
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Priority::Interactive,
+        };
+
+        let err = executor.exec(call("spin")).expect_err("spinning call");
+        assert!(
+            matches!(
+                err,
+                ExecError::Runner {
+                    source: RunnerError::Timeout { .. },
+                    ..
+                }
+            ),
+            "got {err}"
+        );
+        // The 30s wallclock deadline would keep the only worker spinning.
+        executor.exec(call("echo")).expect("worker released");
+    }
+
+    #[test]
+    fn interactive_jobs_jump_the_queue_without_starving_background() {
+        use std::sync::mpsc;
+
+        let workers = Arc::new(WorkerPool::new(1));
+        let timeout = std::time::Duration::from_secs(5);
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        // Hold the only worker until every job is queued.
+        let (release, gate) = mpsc::channel::<()>();
+        let blocker = {
+            let workers = Arc::clone(&workers);
+            thread::spawn(move || {
+                workers.run(Priority::Interactive, timeout, move || {
+                    let _ = gate.recv();
+                    Ok(json!(null))
+                })
+            })
+        };
+        std::thread::sleep(std::time::Duration::from_millis(50));
+
+        let mut callers = Vec::new();
+        let mut enqueue = |priority: Priority, label: String| {
+            let workers = Arc::clone(&workers);
+            let order = Arc::clone(&order);
+            callers.push(thread::spawn(move || {
+                workers.run(priority, timeout, move || {
+                    order.lock().unwrap().push(label);
+                    Ok(json!(null))
+                })
+            }));
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+        enqueue(Priority::Background, "bg".into());
+        for index in 0..10 {
+            enqueue(Priority::Interactive, format!("i{index}"));
+        }
+
+        release.send(()).expect("release worker");
+        blocker.join().expect("blocker").expect("blocker job");
+        for caller in callers {
+            caller.join().expect("caller").expect("job");
+        }
+
+        // The blocker opened the burst, so the background job runs after seven
+        // more interactive jobs rather than after all ten.
+        let order = order.lock().unwrap();
+        assert_eq!(order.iter().position(|label| label == "bg"), Some(7));
+        assert_eq!(order.len(), 11);
+    }
+}
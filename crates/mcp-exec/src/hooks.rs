@@ -0,0 +1,201 @@
+//! Embedder callbacks around every execution.
+//!
+//! [`ExecHooks`] registered on [`crate::ExecConfig::hooks`] see each request
+//! before it runs and its outcome afterwards, so a host can implement approval
+//! gates, quota checks, auditing, or enrichment without forking the exec
+//! pipeline. Before-hooks run in registration order and the first
+//! [`Decision::Deny`] fails the call with [`ExecError::Denied`]; after-hooks
+//! run for every call that was allowed, whether it succeeded or not. Hooks run
+//! after [`crate::TransformHooks`] rewrote the arguments, so they judge the
+//! call that actually executes. Hooks that inspect the arguments should read
+//! them through [`ExecRequest::args_value`], since they may only be present
+//! as [`ExecRequest::args_raw`].
+
+use std::fmt;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::ExecRequest;
+use crate::error::ExecError;
+
+/// Verdict of a before-hook.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Decision {
+    /// Let the call proceed to the next hook and then to execution.
+    Allow,
+    /// Refuse the call; `reason` is reported in [`ExecError::Denied`].
+    Deny { reason: String },
+}
+
+impl Decision {
+    pub fn deny(reason: impl Into<String>) -> Self {
+        Self::Deny {
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Judges a request before it runs.
+pub type BeforeExecHook = Arc<dyn Fn(&ExecRequest) -> Decision + Send + Sync>;
+/// Observes the outcome of a request that ran.
+pub type AfterExecHook = Arc<dyn Fn(&ExecRequest, &Result<Value, ExecError>) + Send + Sync>;
+
+/// Callbacks run around every execution.
+#[derive(Clone, Default)]
+pub struct ExecHooks {
+    before: Vec<BeforeExecHook>,
+    after: Vec<AfterExecHook>,
+}
+
+impl fmt::Debug for ExecHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExecHooks")
+            .field("before", &self.before.len())
+            .field("after", &self.after.len())
+            .finish()
+    }
+}
+
+impl ExecHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decide whether each request may run.
+    pub fn before_exec(
+        mut self,
+        hook: impl Fn(&ExecRequest) -> Decision + Send + Sync + 'static,
+    ) -> Self {
+        self.before.push(Arc::new(hook));
+        self
+    }
+
+    /// Observe the outcome of each request that was allowed to run.
+    pub fn after_exec(
+        mut self,
+        hook: impl Fn(&ExecRequest, &Result<Value, ExecError>) + Send + Sync + 'static,
+    ) -> Self {
+        self.after.push(Arc::new(hook));
+        self
+    }
+
+    /// Fail with [`ExecError::Denied`] on the first before-hook that denies `req`.
+    fn admit(&self, req: &ExecRequest) -> Result<(), ExecError> {
+        for hook in &self.before {
+            if let Decision::Deny { reason } = hook(req) {
+                return Err(ExecError::Denied {
+                    component: req.component.clone(),
+                    action: req.action.clone(),
+                    reason,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn observe(&self, req: &ExecRequest, result: &Result<Value, ExecError>) {
+        for hook in &self.after {
+            hook(req, result);
+        }
+    }
+}
+
+/// Run `exec` on `req` unless a before-hook denies it, then report the outcome
+/// to the after-hooks.
+pub(crate) fn around(
+    hooks: Option<&ExecHooks>,
+    req: ExecRequest,
+    exec: impl FnOnce(ExecRequest) -> Result<Value, ExecError>,
+) -> Result<Value, ExecError> {
+    let Some(hooks) = hooks else {
+        return exec(req);
+    };
+    hooks.admit(&req)?;
+    if hooks.after.is_empty() {
+        return exec(req);
+    }
+    let observed = req.clone();
+    let result = exec(req);
+    hooks.observe(&observed, &result);
+    result
+}
+
+/// [`around`] for an asynchronous `exec`.
+pub(crate) async fn around_async<F>(
+    hooks: Option<&ExecHooks>,
+    req: ExecRequest,
+    exec: impl FnOnce(ExecRequest) -> F,
+) -> Result<Value, ExecError>
+where
+    F: Future<Output = Result<Value, ExecError>>,
+{
+    let Some(hooks) = hooks else {
+        return exec(req).await;
+    };
+    hooks.admit(&req)?;
+    if hooks.after.is_empty() {
+        return exec(req).await;
+    }
+    let observed = req.clone();
+    let result = exec(req).await;
+    hooks.observe(&observed, &result);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    fn request(action: &str) -> ExecRequest {
+        ExecRequest {
+            component: "crm".into(),
+            action: action.into(),
+            args: json!({}),
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Default::default(),
+        }
+    }
+
+    #[test]
+    fn denied_calls_never_run_and_allowed_ones_are_observed() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&seen);
+        let hooks = ExecHooks::new()
+            .before_exec(|req| match req.action.as_str() {
+                "delete" => Decision::deny("needs approval"),
+                _ => Decision::Allow,
+            })
+            .after_exec(move |req, result| {
+                log.lock()
+                    .unwrap()
+                    .push((req.action.clone(), result.is_ok()));
+            });
+
+        let ok = around(Some(&hooks), request("read"), |_| Ok(json!("done")));
+        assert_eq!(ok.expect("allowed"), json!("done"));
+
+        let err = around(Some(&hooks), request("delete"), |_| {
+            panic!("denied call must not run")
+        })
+        .expect_err("denied");
+        assert!(
+            matches!(&err, ExecError::Denied { action, reason, .. }
+                if action == "delete" && reason == "needs approval"),
+            "got {err}"
+        );
+
+        let failed = around(Some(&hooks), request("update"), |req| {
+            Err(ExecError::not_found(req.component, req.action))
+        });
+        assert!(failed.is_err());
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [("read".to_string(), true), ("update".to_string(), false)]
+        );
+    }
+}
@@ -1,58 +1,342 @@
 //! Executor library for loading and running `wasix:mcp` compatible Wasm components.
 //! Users supply an [`ExecConfig`] describing how to resolve artifacts and what
-//! runtime constraints to enforce, then call [`exec`] with a structured request.
+//! runtime constraints to enforce, then call [`exec`] with a structured request,
+//! or create an [`Executor`] once and reuse it across requests.
 
+mod actor;
+pub mod blobs;
+pub mod capabilities;
+pub mod catalog_diff;
+mod catalog_gate;
 mod config;
+pub mod cost;
+pub mod debug_dump;
 pub mod describe;
+mod epoch;
 mod error;
+pub mod events;
+mod executor;
+pub mod hooks;
+mod memory;
+pub mod module_runner;
+pub mod native;
+pub mod parallel;
 mod path_safety;
+pub mod permissions;
+pub mod pool;
+pub mod preflight;
+mod preview1;
+mod quarantine;
 mod resolve;
+pub mod result_cache;
 pub mod router;
 pub mod runner;
+pub mod schema;
+mod scratch;
+pub mod secrets_preflight;
+pub mod stats;
 mod store;
+pub mod stream;
+#[cfg(test)]
+mod stress;
+mod tofu;
+pub mod transform;
 mod verify;
 
-pub use config::{DynSecretsStore, ExecConfig, RuntimePolicy, SecretsStore, VerifyPolicy};
-pub use error::{ExecError, RunnerError};
+pub use blobs::{BlobPolicy, BlobStore, DynBlobStore, InMemoryBlobStore};
+pub use capabilities::{CapabilityReport, GrantedCapabilities};
+pub use catalog_diff::SnapshotTool;
+pub use config::{
+    CatalogGate, ComponentCaching, DynSecretsStore, ExecConfig, HostLimits, NetworkProfile,
+    Preview1Adapter, QuarantinePolicy, ResultCachePolicy, RuntimePolicy, SecretAction,
+    SecretAuditEvent, SecretsAccessPolicy, SecretsGrant, SecretsStore, VerifyPolicy,
+};
+pub use cost::{CostLedger, CostSink, DynCostSink, ExecutionCost, TenantCost};
+pub use error::{ExecError, RunnerError, VerificationError};
+pub use events::{ExecEvent, ExecEventKind};
+pub use executor::Executor;
+pub use hooks::{Decision, ExecHooks};
+pub use native::NativeRouter;
+pub use parallel::{ParallelEntry, ParallelReport};
+pub use permissions::{PermissionDenied, PermissionGate, ToolPermissions, ToolRule};
+pub use pool::{WarmPool, WarmPoolConfig};
+pub use result_cache::ResultCacheStats;
+pub use stats::{ComponentStats, LatencySummary};
 pub use store::{ToolInfo, ToolStore};
+pub use stream::{MalformedChunk, StreamCapture, StreamChunk};
+pub use transform::TransformHooks;
+
+use std::borrow::Cow;
+use std::thread;
 
 use greentic_types::TenantCtx;
 use serde_json::{Value, json};
 
 use crate::runner::Runner;
+use crate::stream::ChunkPipe;
 
 #[derive(Clone, Debug)]
 pub struct ExecRequest {
     pub component: String,
     pub action: String,
     pub args: Value,
+    /// `args` already serialized as JSON. When set, it is handed to the
+    /// component verbatim and `args` is ignored, saving a serialization of
+    /// large payloads. It is only parsed where the host needs the value:
+    /// transforms, result caching, debug dumps, and legacy core modules.
+    pub args_raw: Option<String>,
     pub tenant: Option<TenantCtx>,
+    /// Hex digest the resolved artifact must match, enforced in addition to
+    /// [`VerifyPolicy::required_digests`].
+    pub expected_digest: Option<String>,
+    /// Queueing class on an [`Executor`]'s worker pool; ignored by [`exec`].
+    pub priority: Priority,
+}
+
+impl ExecRequest {
+    /// A request for `action` of `component` with `args`, no tenant or digest
+    /// pin, and [`Priority::Interactive`]. Set the other fields on the result,
+    /// or with struct update syntax.
+    pub fn new(component: impl Into<String>, action: impl Into<String>, args: Value) -> Self {
+        Self {
+            component: component.into(),
+            action: action.into(),
+            args,
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Priority::default(),
+        }
+    }
+
+    /// The arguments as JSON text: [`Self::args_raw`] verbatim, or
+    /// [`Self::args`] serialized.
+    pub fn args_json(&self) -> serde_json::Result<Cow<'_, str>> {
+        match &self.args_raw {
+            Some(raw) => Ok(Cow::Borrowed(raw)),
+            None => serde_json::to_string(&self.args).map(Cow::Owned),
+        }
+    }
+
+    /// The arguments as a value, parsing [`Self::args_raw`] when set.
+    pub fn args_value(&self) -> serde_json::Result<Cow<'_, Value>> {
+        match &self.args_raw {
+            Some(raw) => serde_json::from_str(raw).map(Cow::Owned),
+            None => Ok(Cow::Borrowed(&self.args)),
+        }
+    }
+
+    /// Move parsed [`Self::args_raw`] into [`Self::args`], for code that
+    /// edits the arguments in place.
+    pub(crate) fn materialize_args(&mut self) -> serde_json::Result<()> {
+        if let Some(raw) = self.args_raw.take() {
+            self.args = serde_json::from_str(&raw)?;
+        }
+        Ok(())
+    }
+}
+
+/// Scheduling class of an [`ExecRequest`] on an [`Executor`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Priority {
+    /// User-facing calls, dispatched ahead of queued background work.
+    #[default]
+    Interactive,
+    /// Bulk jobs that may wait behind interactive calls, but are never starved.
+    Background,
 }
 
 /// Execute a single action exported by an MCP component.
 ///
 /// Resolution, verification, and runtime enforcement are performed in sequence,
-/// with detailed errors surfaced through [`ExecError`].
+/// with detailed errors surfaced through [`ExecError`]. Blocks the calling
+/// thread while [`exec_async`] runs on a runtime of its own.
 pub fn exec(req: ExecRequest, cfg: &ExecConfig) -> Result<Value, ExecError> {
-    let resolved = resolve::resolve(&req.component, &cfg.store)
-        .map_err(|err| ExecError::resolve(&req.component, err))?;
+    exec_blocking(req, cfg, None)
+}
 
-    let verified = verify::verify(&req.component, resolved, &cfg.security)
-        .map_err(|err| ExecError::verification(&req.component, err))?;
+/// [`exec`] with the guest's stdout written to `stream`, if given.
+fn exec_blocking(
+    req: ExecRequest,
+    cfg: &ExecConfig,
+    stream: Option<ChunkPipe>,
+) -> Result<Value, ExecError> {
+    let component = req.component.clone();
+    block_on(exec_piped(req, cfg, stream)).unwrap_or_else(|err| {
+        Err(ExecError::runner(
+            component,
+            RunnerError::Internal(format!("exec runtime: {err}")),
+        ))
+    })
+}
+
+/// [`exec`] for hosts running on tokio.
+///
+/// Components are called through Wasmtime's async support and yield to the
+/// runtime while they run, so concurrent calls share its threads rather than
+/// each blocking one, and a call past [`RuntimePolicy::per_call_timeout`] is
+/// cancelled. Artifact resolution, compilation, and legacy core modules run on
+/// tokio's blocking pool. The runtime must have its time driver enabled.
+pub async fn exec_async(req: ExecRequest, cfg: &ExecConfig) -> Result<Value, ExecError> {
+    exec_piped(req, cfg, None).await
+}
+
+/// [`exec_async`] with the guest's stdout written to `stream`, if given.
+async fn exec_piped(
+    req: ExecRequest,
+    cfg: &ExecConfig,
+    stream: Option<ChunkPipe>,
+) -> Result<Value, ExecError> {
+    transform::around_async(cfg.transforms.as_ref(), req, |req| {
+        hooks::around_async(cfg.hooks.as_ref(), req, |req| async move {
+            check_permissions(&req, cfg)?;
+            secrets_preflight::check(&req, cfg)?;
+            exec_admitted_async(req, cfg, stream).await
+        })
+    })
+    .await
+}
+
+/// [`exec_piped`] for a request that already passed [`check_permissions`]
+/// and the [`secrets_preflight`].
+async fn exec_admitted_async(
+    req: ExecRequest,
+    cfg: &ExecConfig,
+    stream: Option<ChunkPipe>,
+) -> Result<Value, ExecError> {
+    let (job_req, job_cfg) = (req.clone(), cfg.clone());
+    let verified = tokio::task::spawn_blocking(move || -> Result<_, ExecError> {
+        let verified = resolve_and_verify(&job_req, &job_cfg)?;
+        catalog_gate::check_artifact(&job_req.component, &verified, &job_cfg)?;
+        Ok(verified)
+    })
+    .await
+    .unwrap_or_else(|_| {
+        Err(ExecError::runner(
+            &req.component,
+            RunnerError::Internal("artifact resolution task failed".into()),
+        ))
+    })?;
+
+    let mut result = runner::run_async(
+        &req,
+        &verified,
+        runner::ExecutionContext {
+            runtime: &cfg.runtime,
+            http_enabled: cfg.http_enabled,
+            secrets_store: cfg.secrets_store.clone(),
+            stream,
+        },
+    )
+    .await;
+    cost::report(cfg, &req, &mut result);
+
+    finish(req, result)
+}
+
+/// Drive `future` to completion on a current-thread runtime of its own.
+fn block_on<F>(future: F) -> std::io::Result<F::Output>
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    let run = || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()?;
+        let output = runtime.block_on(future);
+        // Leave blocking jobs that outlived a timeout running rather than
+        // waiting for them.
+        runtime.shutdown_background();
+        Ok(output)
+    };
+    // A thread inside a runtime, including its blocking pool, cannot start
+    // another one.
+    if tokio::runtime::Handle::try_current().is_err() {
+        return run();
+    }
+    thread::scope(|scope| {
+        scope
+            .spawn(run)
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+/// [`exec`] for a request that already passed [`check_permissions`] and the
+/// [`secrets_preflight`].
+pub(crate) fn exec_admitted(req: ExecRequest, cfg: &ExecConfig) -> Result<Value, ExecError> {
+    let verified = resolve_and_verify(&req, cfg)?;
+    catalog_gate::check_artifact(&req.component, &verified, cfg)?;
 
     let runner = runner::DefaultRunner::new(&cfg.runtime)
         .map_err(|err| ExecError::runner(&req.component, err))?;
 
-    let result = runner.run(
+    let mut result = runner.run(
         &req,
         &verified,
         runner::ExecutionContext {
             runtime: &cfg.runtime,
             http_enabled: cfg.http_enabled,
             secrets_store: cfg.secrets_store.clone(),
+            stream: None,
         },
     );
+    cost::report(cfg, &req, &mut result);
+
+    finish(req, result)
+}
 
+/// Admit the request against [`RuntimePolicy::tool_permissions`], if set.
+pub(crate) fn check_permissions(req: &ExecRequest, cfg: &ExecConfig) -> Result<(), ExecError> {
+    let Some(gate) = &cfg.runtime.tool_permissions else {
+        return Ok(());
+    };
+    gate.check(&req.component, &req.action)
+        .map_err(|source| ExecError::PermissionDenied {
+            component: req.component.clone(),
+            source,
+        })
+}
+
+/// Locate the requested component and enforce the verification policy on it.
+pub(crate) fn resolve_and_verify(
+    req: &ExecRequest,
+    cfg: &ExecConfig,
+) -> Result<verify::VerifiedArtifact, ExecError> {
+    let resolved = resolve_artifact(req, cfg)?;
+    verify_artifact(req, resolved, cfg)
+}
+
+/// Locate the requested component in the tool store.
+pub(crate) fn resolve_artifact(
+    req: &ExecRequest,
+    cfg: &ExecConfig,
+) -> Result<resolve::ResolvedArtifact, ExecError> {
+    resolve::resolve(&req.component, &cfg.store)
+        .map_err(|err| ExecError::resolve(&req.component, err))
+}
+
+/// Enforce the verification policy, and the request's digest pin, on `resolved`.
+pub(crate) fn verify_artifact(
+    req: &ExecRequest,
+    resolved: resolve::ResolvedArtifact,
+    cfg: &ExecConfig,
+) -> Result<verify::VerifiedArtifact, ExecError> {
+    verify::verify_pinned(
+        &req.component,
+        resolved,
+        &cfg.security,
+        req.expected_digest.as_deref(),
+    )
+    .map_err(|err| ExecError::verification(&req.component, err))
+}
+
+/// Map a runner outcome into the public result, surfacing tool-reported errors.
+pub(crate) fn finish(
+    req: ExecRequest,
+    result: Result<Value, RunnerError>,
+) -> Result<Value, ExecError> {
     let value = match result {
         Ok(v) => v,
         Err(RunnerError::ActionNotFound { .. }) => {
@@ -99,6 +383,38 @@ pub fn exec(req: ExecRequest, cfg: &ExecConfig) -> Result<Value, ExecError> {
     Ok(value)
 }
 
+/// Execute an action, handing the guest's stdout to `on_chunk` while it runs.
+///
+/// Requires [`RuntimePolicy::stream_capture`]; without it this is [`exec`].
+/// Chunks are delivered in order on the calling thread as the guest writes
+/// them. Once `max_bytes` are waiting for `on_chunk`, the guest's writes wait
+/// too, so a slow consumer slows the guest down rather than failing it.
+pub fn exec_stream(
+    req: ExecRequest,
+    cfg: &ExecConfig,
+    mut on_chunk: impl FnMut(StreamChunk),
+) -> Result<Value, ExecError> {
+    let Some(capture) = cfg.runtime.stream_capture.clone() else {
+        return exec(req, cfg);
+    };
+    let pipe = ChunkPipe::live(capture);
+    thread::scope(|scope| {
+        let call = scope.spawn(|| {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                exec_blocking(req, cfg, Some(pipe.clone()))
+            }));
+            // Ends the stream even when the guest outlives a timeout.
+            pipe.finish();
+            result.unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        });
+        while let Some(chunk) = pipe.next_chunk() {
+            on_chunk(chunk);
+        }
+        call.join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -154,17 +470,25 @@ mod tests {
                 allow_unverified: false,
                 required_digests: required,
                 trusted_signers: Vec::new(),
+                tofu_store: None,
+                ..Default::default()
             },
             runtime: RuntimePolicy::default(),
             http_enabled: false,
             secrets_store: None,
+            cost_sink: None,
+            transforms: None,
+            hooks: None,
         };
 
         let req = ExecRequest {
             component: "echo.component".into(),
             action: "noop".into(),
             args: json!({"message": "hello"}),
+            args_raw: None,
             tenant: None,
+            expected_digest: None,
+            priority: Priority::Interactive,
         };
 
         // Inject our mock runner to exercise pipeline without executing wasm.
@@ -180,6 +504,7 @@ mod tests {
                     runtime: &cfg.runtime,
                     http_enabled: cfg.http_enabled,
                     secrets_store: cfg.secrets_store.clone(),
+                    stream: None,
                 },
             )
             .expect("run");
@@ -189,4 +514,102 @@ mod tests {
             Some(digest.as_str())
         );
     }
+
+    #[test]
+    fn raw_arguments_pass_through_and_parse_on_demand() {
+        let raw = r#"{"b": 1, "a": [true]}"#;
+        let mut req = ExecRequest {
+            component: "crm".into(),
+            action: "search".into(),
+            args: Value::Null,
+            args_raw: Some(raw.into()),
+            tenant: None,
+            expected_digest: None,
+            priority: Priority::Interactive,
+        };
+        // Handed over verbatim rather than re-encoded.
+        assert_eq!(req.args_json().expect("json"), raw);
+        assert_eq!(
+            *req.args_value().expect("value"),
+            json!({"a": [true], "b": 1})
+        );
+
+        let hooks = TransformHooks::new().rename_arg("crm", "b", "c");
+        let args = transform::around(Some(&hooks), req.clone(), |req| {
+            assert!(req.args_raw.is_none());
+            Ok(req.args)
+        })
+        .expect("transformed");
+        assert_eq!(args, json!({"a": [true], "c": 1}));
+
+        req.args_raw = Some("{not json".into());
+        let err = transform::around(Some(&hooks), req, |_| panic!("must not run"))
+            .expect_err("invalid raw arguments");
+        assert!(matches!(err, ExecError::Runner { .. }), "got {err}");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn async_calls_share_a_thread_and_time_out_cooperatively() {
+        let tempdir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            tempdir.path().join("echo.wasm"),
+            crate::stress::echo_component(3),
+        )
+        .expect("write echo");
+        std::fs::write(
+            tempdir.path().join("spin.wasm"),
+            crate::stress::spinning_component(),
+        )
+        .expect("write spin");
+        let cfg = ExecConfig {
+            store: ToolStore::LocalDir(PathBuf::from(tempdir.path())),
+            security: VerifyPolicy {
+                allow_unverified: true,
+                ..Default::default()
+            },
+            runtime: RuntimePolicy {
+                per_call_timeout: std::time::Duration::from_millis(300),
+                ..RuntimePolicy::default()
+            },
+            http_enabled: false,
+            secrets_store: None,
+            cost_sink: None,
+            transforms: None,
+            hooks: None,
+        };
+        let request = |component: &str| ExecRequest {
+            component: component.into(),
+            action: "run".into(),
+            args: json!({ "n": 1 }),
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Priority::Interactive,
+        };
+
+        // The spinning guest yields at every epoch tick, so the echo call
+        // completes on the same thread while it runs.
+        let (spun, echoed) = tokio::join!(
+            exec_async(request("spin"), &cfg),
+            exec_async(request("echo"), &cfg)
+        );
+        let echoed = echoed.expect("echo");
+        assert_eq!(echoed["version"], json!(3));
+        assert_eq!(echoed["args"], json!({ "n": 1 }));
+        let err = spun.expect_err("spinning call times out");
+        assert!(
+            matches!(
+                err,
+                ExecError::Runner {
+                    source: RunnerError::Timeout { .. },
+                    ..
+                }
+            ),
+            "got {err}"
+        );
+
+        // The blocking wrapper takes the same path.
+        let value = exec(request("echo"), &cfg).expect("sync echo");
+        assert_eq!(value["args"], json!({ "n": 1 }));
+    }
 }
@@ -0,0 +1,90 @@
+//! Guest memory cap from [`RuntimePolicy::max_memory`].
+//!
+//! Each store gets a [`MemoryLimiter`]: wasmtime's [`StoreLimits`] enforces
+//! the cap on every linear memory, and the limiter remembers when a guest
+//! asked for more. Such a growth traps instead of returning `-1`, since guests
+//! rarely recover from a failed allocation, and the call fails with
+//! [`RunnerError::MemoryLimit`] rather than a generic trap.
+//!
+//! [`RuntimePolicy::max_memory`]: crate::RuntimePolicy::max_memory
+
+use wasmtime::{ResourceLimiter, StoreLimits, StoreLimitsBuilder};
+
+use crate::error::RunnerError;
+
+/// Resource limiter installed on every store.
+pub(crate) struct MemoryLimiter {
+    limits: StoreLimits,
+    max: Option<u64>,
+    /// Largest size, in bytes, a guest asked for above `max`.
+    exceeded: Option<u64>,
+}
+
+impl MemoryLimiter {
+    /// Cap each linear memory at `max` bytes; unlimited when `None`.
+    pub(crate) fn new(max: Option<u64>) -> Self {
+        let mut limits = StoreLimitsBuilder::new();
+        if let Some(max) = max {
+            limits = limits.memory_size(usize::try_from(max).unwrap_or(usize::MAX));
+        }
+        Self {
+            limits: limits.build(),
+            max,
+            exceeded: None,
+        }
+    }
+
+    /// The error for a call that failed, if it failed by growing memory past
+    /// the cap.
+    pub(crate) fn exceeded(&self) -> Option<RunnerError> {
+        Some(RunnerError::MemoryLimit {
+            limit: self.max?,
+            requested: self.exceeded?,
+        })
+    }
+}
+
+impl ResourceLimiter for MemoryLimiter {
+    fn memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        if self.limits.memory_growing(current, desired, maximum)? {
+            return Ok(true);
+        }
+        let desired = desired as u64;
+        match self.max {
+            Some(max) if desired > max => {
+                self.exceeded = Some(desired);
+                Err(wasmtime::Error::msg(format!(
+                    "memory growth to {desired} bytes exceeds the limit of {max} bytes"
+                )))
+            }
+            // Past the memory's own declared maximum.
+            _ => Ok(false),
+        }
+    }
+
+    fn table_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        self.limits.table_growing(current, desired, maximum)
+    }
+
+    fn instances(&self) -> usize {
+        self.limits.instances()
+    }
+
+    fn tables(&self) -> usize {
+        self.limits.tables()
+    }
+
+    fn memories(&self) -> usize {
+        self.limits.memories()
+    }
+}
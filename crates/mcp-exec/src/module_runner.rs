@@ -0,0 +1,270 @@
+//! Runner for plain core wasm modules exporting the legacy `exec` ABI.
+//!
+//! Tools that predate components were built as core modules exporting
+//! `memory`, `alloc(len: i32) -> i32`, and `exec(ptr: i32, len: i32) -> i32`.
+//! The host allocates the request through `alloc`, writes it there as JSON
+//! `{"action": ..., "args": ...}`, and calls `exec`. The returned pointer
+//! addresses a little-endian `u32` length followed by that many bytes of JSON
+//! result. Such modules may not import anything, so they get no WASI, HTTP, or
+//! secrets access. [`crate::exec`] and [`crate::Executor`] route core modules
+//! with these exports here, so callers keep using [`ExecRequest`] while the
+//! tools migrate to components.
+
+use std::time::Instant;
+
+use serde_json::{Value, json};
+use wasmtime::{Engine, Instance, Memory, Module, Store};
+
+use crate::ExecRequest;
+use crate::config::RuntimePolicy;
+use crate::cost::{self, ExecutionCost};
+use crate::debug_dump;
+use crate::epoch;
+use crate::error::RunnerError;
+use crate::memory::MemoryLimiter;
+use crate::preview1;
+use crate::runner::{self, ExecutionContext, Runner};
+use crate::verify::VerifiedArtifact;
+
+pub const EXEC_EXPORT: &str = "exec";
+pub const ALLOC_EXPORT: &str = "alloc";
+pub const MEMORY_EXPORT: &str = "memory";
+
+/// [`Runner`] for artifacts that are legacy core modules.
+pub struct ModuleRunner {
+    engine: Engine,
+}
+
+impl ModuleRunner {
+    pub fn new(runtime: &RuntimePolicy) -> Result<Self, RunnerError> {
+        Ok(Self {
+            engine: runner::build_engine(runtime)?,
+        })
+    }
+}
+
+impl Runner for ModuleRunner {
+    fn run(
+        &self,
+        request: &ExecRequest,
+        artifact: &VerifiedArtifact,
+        ctx: ExecutionContext<'_>,
+    ) -> Result<Value, RunnerError> {
+        let module = Module::from_binary(&self.engine, &artifact.resolved.bytes)?;
+        if !exports_legacy_abi(&module) {
+            return Err(RunnerError::CoreModule(format!(
+                "module does not export `{MEMORY_EXPORT}`, `{ALLOC_EXPORT}`, and `{EXEC_EXPORT}`"
+            )));
+        }
+        let engine = self.engine.clone();
+        let runtime = ctx.runtime.clone();
+        let result = runner::run_with_timeout(runtime.per_call_timeout, {
+            let request = request.clone();
+            move || call(&engine, &module, &request, &runtime)
+        });
+        debug_dump::capture(
+            ctx.runtime,
+            &artifact.resolved,
+            request,
+            Vec::new(),
+            &result,
+        );
+        result
+    }
+}
+
+/// Compile `bytes` if it is a core module exporting the legacy ABI.
+pub(crate) fn load(engine: &Engine, bytes: &[u8]) -> Option<Module> {
+    if !preview1::is_core_module(bytes) {
+        return None;
+    }
+    Module::from_binary(engine, bytes)
+        .ok()
+        .filter(exports_legacy_abi)
+}
+
+fn exports_legacy_abi(module: &Module) -> bool {
+    [MEMORY_EXPORT, ALLOC_EXPORT, EXEC_EXPORT]
+        .iter()
+        .all(|name| module.get_export(name).is_some())
+}
+
+/// Run `request` against a fresh instance of `module`.
+///
+/// The execution's [`ExecutionCost`] is attached to the result under [`cost::COST_FIELD`].
+pub(crate) fn call(
+    engine: &Engine,
+    module: &Module,
+    request: &ExecRequest,
+    runtime: &RuntimePolicy,
+) -> Result<Value, RunnerError> {
+    if let Some(import) = module.imports().next() {
+        return Err(RunnerError::CoreModule(format!(
+            "legacy modules cannot import host functions, found `{}::{}`",
+            import.module(),
+            import.name()
+        )));
+    }
+
+    let mut store = Store::new(engine, MemoryLimiter::new(runtime.max_memory));
+    store.limiter(|limiter| limiter);
+    store.set_epoch_deadline(epoch::deadline(runtime.wallclock_timeout));
+    cost::refuel(&mut store, runtime)?;
+    let instance = Instance::new(&mut store, module, &[]).map_err(|err| {
+        store
+            .data()
+            .exceeded()
+            .unwrap_or(RunnerError::Wasmtime(err))
+    })?;
+    let memory = instance
+        .get_memory(&mut store, MEMORY_EXPORT)
+        .ok_or_else(|| RunnerError::CoreModule(format!("`{MEMORY_EXPORT}` is not a memory")))?;
+    let alloc = instance.get_typed_func::<i32, i32>(&mut store, ALLOC_EXPORT)?;
+    let exec = instance.get_typed_func::<(i32, i32), i32>(&mut store, EXEC_EXPORT)?;
+
+    let input = serde_json::to_vec(&json!({
+        "action": request.action,
+        "args": request.args_value()?,
+    }))?;
+    let len = i32::try_from(input.len())
+        .map_err(|_| RunnerError::Internal("request too large for a legacy module".into()))?;
+
+    let started = Instant::now();
+    let ptr = alloc.call(&mut store, len).map_err(|err| {
+        store
+            .data()
+            .exceeded()
+            .unwrap_or(RunnerError::Wasmtime(err))
+    })?;
+    write(&memory, &mut store, ptr, &input)?;
+    // Fuel burned by the start function and `alloc` is not charged to the call.
+    cost::refuel(&mut store, runtime)?;
+    let out = match exec.call(&mut store, (ptr, len)) {
+        Ok(out) => out,
+        Err(trap) => {
+            let message = trap.to_string();
+            if message.contains("transient.") {
+                return Err(RunnerError::ToolTransient {
+                    component: request.component.clone(),
+                    message,
+                });
+            }
+            return Err(store
+                .data()
+                .exceeded()
+                .or_else(|| cost::fuel_exhausted(&store, runtime))
+                .unwrap_or_else(|| {
+                    epoch::call_error(started, runtime.wallclock_timeout, message)
+                }));
+        }
+    };
+    if started.elapsed() > runtime.wallclock_timeout {
+        return Err(RunnerError::Timeout {
+            elapsed: started.elapsed(),
+        });
+    }
+
+    let mut value: Value = serde_json::from_slice(&read_output(&memory, &store, out)?)?;
+    let cost = ExecutionCost {
+        fuel: cost::fuel_used(&store, runtime),
+        wallclock: started.elapsed(),
+        http_bytes_sent: 0,
+        http_bytes_received: 0,
+    };
+    cost::attach(&mut value, &cost);
+    Ok(value)
+}
+
+fn write(
+    memory: &Memory,
+    store: &mut Store<MemoryLimiter>,
+    ptr: i32,
+    bytes: &[u8],
+) -> Result<(), RunnerError> {
+    memory
+        .write(store, ptr as u32 as usize, bytes)
+        .map_err(|_| RunnerError::Internal(format!("`{ALLOC_EXPORT}` returned an invalid pointer")))
+}
+
+/// Read the length-prefixed result `exec` left at `ptr`.
+fn read_output(
+    memory: &Memory,
+    store: &Store<MemoryLimiter>,
+    ptr: i32,
+) -> Result<Vec<u8>, RunnerError> {
+    let invalid = || RunnerError::Internal(format!("`{EXEC_EXPORT}` returned an invalid pointer"));
+    let data = memory.data(store);
+    let start = ptr as u32 as usize;
+    let header = data.get(start..start.checked_add(4).ok_or_else(invalid)?);
+    let len = u32::from_le_bytes(header.ok_or_else(invalid)?.try_into().expect("4 bytes")) as usize;
+    let body = start + 4;
+    data.get(body..body.checked_add(len).ok_or_else(invalid)?)
+        .map(<[u8]>::to_vec)
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Echoes the request back as `{"echo": <request>}`, one page of memory.
+    const ECHO: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+          (data (i32.const 0) "{\"echo\":")
+          (func (export "exec") (param $ptr i32) (param $len i32) (result i32)
+            ;; [512] = len + 9, [516..524] = prefix, [524..] = request, then "}".
+            (i32.store (i32.const 512) (i32.add (local.get $len) (i32.const 9)))
+            (memory.copy (i32.const 516) (i32.const 0) (i32.const 8))
+            (memory.copy (i32.const 524) (local.get $ptr) (local.get $len))
+            (i32.store8 (i32.add (i32.const 524) (local.get $len)) (i32.const 125))
+            (i32.const 512)))
+    "#;
+
+    fn request() -> ExecRequest {
+        ExecRequest {
+            component: "legacy".into(),
+            action: "ping".into(),
+            args: json!({"n": 1}),
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Default::default(),
+        }
+    }
+
+    #[test]
+    fn runs_modules_exporting_the_legacy_abi() {
+        let runtime = RuntimePolicy::default();
+        let engine = runner::build_engine(&runtime).expect("engine");
+        let wasm = wat::parse_str(ECHO).expect("module");
+        let module = load(&engine, &wasm).expect("legacy module");
+
+        let value = call(&engine, &module, &request(), &runtime).expect("call");
+        assert_eq!(value["echo"], json!({"action": "ping", "args": {"n": 1}}));
+        assert!(value.get(cost::COST_FIELD).is_some());
+
+        let plain = wat::parse_str(r#"(module (memory (export "memory") 1))"#).expect("module");
+        assert!(load(&engine, &plain).is_none());
+        let component = wat::parse_str("(component)").expect("component");
+        assert!(load(&engine, &component).is_none());
+
+        let importing = wat::parse_str(
+            r#"(module
+                 (import "env" "log" (func))
+                 (memory (export "memory") 1)
+                 (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                 (func (export "exec") (param i32 i32) (result i32) (i32.const 0)))"#,
+        )
+        .expect("module");
+        let module = load(&engine, &importing).expect("legacy module");
+        let err = call(&engine, &module, &request(), &runtime).expect_err("imports");
+        assert!(matches!(err, RunnerError::CoreModule(_)), "{err}");
+    }
+}
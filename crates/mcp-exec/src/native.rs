@@ -0,0 +1,53 @@
+//! In-host routers implemented in plain Rust.
+//!
+//! Trusted first-party tools can implement [`NativeRouter`] instead of being
+//! compiled to a `wasix:mcp` router component. An [`crate::Executor`] serves
+//! registered native routers alongside wasm ones: requests for a registered
+//! component skip resolution, verification, and the wasm boundary, and their
+//! results are rendered exactly like router component responses. Calls still
+//! run on the executor's worker pool under
+//! [`RuntimePolicy::per_call_timeout`](crate::RuntimePolicy::per_call_timeout),
+//! and their wallclock time is reported like a wasm call's cost. A native call
+//! cannot be interrupted: past the timeout the caller gets
+//! [`RunnerError::Timeout`] while the call finishes on its worker.
+
+use std::time::Instant;
+
+use serde_json::Value;
+
+use crate::cost::{self, ExecutionCost};
+use crate::error::RunnerError;
+use crate::router::{Response, Tool, ToolError, render_response, tool_error_to_value};
+
+/// Rust mirror of the `wasix:mcp/router` tool surface.
+///
+/// Arguments are passed as a JSON string, as in the WIT `call-tool` export.
+pub trait NativeRouter: Send + Sync {
+    /// Programmatic router name.
+    fn name(&self) -> String;
+
+    /// Tools exposed by this router.
+    fn list_tools(&self) -> Result<Vec<Tool>, ToolError>;
+
+    /// Invoke a tool with JSON-encoded arguments.
+    fn call_tool(&self, tool_name: &str, arguments: &str) -> Result<Response, ToolError>;
+}
+
+/// Call `tool` on a native router and render the outcome like a wasm router.
+pub(crate) fn call(
+    router: &dyn NativeRouter,
+    tool: &str,
+    arguments: &str,
+) -> Result<Value, RunnerError> {
+    let started = Instant::now();
+    let mut value = match router.call_tool(tool, arguments) {
+        Ok(response) => render_response(&response),
+        Err(err) => tool_error_to_value(tool, err),
+    };
+    let cost = ExecutionCost {
+        wallclock: started.elapsed(),
+        ..ExecutionCost::default()
+    };
+    cost::attach(&mut value, &cost);
+    Ok(value)
+}
@@ -0,0 +1,142 @@
+//! Concurrent fan-out of independent calls, see [`crate::Executor::call_parallel`].
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{Value, json};
+
+use crate::ExecRequest;
+use crate::error::ExecError;
+
+/// Outcome of one call of a parallel fan-out.
+#[derive(Debug)]
+pub struct ParallelEntry {
+    pub component: String,
+    pub action: String,
+    pub elapsed: Duration,
+    pub result: Result<Value, ExecError>,
+}
+
+impl ParallelEntry {
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// The entry as `{component, action, status, elapsed_ms}` plus either
+    /// `result` or `error`.
+    pub fn to_value(&self) -> Value {
+        let mut entry = json!({
+            "component": self.component,
+            "action": self.action,
+            "status": if self.is_ok() { "ok" } else { "error" },
+            "elapsed_ms": self.elapsed.as_millis() as u64,
+        });
+        match &self.result {
+            Ok(value) => entry["result"] = value.clone(),
+            Err(err) => entry["error"] = error_value(err),
+        }
+        entry
+    }
+}
+
+/// Outcomes of a parallel fan-out, in request order.
+#[derive(Debug, Default)]
+pub struct ParallelReport {
+    pub entries: Vec<ParallelEntry>,
+}
+
+impl ParallelReport {
+    pub fn succeeded(&self) -> usize {
+        self.entries.iter().filter(|entry| entry.is_ok()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.entries.len() - self.succeeded()
+    }
+
+    /// Aggregate as `{results: [...], succeeded, failed}`.
+    pub fn to_value(&self) -> Value {
+        json!({
+            "results": self.entries.iter().map(ParallelEntry::to_value).collect::<Vec<_>>(),
+            "succeeded": self.succeeded(),
+            "failed": self.failed(),
+        })
+    }
+}
+
+fn error_value(err: &ExecError) -> Value {
+    let kind = match err {
+        ExecError::Resolve { .. } => "resolve",
+        ExecError::Verification { .. } => "verification",
+        ExecError::Runner { .. } => "runner",
+        ExecError::NotFound { .. } => "not-found",
+        ExecError::Tool { .. } => "tool",
+        ExecError::PermissionDenied { .. } => "permission-denied",
+        ExecError::Denied { .. } => "denied",
+        ExecError::MissingSecrets { .. } => "missing-secrets",
+        ExecError::Quarantined { .. } => "quarantined",
+    };
+    let mut error = json!({ "kind": kind, "message": err.to_string() });
+    if let ExecError::Tool { code, payload, .. } = err {
+        error["code"] = json!(code);
+        error["payload"] = payload.clone();
+    }
+    error
+}
+
+/// Run `call` over `requests` on at most `max_concurrency` threads.
+pub(crate) fn run(
+    requests: Vec<ExecRequest>,
+    max_concurrency: usize,
+    call: impl Fn(ExecRequest) -> Result<Value, ExecError> + Sync,
+) -> ParallelReport {
+    let total = requests.len();
+    let pending: Vec<Mutex<Option<ExecRequest>>> = requests
+        .into_iter()
+        .map(|req| Mutex::new(Some(req)))
+        .collect();
+    let done: Vec<Mutex<Option<ParallelEntry>>> = (0..total).map(|_| Mutex::new(None)).collect();
+    let next = AtomicUsize::new(0);
+
+    thread::scope(|scope| {
+        for _ in 0..max_concurrency.clamp(1, total.max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(slot) = pending.get(index) else {
+                        break;
+                    };
+                    let req = lock(slot).take().expect("each request is taken once");
+                    let (component, action) = (req.component.clone(), req.action.clone());
+                    let started = Instant::now();
+                    let result = call(req);
+                    *lock(&done[index]) = Some(ParallelEntry {
+                        component,
+                        action,
+                        elapsed: started.elapsed(),
+                        result,
+                    });
+                }
+            });
+        }
+    });
+
+    ParallelReport {
+        entries: done
+            .into_iter()
+            .map(|slot| {
+                slot.into_inner()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .expect("every request ran")
+            })
+            .collect(),
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
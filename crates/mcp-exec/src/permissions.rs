@@ -0,0 +1,251 @@
+//! Declarative tool permissions shared by wasm and remote tools.
+//!
+//! A [`ToolPermissions`] document lists which tools a flow may call, how often,
+//! which need explicit approval, and the data classifications they handle. It
+//! is plain serde data so it can ship alongside flows as JSON or YAML. The
+//! [`PermissionGate`] enforces one document and keeps the call counts: the
+//! executor consults it through [`crate::RuntimePolicy::tool_permissions`], and
+//! remote routers in `greentic-mcp` accept the same gate, so one artifact
+//! governs both.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Permission document; a tool matching no rule is denied.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolPermissions {
+    /// Rules in priority order; the first rule matching a call applies.
+    pub tools: Vec<ToolRule>,
+    /// Data classifications the caller may handle. Tools tagged with any other
+    /// classification are denied. Empty allows every classification.
+    pub allowed_classifications: Vec<String>,
+}
+
+/// Permission for one tool or a family of tools.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolRule {
+    /// Component (wasm) or server name (remote) the rule applies to; any when `None`.
+    pub component: Option<String>,
+    /// Tool name, or a prefix followed by `*`.
+    pub tool: String,
+    /// Calls allowed over the lifetime of the [`PermissionGate`].
+    pub max_calls: Option<u64>,
+    /// Destructive tool: every call needs a prior [`PermissionGate::approve`].
+    pub requires_approval: bool,
+    /// Classification tags of the data the tool reads or writes, e.g. `pii`.
+    pub classifications: Vec<String>,
+}
+
+impl ToolRule {
+    fn matches(&self, component: &str, tool: &str) -> bool {
+        self.component
+            .as_deref()
+            .is_none_or(|expected| expected == component)
+            && match self.tool.strip_suffix('*') {
+                Some(prefix) => tool.starts_with(prefix),
+                None => self.tool == tool,
+            }
+    }
+}
+
+/// Reason a call was refused by a [`PermissionGate`].
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum PermissionDenied {
+    #[error("tool `{tool}` on `{component}` is not permitted")]
+    NotAllowed { component: String, tool: String },
+    #[error("tool `{tool}` on `{component}` exhausted its {max_calls} permitted calls")]
+    CallLimit {
+        component: String,
+        tool: String,
+        max_calls: u64,
+    },
+    #[error("tool `{tool}` on `{component}` requires approval")]
+    ApprovalRequired { component: String, tool: String },
+    #[error("tool `{tool}` on `{component}` handles `{classification}` data, which is not allowed")]
+    Classification {
+        component: String,
+        tool: String,
+        classification: String,
+    },
+}
+
+#[derive(Default)]
+struct Usage {
+    calls: HashMap<(String, String), u64>,
+    approvals: HashMap<(String, String), u32>,
+}
+
+/// Enforces a [`ToolPermissions`] document and tracks its call budgets.
+///
+/// Share one gate (behind an `Arc`) between every executor and router that
+/// serves the same flow run so limits apply across them.
+pub struct PermissionGate {
+    permissions: ToolPermissions,
+    usage: Mutex<Usage>,
+}
+
+impl PermissionGate {
+    pub fn new(permissions: ToolPermissions) -> Self {
+        Self {
+            permissions,
+            usage: Mutex::new(Usage::default()),
+        }
+    }
+
+    pub fn permissions(&self) -> &ToolPermissions {
+        &self.permissions
+    }
+
+    /// Approve the next call of a tool that requires approval.
+    ///
+    /// Approvals are single-use and do not expire.
+    pub fn approve(&self, component: &str, tool: &str) {
+        *self
+            .lock()
+            .approvals
+            .entry((component.to_string(), tool.to_string()))
+            .or_default() += 1;
+    }
+
+    /// Admit one call, consuming its budget and approval.
+    pub fn check(&self, component: &str, tool: &str) -> Result<(), PermissionDenied> {
+        let Some(rule) = self
+            .permissions
+            .tools
+            .iter()
+            .find(|rule| rule.matches(component, tool))
+        else {
+            return Err(PermissionDenied::NotAllowed {
+                component: component.to_string(),
+                tool: tool.to_string(),
+            });
+        };
+
+        let allowed = &self.permissions.allowed_classifications;
+        if !allowed.is_empty()
+            && let Some(classification) = rule
+                .classifications
+                .iter()
+                .find(|tag| !allowed.contains(tag))
+        {
+            return Err(PermissionDenied::Classification {
+                component: component.to_string(),
+                tool: tool.to_string(),
+                classification: classification.clone(),
+            });
+        }
+
+        let key = (component.to_string(), tool.to_string());
+        let mut usage = self.lock();
+        let calls = usage.calls.get(&key).copied().unwrap_or(0);
+        if let Some(max_calls) = rule.max_calls
+            && calls >= max_calls
+        {
+            return Err(PermissionDenied::CallLimit {
+                component: component.to_string(),
+                tool: tool.to_string(),
+                max_calls,
+            });
+        }
+        if rule.requires_approval {
+            match usage.approvals.get_mut(&key) {
+                Some(remaining) if *remaining > 0 => *remaining -= 1,
+                _ => {
+                    return Err(PermissionDenied::ApprovalRequired {
+                        component: component.to_string(),
+                        tool: tool.to_string(),
+                    });
+                }
+            }
+        }
+        usage.calls.insert(key, calls + 1);
+        Ok(())
+    }
+
+    /// Calls admitted so far, keyed by component and tool.
+    pub fn usage(&self) -> HashMap<(String, String), u64> {
+        self.lock().calls.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Usage> {
+        self.usage
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl std::fmt::Debug for PermissionGate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PermissionGate")
+            .field("permissions", &self.permissions)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn gate() -> PermissionGate {
+        let permissions: ToolPermissions = serde_json::from_value(json!({
+            "tools": [
+                { "component": "files", "tool": "delete_file", "requires_approval": true },
+                { "component": "files", "tool": "read_*", "max_calls": 2 },
+                { "tool": "lookup_customer", "classifications": ["pii"] },
+                { "tool": "weather" }
+            ],
+            "allowed_classifications": ["public"]
+        }))
+        .expect("permissions document");
+        PermissionGate::new(permissions)
+    }
+
+    #[test]
+    fn enforces_rules_budgets_and_classifications() {
+        let gate = gate();
+        gate.check("any", "weather").expect("allowed everywhere");
+        assert!(matches!(
+            gate.check("files", "write_file"),
+            Err(PermissionDenied::NotAllowed { .. })
+        ));
+
+        gate.check("files", "read_file").expect("first call");
+        gate.check("files", "read_file").expect("second call");
+        assert!(matches!(
+            gate.check("files", "read_file"),
+            Err(PermissionDenied::CallLimit { max_calls: 2, .. })
+        ));
+        gate.check("files", "read_dir").expect("budget is per tool");
+
+        assert_eq!(
+            gate.check("crm", "lookup_customer"),
+            Err(PermissionDenied::Classification {
+                component: "crm".into(),
+                tool: "lookup_customer".into(),
+                classification: "pii".into(),
+            })
+        );
+        assert_eq!(
+            gate.usage()[&("files".to_string(), "read_file".to_string())],
+            2
+        );
+    }
+
+    #[test]
+    fn approvals_are_single_use() {
+        let gate = gate();
+        assert!(matches!(
+            gate.check("files", "delete_file"),
+            Err(PermissionDenied::ApprovalRequired { .. })
+        ));
+        gate.approve("files", "delete_file");
+        gate.check("files", "delete_file").expect("approved call");
+        assert!(gate.check("files", "delete_file").is_err());
+    }
+}
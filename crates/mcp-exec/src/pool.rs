@@ -0,0 +1,478 @@
+//! Keep-warm pool of pre-instantiated components for hot tools.
+//!
+//! Compiling and linking a component dominates cold-call latency. The pool
+//! keeps the pre-linked component for each configured tool together with a
+//! small queue of ready instances, each of which serves exactly one call.
+//! Ready instances are [`InstanceActor`]s, so their stores stay on the thread
+//! that created them.
+//! Slots are discarded when the resolved artifact digest changes, and ready
+//! instances are evicted once they sit idle longer than `max_idle`.
+//! Slots are topped back up on a background thread, so a call returns
+//! without waiting for replacement instances to be created.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+use tracing::warn;
+use wasmtime::Engine;
+use wasmtime::component::{InstancePre, Linker};
+
+use crate::actor::InstanceActor;
+use crate::catalog_gate;
+use crate::config::ExecConfig;
+use crate::error::{ExecError, RunnerError};
+use crate::hooks;
+use crate::preview1;
+use crate::runner::{self, StoreState};
+use crate::transform;
+use crate::verify::VerifiedArtifact;
+use crate::{ExecRequest, RuntimePolicy};
+
+/// Keep-warm settings for a single component.
+#[derive(Clone, Debug)]
+pub struct WarmPoolConfig {
+    /// Component identifier as passed in [`ExecRequest::component`].
+    pub component: String,
+    /// Number of ready instances to keep around.
+    pub size: usize,
+    /// Ready instances idle for longer than this are discarded.
+    pub max_idle: Duration,
+}
+
+struct Slot {
+    digest: String,
+    pre: InstancePre<StoreState>,
+    ready: VecDeque<(Instant, InstanceActor)>,
+}
+
+/// Pool of ready-to-call instances for a fixed set of hot components.
+pub struct WarmPool {
+    slots: Arc<Slots>,
+    configs: HashMap<String, WarmPoolConfig>,
+    /// Slots to top up, served by the refill thread until the pool is dropped.
+    refills: Sender<Refill>,
+}
+
+/// Slots and what it takes to fill them, shared with the refill thread.
+struct Slots {
+    engine: Engine,
+    linker: Linker<StoreState>,
+    slots: Mutex<HashMap<String, Slot>>,
+}
+
+struct Refill {
+    config: WarmPoolConfig,
+    cfg: ExecConfig,
+}
+
+impl WarmPool {
+    /// Create an empty pool; slots are filled on first use of each component.
+    pub fn new(
+        runtime: &RuntimePolicy,
+        configs: impl IntoIterator<Item = WarmPoolConfig>,
+    ) -> Result<Self, RunnerError> {
+        let engine = runner::build_engine(runtime)?;
+        let linker = runner::build_linker(&engine)?;
+        let configs = configs
+            .into_iter()
+            .map(|config| (config.component.clone(), config))
+            .collect();
+        let slots = Arc::new(Slots {
+            engine,
+            linker,
+            slots: Mutex::new(HashMap::new()),
+        });
+        let (refills, requests) = mpsc::channel::<Refill>();
+        let refiller = Arc::clone(&slots);
+        thread::Builder::new()
+            .name("mcp-exec-warm-refill".into())
+            .spawn(move || {
+                for Refill { config, cfg } in requests {
+                    if let Err(err) = refiller.refill(&config, &cfg) {
+                        warn!(component = %config.component, %err, "warm pool refill failed");
+                    }
+                }
+            })
+            .map_err(|err| RunnerError::Internal(format!("spawn warm pool refill: {err}")))?;
+        Ok(Self {
+            slots,
+            configs,
+            refills,
+        })
+    }
+
+    /// Whether `component` is configured to be kept warm.
+    pub fn is_warm(&self, component: &str) -> bool {
+        self.configs.contains_key(component)
+    }
+
+    /// Number of ready instances currently held for `component`.
+    pub fn ready_count(&self, component: &str) -> usize {
+        self.slots
+            .lock()
+            .get(component)
+            .map_or(0, |slot| slot.ready.len())
+    }
+
+    /// Drop the slot for `component`, e.g. after it was republished.
+    pub fn evict(&self, component: &str) {
+        self.slots.lock().remove(component);
+    }
+
+    /// Drop ready instances that have been idle longer than their `max_idle`.
+    pub fn evict_idle(&self) {
+        let now = Instant::now();
+        let mut slots = self.slots.lock();
+        for (component, slot) in slots.iter_mut() {
+            if let Some(config) = self.configs.get(component) {
+                slot.ready
+                    .retain(|(since, _)| now.duration_since(*since) <= config.max_idle);
+            }
+        }
+    }
+
+    /// Execute a request, serving configured components from warm instances.
+    ///
+    /// Components without a keep-warm configuration go through [`crate::exec`].
+    /// Warm instances are created with the host settings of the refilling call,
+    /// so a pool should only be shared by callers using the same [`ExecConfig`].
+    pub fn exec(&self, req: ExecRequest, cfg: &ExecConfig) -> Result<Value, ExecError> {
+        transform::around(cfg.transforms.as_ref(), req, |req| {
+            hooks::around(cfg.hooks.as_ref(), req, |req| {
+                self.exec_transformed(req, cfg)
+            })
+        })
+    }
+
+    fn exec_transformed(&self, req: ExecRequest, cfg: &ExecConfig) -> Result<Value, ExecError> {
+        crate::check_permissions(&req, cfg)?;
+        crate::secrets_preflight::check(&req, cfg)?;
+        let Some(config) = self.configs.get(&req.component) else {
+            return crate::exec_admitted(req, cfg);
+        };
+
+        let verified = crate::resolve_and_verify(&req, cfg)?;
+        let ready = match self.checkout(config, &verified, cfg)? {
+            Some(ready) => ready,
+            // Not a component binary (e.g. a JSON mock); use the regular path.
+            None => return crate::exec_admitted(req, cfg),
+        };
+
+        // The refill thread only stops once the pool is dropped.
+        let _ = self.refills.send(Refill {
+            config: config.clone(),
+            cfg: cfg.clone(),
+        });
+        let mut result = ready.call(req.clone(), verified.resolved.clone(), cfg.runtime.clone());
+
+        crate::cost::report(cfg, &req, &mut result);
+        crate::finish(req, result)
+    }
+
+    /// Take a ready instance for the verified artifact, instantiating one on a miss.
+    ///
+    /// New slots pass the catalog gate before they are created. Compiling,
+    /// linking, gating, and instantiating all happen outside the slots lock,
+    /// so other components' calls and refills are not held up behind them.
+    fn checkout(
+        &self,
+        config: &WarmPoolConfig,
+        verified: &VerifiedArtifact,
+        cfg: &ExecConfig,
+    ) -> Result<Option<InstanceActor>, ExecError> {
+        let runner_err = |err: RunnerError| ExecError::runner(&config.component, err);
+        let digest = &verified.resolved.digest;
+        let engine = &self.slots.engine;
+
+        let current = {
+            let mut slots = self.slots.lock();
+            match slots.get_mut(&config.component) {
+                Some(slot) if slot.digest == *digest => {
+                    let now = Instant::now();
+                    while let Some((since, ready)) = slot.ready.pop_front() {
+                        if now.duration_since(since) <= config.max_idle {
+                            return Ok(Some(ready));
+                        }
+                    }
+                    Some(slot.pre.clone())
+                }
+                _ => None,
+            }
+        };
+
+        let pre = match current {
+            Some(pre) => pre,
+            None => {
+                let Ok(component) =
+                    preview1::compile(engine, &verified.resolved.bytes, &cfg.runtime)
+                else {
+                    return Ok(None);
+                };
+                let pre =
+                    runner::prepare_instance(engine, &self.slots.linker, &component, &cfg.runtime)
+                        .map_err(runner_err)?;
+                catalog_gate::check(engine, &pre, &config.component, cfg)?;
+                let mut slots = self.slots.lock();
+                match slots.get(&config.component) {
+                    // Another call set up the same artifact meanwhile; keep
+                    // its slot and the instances already made for it.
+                    Some(slot) if slot.digest == *digest => slot.pre.clone(),
+                    _ => {
+                        slots.insert(
+                            config.component.clone(),
+                            Slot {
+                                digest: digest.clone(),
+                                pre: pre.clone(),
+                                ready: VecDeque::new(),
+                            },
+                        );
+                        pre
+                    }
+                }
+            }
+        };
+
+        self.slots
+            .instantiate(&pre, cfg)
+            .map(Some)
+            .map_err(runner_err)
+    }
+}
+
+impl Slots {
+    /// Top the slot for `config` back up to its configured size.
+    fn refill(&self, config: &WarmPoolConfig, cfg: &ExecConfig) -> Result<(), RunnerError> {
+        loop {
+            let (digest, pre) = {
+                let slots = self.lock();
+                match slots.get(&config.component) {
+                    Some(slot) if slot.ready.len() < config.size => {
+                        (slot.digest.clone(), slot.pre.clone())
+                    }
+                    _ => return Ok(()),
+                }
+            };
+            let ready = self.instantiate(&pre, cfg)?;
+            let mut slots = self.lock();
+            match slots.get_mut(&config.component) {
+                // A reload may have replaced the slot while we instantiated;
+                // an instance of the old artifact must not land in the new one.
+                Some(slot) if slot.digest == digest && slot.ready.len() < config.size => {
+                    slot.ready.push_back((Instant::now(), ready));
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn instantiate(
+        &self,
+        pre: &InstancePre<StoreState>,
+        cfg: &ExecConfig,
+    ) -> Result<InstanceActor, RunnerError> {
+        InstanceActor::spawn(
+            self.engine.clone(),
+            pre.clone(),
+            cfg.runtime.clone(),
+            cfg.http_enabled,
+            cfg.secrets_store.clone(),
+        )
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Slot>> {
+        self.slots
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::stress::echo_component;
+    use crate::{ToolStore, VerifyPolicy};
+    use serde_json::json;
+
+    fn config(dir: &Path) -> ExecConfig {
+        ExecConfig {
+            store: ToolStore::LocalDir(dir.to_path_buf()),
+            security: VerifyPolicy {
+                allow_unverified: true,
+                ..Default::default()
+            },
+            runtime: RuntimePolicy::default(),
+            http_enabled: false,
+            secrets_store: None,
+            cost_sink: None,
+            transforms: None,
+            hooks: None,
+        }
+    }
+
+    fn echo_pool(cfg: &ExecConfig, max_idle: Duration) -> WarmPool {
+        WarmPool::new(
+            &cfg.runtime,
+            [WarmPoolConfig {
+                component: "echo".into(),
+                size: 2,
+                max_idle,
+            }],
+        )
+        .expect("pool")
+    }
+
+    fn request(component: &str) -> ExecRequest {
+        ExecRequest {
+            component: component.into(),
+            action: "echo".into(),
+            args: json!({}),
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Default::default(),
+        }
+    }
+
+    /// Wait for the refill thread to bring `component` to `count` ready instances.
+    fn wait_for_ready(pool: &WarmPool, component: &str, count: usize) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while pool.ready_count(component) != count {
+            assert!(Instant::now() < deadline, "slot never reached {count}");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn calls_take_warm_instances_refilled_in_the_background() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(tmp.path().join("echo.wasm"), echo_component(1)).expect("write echo");
+        let cfg = config(tmp.path());
+        let pool = echo_pool(&cfg, Duration::from_secs(60));
+
+        let value = pool.exec(request("echo"), &cfg).expect("cold call");
+        assert_eq!(value["version"], json!(1));
+        wait_for_ready(&pool, "echo", 2);
+
+        let verified = crate::resolve_and_verify(&request("echo"), &cfg).expect("verify");
+        let config = &pool.configs["echo"];
+        let ready = pool
+            .checkout(config, &verified, &cfg)
+            .expect("checkout")
+            .expect("component");
+        assert_eq!(pool.ready_count("echo"), 1, "served from the warm queue");
+        let value = ready
+            .call(
+                request("echo"),
+                verified.resolved.clone(),
+                cfg.runtime.clone(),
+            )
+            .expect("warm call");
+        assert_eq!(value["version"], json!(1));
+
+        pool.exec(request("echo"), &cfg).expect("warm exec");
+        wait_for_ready(&pool, "echo", 2);
+    }
+
+    #[test]
+    fn idle_and_evicted_instances_are_dropped() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(tmp.path().join("echo.wasm"), echo_component(1)).expect("write echo");
+        let cfg = config(tmp.path());
+        let pool = echo_pool(&cfg, Duration::from_millis(50));
+
+        pool.exec(request("echo"), &cfg).expect("cold call");
+        wait_for_ready(&pool, "echo", 2);
+        thread::sleep(Duration::from_millis(100));
+        pool.evict_idle();
+        assert_eq!(pool.ready_count("echo"), 0);
+
+        pool.exec(request("echo"), &cfg)
+            .expect("call after idle eviction");
+        wait_for_ready(&pool, "echo", 2);
+        pool.evict("echo");
+        assert_eq!(pool.ready_count("echo"), 0);
+        assert!(!pool.slots.lock().contains_key("echo"));
+    }
+
+    #[test]
+    fn a_new_digest_replaces_the_slot() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(tmp.path().join("echo.wasm"), echo_component(1)).expect("write echo");
+        let cfg = config(tmp.path());
+        let pool = echo_pool(&cfg, Duration::from_secs(60));
+        let digest = |pool: &WarmPool| pool.slots.lock()["echo"].digest.clone();
+
+        pool.exec(request("echo"), &cfg).expect("first version");
+        wait_for_ready(&pool, "echo", 2);
+        let first = digest(&pool);
+
+        std::fs::write(tmp.path().join("echo.wasm"), echo_component(2)).expect("republish");
+        let value = pool.exec(request("echo"), &cfg).expect("second version");
+        assert_eq!(
+            value["version"],
+            json!(2),
+            "no instance of the old artifact"
+        );
+        wait_for_ready(&pool, "echo", 2);
+        assert_ne!(digest(&pool), first);
+
+        let value = pool
+            .exec(request("echo"), &cfg)
+            .expect("warm second version");
+        assert_eq!(value["version"], json!(2));
+    }
+
+    #[test]
+    fn unconfigured_and_mock_components_use_regular_exec() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            tmp.path().join("mock.wasm"),
+            br#"{"_mock_mcp_exec": true, "responses": {"ping": {"pong": true}}}"#,
+        )
+        .expect("write mock");
+
+        let cfg = ExecConfig {
+            store: ToolStore::LocalDir(tmp.path().to_path_buf()),
+            security: VerifyPolicy {
+                allow_unverified: true,
+                ..Default::default()
+            },
+            runtime: RuntimePolicy::default(),
+            http_enabled: false,
+            secrets_store: None,
+            cost_sink: None,
+            transforms: None,
+            hooks: None,
+        };
+        let pool = WarmPool::new(
+            &cfg.runtime,
+            [WarmPoolConfig {
+                component: "mock".into(),
+                size: 2,
+                max_idle: Duration::from_secs(60),
+            }],
+        )
+        .expect("pool");
+        assert!(pool.is_warm("mock"));
+        assert!(!pool.is_warm("other"));
+
+        let req = ExecRequest {
+            component: "mock".into(),
+            action: "ping".into(),
+            args: json!({}),
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Default::default(),
+        };
+        let value = pool.exec(req, &cfg).expect("mock exec");
+        assert_eq!(value, json!({"pong": true}));
+        assert_eq!(pool.ready_count("mock"), 0);
+    }
+}
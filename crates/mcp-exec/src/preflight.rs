@@ -0,0 +1,224 @@
+//! Pre-flight diagnostics comparing a component's imports with a linker.
+//!
+//! Wasmtime link errors only name the first unresolved import and say nothing
+//! about how to fix it. The report produced here lists every import the
+//! linker cannot satisfy, together with a remediation hint. It asks the
+//! linker itself, so interface versions and the functions each interface
+//! defines count: an import the linker has under an incompatible version, or
+//! without a function the component needs, is reported like a missing one.
+
+use std::fmt;
+
+use wasmtime::component::types::ComponentItem;
+use wasmtime::component::{Component, Linker, LinkerInstance, ResourceType};
+use wasmtime::{Engine, bail};
+
+/// Known-but-unsupported import families mapped to remediation hints.
+const HINTS: &[(&str, &str)] = &[
+    (
+        "wasi:http/incoming-handler",
+        "components are invoked through wasix:mcp/router or exec; serving HTTP is not supported",
+    ),
+    (
+        "wasi:keyvalue/",
+        "use the runner-host KV import from greentic-interfaces instead",
+    ),
+    (
+        "wasi:config/",
+        "pass configuration through call arguments or describe-json defaults",
+    ),
+    (
+        "greentic:secrets/",
+        "import greentic:secrets/secret-store@1.0.0 and configure ExecConfig.secrets_store",
+    ),
+    (
+        "secret_get",
+        "the legacy secret_get import was removed; use greentic:secrets/secret-store@1.0.0",
+    ),
+];
+
+/// Import that the executor host cannot satisfy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingImport {
+    pub name: String,
+    pub hint: Option<&'static str>,
+}
+
+/// Result of diffing a component's imports against the host surface.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub missing: Vec<MissingImport>,
+}
+
+impl ImportReport {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, missing) in self.missing.iter().enumerate() {
+            if idx > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "component needs {} which is not provided", missing.name)?;
+            if let Some(hint) = missing.hint {
+                write!(f, " ({hint})")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// List the component imports that `linker` cannot satisfy.
+///
+/// Pre-instantiation stops at the first unresolved import, so each one found
+/// is stubbed out in a copy of the linker and pre-instantiation retried until
+/// it gets past every import.
+pub fn check_imports<T: 'static>(linker: &Linker<T>, component: &Component) -> ImportReport {
+    let engine = linker.engine();
+    let imports: Vec<(String, ComponentItem)> = component
+        .component_type()
+        .imports(engine)
+        .map(|(name, item)| (name.to_string(), item))
+        .collect();
+    let mut probe = linker.clone();
+    probe.allow_shadowing(true);
+    let mut missing: Vec<MissingImport> = Vec::new();
+    while let Err(err) = probe.instantiate_pre(component) {
+        let message = err.to_string();
+        let Some(name) = unresolved_import(&message) else {
+            break;
+        };
+        let Some((name, item)) = imports.iter().find(|(import, _)| import == name) else {
+            break;
+        };
+        // A stub the linker still rejects would be reported forever.
+        if missing.iter().any(|seen| seen.name == *name) {
+            break;
+        }
+        missing.push(MissingImport {
+            name: name.clone(),
+            hint: hint_for(name),
+        });
+        if stub(&mut probe.root(), name, item, engine).is_err() {
+            break;
+        }
+    }
+    ImportReport { missing }
+}
+
+/// The import named by a linker error such as "component imports instance
+/// `wasi:keyvalue/store@0.2.0`, but a matching implementation was not found".
+fn unresolved_import(message: &str) -> Option<&str> {
+    let rest = message.strip_prefix("component imports ")?;
+    let start = rest.find('`')? + 1;
+    let len = rest[start..].find('`')?;
+    Some(&rest[start..start + len])
+}
+
+/// Define `item` under `name` with functions that trap and placeholder
+/// resources, so pre-instantiation can move on to the next import.
+fn stub<T: 'static>(
+    linker: &mut LinkerInstance<'_, T>,
+    name: &str,
+    item: &ComponentItem,
+    engine: &Engine,
+) -> wasmtime::Result<()> {
+    match item {
+        ComponentItem::ComponentFunc(_) => {
+            let name_owned = name.to_string();
+            linker.func_new(name, move |_, _, _, _| {
+                bail!("`{name_owned}` is not provided by the host")
+            })
+        }
+        ComponentItem::ComponentInstance(instance) => {
+            let mut nested = linker.instance(name)?;
+            for (export, item) in instance.exports(engine) {
+                stub(&mut nested, export, &item, engine)?;
+            }
+            Ok(())
+        }
+        ComponentItem::Resource(_) => {
+            linker.resource(name, ResourceType::host::<()>(), |_, _| Ok(()))
+        }
+        ComponentItem::Type(_) => Ok(()),
+        _ => bail!("cannot stub import `{name}`"),
+    }
+}
+
+fn hint_for(name: &str) -> Option<&'static str> {
+    HINTS
+        .iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .map(|(_, hint)| *hint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RuntimePolicy;
+    use crate::runner;
+
+    #[test]
+    fn reports_unprovided_imports_with_hints() {
+        let wasm = wat::parse_str(
+            r#"(component
+                (import "wasi:clocks/monotonic-clock@0.2.0" (instance
+                    (export "now" (func (result u64)))))
+                (import "wasi:keyvalue/store@0.2.0" (instance
+                    (export "open" (func (param "name" string)))))
+                (import "acme:custom/thing" (instance
+                    (export "run" (func))))
+            )"#,
+        )
+        .expect("wat should parse");
+
+        let runtime = RuntimePolicy::default();
+        let engine = runner::build_engine(&runtime).expect("engine");
+        let linker = runner::build_linker(&engine).expect("linker");
+        let component = Component::from_binary(&engine, &wasm).expect("component");
+
+        let report = check_imports(&linker, &component);
+        let names: Vec<_> = report.missing.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["wasi:keyvalue/store@0.2.0", "acme:custom/thing"]);
+        assert!(report.missing[0].hint.is_some());
+        assert!(report.missing[1].hint.is_none());
+        assert!(
+            report
+                .to_string()
+                .contains("component needs acme:custom/thing which is not provided")
+        );
+    }
+
+    #[test]
+    fn versions_and_functions_are_checked_against_the_linker() {
+        let wasm = wat::parse_str(
+            r#"(component
+                (import "wasi:clocks/monotonic-clock@0.2.0" (instance
+                    (export "now" (func (result u64)))))
+                (import "wasi:clocks/monotonic-clock@0.3.0" (instance
+                    (export "now" (func (result u64)))))
+                (import "wasi:random/random@0.2.0" (instance
+                    (export "get-random-u64" (func (result u64)))
+                    (export "get-random-nothing" (func))))
+            )"#,
+        )
+        .expect("wat should parse");
+        let runtime = RuntimePolicy::default();
+        let engine = runner::build_engine(&runtime).expect("engine");
+        let linker = runner::build_linker(&engine).expect("linker");
+        let component = Component::from_binary(&engine, &wasm).expect("component");
+
+        let report = check_imports(&linker, &component);
+        let names: Vec<_> = report.missing.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "wasi:clocks/monotonic-clock@0.3.0",
+                "wasi:random/random@0.2.0"
+            ]
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! Running `wasm32-wasip1` core modules through the component pipeline.
+//!
+//! Components are compiled as-is. A core module is accepted when the crate is
+//! built with the `wasip1` feature and [`crate::RuntimePolicy::preview1_adapter`]
+//! is set: the module is first wrapped into a component with the
+//! `wasi_snapshot_preview1` adapter, which implements preview1 on top of the
+//! preview2 interfaces the runner links. The result then goes through the same
+//! linking, sandboxing, and limits as any other component.
+
+use wasmtime::Engine;
+use wasmtime::component::Component;
+
+use crate::config::RuntimePolicy;
+use crate::error::RunnerError;
+
+/// Import module name the adapter provides.
+#[cfg(feature = "wasip1")]
+const ADAPTER_NAME: &str = "wasi_snapshot_preview1";
+
+/// Whether `bytes` is a core wasm module rather than a component.
+pub(crate) fn is_core_module(bytes: &[u8]) -> bool {
+    bytes.len() >= 8 && bytes[..4] == *b"\0asm" && bytes[4..8] == [1, 0, 0, 0]
+}
+
+/// Compile an artifact, componentizing a preview1 core module first.
+pub(crate) fn compile(
+    engine: &Engine,
+    bytes: &[u8],
+    runtime: &RuntimePolicy,
+) -> Result<Component, RunnerError> {
+    if !is_core_module(bytes) {
+        return Ok(Component::from_binary(engine, bytes)?);
+    }
+    let component = componentize(bytes, runtime)?;
+    Ok(Component::from_binary(engine, &component)?)
+}
+
+#[cfg(feature = "wasip1")]
+fn componentize(module: &[u8], runtime: &RuntimePolicy) -> Result<Vec<u8>, RunnerError> {
+    let adapter = runtime.preview1_adapter.as_ref().ok_or_else(|| {
+        RunnerError::CoreModule(
+            "set RuntimePolicy::preview1_adapter to run wasm32-wasip1 modules".into(),
+        )
+    })?;
+    wit_component::ComponentEncoder::default()
+        .module(module)
+        .and_then(|encoder| encoder.adapter(ADAPTER_NAME, adapter.bytes()))
+        .and_then(|encoder| encoder.validate(true).encode())
+        .map_err(|err| {
+            RunnerError::CoreModule(format!("adapting the preview1 module failed: {err:#}"))
+        })
+}
+
+#[cfg(not(feature = "wasip1"))]
+fn componentize(_module: &[u8], _runtime: &RuntimePolicy) -> Result<Vec<u8>, RunnerError> {
+    Err(RunnerError::CoreModule(
+        "greentic-mcp-exec was built without the `wasip1` feature".into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn core_modules_need_the_adapter() {
+        let module = wat::parse_str("(module)").expect("core module");
+        let component = wat::parse_str("(component)").expect("component");
+        assert!(is_core_module(&module));
+        assert!(!is_core_module(&component));
+        assert!(!is_core_module(b"{\"_mock_mcp_exec\": true}"));
+
+        let engine = crate::runner::build_engine(&RuntimePolicy::default()).expect("engine");
+        compile(&engine, &component, &RuntimePolicy::default()).expect("component compiles");
+        let Err(err) = compile(&engine, &module, &RuntimePolicy::default()) else {
+            panic!("compiled without an adapter");
+        };
+        assert!(matches!(err, RunnerError::CoreModule(_)), "{err}");
+    }
+}
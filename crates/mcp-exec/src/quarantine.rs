@@ -0,0 +1,150 @@
+//! Crash-loop detection for the [`crate::Executor`].
+//!
+//! A component that traps or times out on every call still costs an
+//! instantiation and a worker slot each time. The tracker counts consecutive
+//! crashes per digest and, past the configured threshold, rejects calls
+//! outright until the cooldown has elapsed.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::QuarantinePolicy;
+use crate::error::RunnerError;
+
+#[derive(Default)]
+struct Streak {
+    failures: u32,
+    until: Option<Instant>,
+}
+
+pub(crate) struct QuarantineTracker {
+    policy: QuarantinePolicy,
+    streaks: Mutex<HashMap<String, Streak>>,
+}
+
+impl QuarantineTracker {
+    pub(crate) fn new(policy: QuarantinePolicy) -> Self {
+        Self {
+            policy,
+            streaks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Time left before `digest` may be called again, if it is quarantined.
+    pub(crate) fn retry_after(&self, digest: &str) -> Option<Duration> {
+        self.retry_after_at(digest, Instant::now())
+    }
+
+    /// Record the outcome of a call made against `digest`.
+    pub(crate) fn record<T>(&self, digest: &str, result: &Result<T, RunnerError>) {
+        let crashed = matches!(
+            result,
+            Err(RunnerError::Timeout { .. }
+                | RunnerError::FuelExhausted { .. }
+                | RunnerError::MemoryLimit { .. }
+                | RunnerError::Wasmtime(_)
+                | RunnerError::Internal(_))
+        );
+        self.record_at(digest, crashed, Instant::now());
+    }
+
+    /// Remaining cooldown per quarantined digest.
+    pub(crate) fn snapshot(&self) -> HashMap<String, Duration> {
+        let now = Instant::now();
+        self.lock()
+            .iter()
+            .filter_map(|(digest, streak)| {
+                let until = streak.until.filter(|until| *until > now)?;
+                Some((digest.clone(), until - now))
+            })
+            .collect()
+    }
+
+    pub(crate) fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn retry_after_at(&self, digest: &str, now: Instant) -> Option<Duration> {
+        let mut streaks = self.lock();
+        let streak = streaks.get_mut(digest)?;
+        let until = streak.until?;
+        if until > now {
+            return Some(until - now);
+        }
+        // Cooldown over: let one probe through, and re-quarantine on its first crash.
+        streak.until = None;
+        streak.failures = self.policy.failure_threshold.saturating_sub(1);
+        None
+    }
+
+    fn record_at(&self, digest: &str, crashed: bool, now: Instant) {
+        let mut streaks = self.lock();
+        if !crashed {
+            streaks.remove(digest);
+            return;
+        }
+        let streak = streaks.entry(digest.to_string()).or_default();
+        streak.failures += 1;
+        if streak.failures >= self.policy.failure_threshold {
+            streak.until = Some(now + self.policy.cooldown);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Streak>> {
+        self.streaks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quarantines_after_threshold_and_probes_after_cooldown() {
+        let tracker = QuarantineTracker::new(QuarantinePolicy {
+            failure_threshold: 2,
+            cooldown: Duration::from_secs(30),
+        });
+        let start = Instant::now();
+
+        tracker.record_at("abc", true, start);
+        assert_eq!(tracker.retry_after_at("abc", start), None);
+        tracker.record_at("abc", true, start);
+        assert_eq!(
+            tracker.retry_after_at("abc", start + Duration::from_secs(10)),
+            Some(Duration::from_secs(20))
+        );
+        assert_eq!(tracker.retry_after_at("other", start), None);
+
+        let later = start + Duration::from_secs(31);
+        assert_eq!(tracker.retry_after_at("abc", later), None);
+        tracker.record_at("abc", true, later);
+        assert!(tracker.retry_after_at("abc", later).is_some());
+    }
+
+    #[test]
+    fn completed_calls_reset_the_streak() {
+        let tracker = QuarantineTracker::new(QuarantinePolicy {
+            failure_threshold: 2,
+            cooldown: Duration::from_secs(30),
+        });
+        tracker.record::<()>(
+            "abc",
+            &Err(RunnerError::Timeout {
+                elapsed: Duration::from_secs(1),
+            }),
+        );
+        tracker.record::<()>(
+            "abc",
+            &Err(RunnerError::ActionNotFound {
+                action: "ping".into(),
+            }),
+        );
+        tracker.record::<()>("abc", &Err(RunnerError::Internal("panic".into())));
+        assert_eq!(tracker.retry_after("abc"), None);
+        assert!(tracker.snapshot().is_empty());
+    }
+}
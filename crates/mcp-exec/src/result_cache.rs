@@ -0,0 +1,306 @@
+//! Result cache for deterministic tools served by an [`crate::Executor`].
+//!
+//! A hit costs a digest lookup and nothing else: no instantiation, no worker
+//! slot, no fuel. Which calls are eligible is decided per component by
+//! [`ResultCachePolicy::components`]; by default a tool is cached only when its
+//! router annotates it `read-only`. Entries are keyed by artifact digest,
+//! tenant, tool, and arguments, so republishing a component or calling for a
+//! different tenant never serves a stale or foreign result.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+
+use serde_json::Value;
+
+use crate::config::{ComponentCaching, ResultCachePolicy};
+use crate::router::Tool;
+
+/// Point-in-time counters of an executor's result cache.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResultCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Results stored after a miss.
+    pub stores: u64,
+    /// Results not stored because they exceeded
+    /// [`ResultCachePolicy::max_entry_bytes`].
+    pub oversized: u64,
+    pub entries: usize,
+}
+
+impl ResultCacheStats {
+    /// Fraction of eligible calls served from cache, in `0.0..=1.0`.
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+struct Entry {
+    stored: Instant,
+    value: Value,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<String, Entry>,
+    /// Read-only tool names per digest, listed once per artifact.
+    read_only: HashMap<String, HashSet<String>>,
+    stats: ResultCacheStats,
+}
+
+pub(crate) struct ResultCache {
+    policy: ResultCachePolicy,
+    state: Mutex<State>,
+}
+
+impl ResultCache {
+    pub(crate) fn new(mut policy: ResultCachePolicy) -> Self {
+        policy.max_entries = policy.max_entries.max(1);
+        Self {
+            policy,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// How calls to `component` are cached.
+    pub(crate) fn mode(&self, component: &str) -> ComponentCaching {
+        self.policy
+            .components
+            .get(component)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Whether the read-only tools of `digest` still need to be listed.
+    pub(crate) fn needs_catalog(&self, digest: &str) -> bool {
+        !self.lock().read_only.contains_key(digest)
+    }
+
+    /// Remember which tools of `digest` are annotated read-only.
+    pub(crate) fn record_catalog(&self, digest: &str, tools: &[Tool]) {
+        let read_only = tools
+            .iter()
+            .filter(|tool| {
+                tool.annotations
+                    .as_ref()
+                    .and_then(|annotations| annotations.read_only)
+                    .unwrap_or(false)
+            })
+            .map(|tool| tool.name.clone())
+            .collect();
+        self.lock().read_only.insert(digest.to_string(), read_only);
+    }
+
+    /// Whether a call of `tool` on `component` at `digest` may be cached.
+    /// Annotation-driven components must have had their catalog recorded.
+    pub(crate) fn eligible(&self, component: &str, digest: &str, tool: &str) -> bool {
+        match self.mode(component) {
+            ComponentCaching::Never => false,
+            ComponentCaching::Always => true,
+            ComponentCaching::ReadOnly => self
+                .lock()
+                .read_only
+                .get(digest)
+                .is_some_and(|tools| tools.contains(tool)),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<Value> {
+        let mut state = self.lock();
+        let fresh = state
+            .entries
+            .get(key)
+            .filter(|entry| entry.stored.elapsed() < self.policy.ttl)
+            .map(|entry| entry.value.clone());
+        match fresh {
+            Some(value) => {
+                state.stats.hits += 1;
+                Some(value)
+            }
+            None => {
+                state.entries.remove(key);
+                state.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub(crate) fn insert(&self, key: String, value: &Value) {
+        let size = serde_json::to_vec(value).map_or(usize::MAX, |bytes| bytes.len());
+        let mut state = self.lock();
+        if size > self.policy.max_entry_bytes {
+            state.stats.oversized += 1;
+            return;
+        }
+        let ttl = self.policy.ttl;
+        if state.entries.len() >= self.policy.max_entries {
+            state
+                .entries
+                .retain(|_, entry| entry.stored.elapsed() < ttl);
+        }
+        if state.entries.len() >= self.policy.max_entries
+            && let Some(oldest) = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.stored)
+                .map(|(key, _)| key.clone())
+        {
+            state.entries.remove(&oldest);
+        }
+        state.entries.insert(
+            key,
+            Entry {
+                stored: Instant::now(),
+                value: value.clone(),
+            },
+        );
+        state.stats.stores += 1;
+    }
+
+    /// Drop every cached result and catalog.
+    pub(crate) fn clear(&self) {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.read_only.clear();
+    }
+
+    pub(crate) fn stats(&self) -> ResultCacheStats {
+        let state = self.lock();
+        ResultCacheStats {
+            entries: state.entries.len(),
+            ..state.stats
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Cache key for one call; object keys in `args` are sorted so equivalent
+/// arguments share an entry.
+pub(crate) fn key(digest: &str, tenant: Option<&str>, tool: &str, args: &Value) -> String {
+    let mut key = format!("{digest}\n{}\n{tool}\n", tenant.unwrap_or(""));
+    write_canonical(args, &mut key);
+    key
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            out.push('{');
+            for (index, (key, value)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::ToolAnnotations;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn tool(name: &str, read_only: Option<bool>) -> Tool {
+        Tool {
+            name: name.into(),
+            title: None,
+            description: String::new(),
+            input_schema: "{}".into(),
+            output_schema: None,
+            annotations: read_only.map(|read_only| ToolAnnotations {
+                read_only: Some(read_only),
+                destructive: None,
+                streaming: None,
+                experimental: None,
+            }),
+            meta: None,
+        }
+    }
+
+    #[test]
+    fn eligibility_follows_annotations_and_overrides() {
+        let cache = ResultCache::new(ResultCachePolicy {
+            components: HashMap::from([
+                ("units".to_string(), ComponentCaching::Always),
+                ("clock".to_string(), ComponentCaching::Never),
+            ]),
+            ..ResultCachePolicy::default()
+        });
+        assert!(cache.needs_catalog("d1"));
+        cache.record_catalog(
+            "d1",
+            &[tool("lookup", Some(true)), tool("write", Some(false))],
+        );
+        assert!(!cache.needs_catalog("d1"));
+
+        assert!(cache.eligible("crm", "d1", "lookup"));
+        assert!(!cache.eligible("crm", "d1", "write"));
+        assert!(!cache.eligible("crm", "d2", "lookup"), "catalog not listed");
+        assert!(cache.eligible("units", "d9", "convert"));
+        assert!(!cache.eligible("clock", "d1", "lookup"));
+    }
+
+    #[test]
+    fn enforces_ttl_and_entry_size() {
+        let cache = ResultCache::new(ResultCachePolicy {
+            ttl: Duration::from_millis(40),
+            max_entry_bytes: 16,
+            ..ResultCachePolicy::default()
+        });
+        let first = key("d1", Some("acme"), "convert", &json!({"a": 1, "b": 2}));
+        let reordered = key("d1", Some("acme"), "convert", &json!({"b": 2, "a": 1}));
+        assert_eq!(first, reordered);
+        assert_ne!(
+            first,
+            key("d1", Some("other"), "convert", &json!({"a": 1, "b": 2}))
+        );
+
+        cache.insert(first.clone(), &json!({"v": 1}));
+        cache.insert("big".into(), &json!({"v": "x".repeat(32)}));
+        assert_eq!(cache.get(&reordered), Some(json!({"v": 1})));
+        assert_eq!(cache.get("big"), None);
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(cache.get(&first), None);
+        assert_eq!(
+            cache.stats(),
+            ResultCacheStats {
+                hits: 1,
+                misses: 2,
+                stores: 1,
+                oversized: 1,
+                entries: 0,
+            }
+        );
+    }
+}
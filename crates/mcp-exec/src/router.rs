@@ -1,9 +1,12 @@
 use base64::Engine;
 use serde_json::Value;
-use wasmtime::component::Linker;
+use wasmtime::component::Instance;
 
 use crate::runner::StoreState;
 
+/// Export name of the router interface, as bound by [`McpRouter`].
+const ROUTER_INTERFACE: &str = "wasix:mcp/router@25.6.18";
+
 mod bindings {
     wasmtime::component::bindgen!({
         path: "wit/wasix-mcp-25.6.18",
@@ -13,50 +16,80 @@ mod bindings {
 
 pub use bindings::McpRouter;
 pub use bindings::exports::wasix::mcp::router::{
-    ContentBlock, Response, Tool, ToolError, ToolResult,
+    Annotations, AudioContent, ContentBlock, EmbeddedResource, ImageContent, MetaEntry,
+    ResourceLinkContent, Response, Role, TextContent, Tool, ToolAnnotations, ToolError, ToolResult,
 };
 
 pub(crate) fn try_call_tool_router(
-    component: &wasmtime::component::Component,
-    linker: &mut Linker<StoreState>,
     store: &mut wasmtime::Store<StoreState>,
+    instance: &Instance,
     tool: &str,
-    arguments_json: &String,
-) -> anyhow::Result<Option<Value>> {
-    let router = match McpRouter::instantiate(&mut *store, component, linker) {
-        Ok(router) => router,
-        Err(err) => {
-            let msg = err.to_string();
-            if msg.contains("unknown export")
-                || msg.contains("No such export")
-                || msg.contains("no exported instance named")
-            {
-                return Ok(None);
-            }
-            return Err(anyhow::anyhow!(err.to_string()));
-        }
+    arguments_json: &str,
+) -> wasmtime::Result<Option<Value>> {
+    let Some(router) = router_for_instance(store, instance)? else {
+        return Ok(None);
     };
 
-    let response = match router
-        .wasix_mcp_router()
-        .call_call_tool(&mut *store, tool, arguments_json)
-    {
+    let response = match router.wasix_mcp_router().call_call_tool(
+        &mut *store,
+        tool,
+        &arguments_json.to_owned(),
+    ) {
         Ok(Ok(resp)) => resp,
         Ok(Err(err)) => return Ok(Some(tool_error_to_value(tool, err))),
-        Err(err) => return Err(anyhow::anyhow!(err.to_string())),
+        Err(err) => return Err(err),
     };
 
     Ok(Some(render_response(&response)))
 }
 
-#[allow(dead_code)]
+/// [`try_call_tool_router`] for stores of an engine with async support.
+///
+/// The generated [`McpRouter`] bindings only call synchronously, so the
+/// `call-tool` export is looked up and called through its typed function.
+pub(crate) async fn try_call_tool_router_async(
+    store: &mut wasmtime::Store<StoreState>,
+    instance: &Instance,
+    tool: &str,
+    arguments_json: &str,
+) -> wasmtime::Result<Option<Value>> {
+    let Some(interface) = instance.get_export_index(&mut *store, None, ROUTER_INTERFACE) else {
+        return Ok(None);
+    };
+    let Some(func) = instance.get_export_index(&mut *store, Some(&interface), "call-tool") else {
+        return Ok(None);
+    };
+    let call_tool = instance
+        .get_typed_func::<(&str, &str), (Result<Response, ToolError>,)>(&mut *store, &func)?;
+    let (response,) = call_tool
+        .call_async(&mut *store, (tool, arguments_json))
+        .await?;
+
+    Ok(Some(match response {
+        Ok(resp) => render_response(&resp),
+        Err(err) => tool_error_to_value(tool, err),
+    }))
+}
+
 pub(crate) fn try_list_tools_router(
-    component: &wasmtime::component::Component,
-    linker: &mut Linker<StoreState>,
     store: &mut wasmtime::Store<StoreState>,
-) -> anyhow::Result<Option<Vec<Tool>>> {
-    let router = match McpRouter::instantiate(&mut *store, component, linker) {
-        Ok(router) => router,
+    instance: &Instance,
+) -> wasmtime::Result<Option<Vec<Tool>>> {
+    let Some(router) = router_for_instance(store, instance)? else {
+        return Ok(None);
+    };
+
+    let tools = router.wasix_mcp_router().call_list_tools(&mut *store)?;
+    Ok(Some(tools))
+}
+
+/// Bind the router exports of an instance, or `None` when it is not a router.
+fn router_for_instance(
+    store: &mut wasmtime::Store<StoreState>,
+    instance: &Instance,
+) -> wasmtime::Result<Option<McpRouter>> {
+    match McpRouter::new(&mut *store, instance) {
+        Ok(router) => Ok(Some(router)),
         Err(err) => {
             let msg = err.to_string();
             if msg.contains("unknown export")
@@ -65,15 +98,9 @@ pub(crate) fn try_list_tools_router(
             {
                 return Ok(None);
             }
-            return Err(anyhow::anyhow!(err.to_string()));
+            Err(wasmtime::format_err!(msg))
         }
-    };
-
-    let tools = router
-        .wasix_mcp_router()
-        .call_list_tools(&mut *store)
-        .map_err(|err| anyhow::anyhow!(err.to_string()))?;
-    Ok(Some(tools))
+    }
 }
 
 pub fn render_response(response: &Response) -> Value {
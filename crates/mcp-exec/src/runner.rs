@@ -1,33 +1,72 @@
 //! Runtime integration with Wasmtime for invoking the MCP component entrypoint.
 
+use std::collections::VecDeque;
+use std::io::Read;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
 
 use greentic_interfaces_wasmtime::host_helpers::v1::{runner_host_http, runner_host_kv};
 use greentic_types::TenantCtx;
-use serde_json::Value;
-use wasmtime::component::{Component, Linker};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tracing::{Level, info};
+use wasmtime::component::{Component, Instance, InstancePre, Linker};
 use wasmtime::{Engine, Store};
 use wasmtime_wasi::{
-    ResourceTable, WasiCtx, WasiCtxBuilder, WasiCtxView, WasiView,
-    p2::add_to_linker_sync as add_wasi_to_linker,
+    DirPerms, FilePerms, ResourceTable, ResourceTableError, WasiCtx, WasiCtxBuilder, WasiCtxView,
+    WasiView, filesystem::WasiFilesystemCtxView, p2::add_to_linker_sync as add_wasi_to_linker,
 };
 use wasmtime_wasi_http::{WasiHttpCtx, WasiHttpView};
 use wasmtime_wasi_tls::{LinkOptions, WasiTls, WasiTlsCtx, WasiTlsCtxBuilder};
 
-use crate::ExecRequest;
-use crate::config::{DynSecretsStore, RuntimePolicy};
+use crate::blobs::{self, BlobPolicy};
+use crate::capabilities::{self, CapabilityReport, GrantedCapabilities};
+use crate::config::{
+    DynSecretsStore, HostLimits, NetworkProfile, RuntimePolicy, SecretAction, SecretAuditEvent,
+    SecretsAccessPolicy,
+};
+use crate::cost::{self, ExecutionCost};
+use crate::debug_dump::{self, HostCall, REDACTED_SECRET};
+use crate::epoch;
 use crate::error::RunnerError;
-use crate::router::try_call_tool_router;
+use crate::events::{self, CallEvents, ExecEventKind};
+use crate::memory::MemoryLimiter;
+use crate::module_runner;
+use crate::preflight::check_imports;
+use crate::preview1;
+use crate::resolve::ResolvedArtifact;
+use crate::router::{
+    Tool, try_call_tool_router, try_call_tool_router_async, try_list_tools_router,
+};
+use crate::scratch::{self, ScratchFilesystem, ScratchQuota};
+use crate::stream::{self, ChunkPipe};
 use crate::verify::VerifiedArtifact;
+use crate::{ExecRequest, Priority};
 
 const LEGACY_EXEC_INTERFACE: &str = "legacy:exec/exec";
+/// Import prefixes that grant a component network reach; rejected in hardened mode.
+const NETWORK_IMPORT_PREFIXES: &[&str] = &["wasi:sockets/", "wasi:http/outgoing-handler"];
+/// Guest path under which the per-call scratch directory is preopened.
+const SCRATCH_GUEST_PATH: &str = "/tmp";
+/// Interface names recorded in host call transcripts.
+const HTTP_INTERFACE: &str = "runner-host-http";
+const KV_INTERFACE: &str = "runner-host-kv";
+const SECRETS_INTERFACE: &str = "greentic:secrets/secret-store@1.0.0";
+const BLOBS_INTERFACE: &str = "greentic:blobs/blob-store@1.0.0";
 type LegacyExecFunc = wasmtime::component::TypedFunc<(String, String), (String,)>;
 pub struct ExecutionContext<'a> {
     pub runtime: &'a RuntimePolicy,
     pub http_enabled: bool,
     pub secrets_store: Option<DynSecretsStore>,
+    /// Guest stdout drained by [`exec_stream`](crate::exec_stream) during the
+    /// call; `None` collects it under [`RuntimePolicy::stream_capture`].
+    pub stream: Option<ChunkPipe>,
 }
 
 pub trait Runner: Send + Sync {
@@ -45,16 +84,39 @@ pub struct DefaultRunner {
 
 impl DefaultRunner {
     pub fn new(runtime: &RuntimePolicy) -> Result<Self, RunnerError> {
-        let mut config = wasmtime::Config::new();
-        config.wasm_component_model(true);
-        // Epoch interruption lets us wire wallclock enforcement without embedding async support.
-        config.epoch_interruption(true);
-        if runtime.fuel.is_some() {
-            config.consume_fuel(true);
-        }
-        let engine = Engine::new(&config)?;
-        Ok(Self { engine })
+        Ok(Self {
+            engine: build_engine(runtime)?,
+        })
+    }
+}
+
+/// Build the Wasmtime engine shared by every execution under `runtime`.
+///
+/// The engine's epoch is advanced by the [`epoch`] ticker, which enforces
+/// wallclock timeouts.
+pub(crate) fn build_engine(runtime: &RuntimePolicy) -> Result<Engine, RunnerError> {
+    let engine = Engine::new(&engine_config(runtime))?;
+    epoch::register(&engine);
+    Ok(engine)
+}
+
+/// Build the engine for [`run_async`]. Wasmtime engines always support async
+/// calls; the [`epoch`] ticker advancing this one makes its guests yield to
+/// the executor at each tick, even while they spin.
+pub(crate) fn build_async_engine(runtime: &RuntimePolicy) -> Result<Engine, RunnerError> {
+    build_engine(runtime)
+}
+
+fn engine_config(runtime: &RuntimePolicy) -> wasmtime::Config {
+    let mut config = wasmtime::Config::new();
+    config.wasm_component_model(true);
+    // Epoch interruption enforces wallclock timeouts without embedding async
+    // support; async engines also yield to the executor at epoch ticks.
+    config.epoch_interruption(true);
+    if runtime.fuel.is_some() {
+        config.consume_fuel(true);
     }
+    config
 }
 
 impl Runner for DefaultRunner {
@@ -70,30 +132,295 @@ impl Runner for DefaultRunner {
         let runtime = ctx.runtime.clone();
         let http_enabled = ctx.http_enabled;
         let secrets_store = ctx.secrets_store.clone();
+        let stream = ctx.stream.clone();
         let timeout_duration = runtime.per_call_timeout;
 
-        let (tx, rx) = mpsc::channel();
-        thread::spawn(move || {
-            let res = run_sync(
+        run_with_timeout(timeout_duration, move || {
+            run_sync(
                 engine,
                 request,
                 artifact,
                 runtime,
                 http_enabled,
                 secrets_store,
-            );
-            let _ = tx.send(res);
+                stream,
+            )
+        })
+    }
+}
+
+/// Run `job` on a worker thread, giving up after `timeout`.
+pub(crate) fn run_with_timeout<F>(timeout: Duration, job: F) -> Result<Value, RunnerError>
+where
+    F: FnOnce() -> Result<Value, RunnerError> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(job());
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(RunnerError::Timeout { elapsed: timeout }),
+        Err(RecvTimeoutError::Disconnected) => {
+            Err(RunnerError::Internal("blocking runner task failed".into()))
+        }
+    }
+}
+
+/// Run `request` on the calling task rather than on a thread of its own.
+///
+/// Components are instantiated and called with Wasmtime's async support and
+/// yield every [`epoch::EPOCH_TICK`], so concurrent calls share the runtime's threads,
+/// and a call past [`RuntimePolicy::per_call_timeout`] is dropped instead of
+/// left running. Compilation happens on tokio's blocking pool, as does the
+/// whole call for core modules, which only have a synchronous path.
+pub(crate) async fn run_async(
+    request: &ExecRequest,
+    artifact: &VerifiedArtifact,
+    ctx: ExecutionContext<'_>,
+) -> Result<Value, RunnerError> {
+    let timeout = ctx.runtime.per_call_timeout;
+    let run = async {
+        let bytes = Arc::clone(&artifact.resolved.bytes);
+        if preview1::is_core_module(&bytes) {
+            let (request, artifact) = (request.clone(), artifact.clone());
+            let runtime = ctx.runtime.clone();
+            let (http_enabled, secrets_store) = (ctx.http_enabled, ctx.secrets_store);
+            let stream = ctx.stream;
+            return blocking(move || {
+                let engine = build_engine(&runtime)?;
+                run_sync(
+                    engine,
+                    request,
+                    artifact,
+                    runtime,
+                    http_enabled,
+                    secrets_store,
+                    stream,
+                )
+            })
+            .await;
+        }
+
+        let engine = build_async_engine(ctx.runtime)?;
+        let compiled = {
+            let (engine, bytes) = (engine.clone(), Arc::clone(&bytes));
+            blocking(move || Ok(Component::from_binary(&engine, &bytes)?)).await
+        };
+        let component = match compiled {
+            Ok(component) => component,
+            Err(err) => return try_mock_json(&bytes, &request.action).unwrap_or(Err(err)),
+        };
+
+        let call = async {
+            let linker = build_async_linker(&engine)?;
+            let pre = prepare_instance(&engine, &linker, &component, ctx.runtime)?;
+            let ready = instantiate_async(
+                &engine,
+                &pre,
+                ctx.runtime,
+                ctx.http_enabled,
+                ctx.secrets_store,
+                ctx.stream,
+            )
+            .await?;
+            ready
+                .with_debug_dump(&artifact.resolved, ctx.runtime)
+                .call_async(request, ctx.runtime)
+                .await
+        };
+        call.await
+    };
+
+    match tokio::time::timeout(timeout, run).await {
+        Ok(result) => result,
+        Err(_) => Err(RunnerError::Timeout { elapsed: timeout }),
+    }
+}
+
+/// Run `job` on tokio's blocking pool.
+async fn blocking<T, F>(job: F) -> Result<T, RunnerError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, RunnerError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(job)
+        .await
+        .unwrap_or_else(|_| Err(RunnerError::Internal("blocking runner task failed".into())))
+}
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+struct Queued {
+    id: u64,
+    job: Job,
+}
+
+/// What a worker reports back to the caller of [`WorkerPool::run`].
+enum Progress {
+    Started,
+    Finished(Result<Value, RunnerError>),
+}
+
+/// Interactive jobs dispatched in a row while background jobs wait, before
+/// one background job is let through.
+const INTERACTIVE_BURST: u32 = 8;
+
+#[derive(Default)]
+struct Queues {
+    interactive: VecDeque<Queued>,
+    background: VecDeque<Queued>,
+    /// Interactive jobs dispatched since a background job last ran.
+    burst: u32,
+    next_id: u64,
+    shutdown: bool,
+}
+
+impl Queues {
+    fn next(&mut self) -> Option<Queued> {
+        let starving = self.burst >= INTERACTIVE_BURST && !self.background.is_empty();
+        if !starving && let Some(job) = self.interactive.pop_front() {
+            self.burst += 1;
+            return Some(job);
+        }
+        let job = self.background.pop_front()?;
+        self.burst = 0;
+        Some(job)
+    }
+
+    /// Take the job `id` off its queue; false if a worker already has it.
+    fn remove(&mut self, id: u64) -> bool {
+        for queue in [&mut self.interactive, &mut self.background] {
+            if let Some(index) = queue.iter().position(|queued| queued.id == id) {
+                queue.remove(index);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+struct Shared {
+    queues: Mutex<Queues>,
+    ready: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Queues> {
+        self.queues
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Fixed set of worker threads that execute calls for a long-lived executor.
+///
+/// Jobs are queued by [`Priority`]: interactive jobs are dispatched first, but
+/// after [`INTERACTIVE_BURST`] of them in a row a waiting background job runs,
+/// so bulk work slows down under interactive load without stalling entirely.
+///
+/// A job's timeout starts when a worker picks it up. A job still queued when
+/// the timeout runs out is dropped. One that is running keeps its worker busy
+/// until it returns, so jobs should stop themselves at the timeout, as guest
+/// calls do through their epoch deadline.
+pub(crate) struct WorkerPool {
+    shared: Arc<Shared>,
+}
+
+impl WorkerPool {
+    pub(crate) fn new(workers: usize) -> Self {
+        let shared = Arc::new(Shared {
+            queues: Mutex::new(Queues::default()),
+            ready: Condvar::new(),
         });
+        for index in 0..workers.max(1) {
+            let shared = Arc::clone(&shared);
+            thread::Builder::new()
+                .name(format!("mcp-exec-worker-{index}"))
+                .spawn(move || {
+                    loop {
+                        let Queued { job, .. } = {
+                            let mut queues = shared.lock();
+                            loop {
+                                if queues.shutdown {
+                                    return;
+                                }
+                                if let Some(queued) = queues.next() {
+                                    break queued;
+                                }
+                                queues = shared
+                                    .ready
+                                    .wait(queues)
+                                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                            }
+                        };
+                        // Keep the worker alive if a job panics; its caller sees a
+                        // disconnected result channel.
+                        let _ = std::panic::catch_unwind(AssertUnwindSafe(job));
+                    }
+                })
+                .expect("spawn executor worker");
+        }
+        Self { shared }
+    }
 
-        match rx.recv_timeout(timeout_duration) {
-            Ok(result) => result,
-            Err(RecvTimeoutError::Timeout) => Err(RunnerError::Timeout {
-                elapsed: timeout_duration,
-            }),
-            Err(RecvTimeoutError::Disconnected) => {
-                Err(RunnerError::Internal("blocking runner task failed".into()))
+    /// Run `job` on a worker, giving up after `timeout` in the queue or
+    /// `timeout` from when a worker picks it up.
+    pub(crate) fn run<F>(
+        &self,
+        priority: Priority,
+        timeout: Duration,
+        job: F,
+    ) -> Result<Value, RunnerError>
+    where
+        F: FnOnce() -> Result<Value, RunnerError> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        let job: Job = Box::new(move || {
+            let _ = tx.send(Progress::Started);
+            let _ = tx.send(Progress::Finished(job()));
+        });
+        let id = {
+            let mut queues = self.shared.lock();
+            if queues.shutdown {
+                return Err(RunnerError::Internal("worker pool shut down".into()));
             }
+            let id = queues.next_id;
+            queues.next_id += 1;
+            let queued = Queued { id, job };
+            match priority {
+                Priority::Interactive => queues.interactive.push_back(queued),
+                Priority::Background => queues.background.push_back(queued),
+            }
+            id
+        };
+        self.shared.ready.notify_one();
+
+        let failed = || RunnerError::Internal("worker task failed".into());
+        match rx.recv_timeout(timeout) {
+            Ok(Progress::Started) => {}
+            Ok(Progress::Finished(result)) => return result,
+            Err(RecvTimeoutError::Timeout) => {
+                if self.shared.lock().remove(id) {
+                    return Err(RunnerError::Timeout { elapsed: timeout });
+                }
+                // A worker picked the job up just now; its clock has started.
+            }
+            Err(RecvTimeoutError::Disconnected) => return Err(failed()),
         }
+        match rx.recv_timeout(timeout) {
+            Ok(Progress::Finished(result)) => result,
+            Ok(Progress::Started) | Err(RecvTimeoutError::Disconnected) => Err(failed()),
+            Err(RecvTimeoutError::Timeout) => Err(RunnerError::Timeout { elapsed: timeout }),
+        }
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        self.shared.lock().shutdown = true;
+        self.shared.ready.notify_all();
     }
 }
 
@@ -104,86 +431,623 @@ fn run_sync(
     runtime: RuntimePolicy,
     http_enabled: bool,
     secrets_store: Option<DynSecretsStore>,
+    stream: Option<ChunkPipe>,
 ) -> Result<Value, RunnerError> {
-    let component = match Component::from_binary(&engine, artifact.resolved.bytes.as_ref()) {
+    if let Some(module) = module_runner::load(&engine, artifact.resolved.bytes.as_ref()) {
+        let result = module_runner::call(&engine, &module, &request, &runtime);
+        debug_dump::capture(&runtime, &artifact.resolved, &request, Vec::new(), &result);
+        return result;
+    }
+    let component = match preview1::compile(&engine, artifact.resolved.bytes.as_ref(), &runtime) {
         Ok(component) => component,
         Err(err) => {
             if let Some(result) = try_mock_json(artifact.resolved.bytes.as_ref(), &request.action) {
                 return result;
             }
-            return Err(err.into());
+            return Err(err);
         }
     };
 
-    let mut linker = Linker::new(&engine);
+    run_compiled(
+        &engine,
+        &component,
+        &artifact.resolved,
+        &request,
+        ExecutionContext {
+            runtime: &runtime,
+            http_enabled,
+            secrets_store,
+            stream,
+        },
+    )
+}
+
+/// Link, instantiate, and call an already compiled component.
+pub(crate) fn run_compiled(
+    engine: &Engine,
+    component: &Component,
+    artifact: &ResolvedArtifact,
+    request: &ExecRequest,
+    ctx: ExecutionContext<'_>,
+) -> Result<Value, RunnerError> {
+    let runtime = ctx.runtime;
+    let linker = build_linker(engine)?;
+    let pre = prepare_instance(engine, &linker, component, runtime)?;
+    let ready = instantiate(
+        engine,
+        &pre,
+        runtime,
+        ctx.http_enabled,
+        ctx.secrets_store,
+        ctx.stream,
+    )?;
+    ready
+        .with_debug_dump(artifact, runtime)
+        .call(request, runtime)
+}
+
+/// Run an artifact with its host calls answered from a recorded transcript.
+///
+/// Returns the outcome and the first point where the guest's host calls
+/// departed from the transcript, if any.
+pub(crate) fn replay(
+    bytes: &[u8],
+    request: &ExecRequest,
+    runtime: &RuntimePolicy,
+    host_calls: Vec<HostCall>,
+) -> (Result<Value, RunnerError>, Option<String>) {
+    let engine = match build_engine(runtime) {
+        Ok(engine) => engine,
+        Err(err) => return (Err(err), None),
+    };
+    if let Some(module) = module_runner::load(&engine, bytes) {
+        return (
+            module_runner::call(&engine, &module, request, runtime),
+            None,
+        );
+    }
+    let ready = preview1::compile(&engine, bytes, runtime).and_then(|component| {
+        let linker = build_linker(&engine)?;
+        let pre = prepare_instance(&engine, &linker, &component, runtime)?;
+        instantiate(&engine, &pre, runtime, false, None, None)
+    });
+    match ready {
+        Ok(ready) => ready.replay(request, runtime, host_calls),
+        Err(err) => (
+            try_mock_json(bytes, &request.action).unwrap_or(Err(err)),
+            None,
+        ),
+    }
+}
+
+/// Link every host import the runner provides.
+pub(crate) fn build_linker(engine: &Engine) -> Result<Linker<StoreState>, RunnerError> {
+    let mut linker = Linker::new(engine);
     linker.allow_shadowing(true);
     add_wasi_to_linker(&mut linker).map_err(|err| RunnerError::Internal(err.to_string()))?;
+    scratch::add_to_linker_sync(&mut linker)
+        .map_err(|err| RunnerError::Internal(err.to_string()))?;
+
+    // Add wasi-http types and turn on the feature in linker
+    wasmtime_wasi_http::add_only_http_to_linker_sync(&mut linker)?;
+
+    add_host_imports(&mut linker)?;
+    Ok(linker)
+}
 
+/// [`build_linker`] for engines built by [`build_async_engine`].
+pub(crate) fn build_async_linker(engine: &Engine) -> Result<Linker<StoreState>, RunnerError> {
+    let mut linker = Linker::new(engine);
+    linker.allow_shadowing(true);
+    wasmtime_wasi::p2::add_to_linker_async(&mut linker)
+        .map_err(|err| RunnerError::Internal(err.to_string()))?;
+    scratch::add_to_linker_async(&mut linker)
+        .map_err(|err| RunnerError::Internal(err.to_string()))?;
+    wasmtime_wasi_http::add_only_http_to_linker_async(&mut linker)?;
+    add_host_imports(&mut linker)?;
+    Ok(linker)
+}
+
+/// Link the imports that are synchronous under either engine.
+fn add_host_imports(linker: &mut Linker<StoreState>) -> Result<(), RunnerError> {
     // Add wasi-tls types and turn on the feature in linker
     let mut opts = LinkOptions::default();
     opts.tls(true);
-    wasmtime_wasi_tls::add_to_linker(&mut linker, &mut opts, |h: &mut StoreState| h.wasi_tls())?;
-
-    // Add wasi-http types and turn on the feature in linker
-    wasmtime_wasi_http::add_only_http_to_linker_sync(&mut linker)?;
+    wasmtime_wasi_tls::add_to_linker(linker, &mut opts, |h: &mut StoreState| h.wasi_tls())?;
 
-    runner_host_http::add_runner_host_http_to_linker(&mut linker, |state: &mut StoreState| state)
+    runner_host_http::add_runner_host_http_to_linker(linker, |state: &mut StoreState| state)
         .map_err(|err| RunnerError::Internal(err.to_string()))?;
-    runner_host_kv::add_runner_host_kv_to_linker(&mut linker, |state: &mut StoreState| state)
+    runner_host_kv::add_runner_host_kv_to_linker(linker, |state: &mut StoreState| state)
         .map_err(|err| RunnerError::Internal(err.to_string()))?;
-    add_secrets_to_linker(&mut linker)?;
+    add_secrets_to_linker(linker)?;
+    add_blobs_to_linker(linker)?;
+    Ok(())
+}
 
-    let mut store = Store::new(
-        &engine,
-        StoreState::new(http_enabled, secrets_store, request.tenant.clone()),
-    );
-    // Epoch interruption requires an explicit deadline; set a far future deadline
-    // until a caller opts into tighter wallclock control.
-    store.set_epoch_deadline(u64::MAX / 2);
-
-    let args_json = serde_json::to_string(&request.args)?;
-    if let Some(value) = try_call_tool_router(
-        &component,
-        &mut linker,
-        &mut store,
-        &request.action,
-        &args_json,
-    )
-    .map_err(|e| RunnerError::Internal(e.to_string()))?
-    {
-        return Ok(value);
+/// Resolve a component's imports against the linker ahead of instantiation.
+///
+/// Enforces the network profile and turns link failures into an actionable report.
+pub(crate) fn prepare_instance(
+    engine: &Engine,
+    linker: &Linker<StoreState>,
+    component: &Component,
+    runtime: &RuntimePolicy,
+) -> Result<InstancePre<StoreState>, RunnerError> {
+    if runtime.network == NetworkProfile::Hardened {
+        reject_network_imports(engine, component)?;
     }
 
-    let instance = linker.instantiate(&mut store, &component)?;
-    let exec = if let Some(func) = legacy_exec_func(&instance, &mut store)? {
-        func
-    } else {
-        instance.get_typed_func::<(String, String), (String,)>(&mut store, "exec")?
-    };
+    linker.instantiate_pre(component).map_err(|err| {
+        let report = check_imports(linker, component);
+        if report.is_empty() {
+            RunnerError::from(err)
+        } else {
+            RunnerError::MissingImports(report)
+        }
+    })
+}
 
-    let started = Instant::now();
-    let (raw_response,) = match exec.call(&mut store, (request.action.clone(), args_json)) {
-        Ok(result) => result,
-        Err(trap) => {
-            let msg = trap.to_string();
-            if msg.contains("transient.") {
-                return Err(RunnerError::ToolTransient {
-                    component: request.component.clone(),
-                    message: msg,
-                });
+/// A component instance plus the per-call host resources backing it.
+///
+/// Each instance serves exactly one call so no guest state leaks between requests.
+pub(crate) struct ReadyInstance {
+    store: Store<StoreState>,
+    instance: Instance,
+    // Dropping the temp dir at the end of the call removes everything the guest wrote.
+    _scratch: Option<tempfile::TempDir>,
+    stdout: Option<ChunkPipe>,
+    /// Built when it is attached to the result or debug logging is on.
+    capabilities: Option<CapabilityReport>,
+    /// Artifact to write a debug dump of the call for.
+    dump: Option<ResolvedArtifact>,
+}
+
+/// Instantiate a pre-linked component with fresh host state.
+///
+/// The guest writes its stdout to `stream` when given, or else to a pipe of
+/// its own under [`RuntimePolicy::stream_capture`].
+pub(crate) fn instantiate(
+    engine: &Engine,
+    pre: &InstancePre<StoreState>,
+    runtime: &RuntimePolicy,
+    http_enabled: bool,
+    secrets_store: Option<DynSecretsStore>,
+    stream: Option<ChunkPipe>,
+) -> Result<ReadyInstance, RunnerError> {
+    let mut fresh = FreshStore::new(engine, pre, runtime, http_enabled, secrets_store, stream)?;
+    let instance = pre
+        .instantiate(&mut fresh.store)
+        .map_err(|err| fresh.instantiation_error(err))?;
+    Ok(fresh.into_ready(instance))
+}
+
+/// [`instantiate`] for engines built by [`build_async_engine`]. The store
+/// yields to the executor whenever the engine's epoch advances.
+pub(crate) async fn instantiate_async(
+    engine: &Engine,
+    pre: &InstancePre<StoreState>,
+    runtime: &RuntimePolicy,
+    http_enabled: bool,
+    secrets_store: Option<DynSecretsStore>,
+    stream: Option<ChunkPipe>,
+) -> Result<ReadyInstance, RunnerError> {
+    let mut fresh = FreshStore::new(engine, pre, runtime, http_enabled, secrets_store, stream)?;
+    fresh.store.set_epoch_deadline(1);
+    fresh.store.epoch_deadline_async_yield_and_update(1);
+    let instance = pre
+        .instantiate_async(&mut fresh.store)
+        .await
+        .map_err(|err| fresh.instantiation_error(err))?;
+    Ok(fresh.into_ready(instance))
+}
+
+/// A store with fresh host state for one call, before instantiation.
+struct FreshStore {
+    store: Store<StoreState>,
+    scratch: Option<tempfile::TempDir>,
+    stdout: Option<ChunkPipe>,
+    capabilities: Option<CapabilityReport>,
+}
+
+impl FreshStore {
+    fn new(
+        engine: &Engine,
+        pre: &InstancePre<StoreState>,
+        runtime: &RuntimePolicy,
+        http_enabled: bool,
+        secrets_store: Option<DynSecretsStore>,
+        stream: Option<ChunkPipe>,
+    ) -> Result<Self, RunnerError> {
+        let scratch = runtime
+            .scratch_quota_bytes
+            .map(|_| tempfile::tempdir())
+            .transpose()
+            .map_err(|err| RunnerError::Internal(format!("scratch dir: {err}")))?;
+
+        let mut state = StoreState::new(http_enabled, secrets_store, None)
+            .with_host_limits(runtime.host_limits.clone())
+            .with_secrets_access(runtime.secrets_access.clone())
+            .with_blobs(runtime.blobs.clone())
+            .with_max_memory(runtime.max_memory)
+            .with_network_profile(runtime.network)?;
+        if let (Some(dir), Some(limit)) = (&scratch, runtime.scratch_quota_bytes) {
+            state = state
+                .with_scratch_dir(dir.path())?
+                .with_scratch_quota(ScratchQuota::new(limit, runtime.scratch_max_entries));
+        }
+        let stdout = stream.or_else(|| runtime.stream_capture.clone().map(ChunkPipe::new));
+        if let Some(pipe) = &stdout {
+            state = state.with_stdout_capture(pipe.clone())?;
+        }
+
+        let capabilities = (runtime.capability_report
+            || tracing::enabled!(target: capabilities::TRACE_TARGET, Level::DEBUG))
+        .then(|| capability_report(engine, pre.component(), &state));
+
+        let mut store = Store::new(engine, state);
+        store.limiter(|state| &mut state.memory);
+        // Covers instantiation; calls restart the deadline when they begin.
+        store.set_epoch_deadline(epoch::deadline(runtime.wallclock_timeout));
+        cost::refuel(&mut store, runtime)?;
+
+        Ok(Self {
+            store,
+            scratch,
+            stdout,
+            capabilities,
+        })
+    }
+
+    /// Memory initially requested past the cap fails instantiation too.
+    fn instantiation_error(&self, err: wasmtime::Error) -> RunnerError {
+        self.store
+            .data()
+            .memory
+            .exceeded()
+            .unwrap_or(RunnerError::Wasmtime(err))
+    }
+
+    fn into_ready(self, instance: Instance) -> ReadyInstance {
+        ReadyInstance {
+            store: self.store,
+            instance,
+            _scratch: self.scratch,
+            stdout: self.stdout,
+            capabilities: self.capabilities,
+            dump: None,
+        }
+    }
+}
+
+/// Imports of `component` and the capabilities `state` grants it.
+pub fn capability_report(
+    engine: &Engine,
+    component: &Component,
+    state: &StoreState,
+) -> CapabilityReport {
+    CapabilityReport {
+        imports: capabilities::imports(engine, component),
+        granted: state.granted_capabilities(),
+    }
+}
+
+/// The report an execution of `component` would get under `runtime`, without
+/// instantiating it.
+pub(crate) fn planned_capabilities(
+    engine: &Engine,
+    component: &Component,
+    runtime: &RuntimePolicy,
+    http_enabled: bool,
+    secrets_store: Option<DynSecretsStore>,
+) -> Result<CapabilityReport, RunnerError> {
+    let mut state =
+        StoreState::new(http_enabled, secrets_store, None).with_network_profile(runtime.network)?;
+    state.stdout_capture = runtime.stream_capture.clone().map(ChunkPipe::new);
+    let mut report = capability_report(engine, component, &state);
+    // The scratch directory only exists for the duration of a call.
+    if runtime.scratch_quota_bytes.is_some() {
+        report
+            .granted
+            .preopened_dirs
+            .push(SCRATCH_GUEST_PATH.to_string());
+    }
+    Ok(report)
+}
+
+impl ReadyInstance {
+    /// Record host calls and dump the call of `artifact` when
+    /// [`RuntimePolicy::debug_dump`] is set.
+    pub(crate) fn with_debug_dump(
+        mut self,
+        artifact: &ResolvedArtifact,
+        runtime: &RuntimePolicy,
+    ) -> Self {
+        if runtime.debug_dump.is_some() {
+            self.store.data_mut().record_host_calls();
+            self.dump = Some(artifact.clone());
+        }
+        self
+    }
+
+    /// Report the host calls of this instance as events of the call, if it
+    /// has listeners.
+    pub(crate) fn with_events(mut self, events: Option<CallEvents>) -> Self {
+        self.store.data_mut().events = events;
+        self
+    }
+
+    /// Invoke the request against this instance, consuming it.
+    ///
+    /// The execution's [`ExecutionCost`] is attached to the result under `cost`.
+    pub(crate) fn call(
+        mut self,
+        request: &ExecRequest,
+        runtime: &RuntimePolicy,
+    ) -> Result<Value, RunnerError> {
+        let started = Instant::now();
+        let invoked = self.invoke(request, runtime);
+        self.settle(request, runtime, started, invoked)
+    }
+
+    /// [`Self::call`] for instances created by [`instantiate_async`].
+    pub(crate) async fn call_async(
+        mut self,
+        request: &ExecRequest,
+        runtime: &RuntimePolicy,
+    ) -> Result<Value, RunnerError> {
+        let started = Instant::now();
+        let invoked = self.invoke_async(request, runtime).await;
+        self.settle(request, runtime, started, invoked)
+    }
+
+    /// Dump the call and attach its cost and capabilities to the result.
+    fn settle(
+        mut self,
+        request: &ExecRequest,
+        runtime: &RuntimePolicy,
+        started: Instant,
+        invoked: Result<Value, RunnerError>,
+    ) -> Result<Value, RunnerError> {
+        if let Some(artifact) = self.dump.take() {
+            let host_calls = self.store.data_mut().take_host_calls();
+            debug_dump::capture(runtime, &artifact, request, host_calls, &invoked);
+        }
+        let mut value = invoked?;
+        let usage = self.store.data();
+        let cost = ExecutionCost {
+            fuel: cost::fuel_used(&self.store, runtime),
+            wallclock: started.elapsed(),
+            http_bytes_sent: usage.http_bytes_sent,
+            http_bytes_received: usage.http_bytes_received,
+        };
+        cost::attach(&mut value, &cost);
+        if let Some(report) = &self.capabilities {
+            capabilities::log(&request.component, report);
+            if runtime.capability_report {
+                capabilities::attach(&mut value, report);
             }
-            return Err(RunnerError::Internal(msg));
         }
-    };
+        Ok(value)
+    }
 
-    if started.elapsed() > runtime.wallclock_timeout {
-        return Err(RunnerError::Timeout {
-            elapsed: started.elapsed(),
-        });
+    fn invoke(
+        &mut self,
+        request: &ExecRequest,
+        runtime: &RuntimePolicy,
+    ) -> Result<Value, RunnerError> {
+        self.begin(request);
+        let args_json = request.args_json()?;
+        // Warm instances may have been created long before they are called.
+        self.store
+            .set_epoch_deadline(epoch::deadline(runtime.wallclock_timeout));
+        // Instantiation is not charged to the call.
+        cost::refuel(&mut self.store, runtime)?;
+        let started = Instant::now();
+        if let Some(value) =
+            try_call_tool_router(&mut self.store, &self.instance, &request.action, &args_json)
+                .map_err(|e| self.call_error(runtime, started, &e))?
+        {
+            return self.finish_router(value);
+        }
+
+        let exec = self.exec_func()?;
+        let call = exec.call(
+            &mut self.store,
+            (request.action.clone(), args_json.into_owned()),
+        );
+        self.finish_exec(request, runtime, started, call)
+    }
+
+    /// [`Self::invoke`] on a store with async support.
+    async fn invoke_async(
+        &mut self,
+        request: &ExecRequest,
+        runtime: &RuntimePolicy,
+    ) -> Result<Value, RunnerError> {
+        self.begin(request);
+        let args_json = request.args_json()?;
+        // Instantiation is not charged to the call.
+        cost::refuel(&mut self.store, runtime)?;
+        let started = Instant::now();
+        if let Some(value) =
+            try_call_tool_router_async(&mut self.store, &self.instance, &request.action, &args_json)
+                .await
+                .map_err(|e| self.call_error(runtime, started, &e))?
+        {
+            return self.finish_router(value);
+        }
+
+        let exec = self.exec_func()?;
+        let call = exec
+            .call_async(
+                &mut self.store,
+                (request.action.clone(), args_json.into_owned()),
+            )
+            .await;
+        self.finish_exec(request, runtime, started, call)
+    }
+
+    fn begin(&mut self, request: &ExecRequest) {
+        self.store.data_mut().set_tenant(request.tenant.clone());
+        self.store.data_mut().set_component(&request.component);
+    }
+
+    /// The legacy `exec` export, under its interface or at the top level.
+    fn exec_func(&mut self) -> Result<LegacyExecFunc, RunnerError> {
+        if let Some(func) = legacy_exec_func(&self.instance, &mut self.store)? {
+            return Ok(func);
+        }
+        Ok(self
+            .instance
+            .get_typed_func::<(String, String), (String,)>(&mut self.store, "exec")?)
+    }
+
+    fn finish_router(&mut self, mut value: Value) -> Result<Value, RunnerError> {
+        self.check_limits()?;
+        self.attach_stream(&mut value);
+        Ok(value)
+    }
+
+    fn finish_exec(
+        &mut self,
+        request: &ExecRequest,
+        runtime: &RuntimePolicy,
+        started: Instant,
+        call: wasmtime::Result<(String,)>,
+    ) -> Result<Value, RunnerError> {
+        self.check_limits()?;
+        let (raw_response,) = match call {
+            Ok(result) => result,
+            Err(trap) => {
+                let msg = trap.to_string();
+                if msg.contains("transient.") {
+                    return Err(RunnerError::ToolTransient {
+                        component: request.component.clone(),
+                        message: msg,
+                    });
+                }
+                return Err(self.call_error(runtime, started, &trap));
+            }
+        };
+
+        if started.elapsed() > runtime.wallclock_timeout {
+            return Err(RunnerError::Timeout {
+                elapsed: started.elapsed(),
+            });
+        }
+
+        let mut value: Value = serde_json::from_str(&raw_response)?;
+        self.attach_stream(&mut value);
+        Ok(value)
+    }
+
+    /// Invoke the request with host calls answered from `host_calls`.
+    ///
+    /// Returns the outcome, without cost, and the first divergence from the
+    /// transcript.
+    fn replay(
+        mut self,
+        request: &ExecRequest,
+        runtime: &RuntimePolicy,
+        host_calls: Vec<HostCall>,
+    ) -> (Result<Value, RunnerError>, Option<String>) {
+        self.store.data_mut().replay_host_calls(host_calls);
+        let result = self.invoke(request, runtime);
+        (result, self.store.data_mut().take_replay_divergence())
     }
 
-    let value: Value = serde_json::from_str(&raw_response)?;
-    Ok(value)
+    /// List the router tools of this instance, or `None` for non-router components.
+    pub(crate) fn list_tools(mut self) -> Result<Option<Vec<Tool>>, RunnerError> {
+        try_list_tools_router(&mut self.store, &self.instance)
+            .map_err(|err| RunnerError::Internal(err.to_string()))
+    }
+
+    /// The error for a guest call that trapped with `trap`: out of memory,
+    /// fuel, scratch space, or resource table entries, past its wallclock
+    /// deadline, or otherwise internal.
+    fn call_error(
+        &self,
+        runtime: &RuntimePolicy,
+        started: Instant,
+        trap: &wasmtime::Error,
+    ) -> RunnerError {
+        let table_full = || {
+            matches!(
+                trap.downcast_ref::<ResourceTableError>(),
+                Some(ResourceTableError::Full)
+            )
+            .then(|| RunnerError::HostResourceLimit {
+                resource: "table-entries".into(),
+                limit: u64::from(runtime.host_limits.max_table_entries),
+            })
+        };
+        self.store
+            .data()
+            .memory
+            .exceeded()
+            .or_else(|| cost::fuel_exhausted(&self.store, runtime))
+            .or_else(|| self.store.data().scratch_quota()?.exceeded())
+            .or_else(table_full)
+            .unwrap_or_else(|| {
+                epoch::call_error(started, runtime.wallclock_timeout, trap.to_string())
+            })
+    }
+
+    fn check_limits(&mut self) -> Result<(), RunnerError> {
+        check_host_limits(&mut self.store)?;
+        match self
+            .store
+            .data()
+            .scratch_quota()
+            .and_then(ScratchQuota::exceeded)
+        {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Attach collected stdout to the result; a live pipe already handed its
+    /// chunks to the consumer.
+    fn attach_stream(&self, value: &mut Value) {
+        if let Some(pipe) = self.stdout.as_ref().filter(|pipe| !pipe.is_live()) {
+            stream::attach(value, &pipe.collected(), pipe.capture());
+        }
+    }
+}
+
+/// Surface a host limit violation recorded during the call, even if the guest
+/// swallowed the host error and returned normally.
+fn check_host_limits(store: &mut Store<StoreState>) -> Result<(), RunnerError> {
+    match store.data_mut().take_host_limit_violation() {
+        Some(violation) => Err(RunnerError::HostResourceLimit {
+            resource: violation.resource.to_string(),
+            limit: violation.limit,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Fail before instantiation when a hardened execution would import network interfaces.
+fn reject_network_imports(engine: &Engine, component: &Component) -> Result<(), RunnerError> {
+    let offending: Vec<String> = component
+        .component_type()
+        .imports(engine)
+        .map(|(name, _)| name.to_string())
+        .filter(|name| {
+            NETWORK_IMPORT_PREFIXES
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+        })
+        .collect();
+
+    if offending.is_empty() {
+        Ok(())
+    } else {
+        Err(RunnerError::PolicyViolation(format!(
+            "hardened network profile forbids imports: {}",
+            offending.join(", ")
+        )))
+    }
 }
 
 fn legacy_exec_func(
@@ -202,40 +1066,77 @@ fn legacy_exec_func(
     Ok(Some(func))
 }
 
+/// Host state of one component instance.
+///
+/// A store is owned by one call: cold calls instantiate and run on one worker,
+/// warm instances live on their own
+/// [`InstanceActor`](crate::actor::InstanceActor) thread, and under
+/// [`exec_async`](crate::exec_async) the store belongs to the calling task,
+/// which the runtime may move between its threads.
 pub struct StoreState {
     http_enabled: bool,
     http_client: Option<reqwest::blocking::Client>,
     secrets_store: Option<DynSecretsStore>,
+    secrets_access: Option<SecretsAccessPolicy>,
+    blobs: Option<BlobPolicy>,
+    component: String,
     tenant: Option<TenantCtx>,
+    network: NetworkProfile,
+    scratch_dir: Option<PathBuf>,
+    scratch_quota: Option<ScratchQuota>,
+    stdout_capture: Option<ChunkPipe>,
+    host_limits: HostLimits,
+    host_usage: HostUsage,
+    host_limit_violation: Option<HostLimitViolation>,
+    http_bytes_sent: u64,
+    http_bytes_received: u64,
+    /// Host calls made so far, when recording for a debug dump.
+    host_calls: Option<Vec<HostCall>>,
+    /// Recorded host calls still to answer, when replaying a debug dump.
+    replay: Option<VecDeque<HostCall>>,
+    replay_divergence: Option<String>,
+    /// Listeners of the call this store serves.
+    events: Option<CallEvents>,
+    /// Installed as the store's limiter by [`instantiate`].
+    memory: MemoryLimiter,
     table: ResourceTable,
     wasi_ctx: WasiCtx,
     wasi_tls_ctx: WasiTlsCtx,
     wasi_http_ctx: WasiHttpCtx,
 }
 
-// The Wasmtime store is confined to a single worker thread for each execution.
-unsafe impl Send for StoreState {}
-unsafe impl Sync for StoreState {}
-
 impl StoreState {
     pub fn new(
         http_enabled: bool,
         secrets_store: Option<DynSecretsStore>,
         tenant: Option<greentic_types::TenantCtx>,
     ) -> Self {
-        let mut builder = WasiCtxBuilder::new();
-        builder.inherit_stdio().inherit_env();
-        if http_enabled {
-            builder.inherit_network().allow_ip_name_lookup(true);
-        }
-        let wasi_ctx = builder.build();
+        let wasi_ctx = build_wasi_ctx(http_enabled, NetworkProfile::Standard, None, None)
+            .expect("WASI context without preopens cannot fail");
         let wasi_tls_ctx = WasiTlsCtxBuilder::new().build();
         let wasi_http_ctx = WasiHttpCtx::new();
         Self {
             http_enabled,
             http_client: None,
             secrets_store,
+            secrets_access: None,
+            blobs: None,
+            component: String::new(),
             tenant,
+            network: NetworkProfile::Standard,
+            scratch_dir: None,
+            scratch_quota: None,
+            stdout_capture: None,
+            host_limits: HostLimits::default(),
+            host_usage: HostUsage::default(),
+            host_limit_violation: None,
+            http_bytes_sent: 0,
+            http_bytes_received: 0,
+            host_calls: None,
+            replay: None,
+            replay_divergence: None,
+            events: None,
+            memory: MemoryLimiter::new(None),
             table: ResourceTable::new(),
             wasi_ctx,
             wasi_tls_ctx,
@@ -243,6 +1144,259 @@ impl StoreState {
         }
     }
 
+    /// Apply a network profile, rebuilding the WASI context accordingly.
+    ///
+    /// The hardened profile also disables the runner-host HTTP import.
+    pub fn with_network_profile(mut self, profile: NetworkProfile) -> Result<Self, RunnerError> {
+        if profile == NetworkProfile::Hardened {
+            self.http_enabled = false;
+            self.http_client = None;
+        }
+        self.network = profile;
+        self.rebuild_wasi_ctx()?;
+        Ok(self)
+    }
+
+    /// Preopen a host directory as the guest's `/tmp`.
+    pub fn with_scratch_dir(mut self, dir: &Path) -> Result<Self, RunnerError> {
+        self.scratch_dir = Some(dir.to_path_buf());
+        self.rebuild_wasi_ctx()?;
+        Ok(self)
+    }
+
+    /// Hold writes to the scratch directory to `quota`.
+    pub(crate) fn with_scratch_quota(mut self, quota: ScratchQuota) -> Self {
+        self.scratch_quota = Some(quota);
+        self
+    }
+
+    pub(crate) fn scratch_quota(&self) -> Option<&ScratchQuota> {
+        self.scratch_quota.as_ref()
+    }
+
+    /// The guest's filesystem, with writes held to the scratch quota.
+    pub(crate) fn scratch_filesystem(&mut self) -> ScratchFilesystem<'_> {
+        let view = WasiFilesystemCtxView {
+            ctx: self.wasi_ctx.filesystem(),
+            table: &mut self.table,
+        };
+        ScratchFilesystem::new(view, self.scratch_quota.as_ref())
+    }
+
+    /// Route guest stdout into a chunk pipe instead of the host's stdout.
+    pub(crate) fn with_stdout_capture(mut self, pipe: ChunkPipe) -> Result<Self, RunnerError> {
+        self.stdout_capture = Some(pipe);
+        self.rebuild_wasi_ctx()?;
+        Ok(self)
+    }
+
+    fn rebuild_wasi_ctx(&mut self) -> Result<(), RunnerError> {
+        self.wasi_ctx = build_wasi_ctx(
+            self.http_enabled,
+            self.network,
+            self.scratch_dir.as_deref(),
+            self.stdout_capture.clone(),
+        )?;
+        Ok(())
+    }
+
+    /// Cap each linear memory of the guest at `max` bytes; unlimited when `None`.
+    pub fn with_max_memory(mut self, max: Option<u64>) -> Self {
+        self.memory = MemoryLimiter::new(max);
+        self
+    }
+
+    /// Override the per-execution host resource limits.
+    pub fn with_host_limits(mut self, limits: HostLimits) -> Self {
+        let entries = usize::try_from(limits.max_table_entries).unwrap_or(usize::MAX);
+        self.table.set_max_capacity(entries);
+        self.host_limits = limits;
+        self
+    }
+
+    /// What the WASI context and host imports of this store allow; mirrors
+    /// [`build_wasi_ctx`] and the host import checks.
+    pub fn granted_capabilities(&self) -> GrantedCapabilities {
+        let network = self.network == NetworkProfile::Standard && self.http_enabled;
+        GrantedCapabilities {
+            sockets: network,
+            ip_name_lookup: network,
+            http: self.http_enabled,
+            preopened_dirs: self
+                .scratch_dir
+                .iter()
+                .map(|_| SCRATCH_GUEST_PATH.to_string())
+                .collect(),
+            env: true,
+            stdout_captured: self.stdout_capture.is_some(),
+            secrets: self.secrets_store.is_some(),
+        }
+    }
+
+    /// Record runner-host and secrets host calls for a debug dump.
+    pub fn record_host_calls(&mut self) {
+        self.host_calls.get_or_insert_with(Vec::new);
+    }
+
+    /// Take the host calls recorded since [`Self::record_host_calls`].
+    pub fn take_host_calls(&mut self) -> Vec<HostCall> {
+        self.host_calls
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Answer host calls from a recorded transcript instead of running them.
+    fn replay_host_calls(&mut self, calls: Vec<HostCall>) {
+        self.replay = Some(calls.into());
+        self.replay_divergence = None;
+    }
+
+    /// The first departure from the replayed transcript, including recorded
+    /// calls the guest never made.
+    fn take_replay_divergence(&mut self) -> Option<String> {
+        let unanswered = self.replay.take().map_or(0, |calls| calls.len());
+        self.replay_divergence.take().or_else(|| {
+            (unanswered > 0).then(|| format!("{unanswered} recorded host call(s) were never made"))
+        })
+    }
+
+    /// Whether host calls go through a transcript, so their requests are needed.
+    fn transcribing(&self) -> bool {
+        self.host_calls.is_some() || self.replay.is_some()
+    }
+
+    /// Run a host call through the recording or replaying transcript.
+    ///
+    /// `request` is built only when [`Self::transcribing`]; `record` turns the
+    /// response into its transcript form.
+    fn host_call<T: Serialize + DeserializeOwned>(
+        &mut self,
+        interface: &str,
+        function: &str,
+        request: Option<Value>,
+        live: impl FnOnce(&mut Self) -> T,
+        record: impl FnOnce(&T) -> Value,
+    ) -> T {
+        let started = Instant::now();
+        let response = self.transcribed_call(interface, function, request, live, record);
+        events::emit(self.events.as_ref(), || ExecEventKind::HostCall {
+            interface: interface.to_string(),
+            function: function.to_string(),
+            elapsed: started.elapsed(),
+        });
+        response
+    }
+
+    fn transcribed_call<T: Serialize + DeserializeOwned>(
+        &mut self,
+        interface: &str,
+        function: &str,
+        request: Option<Value>,
+        live: impl FnOnce(&mut Self) -> T,
+        record: impl FnOnce(&T) -> Value,
+    ) -> T {
+        let Some(request) = request else {
+            return live(self);
+        };
+        if let Some(calls) = &mut self.replay {
+            let divergence = match calls.pop_front() {
+                Some(call)
+                    if call.interface == interface
+                        && call.function == function
+                        && call.request == request =>
+                {
+                    match serde_json::from_value(call.response) {
+                        Ok(response) => return response,
+                        Err(err) => {
+                            format!("recorded response of {interface}.{function} is invalid: {err}")
+                        }
+                    }
+                }
+                Some(call) => format!(
+                    "expected {}.{} {}, guest called {interface}.{function} {request}",
+                    call.interface, call.function, call.request
+                ),
+                None => {
+                    format!("guest called {interface}.{function} {request} past the transcript")
+                }
+            };
+            self.replay_divergence.get_or_insert(divergence);
+            // Replays run without HTTP or a secrets store, so answering live
+            // cannot reach external systems.
+            return live(self);
+        }
+        let response = live(self);
+        if let Some(calls) = &mut self.host_calls {
+            calls.push(HostCall {
+                interface: interface.to_string(),
+                function: function.to_string(),
+                request,
+                response: record(&response),
+            });
+        }
+        response
+    }
+
+    /// Take the first host limit violation recorded since the last call.
+    pub fn take_host_limit_violation(&mut self) -> Option<HostLimitViolation> {
+        self.host_limit_violation.take()
+    }
+
+    /// Account for a host-side allocation made on behalf of the guest.
+    ///
+    /// Returns a wire error and records the violation once either the resource
+    /// count or the buffered byte budget would be exceeded.
+    fn charge_host(&mut self, bytes: usize) -> Result<(), String> {
+        let resources = self.host_usage.resources.saturating_add(1);
+        if resources > self.host_limits.max_resources {
+            return Err(
+                self.record_violation("resources", u64::from(self.host_limits.max_resources))
+            );
+        }
+        let buffered = self.host_usage.buffered_bytes.saturating_add(bytes as u64);
+        if buffered > self.host_limits.max_buffered_bytes {
+            return Err(
+                self.record_violation("buffered-bytes", self.host_limits.max_buffered_bytes)
+            );
+        }
+        self.host_usage = HostUsage {
+            resources,
+            buffered_bytes: buffered,
+        };
+        Ok(())
+    }
+
+    fn record_violation(&mut self, resource: &'static str, limit: u64) -> String {
+        if self.host_limit_violation.is_none() {
+            self.host_limit_violation = Some(HostLimitViolation { resource, limit });
+        }
+        format!("host-resource-limit:{resource}")
+    }
+
+    /// Restrict secrets imports to the names granted by `policy`.
+    pub fn with_secrets_access(mut self, policy: Option<SecretsAccessPolicy>) -> Self {
+        self.secrets_access = policy;
+        self
+    }
+
+    /// Back the blob-store import with `policy`; without one it fails with
+    /// `blobs-unavailable`.
+    pub fn with_blobs(mut self, policy: Option<BlobPolicy>) -> Self {
+        self.blobs = policy;
+        self
+    }
+
+    /// Identify the component being served, for secrets access checks.
+    pub fn set_component(&mut self, component: &str) {
+        self.component = component.to_string();
+    }
+
+    /// Scope secrets access to the tenant of the call being served.
+    pub fn set_tenant(&mut self, tenant: Option<TenantCtx>) {
+        self.tenant = tenant;
+    }
+
     pub fn table_mut(&mut self) -> &mut ResourceTable {
         &mut self.table
     }
@@ -274,7 +1428,20 @@ impl StoreState {
         Ok(self.http_client.as_ref().expect("client initialized"))
     }
 
-    fn secrets_read(&self, name: String) -> Result<Vec<u8>, String> {
+    /// Reject access to `name` unless the secrets policy grants it to this
+    /// component for `tenant`.
+    fn authorize_secret(&self, tenant: &TenantCtx, name: &str, write: bool) -> Result<(), String> {
+        let Some(policy) = &self.secrets_access else {
+            return Ok(());
+        };
+        if policy.allows(&self.component, &tenant.tenant.0, name, write) {
+            Ok(())
+        } else {
+            Err(HostError::forbidden(&self.component, name, write).to_wire_error())
+        }
+    }
+
+    fn secrets_read(&mut self, name: String) -> Result<Vec<u8>, String> {
         let store = self
             .secrets_store
             .as_ref()
@@ -283,13 +1450,17 @@ impl StoreState {
             .tenant
             .as_ref()
             .ok_or_else(|| HostError::missing_ctx().to_wire_error())?;
-        store
+        self.authorize_secret(tenant, &name, false)?;
+        let bytes = store
             .read(tenant, &name)
             .map_err(HostError::from)
-            .map_err(|err| err.to_wire_error())
+            .map_err(|err| err.to_wire_error())?;
+        self.charge_host(bytes.len())?;
+        Ok(bytes)
     }
 
-    fn secrets_write(&self, name: String, bytes: Vec<u8>) -> Result<(), String> {
+    fn secrets_write(&mut self, name: String, bytes: Vec<u8>) -> Result<(), String> {
+        self.charge_host(name.len() + bytes.len())?;
         let store = self
             .secrets_store
             .as_ref()
@@ -298,10 +1469,23 @@ impl StoreState {
             .tenant
             .as_ref()
             .ok_or_else(|| HostError::missing_ctx().to_wire_error())?;
-        store
-            .write(tenant, &name, &bytes)
-            .map_err(HostError::from)
-            .map_err(|err| err.to_wire_error())
+        let result = self.authorize_secret(tenant, &name, true).and_then(|()| {
+            store
+                .write_revision(tenant, &name, &bytes)
+                .map_err(HostError::from)
+                .map_err(|err| err.to_wire_error())
+        });
+        self.audit_secret(SecretAuditEvent {
+            component: self.component.clone(),
+            tenant: tenant.clone(),
+            name,
+            action: SecretAction::Write,
+            value_digest: Some(hex::encode(Sha256::digest(&bytes))),
+            version: result.as_ref().ok().copied().flatten(),
+            error: result.as_ref().err().cloned(),
+            at: SystemTime::now(),
+        });
+        result.map(|_| ())
     }
 
     fn secrets_delete(&self, name: String) -> Result<(), String> {
@@ -313,13 +1497,139 @@ impl StoreState {
             .tenant
             .as_ref()
             .ok_or_else(|| HostError::missing_ctx().to_wire_error())?;
+        let result = self.authorize_secret(tenant, &name, true).and_then(|()| {
+            store
+                .soft_delete(tenant, &name)
+                .map_err(HostError::from)
+                .map_err(|err| err.to_wire_error())
+        });
+        self.audit_secret(SecretAuditEvent {
+            component: self.component.clone(),
+            tenant: tenant.clone(),
+            name,
+            action: if result == Ok(true) {
+                SecretAction::SoftDelete
+            } else {
+                SecretAction::Delete
+            },
+            value_digest: None,
+            version: None,
+            error: result.as_ref().err().cloned(),
+            at: SystemTime::now(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Blob policy and the tenant id blobs are scoped to.
+    fn blob_scope(&self) -> Result<(&BlobPolicy, &str), String> {
+        let policy = self
+            .blobs
+            .as_ref()
+            .ok_or_else(|| "blobs-unavailable:no blob store configured".to_string())?;
+        let tenant = self.tenant.as_ref().ok_or_else(|| {
+            "missing-tenant-ctx:tenant context is required to use blobs".to_string()
+        })?;
+        Ok((policy, &tenant.tenant.0))
+    }
+
+    fn blobs_put(&mut self, bytes: Vec<u8>, ttl_secs: Option<u64>) -> Result<String, String> {
+        self.charge_host(bytes.len())?;
+        let (policy, tenant) = self.blob_scope()?;
+        let size = bytes.len() as u64;
+        if size > policy.max_blob_bytes {
+            return Err(format!(
+                "blobs-too-large:{size} bytes exceeds the {} byte limit",
+                policy.max_blob_bytes
+            ));
+        }
+        let hash = blobs::blob_hash(&bytes);
+        let store = &policy.store;
+        let stored = store
+            .contains(tenant, &hash)
+            .map_err(|err| format!("blobs-error:{err}"))?;
+        if !stored {
+            let usage = store
+                .usage(tenant)
+                .map_err(|err| format!("blobs-error:{err}"))?;
+            if usage.saturating_add(size) > policy.tenant_quota_bytes {
+                return Err(format!(
+                    "blobs-quota:tenant `{tenant}` would exceed its {} byte quota",
+                    policy.tenant_quota_bytes
+                ));
+            }
+        }
+        let expires_at = policy
+            .ttl(ttl_secs.map(Duration::from_secs))
+            .map(|ttl| SystemTime::now() + ttl);
         store
-            .delete(tenant, &name)
-            .map_err(HostError::from)
-            .map_err(|err| err.to_wire_error())
+            .put(tenant, &hash, &bytes, expires_at)
+            .map_err(|err| format!("blobs-error:{err}"))?;
+        Ok(hash)
+    }
+
+    fn blobs_get(&mut self, hash: String) -> Result<Option<Vec<u8>>, String> {
+        if !blobs::is_blob_hash(&hash) {
+            return Err(format!("blobs-invalid-hash:{hash}"));
+        }
+        let (policy, tenant) = self.blob_scope()?;
+        let blob = policy
+            .store
+            .get(tenant, &hash)
+            .map_err(|err| format!("blobs-error:{err}"))?;
+        if let Some(bytes) = &blob {
+            self.charge_host(bytes.len())?;
+        }
+        Ok(blob)
+    }
+
+    /// Log a secret mutation and hand it to the store's audit hook.
+    fn audit_secret(&self, event: SecretAuditEvent) {
+        info!(
+            target: "greentic_mcp_exec::secrets",
+            component = %event.component,
+            tenant = %event.tenant.tenant.0,
+            name = %event.name,
+            action = event.action.as_str(),
+            value_digest = event.value_digest.as_deref().unwrap_or_default(),
+            version = event.version,
+            error = event.error.as_deref(),
+            "secret mutated by component"
+        );
+        if let Some(store) = &self.secrets_store {
+            store.audit(&event);
+        }
     }
 }
 
+fn build_wasi_ctx(
+    http_enabled: bool,
+    profile: NetworkProfile,
+    scratch_dir: Option<&Path>,
+    stdout: Option<ChunkPipe>,
+) -> wasmtime::Result<WasiCtx> {
+    let mut builder = WasiCtxBuilder::new();
+    builder.inherit_stdio().inherit_env();
+    match profile {
+        NetworkProfile::Standard if http_enabled => {
+            builder.inherit_network().allow_ip_name_lookup(true);
+        }
+        NetworkProfile::Standard => {}
+        NetworkProfile::Hardened => {
+            builder
+                .allow_tcp(false)
+                .allow_udp(false)
+                .allow_ip_name_lookup(false);
+        }
+    }
+    if let Some(dir) = scratch_dir {
+        builder.preopened_dir(dir, SCRATCH_GUEST_PATH, DirPerms::all(), FilePerms::all())?;
+    }
+    if let Some(pipe) = stdout {
+        builder.stdout(pipe);
+    }
+    Ok(builder.build())
+}
+
 impl StoreState {
     fn http_request(
         &mut self,
@@ -334,6 +1644,12 @@ impl StoreState {
 
         use reqwest::Method;
 
+        let sent = method.len()
+            + url.len()
+            + headers.iter().map(String::len).sum::<usize>()
+            + body.as_ref().map_or(0, Vec::len);
+        self.http_bytes_sent = self.http_bytes_sent.saturating_add(sent as u64);
+
         let client = self.http_client()?;
         let method =
             Method::from_bytes(method.as_bytes()).map_err(|_| "invalid-method".to_string())?;
@@ -345,23 +1661,49 @@ impl StoreState {
             builder = builder.body(body);
         }
 
-        let response = builder.send().map_err(|err| format!("request: {err}"))?;
+        let response = off_runtime(|| builder.send()).map_err(|err| format!("request: {err}"))?;
 
         if !response.status().is_success() {
             return Err(format!("status-{}", response.status().as_u16()));
         }
 
-        response
-            .bytes()
-            .map(|bytes| bytes.to_vec())
-            .map_err(|err| format!("body: {err}"))
+        // Reject oversized bodies up front when the server advertises a length,
+        // and otherwise stop reading one byte past what is left.
+        let remaining = self
+            .host_limits
+            .max_buffered_bytes
+            .saturating_sub(self.host_usage.buffered_bytes);
+        if response.content_length().is_some_and(|len| len > remaining) {
+            return Err(
+                self.record_violation("buffered-bytes", self.host_limits.max_buffered_bytes)
+            );
+        }
+
+        let mut bytes = Vec::new();
+        off_runtime(|| {
+            response
+                .take(remaining.saturating_add(1))
+                .read_to_end(&mut bytes)
+        })
+        .map_err(|err| format!("body: {err}"))?;
+        self.http_bytes_received = self.http_bytes_received.saturating_add(bytes.len() as u64);
+        if bytes.len() as u64 > remaining {
+            return Err(
+                self.record_violation("buffered-bytes", self.host_limits.max_buffered_bytes)
+            );
+        }
+        self.charge_host(bytes.len())?;
+        Ok(bytes)
     }
 
     fn kv_get(&mut self, _ns: String, _key: String) -> Option<String> {
         None
     }
 
-    fn kv_put(&mut self, _ns: String, _key: String, _val: String) {}
+    fn kv_put(&mut self, ns: String, key: String, val: String) {
+        // The KV import has no error channel; violations surface after the call.
+        let _ = self.charge_host(ns.len() + key.len() + val.len());
+    }
 }
 
 impl runner_host_http::RunnerHostHttp for StoreState {
@@ -375,8 +1717,22 @@ impl runner_host_http::RunnerHostHttp for StoreState {
         wasmtime::component::__internal::Vec<u8>,
         wasmtime::component::__internal::String,
     > {
-        let headers = headers.into_iter().collect();
-        self.http_request(method, url, headers, body)
+        let headers: Vec<String> = headers.into_iter().collect();
+        let request = self.transcribing().then(|| {
+            json!({
+                "method": method,
+                "url": url,
+                "headers": debug_dump::mask_headers(&headers),
+                "body": body,
+            })
+        });
+        self.host_call(
+            HTTP_INTERFACE,
+            "request",
+            request,
+            |state| state.http_request(method, url, headers, body),
+            to_json,
+        )
     }
 }
 
@@ -386,7 +1742,14 @@ impl runner_host_kv::RunnerHostKv for StoreState {
         ns: wasmtime::component::__internal::String,
         key: wasmtime::component::__internal::String,
     ) -> Option<wasmtime::component::__internal::String> {
-        self.kv_get(ns, key)
+        let request = self.transcribing().then(|| json!({ "ns": ns, "key": key }));
+        self.host_call(
+            KV_INTERFACE,
+            "get",
+            request,
+            |state| state.kv_get(ns, key),
+            to_json,
+        )
     }
 
     fn put(
@@ -395,7 +1758,16 @@ impl runner_host_kv::RunnerHostKv for StoreState {
         key: wasmtime::component::__internal::String,
         val: wasmtime::component::__internal::String,
     ) {
-        self.kv_put(ns.to_string(), key.to_string(), val.to_string());
+        let request = self
+            .transcribing()
+            .then(|| json!({ "ns": ns, "key": key, "val": val }));
+        self.host_call(
+            KV_INTERFACE,
+            "put",
+            request,
+            |state| state.kv_put(ns, key, val),
+            to_json,
+        )
     }
 }
 
@@ -418,6 +1790,21 @@ impl WasiHttpView for StoreState {
     }
 }
 
+/// Run blocking host I/O, moving it to a scoped thread when the current thread
+/// drives a tokio runtime, as it does under [`run_async`]: blocking clients
+/// refuse to run there.
+fn off_runtime<T: Send>(io: impl FnOnce() -> T + Send) -> T {
+    if tokio::runtime::Handle::try_current().is_err() {
+        return io();
+    }
+    thread::scope(|scope| {
+        scope
+            .spawn(io)
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
 fn apply_headers(
     mut builder: reqwest::blocking::RequestBuilder,
     headers: &[String],
@@ -443,25 +1830,114 @@ pub fn add_secrets_to_linker(linker: &mut Linker<StoreState>) -> wasmtime::Resul
     secrets.func_wrap(
         "read",
         |mut caller: wasmtime::StoreContextMut<'_, StoreState>, (name,): (String,)| {
-            Ok((caller.data_mut().secrets_read(name),))
+            let state = caller.data_mut();
+            let request = state.transcribing().then(|| json!({ "name": name }));
+            let response = state.host_call(
+                SECRETS_INTERFACE,
+                "read",
+                request,
+                |state| state.secrets_read(name),
+                |response| match response {
+                    Ok(_) => json!({ "Err": REDACTED_SECRET }),
+                    Err(err) => json!({ "Err": err }),
+                },
+            );
+            Ok((response,))
         },
     )?;
     secrets.func_wrap(
         "write",
         |mut caller: wasmtime::StoreContextMut<'_, StoreState>,
          (name, bytes): (String, Vec<u8>)| {
-            Ok((caller.data_mut().secrets_write(name, bytes),))
+            let state = caller.data_mut();
+            let request = state
+                .transcribing()
+                .then(|| json!({ "name": name, "sha256": hex::encode(Sha256::digest(&bytes)) }));
+            let response = state.host_call(
+                SECRETS_INTERFACE,
+                "write",
+                request,
+                |state| state.secrets_write(name, bytes),
+                to_json,
+            );
+            Ok((response,))
         },
     )?;
     secrets.func_wrap(
         "delete",
         |mut caller: wasmtime::StoreContextMut<'_, StoreState>, (name,): (String,)| {
-            Ok((caller.data_mut().secrets_delete(name),))
+            let state = caller.data_mut();
+            let request = state.transcribing().then(|| json!({ "name": name }));
+            let response = state.host_call(
+                SECRETS_INTERFACE,
+                "delete",
+                request,
+                |state| state.secrets_delete(name),
+                to_json,
+            );
+            Ok((response,))
+        },
+    )?;
+    Ok(())
+}
+
+pub fn add_blobs_to_linker(linker: &mut Linker<StoreState>) -> wasmtime::Result<()> {
+    let mut blobs = linker.instance(BLOBS_INTERFACE)?;
+    blobs.func_wrap(
+        "put",
+        |mut caller: wasmtime::StoreContextMut<'_, StoreState>,
+         (bytes, ttl_secs): (Vec<u8>, Option<u64>)| {
+            let state = caller.data_mut();
+            let request = state.transcribing().then(|| {
+                json!({ "hash": blobs::blob_hash(&bytes), "len": bytes.len(), "ttl_secs": ttl_secs })
+            });
+            let response = state.host_call(
+                BLOBS_INTERFACE,
+                "put",
+                request,
+                |state| state.blobs_put(bytes, ttl_secs),
+                to_json,
+            );
+            Ok((response,))
+        },
+    )?;
+    blobs.func_wrap(
+        "get",
+        |mut caller: wasmtime::StoreContextMut<'_, StoreState>, (hash,): (String,)| {
+            let state = caller.data_mut();
+            let request = state.transcribing().then(|| json!({ "hash": hash }));
+            let response = state.host_call(
+                BLOBS_INTERFACE,
+                "get",
+                request,
+                |state| state.blobs_get(hash),
+                to_json,
+            );
+            Ok((response,))
         },
     )?;
     Ok(())
 }
 
+/// Transcript form of a host call response.
+fn to_json<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// Running totals of host-side allocations for a single execution.
+#[derive(Clone, Copy, Debug, Default)]
+struct HostUsage {
+    resources: u32,
+    buffered_bytes: u64,
+}
+
+/// Host limit that was exceeded during an execution.
+#[derive(Clone, Debug)]
+pub struct HostLimitViolation {
+    pub resource: &'static str,
+    pub limit: u64,
+}
+
 #[derive(Clone, Debug)]
 struct HostError {
     code: String,
@@ -482,6 +1958,14 @@ impl HostError {
             message: "tenant context is required to access secrets".into(),
         }
     }
+
+    fn forbidden(component: &str, name: &str, write: bool) -> Self {
+        let access = if write { "write" } else { "read" };
+        Self {
+            code: "secrets-forbidden".into(),
+            message: format!("`{component}` may not {access} secret `{name}`"),
+        }
+    }
 }
 
 impl From<String> for HostError {
@@ -499,7 +1983,7 @@ impl HostError {
     }
 }
 
-fn try_mock_json(bytes: &[u8], action: &str) -> Option<Result<Value, RunnerError>> {
+pub(crate) fn try_mock_json(bytes: &[u8], action: &str) -> Option<Result<Value, RunnerError>> {
     let text = std::str::from_utf8(bytes).ok()?;
     let root: Value = serde_json::from_str(text).ok()?;
 
@@ -523,7 +2007,7 @@ fn try_mock_json(bytes: &[u8], action: &str) -> Option<Result<Value, RunnerError
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{RuntimePolicy, SecretsStore};
+    use crate::config::{HostLimits, RuntimePolicy, SecretsGrant, SecretsStore};
     use greentic_types::{EnvId, TenantCtx, TenantId};
     use std::sync::{Arc, Mutex};
     use wasmtime::component::Component;
@@ -562,7 +2046,7 @@ mod tests {
     #[test]
     fn secrets_read_fails_without_store() {
         let tenant = TenantCtx::new(EnvId("dev".into()), TenantId("acme".into()));
-        let state = StoreState::new(true, None, Some(tenant));
+        let mut state = StoreState::new(true, None, Some(tenant));
         let err = state
             .secrets_read("api-key".into())
             .expect_err("should fail");
@@ -572,11 +2056,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn blobs_are_content_addressed_scoped_and_bounded() {
+        use crate::blobs::{BlobPolicy, InMemoryBlobStore, blob_hash};
+
+        let store = Arc::new(InMemoryBlobStore::new());
+        let policy = BlobPolicy {
+            max_blob_bytes: 8,
+            tenant_quota_bytes: 12,
+            ..BlobPolicy::new(store.clone())
+        };
+        let acme = TenantCtx::new(EnvId("dev".into()), TenantId("acme".into()));
+        let mut state = StoreState::new(false, None, Some(acme)).with_blobs(Some(policy.clone()));
+
+        let hash = state.blobs_put(b"artifact".to_vec(), None).expect("put");
+        assert_eq!(hash, blob_hash(b"artifact"));
+        assert_eq!(
+            state.blobs_get(hash.clone()).expect("get"),
+            Some(b"artifact".to_vec())
+        );
+        // Putting the same content again does not count against the quota.
+        state
+            .blobs_put(b"artifact".to_vec(), Some(60))
+            .expect("idempotent put");
+
+        let err = state.blobs_put(b"oversized".to_vec(), None).unwrap_err();
+        assert!(err.starts_with("blobs-too-large:"), "{err}");
+        let err = state.blobs_put(b"another".to_vec(), None).unwrap_err();
+        assert!(err.starts_with("blobs-quota:"), "{err}");
+        let err = state.blobs_get("sha256:nope".into()).unwrap_err();
+        assert!(err.starts_with("blobs-invalid-hash:"), "{err}");
+
+        let globex = TenantCtx::new(EnvId("dev".into()), TenantId("globex".into()));
+        let mut other = StoreState::new(false, None, Some(globex)).with_blobs(Some(policy));
+        assert_eq!(other.blobs_get(hash.clone()).expect("get"), None);
+
+        let mut unbacked = StoreState::new(false, None, None);
+        let err = unbacked.blobs_get(hash).unwrap_err();
+        assert!(err.starts_with("blobs-unavailable:"), "{err}");
+    }
+
     #[test]
     fn secrets_read_uses_scope() {
         let store = Arc::new(MockSecretsStore::default());
         let tenant = TenantCtx::new(EnvId("dev".into()), TenantId("acme".into()));
-        let state = StoreState::new(true, Some(store.clone()), Some(tenant));
+        let mut state = StoreState::new(true, Some(store.clone()), Some(tenant));
         let bytes = state.secrets_read("api-key".into()).expect("read ok");
         assert_eq!(bytes, b"ok");
         let last = store.last.lock().unwrap().clone().expect("called");
@@ -584,6 +2108,511 @@ mod tests {
         assert_eq!(last.1, "api-key");
     }
 
+    #[test]
+    fn secrets_access_policy_forbids_ungranted_names() {
+        let store = Arc::new(MockSecretsStore::default());
+        let tenant = TenantCtx::new(EnvId("dev".into()), TenantId("acme".into()));
+        let policy = SecretsAccessPolicy {
+            grants: vec![SecretsGrant {
+                component: "weather".into(),
+                tenant: Some("acme".into()),
+                names: vec!["weather/*".into()],
+                write: false,
+            }],
+        };
+        let mut state = StoreState::new(false, Some(store.clone()), Some(tenant))
+            .with_secrets_access(Some(policy));
+        state.set_component("weather");
+
+        state
+            .secrets_read("weather/api-key".into())
+            .expect("granted read");
+        let err = state
+            .secrets_read("billing/api-key".into())
+            .expect_err("ungranted name");
+        assert!(err.starts_with("secrets-forbidden:"), "got {err}");
+        let err = state
+            .secrets_write("weather/api-key".into(), b"new".to_vec())
+            .expect_err("read-only grant");
+        assert!(err.starts_with("secrets-forbidden:"), "got {err}");
+
+        state.set_component("other");
+        assert!(state.secrets_read("weather/api-key".into()).is_err());
+        assert_eq!(
+            store.last.lock().unwrap().clone().map(|(_, name)| name),
+            Some("weather/api-key".to_string())
+        );
+    }
+
+    #[derive(Default)]
+    struct VersionedSecretsStore {
+        versions: Mutex<Vec<Vec<u8>>>,
+        events: Mutex<Vec<SecretAuditEvent>>,
+    }
+
+    impl SecretsStore for VersionedSecretsStore {
+        fn read(&self, _scope: &TenantCtx, _name: &str) -> Result<Vec<u8>, String> {
+            Err("not-found".into())
+        }
+
+        fn write_revision(
+            &self,
+            _scope: &TenantCtx,
+            _name: &str,
+            bytes: &[u8],
+        ) -> Result<Option<u64>, String> {
+            let mut versions = self.versions.lock().unwrap();
+            versions.push(bytes.to_vec());
+            Ok(Some(versions.len() as u64))
+        }
+
+        fn soft_delete(&self, _scope: &TenantCtx, _name: &str) -> Result<bool, String> {
+            Ok(true)
+        }
+
+        fn audit(&self, event: &SecretAuditEvent) {
+            self.events.lock().unwrap().push(event.clone());
+        }
+    }
+
+    #[test]
+    fn secrets_mutations_are_versioned_and_audited() {
+        let store = Arc::new(VersionedSecretsStore::default());
+        let tenant = TenantCtx::new(EnvId("dev".into()), TenantId("acme".into()));
+        let policy = SecretsAccessPolicy {
+            grants: vec![SecretsGrant {
+                component: "weather".into(),
+                tenant: None,
+                names: vec!["weather/*".into()],
+                write: true,
+            }],
+        };
+        let mut state = StoreState::new(false, Some(store.clone()), Some(tenant))
+            .with_secrets_access(Some(policy));
+        state.set_component("weather");
+
+        state
+            .secrets_write("weather/api-key".into(), b"v1".to_vec())
+            .expect("first write");
+        state
+            .secrets_write("weather/api-key".into(), b"v2".to_vec())
+            .expect("second write");
+        state
+            .secrets_delete("weather/api-key".into())
+            .expect("soft delete");
+        state
+            .secrets_write("billing/api-key".into(), b"x".to_vec())
+            .expect_err("ungranted name");
+
+        let events = store.events.lock().unwrap().clone();
+        assert_eq!(events.len(), 4);
+        assert!(events.iter().all(|event| event.component == "weather"));
+        assert_eq!(events[0].action, SecretAction::Write);
+        assert_eq!(events[0].version, Some(1));
+        assert_eq!(
+            events[0].value_digest.as_deref(),
+            Some(hex::encode(Sha256::digest(b"v1")).as_str())
+        );
+        assert_eq!(events[1].version, Some(2));
+        assert_eq!(events[2].action, SecretAction::SoftDelete);
+        assert_eq!(events[2].value_digest, None);
+        assert!(
+            events[3]
+                .error
+                .as_deref()
+                .is_some_and(|err| err.starts_with("secrets-forbidden:"))
+        );
+        assert_eq!(store.versions.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn hardened_profile_disables_http() {
+        let mut state = StoreState::new(true, None, None)
+            .with_network_profile(NetworkProfile::Hardened)
+            .expect("hardened wasi ctx");
+        let result =
+            state.http_request("GET".into(), "https://example.com".into(), Vec::new(), None);
+        assert!(matches!(result, Err(err) if err == "http-disabled"));
+    }
+
+    #[test]
+    fn hardened_profile_rejects_socket_imports() {
+        let wasm = wat::parse_str(
+            r#"(component
+                (import "wasi:sockets/tcp@0.2.0" (instance))
+            )"#,
+        )
+        .expect("wat should parse");
+
+        let runner = DefaultRunner::new(&RuntimePolicy::default()).expect("runner config");
+        let component =
+            Component::from_binary(&runner.engine, &wasm).expect("component should compile");
+        let err = reject_network_imports(&runner.engine, &component).expect_err("should reject");
+        assert!(
+            matches!(&err, RunnerError::PolicyViolation(msg) if msg.contains("wasi:sockets/tcp")),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn scratch_write_past_the_quota_fails_during_the_call() {
+        let runtime = RuntimePolicy {
+            scratch_quota_bytes: Some(128),
+            ..RuntimePolicy::default()
+        };
+        let engine = build_engine(&runtime).expect("engine");
+        let linker = build_linker(&engine).expect("linker");
+        // Writes 64 bytes, then 128 more, and traps unless the second write
+        // fails with `insufficient-space` (error code 23).
+        let wasm = wat::parse_str(
+            r#"(component $c
+                (import "wasi:filesystem/types@0.2.6" (instance $types
+                    (export "descriptor" (type $d (sub resource)))
+                    (type $ec (enum "access" "would-block" "already" "bad-descriptor" "busy"
+                        "deadlock" "quota" "exist" "file-too-large" "illegal-byte-sequence"
+                        "in-progress" "interrupted" "invalid" "io" "is-directory" "loop"
+                        "too-many-links" "message-size" "name-too-long" "no-device" "no-entry"
+                        "no-lock" "insufficient-memory" "insufficient-space" "not-directory"
+                        "not-empty" "not-recoverable" "unsupported" "no-tty" "no-such-device"
+                        "overflow" "not-permitted" "pipe" "read-only" "invalid-seek"
+                        "text-file-busy" "cross-device"))
+                    (export "error-code" (type $e (eq $ec)))
+                    (type $pf (flags "symlink-follow"))
+                    (export "path-flags" (type $pfe (eq $pf)))
+                    (type $of (flags "create" "directory" "exclusive" "truncate"))
+                    (export "open-flags" (type $ofe (eq $of)))
+                    (type $df (flags "read" "write" "file-integrity-sync" "data-integrity-sync"
+                        "requested-write-sync" "mutate-directory"))
+                    (export "descriptor-flags" (type $dfe (eq $df)))
+                    (export "[method]descriptor.open-at" (func (param "self" (borrow $d))
+                        (param "path-flags" $pfe) (param "path" string) (param "open-flags" $ofe)
+                        (param "flags" $dfe) (result (result (own $d) (error $e)))))
+                    (export "[method]descriptor.write" (func (param "self" (borrow $d))
+                        (param "buffer" (list u8)) (param "offset" u64)
+                        (result (result u64 (error $e)))))))
+                (alias export $types "descriptor" (type $descriptor))
+                (import "wasi:filesystem/preopens@0.2.6" (instance $preopens
+                    (alias outer $c $descriptor (type $d))
+                    (export "get-directories" (func (result (list (tuple (own $d) string)))))))
+                (core module $mem
+                    (memory (export "memory") 1)
+                    (global $next (mut i32) (i32.const 8192))
+                    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                        (local $ptr i32)
+                        (local.set $ptr (i32.and
+                            (i32.add (global.get $next) (i32.sub (local.get 2) (i32.const 1)))
+                            (i32.sub (i32.const 0) (local.get 2))))
+                        (global.set $next (i32.add (local.get $ptr) (local.get 3)))
+                        (local.get $ptr)))
+                (core instance $m (instantiate $mem))
+                (core func $get_dirs (canon lower (func $preopens "get-directories")
+                    (memory $m "memory") (realloc (func $m "realloc"))))
+                (core func $open_at (canon lower (func $types "[method]descriptor.open-at")
+                    (memory $m "memory")))
+                (core func $write (canon lower (func $types "[method]descriptor.write")
+                    (memory $m "memory")))
+                (core module $main
+                    (import "host" "memory" (memory 1))
+                    (import "host" "get-directories" (func $get_dirs (param i32)))
+                    (import "host" "open-at" (func $open_at
+                        (param i32 i32 i32 i32 i32 i32 i32)))
+                    (import "host" "write" (func $write (param i32 i32 i32 i64 i32)))
+                    (data (i32.const 16) "out.bin")
+                    (data (i32.const 32) "{}")
+                    (func (export "exec") (param i32 i32 i32 i32) (result i32)
+                        (local $file i32)
+                        (call $get_dirs (i32.const 64))
+                        (call $open_at (i32.load (i32.load (i32.const 64))) (i32.const 0)
+                            (i32.const 16) (i32.const 7) (i32.const 1) (i32.const 2)
+                            (i32.const 80))
+                        (if (i32.load8_u (i32.const 80)) (then (unreachable)))
+                        (local.set $file (i32.load (i32.const 84)))
+                        (call $write (local.get $file) (i32.const 4096) (i32.const 64)
+                            (i64.const 0) (i32.const 96))
+                        (if (i32.load8_u (i32.const 96)) (then (unreachable)))
+                        (call $write (local.get $file) (i32.const 4096) (i32.const 128)
+                            (i64.const 64) (i32.const 96))
+                        (if (i32.eqz (i32.load8_u (i32.const 96))) (then (unreachable)))
+                        (if (i32.ne (i32.load8_u (i32.const 104)) (i32.const 23))
+                            (then (unreachable)))
+                        (i32.store (i32.const 128) (i32.const 32))
+                        (i32.store (i32.const 132) (i32.const 2))
+                        (i32.const 128)))
+                (core instance $i (instantiate $main
+                    (with "host" (instance
+                        (export "memory" (memory $m "memory"))
+                        (export "get-directories" (func $get_dirs))
+                        (export "open-at" (func $open_at))
+                        (export "write" (func $write))))))
+                (func (export "exec") (param "action" string) (param "args" string) (result string)
+                    (canon lift (core func $i "exec") (memory $m "memory")
+                        (realloc (func $m "realloc")))))"#,
+        )
+        .expect("writing component");
+        let component = Component::new(&engine, wasm).expect("component");
+        let pre = prepare_instance(&engine, &linker, &component, &runtime).expect("pre");
+        let request = ExecRequest {
+            component: "scratch".into(),
+            action: "run".into(),
+            args: json!({}),
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Priority::Interactive,
+        };
+
+        let err = instantiate(&engine, &pre, &runtime, false, None, None)
+            .expect("instantiate")
+            .call(&request, &runtime)
+            .expect_err("over quota");
+        assert!(
+            matches!(
+                err,
+                RunnerError::HostResourceLimit { ref resource, limit: 128 } if resource == "scratch-bytes"
+            ),
+            "got {err}"
+        );
+    }
+
+    #[test]
+    fn scratch_creates_past_the_entry_cap_fail_during_the_call() {
+        let runtime = RuntimePolicy {
+            scratch_quota_bytes: Some(1024),
+            scratch_max_entries: 3,
+            ..RuntimePolicy::default()
+        };
+        let engine = build_engine(&runtime).expect("engine");
+        let linker = build_linker(&engine).expect("linker");
+        // Creates files `a`, `b`, ... and traps unless the fourth create is
+        // the first to fail, with `quota` (error code 6).
+        let wasm = wat::parse_str(
+            r#"(component $c
+                (import "wasi:filesystem/types@0.2.6" (instance $types
+                    (export "descriptor" (type $d (sub resource)))
+                    (type $ec (enum "access" "would-block" "already" "bad-descriptor" "busy"
+                        "deadlock" "quota" "exist" "file-too-large" "illegal-byte-sequence"
+                        "in-progress" "interrupted" "invalid" "io" "is-directory" "loop"
+                        "too-many-links" "message-size" "name-too-long" "no-device" "no-entry"
+                        "no-lock" "insufficient-memory" "insufficient-space" "not-directory"
+                        "not-empty" "not-recoverable" "unsupported" "no-tty" "no-such-device"
+                        "overflow" "not-permitted" "pipe" "read-only" "invalid-seek"
+                        "text-file-busy" "cross-device"))
+                    (export "error-code" (type $e (eq $ec)))
+                    (type $pf (flags "symlink-follow"))
+                    (export "path-flags" (type $pfe (eq $pf)))
+                    (type $of (flags "create" "directory" "exclusive" "truncate"))
+                    (export "open-flags" (type $ofe (eq $of)))
+                    (type $df (flags "read" "write" "file-integrity-sync" "data-integrity-sync"
+                        "requested-write-sync" "mutate-directory"))
+                    (export "descriptor-flags" (type $dfe (eq $df)))
+                    (export "[method]descriptor.open-at" (func (param "self" (borrow $d))
+                        (param "path-flags" $pfe) (param "path" string) (param "open-flags" $ofe)
+                        (param "flags" $dfe) (result (result (own $d) (error $e)))))))
+                (alias export $types "descriptor" (type $descriptor))
+                (import "wasi:filesystem/preopens@0.2.6" (instance $preopens
+                    (alias outer $c $descriptor (type $d))
+                    (export "get-directories" (func (result (list (tuple (own $d) string)))))))
+                (core module $mem
+                    (memory (export "memory") 1)
+                    (global $next (mut i32) (i32.const 8192))
+                    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                        (local $ptr i32)
+                        (local.set $ptr (i32.and
+                            (i32.add (global.get $next) (i32.sub (local.get 2) (i32.const 1)))
+                            (i32.sub (i32.const 0) (local.get 2))))
+                        (global.set $next (i32.add (local.get $ptr) (local.get 3)))
+                        (local.get $ptr)))
+                (core instance $m (instantiate $mem))
+                (core func $get_dirs (canon lower (func $preopens "get-directories")
+                    (memory $m "memory") (realloc (func $m "realloc"))))
+                (core func $open_at (canon lower (func $types "[method]descriptor.open-at")
+                    (memory $m "memory")))
+                (core module $main
+                    (import "host" "memory" (memory 1))
+                    (import "host" "get-directories" (func $get_dirs (param i32)))
+                    (import "host" "open-at" (func $open_at
+                        (param i32 i32 i32 i32 i32 i32 i32)))
+                    (data (i32.const 32) "{}")
+                    (func (export "exec") (param i32 i32 i32 i32) (result i32)
+                        (local $dir i32)
+                        (local $i i32)
+                        (call $get_dirs (i32.const 64))
+                        (local.set $dir (i32.load (i32.load (i32.const 64))))
+                        (loop $next
+                            (i32.store8 (i32.const 16) (i32.add (i32.const 97) (local.get $i)))
+                            (call $open_at (local.get $dir) (i32.const 0) (i32.const 16)
+                                (i32.const 1) (i32.const 1) (i32.const 2) (i32.const 80))
+                            (if (i32.load8_u (i32.const 80))
+                                (then
+                                    (if (i32.ne (i32.load8_u (i32.const 84)) (i32.const 6))
+                                        (then (unreachable)))
+                                    (if (i32.ne (local.get $i) (i32.const 3))
+                                        (then (unreachable)))
+                                    (i32.store (i32.const 128) (i32.const 32))
+                                    (i32.store (i32.const 132) (i32.const 2))
+                                    (return (i32.const 128))))
+                            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                            (br_if $next (i32.lt_u (local.get $i) (i32.const 10))))
+                        (unreachable)))
+                (core instance $i (instantiate $main
+                    (with "host" (instance
+                        (export "memory" (memory $m "memory"))
+                        (export "get-directories" (func $get_dirs))
+                        (export "open-at" (func $open_at))))))
+                (func (export "exec") (param "action" string) (param "args" string) (result string)
+                    (canon lift (core func $i "exec") (memory $m "memory")
+                        (realloc (func $m "realloc")))))"#,
+        )
+        .expect("creating component");
+        let component = Component::new(&engine, wasm).expect("component");
+        let pre = prepare_instance(&engine, &linker, &component, &runtime).expect("pre");
+        let request = ExecRequest {
+            component: "scratch".into(),
+            action: "run".into(),
+            args: json!({}),
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Priority::Interactive,
+        };
+
+        let err = instantiate(&engine, &pre, &runtime, false, None, None)
+            .expect("instantiate")
+            .call(&request, &runtime)
+            .expect_err("over the entry cap");
+        assert!(
+            matches!(
+                err,
+                RunnerError::HostResourceLimit { ref resource, limit: 3 } if resource == "scratch-entries"
+            ),
+            "got {err}"
+        );
+    }
+
+    #[test]
+    fn secrets_reads_are_bounded_by_host_limits() {
+        let store = Arc::new(MockSecretsStore::default());
+        let tenant = TenantCtx::new(EnvId("dev".into()), TenantId("acme".into()));
+        let mut state =
+            StoreState::new(false, Some(store), Some(tenant)).with_host_limits(HostLimits {
+                max_resources: 1,
+                ..HostLimits::default()
+            });
+
+        state.secrets_read("api-key".into()).expect("first read ok");
+        let err = state
+            .secrets_read("api-key".into())
+            .expect_err("second read should hit the limit");
+        assert_eq!(err, "host-resource-limit:resources");
+
+        let violation = state
+            .take_host_limit_violation()
+            .expect("violation recorded");
+        assert_eq!(violation.resource, "resources");
+        assert_eq!(violation.limit, 1);
+        assert!(state.take_host_limit_violation().is_none());
+    }
+
+    #[test]
+    fn kv_put_records_buffered_bytes_violation() {
+        let mut state = StoreState::new(false, None, None).with_host_limits(HostLimits {
+            max_buffered_bytes: 4,
+            ..HostLimits::default()
+        });
+
+        state.kv_put("ns".into(), "key".into(), "value".into());
+        let violation = state
+            .take_host_limit_violation()
+            .expect("violation recorded");
+        assert_eq!(violation.resource, "buffered-bytes");
+        assert_eq!(violation.limit, 4);
+    }
+
+    #[test]
+    fn unadvertised_http_bodies_stop_at_the_buffered_bytes_cap() {
+        use std::io::Write;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = thread::spawn(move || {
+            let (mut conn, _) = listener.accept().expect("accept");
+            let mut request = [0u8; 1024];
+            let _ = conn.read(&mut request);
+            // Chunked, so the client cannot see the length up front.
+            let _ = conn.write_all(
+                b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n",
+            );
+            for _ in 0..64 {
+                if conn.write_all(b"400\r\n").is_err()
+                    || conn.write_all(&[b'x'; 1024]).is_err()
+                    || conn.write_all(b"\r\n").is_err()
+                {
+                    return;
+                }
+            }
+            let _ = conn.write_all(b"0\r\n\r\n");
+        });
+
+        let mut state = StoreState::new(true, None, None).with_host_limits(HostLimits {
+            max_buffered_bytes: 4096,
+            ..HostLimits::default()
+        });
+        let err = state
+            .http_request("GET".into(), format!("http://{addr}/"), Vec::new(), None)
+            .expect_err("body past the cap");
+        assert_eq!(err, "host-resource-limit:buffered-bytes");
+        assert!(state.http_bytes_received <= 4097);
+        let violation = state
+            .take_host_limit_violation()
+            .expect("violation recorded");
+        assert_eq!(violation.limit, 4096);
+        drop(state);
+        server.join().expect("server");
+    }
+
+    #[test]
+    fn host_calls_are_recorded_and_replayed() {
+        use runner_host_http::RunnerHostHttp;
+        use runner_host_kv::RunnerHostKv;
+
+        let mut state = StoreState::new(false, None, None);
+        state.record_host_calls();
+        let headers = vec!["Authorization: Bearer token".to_string()];
+        let err = state
+            .request("GET".into(), "https://example.com".into(), headers, None)
+            .expect_err("http disabled");
+        state.put("ns".into(), "key".into(), "value".into());
+        let calls = state.take_host_calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].interface, HTTP_INTERFACE);
+        assert_eq!(
+            calls[0].request["headers"],
+            json!(["Authorization: <redacted>"])
+        );
+        assert_eq!(calls[0].response, json!({ "Err": err }));
+
+        let mut replay = StoreState::new(false, None, None);
+        replay.replay_host_calls(calls.clone());
+        let headers = vec!["Authorization: Bearer other".to_string()];
+        let replayed = replay.request("GET".into(), "https://example.com".into(), headers, None);
+        assert_eq!(replayed, Err(err));
+        assert!(
+            replay
+                .take_replay_divergence()
+                .expect("unanswered put")
+                .contains("never made")
+        );
+
+        replay.replay_host_calls(calls);
+        assert_eq!(replay.get("ns".into(), "key".into()), None);
+        let divergence = replay.take_replay_divergence().expect("diverged");
+        assert!(
+            divergence.contains("guest called runner-host-kv.get"),
+            "{divergence}"
+        );
+    }
+
     #[test]
     fn links_preview2_wasi_imports() {
         let wasm = wat::parse_str(
@@ -614,4 +2643,181 @@ mod tests {
             .instantiate(&mut store, &component)
             .expect("instantiate with preview2 imports");
     }
+
+    #[test]
+    fn runaway_guests_are_interrupted_at_the_wallclock_timeout() {
+        let runtime = RuntimePolicy {
+            wallclock_timeout: Duration::from_millis(100),
+            ..RuntimePolicy::default()
+        };
+        let engine = build_engine(&runtime).expect("engine");
+        let linker = build_linker(&engine).expect("linker");
+        let component = Component::new(&engine, crate::stress::spinning_component())
+            .expect("spinning component");
+        let pre = prepare_instance(&engine, &linker, &component, &runtime).expect("pre");
+        let request = ExecRequest {
+            component: "spin".into(),
+            action: "run".into(),
+            args: json!({}),
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Priority::Interactive,
+        };
+
+        // Called on this thread, without a per-call timeout to fall back on.
+        let started = Instant::now();
+        let err = instantiate(&engine, &pre, &runtime, false, None, None)
+            .expect("instantiate")
+            .call(&request, &runtime)
+            .expect_err("interrupted");
+        assert!(matches!(err, RunnerError::Timeout { .. }), "got {err}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn runaway_guests_run_out_of_their_fuel_budget() {
+        let runtime = RuntimePolicy {
+            fuel: Some(100_000),
+            wallclock_timeout: Duration::from_secs(30),
+            ..RuntimePolicy::default()
+        };
+        let engine = build_engine(&runtime).expect("engine");
+        let linker = build_linker(&engine).expect("linker");
+        let component = Component::new(&engine, crate::stress::spinning_component())
+            .expect("spinning component");
+        let pre = prepare_instance(&engine, &linker, &component, &runtime).expect("pre");
+        let request = ExecRequest {
+            component: "spin".into(),
+            action: "run".into(),
+            args: json!({}),
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Priority::Interactive,
+        };
+
+        let err = instantiate(&engine, &pre, &runtime, false, None, None)
+            .expect("instantiate")
+            .call(&request, &runtime)
+            .expect_err("out of fuel");
+        assert!(
+            matches!(err, RunnerError::FuelExhausted { budget: 100_000 }),
+            "got {err}"
+        );
+    }
+
+    #[test]
+    fn host_calls_past_the_table_cap_fail_with_a_host_resource_limit() {
+        let runtime = RuntimePolicy {
+            host_limits: HostLimits {
+                max_table_entries: 8,
+                ..HostLimits::default()
+            },
+            ..RuntimePolicy::default()
+        };
+        let engine = build_engine(&runtime).expect("engine");
+        let linker = build_linker(&engine).expect("linker");
+        // Every `get-stdout` adds a stream to the table; none are dropped.
+        let wasm = wat::parse_str(
+            r#"(component $c
+                (import "wasi:io/streams@0.2.6" (instance $streams
+                    (export "output-stream" (type (sub resource)))))
+                (alias export $streams "output-stream" (type $output_stream))
+                (import "wasi:cli/stdout@0.2.6" (instance $stdout
+                    (alias outer $c $output_stream (type $o))
+                    (export "get-stdout" (func (result (own $o))))))
+                (core func $get_stdout (canon lower (func $stdout "get-stdout")))
+                (core module $m
+                    (import "host" "get-stdout" (func $get_stdout (result i32)))
+                    (memory (export "memory") 1)
+                    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                        (i32.const 1024))
+                    (func (export "exec") (param i32 i32 i32 i32) (result i32)
+                        (loop $leak
+                            (drop (call $get_stdout))
+                            (br $leak))
+                        (unreachable)))
+                (core instance $i (instantiate $m
+                    (with "host" (instance (export "get-stdout" (func $get_stdout))))))
+                (func (export "exec") (param "action" string) (param "args" string) (result string)
+                    (canon lift (core func $i "exec") (memory $i "memory")
+                        (realloc (func $i "realloc")))))"#,
+        )
+        .expect("leaking component");
+        let component = Component::new(&engine, wasm).expect("component");
+        let pre = prepare_instance(&engine, &linker, &component, &runtime).expect("pre");
+        let request = ExecRequest {
+            component: "leak".into(),
+            action: "run".into(),
+            args: json!({}),
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Priority::Interactive,
+        };
+
+        let err = instantiate(&engine, &pre, &runtime, false, None, None)
+            .expect("instantiate")
+            .call(&request, &runtime)
+            .expect_err("table cap");
+        assert!(
+            matches!(
+                err,
+                RunnerError::HostResourceLimit { ref resource, limit: 8 } if resource == "table-entries"
+            ),
+            "got {err}"
+        );
+    }
+
+    #[test]
+    fn memory_growth_past_the_cap_fails_with_memory_limit() {
+        let runtime = RuntimePolicy {
+            max_memory: Some(1 << 20),
+            ..RuntimePolicy::default()
+        };
+        let engine = build_engine(&runtime).expect("engine");
+        let linker = build_linker(&engine).expect("linker");
+        let wasm = wat::parse_str(
+            r#"(component
+                (core module $m
+                    (memory (export "memory") 1)
+                    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                        (i32.const 1024))
+                    (func (export "exec") (param i32 i32 i32 i32) (result i32)
+                        (drop (memory.grow (i32.const 100)))
+                        (unreachable)))
+                (core instance $i (instantiate $m))
+                (func (export "exec") (param "action" string) (param "args" string) (result string)
+                    (canon lift (core func $i "exec") (memory $i "memory")
+                        (realloc (func $i "realloc")))))"#,
+        )
+        .expect("growing component");
+        let component = Component::new(&engine, wasm).expect("component");
+        let pre = prepare_instance(&engine, &linker, &component, &runtime).expect("pre");
+        let request = ExecRequest {
+            component: "grow".into(),
+            action: "run".into(),
+            args: json!({}),
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Priority::Interactive,
+        };
+
+        let err = instantiate(&engine, &pre, &runtime, false, None, None)
+            .expect("instantiate")
+            .call(&request, &runtime)
+            .expect_err("memory limit");
+        assert!(
+            matches!(
+                err,
+                RunnerError::MemoryLimit {
+                    limit: 1_048_576,
+                    requested: 6_619_136,
+                }
+            ),
+            "got {err}"
+        );
+    }
 }
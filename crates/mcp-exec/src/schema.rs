@@ -0,0 +1,399 @@
+//! Helpers for working with tool input schemas (JSON Schema objects as
+//! published in a router's `input-schema`).
+
+use std::io::{self, BufRead, Write};
+
+use serde_json::{Map, Value};
+
+/// Scalar kind of a top-level argument field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldKind {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    /// Objects, arrays, and anything untyped; entered as raw JSON.
+    Json,
+}
+
+impl FieldKind {
+    fn from_schema(schema: &Value) -> Self {
+        let ty = match schema.get("type") {
+            Some(Value::String(ty)) => Some(ty.as_str()),
+            // `["string", "null"]` style unions: use the first non-null type.
+            Some(Value::Array(types)) => types
+                .iter()
+                .filter_map(Value::as_str)
+                .find(|ty| *ty != "null"),
+            _ => None,
+        };
+        match ty {
+            Some("string") => FieldKind::String,
+            Some("integer") => FieldKind::Integer,
+            Some("number") => FieldKind::Number,
+            Some("boolean") => FieldKind::Boolean,
+            _ => FieldKind::Json,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldKind::String => "string",
+            FieldKind::Integer => "integer",
+            FieldKind::Number => "number",
+            FieldKind::Boolean => "boolean",
+            FieldKind::Json => "json",
+        }
+    }
+}
+
+/// A top-level property of an object input schema.
+#[derive(Clone, Debug)]
+pub struct Field {
+    pub name: String,
+    pub kind: FieldKind,
+    pub description: Option<String>,
+    pub required: bool,
+    pub default: Option<Value>,
+    /// Allowed values from `enum`, if any.
+    pub choices: Vec<Value>,
+    /// The property's own schema.
+    pub schema: Value,
+}
+
+impl Field {
+    /// Parse user input for this field.
+    ///
+    /// Empty input yields the default, or `None` for optional fields; required
+    /// fields without a default reject it.
+    pub fn parse(&self, raw: &str) -> Result<Option<Value>, String> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return match (&self.default, self.required) {
+                (Some(default), _) => Ok(Some(default.clone())),
+                (None, false) => Ok(None),
+                (None, true) => Err(format!("`{}` is required", self.name)),
+            };
+        }
+
+        let value = match self.kind {
+            FieldKind::String => Value::String(raw.to_string()),
+            FieldKind::Integer => raw
+                .parse::<i64>()
+                .map(Value::from)
+                .map_err(|_| format!("`{raw}` is not an integer"))?,
+            FieldKind::Number => raw
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(|| format!("`{raw}` is not a number"))?,
+            FieldKind::Boolean => match raw.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "y" => Value::Bool(true),
+                "false" | "no" | "n" => Value::Bool(false),
+                _ => return Err(format!("`{raw}` is not a boolean (true/false)")),
+            },
+            FieldKind::Json => {
+                serde_json::from_str(raw).map_err(|err| format!("invalid JSON: {err}"))?
+            }
+        };
+
+        if !self.choices.is_empty() && !self.choices.contains(&value) {
+            return Err(format!(
+                "`{raw}` is not one of {}",
+                render_choices(&self.choices)
+            ));
+        }
+        Ok(Some(value))
+    }
+
+    fn prompt(&self) -> String {
+        let mut prompt = format!("{} ({}", self.name, self.kind.as_str());
+        if self.required {
+            prompt.push_str(", required");
+        }
+        prompt.push(')');
+        if !self.choices.is_empty() {
+            prompt.push_str(&format!(" {}", render_choices(&self.choices)));
+        }
+        if let Some(default) = &self.default {
+            prompt.push_str(&format!(" [{}]", render_scalar(default)));
+        }
+        prompt.push_str(": ");
+        prompt
+    }
+}
+
+/// Top-level fields of an object schema, required ones first.
+pub fn fields(schema: &Value) -> Vec<Field> {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+
+    let mut fields: Vec<Field> = properties
+        .iter()
+        .map(|(name, property)| Field {
+            name: name.clone(),
+            kind: FieldKind::from_schema(property),
+            description: property
+                .get("description")
+                .and_then(Value::as_str)
+                .map(str::to_owned),
+            required: required.contains(&name.as_str()),
+            default: property.get("default").cloned(),
+            choices: property
+                .get("enum")
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default(),
+            schema: property.clone(),
+        })
+        .collect();
+    fields.sort_by_key(|field| !field.required);
+    fields
+}
+
+/// Build an arguments object by prompting for each field of `schema`.
+///
+/// Prompts go to `output`, answers are read line by line from `input`, and
+/// invalid answers are re-asked. Schemas without properties are entered as a
+/// single raw JSON object.
+pub fn prompt_arguments(
+    schema: &Value,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<Value> {
+    let fields = fields(schema);
+    if fields.is_empty() {
+        let raw = Field {
+            name: "arguments".into(),
+            kind: FieldKind::Json,
+            description: Some("tool schema has no properties; enter a JSON object".into()),
+            required: false,
+            default: Some(Value::Object(Map::new())),
+            choices: Vec::new(),
+            schema: schema.clone(),
+        };
+        return Ok(ask(&raw, input, output)?.unwrap_or_default());
+    }
+
+    let mut arguments = Map::new();
+    for field in &fields {
+        if let Some(value) = ask(field, input, output)? {
+            arguments.insert(field.name.clone(), value);
+        }
+    }
+    Ok(Value::Object(arguments))
+}
+
+/// Synthesize an example value for `schema`.
+///
+/// Prefers `default`, then `const`, the first `examples` entry, and the first
+/// `enum` value; otherwise builds a placeholder from the type, using `format`
+/// for strings and recursing into object properties and array items.
+pub fn example_value(schema: &Value) -> Value {
+    example_named(schema, "value")
+}
+
+fn example_named(schema: &Value, name: &str) -> Value {
+    if let Some(value) = schema.get("default").or_else(|| schema.get("const")) {
+        return value.clone();
+    }
+    if let Some(value) = schema
+        .get("examples")
+        .and_then(Value::as_array)
+        .and_then(|examples| examples.first())
+        .or_else(|| {
+            schema
+                .get("enum")
+                .and_then(Value::as_array)
+                .and_then(|choices| choices.first())
+        })
+    {
+        return value.clone();
+    }
+    if let Some(first) = ["oneOf", "anyOf"]
+        .iter()
+        .find_map(|key| schema.get(*key).and_then(Value::as_array))
+        .and_then(|variants| variants.first())
+    {
+        return example_named(first, name);
+    }
+
+    if schema.get("properties").is_some() {
+        let properties = fields(schema)
+            .into_iter()
+            .map(|field| {
+                let value = example_named(&field.schema, &field.name);
+                (field.name, value)
+            })
+            .collect();
+        return Value::Object(properties);
+    }
+
+    match FieldKind::from_schema(schema) {
+        FieldKind::String => Value::String(string_placeholder(schema, name)),
+        FieldKind::Integer => schema
+            .get("minimum")
+            .and_then(Value::as_i64)
+            .map_or(Value::from(1), Value::from),
+        FieldKind::Number => schema
+            .get("minimum")
+            .cloned()
+            .unwrap_or_else(|| Value::from(1.5)),
+        FieldKind::Boolean => Value::Bool(false),
+        FieldKind::Json => match schema.get("type").and_then(Value::as_str) {
+            Some("array") => {
+                let item = schema
+                    .get("items")
+                    .map_or(Value::String(format!("<{name}>")), |items| {
+                        example_named(items, name)
+                    });
+                Value::Array(vec![item])
+            }
+            Some("object") => Value::Object(Map::new()),
+            _ => Value::Null,
+        },
+    }
+}
+
+fn string_placeholder(schema: &Value, name: &str) -> String {
+    match schema.get("format").and_then(Value::as_str) {
+        Some("date-time") => "2025-01-01T00:00:00Z".into(),
+        Some("date") => "2025-01-01".into(),
+        Some("time") => "12:00:00".into(),
+        Some("email") => "user@example.com".into(),
+        Some("uri" | "url" | "iri") => "https://example.com".into(),
+        Some("uuid") => "00000000-0000-0000-0000-000000000000".into(),
+        Some("ipv4") => "192.0.2.1".into(),
+        Some("ipv6") => "2001:db8::1".into(),
+        Some("hostname") => "example.com".into(),
+        _ => format!("<{name}>"),
+    }
+}
+
+fn ask(
+    field: &Field,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<Option<Value>> {
+    if let Some(description) = &field.description {
+        writeln!(output, "# {description}")?;
+    }
+    loop {
+        write!(output, "{}", field.prompt())?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("input ended while prompting for `{}`", field.name),
+            ));
+        }
+        match field.parse(&line) {
+            Ok(value) => return Ok(value),
+            Err(message) => writeln!(output, "  {message}")?,
+        }
+    }
+}
+
+fn render_choices(choices: &[Value]) -> String {
+    let rendered: Vec<String> = choices.iter().map(render_scalar).collect();
+    format!("{{{}}}", rendered.join("|"))
+}
+
+fn render_scalar(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Cursor;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["city"],
+            "properties": {
+                "city": { "type": "string", "description": "City to forecast" },
+                "days": { "type": "integer", "default": 3 },
+                "units": { "type": "string", "enum": ["metric", "imperial"] },
+                "verbose": { "type": "boolean" }
+            }
+        })
+    }
+
+    #[test]
+    fn required_fields_are_listed_first() {
+        let names: Vec<_> = fields(&schema()).into_iter().map(|f| f.name).collect();
+        assert_eq!(names[0], "city");
+        assert_eq!(names.len(), 4);
+    }
+
+    #[test]
+    fn prompts_until_answers_are_valid() {
+        // city: empty (rejected) then "AMS"; days: default; units: bad then
+        // valid; verbose: skipped.
+        let mut input = Cursor::new("\nAMS\n\nkelvin\nmetric\n\n");
+        let mut output = Vec::new();
+        let args = prompt_arguments(&schema(), &mut input, &mut output).expect("prompt");
+
+        assert_eq!(args, json!({"city": "AMS", "days": 3, "units": "metric"}));
+        let transcript = String::from_utf8(output).expect("utf8");
+        assert!(transcript.contains("# City to forecast"));
+        assert!(transcript.contains("`city` is required"));
+        assert!(transcript.contains("units (string) {metric|imperial}: "));
+        assert!(transcript.contains("not one of {metric|imperial}"));
+    }
+
+    #[test]
+    fn examples_prefer_defaults_enums_and_formats() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "city": { "type": "string" },
+                "days": { "type": "integer", "default": 3 },
+                "units": { "type": "string", "enum": ["metric", "imperial"] },
+                "when": { "type": "string", "format": "date-time" },
+                "contact": { "type": ["string", "null"], "format": "email" },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "limit": { "type": "integer", "minimum": 10 },
+                "nested": {
+                    "type": "object",
+                    "properties": { "flag": { "type": "boolean" } }
+                }
+            }
+        });
+
+        assert_eq!(
+            example_value(&schema),
+            json!({
+                "city": "<city>",
+                "days": 3,
+                "units": "metric",
+                "when": "2025-01-01T00:00:00Z",
+                "contact": "user@example.com",
+                "tags": ["<tags>"],
+                "limit": 10,
+                "nested": { "flag": false }
+            })
+        );
+    }
+
+    #[test]
+    fn eof_while_prompting_is_an_error() {
+        let mut input = Cursor::new("");
+        let err = prompt_arguments(&schema(), &mut input, &mut Vec::new()).expect_err("eof");
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
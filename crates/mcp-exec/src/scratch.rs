@@ -0,0 +1,843 @@
+//! Scratch directory quota from [`RuntimePolicy::scratch_quota_bytes`] and
+//! [`RuntimePolicy::scratch_max_entries`].
+//!
+//! The scratch directory is the only one preopened for the guest, so every
+//! filesystem write lands in it. The linker serves `wasi:filesystem/types`
+//! through [`ScratchFilesystem`], which passes every call on to wasmtime-wasi
+//! but keeps a running count of the bytes and entries the guest adds:
+//!
+//! - `write`, `set-size`, `write-via-stream`, and `append-via-stream` are
+//!   charged the bytes they add, and fail with `insufficient-space` past the
+//!   byte quota;
+//! - creating a file, directory, or link is charged one entry, and fails with
+//!   `quota` past the entry cap;
+//! - shrinking, truncating, unlinking, and removing give back what they free.
+//!
+//! A refused write or create also fails the call with
+//! [`RunnerError::HostResourceLimit`], even if the guest ignores the error.
+//! Stream writes are charged in full, even when they overwrite bytes already
+//! on disk.
+//!
+//! [`RuntimePolicy::scratch_quota_bytes`]: crate::RuntimePolicy::scratch_quota_bytes
+//! [`RuntimePolicy::scratch_max_entries`]: crate::RuntimePolicy::scratch_max_entries
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use bytes::Bytes;
+use wasmtime::component::{HasData, Linker, Resource};
+use wasmtime_wasi::filesystem::WasiFilesystemCtxView;
+use wasmtime_wasi::p2::bindings::filesystem::types::{
+    self, Descriptor, DirectoryEntryStream, ErrorCode, Filesize,
+};
+use wasmtime_wasi::p2::bindings::sync::filesystem::types as sync_types;
+use wasmtime_wasi::p2::pipe::SinkOutputStream;
+use wasmtime_wasi::p2::{
+    DynInputStream, DynOutputStream, FsError, FsResult, OutputStream, Pollable, StreamError,
+};
+use wasmtime_wasi::runtime::in_tokio;
+
+use crate::error::RunnerError;
+use crate::runner::StoreState;
+
+/// Space and entries a guest may use in its scratch directory. The
+/// directory starts empty, and clones share the counts.
+#[derive(Clone, Debug)]
+pub(crate) struct ScratchQuota {
+    bytes: Budget,
+    entries: Budget,
+}
+
+impl ScratchQuota {
+    pub(crate) fn new(max_bytes: u64, max_entries: u64) -> Self {
+        Self {
+            bytes: Budget::new("scratch-bytes", max_bytes),
+            entries: Budget::new("scratch-entries", max_entries),
+        }
+    }
+
+    /// Bytes the directory can still grow by.
+    fn room(&self) -> u64 {
+        self.bytes.room()
+    }
+
+    /// The error for a call that tried to go past the quota.
+    pub(crate) fn exceeded(&self) -> Option<RunnerError> {
+        self.bytes.exceeded().or_else(|| self.entries.exceeded())
+    }
+}
+
+/// A running count held to a limit.
+#[derive(Clone, Debug)]
+struct Budget {
+    resource: &'static str,
+    limit: u64,
+    used: Arc<AtomicU64>,
+    /// A charge was refused.
+    refused: Arc<AtomicBool>,
+}
+
+impl Budget {
+    fn new(resource: &'static str, limit: u64) -> Self {
+        Self {
+            resource,
+            limit,
+            used: Arc::default(),
+            refused: Arc::default(),
+        }
+    }
+
+    fn room(&self) -> u64 {
+        self.limit.saturating_sub(self.used.load(Ordering::Relaxed))
+    }
+
+    /// Take `amount` if it fits; remembers a refusal.
+    fn charge(&self, amount: u64) -> bool {
+        let charged = self
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                used.checked_add(amount)
+                    .filter(|&total| total <= self.limit)
+            })
+            .is_ok();
+        if !charged {
+            self.refused.store(true, Ordering::Relaxed);
+        }
+        charged
+    }
+
+    fn refund(&self, amount: u64) {
+        let _ = self
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                Some(used.saturating_sub(amount))
+            });
+    }
+
+    fn exceeded(&self) -> Option<RunnerError> {
+        self.refused
+            .load(Ordering::Relaxed)
+            .then(|| RunnerError::HostResourceLimit {
+                resource: self.resource.into(),
+                limit: self.limit,
+            })
+    }
+}
+
+/// A file's output stream, held to the scratch quota.
+struct QuotaStream {
+    inner: DynOutputStream,
+    quota: ScratchQuota,
+}
+
+impl QuotaStream {
+    fn over_quota(&self) -> StreamError {
+        StreamError::LastOperationFailed(wasmtime::format_err!(
+            "scratch directory quota of {} bytes exceeded",
+            self.quota.bytes.limit
+        ))
+    }
+}
+
+#[async_trait::async_trait]
+impl OutputStream for QuotaStream {
+    fn write(&mut self, bytes: Bytes) -> Result<(), StreamError> {
+        let len = bytes.len() as u64;
+        if !self.quota.bytes.charge(len) {
+            return Err(self.over_quota());
+        }
+        self.inner
+            .write(bytes)
+            .inspect_err(|_| self.quota.bytes.refund(len))
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.inner.flush()
+    }
+
+    fn check_write(&mut self) -> Result<usize, StreamError> {
+        let permit = self.inner.check_write()?;
+        if permit == 0 {
+            return Ok(0);
+        }
+        let room = self.quota.room();
+        if room == 0 {
+            self.quota.bytes.refused.store(true, Ordering::Relaxed);
+            return Err(self.over_quota());
+        }
+        Ok(permit.min(usize::try_from(room).unwrap_or(usize::MAX)))
+    }
+
+    async fn cancel(&mut self) {
+        self.inner.cancel().await
+    }
+}
+
+#[async_trait::async_trait]
+impl Pollable for QuotaStream {
+    async fn ready(&mut self) {
+        self.inner.ready().await
+    }
+}
+
+/// Serve `wasi:filesystem/types` through [`ScratchFilesystem`] in a linker
+/// built by [`build_linker`](crate::runner::build_linker). Must run after
+/// wasi is linked, since it replaces the whole interface.
+pub(crate) fn add_to_linker_sync(linker: &mut Linker<StoreState>) -> wasmtime::Result<()> {
+    sync_types::add_to_linker::<StoreState, HasScratchFilesystem>(
+        linker,
+        StoreState::scratch_filesystem,
+    )
+}
+
+/// [`add_to_linker_sync`] for [`build_async_linker`](crate::runner::build_async_linker).
+pub(crate) fn add_to_linker_async(linker: &mut Linker<StoreState>) -> wasmtime::Result<()> {
+    types::add_to_linker::<StoreState, HasScratchFilesystem>(linker, StoreState::scratch_filesystem)
+}
+
+struct HasScratchFilesystem;
+
+impl HasData for HasScratchFilesystem {
+    type Data<'a> = ScratchFilesystem<'a>;
+}
+
+/// A store's filesystem view, with writes held to its scratch quota.
+pub(crate) struct ScratchFilesystem<'a> {
+    view: WasiFilesystemCtxView<'a>,
+    quota: Option<&'a ScratchQuota>,
+}
+
+impl<'a> ScratchFilesystem<'a> {
+    pub(crate) fn new(view: WasiFilesystemCtxView<'a>, quota: Option<&'a ScratchQuota>) -> Self {
+        Self { view, quota }
+    }
+
+    /// Size of the file behind `fd`; `None` when it cannot be read, which
+    /// is left to the call itself to report.
+    async fn size(&mut self, fd: &Resource<Descriptor>) -> Option<Filesize> {
+        let fd = Resource::new_borrow(fd.rep());
+        let stat = types::HostDescriptor::stat(&mut self.view, fd).await.ok()?;
+        Some(stat.size)
+    }
+
+    /// The entry at `path` under `fd`, if there is one.
+    async fn stat_at(
+        &mut self,
+        fd: &Resource<Descriptor>,
+        path: &str,
+    ) -> Option<types::DescriptorStat> {
+        let fd = Resource::new_borrow(fd.rep());
+        let flags = types::PathFlags::empty();
+        types::HostDescriptor::stat_at(&mut self.view, fd, flags, path.to_string())
+            .await
+            .ok()
+    }
+
+    /// Bytes removing the entry at `path` under `fd` would free, unless other
+    /// links keep them; `None` when there is no entry or no quota.
+    async fn freed_by_removing(
+        &mut self,
+        fd: &Resource<Descriptor>,
+        path: &str,
+    ) -> Option<Filesize> {
+        self.quota?;
+        let stat = self.stat_at(fd, path).await?;
+        let file = stat.type_ == types::DescriptorType::RegularFile;
+        Some(if file && stat.link_count <= 1 {
+            stat.size
+        } else {
+            0
+        })
+    }
+
+    /// Charge `bytes` of growth, or refuse with `insufficient-space`.
+    fn charge_bytes(&self, bytes: u64) -> FsResult<()> {
+        match self.quota {
+            Some(quota) if !quota.bytes.charge(bytes) => Err(ErrorCode::InsufficientSpace.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Charge one new entry, or refuse with `quota`.
+    fn charge_entry(&self) -> FsResult<()> {
+        match self.quota {
+            Some(quota) if !quota.entries.charge(1) => Err(ErrorCode::Quota.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn refund(&self, bytes: u64, entries: u64) {
+        if let Some(quota) = self.quota {
+            quota.bytes.refund(bytes);
+            quota.entries.refund(entries);
+        }
+    }
+
+    /// Give back an entry removed with the bytes [`Self::freed_by_removing`]
+    /// found.
+    fn refund_removed(&self, freed: Option<Filesize>) {
+        if let Some(bytes) = freed {
+            self.refund(bytes, 1);
+        }
+    }
+
+    /// Put the file stream `opened` under the quota.
+    fn hold(
+        &mut self,
+        opened: FsResult<Resource<DynOutputStream>>,
+    ) -> FsResult<Resource<DynOutputStream>> {
+        let stream = opened?;
+        if let Some(quota) = self.quota {
+            let slot = self.view.table.get_mut(&stream)?;
+            let inner = std::mem::replace(slot, Box::new(SinkOutputStream));
+            *slot = Box::new(QuotaStream {
+                inner,
+                quota: quota.clone(),
+            });
+        }
+        Ok(stream)
+    }
+}
+
+impl types::Host for ScratchFilesystem<'_> {
+    fn convert_error_code(&mut self, err: FsError) -> wasmtime::Result<ErrorCode> {
+        types::Host::convert_error_code(&mut self.view, err)
+    }
+
+    fn filesystem_error_code(
+        &mut self,
+        err: Resource<wasmtime::Error>,
+    ) -> wasmtime::Result<Option<ErrorCode>> {
+        types::Host::filesystem_error_code(&mut self.view, err)
+    }
+}
+
+impl types::HostDescriptor for ScratchFilesystem<'_> {
+    async fn write(
+        &mut self,
+        fd: Resource<Descriptor>,
+        buf: Vec<u8>,
+        offset: Filesize,
+    ) -> FsResult<Filesize> {
+        let end = offset.saturating_add(buf.len() as u64);
+        let size = match self.quota {
+            Some(_) => self.size(&fd).await,
+            None => None,
+        };
+        let grown = size.map_or(0, |size| end.saturating_sub(size));
+        self.charge_bytes(grown)?;
+        types::HostDescriptor::write(&mut self.view, fd, buf, offset)
+            .await
+            .inspect_err(|_| self.refund(grown, 0))
+    }
+
+    async fn set_size(&mut self, fd: Resource<Descriptor>, end: Filesize) -> FsResult<()> {
+        let size = match self.quota {
+            Some(_) => self.size(&fd).await,
+            None => None,
+        };
+        let grown = size.map_or(0, |size| end.saturating_sub(size));
+        self.charge_bytes(grown)?;
+        types::HostDescriptor::set_size(&mut self.view, fd, end)
+            .await
+            .inspect_err(|_| self.refund(grown, 0))?;
+        self.refund(size.map_or(0, |size| size.saturating_sub(end)), 0);
+        Ok(())
+    }
+
+    fn write_via_stream(
+        &mut self,
+        fd: Resource<Descriptor>,
+        offset: Filesize,
+    ) -> FsResult<Resource<DynOutputStream>> {
+        let opened = types::HostDescriptor::write_via_stream(&mut self.view, fd, offset);
+        self.hold(opened)
+    }
+
+    fn append_via_stream(
+        &mut self,
+        fd: Resource<Descriptor>,
+    ) -> FsResult<Resource<DynOutputStream>> {
+        let opened = types::HostDescriptor::append_via_stream(&mut self.view, fd);
+        self.hold(opened)
+    }
+
+    async fn advise(
+        &mut self,
+        fd: Resource<Descriptor>,
+        offset: Filesize,
+        len: Filesize,
+        advice: types::Advice,
+    ) -> FsResult<()> {
+        types::HostDescriptor::advise(&mut self.view, fd, offset, len, advice).await
+    }
+
+    async fn sync_data(&mut self, fd: Resource<Descriptor>) -> FsResult<()> {
+        types::HostDescriptor::sync_data(&mut self.view, fd).await
+    }
+
+    async fn get_flags(&mut self, fd: Resource<Descriptor>) -> FsResult<types::DescriptorFlags> {
+        types::HostDescriptor::get_flags(&mut self.view, fd).await
+    }
+
+    async fn get_type(&mut self, fd: Resource<Descriptor>) -> FsResult<types::DescriptorType> {
+        types::HostDescriptor::get_type(&mut self.view, fd).await
+    }
+
+    async fn set_times(
+        &mut self,
+        fd: Resource<Descriptor>,
+        atim: types::NewTimestamp,
+        mtim: types::NewTimestamp,
+    ) -> FsResult<()> {
+        types::HostDescriptor::set_times(&mut self.view, fd, atim, mtim).await
+    }
+
+    async fn read(
+        &mut self,
+        fd: Resource<Descriptor>,
+        len: Filesize,
+        offset: Filesize,
+    ) -> FsResult<(Vec<u8>, bool)> {
+        types::HostDescriptor::read(&mut self.view, fd, len, offset).await
+    }
+
+    async fn read_directory(
+        &mut self,
+        fd: Resource<Descriptor>,
+    ) -> FsResult<Resource<DirectoryEntryStream>> {
+        types::HostDescriptor::read_directory(&mut self.view, fd).await
+    }
+
+    async fn sync(&mut self, fd: Resource<Descriptor>) -> FsResult<()> {
+        types::HostDescriptor::sync(&mut self.view, fd).await
+    }
+
+    async fn create_directory_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path: String,
+    ) -> FsResult<()> {
+        self.charge_entry()?;
+        types::HostDescriptor::create_directory_at(&mut self.view, fd, path)
+            .await
+            .inspect_err(|_| self.refund(0, 1))
+    }
+
+    async fn stat(&mut self, fd: Resource<Descriptor>) -> FsResult<types::DescriptorStat> {
+        types::HostDescriptor::stat(&mut self.view, fd).await
+    }
+
+    async fn stat_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: types::PathFlags,
+        path: String,
+    ) -> FsResult<types::DescriptorStat> {
+        types::HostDescriptor::stat_at(&mut self.view, fd, path_flags, path).await
+    }
+
+    async fn set_times_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: types::PathFlags,
+        path: String,
+        atim: types::NewTimestamp,
+        mtim: types::NewTimestamp,
+    ) -> FsResult<()> {
+        types::HostDescriptor::set_times_at(&mut self.view, fd, path_flags, path, atim, mtim).await
+    }
+
+    async fn link_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        old_path_flags: types::PathFlags,
+        old_path: String,
+        new_descriptor: Resource<Descriptor>,
+        new_path: String,
+    ) -> FsResult<()> {
+        self.charge_entry()?;
+        types::HostDescriptor::link_at(
+            &mut self.view,
+            fd,
+            old_path_flags,
+            old_path,
+            new_descriptor,
+            new_path,
+        )
+        .await
+        .inspect_err(|_| self.refund(0, 1))
+    }
+
+    async fn open_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: types::PathFlags,
+        path: String,
+        oflags: types::OpenFlags,
+        flags: types::DescriptorFlags,
+    ) -> FsResult<Resource<Descriptor>> {
+        let creates = oflags.contains(types::OpenFlags::CREATE);
+        let truncates = oflags.contains(types::OpenFlags::TRUNCATE);
+        let existing = match self.quota {
+            Some(_) if creates || truncates => self.stat_at(&fd, &path).await,
+            _ => None,
+        };
+        let new_entry = creates && existing.is_none();
+        if new_entry {
+            self.charge_entry()?;
+        }
+        let opened =
+            types::HostDescriptor::open_at(&mut self.view, fd, path_flags, path, oflags, flags)
+                .await;
+        match (&opened, existing) {
+            (Err(_), _) if new_entry => self.refund(0, 1),
+            (Ok(_), Some(stat)) if truncates => self.refund(stat.size, 0),
+            _ => {}
+        }
+        opened
+    }
+
+    fn drop(&mut self, fd: Resource<Descriptor>) -> wasmtime::Result<()> {
+        types::HostDescriptor::drop(&mut self.view, fd)
+    }
+
+    async fn readlink_at(&mut self, fd: Resource<Descriptor>, path: String) -> FsResult<String> {
+        types::HostDescriptor::readlink_at(&mut self.view, fd, path).await
+    }
+
+    async fn remove_directory_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path: String,
+    ) -> FsResult<()> {
+        let freed = self.freed_by_removing(&fd, &path).await;
+        types::HostDescriptor::remove_directory_at(&mut self.view, fd, path).await?;
+        self.refund_removed(freed);
+        Ok(())
+    }
+
+    async fn rename_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        old_path: String,
+        new_fd: Resource<Descriptor>,
+        new_path: String,
+    ) -> FsResult<()> {
+        // A rename over an existing entry removes it.
+        let freed = self.freed_by_removing(&new_fd, &new_path).await;
+        types::HostDescriptor::rename_at(&mut self.view, fd, old_path, new_fd, new_path).await?;
+        self.refund_removed(freed);
+        Ok(())
+    }
+
+    async fn symlink_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        src_path: String,
+        dest_path: String,
+    ) -> FsResult<()> {
+        self.charge_entry()?;
+        types::HostDescriptor::symlink_at(&mut self.view, fd, src_path, dest_path)
+            .await
+            .inspect_err(|_| self.refund(0, 1))
+    }
+
+    async fn unlink_file_at(&mut self, fd: Resource<Descriptor>, path: String) -> FsResult<()> {
+        let freed = self.freed_by_removing(&fd, &path).await;
+        types::HostDescriptor::unlink_file_at(&mut self.view, fd, path).await?;
+        self.refund_removed(freed);
+        Ok(())
+    }
+
+    fn read_via_stream(
+        &mut self,
+        fd: Resource<Descriptor>,
+        offset: Filesize,
+    ) -> FsResult<Resource<DynInputStream>> {
+        types::HostDescriptor::read_via_stream(&mut self.view, fd, offset)
+    }
+
+    async fn is_same_object(
+        &mut self,
+        a: Resource<Descriptor>,
+        b: Resource<Descriptor>,
+    ) -> wasmtime::Result<bool> {
+        types::HostDescriptor::is_same_object(&mut self.view, a, b).await
+    }
+
+    async fn metadata_hash(
+        &mut self,
+        fd: Resource<Descriptor>,
+    ) -> FsResult<types::MetadataHashValue> {
+        types::HostDescriptor::metadata_hash(&mut self.view, fd).await
+    }
+
+    async fn metadata_hash_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: types::PathFlags,
+        path: String,
+    ) -> FsResult<types::MetadataHashValue> {
+        types::HostDescriptor::metadata_hash_at(&mut self.view, fd, path_flags, path).await
+    }
+}
+
+impl types::HostDirectoryEntryStream for ScratchFilesystem<'_> {
+    async fn read_directory_entry(
+        &mut self,
+        stream: Resource<DirectoryEntryStream>,
+    ) -> FsResult<Option<types::DirectoryEntry>> {
+        types::HostDirectoryEntryStream::read_directory_entry(&mut self.view, stream).await
+    }
+
+    fn drop(&mut self, stream: Resource<DirectoryEntryStream>) -> wasmtime::Result<()> {
+        types::HostDirectoryEntryStream::drop(&mut self.view, stream)
+    }
+}
+
+// The synchronous bindings, for engines without async support.
+
+impl sync_types::Host for ScratchFilesystem<'_> {
+    fn convert_error_code(&mut self, err: FsError) -> wasmtime::Result<sync_types::ErrorCode> {
+        sync_types::Host::convert_error_code(&mut self.view, err)
+    }
+
+    fn filesystem_error_code(
+        &mut self,
+        err: Resource<wasmtime::Error>,
+    ) -> wasmtime::Result<Option<sync_types::ErrorCode>> {
+        sync_types::Host::filesystem_error_code(&mut self.view, err)
+    }
+}
+
+impl sync_types::HostDescriptor for ScratchFilesystem<'_> {
+    fn write(
+        &mut self,
+        fd: Resource<Descriptor>,
+        buf: Vec<u8>,
+        offset: Filesize,
+    ) -> FsResult<Filesize> {
+        in_tokio(types::HostDescriptor::write(self, fd, buf, offset))
+    }
+
+    fn set_size(&mut self, fd: Resource<Descriptor>, size: Filesize) -> FsResult<()> {
+        in_tokio(types::HostDescriptor::set_size(self, fd, size))
+    }
+
+    fn write_via_stream(
+        &mut self,
+        fd: Resource<Descriptor>,
+        offset: Filesize,
+    ) -> FsResult<Resource<DynOutputStream>> {
+        let opened = sync_types::HostDescriptor::write_via_stream(&mut self.view, fd, offset);
+        self.hold(opened)
+    }
+
+    fn append_via_stream(
+        &mut self,
+        fd: Resource<Descriptor>,
+    ) -> FsResult<Resource<DynOutputStream>> {
+        let opened = sync_types::HostDescriptor::append_via_stream(&mut self.view, fd);
+        self.hold(opened)
+    }
+
+    fn advise(
+        &mut self,
+        fd: Resource<Descriptor>,
+        offset: Filesize,
+        len: Filesize,
+        advice: sync_types::Advice,
+    ) -> FsResult<()> {
+        sync_types::HostDescriptor::advise(&mut self.view, fd, offset, len, advice)
+    }
+
+    fn sync_data(&mut self, fd: Resource<Descriptor>) -> FsResult<()> {
+        sync_types::HostDescriptor::sync_data(&mut self.view, fd)
+    }
+
+    fn get_flags(&mut self, fd: Resource<Descriptor>) -> FsResult<sync_types::DescriptorFlags> {
+        sync_types::HostDescriptor::get_flags(&mut self.view, fd)
+    }
+
+    fn get_type(&mut self, fd: Resource<Descriptor>) -> FsResult<sync_types::DescriptorType> {
+        sync_types::HostDescriptor::get_type(&mut self.view, fd)
+    }
+
+    fn set_times(
+        &mut self,
+        fd: Resource<Descriptor>,
+        atim: sync_types::NewTimestamp,
+        mtim: sync_types::NewTimestamp,
+    ) -> FsResult<()> {
+        sync_types::HostDescriptor::set_times(&mut self.view, fd, atim, mtim)
+    }
+
+    fn read(
+        &mut self,
+        fd: Resource<Descriptor>,
+        len: Filesize,
+        offset: Filesize,
+    ) -> FsResult<(Vec<u8>, bool)> {
+        sync_types::HostDescriptor::read(&mut self.view, fd, len, offset)
+    }
+
+    fn read_directory(
+        &mut self,
+        fd: Resource<Descriptor>,
+    ) -> FsResult<Resource<DirectoryEntryStream>> {
+        sync_types::HostDescriptor::read_directory(&mut self.view, fd)
+    }
+
+    fn sync(&mut self, fd: Resource<Descriptor>) -> FsResult<()> {
+        sync_types::HostDescriptor::sync(&mut self.view, fd)
+    }
+
+    fn create_directory_at(&mut self, fd: Resource<Descriptor>, path: String) -> FsResult<()> {
+        in_tokio(types::HostDescriptor::create_directory_at(self, fd, path))
+    }
+
+    fn stat(&mut self, fd: Resource<Descriptor>) -> FsResult<sync_types::DescriptorStat> {
+        sync_types::HostDescriptor::stat(&mut self.view, fd)
+    }
+
+    fn stat_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: sync_types::PathFlags,
+        path: String,
+    ) -> FsResult<sync_types::DescriptorStat> {
+        sync_types::HostDescriptor::stat_at(&mut self.view, fd, path_flags, path)
+    }
+
+    fn set_times_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: sync_types::PathFlags,
+        path: String,
+        atim: sync_types::NewTimestamp,
+        mtim: sync_types::NewTimestamp,
+    ) -> FsResult<()> {
+        sync_types::HostDescriptor::set_times_at(&mut self.view, fd, path_flags, path, atim, mtim)
+    }
+
+    fn link_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        old_path_flags: sync_types::PathFlags,
+        old_path: String,
+        new_descriptor: Resource<Descriptor>,
+        new_path: String,
+    ) -> FsResult<()> {
+        in_tokio(types::HostDescriptor::link_at(
+            self,
+            fd,
+            old_path_flags.into(),
+            old_path,
+            new_descriptor,
+            new_path,
+        ))
+    }
+
+    fn open_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: sync_types::PathFlags,
+        path: String,
+        oflags: sync_types::OpenFlags,
+        flags: sync_types::DescriptorFlags,
+    ) -> FsResult<Resource<Descriptor>> {
+        in_tokio(types::HostDescriptor::open_at(
+            self,
+            fd,
+            path_flags.into(),
+            path,
+            oflags.into(),
+            flags.into(),
+        ))
+    }
+
+    fn drop(&mut self, fd: Resource<Descriptor>) -> wasmtime::Result<()> {
+        sync_types::HostDescriptor::drop(&mut self.view, fd)
+    }
+
+    fn readlink_at(&mut self, fd: Resource<Descriptor>, path: String) -> FsResult<String> {
+        sync_types::HostDescriptor::readlink_at(&mut self.view, fd, path)
+    }
+
+    fn remove_directory_at(&mut self, fd: Resource<Descriptor>, path: String) -> FsResult<()> {
+        in_tokio(types::HostDescriptor::remove_directory_at(self, fd, path))
+    }
+
+    fn rename_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        old_path: String,
+        new_fd: Resource<Descriptor>,
+        new_path: String,
+    ) -> FsResult<()> {
+        in_tokio(types::HostDescriptor::rename_at(
+            self, fd, old_path, new_fd, new_path,
+        ))
+    }
+
+    fn symlink_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        src_path: String,
+        dest_path: String,
+    ) -> FsResult<()> {
+        in_tokio(types::HostDescriptor::symlink_at(
+            self, fd, src_path, dest_path,
+        ))
+    }
+
+    fn unlink_file_at(&mut self, fd: Resource<Descriptor>, path: String) -> FsResult<()> {
+        in_tokio(types::HostDescriptor::unlink_file_at(self, fd, path))
+    }
+
+    fn read_via_stream(
+        &mut self,
+        fd: Resource<Descriptor>,
+        offset: Filesize,
+    ) -> FsResult<Resource<DynInputStream>> {
+        sync_types::HostDescriptor::read_via_stream(&mut self.view, fd, offset)
+    }
+
+    fn is_same_object(
+        &mut self,
+        a: Resource<Descriptor>,
+        b: Resource<Descriptor>,
+    ) -> wasmtime::Result<bool> {
+        sync_types::HostDescriptor::is_same_object(&mut self.view, a, b)
+    }
+
+    fn metadata_hash(
+        &mut self,
+        fd: Resource<Descriptor>,
+    ) -> FsResult<sync_types::MetadataHashValue> {
+        sync_types::HostDescriptor::metadata_hash(&mut self.view, fd)
+    }
+
+    fn metadata_hash_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: sync_types::PathFlags,
+        path: String,
+    ) -> FsResult<sync_types::MetadataHashValue> {
+        sync_types::HostDescriptor::metadata_hash_at(&mut self.view, fd, path_flags, path)
+    }
+}
+
+impl sync_types::HostDirectoryEntryStream for ScratchFilesystem<'_> {
+    fn read_directory_entry(
+        &mut self,
+        stream: Resource<DirectoryEntryStream>,
+    ) -> FsResult<Option<sync_types::DirectoryEntry>> {
+        sync_types::HostDirectoryEntryStream::read_directory_entry(&mut self.view, stream)
+    }
+
+    fn drop(&mut self, stream: Resource<DirectoryEntryStream>) -> wasmtime::Result<()> {
+        sync_types::HostDirectoryEntryStream::drop(&mut self.view, stream)
+    }
+}
@@ -0,0 +1,140 @@
+//! Pre-flight check of the secrets a component declares it needs.
+//!
+//! When [`RuntimePolicy::required_secrets`](crate::RuntimePolicy::required_secrets)
+//! lists a component's requirements, typically the `secret_requirements`
+//! returned by [`describe_tool`](crate::describe::describe_tool), every call
+//! first reads each required key from the configured [`SecretsStore`] for the
+//! call's tenant. Keys that cannot be read fail the call with
+//! [`ExecError::MissingSecrets`] before the component is resolved, instead of
+//! leaving the tool to fail mid-call with an opaque secrets error. Optional
+//! requirements are not checked.
+
+use greentic_types::{SecretRequirement, TenantCtx};
+
+use crate::ExecRequest;
+use crate::config::{ExecConfig, SecretsStore};
+use crate::error::ExecError;
+
+/// Keys among the required `requirements` that `store` cannot read for
+/// `tenant`, in declaration order.
+///
+/// Every required key is missing without a store or a tenant. A read that
+/// fails for any reason counts as missing, so hosts can run this ahead of
+/// time for each tenant they serve.
+pub fn missing_secrets(
+    store: Option<&dyn SecretsStore>,
+    tenant: Option<&TenantCtx>,
+    requirements: &[SecretRequirement],
+) -> Vec<String> {
+    requirements
+        .iter()
+        .filter(|req| req.required)
+        .map(|req| req.key.as_str())
+        .filter(|key| match (store, tenant) {
+            (Some(store), Some(tenant)) => store.read(tenant, key).is_err(),
+            _ => true,
+        })
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Fail `req` if its component has required secrets its tenant lacks.
+pub(crate) fn check(req: &ExecRequest, cfg: &ExecConfig) -> Result<(), ExecError> {
+    let Some(requirements) = cfg.runtime.required_secrets.get(&req.component) else {
+        return Ok(());
+    };
+    let missing = missing_secrets(
+        cfg.secrets_store.as_deref(),
+        req.tenant.as_ref(),
+        requirements,
+    );
+    if missing.is_empty() {
+        return Ok(());
+    }
+    Err(ExecError::MissingSecrets {
+        component: req.component.clone(),
+        tenant: req.tenant.as_ref().map(|tenant| tenant.tenant.0.clone()),
+        missing,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use greentic_types::{EnvId, TenantId};
+
+    use super::*;
+    use crate::config::{DynSecretsStore, RuntimePolicy, VerifyPolicy};
+    use crate::store::ToolStore;
+
+    struct Vault;
+
+    impl SecretsStore for Vault {
+        fn read(&self, scope: &TenantCtx, name: &str) -> Result<Vec<u8>, String> {
+            match (scope.tenant.0.as_str(), name) {
+                ("acme", "API_KEY") => Ok(b"key".to_vec()),
+                _ => Err("not-found".into()),
+            }
+        }
+    }
+
+    fn requirement(key: &str, required: bool) -> SecretRequirement {
+        let mut req = SecretRequirement::default();
+        req.key = key.into();
+        req.required = required;
+        req
+    }
+
+    #[test]
+    fn lists_required_keys_the_tenant_lacks() {
+        let requirements = [
+            requirement("API_KEY", true),
+            requirement("WEBHOOK_SECRET", true),
+            requirement("TELEMETRY_TOKEN", false),
+        ];
+        let store: DynSecretsStore = Arc::new(Vault);
+        let mut cfg = ExecConfig {
+            store: ToolStore::LocalDir(PathBuf::from(".")),
+            security: VerifyPolicy::default(),
+            runtime: RuntimePolicy::default(),
+            http_enabled: false,
+            secrets_store: Some(store),
+            cost_sink: None,
+            transforms: None,
+            hooks: None,
+        };
+        cfg.runtime
+            .required_secrets
+            .insert("weather".into(), requirements.to_vec());
+        let request = |tenant: &str| ExecRequest {
+            component: "weather".into(),
+            action: "forecast".into(),
+            args: serde_json::json!({}),
+            args_raw: None,
+            tenant: Some(TenantCtx::new(EnvId("dev".into()), TenantId(tenant.into()))),
+            expected_digest: None,
+            priority: Default::default(),
+        };
+
+        match check(&request("acme"), &cfg) {
+            Err(ExecError::MissingSecrets {
+                tenant, missing, ..
+            }) => {
+                assert_eq!(tenant.as_deref(), Some("acme"));
+                assert_eq!(missing, ["WEBHOOK_SECRET"]);
+            }
+            other => panic!("expected missing secrets, got {other:?}"),
+        }
+        match check(&request("globex"), &cfg) {
+            Err(ExecError::MissingSecrets { missing, .. }) => {
+                assert_eq!(missing, ["API_KEY", "WEBHOOK_SECRET"]);
+            }
+            other => panic!("expected missing secrets, got {other:?}"),
+        }
+
+        cfg.runtime.required_secrets.clear();
+        check(&request("globex"), &cfg).expect("unchecked without requirements");
+    }
+}
@@ -0,0 +1,191 @@
+//! Rolling execution statistics keyed by component digest.
+//!
+//! Counters are cumulative for the lifetime of the recorder; latency
+//! percentiles are computed over the most recent [`LATENCY_WINDOW`] calls so
+//! they track current behaviour rather than the whole history.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Number of recent call latencies kept per component.
+pub const LATENCY_WINDOW: usize = 1024;
+
+/// Point-in-time statistics for one component digest.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ComponentStats {
+    /// Component identifier most recently executed under this digest.
+    pub component: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub latency: LatencySummary,
+}
+
+impl ComponentStats {
+    /// Fraction of calls that returned an error, in `0.0..=1.0`.
+    pub fn error_rate(&self) -> f64 {
+        ratio(self.errors, self.calls)
+    }
+
+    /// Fraction of compiled-component lookups served from cache, in `0.0..=1.0`.
+    pub fn cache_hit_rate(&self) -> f64 {
+        ratio(self.cache_hits, self.cache_hits + self.cache_misses)
+    }
+}
+
+/// Latency percentiles over the recent call window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LatencySummary {
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl LatencySummary {
+    fn from_samples(samples: &VecDeque<Duration>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let mut sorted: Vec<Duration> = samples.iter().copied().collect();
+        sorted.sort_unstable();
+        Self {
+            p50: percentile(&sorted, 50),
+            p90: percentile(&sorted, 90),
+            p99: percentile(&sorted, 99),
+            max: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+#[derive(Default)]
+struct Entry {
+    component: String,
+    calls: u64,
+    errors: u64,
+    cache_hits: u64,
+    cache_misses: u64,
+    latencies: VecDeque<Duration>,
+}
+
+/// Thread-safe recorder behind [`crate::Executor::stats`].
+#[derive(Default)]
+pub(crate) struct StatsRecorder {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl StatsRecorder {
+    pub(crate) fn record_call(&self, digest: &str, component: &str, elapsed: Duration, ok: bool) {
+        self.with_entry(digest, |entry| {
+            if entry.component != component {
+                entry.component = component.to_string();
+            }
+            entry.calls += 1;
+            if !ok {
+                entry.errors += 1;
+            }
+            if entry.latencies.len() == LATENCY_WINDOW {
+                entry.latencies.pop_front();
+            }
+            entry.latencies.push_back(elapsed);
+        });
+    }
+
+    pub(crate) fn record_cache(&self, digest: &str, hit: bool) {
+        self.with_entry(digest, |entry| {
+            if hit {
+                entry.cache_hits += 1;
+            } else {
+                entry.cache_misses += 1;
+            }
+        });
+    }
+
+    pub(crate) fn snapshot(&self) -> HashMap<String, ComponentStats> {
+        let entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        entries
+            .iter()
+            .map(|(digest, entry)| {
+                let stats = ComponentStats {
+                    component: entry.component.clone(),
+                    calls: entry.calls,
+                    errors: entry.errors,
+                    cache_hits: entry.cache_hits,
+                    cache_misses: entry.cache_misses,
+                    latency: LatencySummary::from_samples(&entry.latencies),
+                };
+                (digest.clone(), stats)
+            })
+            .collect()
+    }
+
+    fn with_entry(&self, digest: &str, update: impl FnOnce(&mut Entry)) {
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        update(entries.entry(digest.to_string()).or_default());
+    }
+}
+
+fn ratio(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice.
+fn percentile(sorted: &[Duration], pct: usize) -> Duration {
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted[rank.min(sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_and_rates() {
+        let recorder = StatsRecorder::default();
+        for ms in 1..=100 {
+            recorder.record_call("abc", "echo", Duration::from_millis(ms), ms % 10 != 0);
+        }
+        recorder.record_cache("abc", false);
+        recorder.record_cache("abc", true);
+        recorder.record_cache("abc", true);
+        recorder.record_cache("abc", true);
+
+        let stats = recorder.snapshot().remove("abc").expect("entry");
+        assert_eq!(stats.component, "echo");
+        assert_eq!(stats.calls, 100);
+        assert_eq!(stats.errors, 10);
+        assert!((stats.error_rate() - 0.1).abs() < f64::EPSILON);
+        assert!((stats.cache_hit_rate() - 0.75).abs() < f64::EPSILON);
+        assert_eq!(stats.latency.p50, Duration::from_millis(50));
+        assert_eq!(stats.latency.p90, Duration::from_millis(90));
+        assert_eq!(stats.latency.p99, Duration::from_millis(99));
+        assert_eq!(stats.latency.max, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn latency_window_is_bounded() {
+        let recorder = StatsRecorder::default();
+        for _ in 0..LATENCY_WINDOW {
+            recorder.record_call("abc", "echo", Duration::from_secs(5), true);
+        }
+        for _ in 0..LATENCY_WINDOW {
+            recorder.record_call("abc", "echo", Duration::from_millis(1), true);
+        }
+
+        let stats = recorder.snapshot().remove("abc").expect("entry");
+        assert_eq!(stats.calls, 2 * LATENCY_WINDOW as u64);
+        assert_eq!(stats.latency.max, Duration::from_millis(1));
+    }
+}
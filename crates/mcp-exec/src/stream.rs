@@ -0,0 +1,525 @@
+//! Streaming of guest stdout as chunked result content.
+//!
+//! Routers that produce large payloads can write them to their WASI stdout
+//! stream instead of returning one giant string. When capture is enabled the
+//! host replaces the guest's stdout with a [`ChunkPipe`] that cuts what it
+//! writes into chunks of `chunk_bytes`.
+//!
+//! [`exec_stream`](crate::exec_stream) hands those chunks to its callback
+//! while the call runs. At most `max_bytes` wait for the callback; past that
+//! the guest's writes block until it catches up, which is the backpressure.
+//! When `max_bytes` fill up before a chunk is complete, the bytes so far go
+//! out as a shorter chunk so the callback always has something to take.
+//! Other calls keep the first `max_bytes` and attach them to the result as
+//! base64-encoded chunks under [`STREAM_FIELD`]; further writes fail in the
+//! guest as a closed stream.
+
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use bytes::Bytes;
+use serde_json::{Value, json};
+use thiserror::Error;
+use tokio::io::AsyncWrite;
+use wasmtime_wasi::cli::{IsTerminal, StdoutStream};
+use wasmtime_wasi::p2::{OutputStream, Pollable, StreamError, StreamResult};
+
+/// Result key the captured stream is attached under. Reserved, like
+/// [`cost::COST_FIELD`](crate::cost::COST_FIELD), so a `stream` field of the
+/// guest's own is left alone.
+pub const STREAM_FIELD: &str = "_exec_stream";
+
+/// Settings for capturing a guest's stdout stream.
+#[derive(Clone, Debug)]
+pub struct StreamCapture {
+    /// Maximum number of bytes buffered from the stream.
+    pub max_bytes: usize,
+    /// Size of each chunk handed to consumers.
+    pub chunk_bytes: usize,
+}
+
+impl Default for StreamCapture {
+    fn default() -> Self {
+        Self {
+            max_bytes: 16 * 1024 * 1024,
+            chunk_bytes: 64 * 1024,
+        }
+    }
+}
+
+/// A chunk of data drained from a guest output stream.
+///
+/// Streamed chunks end with one flagged `last`, which holds the bytes after
+/// the last full chunk and is empty when the output ended on a boundary.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamChunk {
+    pub index: usize,
+    pub data: Vec<u8>,
+    pub last: bool,
+}
+
+/// A [`STREAM_FIELD`] attachment that cannot be decoded.
+#[derive(Debug, Error)]
+#[error("stream chunk {index} is malformed: {reason}")]
+pub struct MalformedChunk {
+    pub index: usize,
+    pub reason: String,
+}
+
+/// Split captured bytes into chunks of at most `chunk_bytes`.
+pub fn split_chunks(bytes: &[u8], chunk_bytes: usize) -> Vec<StreamChunk> {
+    let chunks: Vec<&[u8]> = bytes.chunks(chunk_bytes.max(1)).collect();
+    let count = chunks.len();
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, data)| StreamChunk {
+            index,
+            data: data.to_vec(),
+            last: index + 1 == count,
+        })
+        .collect()
+}
+
+/// Attach captured stream bytes to a result value under [`STREAM_FIELD`].
+pub(crate) fn attach(value: &mut Value, bytes: &[u8], capture: &StreamCapture) {
+    if bytes.is_empty() {
+        return;
+    }
+    let Value::Object(map) = value else {
+        return;
+    };
+    let chunks: Vec<String> = split_chunks(bytes, capture.chunk_bytes)
+        .iter()
+        .map(|chunk| STANDARD.encode(&chunk.data))
+        .collect();
+    map.insert(
+        STREAM_FIELD.to_string(),
+        json!({
+            "encoding": "base64",
+            "bytes": bytes.len(),
+            "truncated": bytes.len() >= capture.max_bytes,
+            "chunks": chunks,
+        }),
+    );
+}
+
+/// Remove the [`STREAM_FIELD`] attachment from a result and decode it into
+/// chunks.
+pub fn take_chunks(value: &mut Value) -> Result<Vec<StreamChunk>, MalformedChunk> {
+    let Some(stream) = value
+        .as_object_mut()
+        .and_then(|map| map.remove(STREAM_FIELD))
+    else {
+        return Ok(Vec::new());
+    };
+    let Some(encoded) = stream.get("chunks").and_then(Value::as_array) else {
+        return Err(MalformedChunk {
+            index: 0,
+            reason: "`chunks` is not an array".into(),
+        });
+    };
+    let count = encoded.len();
+    encoded
+        .iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            let malformed = |reason: String| MalformedChunk { index, reason };
+            let chunk = chunk
+                .as_str()
+                .ok_or_else(|| malformed("not a string".into()))?;
+            let data = STANDARD
+                .decode(chunk)
+                .map_err(|err| malformed(err.to_string()))?;
+            Ok(StreamChunk {
+                index,
+                data,
+                last: index + 1 == count,
+            })
+        })
+        .collect()
+}
+
+/// Guest stdout of one call under [`StreamCapture`].
+///
+/// Clones share the same buffer: the store writes through one, the host reads
+/// through another.
+#[derive(Clone)]
+pub struct ChunkPipe {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    capture: StreamCapture,
+    /// A consumer drains chunks during the call.
+    live: bool,
+    state: Mutex<PipeState>,
+    chunk_ready: Condvar,
+}
+
+#[derive(Default)]
+struct PipeState {
+    /// Bytes written since the last full chunk.
+    partial: Vec<u8>,
+    /// Full chunks not yet taken.
+    chunks: VecDeque<StreamChunk>,
+    /// Bytes in `partial` and `chunks`.
+    buffered: usize,
+    /// Index of the next chunk cut.
+    next_index: usize,
+    /// The call ended; later writes fail.
+    finished: bool,
+    /// Guest writes waiting for the consumer to make room.
+    writers: Vec<Waker>,
+}
+
+impl ChunkPipe {
+    /// A pipe whose chunks are kept for [`Self::collected`].
+    pub(crate) fn new(capture: StreamCapture) -> Self {
+        Self::with_mode(capture, false)
+    }
+
+    /// A pipe whose chunks are taken with [`Self::next_chunk`] during the call.
+    pub(crate) fn live(capture: StreamCapture) -> Self {
+        Self::with_mode(capture, true)
+    }
+
+    fn with_mode(capture: StreamCapture, live: bool) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                capture,
+                live,
+                state: Mutex::new(PipeState::default()),
+                chunk_ready: Condvar::new(),
+            }),
+        }
+    }
+
+    pub(crate) fn is_live(&self) -> bool {
+        self.shared.live
+    }
+
+    pub(crate) fn capture(&self) -> &StreamCapture {
+        &self.shared.capture
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PipeState> {
+        self.shared
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Bytes the guest may write now. Zero means wait for the consumer.
+    fn room(&self, state: &PipeState) -> StreamResult<usize> {
+        let room = self.shared.capture.max_bytes.saturating_sub(state.buffered);
+        if state.finished || (room == 0 && !self.shared.live) {
+            return Err(StreamError::Closed);
+        }
+        Ok(room)
+    }
+
+    fn push(&self, bytes: &[u8]) -> StreamResult<()> {
+        let mut state = self.lock();
+        if bytes.len() > self.room(&state)? {
+            return Err(StreamError::trap("write exceeds the stream's permit"));
+        }
+        state.partial.extend_from_slice(bytes);
+        state.buffered += bytes.len();
+        let chunk_bytes = self.shared.capture.chunk_bytes.max(1);
+        let mut cut = false;
+        while state.partial.len() >= chunk_bytes {
+            let rest = state.partial.split_off(chunk_bytes);
+            let data = std::mem::replace(&mut state.partial, rest);
+            cut_chunk(&mut state, data, false);
+            cut = true;
+        }
+        // A full buffer with no chunk to take would leave the guest waiting
+        // for a consumer that is waiting for it.
+        let full = state.buffered >= self.shared.capture.max_bytes;
+        if self.shared.live && full && !state.partial.is_empty() {
+            let data = std::mem::take(&mut state.partial);
+            cut_chunk(&mut state, data, false);
+            cut = true;
+        }
+        if cut {
+            self.shared.chunk_ready.notify_all();
+        }
+        Ok(())
+    }
+
+    fn poll_room(&self, cx: &mut Context<'_>) -> Poll<StreamResult<usize>> {
+        let mut state = self.lock();
+        match self.room(&state) {
+            Ok(0) => {
+                state.writers.push(cx.waker().clone());
+                Poll::Pending
+            }
+            ready => Poll::Ready(ready),
+        }
+    }
+
+    /// Mark the end of the call: the remaining bytes form the last chunk,
+    /// and writes still in flight fail.
+    pub(crate) fn finish(&self) {
+        let mut state = self.lock();
+        if state.finished {
+            return;
+        }
+        state.finished = true;
+        let partial = std::mem::take(&mut state.partial);
+        if !partial.is_empty() || state.next_index > 0 {
+            cut_chunk(&mut state, partial, true);
+        }
+        for writer in state.writers.drain(..) {
+            writer.wake();
+        }
+        self.shared.chunk_ready.notify_all();
+    }
+
+    /// The next chunk, waiting for the guest to write one. `None` once the
+    /// call has finished and every chunk was taken.
+    pub(crate) fn next_chunk(&self) -> Option<StreamChunk> {
+        let mut state = self.lock();
+        loop {
+            if let Some(chunk) = state.chunks.pop_front() {
+                state.buffered -= chunk.data.len();
+                for writer in state.writers.drain(..) {
+                    writer.wake();
+                }
+                return Some(chunk);
+            }
+            if state.finished {
+                return None;
+            }
+            state = self
+                .shared
+                .chunk_ready
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Everything written so far that no consumer has taken.
+    pub(crate) fn collected(&self) -> Vec<u8> {
+        let state = self.lock();
+        let mut bytes = Vec::with_capacity(state.buffered);
+        for chunk in &state.chunks {
+            bytes.extend_from_slice(&chunk.data);
+        }
+        bytes.extend_from_slice(&state.partial);
+        bytes
+    }
+}
+
+fn cut_chunk(state: &mut PipeState, data: Vec<u8>, last: bool) {
+    let index = state.next_index;
+    state.next_index += 1;
+    state.chunks.push_back(StreamChunk { index, data, last });
+}
+
+impl IsTerminal for ChunkPipe {
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+impl StdoutStream for ChunkPipe {
+    fn p2_stream(&self) -> Box<dyn OutputStream> {
+        Box::new(self.clone())
+    }
+
+    fn async_stream(&self) -> Box<dyn AsyncWrite + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl OutputStream for ChunkPipe {
+    fn write(&mut self, bytes: Bytes) -> StreamResult<()> {
+        self.push(&bytes)
+    }
+
+    fn flush(&mut self) -> StreamResult<()> {
+        // Chunks are cut as bytes arrive; there is nothing to flush.
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> StreamResult<usize> {
+        let state = self.lock();
+        self.room(&state)
+    }
+}
+
+#[async_trait::async_trait]
+impl Pollable for ChunkPipe {
+    async fn ready(&mut self) {
+        // Errors are reported by the `check_write` that follows.
+        let _ = std::future::poll_fn(|cx| self.poll_room(cx)).await;
+    }
+}
+
+impl AsyncWrite for ChunkPipe {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.poll_room(cx) {
+            Poll::Ready(Ok(room)) => {
+                let len = buf.len().min(room);
+                Poll::Ready(
+                    self.push(&buf[..len])
+                        .map(|()| len)
+                        .map_err(|err| io::Error::new(io::ErrorKind::BrokenPipe, err)),
+                )
+            }
+            Poll::Ready(Err(err)) => {
+                Poll::Ready(Err(io::Error::new(io::ErrorKind::BrokenPipe, err)))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn attaches_and_takes_chunks() {
+        let capture = StreamCapture {
+            max_bytes: 1024,
+            chunk_bytes: 4,
+        };
+        let mut value = json!({"ok": true, "stream": "guest"});
+        attach(&mut value, b"hello world", &capture);
+        assert_eq!(value.pointer("/_exec_stream/bytes"), Some(&json!(11)));
+        assert_eq!(
+            value.pointer("/_exec_stream/truncated"),
+            Some(&json!(false))
+        );
+
+        let chunks = take_chunks(&mut value).expect("chunks");
+        assert!(value.get(STREAM_FIELD).is_none());
+        assert_eq!(value["stream"], "guest", "the guest's own field is kept");
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].data, b"hell");
+        assert_eq!(chunks[2].data, b"rld");
+        assert!(chunks[2].last);
+        let joined: Vec<u8> = chunks.into_iter().flat_map(|chunk| chunk.data).collect();
+        assert_eq!(joined, b"hello world");
+    }
+
+    #[test]
+    fn empty_streams_are_not_attached() {
+        let mut value = json!({"ok": true});
+        attach(&mut value, b"", &StreamCapture::default());
+        assert!(value.get(STREAM_FIELD).is_none());
+    }
+
+    #[test]
+    fn malformed_chunks_are_reported() {
+        let mut value = json!({ STREAM_FIELD: { "chunks": ["aGk=", "not base64!"] } });
+        let err = take_chunks(&mut value).expect_err("bad chunk");
+        assert_eq!(err.index, 1);
+    }
+
+    #[test]
+    fn live_pipes_deliver_during_the_call_and_apply_backpressure() {
+        let pipe = ChunkPipe::live(StreamCapture {
+            max_bytes: 8,
+            chunk_bytes: 4,
+        });
+        let mut writer = pipe.clone();
+        let guest = thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .build()
+                .expect("runtime");
+            runtime.block_on(async {
+                for _ in 0..4 {
+                    writer
+                        .blocking_write_and_flush(Bytes::from_static(b"abcdef"))
+                        .await
+                        .expect("write waits for room instead of failing");
+                }
+            });
+            writer.finish();
+        });
+
+        // The guest fills the window, then waits for the consumer.
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(pipe.lock().buffered, 8);
+        assert!(!guest.is_finished());
+
+        let mut chunks = Vec::new();
+        while let Some(chunk) = pipe.next_chunk() {
+            chunks.push(chunk);
+        }
+        guest.join().expect("guest");
+        assert_eq!(chunks.len(), 7);
+        assert!(chunks.iter().enumerate().all(|(i, c)| c.index == i));
+        assert!(chunks[6].last && chunks[6].data.is_empty());
+        let joined: Vec<u8> = chunks.into_iter().flat_map(|chunk| chunk.data).collect();
+        assert_eq!(joined, b"abcdef".repeat(4));
+    }
+
+    #[test]
+    fn live_pipes_flush_short_chunks_when_chunks_outgrow_the_buffer() {
+        let pipe = ChunkPipe::live(StreamCapture {
+            max_bytes: 4,
+            chunk_bytes: 16,
+        });
+        let mut writer = pipe.clone();
+        let guest = thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .build()
+                .expect("runtime");
+            runtime.block_on(async {
+                writer
+                    .blocking_write_and_flush(Bytes::from_static(b"abcdefghij"))
+                    .await
+                    .expect("write");
+            });
+            writer.finish();
+        });
+
+        let mut chunks = Vec::new();
+        while let Some(chunk) = pipe.next_chunk() {
+            chunks.push(chunk);
+        }
+        guest.join().expect("guest");
+        assert!(chunks.iter().all(|chunk| chunk.data.len() <= 4));
+        assert!(chunks.last().expect("last").last);
+        let joined: Vec<u8> = chunks.into_iter().flat_map(|chunk| chunk.data).collect();
+        assert_eq!(joined, b"abcdefghij");
+    }
+
+    #[test]
+    fn collected_pipes_close_at_the_cap() {
+        let mut pipe = ChunkPipe::new(StreamCapture {
+            max_bytes: 6,
+            chunk_bytes: 4,
+        });
+        assert_eq!(pipe.check_write().expect("room"), 6);
+        pipe.write(Bytes::from_static(b"abcdef")).expect("write");
+        assert!(matches!(pipe.check_write(), Err(StreamError::Closed)));
+        assert_eq!(pipe.collected(), b"abcdef");
+    }
+}
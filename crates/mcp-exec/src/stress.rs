@@ -0,0 +1,320 @@
+//! Stress tests for the executor's shared state.
+//!
+//! A store is only ever owned by one call: it is created for a request on a
+//! worker thread, or ahead of time on a warm instance's actor thread, and is
+//! dropped on that same thread once the call returns. These tests drive
+//! [`Executor`] and [`WarmPool`] from many threads while the component is
+//! republished and caches are cleared, and check the invariants this relies on:
+//!
+//! - no instance serves two calls (the test component traps on a second one);
+//! - every caller gets its own tenant's result back, cached or fresh;
+//! - once reloads settle, no instance of a replaced artifact is served.
+//!
+//! Set `GREENTIC_MCP_EXEC_STRESS_ITERATIONS` to soak for longer.
+
+use std::path::Path;
+use std::sync::{Barrier, Mutex};
+use std::time::Duration;
+use std::{env, fs, thread};
+
+use greentic_types::{EnvId, TenantCtx, TenantId};
+use serde_json::{Value, json};
+
+use crate::{
+    ComponentCaching, ExecConfig, ExecError, ExecRequest, Executor, Priority, ResultCachePolicy,
+    RuntimePolicy, ToolStore, VerifyPolicy, WarmPool, WarmPoolConfig,
+};
+
+const CALLERS: usize = 6;
+const TENANTS: usize = 3;
+const RELOADS: u8 = 8;
+const ITERATIONS_ENV: &str = "GREENTIC_MCP_EXEC_STRESS_ITERATIONS";
+
+fn iterations() -> usize {
+    env::var(ITERATIONS_ENV)
+        .ok()
+        .and_then(|raw| raw.parse().ok())
+        .unwrap_or(24)
+}
+
+/// A component whose `exec` answers `{"version":<version>,"args":<args>}` and
+/// traps if its instance is ever called a second time.
+pub(crate) fn echo_component(version: u8) -> Vec<u8> {
+    let prefix = format!(r#"{{"version":{version},"args":"#);
+    let len = prefix.len();
+    let data = prefix.replace('"', "\\\"");
+    wat::parse_str(format!(
+        r#"(component
+            (core module $m
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{data}")
+                (global $next (mut i32) (i32.const 1024))
+                (global $calls (mut i32) (i32.const 0))
+                (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                    (local $ptr i32)
+                    (local.set $ptr (global.get $next))
+                    (global.set $next (i32.add (local.get $ptr) (local.get 3)))
+                    (local.get $ptr))
+                (func (export "exec") (param i32 i32 i32 i32) (result i32)
+                    (local $out i32)
+                    (if (global.get $calls) (then unreachable))
+                    (global.set $calls (i32.const 1))
+                    (local.set $out (global.get $next))
+                    (global.set $next
+                        (i32.add (local.get $out) (i32.add (local.get 3) (i32.const {end}))))
+                    (memory.copy (local.get $out) (i32.const 0) (i32.const {len}))
+                    (memory.copy
+                        (i32.add (local.get $out) (i32.const {len})) (local.get 2) (local.get 3))
+                    (i32.store8
+                        (i32.add (i32.add (local.get $out) (i32.const {len})) (local.get 3))
+                        (i32.const 125))
+                    (i32.store (i32.const 64) (local.get $out))
+                    (i32.store (i32.const 68) (i32.add (local.get 3) (i32.const {end})))
+                    (i32.const 64)))
+            (core instance $i (instantiate $m))
+            (func (export "exec") (param "action" string) (param "args" string) (result string)
+                (canon lift (core func $i "exec") (memory $i "memory")
+                    (realloc (func $i "realloc")))))"#,
+        end = len + 1,
+    ))
+    .expect("echo component")
+}
+
+/// A component whose `exec` never returns.
+pub(crate) fn spinning_component() -> Vec<u8> {
+    wat::parse_str(
+        r#"(component
+            (core module $m
+                (memory (export "memory") 1)
+                (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                    (i32.const 1024))
+                (func (export "exec") (param i32 i32 i32 i32) (result i32)
+                    (loop $spin (br $spin))
+                    (unreachable)))
+            (core instance $i (instantiate $m))
+            (func (export "exec") (param "action" string) (param "args" string) (result string)
+                (canon lift (core func $i "exec") (memory $i "memory")
+                    (realloc (func $i "realloc")))))"#,
+    )
+    .expect("spinning component")
+}
+
+/// Replace `echo.wasm` in `dir` with `version`, atomically so a concurrent
+/// resolve never reads a half-written artifact.
+fn publish(dir: &Path, version: u8) {
+    let staged = dir.join(format!(".echo-{version}"));
+    fs::write(&staged, echo_component(version)).expect("stage echo");
+    fs::rename(&staged, dir.join("echo.wasm")).expect("publish echo");
+}
+
+fn config(dir: &Path) -> ExecConfig {
+    ExecConfig {
+        store: ToolStore::LocalDir(dir.to_path_buf()),
+        security: VerifyPolicy {
+            allow_unverified: true,
+            ..Default::default()
+        },
+        runtime: RuntimePolicy {
+            result_cache: Some(ResultCachePolicy {
+                components: [("echo".to_string(), ComponentCaching::Always)].into(),
+                ..ResultCachePolicy::default()
+            }),
+            ..RuntimePolicy::default()
+        },
+        http_enabled: false,
+        secrets_store: None,
+        cost_sink: None,
+        transforms: None,
+        hooks: None,
+    }
+}
+
+fn request(tenant: &str, args: Value, priority: Priority) -> ExecRequest {
+    ExecRequest {
+        component: "echo".into(),
+        action: "echo".into(),
+        args,
+        args_raw: None,
+        tenant: Some(TenantCtx::new(
+            EnvId("dev".into()),
+            TenantId(tenant.to_string()),
+        )),
+        expected_digest: None,
+        priority,
+    }
+}
+
+/// The `iteration`-th request of `caller`. Arguments repeat, so calls also
+/// go through the result cache.
+fn caller_request(caller: usize, iteration: usize) -> ExecRequest {
+    let tenant = format!("tenant-{}", caller % TENANTS);
+    let priority = if iteration.is_multiple_of(2) {
+        Priority::Interactive
+    } else {
+        Priority::Background
+    };
+    request(
+        &tenant,
+        json!({ "tenant": tenant, "n": iteration % 4 }),
+        priority,
+    )
+}
+
+/// Check that `result` echoes `req`'s own arguments and return the version
+/// that served it.
+fn check_echo(req: &ExecRequest, result: Result<Value, ExecError>) -> Result<u64, String> {
+    let value = result.map_err(|err| err.to_string())?;
+    if value["args"] != req.args {
+        return Err(format!("asked with {}, got {value}", req.args));
+    }
+    value["version"]
+        .as_u64()
+        .ok_or_else(|| format!("no version in {value}"))
+}
+
+/// Run `call(caller, iteration)` on [`CALLERS`] threads at once while
+/// `meddle` runs on another, and collect the violations `call` reports.
+fn hammer<Call, Meddle>(iterations: usize, call: Call, meddle: Meddle) -> Vec<String>
+where
+    Call: Fn(usize, usize) -> Result<(), String> + Sync,
+    Meddle: FnOnce() + Send,
+{
+    let violations = Mutex::new(Vec::new());
+    let start = Barrier::new(CALLERS + 1);
+    thread::scope(|scope| {
+        for caller in 0..CALLERS {
+            let (call, start, violations) = (&call, &start, &violations);
+            scope.spawn(move || {
+                start.wait();
+                for iteration in 0..iterations {
+                    if let Err(violation) = call(caller, iteration) {
+                        violations.lock().unwrap().push(format!(
+                            "caller {caller}, iteration {iteration}: {violation}"
+                        ));
+                    }
+                }
+            });
+        }
+        scope.spawn(|| {
+            start.wait();
+            meddle();
+        });
+    });
+    violations.into_inner().unwrap()
+}
+
+#[test]
+fn executor_keeps_calls_apart_under_reloads_and_invalidations() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    publish(tmp.path(), 0);
+    let executor = Executor::with_workers(config(tmp.path()), 4).expect("executor");
+
+    let iterations = iterations();
+    let violations = hammer(
+        iterations,
+        |caller, iteration| {
+            let req = caller_request(caller, iteration);
+            check_echo(&req, executor.exec(req.clone())).map(drop)
+        },
+        || {
+            for version in 1..=RELOADS {
+                thread::sleep(Duration::from_millis(5));
+                publish(tmp.path(), version);
+                executor.clear_cache();
+            }
+        },
+    );
+    assert!(violations.is_empty(), "{violations:#?}");
+
+    let req = caller_request(0, 0);
+    assert_eq!(
+        check_echo(&req, executor.exec(req.clone())),
+        Ok(u64::from(RELOADS))
+    );
+    let stats = executor.result_cache_stats().expect("cache enabled");
+    assert_eq!(
+        stats.hits + stats.misses,
+        (CALLERS * iterations + 1) as u64,
+        "every cacheable call counts once"
+    );
+}
+
+#[test]
+fn warm_pool_serves_each_instance_once_across_reloads() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    publish(tmp.path(), 0);
+    let cfg = config(tmp.path());
+    let size = 3;
+    let pool = WarmPool::new(
+        &cfg.runtime,
+        [WarmPoolConfig {
+            component: "echo".into(),
+            size,
+            max_idle: Duration::from_secs(60),
+        }],
+    )
+    .expect("pool");
+
+    let violations = hammer(
+        iterations(),
+        |caller, iteration| {
+            let req = caller_request(caller, iteration);
+            check_echo(&req, pool.exec(req.clone(), &cfg)).map(drop)
+        },
+        || {
+            for version in 1..=RELOADS {
+                thread::sleep(Duration::from_millis(5));
+                publish(tmp.path(), version);
+                // Half the reloads are only noticed through the digest change.
+                if version % 2 == 0 {
+                    pool.evict("echo");
+                }
+            }
+        },
+    );
+    assert!(violations.is_empty(), "{violations:#?}");
+
+    // Drain more than the pool holds: refills racing the last reload must not
+    // have left instances of an older artifact behind.
+    for n in 0..=size {
+        let req = caller_request(n, n);
+        assert_eq!(
+            check_echo(&req, pool.exec(req.clone(), &cfg)),
+            Ok(u64::from(RELOADS)),
+            "call {n} after the last reload"
+        );
+    }
+}
+
+#[test]
+fn cached_results_never_cross_tenants() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    publish(tmp.path(), 0);
+    let executor = Executor::with_workers(config(tmp.path()), 4).expect("executor");
+    let shared = |tenant: usize| {
+        request(
+            &format!("tenant-{tenant}"),
+            json!({ "query": "same for everyone" }),
+            Priority::Interactive,
+        )
+    };
+
+    let req = shared(0);
+    check_echo(&req, executor.exec(req.clone())).expect("warm the cache");
+    let violations = hammer(
+        1,
+        |caller, _| {
+            let req = shared(caller + 1);
+            check_echo(&req, executor.exec(req.clone())).map(drop)
+        },
+        || {},
+    );
+    assert!(violations.is_empty(), "{violations:#?}");
+
+    let stats = executor.result_cache_stats().expect("cache enabled");
+    assert_eq!(
+        (stats.hits, stats.misses),
+        (0, 1 + CALLERS as u64),
+        "identical arguments from another tenant are a miss"
+    );
+}
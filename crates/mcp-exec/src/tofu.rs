@@ -0,0 +1,87 @@
+//! Trust-on-first-use digest records persisted between executions.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::error::VerificationError;
+
+/// Serializes read-modify-write cycles on TOFU files within this process.
+static TOFU_LOCK: Mutex<()> = Mutex::new(());
+
+/// Enforce a previously recorded digest for `component`, or record `digest`
+/// when the component has never been seen before.
+pub(crate) fn check_or_record(
+    path: &Path,
+    component: &str,
+    digest: &str,
+) -> Result<(), VerificationError> {
+    let _guard = TOFU_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let mut records = load(path).map_err(|err| store_error(path, err))?;
+    match records.get(component) {
+        Some(recorded) if recorded == digest => Ok(()),
+        Some(recorded) => Err(VerificationError::TofuMismatch {
+            recorded: recorded.clone(),
+            actual: digest.to_string(),
+        }),
+        None => {
+            records.insert(component.to_string(), digest.to_string());
+            save(path, &records).map_err(|err| store_error(path, err))
+        }
+    }
+}
+
+fn load(path: &Path) -> io::Result<BTreeMap<String, String>> {
+    match fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(io::Error::other),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(err) => Err(err),
+    }
+}
+
+fn save(path: &Path, records: &BTreeMap<String, String>) -> io::Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)?;
+    }
+    let bytes = serde_json::to_vec_pretty(records).map_err(io::Error::other)?;
+    let tmp = path.with_extension("tofu-tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)
+}
+
+fn store_error(path: &Path, err: io::Error) -> VerificationError {
+    VerificationError::TofuStore(format!("{}: {err}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_first_digest_and_flags_changes() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("tofu.json");
+
+        check_or_record(&path, "tool", "aaa").expect("first sight is recorded");
+        check_or_record(&path, "tool", "aaa").expect("same digest passes");
+        check_or_record(&path, "other", "bbb").expect("other component recorded");
+
+        let err = check_or_record(&path, "tool", "ccc").expect_err("changed digest");
+        assert!(matches!(
+            err,
+            VerificationError::TofuMismatch { ref recorded, ref actual }
+                if recorded == "aaa" && actual == "ccc"
+        ));
+
+        let records = load(&path).expect("load records");
+        assert_eq!(records.get("tool").map(String::as_str), Some("aaa"));
+        assert_eq!(records.get("other").map(String::as_str), Some("bbb"));
+    }
+}
@@ -0,0 +1,211 @@
+//! Host-side rewriting of call arguments and results.
+//!
+//! [`TransformHooks`] registered on [`crate::ExecConfig::transforms`] let a host
+//! adapt a component whose schema does not quite match its callers without
+//! rebuilding the wasm: pre-call hooks rewrite the arguments (inject defaults,
+//! rename fields) and post-call hooks rewrite successful results (trim fields).
+//! Hooks are registered per component and run in registration order; errors
+//! are passed through untouched.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::ExecRequest;
+use crate::error::ExecError;
+
+/// Rewrites `args` of a call to the given action.
+pub type ArgsHook = Arc<dyn Fn(&str, &mut Value) + Send + Sync>;
+/// Rewrites the successful result of a call to the given action.
+pub type ResultHook = Arc<dyn Fn(&str, &mut Value) + Send + Sync>;
+
+#[derive(Clone, Default)]
+struct ComponentHooks {
+    before: Vec<ArgsHook>,
+    after: Vec<ResultHook>,
+}
+
+/// Per-component argument and result transformations.
+#[derive(Clone, Default)]
+pub struct TransformHooks {
+    components: HashMap<String, ComponentHooks>,
+}
+
+impl fmt::Debug for TransformHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut components: Vec<&String> = self.components.keys().collect();
+        components.sort();
+        f.debug_struct("TransformHooks")
+            .field("components", &components)
+            .finish()
+    }
+}
+
+impl TransformHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rewrite the arguments of every call to `component` before it runs.
+    pub fn before_call(
+        mut self,
+        component: impl Into<String>,
+        hook: impl Fn(&str, &mut Value) + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks(component).before.push(Arc::new(hook));
+        self
+    }
+
+    /// Rewrite the successful results of calls to `component`.
+    pub fn after_call(
+        mut self,
+        component: impl Into<String>,
+        hook: impl Fn(&str, &mut Value) + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks(component).after.push(Arc::new(hook));
+        self
+    }
+
+    /// Fill in top-level arguments the caller left out from `defaults`.
+    pub fn with_defaults(self, component: impl Into<String>, defaults: Value) -> Self {
+        self.before_call(component, move |_, args| {
+            let (Some(args), Some(defaults)) = (args.as_object_mut(), defaults.as_object()) else {
+                return;
+            };
+            for (key, value) in defaults {
+                args.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        })
+    }
+
+    /// Rename the top-level argument `from` to `to`.
+    pub fn rename_arg(
+        self,
+        component: impl Into<String>,
+        from: impl Into<String>,
+        to: impl Into<String>,
+    ) -> Self {
+        let (from, to) = (from.into(), to.into());
+        self.before_call(component, move |_, args| {
+            if let Some(args) = args.as_object_mut()
+                && let Some(value) = args.remove(&from)
+            {
+                args.insert(to.clone(), value);
+            }
+        })
+    }
+
+    /// Remove top-level `fields` from the results of `component`.
+    pub fn drop_result_fields(
+        self,
+        component: impl Into<String>,
+        fields: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        let fields: Vec<String> = fields.into_iter().map(Into::into).collect();
+        self.after_call(component, move |_, result| {
+            if let Some(result) = result.as_object_mut() {
+                for field in &fields {
+                    result.remove(field);
+                }
+            }
+        })
+    }
+
+    fn hooks(&mut self, component: impl Into<String>) -> &mut ComponentHooks {
+        self.components.entry(component.into()).or_default()
+    }
+
+    fn for_request(&self, req: &ExecRequest) -> Option<&ComponentHooks> {
+        self.components.get(&req.component)
+    }
+}
+
+impl ComponentHooks {
+    fn rewrite_args(&self, req: &mut ExecRequest) -> Result<(), ExecError> {
+        req.materialize_args()
+            .map_err(|err| ExecError::runner(&req.component, err.into()))?;
+        for hook in &self.before {
+            hook(&req.action, &mut req.args);
+        }
+        Ok(())
+    }
+
+    fn rewrite_result(&self, action: &str, value: &mut Value) {
+        for hook in &self.after {
+            hook(action, value);
+        }
+    }
+}
+
+/// Run `exec` on `req` with the hooks for its component applied around it.
+pub(crate) fn around(
+    hooks: Option<&TransformHooks>,
+    mut req: ExecRequest,
+    exec: impl FnOnce(ExecRequest) -> Result<Value, ExecError>,
+) -> Result<Value, ExecError> {
+    let Some(hooks) = hooks.and_then(|hooks| hooks.for_request(&req)) else {
+        return exec(req);
+    };
+    hooks.rewrite_args(&mut req)?;
+    let action = req.action.clone();
+    let mut value = exec(req)?;
+    hooks.rewrite_result(&action, &mut value);
+    Ok(value)
+}
+
+/// [`around`] for an asynchronous `exec`.
+pub(crate) async fn around_async<F>(
+    hooks: Option<&TransformHooks>,
+    mut req: ExecRequest,
+    exec: impl FnOnce(ExecRequest) -> F,
+) -> Result<Value, ExecError>
+where
+    F: Future<Output = Result<Value, ExecError>>,
+{
+    let Some(hooks) = hooks.and_then(|hooks| hooks.for_request(&req)) else {
+        return exec(req).await;
+    };
+    hooks.rewrite_args(&mut req)?;
+    let action = req.action.clone();
+    let mut value = exec(req).await?;
+    hooks.rewrite_result(&action, &mut value);
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(component: &str, args: Value) -> ExecRequest {
+        ExecRequest {
+            component: component.into(),
+            action: "search".into(),
+            args,
+            args_raw: None,
+            tenant: None,
+            expected_digest: None,
+            priority: Default::default(),
+        }
+    }
+
+    #[test]
+    fn hooks_rewrite_arguments_and_results_per_component() {
+        let hooks = TransformHooks::new()
+            .with_defaults("docs", json!({"limit": 10, "query": "ignored"}))
+            .rename_arg("docs", "q", "query")
+            .drop_result_fields("docs", ["debug"]);
+
+        let echo = |req: ExecRequest| Ok(json!({"args": req.args, "debug": true}));
+        let value =
+            around(Some(&hooks), request("docs", json!({"q": "rust"})), echo).expect("call");
+        // Defaults run before the rename, so the renamed argument wins.
+        assert_eq!(value, json!({"args": {"limit": 10, "query": "rust"}}));
+
+        let untouched =
+            around(Some(&hooks), request("other", json!({"q": "rust"})), echo).expect("call");
+        assert_eq!(untouched, json!({"args": {"q": "rust"}, "debug": true}));
+    }
+}
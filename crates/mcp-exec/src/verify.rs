@@ -18,6 +18,28 @@ pub fn verify(
     artifact: ResolvedArtifact,
     policy: &VerifyPolicy,
 ) -> Result<VerifiedArtifact, VerificationError> {
+    verify_pinned(component, artifact, policy, None)
+}
+
+/// Verify an artifact, additionally enforcing a digest pinned by the caller.
+///
+/// A pinned digest counts as verification on its own, so it satisfies policies
+/// that reject unverified artifacts; policy digests are still enforced as well.
+pub fn verify_pinned(
+    component: &str,
+    artifact: ResolvedArtifact,
+    policy: &VerifyPolicy,
+    pinned_digest: Option<&str>,
+) -> Result<VerifiedArtifact, VerificationError> {
+    if let Some(pinned) = pinned_digest
+        && artifact.digest != pinned
+    {
+        return Err(VerificationError::PinnedDigestMismatch {
+            expected: pinned.to_string(),
+            actual: artifact.digest,
+        });
+    }
+
     if let Some(expected_digest) = policy.required_digests.get(component) {
         if artifact.digest != *expected_digest {
             return Err(VerificationError::DigestMismatch {
@@ -25,7 +47,9 @@ pub fn verify(
                 actual: artifact.digest,
             });
         }
-    } else if !policy.allow_unverified {
+    } else if let Some(tofu_store) = &policy.tofu_store {
+        crate::tofu::check_or_record(tofu_store, component, &artifact.digest)?;
+    } else if pinned_digest.is_none() && !policy.allow_unverified {
         return Err(VerificationError::UnsignedRejected);
     }
 
@@ -66,6 +90,50 @@ mod tests {
         assert!(matches!(err, VerificationError::DigestMismatch { .. }));
     }
 
+    #[test]
+    fn pinned_digest_is_enforced_and_satisfies_policy() {
+        let policy = VerifyPolicy::default();
+
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(tmp.path().join("tool.wasm"), b"bytes").expect("write wasm");
+        let store = ToolStore::LocalDir(PathBuf::from(tmp.path()));
+
+        let artifact = resolve::resolve("tool", &store).expect("resolve");
+        let digest = artifact.digest.clone();
+        let verified =
+            verify_pinned("tool", artifact, &policy, Some(&digest)).expect("pinned verify");
+        assert_eq!(verified.verified_digest.as_deref(), Some(digest.as_str()));
+
+        let artifact = resolve::resolve("tool", &store).expect("resolve");
+        let err = verify_pinned("tool", artifact, &policy, Some("other-digest"))
+            .expect_err("should fail");
+        assert!(matches!(
+            err,
+            VerificationError::PinnedDigestMismatch { .. }
+        ));
+    }
+
+    #[test]
+    fn tofu_store_accepts_first_sight_and_rejects_changes() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let policy = VerifyPolicy {
+            tofu_store: Some(tmp.path().join("tofu.json")),
+            ..Default::default()
+        };
+        let store_dir = tmp.path().join("tools");
+        std::fs::create_dir_all(&store_dir).expect("tools dir");
+        let store = ToolStore::LocalDir(store_dir.clone());
+
+        std::fs::write(store_dir.join("tool.wasm"), b"first").expect("write wasm");
+        let artifact = resolve::resolve("tool", &store).expect("resolve");
+        verify("tool", artifact, &policy).expect("first use is trusted");
+
+        std::fs::write(store_dir.join("tool.wasm"), b"second").expect("rewrite wasm");
+        let artifact = resolve::resolve("tool", &store).expect("resolve");
+        let err = verify("tool", artifact, &policy).expect_err("changed bytes rejected");
+        assert!(matches!(err, VerificationError::TofuMismatch { .. }));
+    }
+
     #[test]
     fn allows_unsigned_when_policy_permits() {
         let policy = VerifyPolicy {
@@ -20,6 +20,9 @@ fn offline_mock_describe_and_list() {
         runtime: Default::default(),
         http_enabled: false,
         secrets_store: None,
+        cost_sink: None,
+        transforms: None,
+        hooks: None,
     };
 
     let tools = cfg.store.list().unwrap();
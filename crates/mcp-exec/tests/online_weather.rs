@@ -21,6 +21,9 @@ fn online_weather_list_and_describe() {
         runtime: Default::default(),
         http_enabled: true,
         secrets_store: None,
+        cost_sink: None,
+        transforms: None,
+        hooks: None,
     };
 
     let tools = match cfg.store.list() {
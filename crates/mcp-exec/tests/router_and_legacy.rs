@@ -56,13 +56,19 @@ fn executes_router_world() {
         runtime: RuntimePolicy::default(),
         http_enabled: false,
         secrets_store: None,
+        cost_sink: None,
+        transforms: None,
+        hooks: None,
     };
 
     let req = ExecRequest {
         component: "router_echo".into(),
         action: "echo".into(),
         args: json!({"msg": "hi"}),
+        args_raw: None,
         tenant: None,
+        expected_digest: None,
+        priority: Default::default(),
     };
 
     let value = greentic_mcp_exec::exec(req, &cfg).expect("router exec");
@@ -74,6 +80,44 @@ fn executes_router_world() {
     assert!(text.contains("\"msg\""));
 }
 
+#[test]
+fn router_calls_take_pre_serialized_arguments() {
+    let Some(wasm_path) = build_fixture("tests/router_echo", "router_echo") else {
+        return;
+    };
+
+    let cfg = ExecConfig {
+        store: ToolStore::LocalDir(wasm_path.parent().expect("parent").to_path_buf()),
+        security: VerifyPolicy {
+            allow_unverified: true,
+            ..Default::default()
+        },
+        runtime: RuntimePolicy::default(),
+        http_enabled: false,
+        secrets_store: None,
+        cost_sink: None,
+        transforms: None,
+        hooks: None,
+    };
+
+    let req = ExecRequest {
+        component: "router_echo".into(),
+        action: "echo".into(),
+        args: serde_json::Value::Null,
+        args_raw: Some(r#"{"msg":"raw"}"#.into()),
+        tenant: None,
+        expected_digest: None,
+        priority: Default::default(),
+    };
+
+    let value = greentic_mcp_exec::exec(req, &cfg).expect("router exec");
+    let text = value
+        .pointer("/result/content/0/text")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    assert!(text.contains("\"raw\""), "{text}");
+}
+
 #[test]
 fn falls_back_to_legacy_exec() {
     let Some(wasm_path) = build_fixture("tests/legacy_exec", "legacy_exec") else {
@@ -88,13 +132,19 @@ fn falls_back_to_legacy_exec() {
         runtime: RuntimePolicy::default(),
         http_enabled: false,
         secrets_store: None,
+        cost_sink: None,
+        transforms: None,
+        hooks: None,
     };
 
     let req = ExecRequest {
         component: "legacy_exec".into(),
         action: "anything".into(),
         args: json!({"k": "v"}),
+        args_raw: None,
         tenant: None,
+        expected_digest: None,
+        priority: Default::default(),
     };
 
     let value = greentic_mcp_exec::exec(req, &cfg).expect("legacy exec");
@@ -70,13 +70,19 @@ fn router_executes_echo_tool() {
         runtime: RuntimePolicy::default(),
         http_enabled: false,
         secrets_store: None,
+        cost_sink: None,
+        transforms: None,
+        hooks: None,
     };
 
     let req = ExecRequest {
         component: "router_echo".into(),
         action: "echo".into(),
         args: json!({"text": "hi"}),
+        args_raw: None,
         tenant: None,
+        expected_digest: None,
+        priority: Default::default(),
     };
 
     let value = greentic_mcp_exec::exec(req, &cfg).expect("router call succeeds");
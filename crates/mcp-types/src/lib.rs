@@ -0,0 +1,762 @@
+//! MCP message types shared by Greentic hosts and guests.
+//!
+//! These are the JSON-RPC envelopes, tool descriptions, results, and request
+//! builders `greentic-mcp` uses on the wire, split out so guest components and
+//! constrained environments can encode and decode the same structs without
+//! pulling in an HTTP client or an async runtime. `greentic-mcp` re-exports
+//! everything here from `greentic_mcp::protocol`.
+//!
+//! The crate is `no_std` (with `alloc`) when built without the default `std`
+//! feature; see `Tool::secret_requirements` for the one difference.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use core::marker::PhantomData;
+use core::str::FromStr;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(feature = "std")]
+use greentic_types::{SecretKey, SecretRequirement};
+use serde::de::{self, DeserializeOwned, Error as _};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use serde_json::{Map, Value};
+
+const JSONRPC_2_0: &str = "2.0";
+
+fn jsonrpc_version() -> String {
+    JSONRPC_2_0.to_string()
+}
+
+/// Supported MCP protocol revisions.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, Default)]
+pub enum ProtocolRevision {
+    #[serde(rename = "2025-03-26")]
+    V2025_03_26,
+    #[default]
+    #[serde(rename = "2025-06-18")]
+    V2025_06_18,
+}
+
+impl ProtocolRevision {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ProtocolRevision::V2025_03_26 => "2025-03-26",
+            ProtocolRevision::V2025_06_18 => "2025-06-18",
+        }
+    }
+}
+
+impl Display for ProtocolRevision {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ProtocolRevision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "2025-03-26" | "v2025-03-26" | "2025_03_26" => Ok(ProtocolRevision::V2025_03_26),
+            "2025-06-18" | "v2025-06-18" | "2025_06_18" | "2025-06" => {
+                Ok(ProtocolRevision::V2025_06_18)
+            }
+            other => Err(format!(
+                "unsupported protocol revision '{}'; expected 2025-03-26 or 2025-06-18",
+                other
+            )),
+        }
+    }
+}
+
+/// JSON-RPC 2.0 request shape used by MCP.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct McpRequest<P = Value> {
+    #[serde(default = "jsonrpc_version")]
+    pub jsonrpc: String,
+    pub id: Value,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<P>,
+    #[serde(default, flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+/// JSON-RPC 2.0 response shape used by MCP.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct McpResponse<R = Value, E = RpcError> {
+    #[serde(default = "jsonrpc_version")]
+    pub jsonrpc: String,
+    pub id: Value,
+    // A named default keeps serde from requiring `R: Default` and `E: Default`.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub result: Option<R>,
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub error: Option<E>,
+    #[serde(default, flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+/// JSON-RPC notification shape used by MCP (no ID).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct McpNotification<P = Value> {
+    #[serde(default = "jsonrpc_version")]
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<P>,
+    #[serde(default, flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(default, flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+/// Protocol content wrapper. Flexible, passes through unknown fields.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Content {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(default, flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+/// JSON kept as its source text and parsed only on demand.
+///
+/// Tool schemas and structured results can be large, and clients aggregating
+/// many tools rarely look inside most of them. Holding the text skips building
+/// a [`Value`] for each, and serializing writes the text back verbatim. Only
+/// `serde_json` can capture or reproduce the text.
+#[derive(Clone, Debug)]
+pub struct RawJson(Box<RawValue>);
+
+impl RawJson {
+    /// Keep `text` as is, once it is checked to be valid JSON.
+    pub fn from_text(text: impl Into<String>) -> serde_json::Result<Self> {
+        RawValue::from_string(text.into()).map(Self)
+    }
+
+    /// The JSON text.
+    pub fn get(&self) -> &str {
+        self.0.get()
+    }
+
+    /// Parse the text as `T`.
+    pub fn parse<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_str(self.0.get())
+    }
+
+    /// Parse the text as a [`Value`].
+    pub fn to_value(&self) -> Value {
+        // The text was validated when it was captured.
+        self.parse().unwrap_or(Value::Null)
+    }
+}
+
+impl From<Value> for RawJson {
+    fn from(value: Value) -> Self {
+        Self(serde_json::value::to_raw_value(&value).expect("a Value always encodes"))
+    }
+}
+
+impl Serialize for RawJson {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RawJson {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Box::<RawValue>::deserialize(deserializer).map(Self)
+    }
+}
+
+/// A JSON object's fields, each kept as its source text.
+///
+/// Structs holding [`RawJson`] next to a flattened `extra` map deserialize
+/// through this, since `#[serde(flatten)]` buffers every field as parsed
+/// content and so cannot capture text.
+struct RawFields<E> {
+    fields: BTreeMap<String, Box<RawValue>>,
+    error: PhantomData<E>,
+}
+
+impl<E: de::Error> RawFields<E> {
+    fn deserialize<'de, D>(deserializer: D) -> Result<Self, E>
+    where
+        D: serde::Deserializer<'de, Error = E>,
+    {
+        Ok(Self {
+            fields: BTreeMap::deserialize(deserializer)?,
+            error: PhantomData,
+        })
+    }
+
+    /// Remove the first of `keys` present, unparsed.
+    #[cfg(feature = "std")]
+    fn take_raw(&mut self, keys: &[&str]) -> Option<Box<RawValue>> {
+        keys.iter().find_map(|key| self.fields.remove(*key))
+    }
+
+    /// Remove and parse `key`; `null` counts as absent.
+    fn take<T: DeserializeOwned>(&mut self, key: &str) -> Result<Option<T>, E> {
+        match self.fields.remove(key) {
+            Some(raw) if raw.get() != "null" => serde_json::from_str(raw.get())
+                .map(Some)
+                .map_err(|err| E::custom(format!("invalid `{key}`: {err}"))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Remove `key` as text; `null` counts as absent.
+    fn take_json(&mut self, key: &str) -> Option<RawJson> {
+        self.fields
+            .remove(key)
+            .filter(|raw| raw.get() != "null")
+            .map(RawJson)
+    }
+
+    /// The fields not taken, parsed.
+    fn into_extra(self) -> Result<BTreeMap<String, Value>, E> {
+        self.fields
+            .into_iter()
+            .map(|(key, raw)| {
+                serde_json::from_str(raw.get())
+                    .map(|value| (key, value))
+                    .map_err(E::custom)
+            })
+            .collect()
+    }
+}
+
+/// MCP tool schema.
+///
+/// Schemas are kept as [`RawJson`] and parsed only when read.
+#[derive(Clone, Debug, Serialize)]
+pub struct Tool {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "inputSchema")]
+    pub input_schema: Option<RawJson>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "outputSchema")]
+    pub output_schema: Option<RawJson>,
+    /// Secrets the tool needs, from `secret_requirements` or
+    /// `secretRequirements`. Only with the `std` feature; without it the
+    /// entry is kept in `extra` as sent.
+    #[cfg(feature = "std")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub secret_requirements: Vec<SecretRequirement>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+impl<'de> Deserialize<'de> for Tool {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut fields = RawFields::deserialize(deserializer)?;
+        #[cfg(feature = "std")]
+        let secret_requirements =
+            match fields.take_raw(&["secret_requirements", "secretRequirements"]) {
+                Some(raw) => deserialize_secret_requirements(
+                    &mut serde_json::Deserializer::from_str(raw.get()),
+                )
+                .map_err(D::Error::custom)?,
+                None => Vec::new(),
+            };
+        Ok(Tool {
+            name: fields
+                .take("name")?
+                .ok_or_else(|| D::Error::missing_field("name"))?,
+            description: fields.take("description")?,
+            input_schema: fields.take_json("inputSchema"),
+            output_schema: fields.take_json("outputSchema"),
+            #[cfg(feature = "std")]
+            secret_requirements,
+            extra: fields.into_extra()?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ToolListResult {
+    pub tools: Vec<Tool>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+impl<'de> Deserialize<'de> for ToolListResult {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut fields = RawFields::deserialize(deserializer)?;
+        Ok(ToolListResult {
+            tools: fields
+                .take("tools")?
+                .ok_or_else(|| D::Error::missing_field("tools"))?,
+            extra: fields.into_extra()?,
+        })
+    }
+}
+
+/// Tool call result payload.
+///
+/// `structured_content` is kept as [`RawJson`] and parsed only when read.
+#[derive(Clone, Debug, Serialize)]
+pub struct CallToolResult {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub content: Vec<Content>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "isError")]
+    pub is_error: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "structuredContent")]
+    pub structured_content: Option<RawJson>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+impl<'de> Deserialize<'de> for CallToolResult {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut fields = RawFields::deserialize(deserializer)?;
+        Ok(CallToolResult {
+            content: fields.take("content")?.unwrap_or_default(),
+            is_error: fields.take("isError")?,
+            structured_content: fields.take_json("structuredContent"),
+            extra: fields.into_extra()?,
+        })
+    }
+}
+
+/// Initialize request parameters; kept intentionally loose for compatibility.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InitializeParams {
+    #[serde(rename = "protocol")]
+    pub protocol_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub capabilities: BTreeMap<String, Value>,
+    #[serde(default, flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+#[cfg(feature = "std")]
+fn deserialize_secret_requirements<'de, D>(
+    deserializer: D,
+) -> Result<Vec<SecretRequirement>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    let arr = match value {
+        Value::Array(items) => items,
+        other => {
+            return Err(serde::de::Error::custom(format!(
+                "secret_requirements must be an array, got {other}"
+            )));
+        }
+    };
+
+    let mut out = Vec::with_capacity(arr.len());
+    for item in arr {
+        match item {
+            Value::String(key) => {
+                let mut req = SecretRequirement::default();
+                req.key = SecretKey::from(key.as_str());
+                out.push(req);
+            }
+            other => {
+                let req: SecretRequirement = serde_json::from_value(other).map_err(|err| {
+                    serde::de::Error::custom(format!("invalid secret requirement: {err}"))
+                })?;
+                out.push(req);
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Helper to build an initialize request with the correct revision string.
+pub fn initialize_request_with_revision(
+    id: Value,
+    revision: ProtocolRevision,
+    params_extra: BTreeMap<String, Value>,
+) -> McpRequest<InitializeParams> {
+    McpRequest {
+        jsonrpc: jsonrpc_version(),
+        id,
+        method: "initialize".to_string(),
+        params: Some(InitializeParams {
+            protocol_version: revision.as_str().to_string(),
+            client: None,
+            capabilities: BTreeMap::new(),
+            extra: params_extra,
+        }),
+        extra: BTreeMap::new(),
+    }
+}
+
+/// Next id from a process-wide counter, used by request builders without an explicit id.
+///
+/// Integers counting up from 1, like `greentic_mcp::RequestIdGenerator::sequential`.
+pub fn next_request_id() -> Value {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    Value::from(NEXT.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Fluent builder for arbitrary MCP requests.
+///
+/// ```
+/// use greentic_mcp_types::RequestBuilder;
+///
+/// let request = RequestBuilder::new("resources/read")
+///     .param("uri", "file:///notes.md")
+///     .build();
+/// assert_eq!(request.method, "resources/read");
+/// ```
+#[derive(Clone, Debug)]
+pub struct RequestBuilder {
+    id: Option<Value>,
+    method: String,
+    params: Map<String, Value>,
+}
+
+impl RequestBuilder {
+    pub fn new(method: impl Into<String>) -> Self {
+        Self {
+            id: None,
+            method: method.into(),
+            params: Map::new(),
+        }
+    }
+
+    /// Use `id` instead of an auto-generated one.
+    pub fn id(mut self, id: impl Into<Value>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set a top-level parameter.
+    pub fn param(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.params.insert(key.into(), value.into());
+        self
+    }
+
+    /// Ask for `notifications/progress` updates tagged with `token`.
+    pub fn progress_token(self, token: impl Into<Value>) -> Self {
+        self.meta("progressToken", token)
+    }
+
+    /// Set a `_meta` entry.
+    pub fn meta(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        let meta = self
+            .params
+            .entry("_meta")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !meta.is_object() {
+            *meta = Value::Object(Map::new());
+        }
+        if let Value::Object(meta) = meta {
+            meta.insert(key.into(), value.into());
+        }
+        self
+    }
+
+    pub fn build(self) -> McpRequest {
+        McpRequest {
+            jsonrpc: jsonrpc_version(),
+            id: self.id.unwrap_or_else(next_request_id),
+            method: self.method,
+            params: (!self.params.is_empty()).then_some(Value::Object(self.params)),
+            extra: BTreeMap::new(),
+        }
+    }
+}
+
+/// Builder for `tools/call` requests.
+///
+/// ```
+/// use greentic_mcp_types::CallToolRequest;
+///
+/// let request = CallToolRequest::new("forecast")
+///     .arg("city", "AMS")
+///     .arg("days", 3)
+///     .progress_token("forecast-1")
+///     .build();
+/// assert_eq!(request.method, "tools/call");
+/// ```
+#[derive(Clone, Debug)]
+pub struct CallToolRequest {
+    builder: RequestBuilder,
+    arguments: Value,
+}
+
+impl CallToolRequest {
+    pub fn new(tool: impl Into<String>) -> Self {
+        Self {
+            builder: RequestBuilder::new("tools/call").param("name", Value::String(tool.into())),
+            arguments: Value::Object(Map::new()),
+        }
+    }
+
+    /// Set a single argument.
+    pub fn arg(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        if !self.arguments.is_object() {
+            self.arguments = Value::Object(Map::new());
+        }
+        if let Value::Object(arguments) = &mut self.arguments {
+            arguments.insert(key.into(), value.into());
+        }
+        self
+    }
+
+    /// Replace all arguments with `arguments`.
+    pub fn arguments(mut self, arguments: Value) -> Self {
+        self.arguments = arguments;
+        self
+    }
+
+    pub fn id(mut self, id: impl Into<Value>) -> Self {
+        self.builder = self.builder.id(id);
+        self
+    }
+
+    pub fn progress_token(mut self, token: impl Into<Value>) -> Self {
+        self.builder = self.builder.progress_token(token);
+        self
+    }
+
+    pub fn meta(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.builder = self.builder.meta(key, value);
+        self
+    }
+
+    pub fn build(self) -> McpRequest {
+        self.builder.param("arguments", self.arguments).build()
+    }
+}
+
+/// Builder for `tools/list` requests.
+#[derive(Clone, Debug)]
+pub struct ListToolsRequest {
+    builder: RequestBuilder,
+}
+
+impl ListToolsRequest {
+    pub fn new() -> Self {
+        Self {
+            builder: RequestBuilder::new("tools/list"),
+        }
+    }
+
+    /// Continue from the `nextCursor` of a previous page.
+    pub fn cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.builder = self.builder.param("cursor", Value::String(cursor.into()));
+        self
+    }
+
+    pub fn id(mut self, id: impl Into<Value>) -> Self {
+        self.builder = self.builder.id(id);
+        self
+    }
+
+    pub fn build(self) -> McpRequest {
+        self.builder.build()
+    }
+}
+
+impl Default for ListToolsRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<RequestBuilder> for McpRequest {
+    fn from(builder: RequestBuilder) -> Self {
+        builder.build()
+    }
+}
+
+impl From<CallToolRequest> for McpRequest {
+    fn from(builder: CallToolRequest) -> Self {
+        builder.build()
+    }
+}
+
+impl From<ListToolsRequest> for McpRequest {
+    fn from(builder: ListToolsRequest) -> Self {
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_protocol_revision_from_str() {
+        assert_eq!(
+            ProtocolRevision::from_str("2025-03-26").unwrap(),
+            ProtocolRevision::V2025_03_26
+        );
+        assert_eq!(
+            ProtocolRevision::from_str("2025-06-18").unwrap(),
+            ProtocolRevision::V2025_06_18
+        );
+        assert!(ProtocolRevision::from_str("2024-01-01").is_err());
+    }
+
+    #[test]
+    fn initialize_requests_carry_revision() {
+        let new_req = initialize_request_with_revision(
+            json!(1),
+            ProtocolRevision::V2025_06_18,
+            BTreeMap::new(),
+        );
+        let old_req = initialize_request_with_revision(
+            json!(1),
+            ProtocolRevision::V2025_03_26,
+            BTreeMap::new(),
+        );
+
+        let new_proto = new_req.params.as_ref().unwrap().protocol_version.clone();
+        let old_proto = old_req.params.as_ref().unwrap().protocol_version.clone();
+        assert_ne!(new_proto, old_proto);
+        assert_eq!(new_proto, "2025-06-18");
+        assert_eq!(old_proto, "2025-03-26");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn tool_and_call_results_capture_optional_fields() {
+        let list_json = json!({
+            "tools": [
+                {
+                    "name": "echo",
+                    "description": "echo message",
+                    "inputSchema": {"type": "object"},
+                    "outputSchema": {"type": "string"},
+                    "secret_requirements": ["api-key"],
+                    "x-extra": true
+                }
+            ],
+            "meta": "ok"
+        });
+
+        let parsed: ToolListResult = serde_json::from_value(list_json).expect("parse tool list");
+        assert_eq!(parsed.tools.len(), 1);
+        let tool = &parsed.tools[0];
+        assert_eq!(
+            tool.output_schema.as_ref().map(RawJson::to_value),
+            Some(json!({"type": "string"}))
+        );
+        assert!(tool.extra.contains_key("x-extra"));
+        assert_eq!(tool.secret_requirements.len(), 1);
+        assert_eq!(tool.secret_requirements[0].key.as_str(), "api-key");
+        assert!(parsed.extra.contains_key("meta"));
+
+        let call_json = json!({
+            "content": [
+                {"type": "text", "text": "hello"}
+            ],
+            "isError": false,
+            "structuredContent": {"kind": "object", "value": {"message": "ok"}},
+            "requestId": "abc123"
+        });
+
+        let call: CallToolResult = serde_json::from_value(call_json).expect("parse call result");
+        assert_eq!(call.is_error, Some(false));
+        assert!(call.structured_content.is_some());
+        assert!(call.extra.contains_key("requestId"));
+        assert_eq!(call.content.len(), 1);
+        assert_eq!(call.content[0].text.as_deref(), Some("hello"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn schemas_and_structured_content_stay_unparsed_text() {
+        let raw = r#"{"tools":[{"name":"echo","inputSchema": {"type" : "object"},"outputSchema":null,"secretRequirements":["api-key"]}],"nextCursor":"c2"}"#;
+        let parsed: ToolListResult = serde_json::from_str(raw).expect("parse tool list");
+        let tool = &parsed.tools[0];
+        let schema = tool.input_schema.as_ref().expect("input schema");
+        assert_eq!(schema.get(), r#"{"type" : "object"}"#);
+        assert_eq!(schema.to_value(), json!({"type": "object"}));
+        assert!(tool.output_schema.is_none());
+        assert_eq!(tool.secret_requirements[0].key.as_str(), "api-key");
+        assert_eq!(parsed.extra.get("nextCursor"), Some(&json!("c2")));
+        let encoded = serde_json::to_string(&parsed).expect("encode");
+        assert!(encoded.contains(r#""inputSchema":{"type" : "object"}"#));
+
+        let call: CallToolResult =
+            serde_json::from_str(r#"{"structuredContent": [1, 2],"isError":false}"#)
+                .expect("parse call result");
+        let structured = call.structured_content.expect("structured content");
+        assert_eq!(structured.get(), "[1, 2]");
+        assert_eq!(structured.parse::<Vec<u8>>().expect("rows"), [1, 2]);
+
+        let err = serde_json::from_str::<Tool>(r#"{"description":"no name"}"#)
+            .expect_err("name is required");
+        assert!(err.to_string().contains("missing field `name`"), "{err}");
+    }
+
+    #[cfg(not(feature = "std"))]
+    #[test]
+    fn secret_requirements_stay_in_extra_without_std() {
+        let tool: Tool =
+            serde_json::from_str(r#"{"name":"echo","secretRequirements":["api-key"]}"#)
+                .expect("parse tool");
+        assert_eq!(
+            tool.extra.get("secretRequirements"),
+            Some(&json!(["api-key"]))
+        );
+        let encoded = serde_json::to_value(&tool).expect("encode");
+        assert_eq!(encoded["secretRequirements"], json!(["api-key"]));
+    }
+
+    #[test]
+    fn builders_produce_well_formed_requests() {
+        let call = CallToolRequest::new("forecast")
+            .arg("city", "AMS")
+            .arg("days", 3)
+            .progress_token("tok-1")
+            .id(42)
+            .build();
+        assert_eq!(
+            serde_json::to_value(&call).unwrap(),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 42,
+                "method": "tools/call",
+                "params": {
+                    "name": "forecast",
+                    "arguments": { "city": "AMS", "days": 3 },
+                    "_meta": { "progressToken": "tok-1" }
+                }
+            })
+        );
+
+        let first = ListToolsRequest::new().build();
+        let second = ListToolsRequest::new().cursor("page-2").build();
+        assert!(first.params.is_none());
+        assert_eq!(second.params.unwrap()["cursor"], "page-2");
+        assert_ne!(first.id, second.id, "ids are generated per request");
+    }
+}
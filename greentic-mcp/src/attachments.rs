@@ -0,0 +1,466 @@
+//! Signed, expiring download URLs for large tool-result content.
+//!
+//! Router components return images, audio, and embedded resources inline, and
+//! [`crate::convert::call_result_from_wit`] carries them into the protocol
+//! result as base64 or text. A host bridging routers over HTTP passes each
+//! result through [`Attachments::offload`] before writing it to the SSE
+//! stream: content over [`AttachmentConfig::inline_limit`] is kept in memory
+//! and replaced by a `resource_link` whose URI points at
+//! [`AttachmentConfig::base_url`], signed with [`AttachmentConfig::key`] and
+//! valid for [`AttachmentConfig::ttl`]. The host's download endpoint hands the
+//! requested URL to [`Attachments::fetch`], which checks the signature and
+//! expiry before returning the bytes.
+//!
+//! The bytes live in an [`AttachmentStore`]. The default
+//! [`InMemoryAttachmentStore`] holds them in process, so a URL only resolves
+//! on the replica that issued it; behind a load balancer, route downloads
+//! back to that replica or give every replica a shared store with
+//! [`Attachments::with_store`].
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use hmac::{Hmac, Mac};
+use serde_json::{Value, json};
+use sha2::Sha256;
+use thiserror::Error;
+use tracing::warn;
+use url::Url;
+
+use crate::protocol::{CallToolResult, Content};
+use crate::secret::Secret;
+use crate::session::generate_id;
+use crate::types::McpError;
+
+/// Where and for how long offloaded content is served.
+#[derive(Clone, Debug)]
+pub struct AttachmentConfig {
+    /// Download endpoint; attachment ids are appended as a path segment.
+    pub base_url: Url,
+    /// Key the download URLs are signed with.
+    pub key: Secret,
+    /// How long a download URL stays valid.
+    pub ttl: Duration,
+    /// Largest encoded content, in bytes, left inline.
+    pub inline_limit: usize,
+}
+
+impl AttachmentConfig {
+    pub fn new(base_url: Url, key: impl Into<Secret>) -> Self {
+        Self {
+            base_url,
+            key: key.into(),
+            ttl: Duration::from_secs(15 * 60),
+            inline_limit: 64 * 1024,
+        }
+    }
+}
+
+/// Content served from a download URL.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attachment {
+    pub bytes: Vec<u8>,
+    pub mime_type: Option<String>,
+}
+
+/// Why [`Attachments::fetch`] refused a URL.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AttachmentError {
+    #[error("attachment URL is malformed or its signature does not match")]
+    InvalidSignature,
+    #[error("attachment URL has expired")]
+    Expired,
+    #[error("attachment not found")]
+    NotFound,
+    #[error("attachment store failed: {0}")]
+    Store(String),
+}
+
+/// Storage for offloaded content, keyed by attachment id.
+pub trait AttachmentStore: Send + Sync {
+    /// Keep `attachment` until `expires`, in seconds since the Unix epoch.
+    /// `Ok(false)` when the store will not hold it, which leaves the content
+    /// inline.
+    fn put(&self, id: &str, attachment: Attachment, expires: u64) -> Result<bool, McpError>;
+
+    /// Fetch an attachment; unknown and expired ids yield `None`.
+    fn get(&self, id: &str) -> Result<Option<Attachment>, McpError>;
+}
+
+/// Process-local attachment store holding at most a number of bytes; the
+/// oldest attachments go first past it.
+pub struct InMemoryAttachmentStore {
+    max_stored_bytes: usize,
+    stored: Mutex<Stored>,
+}
+
+#[derive(Default)]
+struct Stored {
+    by_id: HashMap<String, (Attachment, u64)>,
+    /// Ids in the order they were stored, which is also expiry order.
+    order: VecDeque<String>,
+    bytes: usize,
+}
+
+impl Stored {
+    fn evict_oldest(&mut self) -> bool {
+        let Some(id) = self.order.pop_front() else {
+            return false;
+        };
+        if let Some((attachment, _)) = self.by_id.remove(&id) {
+            self.bytes -= attachment.bytes.len();
+        }
+        true
+    }
+
+    fn evict_expired(&mut self, now: u64) {
+        while let Some(id) = self.order.front() {
+            match self.by_id.get(id) {
+                Some((_, expires)) if *expires > now => break,
+                _ => {
+                    self.evict_oldest();
+                }
+            }
+        }
+    }
+}
+
+impl InMemoryAttachmentStore {
+    pub fn new(max_stored_bytes: usize) -> Self {
+        Self {
+            max_stored_bytes,
+            stored: Mutex::default(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Stored> {
+        self.stored
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for InMemoryAttachmentStore {
+    fn default() -> Self {
+        Self::new(256 * 1024 * 1024)
+    }
+}
+
+impl AttachmentStore for InMemoryAttachmentStore {
+    fn put(&self, id: &str, attachment: Attachment, expires: u64) -> Result<bool, McpError> {
+        let size = attachment.bytes.len();
+        if size > self.max_stored_bytes {
+            return Ok(false);
+        }
+        let mut stored = self.lock();
+        stored.evict_expired(now_secs());
+        while stored.bytes + size > self.max_stored_bytes && stored.evict_oldest() {}
+        stored.bytes += size;
+        stored.order.push_back(id.to_string());
+        stored.by_id.insert(id.to_string(), (attachment, expires));
+        Ok(true)
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Attachment>, McpError> {
+        let mut stored = self.lock();
+        stored.evict_expired(now_secs());
+        Ok(stored
+            .by_id
+            .get(id)
+            .map(|(attachment, _)| attachment.clone()))
+    }
+}
+
+/// Offloads content to an [`AttachmentStore`] behind signed URLs.
+pub struct Attachments {
+    config: AttachmentConfig,
+    store: Arc<dyn AttachmentStore>,
+}
+
+impl Attachments {
+    /// Offload into an [`InMemoryAttachmentStore`] of the default size.
+    pub fn new(config: AttachmentConfig) -> Self {
+        Self {
+            config,
+            store: Arc::new(InMemoryAttachmentStore::default()),
+        }
+    }
+
+    /// Offload into `store` instead, e.g. one shared by every replica.
+    pub fn with_store(mut self, store: Arc<dyn AttachmentStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Replace content over the inline limit with links to signed URLs.
+    /// Returns how many content items were offloaded.
+    pub fn offload(&self, result: &mut CallToolResult) -> usize {
+        let mut offloaded = 0;
+        for content in &mut result.content {
+            if let Some(link) = self.offload_content(content) {
+                *content = link;
+                offloaded += 1;
+            }
+        }
+        offloaded
+    }
+
+    /// Check `url` and return the attachment it points at.
+    pub fn fetch(&self, url: &Url) -> Result<Attachment, AttachmentError> {
+        let id = url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .ok_or(AttachmentError::InvalidSignature)?;
+        let query = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+        };
+        let expires = query("expires")
+            .and_then(|expires| expires.parse::<u64>().ok())
+            .ok_or(AttachmentError::InvalidSignature)?;
+        let signature = query("sig")
+            .and_then(|signature| hex::decode(signature).ok())
+            .ok_or(AttachmentError::InvalidSignature)?;
+        self.mac(id, expires)
+            .verify_slice(&signature)
+            .map_err(|_| AttachmentError::InvalidSignature)?;
+        if expires <= now_secs() {
+            return Err(AttachmentError::Expired);
+        }
+        self.store
+            .get(id)
+            .map_err(|err| AttachmentError::Store(err.to_string()))?
+            .ok_or(AttachmentError::NotFound)
+    }
+
+    fn offload_content(&self, content: &Content) -> Option<Content> {
+        let string = |value: Option<&Value>| value.and_then(Value::as_str).map(str::to_owned);
+        let (attachment, name, uri) = match content.kind.as_str() {
+            "image" | "audio" => {
+                let data = content.data.as_ref()?.as_str()?;
+                if data.len() <= self.config.inline_limit {
+                    return None;
+                }
+                let attachment = Attachment {
+                    bytes: STANDARD.decode(data).ok()?,
+                    mime_type: string(content.extra.get("mimeType")),
+                };
+                (attachment, content.kind.clone(), None)
+            }
+            "resource" => {
+                let resource = content.extra.get("resource")?;
+                let bytes = match (resource.get("blob"), resource.get("text")) {
+                    (Some(Value::String(blob)), _) if blob.len() > self.config.inline_limit => {
+                        STANDARD.decode(blob).ok()?
+                    }
+                    (_, Some(Value::String(text))) if text.len() > self.config.inline_limit => {
+                        text.clone().into_bytes()
+                    }
+                    _ => return None,
+                };
+                let uri = string(resource.get("uri"));
+                let attachment = Attachment {
+                    bytes,
+                    mime_type: string(resource.get("mimeType")),
+                };
+                let name = uri.clone().unwrap_or_else(|| "resource".into());
+                (attachment, name, uri)
+            }
+            _ => return None,
+        };
+
+        let size = attachment.bytes.len();
+        let link_mime = attachment.mime_type.clone();
+        let url = self.store(attachment)?;
+        let mut link = serde_json::Map::new();
+        link.insert("uri".into(), json!(url.as_str()));
+        link.insert("name".into(), json!(name));
+        link.insert("size".into(), json!(size));
+        if let Some(mime_type) = link_mime {
+            link.insert("mimeType".into(), json!(mime_type));
+        }
+        if let Some(uri) = uri {
+            link.insert("_meta".into(), json!({ "originalUri": uri }));
+        }
+        if let Some(annotations) = content.extra.get("annotations") {
+            link.insert("annotations".into(), annotations.clone());
+        }
+        Some(Content {
+            kind: "resource_link".into(),
+            text: None,
+            data: None,
+            extra: link.into_iter().collect(),
+        })
+    }
+
+    /// Keep `attachment` and return its signed URL; `None` when the store
+    /// does not take it.
+    fn store(&self, attachment: Attachment) -> Option<Url> {
+        let expires = now_secs() + self.config.ttl.as_secs().max(1);
+        let id = generate_id();
+        let mut url = self.config.base_url.clone();
+        url.path_segments_mut().ok()?.pop_if_empty().push(&id);
+        url.query_pairs_mut()
+            .append_pair("expires", &expires.to_string())
+            .append_pair("sig", &hex::encode(self.sign(&id, expires)));
+        match self.store.put(&id, attachment, expires) {
+            Ok(true) => Some(url),
+            Ok(false) => None,
+            Err(err) => {
+                warn!(error = %err, "keeping content inline: attachment store failed");
+                None
+            }
+        }
+    }
+
+    /// HMAC-SHA256 over the id and expiry under the configured key.
+    fn mac(&self, id: &str, expires: u64) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.config.key.expose().as_bytes())
+            .expect("HMAC takes keys of any length");
+        mac.update(format!("{id}\n{expires}").as_bytes());
+        mac
+    }
+
+    fn sign(&self, id: &str, expires: u64) -> Vec<u8> {
+        self.mac(id, expires).finalize().into_bytes().to_vec()
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attachments(ttl: Duration) -> Attachments {
+        let mut config = AttachmentConfig::new(
+            Url::parse("https://gateway.example/attachments/").expect("url"),
+            "signing-key",
+        );
+        config.ttl = ttl;
+        config.inline_limit = 16;
+        Attachments::new(config)
+    }
+
+    fn result(content: Value) -> CallToolResult {
+        serde_json::from_value(json!({ "content": content })).expect("result")
+    }
+
+    #[test]
+    fn large_content_becomes_signed_links() {
+        let attachments = attachments(Duration::from_secs(60));
+        let png = vec![7u8; 64];
+        let mut result = result(json!([
+            { "type": "text", "text": "chart below" },
+            { "type": "image", "mimeType": "image/png", "data": STANDARD.encode(&png) },
+            { "type": "image", "mimeType": "image/png", "data": "AAAA" },
+            { "type": "resource", "resource": {
+                "uri": "file:///report.csv", "mimeType": "text/csv", "text": "a,b\n".repeat(10)
+            } },
+        ]));
+
+        assert_eq!(attachments.offload(&mut result), 2);
+        let kinds: Vec<_> = result.content.iter().map(|c| c.kind.as_str()).collect();
+        assert_eq!(kinds, ["text", "resource_link", "image", "resource_link"]);
+
+        let image_url =
+            Url::parse(result.content[1].extra["uri"].as_str().expect("uri")).expect("signed url");
+        assert!(
+            image_url
+                .as_str()
+                .starts_with("https://gateway.example/attachments/")
+        );
+        assert_eq!(result.content[1].extra["size"], json!(64));
+        let image = attachments.fetch(&image_url).expect("fetch");
+        assert_eq!(image.bytes, png);
+        assert_eq!(image.mime_type.as_deref(), Some("image/png"));
+
+        let report = &result.content[3].extra;
+        assert_eq!(report["name"], json!("file:///report.csv"));
+        let url = Url::parse(report["uri"].as_str().expect("uri")).expect("signed url");
+        assert_eq!(
+            attachments.fetch(&url).expect("fetch").bytes,
+            "a,b\n".repeat(10).into_bytes()
+        );
+    }
+
+    #[test]
+    fn tampered_and_expired_urls_are_refused() {
+        let attachments = attachments(Duration::from_secs(60));
+        let mut result = result(json!([
+            { "type": "audio", "mimeType": "audio/wav", "data": STANDARD.encode([1u8; 32]) },
+        ]));
+        attachments.offload(&mut result);
+        let url = Url::parse(result.content[0].extra["uri"].as_str().expect("uri")).expect("url");
+
+        let mut tampered = url.clone();
+        let expires = now_secs() + 3600;
+        let signature = url
+            .query_pairs()
+            .find(|(key, _)| key == "sig")
+            .map(|(_, sig)| sig.into_owned())
+            .expect("sig");
+        tampered
+            .query_pairs_mut()
+            .clear()
+            .append_pair("expires", &expires.to_string())
+            .append_pair("sig", &signature);
+        assert_eq!(
+            attachments.fetch(&tampered),
+            Err(AttachmentError::InvalidSignature)
+        );
+
+        let mut expired = url.clone();
+        let id = url
+            .path_segments()
+            .and_then(|mut s| s.next_back())
+            .expect("id");
+        let past = now_secs() - 1;
+        expired
+            .query_pairs_mut()
+            .clear()
+            .append_pair("expires", &past.to_string())
+            .append_pair("sig", &hex::encode(attachments.sign(id, past)));
+        assert_eq!(attachments.fetch(&expired), Err(AttachmentError::Expired));
+
+        // Signed by someone else's key.
+        let other = Attachments::new(AttachmentConfig::new(
+            Url::parse("https://gateway.example/attachments/").expect("url"),
+            "another-key",
+        ));
+        assert_eq!(other.fetch(&url), Err(AttachmentError::InvalidSignature));
+    }
+
+    #[test]
+    fn oldest_attachments_make_room_for_new_ones() {
+        let mut config = AttachmentConfig::new(
+            Url::parse("https://gateway.example/a").expect("url"),
+            "signing-key",
+        );
+        config.inline_limit = 0;
+        let attachments =
+            Attachments::new(config).with_store(Arc::new(InMemoryAttachmentStore::new(100)));
+        let urls: Vec<Url> = (0..3)
+            .map(|_| {
+                let mut result = result(json!([
+                    { "type": "image", "mimeType": "image/png", "data": STANDARD.encode([0u8; 40]) },
+                ]));
+                attachments.offload(&mut result);
+                Url::parse(result.content[0].extra["uri"].as_str().expect("uri")).expect("url")
+            })
+            .collect();
+
+        assert!(urls[0].as_str().starts_with("https://gateway.example/a/"));
+        assert_eq!(attachments.fetch(&urls[0]), Err(AttachmentError::NotFound));
+        attachments.fetch(&urls[1]).expect("kept");
+        attachments.fetch(&urls[2]).expect("kept");
+    }
+}
@@ -1,16 +1,19 @@
 use crate::protocol::{AuthMode, McpServerConfig, ProtocolRevision};
+use crate::secret::Secret;
 use std::collections::HashMap;
 use std::sync::Mutex;
 use tracing::warn;
 
 /// Minimal OAuth broker interface for obtaining scoped tokens.
+///
+/// Tokens are returned as [`Secret`]s so they stay masked in logs and errors.
 pub trait OAuthBroker: Send + Sync {
     fn fetch_token(
         &self,
         provider: &str,
         resource: &str,
         scopes: &[String],
-    ) -> Result<String, String>;
+    ) -> Result<Secret, String>;
 }
 
 type TokenCacheKey = (String, String, Vec<String>);
@@ -18,7 +21,7 @@ type TokenCacheKey = (String, String, Vec<String>);
 /// Simple cache wrapper to avoid repeated broker calls for the same tuple.
 pub struct CachedBroker<B: OAuthBroker> {
     broker: B,
-    cache: Mutex<HashMap<TokenCacheKey, String>>,
+    cache: Mutex<HashMap<TokenCacheKey, Secret>>,
 }
 
 impl<B: OAuthBroker> CachedBroker<B> {
@@ -34,7 +37,7 @@ impl<B: OAuthBroker> CachedBroker<B> {
         provider: &str,
         resource: &str,
         scopes: &[String],
-    ) -> Result<String, String> {
+    ) -> Result<Secret, String> {
         let key: TokenCacheKey = (provider.to_string(), resource.to_string(), scopes.to_vec());
         if let Some(tok) = self.cache.lock().unwrap().get(&key) {
             return Ok(tok.clone());
@@ -50,7 +53,7 @@ pub fn fetch_oauth_token<B: OAuthBroker>(
     broker: &B,
     server: &McpServerConfig,
     revision: ProtocolRevision,
-) -> Result<String, String> {
+) -> Result<Secret, String> {
     let auth_mode = server.resolved_auth_mode();
     if auth_mode != AuthMode::OAuth {
         return Err("auth_mode is not OAuth".into());
@@ -102,19 +105,22 @@ mod tests {
             provider: &str,
             resource: &str,
             scopes: &[String],
-        ) -> Result<String, String> {
+        ) -> Result<Secret, String> {
             self.calls.lock().unwrap().push((
                 provider.to_string(),
                 resource.to_string(),
                 scopes.to_vec(),
             ));
-            Ok(self.token.clone())
+            Ok(Secret::new(self.token.clone()))
         }
     }
 
     fn server(resource: Option<&str>, rev: ProtocolRevision) -> McpServerConfig {
         McpServerConfig {
             name: "svc".into(),
+            transport: None,
+            url: None,
+            command: None,
             protocol_revision: Some(rev),
             auth_mode: AuthMode::OAuth,
             oauth: Some(OAuthConfig {
@@ -125,6 +131,7 @@ mod tests {
             }),
             api_key: None,
             bearer_token: None,
+            sanitizer: None,
             extra: Default::default(),
         }
     }
@@ -148,7 +155,7 @@ mod tests {
         };
         let server = server(Some("https://svc"), ProtocolRevision::V2025_06_18);
         let token = fetch_oauth_token(&mock, &server, ProtocolRevision::V2025_06_18).unwrap();
-        assert_eq!(token, "tok");
+        assert_eq!(token.expose(), "tok");
         let calls = mock.calls.lock().unwrap();
         assert_eq!(calls.len(), 1);
         assert_eq!(calls[0].0, "auth0");
@@ -164,6 +171,6 @@ mod tests {
         };
         let server = server(None, ProtocolRevision::V2025_03_26);
         let token = fetch_oauth_token(&mock, &server, ProtocolRevision::V2025_03_26).unwrap();
-        assert_eq!(token, "tok");
+        assert_eq!(token.expose(), "tok");
     }
 }
@@ -1,14 +1,21 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
-use anyhow::Result;
-use clap::{Parser, Subcommand};
-use greentic_mcp::compose::compose_router_with_bundled_adapter;
+use anyhow::{Context, Result, anyhow, bail};
+use clap::{ArgGroup, Parser, Subcommand};
+use greentic_mcp::compose::{AdapterVariant, compose_router};
+use greentic_mcp::protocol::{McpServerConfig, TransportKind};
+use greentic_mcp::{
+    HttpTransport, NodeManifest, RemoteRouter, RemoteTransport, StdioSandbox, StdioTransport,
+    load_server_configs,
+};
+use serde_json::Value;
 
 #[derive(Parser)]
 #[command(
     name = "greentic-mcp",
     version,
-    about = "Compose MCP router components with the bundled adapter"
+    about = "Compose MCP router components and exercise remote MCP servers"
 )]
 struct Cli {
     #[command(subcommand)]
@@ -19,6 +26,8 @@ struct Cli {
 enum Commands {
     /// Compose a router component into the bundled adapter.
     Compose(ComposeArgs),
+    /// List or call the tools of a remote MCP server.
+    Client(ClientArgs),
 }
 
 #[derive(Parser)]
@@ -32,15 +41,166 @@ struct ComposeArgs {
     /// Path to wasm-tools (defaults to GREENTIC_MCP_WASM_TOOLS or wasm-tools in PATH).
     #[arg(long, value_name = "PATH")]
     wasm_tools: Option<PathBuf>,
+    /// Compose with the slim adapter instead of the bundled one.
+    #[arg(long)]
+    slim: bool,
+    /// Path to the slim adapter component (defaults to GREENTIC_MCP_SLIM_ADAPTER); implies --slim.
+    #[arg(long, value_name = "PATH")]
+    slim_adapter: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+#[command(group(
+    ArgGroup::new("action")
+        .required(true)
+        .args(["list_tools", "call", "node_manifest"])
+))]
+struct ClientArgs {
+    /// Server configuration file (JSON or YAML), as read by `load_server_configs`.
+    #[arg(long, value_name = "PATH")]
+    server_config: PathBuf,
+    /// Server to use when the configuration lists several.
+    #[arg(long, value_name = "NAME")]
+    server: Option<String>,
+    /// Print the server's tool catalog.
+    #[arg(long)]
+    list_tools: bool,
+    /// Print a Greentic flow node manifest stub for the server's tools.
+    #[arg(long)]
+    node_manifest: bool,
+    /// Call a tool and print its result.
+    #[arg(long, value_name = "TOOL")]
+    call: Option<String>,
+    /// Arguments of the call, as a JSON object.
+    #[arg(long, value_name = "JSON", default_value = "{}", requires = "call")]
+    input: String,
+    /// Request timeout for http servers, in seconds.
+    #[arg(long, value_name = "SECS", default_value_t = 30)]
+    timeout: u64,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
     match cli.command {
-        Commands::Compose(args) => compose_router_with_bundled_adapter(
-            &args.router,
-            &args.output,
-            args.wasm_tools.as_deref(),
+        Commands::Compose(args) => {
+            let adapter = if args.slim || args.slim_adapter.is_some() {
+                AdapterVariant::Slim(args.slim_adapter)
+            } else {
+                AdapterVariant::Bundled
+            };
+            compose_router(
+                &args.router,
+                &args.output,
+                &adapter,
+                args.wasm_tools.as_deref(),
+            )
+        }
+        Commands::Client(args) => client(args),
+    }
+}
+
+fn client(args: ClientArgs) -> Result<()> {
+    let server = select_server(&args)?;
+    let transport = server.transport.or(if server.url.is_some() {
+        Some(TransportKind::Http)
+    } else if server.command.is_some() {
+        Some(TransportKind::Stdio)
+    } else {
+        None
+    });
+    match transport {
+        Some(TransportKind::Http) => {
+            let url = server.url.clone().unwrap_or_default();
+            let transport = HttpTransport::new(url, &server, Duration::from_secs(args.timeout))?;
+            run_client(RemoteRouter::new(server, transport), &args)
+        }
+        Some(TransportKind::Stdio) => {
+            let command = server.command.clone().unwrap_or_default();
+            let transport =
+                StdioTransport::spawn(command, stdio_args(&server)?, &StdioSandbox::default())?;
+            run_client(RemoteRouter::new(server, transport), &args)
+        }
+        None => bail!("server `{}` sets neither url nor command", server.name),
+    }
+}
+
+/// The configured server named by `--server`, or the only one in the file.
+fn select_server(args: &ClientArgs) -> Result<McpServerConfig> {
+    let configs = load_server_configs(&args.server_config)
+        .with_context(|| format!("failed to load {}", args.server_config.display()))?;
+    if let Err(diagnostics) = McpServerConfig::validate_all(&configs) {
+        let report: Vec<String> = diagnostics
+            .iter()
+            .map(|d| format!("  {}: {}", d.pointer, d.message))
+            .collect();
+        bail!(
+            "invalid server configuration {}:\n{}",
+            args.server_config.display(),
+            report.join("\n")
+        );
+    }
+    let names = || {
+        configs
+            .iter()
+            .map(|config| config.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match &args.server {
+        Some(name) => configs
+            .iter()
+            .find(|config| &config.name == name)
+            .cloned()
+            .ok_or_else(|| {
+                anyhow!(
+                    "no server `{name}` in the configuration (have: {})",
+                    names()
+                )
+            }),
+        None if configs.len() == 1 => Ok(configs[0].clone()),
+        None if configs.is_empty() => bail!("the configuration lists no servers"),
+        None => bail!(
+            "the configuration lists several servers, pick one with --server ({})",
+            names()
         ),
     }
 }
+
+/// Arguments of a stdio server, from an optional `args` array in its configuration.
+fn stdio_args(server: &McpServerConfig) -> Result<Vec<String>> {
+    let Some(args) = server.extra.get("args") else {
+        return Ok(Vec::new());
+    };
+    serde_json::from_value(args.clone()).with_context(|| {
+        format!(
+            "`args` of server `{}` must be an array of strings",
+            server.name
+        )
+    })
+}
+
+fn run_client<T: RemoteTransport>(router: RemoteRouter<T>, args: &ClientArgs) -> Result<()> {
+    let output = match &args.call {
+        Some(tool) => {
+            let input: Value =
+                serde_json::from_str(&args.input).context("--input must be valid JSON")?;
+            if !input.is_object() {
+                bail!("--input must be a JSON object");
+            }
+            router.call_tool(tool, input)
+        }
+        None if args.node_manifest => router.list_tools().and_then(|tools| {
+            Ok(serde_json::to_value(NodeManifest::from_tools(
+                router.name(),
+                &tools,
+            ))?)
+        }),
+        None => router
+            .list_tools()
+            .and_then(|tools| Ok(serde_json::to_value(tools)?)),
+    };
+    let shutdown = router.shutdown();
+    println!("{}", serde_json::to_string_pretty(&output?)?);
+    shutdown?;
+    Ok(())
+}
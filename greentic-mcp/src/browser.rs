@@ -0,0 +1,543 @@
+//! MCP client for browsers and web workers (`wasm32-unknown-unknown`).
+//!
+//! The native client blocks on a thread per call; a browser has one thread and
+//! an event loop, so this module speaks the same protocol asynchronously over
+//! `fetch` ([`FetchTransport`], Streamable HTTP) or a WebSocket
+//! ([`WebSocketTransport`]). [`BrowserClient`] runs the `initialize` handshake
+//! and exchanges requests with the types from [`crate::protocol`], so browser
+//! tooling and the host share one wire format.
+//!
+//! Build with `--no-default-features --features browser`. There is no call
+//! timeout here: abort from JavaScript if a server hangs.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use futures_channel::oneshot;
+use js_sys::{Function, Promise};
+use serde_json::{Value, json};
+use tracing::debug;
+use url::Url;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    CloseEvent, Headers, MessageEvent, Request, RequestInit, RequestMode, Response, WebSocket,
+    Window, WorkerGlobalScope,
+};
+
+use crate::codec;
+use crate::ids::RequestIdGenerator;
+use crate::protocol::{
+    AuthMode, CallToolRequest, CallToolResult, ListToolsRequest, McpRequest, McpResponse,
+    McpServerConfig, RpcError, Tool, ToolListResult, initialize_request_with_revision,
+};
+use crate::secret::{MASK, Secret, redact};
+use crate::session::SESSION_HEADER;
+use crate::types::McpError;
+
+/// Asynchronous counterpart of [`crate::RemoteTransport`].
+///
+/// Transports live on the page's single thread, so futures are not `Send`.
+#[allow(async_fn_in_trait)]
+pub trait BrowserTransport {
+    /// Send `message`; returns the response for requests and `None` for
+    /// notifications.
+    async fn send(&self, message: &Value) -> Result<Option<Value>, McpError>;
+
+    /// End the session with the server.
+    async fn close(&self) -> Result<(), McpError> {
+        Ok(())
+    }
+}
+
+/// Streamable HTTP over the `fetch` API of the window or worker.
+///
+/// The server must allow the page's origin through CORS, including the
+/// `MCP-Protocol-Version`, `Mcp-Session-Id`, and any auth headers, and must
+/// expose `Mcp-Session-Id` for sessions to work. Credentials are scrubbed from
+/// every error the transport returns, along with any password in the URL.
+pub struct FetchTransport {
+    url: String,
+    /// `url` with its password masked, for error messages.
+    display_url: String,
+    headers: Vec<(String, String)>,
+    /// Headers carrying credentials.
+    credentials: Vec<(String, Secret)>,
+    /// Raw credential values scrubbed from error messages.
+    secrets: Vec<Secret>,
+    session_id: RefCell<Option<String>>,
+}
+
+impl FetchTransport {
+    /// Build a transport for `url`, applying API-key or bearer auth from `server`.
+    ///
+    /// OAuth servers need a token obtained by the page and passed with
+    /// [`FetchTransport::with_bearer_token`].
+    pub fn new(url: impl Into<String>, server: &McpServerConfig) -> Self {
+        let url = url.into();
+        let mut display_url = url.clone();
+        let mut secrets = Vec::new();
+        if let Ok(mut parsed) = Url::parse(&url)
+            && let Some(password) = parsed.password().map(str::to_owned)
+        {
+            secrets.push(Secret::new(password));
+            if parsed.set_password(Some(MASK)).is_ok() {
+                display_url = parsed.to_string();
+            }
+        }
+        let mut transport = Self {
+            url,
+            display_url,
+            headers: vec![(
+                "MCP-Protocol-Version".into(),
+                server.resolved_protocol_revision().as_str().into(),
+            )],
+            credentials: Vec::new(),
+            secrets,
+            session_id: RefCell::new(None),
+        };
+        match server.resolved_auth_mode() {
+            AuthMode::ApiKey => {
+                if let Some(key) = &server.api_key {
+                    transport = transport.with_credential("X-API-Key", key.clone(), key.clone());
+                }
+            }
+            AuthMode::BearerToken => {
+                if let Some(token) = &server.bearer_token {
+                    transport = transport.with_bearer_token(token.clone());
+                }
+            }
+            AuthMode::OAuth | AuthMode::None => {}
+        }
+        transport
+    }
+
+    /// Send `Authorization: Bearer <token>` with every message.
+    pub fn with_bearer_token(self, token: impl Into<Secret>) -> Self {
+        let token = token.into();
+        let header = Secret::new(format!("Bearer {}", token.expose()));
+        self.with_credential("Authorization", header, token)
+    }
+
+    fn with_credential(mut self, name: &str, value: Secret, raw: Secret) -> Self {
+        self.credentials.push((name.into(), value));
+        self.secrets.push(raw);
+        self
+    }
+
+    /// Transient failure with credentials scrubbed from the message.
+    fn transient(&self, message: String) -> McpError {
+        McpError::Transient(self.display_url.clone(), redact(&message, &self.secrets))
+    }
+
+    /// Headers for a request, including the session once one was assigned.
+    fn request_headers(&self) -> Result<Headers, McpError> {
+        let headers = Headers::new().map_err(|err| self.transient(js_error(err)))?;
+        let session = self.session_id.borrow().clone();
+        let session = session.map(|id| (SESSION_HEADER, id));
+        let plain = self.headers.iter().map(|(k, v)| (k.as_str(), v.clone()));
+        let secret = self
+            .credentials
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.expose().to_string()));
+        for (name, value) in plain.chain(secret).chain(session) {
+            headers.set(name, &value).map_err(|_| {
+                McpError::InvalidInput(format!("`{name}` is not a valid header value"))
+            })?;
+        }
+        Ok(headers)
+    }
+
+    /// Run `method` against the endpoint and wait for the response headers.
+    async fn fetch(
+        &self,
+        method: &str,
+        headers: Headers,
+        body: Option<String>,
+    ) -> Result<Response, McpError> {
+        let init = RequestInit::new();
+        init.set_method(method);
+        init.set_mode(RequestMode::Cors);
+        init.set_headers(&headers);
+        if let Some(body) = body {
+            init.set_body(&JsValue::from_str(&body));
+        }
+        let request = Request::new_with_str_and_init(&self.url, &init)
+            .map_err(|err| McpError::InvalidInput(redact(&js_error(err), &self.secrets)))?;
+        let response = JsFuture::from(global_fetch(&request)?)
+            .await
+            .map_err(|err| self.transient(js_error(err)))?;
+        response.dyn_into::<Response>().map_err(|_| {
+            McpError::Internal("fetch resolved to something other than a Response".into())
+        })
+    }
+
+    /// POST `message`, tracking the session and rejecting error statuses.
+    async fn post(&self, message: &Value) -> Result<Response, McpError> {
+        let headers = self.request_headers()?;
+        for (name, value) in [
+            ("Accept", "application/json, text/event-stream"),
+            ("Content-Type", "application/json"),
+        ] {
+            headers
+                .set(name, value)
+                .map_err(|err| self.transient(js_error(err)))?;
+        }
+        let response = self
+            .fetch("POST", headers, Some(serde_json::to_string(message)?))
+            .await?;
+        if let Some(session) = header(&response, SESSION_HEADER) {
+            *self.session_id.borrow_mut() = Some(session);
+        }
+
+        let status = response.status();
+        if status >= 500 {
+            return Err(self.transient(format!("server responded {status}")));
+        }
+        if !response.ok() {
+            return Err(McpError::ExecutionFailed(format!(
+                "`{}` responded {status}",
+                self.display_url
+            )));
+        }
+        Ok(response)
+    }
+}
+
+impl BrowserTransport for FetchTransport {
+    async fn send(&self, message: &Value) -> Result<Option<Value>, McpError> {
+        let response = self.post(message).await?;
+        if message.get("id").is_none() {
+            return Ok(None);
+        }
+
+        let is_sse = header(&response, "Content-Type")
+            .is_some_and(|value| value.starts_with("text/event-stream"));
+        let text = response
+            .text()
+            .map_err(|err| self.transient(js_error(err)))?;
+        let body = JsFuture::from(text)
+            .await
+            .map_err(|err| self.transient(js_error(err)))?
+            .as_string()
+            .unwrap_or_default();
+        if is_sse {
+            return Ok(codec::sse_response(&body, &message["id"]));
+        }
+        Ok(Some(codec::from_str(&body)?))
+    }
+
+    /// Terminate the session with `DELETE`, as Streamable HTTP asks clients to.
+    ///
+    /// Servers that do not let clients end sessions answer 405, which is fine.
+    async fn close(&self) -> Result<(), McpError> {
+        if self.session_id.borrow().is_none() {
+            return Ok(());
+        }
+        let headers = self.request_headers()?;
+        self.session_id.borrow_mut().take();
+
+        let response = self.fetch("DELETE", headers, None).await?;
+        let status = response.status();
+        if response.ok() || status == 405 {
+            Ok(())
+        } else {
+            Err(McpError::ExecutionFailed(format!(
+                "`{}` responded {status} to session termination",
+                self.display_url
+            )))
+        }
+    }
+}
+
+/// `fetch` on whichever global this runs in: a window or a worker.
+fn global_fetch(request: &Request) -> Result<Promise, McpError> {
+    let global = js_sys::global();
+    if let Some(window) = global.dyn_ref::<Window>() {
+        return Ok(window.fetch_with_request(request));
+    }
+    if let Some(worker) = global.dyn_ref::<WorkerGlobalScope>() {
+        return Ok(worker.fetch_with_request(request));
+    }
+    Err(McpError::Internal(
+        "fetch is only available in a window or a worker".into(),
+    ))
+}
+
+fn header(response: &Response, name: &str) -> Option<String> {
+    response.headers().get(name).ok().flatten()
+}
+
+/// Responses awaited on a [`WebSocketTransport`], by serialized request id.
+type Pending = Rc<RefCell<HashMap<String, oneshot::Sender<Value>>>>;
+
+/// JSON-RPC over a WebSocket, one message per text frame.
+///
+/// Responses are matched to requests by id, so several calls can be in flight.
+/// Messages from the server that answer no pending request, such as
+/// notifications, are dropped. When the socket closes, pending calls fail
+/// with [`McpError::Transient`].
+pub struct WebSocketTransport {
+    url: String,
+    socket: WebSocket,
+    pending: Pending,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_close: Closure<dyn FnMut(CloseEvent)>,
+}
+
+impl WebSocketTransport {
+    /// Open a socket to `url` and wait until it is connected.
+    pub async fn connect(url: impl Into<String>) -> Result<Self, McpError> {
+        let url = url.into();
+        let socket = WebSocket::new(&url).map_err(|err| McpError::InvalidInput(js_error(err)))?;
+        let opened = Promise::new(&mut |resolve: Function, reject: Function| {
+            socket.set_onopen(Some(&resolve));
+            socket.set_onerror(Some(&reject));
+        });
+        let result = JsFuture::from(opened).await;
+        socket.set_onopen(None);
+        socket.set_onerror(None);
+        if result.is_err() {
+            return Err(McpError::Transient(
+                url,
+                "WebSocket connection failed".into(),
+            ));
+        }
+
+        let pending = Pending::default();
+        let inbox = Rc::clone(&pending);
+        let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            let Some(text) = event.data().as_string() else {
+                debug!("ignoring binary WebSocket frame");
+                return;
+            };
+            let Ok(message) = codec::from_str::<Value>(&text) else {
+                debug!("ignoring malformed WebSocket frame");
+                return;
+            };
+            let waiter = message
+                .get("id")
+                .and_then(|id| inbox.borrow_mut().remove(&id.to_string()));
+            match waiter {
+                Some(waiter) => {
+                    let _ = waiter.send(message);
+                }
+                None => debug!("ignoring unsolicited WebSocket message"),
+            }
+        });
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+        let orphans = Rc::clone(&pending);
+        let on_close = Closure::<dyn FnMut(CloseEvent)>::new(move |_: CloseEvent| {
+            // Dropping the senders fails every pending call.
+            orphans.borrow_mut().clear();
+        });
+        socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+
+        Ok(Self {
+            url,
+            socket,
+            pending,
+            _on_message: on_message,
+            _on_close: on_close,
+        })
+    }
+
+    fn transient(&self, message: impl Into<String>) -> McpError {
+        McpError::Transient(self.url.clone(), message.into())
+    }
+}
+
+impl BrowserTransport for WebSocketTransport {
+    async fn send(&self, message: &Value) -> Result<Option<Value>, McpError> {
+        if self.socket.ready_state() != WebSocket::OPEN {
+            return Err(self.transient("WebSocket is not open"));
+        }
+        let response = match message.get("id") {
+            Some(id) => {
+                let (sender, receiver) = oneshot::channel();
+                self.pending.borrow_mut().insert(id.to_string(), sender);
+                Some((id.to_string(), receiver))
+            }
+            None => None,
+        };
+        if let Err(err) = self.socket.send_with_str(&serde_json::to_string(message)?) {
+            if let Some((id, _)) = &response {
+                self.pending.borrow_mut().remove(id);
+            }
+            return Err(self.transient(js_error(err)));
+        }
+
+        match response {
+            Some((_, receiver)) => receiver
+                .await
+                .map(Some)
+                .map_err(|_| self.transient("WebSocket closed before the response arrived")),
+            None => Ok(None),
+        }
+    }
+
+    async fn close(&self) -> Result<(), McpError> {
+        self.socket
+            .close()
+            .map_err(|err| self.transient(js_error(err)))
+    }
+}
+
+impl Drop for WebSocketTransport {
+    fn drop(&mut self) {
+        // The handlers are about to be freed; detach them before the socket
+        // can call into them again.
+        self.socket.set_onmessage(None);
+        self.socket.set_onclose(None);
+        let _ = self.socket.close();
+    }
+}
+
+/// Message of a JavaScript exception, or its debug form for other values.
+fn js_error(err: JsValue) -> String {
+    match err.dyn_ref::<js_sys::Error>() {
+        Some(error) => error.message().into(),
+        None => err.as_string().unwrap_or_else(|| format!("{err:?}")),
+    }
+}
+
+/// MCP client session over a [`BrowserTransport`].
+///
+/// The browser counterpart of [`crate::RemoteRouter`] without its host-side
+/// policies: permissions, sanitizing, and caching are up to the page.
+pub struct BrowserClient<T: BrowserTransport> {
+    server: McpServerConfig,
+    transport: T,
+    ids: RequestIdGenerator,
+    closed: Cell<bool>,
+}
+
+impl<T: BrowserTransport> BrowserClient<T> {
+    /// Run the `initialize` handshake with `server` over `transport`.
+    pub async fn connect(server: McpServerConfig, transport: T) -> Result<Self, McpError> {
+        let client = Self {
+            server,
+            transport,
+            ids: RequestIdGenerator::sequential(),
+            closed: Cell::new(false),
+        };
+        let request = initialize_request_with_revision(
+            client.ids.next_id(),
+            client.server.resolved_protocol_revision(),
+            BTreeMap::new(),
+        );
+        let response = client
+            .transport
+            .send(&serde_json::to_value(&request)?)
+            .await?
+            .ok_or_else(|| McpError::ExecutionFailed("no response to initialize".into()))?;
+        let response: McpResponse = serde_json::from_value(response)?;
+        if let Some(err) = response.error {
+            return Err(McpError::ExecutionFailed(format!(
+                "server `{}` rejected initialize: {}",
+                client.server.name, err.message
+            )));
+        }
+
+        client
+            .transport
+            .send(&json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/initialized",
+            }))
+            .await?;
+        Ok(client)
+    }
+
+    pub fn server(&self) -> &McpServerConfig {
+        &self.server
+    }
+
+    /// Every tool the server offers, following pagination.
+    pub async fn list_tools(&self) -> Result<Vec<Tool>, McpError> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut request = ListToolsRequest::new();
+            if let Some(cursor) = &cursor {
+                request = request.cursor(cursor.as_str());
+            }
+            let result = self
+                .request(request.id(self.ids.next_id()).build())
+                .await?
+                .map_err(|err| {
+                    McpError::ExecutionFailed(format!(
+                        "server `{}` failed to list tools: {}",
+                        self.server.name, err.message
+                    ))
+                })?;
+            let page: ToolListResult = serde_json::from_value(result)?;
+            tools.extend(page.tools);
+            cursor = page
+                .extra
+                .get("nextCursor")
+                .and_then(Value::as_str)
+                .map(str::to_owned);
+            if cursor.is_none() {
+                return Ok(tools);
+            }
+        }
+    }
+
+    /// Call `tool`, returning its result or the server's JSON-RPC error.
+    pub async fn call_tool(
+        &self,
+        tool: &str,
+        arguments: Value,
+    ) -> Result<Result<CallToolResult, RpcError>, McpError> {
+        let request = CallToolRequest::new(tool)
+            .arguments(arguments)
+            .id(self.ids.next_id())
+            .build();
+        match self.request(request).await? {
+            Ok(result) => Ok(Ok(serde_json::from_value(result)?)),
+            Err(err) => Ok(Err(err)),
+        }
+    }
+
+    /// Send any request, returning the result or the JSON-RPC error.
+    pub async fn request(&self, request: McpRequest) -> Result<Result<Value, RpcError>, McpError> {
+        if self.closed.get() {
+            return Err(McpError::ExecutionFailed(format!(
+                "client for `{}` is shut down",
+                self.server.name
+            )));
+        }
+        let response = self
+            .transport
+            .send(&serde_json::to_value(&request)?)
+            .await?
+            .ok_or_else(|| {
+                McpError::ExecutionFailed(format!("no response to `{}`", request.method))
+            })?;
+        let response: McpResponse = serde_json::from_value(response)?;
+        if response.id != request.id {
+            return Err(McpError::ExecutionFailed(format!(
+                "server `{}` answered `{}` request {} with id {}",
+                self.server.name, request.method, request.id, response.id
+            )));
+        }
+        match (response.result, response.error) {
+            (_, Some(err)) => Ok(Err(err)),
+            (Some(result), None) => Ok(Ok(result)),
+            (None, None) => Ok(Ok(Value::Null)),
+        }
+    }
+
+    /// Stop accepting requests and close the transport, ending the session.
+    /// Shutting down twice is a no-op.
+    pub async fn shutdown(&self) -> Result<(), McpError> {
+        if self.closed.replace(true) {
+            return Ok(());
+        }
+        self.transport.close().await
+    }
+}
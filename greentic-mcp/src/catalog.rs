@@ -0,0 +1,116 @@
+//! Tool catalog diffing for MCP `tools/list` results.
+//!
+//! Uses the same compatibility rules as the `greentic-mcp-exec diff` command,
+//! so a CI gate comparing a remote server's catalog against a recorded one
+//! classifies changes exactly like a comparison of two router components.
+
+use greentic_mcp_exec::catalog_diff::{self, CatalogTool};
+use serde_json::{Value, json};
+
+pub use greentic_mcp_exec::catalog_diff::{CatalogDiff, Change, Impact, ToolChanges};
+
+use crate::protocol::{RawJson, Tool, ToolListResult};
+
+/// A protocol [`Tool`] seen through [`CatalogTool`]; both are foreign to this crate.
+struct Listed<'a>(&'a Tool);
+
+impl CatalogTool for Listed<'_> {
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn title(&self) -> Option<&str> {
+        self.0.extra.get("title").and_then(Value::as_str)
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.0.description.as_deref()
+    }
+
+    fn annotations(&self) -> Option<String> {
+        self.0.extra.get("annotations").map(Value::to_string)
+    }
+
+    fn input_schema(&self) -> Value {
+        // MCP requires an input schema; servers that omit it accept any object.
+        self.0
+            .input_schema
+            .as_ref()
+            .map_or_else(|| json!({ "type": "object" }), RawJson::to_value)
+    }
+
+    fn output_schema(&self) -> Option<Value> {
+        self.0.output_schema.as_ref().map(RawJson::to_value)
+    }
+}
+
+/// Diff two `tools/list` results, classifying breaking changes.
+pub fn diff_tool_lists(old: &ToolListResult, new: &ToolListResult) -> CatalogDiff {
+    diff_tools(&old.tools, &new.tools)
+}
+
+/// Diff two tool catalogs, classifying breaking changes.
+pub fn diff_tools(old: &[Tool], new: &[Tool]) -> CatalogDiff {
+    let old: Vec<Listed> = old.iter().map(Listed).collect();
+    let new: Vec<Listed> = new.iter().map(Listed).collect();
+    catalog_diff::diff_catalogs(&old, &new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(value: Value) -> ToolListResult {
+        serde_json::from_value(value).expect("tool list")
+    }
+
+    #[test]
+    fn classifies_tools_list_changes() {
+        let old = list(json!({
+            "tools": [
+                {
+                    "name": "forecast",
+                    "inputSchema": {
+                        "type": "object",
+                        "required": ["city"],
+                        "properties": { "city": { "type": "string" } }
+                    }
+                },
+                { "name": "legacy" }
+            ]
+        }));
+        let new = list(json!({
+            "tools": [
+                {
+                    "name": "forecast",
+                    "title": "Forecast",
+                    "inputSchema": {
+                        "type": "object",
+                        "required": ["city"],
+                        "properties": {
+                            "city": { "type": "string" },
+                            "days": { "type": "integer" }
+                        }
+                    }
+                },
+                { "name": "alerts" }
+            ]
+        }));
+
+        let diff = diff_tool_lists(&old, &new);
+        assert_eq!(diff.added, ["alerts"]);
+        assert_eq!(diff.removed, ["legacy"]);
+        assert!(diff.breaking, "removing a tool is breaking");
+
+        let forecast = &diff.changed[0];
+        assert_eq!(forecast.tool, "forecast");
+        assert!(
+            forecast
+                .changes
+                .iter()
+                .all(|change| change.impact == Impact::NonBreaking)
+        );
+        assert!(forecast.changes.iter().any(|c| c.path == "input.days"));
+        assert!(forecast.changes.iter().any(|c| c.path == "title"));
+    }
+}
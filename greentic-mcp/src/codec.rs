@@ -0,0 +1,62 @@
+//! JSON decoding for the hot paths: transport frames, SSE events, and tool
+//! output from the executor, kept in one place so the transports decode alike.
+
+use serde::de::DeserializeOwned;
+#[cfg(any(feature = "native", feature = "browser"))]
+use serde_json::Value;
+
+/// Decode `bytes` as JSON.
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> serde_json::Result<T> {
+    serde_json::from_slice(bytes)
+}
+
+/// Decode `text` as JSON.
+pub fn from_str<T: DeserializeOwned>(text: &str) -> serde_json::Result<T> {
+    from_slice(text.as_bytes())
+}
+
+/// Pick the JSON-RPC response matching `id` out of an SSE body.
+#[cfg(any(feature = "native", feature = "browser"))]
+pub(crate) fn sse_response(body: &str, id: &Value) -> Option<Value> {
+    body.lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .filter_map(|data| from_str::<Value>(data.trim()).ok())
+        .find(|message| message.get("id") == Some(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn decodes_like_serde_json() {
+        let message = json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "result": {
+                "tools": [{ "name": "echo", "inputSchema": { "type": "object" } }],
+                "ratio": 0.25,
+                "text": "caf\u{e9} \u{1f600}",
+            },
+        });
+        let raw = message.to_string();
+        assert_eq!(from_str::<Value>(&raw).expect("decode"), message);
+        assert_eq!(
+            from_slice::<Value>(raw.as_bytes()).expect("decode"),
+            message
+        );
+
+        let err = from_str::<Value>(r#"{"id": 1,"#).expect_err("truncated");
+        let expected = serde_json::from_str::<Value>(r#"{"id": 1,"#).expect_err("truncated");
+        assert_eq!(err.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn picks_matching_response_from_sse_body() {
+        let body = "event: message\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\"}\n\n\
+                    event: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":7,\"result\":{}}\n\n";
+        let response = sse_response(body, &json!(7)).expect("response");
+        assert_eq!(response["id"], json!(7));
+    }
+}
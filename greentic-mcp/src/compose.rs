@@ -7,10 +7,31 @@ const ADAPTER_COMPONENT: &[u8] = include_bytes!("../assets/mcp_adapter_25_06_18.
 
 pub const ADAPTER_PROTOCOL: &str = "25.06.18";
 
+/// Adapter build a router is composed with.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum AdapterVariant {
+    /// The full adapter bundled with this crate.
+    #[default]
+    Bundled,
+    /// The slim adapter from `scripts/build_adapter.sh --slim`, at the given
+    /// path or else at `GREENTIC_MCP_SLIM_ADAPTER`.
+    Slim(Option<PathBuf>),
+}
+
 pub fn compose_router_with_bundled_adapter(
     router: &Path,
     output: &Path,
     wasm_tools: Option<&Path>,
+) -> Result<()> {
+    compose_router(router, output, &AdapterVariant::Bundled, wasm_tools)
+}
+
+/// Compose `router` with the `adapter` variant into `output`.
+pub fn compose_router(
+    router: &Path,
+    output: &Path,
+    adapter: &AdapterVariant,
+    wasm_tools: Option<&Path>,
 ) -> Result<()> {
     if !router.exists() {
         return Err(anyhow!("router component not found: {}", router.display()));
@@ -24,12 +45,20 @@ pub fn compose_router_with_bundled_adapter(
     }
 
     let wasm_tools = resolve_wasm_tools(wasm_tools)?;
-    let adapter_path = write_adapter_component()?;
+    // Keeps the bundled adapter's temp file alive until wasm-tools is done.
+    let bundled;
+    let adapter_path = match adapter {
+        AdapterVariant::Bundled => {
+            bundled = write_adapter_component()?;
+            bundled.path().to_path_buf()
+        }
+        AdapterVariant::Slim(path) => resolve_slim_adapter(path.as_deref())?,
+    };
 
     let output = output.to_path_buf();
     let status = Command::new(&wasm_tools)
         .arg("compose")
-        .arg(adapter_path.path())
+        .arg(&adapter_path)
         .arg("-d")
         .arg(router)
         .arg("-o")
@@ -56,6 +85,28 @@ fn resolve_wasm_tools(wasm_tools: Option<&Path>) -> Result<PathBuf> {
     Ok(PathBuf::from("wasm-tools"))
 }
 
+fn resolve_slim_adapter(path: Option<&Path>) -> Result<PathBuf> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => match std::env::var("GREENTIC_MCP_SLIM_ADAPTER") {
+            Ok(path) if !path.trim().is_empty() => PathBuf::from(path),
+            _ => {
+                return Err(anyhow!(
+                    "no slim adapter given; build one with scripts/build_adapter.sh --slim \
+                     and pass its path or set GREENTIC_MCP_SLIM_ADAPTER"
+                ));
+            }
+        },
+    };
+    if !path.exists() {
+        return Err(anyhow!(
+            "slim adapter component not found: {}",
+            path.display()
+        ));
+    }
+    Ok(path)
+}
+
 fn write_adapter_component() -> Result<tempfile::NamedTempFile> {
     let mut file = tempfile::Builder::new()
         .prefix("mcp_adapter_")
@@ -1,6 +1,9 @@
 use std::fs;
 use std::path::Path;
 
+use serde_json::Value;
+
+use crate::protocol::McpServerConfig;
 use crate::types::{McpError, ToolMapConfig};
 
 /// Load a [`ToolMapConfig`] from JSON or YAML.
@@ -17,6 +20,126 @@ fn parse_tool_map_config(path: &Path, content: &str) -> Result<ToolMapConfig, Mc
     }
 }
 
+/// Load a list of [`McpServerConfig`]s from JSON or YAML, expanding
+/// environment variables in every string value (see [`interpolate_env`]).
+pub fn load_server_configs(path: &Path) -> Result<Vec<McpServerConfig>, McpError> {
+    let content = fs::read_to_string(path)?;
+    parse_server_configs(path, &content, &|name| std::env::var(name).ok())
+}
+
+fn parse_server_configs(
+    path: &Path,
+    content: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<Vec<McpServerConfig>, McpError> {
+    let mut document: Value = if is_json(path, content) {
+        serde_json::from_str(content)?
+    } else {
+        serde_yaml_bw::from_str(content)?
+    };
+    interpolate_with(&mut document, "", lookup)?;
+    Ok(serde_json::from_value(document)?)
+}
+
+/// Expand environment variable references in every string of `value`:
+///
+/// - `${VAR}` is replaced by the value of `VAR`, and fails when it is unset.
+/// - `${VAR:-default}` falls back to `default` when `VAR` is unset or empty.
+/// - `${VAR:?message}` fails with `message` when `VAR` is unset or empty.
+/// - `$${` is a literal `${`.
+///
+/// Errors name the JSON pointer of the offending string.
+pub fn interpolate_env(value: &mut Value) -> Result<(), McpError> {
+    interpolate_with(value, "", &|name| std::env::var(name).ok())
+}
+
+fn interpolate_with(
+    value: &mut Value,
+    pointer: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<(), McpError> {
+    match value {
+        Value::String(text) => {
+            *text = expand(text, lookup)
+                .map_err(|err| McpError::InvalidInput(format!("{pointer}: {err}")))?;
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                interpolate_with(item, &format!("{pointer}/{index}"), lookup)?;
+            }
+        }
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                let key = key.replace('~', "~0").replace('/', "~1");
+                interpolate_with(field, &format!("{pointer}/{key}"), lookup)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn expand(text: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(escaped) = after.strip_prefix("${") {
+            out.push_str("${");
+            rest = escaped;
+            continue;
+        }
+        let Some(reference) = after.strip_prefix('{') else {
+            out.push('$');
+            rest = after;
+            continue;
+        };
+        let end = reference
+            .find('}')
+            .ok_or_else(|| format!("unterminated `${{` in `{text}`"))?;
+        let expression = &reference[..end];
+        rest = &reference[end + 1..];
+
+        let (name, fallback) = match expression.find(':') {
+            Some(index) => (&expression[..index], Some(&expression[index..])),
+            None => (expression, None),
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("invalid environment variable name `{name}`"));
+        }
+        if let Some(fallback) = fallback
+            && !fallback.starts_with(":-")
+            && !fallback.starts_with(":?")
+        {
+            return Err(format!("unsupported expansion `${{{expression}}}`"));
+        }
+        let value = lookup(name);
+        let Some(fallback) = fallback else {
+            let value = value.ok_or_else(|| format!("environment variable `{name}` is not set"))?;
+            out.push_str(&value);
+            continue;
+        };
+        match value.filter(|value| !value.is_empty()) {
+            Some(value) => out.push_str(&value),
+            None => {
+                if let Some(default) = fallback.strip_prefix(":-") {
+                    out.push_str(default);
+                } else {
+                    let message = &fallback[2..];
+                    return Err(if message.is_empty() {
+                        format!("environment variable `{name}` is required")
+                    } else {
+                        format!("`{name}`: {message}")
+                    });
+                }
+            }
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
 fn is_json(path: &Path, content: &str) -> bool {
     if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
         if matches!(ext, "json") {
@@ -49,6 +172,54 @@ mod tests {
         assert_eq!(config.tools[0].name, "echo");
     }
 
+    #[test]
+    fn interpolates_server_configs() {
+        let lookup = |name: &str| match name {
+            "CRM_TOKEN" => Some("tok-1".to_string()),
+            "CRM_HOST" => Some("crm.example".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        };
+        let configs = parse_server_configs(
+            Path::new("servers.yaml"),
+            r#"
+- name: crm
+  url: https://${CRM_HOST}/mcp
+  bearer_token: ${CRM_TOKEN}
+  region: ${CRM_REGION:-eu} ${EMPTY} $${LITERAL} $5
+"#,
+            &lookup,
+        )
+        .unwrap();
+        assert_eq!(configs[0].url.as_deref(), Some("https://crm.example/mcp"));
+        assert_eq!(
+            configs[0].bearer_token.as_ref().map(|t| t.expose()),
+            Some("tok-1")
+        );
+        assert_eq!(configs[0].extra["region"], "eu  ${LITERAL} $5");
+
+        let err = parse_server_configs(
+            Path::new("servers.json"),
+            r#"[{"name": "crm", "api_key": "${CRM_KEY}"}]"#,
+            &lookup,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid input: /0/api_key: environment variable `CRM_KEY` is not set"
+        );
+
+        let mut value = serde_json::json!({"a/b": ["${EMPTY:?set EMPTY for crm}"]});
+        let err = interpolate_with(&mut value, "", &lookup).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid input: /a~1b/0: `EMPTY`: set EMPTY for crm"
+        );
+        assert!(expand("${UNCLOSED", &lookup).is_err());
+        assert!(expand("${BAD-NAME}", &lookup).is_err());
+        assert!(expand("${CRM_TOKEN:+x}", &lookup).is_err());
+    }
+
     #[test]
     fn parses_yaml() {
         let config = parse_tool_map_config(
@@ -0,0 +1,614 @@
+//! Conformance checks for vetting third-party MCP servers.
+//!
+//! [`run`] drives a server through any [`RemoteTransport`] with a fixed matrix
+//! of spec behaviors: initialize variants, ping, tool listing and pagination,
+//! JSON-RPC error codes, cancellation, and malformed input. It returns a
+//! [`ConformanceReport`] with one pass/fail/skip line per check, so a server
+//! can be assessed before it is onboarded behind a [`crate::RemoteRouter`].
+//!
+//! Tools are only called when [`ConformanceConfig::probe_tool`] names one that
+//! is safe to invoke; the checks that need a call are skipped otherwise.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::ids::RequestIdGenerator;
+use crate::protocol::{ProtocolRevision, RpcError};
+use crate::remote::RemoteTransport;
+use crate::types::McpError;
+
+const PARSE_OR_INVALID_REQUEST: [i64; 2] = [-32700, -32600];
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// What [`run`] sends to the server under test.
+#[derive(Clone, Debug)]
+pub struct ConformanceConfig {
+    /// Revision requested in the primary `initialize`.
+    pub revision: ProtocolRevision,
+    /// Tool that is safe to call with [`Self::probe_arguments`]; enables the
+    /// call, bad-argument, and cancellation checks.
+    pub probe_tool: Option<String>,
+    pub probe_arguments: Value,
+    /// Upper bound on `tools/list` pages followed before pagination fails.
+    pub max_pages: usize,
+}
+
+impl Default for ConformanceConfig {
+    fn default() -> Self {
+        Self {
+            revision: ProtocolRevision::default(),
+            probe_tool: None,
+            probe_arguments: json!({}),
+            max_pages: 100,
+        }
+    }
+}
+
+/// Outcome of a single check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    Skip,
+}
+
+impl CheckStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Fail => "FAIL",
+            CheckStatus::Skip => "SKIP",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CheckResult {
+    /// Dotted check name, e.g. `tools.pagination`.
+    pub name: &'static str,
+    pub status: CheckStatus,
+    /// What was observed, for failures and skips in particular.
+    pub detail: String,
+}
+
+/// Pass/fail report produced by [`run`]; `Display` renders one line per check.
+#[derive(Clone, Debug, Serialize)]
+pub struct ConformanceReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl ConformanceReport {
+    /// True when no check failed; skipped checks do not count against it.
+    pub fn passed(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks
+            .iter()
+            .filter(|check| check.status == CheckStatus::Fail)
+    }
+
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == status)
+            .count()
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(
+                f,
+                "{} {}: {}",
+                check.status.as_str(),
+                check.name,
+                check.detail
+            )?;
+        }
+        write!(
+            f,
+            "{} passed, {} failed, {} skipped",
+            self.count(CheckStatus::Pass),
+            self.count(CheckStatus::Fail),
+            self.count(CheckStatus::Skip)
+        )
+    }
+}
+
+type Outcome = (CheckStatus, String);
+type Check = fn(&Probe<'_>, &ConformanceConfig) -> Result<Outcome, McpError>;
+
+/// Checks in the order they run. The primary `initialize` comes after the
+/// malformed one and before the renegotiation attempt, so servers that reject
+/// a second `initialize` still get a session.
+const CHECKS: &[(&str, Check)] = &[
+    ("initialize.missing_params", initialize_missing_params),
+    (
+        "initialize.supported_revision",
+        initialize_supported_revision,
+    ),
+    ("initialize.unknown_revision", initialize_unknown_revision),
+    ("ping", ping),
+    ("tools.list", tools_list),
+    ("tools.pagination", tools_pagination),
+    ("tools.invalid_cursor", tools_invalid_cursor),
+    ("errors.unknown_method", unknown_method),
+    ("errors.unknown_tool", unknown_tool),
+    ("bad_inputs.missing_tool_name", missing_tool_name),
+    ("bad_inputs.malformed_request", malformed_request),
+    ("bad_inputs.invalid_arguments", invalid_arguments),
+    ("tools.call", call_probe_tool),
+    (
+        "cancellation.ignored_after_completion",
+        cancel_completed_request,
+    ),
+];
+
+/// Run the conformance matrix against the server behind `transport`.
+///
+/// Transport failures fail the check that hit them and the run moves on. The
+/// transport is left open; close it when done.
+pub fn run(transport: &dyn RemoteTransport, config: &ConformanceConfig) -> ConformanceReport {
+    let probe = Probe {
+        transport,
+        ids: RequestIdGenerator::sequential(),
+    };
+    let checks = CHECKS
+        .iter()
+        .map(|(name, check)| {
+            let (status, detail) = check(&probe, config)
+                .unwrap_or_else(|err| (CheckStatus::Fail, format!("transport error: {err}")));
+            CheckResult {
+                name,
+                status,
+                detail,
+            }
+        })
+        .collect();
+    ConformanceReport { checks }
+}
+
+struct Probe<'a> {
+    transport: &'a dyn RemoteTransport,
+    ids: RequestIdGenerator,
+}
+
+impl Probe<'_> {
+    /// Send a request and check the response envelope: JSON-RPC version, the
+    /// echoed id, and exactly one of `result` or `error`.
+    fn request(
+        &self,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<Result<Result<Value, RpcError>, String>, McpError> {
+        let id = self.ids.next_id();
+        let mut message = json!({ "jsonrpc": "2.0", "id": id, "method": method });
+        if let Some(params) = params {
+            message["params"] = params;
+        }
+        let Some(response) = self.transport.send(&message)? else {
+            return Ok(Err(format!("no response to `{method}`")));
+        };
+        Ok(parse_response(&response, &id))
+    }
+
+    fn notify(&self, method: &str, params: Value) -> Result<(), McpError> {
+        self.transport
+            .send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+            .map(drop)
+    }
+}
+
+fn parse_response(response: &Value, id: &Value) -> Result<Result<Value, RpcError>, String> {
+    if response.get("jsonrpc") != Some(&json!("2.0")) {
+        return Err(format!("response is not JSON-RPC 2.0: {response}"));
+    }
+    if response.get("id") != Some(id) {
+        return Err(format!(
+            "response id {} does not echo request id {id}",
+            response.get("id").unwrap_or(&Value::Null)
+        ));
+    }
+    match (response.get("result"), response.get("error")) {
+        (Some(result), None) => Ok(Ok(result.clone())),
+        (None, Some(error)) => serde_json::from_value(error.clone())
+            .map(Err)
+            .map_err(|err| format!("malformed error object: {err}")),
+        _ => Err("response must carry exactly one of `result` or `error`".into()),
+    }
+}
+
+fn pass(detail: impl Into<String>) -> Result<Outcome, McpError> {
+    Ok((CheckStatus::Pass, detail.into()))
+}
+
+fn fail(detail: impl Into<String>) -> Result<Outcome, McpError> {
+    Ok((CheckStatus::Fail, detail.into()))
+}
+
+fn skip(detail: impl Into<String>) -> Result<Outcome, McpError> {
+    Ok((CheckStatus::Skip, detail.into()))
+}
+
+/// Pass when the server answered with one of `codes`.
+fn expect_error(
+    reply: Result<Result<Value, RpcError>, String>,
+    codes: &[i64],
+) -> Result<Outcome, McpError> {
+    match reply {
+        Err(problem) => fail(problem),
+        Ok(Ok(result)) => fail(format!("expected an error, got result {result}")),
+        Ok(Err(err)) if codes.contains(&err.code) => pass(format!("error {}", err.code)),
+        Ok(Err(err)) => fail(format!(
+            "expected error code {codes:?}, got {}: {}",
+            err.code, err.message
+        )),
+    }
+}
+
+fn initialize_params(revision: &str) -> Value {
+    json!({
+        "protocolVersion": revision,
+        "capabilities": {},
+        "clientInfo": { "name": "greentic-mcp-conformance", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+fn initialize_missing_params(
+    probe: &Probe<'_>,
+    _: &ConformanceConfig,
+) -> Result<Outcome, McpError> {
+    expect_error(
+        probe.request("initialize", None)?,
+        &[INVALID_PARAMS, -32600],
+    )
+}
+
+fn initialize_supported_revision(
+    probe: &Probe<'_>,
+    config: &ConformanceConfig,
+) -> Result<Outcome, McpError> {
+    let requested = config.revision.as_str();
+    let result = match probe.request("initialize", Some(initialize_params(requested)))? {
+        Err(problem) => return fail(problem),
+        Ok(Err(err)) => return fail(format!("initialize rejected: {}", err.message)),
+        Ok(Ok(result)) => result,
+    };
+    probe.notify("notifications/initialized", json!({}))?;
+
+    let Some(version) = result.get("protocolVersion").and_then(Value::as_str) else {
+        return fail("result has no `protocolVersion`");
+    };
+    if !result.get("capabilities").is_some_and(Value::is_object) {
+        return fail("result has no `capabilities` object");
+    }
+    if !result
+        .pointer("/serverInfo/name")
+        .is_some_and(Value::is_string)
+    {
+        return fail("result has no `serverInfo.name`");
+    }
+    if version != requested {
+        return fail(format!(
+            "requested {requested}, server negotiated {version}"
+        ));
+    }
+    pass(format!("negotiated {version}"))
+}
+
+/// A client asking for a revision the server does not know must get an error
+/// or a counter-offer of a revision the server does support.
+fn initialize_unknown_revision(
+    probe: &Probe<'_>,
+    _: &ConformanceConfig,
+) -> Result<Outcome, McpError> {
+    match probe.request("initialize", Some(initialize_params("1999-01-01")))? {
+        Err(problem) => fail(problem),
+        Ok(Err(err)) => pass(format!("rejected with error {}", err.code)),
+        Ok(Ok(result)) => match result.get("protocolVersion").and_then(Value::as_str) {
+            Some("1999-01-01") => fail("server accepted an unknown revision"),
+            Some(version) => pass(format!("counter-offered {version}")),
+            None => fail("result has no `protocolVersion`"),
+        },
+    }
+}
+
+fn ping(probe: &Probe<'_>, _: &ConformanceConfig) -> Result<Outcome, McpError> {
+    match probe.request("ping", None)? {
+        Err(problem) => fail(problem),
+        Ok(Err(err)) => fail(format!("ping failed: {}", err.message)),
+        Ok(Ok(result)) if result.is_object() => pass("empty result"),
+        Ok(Ok(result)) => fail(format!("expected an object, got {result}")),
+    }
+}
+
+fn tools_list(probe: &Probe<'_>, _: &ConformanceConfig) -> Result<Outcome, McpError> {
+    let result = match probe.request("tools/list", None)? {
+        Err(problem) => return fail(problem),
+        Ok(Err(err)) => return fail(format!("tools/list failed: {}", err.message)),
+        Ok(Ok(result)) => result,
+    };
+    let Some(tools) = result.get("tools").and_then(Value::as_array) else {
+        return fail("result has no `tools` array");
+    };
+    for tool in tools {
+        let Some(name) = tool.get("name").and_then(Value::as_str) else {
+            return fail(format!("tool without a name: {tool}"));
+        };
+        if !tool.get("inputSchema").is_some_and(Value::is_object) {
+            return fail(format!("tool `{name}` has no `inputSchema` object"));
+        }
+    }
+    pass(format!("{} tools on the first page", tools.len()))
+}
+
+fn tools_pagination(probe: &Probe<'_>, config: &ConformanceConfig) -> Result<Outcome, McpError> {
+    let mut cursors = BTreeSet::new();
+    let mut cursor: Option<String> = None;
+    let mut tools = 0;
+    for page in 1..=config.max_pages {
+        let params = cursor.as_ref().map(|cursor| json!({ "cursor": cursor }));
+        let result = match probe.request("tools/list", params)? {
+            Err(problem) => return fail(problem),
+            Ok(Err(err)) => return fail(format!("page {page} failed: {}", err.message)),
+            Ok(Ok(result)) => result,
+        };
+        tools += result
+            .get("tools")
+            .and_then(Value::as_array)
+            .map_or(0, Vec::len);
+        cursor = match result.get("nextCursor") {
+            None | Some(Value::Null) => return pass(format!("{tools} tools over {page} pages")),
+            Some(Value::String(next)) => Some(next.clone()),
+            Some(other) => return fail(format!("`nextCursor` is not a string: {other}")),
+        };
+        if let Some(next) = &cursor
+            && !cursors.insert(next.clone())
+        {
+            return fail(format!("cursor `{next}` repeated on page {page}"));
+        }
+    }
+    fail(format!("still paginating after {} pages", config.max_pages))
+}
+
+fn tools_invalid_cursor(probe: &Probe<'_>, _: &ConformanceConfig) -> Result<Outcome, McpError> {
+    let params = json!({ "cursor": "greentic-conformance-invalid-cursor" });
+    expect_error(
+        probe.request("tools/list", Some(params))?,
+        &[INVALID_PARAMS],
+    )
+}
+
+fn unknown_method(probe: &Probe<'_>, _: &ConformanceConfig) -> Result<Outcome, McpError> {
+    expect_error(
+        probe.request("greentic/conformance-unknown", None)?,
+        &[METHOD_NOT_FOUND],
+    )
+}
+
+/// Unknown tools may be reported as a protocol error or a tool error result.
+fn unknown_tool(probe: &Probe<'_>, _: &ConformanceConfig) -> Result<Outcome, McpError> {
+    let params = json!({ "name": "greentic-conformance-missing-tool", "arguments": {} });
+    match probe.request("tools/call", Some(params))? {
+        Ok(Ok(result)) if result.get("isError") == Some(&Value::Bool(true)) => {
+            pass("tool error result")
+        }
+        reply => expect_error(reply, &[INVALID_PARAMS, METHOD_NOT_FOUND]),
+    }
+}
+
+fn missing_tool_name(probe: &Probe<'_>, _: &ConformanceConfig) -> Result<Outcome, McpError> {
+    let params = json!({ "arguments": {} });
+    expect_error(
+        probe.request("tools/call", Some(params))?,
+        &[INVALID_PARAMS],
+    )
+}
+
+/// A request without `method` must be rejected as an invalid request; HTTP
+/// servers may instead refuse it at the transport level.
+fn malformed_request(probe: &Probe<'_>, _: &ConformanceConfig) -> Result<Outcome, McpError> {
+    let id = probe.ids.next_id();
+    match probe.transport.send(&json!({ "jsonrpc": "2.0", "id": id })) {
+        Err(McpError::ExecutionFailed(message)) => pass(format!("refused by transport: {message}")),
+        Err(err) => Err(err),
+        Ok(None) => fail("no response to a malformed request"),
+        Ok(Some(response)) => {
+            // Servers cannot always recover the id of an invalid request.
+            let echoed = if response.get("id") == Some(&Value::Null) {
+                Value::Null
+            } else {
+                id
+            };
+            expect_error(
+                parse_response(&response, &echoed),
+                &PARSE_OR_INVALID_REQUEST,
+            )
+        }
+    }
+}
+
+fn invalid_arguments(probe: &Probe<'_>, config: &ConformanceConfig) -> Result<Outcome, McpError> {
+    let Some(tool) = &config.probe_tool else {
+        return skip("no probe tool configured");
+    };
+    let params = json!({ "name": tool, "arguments": "not-an-object" });
+    match probe.request("tools/call", Some(params))? {
+        Ok(Ok(result)) if result.get("isError") == Some(&Value::Bool(true)) => {
+            pass("tool error result")
+        }
+        reply => expect_error(reply, &[INVALID_PARAMS]),
+    }
+}
+
+fn call_probe_tool(probe: &Probe<'_>, config: &ConformanceConfig) -> Result<Outcome, McpError> {
+    let Some(tool) = &config.probe_tool else {
+        return skip("no probe tool configured");
+    };
+    let params = json!({ "name": tool, "arguments": config.probe_arguments });
+    let result = match probe.request("tools/call", Some(params))? {
+        Err(problem) => return fail(problem),
+        Ok(Err(err)) => return fail(format!("call failed: {}", err.message)),
+        Ok(Ok(result)) => result,
+    };
+    match result.get("content").and_then(Value::as_array) {
+        Some(content) if content.iter().all(|block| block.get("type").is_some()) => {
+            pass(format!("{} content blocks", content.len()))
+        }
+        Some(_) => fail("content block without a `type`"),
+        None => fail("result has no `content` array"),
+    }
+}
+
+/// Transports here are synchronous, so an in-flight request cannot be
+/// cancelled; instead this checks that a late `notifications/cancelled` is
+/// ignored and leaves the session usable, as the spec requires.
+fn cancel_completed_request(
+    probe: &Probe<'_>,
+    config: &ConformanceConfig,
+) -> Result<Outcome, McpError> {
+    let request_id = match &config.probe_tool {
+        Some(tool) => {
+            let id = probe.ids.next_id();
+            let params = json!({ "name": tool, "arguments": config.probe_arguments });
+            probe.transport.send(&json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "tools/call",
+                "params": params,
+            }))?;
+            id
+        }
+        None => json!("greentic-conformance-unknown-request"),
+    };
+    probe.notify(
+        "notifications/cancelled",
+        json!({ "requestId": request_id, "reason": "conformance check" }),
+    )?;
+    match probe.request("ping", None)? {
+        Ok(Ok(_)) => pass("session still usable after cancellation"),
+        Ok(Err(err)) => fail(format!("ping failed after cancellation: {}", err.message)),
+        Err(problem) => fail(problem),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Minimal server that follows the spec for everything the matrix probes,
+    /// except where `sloppy` makes it ignore errors.
+    #[derive(Default)]
+    struct FakeServer {
+        sloppy: bool,
+        sent: Mutex<Vec<Value>>,
+    }
+
+    impl RemoteTransport for FakeServer {
+        fn send(&self, message: &Value) -> Result<Option<Value>, McpError> {
+            self.sent.lock().unwrap().push(message.clone());
+            let Some(id) = message.get("id").cloned() else {
+                return Ok(None);
+            };
+            let error = |code: i64| -> Result<Result<Value, Value>, McpError> {
+                Ok(Err(json!({ "code": code, "message": "rejected" })))
+            };
+            let params = message.get("params");
+            let reply: Result<Result<Value, Value>, McpError> =
+                match message.get("method").and_then(Value::as_str) {
+                    None => error(-32600),
+                    Some("initialize") => match params
+                        .and_then(|params| params.get("protocolVersion"))
+                        .and_then(Value::as_str)
+                    {
+                        None => error(INVALID_PARAMS),
+                        Some(_) => Ok(Ok(json!({
+                            "protocolVersion": "2025-06-18",
+                            "capabilities": { "tools": {} },
+                            "serverInfo": { "name": "fake" },
+                        }))),
+                    },
+                    Some("ping") => Ok(Ok(json!({}))),
+                    Some("tools/list") => match params.and_then(|params| params.get("cursor")) {
+                        None => Ok(Ok(json!({
+                            "tools": [{ "name": "echo", "inputSchema": { "type": "object" } }],
+                            "nextCursor": "2",
+                        }))),
+                        Some(cursor) if cursor == "2" => Ok(Ok(json!({
+                            "tools": [{ "name": "sum", "inputSchema": { "type": "object" } }],
+                        }))),
+                        Some(_) if self.sloppy => Ok(Ok(json!({ "tools": [] }))),
+                        Some(_) => error(INVALID_PARAMS),
+                    },
+                    Some("tools/call") => match params.and_then(|params| params.get("name")) {
+                        Some(name) if name == "echo" => {
+                            if params
+                                .and_then(|p| p.get("arguments"))
+                                .is_some_and(Value::is_object)
+                            {
+                                Ok(Ok(json!({ "content": [{ "type": "text", "text": "hi" }] })))
+                            } else {
+                                error(INVALID_PARAMS)
+                            }
+                        }
+                        Some(_) => Ok(Ok(json!({ "content": [], "isError": true }))),
+                        None => error(INVALID_PARAMS),
+                    },
+                    Some(_) if self.sloppy => Ok(Ok(json!({}))),
+                    Some(_) => error(METHOD_NOT_FOUND),
+                };
+            let response = match reply? {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+            };
+            Ok(Some(response))
+        }
+    }
+
+    #[test]
+    fn conforming_server_passes_every_check() {
+        let server = FakeServer::default();
+        let config = ConformanceConfig {
+            probe_tool: Some("echo".into()),
+            ..Default::default()
+        };
+        let report = run(&server, &config);
+        assert!(report.passed(), "{report}");
+        assert_eq!(report.count(CheckStatus::Skip), 0);
+        assert_eq!(report.checks.len(), CHECKS.len());
+
+        let sent = server.sent.lock().unwrap();
+        assert!(
+            sent.iter()
+                .any(|m| m["method"] == "notifications/cancelled")
+        );
+    }
+
+    #[test]
+    fn reports_failures_and_skips_probe_checks() {
+        let server = FakeServer {
+            sloppy: true,
+            ..Default::default()
+        };
+        let report = run(&server, &ConformanceConfig::default());
+        assert!(!report.passed());
+
+        let failed: Vec<_> = report.failures().map(|check| check.name).collect();
+        assert_eq!(failed, ["tools.invalid_cursor", "errors.unknown_method"]);
+        assert_eq!(report.count(CheckStatus::Skip), 2);
+        assert!(
+            report
+                .to_string()
+                .ends_with("10 passed, 2 failed, 2 skipped")
+        );
+    }
+}
@@ -0,0 +1,342 @@
+//! Conversions between `wasix:mcp` router types and the JSON-RPC structs in
+//! [`crate::protocol`].
+//!
+//! Router components speak the WIT records re-exported by
+//! [`greentic_mcp_exec::router`]; MCP servers speak the protocol's JSON. The
+//! functions here map tools, content blocks, and tool results in both
+//! directions, including titles, annotations, and `_meta`, so bridges and
+//! gateways do not each carry their own mapping.
+//!
+//! JSON-typed WIT fields (schemas, structured content, meta values) hold
+//! encoded JSON; text that does not parse is carried as a JSON string.
+//! Protocol content without a WIT counterpart becomes a text block holding
+//! the content's JSON, and tool-result progress has no protocol counterpart,
+//! so it is dropped.
+
+use greentic_mcp_exec::router;
+use serde_json::{Map, Value, json};
+
+use crate::protocol::{CallToolResult, Content, RawJson, Tool};
+
+/// A protocol tool as a router tool descriptor.
+pub fn tool_to_wit(tool: Tool) -> router::Tool {
+    router::Tool {
+        title: string(tool.extra.get("title")),
+        description: tool.description.unwrap_or_default(),
+        input_schema: tool.input_schema.map_or_else(
+            || json!({ "type": "object" }).to_string(),
+            |schema| schema.get().to_owned(),
+        ),
+        output_schema: tool.output_schema.map(|schema| schema.get().to_owned()),
+        annotations: tool.extra.get("annotations").map(tool_annotations_to_wit),
+        meta: tool.extra.get("_meta").and_then(meta_to_wit),
+        name: tool.name,
+    }
+}
+
+/// A router tool descriptor as a protocol tool.
+pub fn tool_from_wit(tool: &router::Tool) -> Tool {
+    let mut extra = Map::new();
+    if let Some(title) = &tool.title {
+        extra.insert("title".into(), json!(title));
+    }
+    if let Some(annotations) = &tool.annotations {
+        extra.insert("annotations".into(), tool_annotations_from_wit(annotations));
+    }
+    if let Some(meta) = &tool.meta {
+        extra.insert("_meta".into(), meta_from_wit(meta));
+    }
+    Tool {
+        name: tool.name.clone(),
+        description: Some(tool.description.clone()),
+        input_schema: Some(raw_json(&tool.input_schema)),
+        output_schema: tool.output_schema.as_deref().map(raw_json),
+        secret_requirements: Vec::new(),
+        extra: extra.into_iter().collect(),
+    }
+}
+
+/// A protocol content item as a router content block.
+pub fn content_to_wit(content: Content) -> router::ContentBlock {
+    let annotations = content.extra.get("annotations").map(annotations_to_wit);
+    let data = string(content.data.as_ref());
+    let mime_type = string(content.extra.get("mimeType"));
+    let resource = content.extra.get("resource").and_then(Value::as_object);
+    match (content.kind.as_str(), resource) {
+        ("image", _) => router::ContentBlock::Image(router::ImageContent {
+            data: data.unwrap_or_default(),
+            mime_type: mime_type.unwrap_or_default(),
+            annotations,
+        }),
+        ("audio", _) => router::ContentBlock::Audio(router::AudioContent {
+            data: data.unwrap_or_default(),
+            mime_type: mime_type.unwrap_or_default(),
+            annotations,
+        }),
+        ("resource_link", _) => router::ContentBlock::ResourceLink(router::ResourceLinkContent {
+            uri: string(content.extra.get("uri")).unwrap_or_default(),
+            title: string(content.extra.get("title")),
+            description: string(content.extra.get("description")),
+            mime_type,
+            annotations,
+        }),
+        ("resource", Some(resource)) => {
+            router::ContentBlock::EmbeddedResource(router::EmbeddedResource {
+                uri: string(resource.get("uri")).unwrap_or_default(),
+                title: string(resource.get("title")),
+                description: string(resource.get("description")),
+                mime_type: string(resource.get("mimeType")),
+                data: string(resource.get("text"))
+                    .or_else(|| string(resource.get("blob")))
+                    .unwrap_or_default(),
+                annotations,
+            })
+        }
+        // Text, and anything without a WIT counterpart, travels as text.
+        _ => router::ContentBlock::Text(router::TextContent {
+            text: match content.text {
+                Some(text) => text,
+                None => serde_json::to_string(&content).unwrap_or_default(),
+            },
+            annotations,
+        }),
+    }
+}
+
+/// A router content block as a protocol content item.
+pub fn content_from_wit(block: &router::ContentBlock) -> Content {
+    let mut extra = Map::new();
+    let (kind, text, data, annotations) = match block {
+        router::ContentBlock::Text(text) => {
+            ("text", Some(text.text.clone()), None, &text.annotations)
+        }
+        router::ContentBlock::Image(image) => {
+            extra.insert("mimeType".into(), json!(image.mime_type));
+            ("image", None, Some(json!(image.data)), &image.annotations)
+        }
+        router::ContentBlock::Audio(audio) => {
+            extra.insert("mimeType".into(), json!(audio.mime_type));
+            ("audio", None, Some(json!(audio.data)), &audio.annotations)
+        }
+        router::ContentBlock::ResourceLink(link) => {
+            extra.insert("uri".into(), json!(link.uri));
+            insert_some(&mut extra, "title", &link.title);
+            insert_some(&mut extra, "description", &link.description);
+            insert_some(&mut extra, "mimeType", &link.mime_type);
+            ("resource_link", None, None, &link.annotations)
+        }
+        router::ContentBlock::EmbeddedResource(resource) => {
+            let mut embedded = Map::new();
+            embedded.insert("uri".into(), json!(resource.uri));
+            insert_some(&mut embedded, "title", &resource.title);
+            insert_some(&mut embedded, "description", &resource.description);
+            insert_some(&mut embedded, "mimeType", &resource.mime_type);
+            embedded.insert("text".into(), json!(resource.data));
+            extra.insert("resource".into(), Value::Object(embedded));
+            ("resource", None, None, &resource.annotations)
+        }
+    };
+    if let Some(annotations) = annotations {
+        extra.insert("annotations".into(), annotations_from_wit(annotations));
+    }
+    Content {
+        kind: kind.into(),
+        text,
+        data,
+        extra: extra.into_iter().collect(),
+    }
+}
+
+/// A protocol tool result as a router tool result.
+pub fn call_result_to_wit(result: CallToolResult) -> router::ToolResult {
+    router::ToolResult {
+        meta: result.extra.get("_meta").and_then(meta_to_wit),
+        content: result.content.into_iter().map(content_to_wit).collect(),
+        structured_content: result
+            .structured_content
+            .map(|value| value.get().to_owned()),
+        progress: None,
+        is_error: result.is_error,
+    }
+}
+
+/// A router tool result as a protocol tool result.
+pub fn call_result_from_wit(result: &router::ToolResult) -> CallToolResult {
+    let mut extra = Map::new();
+    if let Some(meta) = &result.meta {
+        extra.insert("_meta".into(), meta_from_wit(meta));
+    }
+    CallToolResult {
+        content: result.content.iter().map(content_from_wit).collect(),
+        is_error: result.is_error,
+        structured_content: result.structured_content.as_deref().map(raw_json),
+        extra: extra.into_iter().collect(),
+    }
+}
+
+fn tool_annotations_to_wit(value: &Value) -> router::ToolAnnotations {
+    let flag = |key: &str| value.get(key).and_then(Value::as_bool);
+    router::ToolAnnotations {
+        read_only: flag("readOnlyHint"),
+        destructive: flag("destructiveHint"),
+        streaming: flag("streaming"),
+        experimental: flag("experimental"),
+    }
+}
+
+fn tool_annotations_from_wit(annotations: &router::ToolAnnotations) -> Value {
+    let mut map = Map::new();
+    insert_some(&mut map, "readOnlyHint", &annotations.read_only);
+    insert_some(&mut map, "destructiveHint", &annotations.destructive);
+    insert_some(&mut map, "streaming", &annotations.streaming);
+    insert_some(&mut map, "experimental", &annotations.experimental);
+    Value::Object(map)
+}
+
+fn annotations_to_wit(value: &Value) -> router::Annotations {
+    router::Annotations {
+        audience: value
+            .get("audience")
+            .and_then(Value::as_array)
+            .map(|roles| {
+                roles
+                    .iter()
+                    .filter_map(|role| match role.as_str()? {
+                        "user" => Some(router::Role::User),
+                        "assistant" => Some(router::Role::Assistant),
+                        _ => None,
+                    })
+                    .collect()
+            }),
+        priority: value
+            .get("priority")
+            .and_then(Value::as_f64)
+            .map(|priority| priority as f32),
+        timestamp: value
+            .get("lastModified")
+            .and_then(Value::as_str)
+            .map(str::to_owned),
+    }
+}
+
+fn annotations_from_wit(annotations: &router::Annotations) -> Value {
+    let mut map = Map::new();
+    if let Some(audience) = &annotations.audience {
+        let roles: Vec<&str> = audience
+            .iter()
+            .map(|role| match role {
+                router::Role::User => "user",
+                router::Role::Assistant => "assistant",
+            })
+            .collect();
+        map.insert("audience".into(), json!(roles));
+    }
+    insert_some(&mut map, "priority", &annotations.priority);
+    insert_some(&mut map, "lastModified", &annotations.timestamp);
+    Value::Object(map)
+}
+
+fn meta_to_wit(value: &Value) -> Option<Vec<router::MetaEntry>> {
+    let map = value.as_object()?;
+    Some(
+        map.iter()
+            .map(|(key, value)| router::MetaEntry {
+                key: key.clone(),
+                value: value.to_string(),
+            })
+            .collect(),
+    )
+}
+
+fn meta_from_wit(entries: &[router::MetaEntry]) -> Value {
+    Value::Object(
+        entries
+            .iter()
+            .map(|entry| (entry.key.clone(), parse_json(&entry.value)))
+            .collect(),
+    )
+}
+
+fn parse_json(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+/// `raw` as JSON text, or as a JSON string when it is not valid JSON.
+fn raw_json(raw: &str) -> RawJson {
+    RawJson::from_text(raw).unwrap_or_else(|_| RawJson::from(Value::String(raw.to_string())))
+}
+
+fn string(value: Option<&Value>) -> Option<String> {
+    value.and_then(Value::as_str).map(str::to_owned)
+}
+
+fn insert_some<T: serde::Serialize>(map: &mut Map<String, Value>, key: &str, value: &Option<T>) {
+    if let Some(value) = value {
+        map.insert(key.into(), json!(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tools_and_results_round_trip_through_wit() {
+        let tool: Tool = serde_json::from_value(json!({
+            "name": "search",
+            "title": "Search",
+            "description": "Find documents",
+            "inputSchema": { "type": "object", "required": ["q"] },
+            "outputSchema": { "type": "object" },
+            "annotations": { "readOnlyHint": true, "destructiveHint": false },
+            "_meta": { "cache_ttl_seconds": 60 }
+        }))
+        .expect("tool");
+        let wit = tool_to_wit(tool.clone());
+        assert_eq!(wit.title.as_deref(), Some("Search"));
+        assert_eq!(
+            wit.annotations.as_ref().and_then(|a| a.read_only),
+            Some(true)
+        );
+        assert_eq!(wit.meta.as_ref().map(Vec::len), Some(1));
+        assert_eq!(
+            serde_json::to_value(tool_from_wit(&wit)).expect("encode"),
+            serde_json::to_value(&tool).expect("encode")
+        );
+
+        let result: CallToolResult = serde_json::from_value(json!({
+            "content": [
+                { "type": "text", "text": "hi", "annotations": { "audience": ["user"], "priority": 0.5 } },
+                { "type": "image", "data": "aGk=", "mimeType": "image/png" },
+                { "type": "resource_link", "uri": "file:///a", "title": "A" },
+                { "type": "resource", "resource": { "uri": "file:///b", "mimeType": "text/plain", "text": "b" } }
+            ],
+            "structuredContent": { "count": 2 },
+            "isError": false,
+            "_meta": { "trace": "t-1" }
+        }))
+        .expect("result");
+        let wit = call_result_to_wit(result.clone());
+        assert!(matches!(
+            &wit.content[3],
+            router::ContentBlock::EmbeddedResource(resource) if resource.data == "b"
+        ));
+        assert_eq!(
+            serde_json::to_value(call_result_from_wit(&wit)).expect("encode"),
+            serde_json::to_value(&result).expect("encode")
+        );
+    }
+
+    #[test]
+    fn unmapped_content_travels_as_text() {
+        let content: Content =
+            serde_json::from_value(json!({ "type": "chart", "series": [1, 2] })).expect("content");
+        let router::ContentBlock::Text(text) = content_to_wit(content) else {
+            panic!("expected a text block");
+        };
+        assert_eq!(
+            serde_json::from_str::<Value>(&text.text).expect("json"),
+            json!({ "type": "chart", "series": [1, 2] })
+        );
+    }
+}
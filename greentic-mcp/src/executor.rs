@@ -10,6 +10,7 @@ use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiCtxView, WasiView};
 use wasmtime_wasi_http::{WasiHttpCtx, WasiHttpView};
 use wasmtime_wasi_tls::{LinkOptions, WasiTls, WasiTlsCtx, WasiTlsCtxBuilder};
 
+use crate::codec;
 use crate::retry;
 use crate::types::{McpError, ToolInput, ToolOutput, ToolRef};
 
@@ -57,7 +58,7 @@ impl WasixExecutor {
 
             match result {
                 Ok(bytes) => {
-                    let payload = serde_json::from_slice(&bytes).map_err(|err| {
+                    let payload = codec::from_slice(&bytes).map_err(|err| {
                         McpError::ExecutionFailed(format!("invalid tool output JSON: {err}"))
                     })?;
                     let structured_content = match &payload {
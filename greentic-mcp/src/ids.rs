@@ -0,0 +1,190 @@
+//! JSON-RPC id generation and response correlation.
+//!
+//! Transports that multiplex requests over one stream need ids that never
+//! collide and a way to route each response back to its caller. The same
+//! helpers back [`crate::RemoteRouter`], so custom transports and test
+//! harnesses can generate ids consistently with it. The request builders in
+//! [`crate::protocol`] count up from 1 like [`RequestIdGenerator::sequential`].
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rand::distr::{Distribution, Uniform};
+use serde_json::Value;
+
+/// How a [`RequestIdGenerator`] produces ids.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdStrategy {
+    /// Monotonic integers starting at the configured value.
+    Sequential,
+    /// Random (version 4) UUID strings, unique across processes.
+    Uuid,
+}
+
+/// Thread-safe source of request ids.
+#[derive(Debug)]
+pub struct RequestIdGenerator {
+    strategy: IdStrategy,
+    next: AtomicU64,
+}
+
+impl RequestIdGenerator {
+    /// Integers counting up from 1.
+    pub const fn sequential() -> Self {
+        Self::starting_at(1)
+    }
+
+    /// Integers counting up from `first`.
+    pub const fn starting_at(first: u64) -> Self {
+        Self {
+            strategy: IdStrategy::Sequential,
+            next: AtomicU64::new(first),
+        }
+    }
+
+    /// Random UUID strings.
+    pub const fn uuid() -> Self {
+        Self {
+            strategy: IdStrategy::Uuid,
+            next: AtomicU64::new(0),
+        }
+    }
+
+    pub fn strategy(&self) -> IdStrategy {
+        self.strategy
+    }
+
+    pub fn next_id(&self) -> Value {
+        match self.strategy {
+            IdStrategy::Sequential => Value::from(self.next.fetch_add(1, Ordering::Relaxed)),
+            IdStrategy::Uuid => Value::String(uuid_v4()),
+        }
+    }
+}
+
+impl Default for RequestIdGenerator {
+    fn default() -> Self {
+        Self::sequential()
+    }
+}
+
+/// Pending requests keyed by id, awaiting their responses.
+///
+/// `T` is whatever the caller needs to complete the request, e.g. a channel
+/// sender or the originating method name.
+#[derive(Debug)]
+pub struct Correlator<T> {
+    pending: Mutex<HashMap<String, T>>,
+}
+
+impl<T> Correlator<T> {
+    pub fn new() -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Track a request; returns the previous entry if `id` was already pending.
+    pub fn register(&self, id: &Value, entry: T) -> Option<T> {
+        self.lock().insert(key(id), entry)
+    }
+
+    /// Take the entry for a response message, matched on its `id`.
+    ///
+    /// Notifications, server requests, and unknown ids yield `None`.
+    pub fn resolve(&self, response: &Value) -> Option<T> {
+        if response.get("method").is_some() {
+            return None;
+        }
+        let id = response.get("id")?;
+        self.take(id)
+    }
+
+    /// Take the entry for `id`, e.g. after a timeout or cancellation.
+    pub fn take(&self, id: &Value) -> Option<T> {
+        self.lock().remove(&key(id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Remove and return every pending entry, e.g. when the connection drops.
+    pub fn drain(&self) -> Vec<T> {
+        self.lock().drain().map(|(_, entry)| entry).collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, T>> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<T> Default for Correlator<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Canonical map key: `1` and `"1"` are different JSON-RPC ids.
+fn key(id: &Value) -> String {
+    id.to_string()
+}
+
+fn uuid_v4() -> String {
+    let uniform = Uniform::new_inclusive(0u64, u64::MAX).expect("valid id bounds");
+    let mut rng = rand::rng();
+    let high = (uniform.sample(&mut rng) & !0xF000) | 0x4000;
+    let low = (uniform.sample(&mut rng) & !(0b11 << 62)) | (0b10 << 62);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xFFFF,
+        high & 0xFFFF,
+        low >> 48,
+        low & 0xFFFF_FFFF_FFFF
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn generators_do_not_repeat() {
+        let ids = RequestIdGenerator::starting_at(10);
+        assert_eq!(ids.next_id(), json!(10));
+        assert_eq!(ids.next_id(), json!(11));
+
+        let uuids = RequestIdGenerator::uuid();
+        let first = uuids.next_id();
+        let first = first.as_str().expect("string id");
+        assert_eq!(first.len(), 36);
+        assert_eq!(&first[14..15], "4");
+        assert_ne!(uuids.next_id(), json!(first));
+    }
+
+    #[test]
+    fn correlator_matches_responses_by_id() {
+        let pending = Correlator::new();
+        pending.register(&json!(1), "tools/list");
+        pending.register(&json!("1"), "tools/call");
+
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/progress" });
+        assert_eq!(pending.resolve(&notification), None);
+        assert_eq!(
+            pending.resolve(&json!({ "jsonrpc": "2.0", "id": "1", "result": {} })),
+            Some("tools/call")
+        );
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending.drain(), ["tools/list"]);
+        assert!(pending.is_empty());
+    }
+}
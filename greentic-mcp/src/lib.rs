@@ -1,24 +1,99 @@
 //! Host-side ToolMap management and WASIX/WASI execution bridge for Greentic MCP tools.
+//!
+//! Everything that needs wasmtime, a blocking HTTP client, or tokio sits behind
+//! the default `native` feature. Without it the protocol types, ids, sessions,
+//! and sanitizer still build, including for `wasm32-unknown-unknown`, where the
+//! `browser` feature adds a fetch/WebSocket client in [`browser`].
 
+#[cfg(feature = "browser")]
+pub mod browser;
+
+#[cfg(feature = "native")]
+pub mod attachments;
 pub mod auth;
+#[cfg(feature = "native")]
+pub mod catalog;
+pub mod codec;
+#[cfg(feature = "native")]
 pub mod compose;
 pub mod config;
+#[cfg(feature = "native")]
+pub mod conformance;
+#[cfg(feature = "native")]
+pub mod convert;
+#[cfg(feature = "native")]
 pub mod executor;
+pub mod ids;
+pub mod manifest;
 pub mod protocol;
+#[cfg(feature = "native")]
+pub mod remote;
+pub mod result_cache;
 pub mod retry;
+pub mod sanitize;
+pub mod secret;
+#[cfg(feature = "native")]
+pub mod serve;
+pub mod session;
+#[cfg(feature = "native")]
+pub mod stdio;
+#[cfg(feature = "native")]
+pub mod streaming;
+#[cfg(feature = "native")]
+pub mod tap;
 pub mod tool_map;
 pub mod types;
-
-pub use config::load_tool_map_config;
+pub mod usage;
+
+#[cfg(feature = "native")]
+pub use attachments::{
+    Attachment, AttachmentConfig, AttachmentError, AttachmentStore, Attachments,
+    InMemoryAttachmentStore,
+};
+#[cfg(feature = "browser")]
+pub use browser::{BrowserClient, BrowserTransport, FetchTransport, WebSocketTransport};
+#[cfg(feature = "native")]
+pub use catalog::{diff_tool_lists, diff_tools};
+pub use config::{interpolate_env, load_server_configs, load_tool_map_config};
+#[cfg(feature = "native")]
 pub use executor::WasixExecutor;
+#[cfg(feature = "native")]
+pub use greentic_mcp_exec::{PermissionDenied, PermissionGate, ToolPermissions, ToolRule};
+pub use ids::{Correlator, IdStrategy, RequestIdGenerator};
+pub use manifest::{NodeManifest, NodeOperation};
+#[cfg(feature = "native")]
+pub use remote::{HttpTransport, RemoteRouter, RemoteTransport, RouterEvent};
+pub use result_cache::ResultCache;
+pub use secret::Secret;
+#[cfg(feature = "native")]
+pub use serve::{
+    Admitted, EventReceiver, EventSender, RequestPermit, ServeLimits, SessionGuard, event_stream,
+};
+#[cfg(feature = "file-sessions")]
+pub use session::FileSessionStore;
+#[cfg(feature = "redis-sessions")]
+pub use session::RedisSessionStore;
+pub use session::{InMemorySessionStore, Session, SessionStore};
+#[cfg(feature = "native")]
+pub use stdio::{StdioSandbox, StdioTransport};
+#[cfg(feature = "native")]
+pub use streaming::{MessageStream, StreamBuffer};
+#[cfg(feature = "native")]
+pub use tap::{Frame, FrameDirection, TapConfig, TapTransport};
 pub use tool_map::ToolMap;
 pub use types::{McpError, ToolInput, ToolMapConfig, ToolOutput, ToolRef};
+pub use usage::{ToolUsage, UsageLedger, UsageRecord};
 
+#[cfg(feature = "native")]
 use greentic_mcp_exec::{ExecConfig, ExecError, ExecRequest, RunnerError};
+#[cfg(feature = "native")]
 use serde_json::{Value, json};
+#[cfg(feature = "native")]
 use std::sync::Arc;
+#[cfg(feature = "native")]
 use tokio::time::sleep;
 /// Invoke a tool by name using a [`ToolMap`] and [`WasixExecutor`].
+#[cfg(feature = "native")]
 pub async fn invoke_with_map(
     map: &ToolMap,
     executor: &WasixExecutor,
@@ -41,14 +116,18 @@ pub fn load_tool_map(path: &std::path::Path) -> Result<ToolMap, McpError> {
 
 pub mod test_tools;
 
+#[cfg(feature = "native")]
 use std::time::Duration;
 
+#[cfg(feature = "native")]
 type ExecFn = dyn Fn(ExecRequest, &ExecConfig) -> Result<Value, ExecError> + Send + Sync;
 
+#[cfg(feature = "native")]
 pub async fn exec_with_retries(req: ExecRequest, cfg: &ExecConfig) -> Result<Value, ExecError> {
     exec_with_retries_with(req, cfg, Arc::new(greentic_mcp_exec::exec)).await
 }
 
+#[cfg(feature = "native")]
 pub async fn exec_with_retries_backend<F>(
     req: ExecRequest,
     cfg: &ExecConfig,
@@ -60,6 +139,7 @@ where
     exec_with_retries_with(req, cfg, Arc::new(exec_fn)).await
 }
 
+#[cfg(feature = "native")]
 async fn exec_with_retries_with(
     mut req: ExecRequest,
     cfg: &ExecConfig,
@@ -108,6 +188,7 @@ async fn exec_with_retries_with(
     unreachable!("retry loop should never exit without returning")
 }
 
+#[cfg(feature = "native")]
 fn is_transient_error(err: &ExecError) -> bool {
     match err {
         ExecError::Runner { source, .. } => matches!(source, RunnerError::Timeout { .. }),
@@ -117,12 +198,14 @@ fn is_transient_error(err: &ExecError) -> bool {
 }
 
 /// Test-only helpers that run native “tools” without Wasm.
+#[cfg(feature = "native")]
 pub enum TestBackend {
     NativeEcho,
     NativeFlaky,
     NativeTimeout(Duration),
 }
 
+#[cfg(feature = "native")]
 pub fn exec_test_backend(
     backend: TestBackend,
     input: Value,
@@ -153,6 +236,7 @@ pub fn exec_test_backend(
     }
 }
 
+#[cfg(feature = "native")]
 fn tool_error(component: &str, action: &str, code: &str, message: String) -> ExecError {
     ExecError::tool_error(component, action, code, json!({ "message": message }))
 }
@@ -0,0 +1,169 @@
+//! Greentic flow node manifest stubs generated from MCP tool catalogs.
+//!
+//! A composed MCP component runs as one flow node whose `call` operation
+//! reaches any tool of its router. [`NodeManifest::from_tools`] turns a
+//! catalog, as listed by a [`RemoteRouter`](crate::RemoteRouter) or converted
+//! from a wasm router with [`convert::tool_from_wit`](crate::convert), into a
+//! stub with one operation per tool. An operation's config schema is the
+//! tool's input schema, and the secrets the tools declare are collected at the
+//! top. Secrets a component declares through `describe`, which apply to every
+//! tool, are merged in with [`NodeManifest::with_secret_requirements`].
+//!
+//! Stubs are a starting point for flow authors to review and check in; the
+//! host does not enforce them.
+
+use std::collections::HashSet;
+
+use greentic_types::SecretRequirement;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::protocol::{RawJson, Tool};
+
+/// Node manifest stub for the tools of one component or server.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeManifest {
+    /// Component the node runs: the composed router's name in the tool store,
+    /// or the name of the remote server.
+    pub component: String,
+    pub operations: Vec<NodeOperation>,
+    /// Secrets at least one operation needs, each key listed once.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secret_requirements: Vec<SecretRequirement>,
+}
+
+/// One tool, exposed as a node operation.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeOperation {
+    /// Tool name, passed as `tool` in the adapter's `call` payload.
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// JSON Schema of the operation's `arguments`.
+    pub config_schema: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Value>,
+    /// Secrets the tool declares in its catalog entry.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secret_requirements: Vec<SecretRequirement>,
+}
+
+impl NodeOperation {
+    fn from_tool(tool: &Tool) -> Self {
+        Self {
+            name: tool.name.clone(),
+            title: tool
+                .extra
+                .get("title")
+                .and_then(Value::as_str)
+                .map(str::to_owned),
+            description: tool.description.clone(),
+            // MCP requires an input schema; servers that omit it accept any object.
+            config_schema: tool
+                .input_schema
+                .as_ref()
+                .map_or_else(|| json!({ "type": "object" }), RawJson::to_value),
+            output_schema: tool.output_schema.as_ref().map(RawJson::to_value),
+            secret_requirements: tool.secret_requirements.clone(),
+        }
+    }
+}
+
+impl NodeManifest {
+    /// Stub for `component` with one operation per tool, in catalog order.
+    pub fn from_tools(component: impl Into<String>, tools: &[Tool]) -> Self {
+        let operations: Vec<NodeOperation> = tools.iter().map(NodeOperation::from_tool).collect();
+        let mut manifest = Self {
+            component: component.into(),
+            operations,
+            secret_requirements: Vec::new(),
+        };
+        let declared: Vec<SecretRequirement> = manifest
+            .operations
+            .iter()
+            .flat_map(|operation| operation.secret_requirements.iter().cloned())
+            .collect();
+        manifest.add_secrets(declared);
+        manifest
+    }
+
+    /// Add secrets the whole component needs, such as the
+    /// `secret_requirements` returned by `describe`. Keys already listed keep
+    /// their first definition.
+    pub fn with_secret_requirements(
+        mut self,
+        requirements: impl IntoIterator<Item = SecretRequirement>,
+    ) -> Self {
+        self.add_secrets(requirements);
+        self
+    }
+
+    /// The operation named `name`, if the catalog had that tool.
+    pub fn operation(&self, name: &str) -> Option<&NodeOperation> {
+        self.operations
+            .iter()
+            .find(|operation| operation.name == name)
+    }
+
+    fn add_secrets(&mut self, requirements: impl IntoIterator<Item = SecretRequirement>) {
+        let mut seen: HashSet<_> = self
+            .secret_requirements
+            .iter()
+            .map(|req| req.key.clone())
+            .collect();
+        for req in requirements {
+            if seen.insert(req.key.clone()) {
+                self.secret_requirements.push(req);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::ToolListResult;
+
+    #[test]
+    fn one_operation_per_tool_with_secrets_collected() {
+        let list: ToolListResult = serde_json::from_value(json!({
+            "tools": [
+                {
+                    "name": "forecast",
+                    "title": "Forecast",
+                    "description": "Weather for a city",
+                    "inputSchema": {
+                        "type": "object",
+                        "required": ["city"],
+                        "properties": { "city": { "type": "string" } }
+                    },
+                    "secret_requirements": ["WEATHER_API_KEY"]
+                },
+                { "name": "ping", "secretRequirements": ["WEATHER_API_KEY"] }
+            ]
+        }))
+        .expect("tool list");
+        let mut shared = SecretRequirement::default();
+        shared.key = "TELEMETRY_TOKEN".into();
+
+        let manifest =
+            NodeManifest::from_tools("weather", &list.tools).with_secret_requirements([shared]);
+
+        assert_eq!(manifest.component, "weather");
+        let forecast = manifest.operation("forecast").expect("forecast");
+        assert_eq!(forecast.title.as_deref(), Some("Forecast"));
+        assert_eq!(forecast.config_schema["required"], json!(["city"]));
+        let ping = manifest.operation("ping").expect("ping");
+        assert_eq!(ping.config_schema, json!({ "type": "object" }));
+        assert!(ping.output_schema.is_none());
+
+        let keys: Vec<&str> = manifest
+            .secret_requirements
+            .iter()
+            .map(|req| req.key.as_str())
+            .collect();
+        assert_eq!(keys, ["WEATHER_API_KEY", "TELEMETRY_TOKEN"]);
+    }
+}
@@ -1,62 +1,35 @@
-use greentic_types::{SecretKey, SecretRequirement};
+//! MCP message types and per-server configuration.
+//!
+//! The message types live in `greentic-mcp-types`, which guest components and
+//! `no_std` environments can depend on alone, and are re-exported here; the
+//! server configuration is host-side and stays in this crate.
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::BTreeMap;
-use std::fmt::{self, Display, Formatter};
-use std::str::FromStr;
-
-const JSONRPC_2_0: &str = "2.0";
-
-fn jsonrpc_version() -> String {
-    JSONRPC_2_0.to_string()
-}
-
-/// Supported MCP protocol revisions.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, Default)]
-pub enum ProtocolRevision {
-    #[serde(rename = "2025-03-26")]
-    V2025_03_26,
-    #[default]
-    #[serde(rename = "2025-06-18")]
-    V2025_06_18,
-}
-
-impl ProtocolRevision {
-    pub const fn as_str(&self) -> &'static str {
-        match self {
-            ProtocolRevision::V2025_03_26 => "2025-03-26",
-            ProtocolRevision::V2025_06_18 => "2025-06-18",
-        }
-    }
-}
-
-impl Display for ProtocolRevision {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.write_str(self.as_str())
-    }
-}
+use std::collections::{BTreeMap, HashMap};
 
-impl FromStr for ProtocolRevision {
-    type Err = String;
+pub use greentic_mcp_types::{
+    CallToolRequest, CallToolResult, Content, InitializeParams, ListToolsRequest, McpNotification,
+    McpRequest, McpResponse, ProtocolRevision, RawJson, RequestBuilder, RpcError, Tool,
+    ToolListResult, initialize_request_with_revision, next_request_id,
+};
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.trim() {
-            "2025-03-26" | "v2025-03-26" | "2025_03_26" => Ok(ProtocolRevision::V2025_03_26),
-            "2025-06-18" | "v2025-06-18" | "2025_06_18" | "2025-06" => {
-                Ok(ProtocolRevision::V2025_06_18)
-            }
-            other => Err(format!(
-                "unsupported protocol revision '{}'; expected 2025-03-26 or 2025-06-18",
-                other
-            )),
-        }
-    }
-}
+use crate::sanitize::SanitizerConfig;
+use crate::secret::Secret;
 
 /// Per-server configuration for selecting protocol revisions.
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct McpServerConfig {
     pub name: String,
+    /// How the server is reached; inferred from `url` or `command` when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transport: Option<TransportKind>,
+    /// Endpoint of an `http` server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Executable of a `stdio` server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub protocol_revision: Option<ProtocolRevision>,
     #[serde(default = "default_auth_mode")]
@@ -64,9 +37,12 @@ pub struct McpServerConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub oauth: Option<OAuthConfig>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub api_key: Option<String>,
+    pub api_key: Option<Secret>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub bearer_token: Option<String>,
+    pub bearer_token: Option<Secret>,
+    /// Cleaning applied to this server's tool output before it is returned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sanitizer: Option<SanitizerConfig>,
     #[serde(default, flatten)]
     pub extra: BTreeMap<String, Value>,
 }
@@ -116,6 +92,149 @@ impl McpServerConfig {
         }
         Ok(())
     }
+
+    /// Check every configuration up front instead of failing at connect time.
+    ///
+    /// Reports all problems at once: missing or conflicting credentials for the
+    /// auth mode, a `url` or `command` that does not fit the transport, and
+    /// duplicate names. Each diagnostic carries a JSON pointer into `configs`
+    /// as a JSON array, e.g. `/1/api_key`.
+    pub fn validate_all(configs: &[McpServerConfig]) -> Result<(), Vec<ConfigDiagnostic>> {
+        let mut diagnostics = Vec::new();
+        let mut first_index: HashMap<&str, usize> = HashMap::new();
+        for (index, config) in configs.iter().enumerate() {
+            let mut report = |field: &str, message: String| {
+                diagnostics.push(ConfigDiagnostic {
+                    pointer: format!("/{index}/{field}"),
+                    message,
+                });
+            };
+
+            let name = config.name.trim();
+            if name.is_empty() {
+                report("name", "server name must not be empty".into());
+            } else if let Some(first) = first_index.get(name) {
+                report(
+                    "name",
+                    format!("duplicate server name '{name}', first defined at /{first}"),
+                );
+            } else {
+                first_index.insert(name, index);
+            }
+
+            config.check_auth(&mut report);
+            config.check_transport(&mut report);
+        }
+        if diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(diagnostics)
+        }
+    }
+
+    fn check_auth(&self, report: &mut impl FnMut(&str, String)) {
+        let mode = self.resolved_auth_mode();
+        let blank = |secret: &Option<Secret>| secret.as_ref().is_none_or(|s| s.expose().is_empty());
+        match mode {
+            AuthMode::ApiKey if blank(&self.api_key) => report(
+                "api_key",
+                "auth_mode api_key requires a non-empty api_key".into(),
+            ),
+            AuthMode::BearerToken if blank(&self.bearer_token) => report(
+                "bearer_token",
+                "auth_mode bearer_token requires a non-empty bearer_token".into(),
+            ),
+            AuthMode::OAuth => match &self.oauth {
+                None => report("oauth", "auth_mode oauth requires an oauth section".into()),
+                Some(oauth) => {
+                    if oauth.provider.trim().is_empty() {
+                        report("oauth/provider", "oauth provider must not be empty".into());
+                    }
+                    if let Err(err) = self.validate() {
+                        report("oauth/resource", err);
+                    }
+                }
+            },
+            _ => {}
+        }
+
+        let unused = [
+            ("oauth", self.oauth.is_some(), AuthMode::OAuth),
+            ("api_key", self.api_key.is_some(), AuthMode::ApiKey),
+            (
+                "bearer_token",
+                self.bearer_token.is_some(),
+                AuthMode::BearerToken,
+            ),
+        ];
+        for (field, present, used_by) in unused {
+            if present && mode != used_by {
+                report(
+                    field,
+                    format!("{field} is ignored because the resolved auth mode is {mode:?}"),
+                );
+            }
+        }
+    }
+
+    fn check_transport(&self, report: &mut impl FnMut(&str, String)) {
+        let transport = match (self.transport, &self.url, &self.command) {
+            (Some(kind), _, _) => kind,
+            (None, Some(_), Some(_)) => {
+                report(
+                    "command",
+                    "set either url (http) or command (stdio), not both".into(),
+                );
+                return;
+            }
+            (None, Some(_), None) => TransportKind::Http,
+            (None, None, Some(_)) => TransportKind::Stdio,
+            (None, None, None) => return,
+        };
+        match transport {
+            TransportKind::Http => {
+                match self.url.as_deref().map(url::Url::parse) {
+                    None => report("url", "transport http requires a url".into()),
+                    Some(Err(err)) => report("url", format!("invalid url: {err}")),
+                    Some(Ok(url)) if !matches!(url.scheme(), "http" | "https") => report(
+                        "url",
+                        format!("url scheme must be http or https, got '{}'", url.scheme()),
+                    ),
+                    Some(Ok(_)) => {}
+                }
+                if self.command.is_some() {
+                    report("command", "command is not used by transport http".into());
+                }
+            }
+            TransportKind::Stdio => {
+                if self.command.as_deref().is_none_or(|c| c.trim().is_empty()) {
+                    report("command", "transport stdio requires a command".into());
+                }
+                if self.url.is_some() {
+                    report("url", "url is not used by transport stdio".into());
+                }
+            }
+        }
+    }
+}
+
+/// Transport a server configuration describes.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransportKind {
+    /// Streamable HTTP, served by [`crate::HttpTransport`].
+    Http,
+    /// A spawned process, served by [`crate::StdioTransport`].
+    Stdio,
+}
+
+/// A problem found by [`McpServerConfig::validate_all`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{pointer}: {message}")]
+pub struct ConfigDiagnostic {
+    /// JSON pointer to the offending field, e.g. `/0/oauth/resource`.
+    pub pointer: String,
+    pub message: String,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
@@ -139,209 +258,11 @@ pub struct OAuthConfig {
     pub extra: BTreeMap<String, Value>,
 }
 
-/// JSON-RPC 2.0 request shape used by MCP.
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct McpRequest<P = Value> {
-    #[serde(default = "jsonrpc_version")]
-    pub jsonrpc: String,
-    pub id: Value,
-    pub method: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub params: Option<P>,
-    #[serde(default, flatten)]
-    pub extra: BTreeMap<String, Value>,
-}
-
-/// JSON-RPC 2.0 response shape used by MCP.
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct McpResponse<R = Value, E = RpcError> {
-    #[serde(default = "jsonrpc_version")]
-    pub jsonrpc: String,
-    pub id: Value,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub result: Option<R>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub error: Option<E>,
-    #[serde(default, flatten)]
-    pub extra: BTreeMap<String, Value>,
-}
-
-/// JSON-RPC notification shape used by MCP (no ID).
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct McpNotification<P = Value> {
-    #[serde(default = "jsonrpc_version")]
-    pub jsonrpc: String,
-    pub method: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub params: Option<P>,
-    #[serde(default, flatten)]
-    pub extra: BTreeMap<String, Value>,
-}
-
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct RpcError {
-    pub code: i64,
-    pub message: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub data: Option<Value>,
-    #[serde(default, flatten)]
-    pub extra: BTreeMap<String, Value>,
-}
-
-/// Protocol content wrapper. Flexible, passes through unknown fields.
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct Content {
-    #[serde(rename = "type")]
-    pub kind: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub text: Option<String>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub data: Option<Value>,
-    #[serde(default, flatten)]
-    pub extra: BTreeMap<String, Value>,
-}
-
-/// MCP tool schema.
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct Tool {
-    pub name: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub description: Option<String>,
-    #[serde(
-        default,
-        skip_serializing_if = "Option::is_none",
-        rename = "inputSchema"
-    )]
-    pub input_schema: Option<Value>,
-    #[serde(
-        default,
-        skip_serializing_if = "Option::is_none",
-        rename = "outputSchema"
-    )]
-    pub output_schema: Option<Value>,
-    #[serde(
-        default,
-        skip_serializing_if = "Vec::is_empty",
-        rename = "secret_requirements",
-        alias = "secretRequirements",
-        deserialize_with = "deserialize_secret_requirements"
-    )]
-    pub secret_requirements: Vec<SecretRequirement>,
-    #[serde(default, flatten)]
-    pub extra: BTreeMap<String, Value>,
-}
-
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct ToolListResult {
-    pub tools: Vec<Tool>,
-    #[serde(default, flatten)]
-    pub extra: BTreeMap<String, Value>,
-}
-
-/// Tool call result payload.
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct CallToolResult {
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub content: Vec<Content>,
-    #[serde(default, skip_serializing_if = "Option::is_none", rename = "isError")]
-    pub is_error: Option<bool>,
-    #[serde(
-        default,
-        skip_serializing_if = "Option::is_none",
-        rename = "structuredContent"
-    )]
-    pub structured_content: Option<Value>,
-    #[serde(default, flatten)]
-    pub extra: BTreeMap<String, Value>,
-}
-
-/// Initialize request parameters; kept intentionally loose for compatibility.
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct InitializeParams {
-    #[serde(rename = "protocol")]
-    pub protocol_version: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub client: Option<String>,
-    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
-    pub capabilities: BTreeMap<String, Value>,
-    #[serde(default, flatten)]
-    pub extra: BTreeMap<String, Value>,
-}
-
-fn deserialize_secret_requirements<'de, D>(
-    deserializer: D,
-) -> Result<Vec<SecretRequirement>, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let value = serde_json::Value::deserialize(deserializer)?;
-    let arr = match value {
-        Value::Array(items) => items,
-        other => {
-            return Err(serde::de::Error::custom(format!(
-                "secret_requirements must be an array, got {other}"
-            )));
-        }
-    };
-
-    let mut out = Vec::with_capacity(arr.len());
-    for item in arr {
-        match item {
-            Value::String(key) => {
-                let mut req = SecretRequirement::default();
-                req.key = SecretKey::from(key.as_str());
-                out.push(req);
-            }
-            other => {
-                let req: SecretRequirement = serde_json::from_value(other).map_err(|err| {
-                    serde::de::Error::custom(format!("invalid secret requirement: {err}"))
-                })?;
-                out.push(req);
-            }
-        }
-    }
-
-    Ok(out)
-}
-
-/// Helper to build an initialize request with the correct revision string.
-pub fn initialize_request_with_revision(
-    id: Value,
-    revision: ProtocolRevision,
-    params_extra: BTreeMap<String, Value>,
-) -> McpRequest<InitializeParams> {
-    McpRequest {
-        jsonrpc: jsonrpc_version(),
-        id,
-        method: "initialize".to_string(),
-        params: Some(InitializeParams {
-            protocol_version: revision.as_str().to_string(),
-            client: None,
-            capabilities: BTreeMap::new(),
-            extra: params_extra,
-        }),
-        extra: BTreeMap::new(),
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
 
-    #[test]
-    fn parses_protocol_revision_from_str() {
-        assert_eq!(
-            ProtocolRevision::from_str("2025-03-26").unwrap(),
-            ProtocolRevision::V2025_03_26
-        );
-        assert_eq!(
-            ProtocolRevision::from_str("2025-06-18").unwrap(),
-            ProtocolRevision::V2025_06_18
-        );
-        assert!(ProtocolRevision::from_str("2024-01-01").is_err());
-    }
-
     #[test]
     fn defaults_protocol_revision_when_missing_in_config() {
         let raw = r#"{ "name": "demo" }"#;
@@ -374,70 +295,33 @@ mod tests {
     }
 
     #[test]
-    fn initialize_requests_carry_revision() {
-        let new_req = initialize_request_with_revision(
-            json!(1),
-            ProtocolRevision::V2025_06_18,
-            BTreeMap::new(),
-        );
-        let old_req = initialize_request_with_revision(
-            json!(1),
-            ProtocolRevision::V2025_03_26,
-            BTreeMap::new(),
-        );
-
-        let new_proto = new_req.params.as_ref().unwrap().protocol_version.clone();
-        let old_proto = old_req.params.as_ref().unwrap().protocol_version.clone();
-        assert_ne!(new_proto, old_proto);
-        assert_eq!(new_proto, "2025-06-18");
-        assert_eq!(old_proto, "2025-03-26");
-    }
-
-    #[test]
-    fn tool_and_call_results_capture_optional_fields() {
-        let list_json = json!({
-            "tools": [
-                {
-                    "name": "echo",
-                    "description": "echo message",
-                    "inputSchema": {"type": "object"},
-                    "outputSchema": {"type": "string"},
-                    "secret_requirements": ["api-key"],
-                    "x-extra": true
-                }
-            ],
-            "meta": "ok"
-        });
-
-        let parsed: ToolListResult = serde_json::from_value(list_json).expect("parse tool list");
-        assert_eq!(parsed.tools.len(), 1);
-        let tool = &parsed.tools[0];
+    fn validate_all_reports_every_problem_with_pointers() {
+        let configs: Vec<McpServerConfig> = serde_json::from_value(json!([
+            { "name": "files", "command": "files-mcp" },
+            { "name": "crm", "auth_mode": "api_key", "bearer_token": "t", "url": "ftp://crm" },
+            { "name": "files", "transport": "stdio", "url": "https://files" },
+            { "name": "sso", "oauth": { "provider": "" }, "url": "https://sso", "command": "sso" },
+            { "name": "ok", "bearer_token": "t", "url": "https://ok/mcp" }
+        ]))
+        .expect("parse configs");
+
+        let diagnostics = McpServerConfig::validate_all(&configs).expect_err("invalid");
+        let pointers: Vec<&str> = diagnostics.iter().map(|d| d.pointer.as_str()).collect();
         assert_eq!(
-            tool.output_schema
-                .as_ref()
-                .and_then(|v| v.get("type"))
-                .and_then(Value::as_str),
-            Some("string")
+            pointers,
+            [
+                "/1/api_key",
+                "/1/bearer_token",
+                "/1/url",
+                "/2/name",
+                "/2/command",
+                "/2/url",
+                "/3/oauth/provider",
+                "/3/oauth/resource",
+                "/3/command",
+            ]
         );
-        assert!(tool.extra.contains_key("x-extra"));
-        assert_eq!(tool.secret_requirements.len(), 1);
-        assert_eq!(tool.secret_requirements[0].key.as_str(), "api-key");
-        assert!(parsed.extra.contains_key("meta"));
-
-        let call_json = json!({
-            "content": [
-                {"type": "text", "text": "hello"}
-            ],
-            "isError": false,
-            "structuredContent": {"kind": "object", "value": {"message": "ok"}},
-            "requestId": "abc123"
-        });
-
-        let call: CallToolResult = serde_json::from_value(call_json).expect("parse call result");
-        assert_eq!(call.is_error, Some(false));
-        assert!(call.structured_content.is_some());
-        assert!(call.extra.contains_key("requestId"));
-        assert_eq!(call.content.len(), 1);
-        assert_eq!(call.content[0].text.as_deref(), Some("hello"));
+        assert!(diagnostics[3].to_string().contains("first defined at /0"));
+        assert!(McpServerConfig::validate_all(&configs[4..]).is_ok());
     }
 }
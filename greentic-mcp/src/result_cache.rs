@@ -0,0 +1,226 @@
+//! Deduplication cache for read-only tool calls.
+//!
+//! Agents often repeat the same lookup several times in one task. When a tool
+//! declares `readOnlyHint: true` in its annotations, calling it again with the
+//! same arguments cannot change anything, so a [`ResultCache`] can answer from
+//! the previous result until its TTL runs out. Tools without the hint are never
+//! cached.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::protocol::Tool;
+
+/// Whether a tool's annotations mark it as free of side effects.
+pub fn is_read_only(tool: &Tool) -> bool {
+    tool.extra
+        .get("annotations")
+        .and_then(|annotations| annotations.get("readOnlyHint"))
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// Hit and miss counters of a [`ResultCache`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+struct Entry {
+    stored: Instant,
+    value: Value,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<String, Entry>,
+    hits: u64,
+    misses: u64,
+}
+
+/// Results of read-only tool calls keyed by tool name and arguments.
+///
+/// Arguments are compared structurally, so key order in objects does not
+/// matter. When full, expired entries are evicted first, then the oldest.
+pub struct ResultCache {
+    ttl: Duration,
+    max_entries: usize,
+    state: Mutex<State>,
+}
+
+impl ResultCache {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            max_entries: max_entries.max(1),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// The cached result of `tool` called with `arguments`, if still fresh.
+    pub fn get(&self, tool: &str, arguments: &Value) -> Option<Value> {
+        let key = cache_key(tool, arguments);
+        let mut state = self.lock();
+        let fresh = state
+            .entries
+            .get(&key)
+            .filter(|entry| entry.stored.elapsed() < self.ttl)
+            .map(|entry| entry.value.clone());
+        match fresh {
+            Some(value) => {
+                state.hits += 1;
+                Some(value)
+            }
+            None => {
+                state.entries.remove(&key);
+                state.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, tool: &str, arguments: &Value, value: Value) {
+        let mut state = self.lock();
+        if state.entries.len() >= self.max_entries {
+            state
+                .entries
+                .retain(|_, entry| entry.stored.elapsed() < self.ttl);
+        }
+        if state.entries.len() >= self.max_entries
+            && let Some(oldest) = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.stored)
+                .map(|(key, _)| key.clone())
+        {
+            state.entries.remove(&oldest);
+        }
+        state.entries.insert(
+            cache_key(tool, arguments),
+            Entry {
+                stored: Instant::now(),
+                value,
+            },
+        );
+    }
+
+    /// Drop every cached result, e.g. after the server restarted.
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let state = self.lock();
+        CacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            entries: state.entries.len(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn cache_key(tool: &str, arguments: &Value) -> String {
+    let mut key = format!("{tool}\n");
+    write_canonical(arguments, &mut key);
+    key
+}
+
+/// Serialize `value` with object keys sorted, independent of map ordering.
+pub(crate) fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            out.push('{');
+            for (index, (key, value)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn hits_ignore_key_order_and_expire() {
+        let cache = ResultCache::new(Duration::from_millis(50), 8);
+        cache.insert("lookup", &json!({ "a": 1, "b": [1, 2] }), json!("first"));
+
+        assert_eq!(
+            cache.get("lookup", &json!({ "b": [1, 2], "a": 1 })),
+            Some(json!("first"))
+        );
+        assert_eq!(cache.get("lookup", &json!({ "a": 2, "b": [1, 2] })), None);
+        assert_eq!(cache.get("other", &json!({ "a": 1, "b": [1, 2] })), None);
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(cache.get("lookup", &json!({ "a": 1, "b": [1, 2] })), None);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 3,
+                entries: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn evicts_oldest_when_full() {
+        let cache = ResultCache::new(Duration::from_secs(60), 2);
+        cache.insert("t", &json!(1), json!("one"));
+        std::thread::sleep(Duration::from_millis(1));
+        cache.insert("t", &json!(2), json!("two"));
+        std::thread::sleep(Duration::from_millis(1));
+        cache.insert("t", &json!(3), json!("three"));
+        assert_eq!(cache.get("t", &json!(1)), None);
+        assert_eq!(cache.get("t", &json!(3)), Some(json!("three")));
+    }
+
+    #[test]
+    fn reads_read_only_hint() {
+        let tool: Tool = serde_json::from_value(json!({
+            "name": "lookup",
+            "annotations": { "readOnlyHint": true },
+        }))
+        .expect("tool");
+        assert!(is_read_only(&tool));
+        let tool: Tool = serde_json::from_value(json!({ "name": "write" })).expect("tool");
+        assert!(!is_read_only(&tool));
+    }
+}
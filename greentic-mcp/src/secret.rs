@@ -0,0 +1,170 @@
+//! Credentials that must not leak into logs, errors, or wire taps.
+//!
+//! API keys, bearer tokens, and OAuth tokens are held as [`Secret`]s from the
+//! moment they are configured or fetched. A `Secret` prints as [`MASK`] through
+//! `Debug` and `Display`, wipes its memory when dropped, and only yields its
+//! value through [`Secret::expose`], at the point where it goes on the wire.
+//! [`redact`] and [`redact_json`] scrub text and JSON that may still carry a
+//! credential, such as transport error messages and tapped frames.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use zeroize::Zeroize;
+
+/// Placeholder shown instead of a credential.
+pub const MASK: &str = "***";
+
+/// A credential value that never prints itself.
+///
+/// Serialization keeps the value so configurations round-trip; do not log
+/// serialized configurations.
+#[derive(Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// The credential itself, for placing it in a request.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({MASK})")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(MASK)
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Replace every occurrence of the `secrets` in `text` with [`MASK`].
+pub fn redact<'a>(text: &str, secrets: impl IntoIterator<Item = &'a Secret>) -> String {
+    let mut redacted = text.to_string();
+    for secret in secrets {
+        if !secret.expose().is_empty() {
+            redacted = redacted.replace(secret.expose(), MASK);
+        }
+    }
+    redacted
+}
+
+/// Field names treated as credentials wherever they appear in JSON, compared
+/// case-insensitively and ignoring `-` and `_`.
+const SENSITIVE_KEYS: &[&str] = &[
+    "accesstoken",
+    "apikey",
+    "authorization",
+    "bearertoken",
+    "clientsecret",
+    "idtoken",
+    "password",
+    "refreshtoken",
+    "secret",
+    "token",
+    "xapikey",
+];
+
+/// Whether a JSON field or header named `key` holds a credential.
+pub fn is_sensitive_key(key: &str) -> bool {
+    let normalized: String = key
+        .chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    SENSITIVE_KEYS.contains(&normalized.as_str())
+}
+
+/// Mask the values of credential fields anywhere in `value`.
+pub fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if is_sensitive_key(key) && !field.is_null() {
+                    *field = Value::String(MASK.into());
+                } else {
+                    redact_json(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn secrets_print_masked_and_round_trip() {
+        let secret = Secret::from("sk-live-123");
+        assert_eq!(format!("{secret}"), MASK);
+        assert_eq!(format!("{secret:?}"), "Secret(***)");
+        assert_eq!(secret.expose(), "sk-live-123");
+
+        let encoded = serde_json::to_value(&secret).expect("serialize");
+        assert_eq!(encoded, json!("sk-live-123"));
+        let decoded: Secret = serde_json::from_value(encoded).expect("deserialize");
+        assert_eq!(decoded, secret);
+    }
+
+    #[test]
+    fn redacts_text_and_credential_fields() {
+        let token = Secret::from("tok-42");
+        assert_eq!(
+            redact(
+                "401 for Bearer tok-42 (tok-42)",
+                [&token, &Secret::default()]
+            ),
+            "401 for Bearer *** (***)"
+        );
+
+        let mut frame = json!({
+            "params": {
+                "arguments": { "query": "q", "API_KEY": "k", "nested": [{ "access-token": "t" }] },
+                "headers": { "Authorization": "Bearer t" },
+                "token": null
+            }
+        });
+        redact_json(&mut frame);
+        assert_eq!(
+            frame,
+            json!({
+                "params": {
+                    "arguments": { "query": "q", "API_KEY": MASK, "nested": [{ "access-token": MASK }] },
+                    "headers": { "Authorization": MASK },
+                    "token": null
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,383 @@
+//! Limits for hosts serving MCP sessions over Streamable HTTP.
+//!
+//! A host that answers `POST`s and streams SSE for the sessions in a
+//! [`SessionStore`](crate::SessionStore) reads each inbound message through
+//! [`SessionGuard::admit`], and writes each session's SSE events through an
+//! [`EventSender`]. Between them one client cannot exhaust the server's
+//! memory:
+//!
+//! - a message over [`ServeLimits::max_message_bytes`] is refused after
+//!   reading at most one byte past the limit;
+//! - a session may have at most [`ServeLimits::max_concurrent_requests`]
+//!   requests in flight;
+//! - events wait at most [`ServeLimits::write_timeout`] for a slow consumer to
+//!   make room among the stream's [`ServeLimits::max_buffered_events`], after
+//!   which the stream is closed.
+//!
+//! Refused messages come back as JSON-RPC error responses the host sends as
+//! the reply to the request.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::SendTimeoutError;
+
+use crate::protocol::{McpResponse, RpcError};
+use crate::types::McpError;
+
+/// JSON-RPC code for a message over [`ServeLimits::max_message_bytes`].
+pub const MESSAGE_TOO_LARGE: i64 = -32600;
+/// JSON-RPC code for a request past [`ServeLimits::max_concurrent_requests`].
+pub const TOO_MANY_REQUESTS: i64 = -32000;
+
+/// Limits applied to every served session.
+#[derive(Clone, Debug)]
+pub struct ServeLimits {
+    /// Largest inbound message, in bytes.
+    pub max_message_bytes: usize,
+    /// Requests one session may have in flight at once.
+    pub max_concurrent_requests: usize,
+    /// How long an event may wait for room in a session's SSE stream.
+    pub write_timeout: Duration,
+    /// Events an SSE stream holds for its consumer.
+    pub max_buffered_events: usize,
+}
+
+impl Default for ServeLimits {
+    fn default() -> Self {
+        Self {
+            max_message_bytes: 4 * 1024 * 1024,
+            max_concurrent_requests: 16,
+            write_timeout: Duration::from_secs(30),
+            max_buffered_events: 256,
+        }
+    }
+}
+
+/// Admits inbound messages to served sessions under [`ServeLimits`].
+pub struct SessionGuard {
+    limits: ServeLimits,
+    in_flight: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl SessionGuard {
+    pub fn new(limits: ServeLimits) -> Self {
+        Self {
+            limits,
+            in_flight: Arc::default(),
+        }
+    }
+
+    pub fn limits(&self) -> &ServeLimits {
+        &self.limits
+    }
+
+    /// Read one message for `session` from `body`, or return the error
+    /// response to send back.
+    ///
+    /// A `content_length` over the limit is refused without reading; otherwise
+    /// at most one byte past [`ServeLimits::max_message_bytes`] is read, so a
+    /// body without a length cannot grow the buffer either. The permit holds
+    /// one of the session's request slots until it is dropped, so keep it
+    /// until the response has been written.
+    pub fn admit(
+        &self,
+        session: &str,
+        content_length: Option<u64>,
+        body: impl Read,
+    ) -> Result<Admitted, Box<McpResponse>> {
+        let max = self.limits.max_message_bytes;
+        let too_large = |len: u64| {
+            error_response(
+                Value::Null,
+                MESSAGE_TOO_LARGE,
+                format!("message of {len} bytes exceeds the limit of {max} bytes"),
+            )
+        };
+        if let Some(len) = content_length.filter(|&len| len > max as u64) {
+            return Err(too_large(len));
+        }
+        let mut message = Vec::new();
+        if let Err(err) = body.take(max as u64 + 1).read_to_end(&mut message) {
+            return Err(error_response(
+                Value::Null,
+                MESSAGE_TOO_LARGE,
+                format!("reading the message failed: {err}"),
+            ));
+        }
+        if message.len() > max {
+            return Err(too_large(message.len() as u64));
+        }
+
+        let mut in_flight = lock(&self.in_flight);
+        let count = in_flight.entry(session.to_string()).or_default();
+        if *count >= self.limits.max_concurrent_requests {
+            if *count == 0 {
+                in_flight.remove(session);
+            }
+            return Err(error_response(
+                request_id(&message),
+                TOO_MANY_REQUESTS,
+                format!(
+                    "session already has {} requests in flight",
+                    self.limits.max_concurrent_requests
+                ),
+            ));
+        }
+        *count += 1;
+        Ok(Admitted {
+            message,
+            permit: RequestPermit {
+                session: session.to_string(),
+                in_flight: Arc::clone(&self.in_flight),
+            },
+        })
+    }
+
+    /// Requests currently in flight for `session`.
+    pub fn in_flight(&self, session: &str) -> usize {
+        lock(&self.in_flight).get(session).copied().unwrap_or(0)
+    }
+}
+
+/// A message read by [`SessionGuard::admit`], with the slot it holds.
+pub struct Admitted {
+    pub message: Vec<u8>,
+    pub permit: RequestPermit,
+}
+
+/// One admitted request; releases its session slot when dropped.
+pub struct RequestPermit {
+    session: String,
+    in_flight: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl Drop for RequestPermit {
+    fn drop(&mut self) {
+        let mut in_flight = lock(&self.in_flight);
+        if let Some(count) = in_flight.get_mut(&self.session) {
+            *count -= 1;
+            if *count == 0 {
+                in_flight.remove(&self.session);
+            }
+        }
+    }
+}
+
+/// The id of a JSON-RPC request, or `null` when it has none or is not JSON.
+fn request_id(message: &[u8]) -> Value {
+    #[derive(Deserialize)]
+    struct Envelope {
+        #[serde(default)]
+        id: Value,
+    }
+    serde_json::from_slice::<Envelope>(message).map_or(Value::Null, |envelope| envelope.id)
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Box<McpResponse> {
+    Box::new(McpResponse {
+        jsonrpc: "2.0".into(),
+        id,
+        result: None,
+        error: Some(RpcError {
+            code,
+            message,
+            data: None,
+            extra: BTreeMap::new(),
+        }),
+        extra: BTreeMap::new(),
+    })
+}
+
+/// Open an SSE stream for one session: handlers send events, and the
+/// connection writing to the client receives them as encoded SSE frames.
+pub fn event_stream(limits: &ServeLimits) -> (EventSender, EventReceiver) {
+    let (frames, receiver) = mpsc::channel(limits.max_buffered_events.max(1));
+    let closed = Arc::new(AtomicBool::new(false));
+    (
+        EventSender {
+            frames,
+            closed: Arc::clone(&closed),
+            write_timeout: limits.write_timeout,
+        },
+        EventReceiver {
+            frames: receiver,
+            closed,
+        },
+    )
+}
+
+/// Writing end of an SSE stream; clones share the stream.
+#[derive(Clone)]
+pub struct EventSender {
+    frames: mpsc::Sender<Vec<u8>>,
+    /// Set when the consumer fell too far behind.
+    closed: Arc<AtomicBool>,
+    write_timeout: Duration,
+}
+
+impl EventSender {
+    /// Queue `event` as a `message` frame.
+    ///
+    /// Waits up to the write timeout for the consumer to make room. A consumer
+    /// that does not is too slow to keep: the stream is closed and this and
+    /// every later send fail.
+    pub async fn send(&self, event: &Value) -> Result<(), McpError> {
+        if !self.is_open() {
+            return Err(io::Error::from(io::ErrorKind::BrokenPipe).into());
+        }
+        let frame = format!("event: message\ndata: {event}\n\n").into_bytes();
+        match self.frames.send_timeout(frame, self.write_timeout).await {
+            Ok(()) => Ok(()),
+            Err(SendTimeoutError::Timeout(_)) => {
+                self.closed.store(true, Ordering::Release);
+                Err(McpError::timeout("sse stream", self.write_timeout))
+            }
+            Err(SendTimeoutError::Closed(_)) => {
+                Err(io::Error::from(io::ErrorKind::BrokenPipe).into())
+            }
+        }
+    }
+
+    /// Whether the stream still has a consumer keeping up with it.
+    pub fn is_open(&self) -> bool {
+        !self.closed.load(Ordering::Acquire) && !self.frames.is_closed()
+    }
+}
+
+/// Reading end of an SSE stream, held by the connection to the client.
+/// Dropping it closes the stream.
+pub struct EventReceiver {
+    frames: mpsc::Receiver<Vec<u8>>,
+    closed: Arc<AtomicBool>,
+}
+
+impl EventReceiver {
+    /// The next encoded frame, waiting for one; `None` once every sender is
+    /// gone and the queue is drained, or the stream was closed.
+    pub async fn next_frame(&mut self) -> Option<Vec<u8>> {
+        if self.closed.load(Ordering::Acquire) {
+            self.frames.close();
+            return None;
+        }
+        self.frames.recv().await
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Instant;
+
+    #[test]
+    fn refuses_oversized_messages_and_excess_concurrency() {
+        let guard = SessionGuard::new(ServeLimits {
+            max_message_bytes: 64,
+            max_concurrent_requests: 2,
+            ..ServeLimits::default()
+        });
+        let request = |id: u64| {
+            serde_json::to_vec(&json!({ "jsonrpc": "2.0", "id": id, "method": "ping" }))
+                .expect("encode")
+        };
+        let admit = |session: &str, message: &[u8]| {
+            guard.admit(session, Some(message.len() as u64), message)
+        };
+
+        let oversized = admit("s1", &[b' '; 65]).err().expect("too large");
+        assert_eq!(oversized.error.expect("error").code, MESSAGE_TOO_LARGE);
+        assert_eq!(oversized.id, Value::Null);
+
+        let first = admit("s1", &request(1)).expect("first");
+        assert_eq!(first.message, request(1));
+        let _second = admit("s1", &request(2)).expect("second");
+        let refused = admit("s1", &request(3)).err().expect("third");
+        assert_eq!(refused.id, json!(3));
+        assert_eq!(refused.error.expect("error").code, TOO_MANY_REQUESTS);
+        // Other sessions have their own slots.
+        let _other = admit("s2", &request(4)).expect("other session");
+
+        drop(first);
+        assert_eq!(guard.in_flight("s1"), 1);
+        admit("s1", &request(5)).expect("slot released");
+    }
+
+    #[test]
+    fn bodies_without_a_length_are_read_one_byte_past_the_limit() {
+        let guard = SessionGuard::new(ServeLimits {
+            max_message_bytes: 64,
+            ..ServeLimits::default()
+        });
+        // Stands in for a chunked body that never ends.
+        let mut endless = io::repeat(b' ');
+
+        let refused = guard
+            .admit("s1", None, &mut endless)
+            .err()
+            .expect("too large");
+        let error = refused.error.expect("error");
+        assert_eq!(error.code, MESSAGE_TOO_LARGE);
+        assert!(error.message.contains("65 bytes"), "{}", error.message);
+
+        let advertised = guard
+            .admit("s1", Some(1 << 40), &mut endless)
+            .err()
+            .expect("advertised too large");
+        assert_eq!(advertised.error.expect("error").code, MESSAGE_TOO_LARGE);
+        assert_eq!(guard.in_flight("s1"), 0);
+    }
+
+    #[tokio::test]
+    async fn slow_consumers_are_cut_off_after_the_write_timeout() {
+        let (sender, mut receiver) = event_stream(&ServeLimits {
+            write_timeout: Duration::from_millis(50),
+            max_buffered_events: 1,
+            ..ServeLimits::default()
+        });
+        let event = json!({ "jsonrpc": "2.0", "method": "notifications/progress" });
+
+        // The first frame fits; the second waits for room that never comes.
+        sender.send(&event).await.expect("buffered");
+        let started = Instant::now();
+        let err = sender.send(&event).await.expect_err("consumer too slow");
+        assert!(matches!(err, McpError::Timeout { .. }), "got {err}");
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert!(!sender.is_open());
+        assert!(receiver.next_frame().await.is_none());
+        assert!(sender.send(&event).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn consumers_keeping_up_receive_every_frame() {
+        let (sender, mut receiver) = event_stream(&ServeLimits {
+            write_timeout: Duration::from_secs(5),
+            max_buffered_events: 2,
+            ..ServeLimits::default()
+        });
+        let producer = tokio::spawn(async move {
+            for index in 0..20 {
+                sender.send(&json!({ "n": index })).await.expect("send");
+            }
+        });
+        let mut frames = Vec::new();
+        while let Some(frame) = receiver.next_frame().await {
+            frames.push(String::from_utf8(frame).expect("utf-8"));
+        }
+        producer.await.expect("producer");
+        assert_eq!(frames.len(), 20);
+        assert_eq!(frames[3], "event: message\ndata: {\"n\":3}\n\n");
+    }
+}
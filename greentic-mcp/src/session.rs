@@ -0,0 +1,589 @@
+//! Session state for serving MCP over Streamable HTTP.
+//!
+//! Servers assign an `Mcp-Session-Id` at `initialize` and look the session up
+//! on every later request. Keeping that state behind [`SessionStore`] lets a
+//! deployment swap the in-process [`InMemorySessionStore`] for a shared
+//! backend, so sessions survive restarts and any replica behind a load
+//! balancer can serve them. Sessions are plain serde values to make such
+//! backends a matter of storing JSON under the session id.
+//!
+//! With the `file-sessions` feature, [`FileSessionStore`] keeps one JSON file
+//! per session in a directory, so sessions outlive the process and replicas
+//! sharing the directory serve the same sessions.
+//!
+//! With the `redis-sessions` feature, [`RedisSessionStore`] keeps each session
+//! as JSON under a key in Redis, which expires idle sessions itself.
+
+use std::collections::{BTreeMap, HashMap};
+#[cfg(feature = "file-sessions")]
+use std::fs;
+#[cfg(feature = "file-sessions")]
+use std::io::ErrorKind;
+#[cfg(feature = "file-sessions")]
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rand::distr::{Distribution, Uniform};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::protocol::ProtocolRevision;
+use crate::types::McpError;
+
+/// HTTP header carrying the session id.
+pub const SESSION_HEADER: &str = "Mcp-Session-Id";
+
+/// State of one client session.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    /// Protocol revision negotiated at `initialize`.
+    pub protocol: ProtocolRevision,
+    /// `clientInfo` sent by the client, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_info: Option<Value>,
+    /// Seconds since the Unix epoch.
+    pub created_at: u64,
+    /// Seconds since the Unix epoch of the last request in this session.
+    pub last_seen: u64,
+    /// Server-defined per-session values.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub data: BTreeMap<String, Value>,
+}
+
+impl Session {
+    /// Start a session with a freshly generated id.
+    pub fn new(protocol: ProtocolRevision) -> Self {
+        let now = now_secs();
+        Self {
+            id: generate_id(),
+            protocol,
+            client_info: None,
+            created_at: now,
+            last_seen: now,
+            data: BTreeMap::new(),
+        }
+    }
+}
+
+/// Storage for served sessions, keyed by `Mcp-Session-Id`.
+pub trait SessionStore: Send + Sync {
+    /// Fetch a live session; unknown and expired ids yield `None`.
+    fn load(&self, id: &str) -> Result<Option<Session>, McpError>;
+
+    /// Insert or replace a session.
+    fn save(&self, session: &Session) -> Result<(), McpError>;
+
+    /// Forget a session, e.g. when the client sends `DELETE`.
+    fn remove(&self, id: &str) -> Result<(), McpError>;
+
+    /// Load a session and record activity on it.
+    fn touch(&self, id: &str) -> Result<Option<Session>, McpError> {
+        let Some(mut session) = self.load(id)? else {
+            return Ok(None);
+        };
+        session.last_seen = now_secs();
+        self.save(&session)?;
+        Ok(Some(session))
+    }
+}
+
+/// Process-local session store, optionally expiring idle sessions.
+///
+/// Expired sessions are dropped when they are next loaded, and by a sweep over
+/// every session at most once per idle timeout, run by `save`; sessions a
+/// client abandoned therefore do not pile up.
+#[derive(Default)]
+pub struct InMemorySessionStore {
+    sessions: Mutex<Sessions>,
+    idle_timeout: Option<Duration>,
+}
+
+#[derive(Default)]
+struct Sessions {
+    by_id: HashMap<String, Session>,
+    /// Seconds since the Unix epoch of the last sweep.
+    swept_at: u64,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expire sessions that have been idle for longer than `timeout`.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Number of sessions currently held, including expired ones not yet
+    /// dropped.
+    pub fn len(&self) -> usize {
+        self.lock().by_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().by_id.is_empty()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Sessions> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl SessionStore for InMemorySessionStore {
+    fn load(&self, id: &str) -> Result<Option<Session>, McpError> {
+        let mut sessions = self.lock();
+        match sessions.by_id.get(id) {
+            Some(session) if expired(self.idle_timeout, session) => {
+                sessions.by_id.remove(id);
+                Ok(None)
+            }
+            other => Ok(other.cloned()),
+        }
+    }
+
+    fn save(&self, session: &Session) -> Result<(), McpError> {
+        let mut sessions = self.lock();
+        if let Some(timeout) = self.idle_timeout {
+            let now = now_secs();
+            if now.saturating_sub(sessions.swept_at) >= timeout.as_secs() {
+                sessions.swept_at = now;
+                sessions
+                    .by_id
+                    .retain(|_, session| !expired(Some(timeout), session));
+            }
+        }
+        sessions.by_id.insert(session.id.clone(), session.clone());
+        Ok(())
+    }
+
+    fn remove(&self, id: &str) -> Result<(), McpError> {
+        self.lock().by_id.remove(id);
+        Ok(())
+    }
+}
+
+/// Session store keeping each session as `<id>.json` in a directory,
+/// optionally expiring idle sessions.
+///
+/// Writes go through a temporary file and a rename, so a concurrent load sees
+/// either the old or the new session. Ids other than ASCII letters, digits,
+/// `-`, and `_` never name a file: they load as unknown and cannot be saved.
+#[cfg(feature = "file-sessions")]
+pub struct FileSessionStore {
+    dir: PathBuf,
+    idle_timeout: Option<Duration>,
+}
+
+#[cfg(feature = "file-sessions")]
+impl FileSessionStore {
+    /// Keep sessions in `dir`, creating it if it does not exist.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, McpError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            idle_timeout: None,
+        })
+    }
+
+    /// Expire sessions that have been idle for longer than `timeout`.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// The file of session `id`, or `None` if `id` cannot name one.
+    fn path(&self, id: &str) -> Option<PathBuf> {
+        let valid = !id.is_empty()
+            && id.len() <= 128
+            && id
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_');
+        valid.then(|| self.dir.join(format!("{id}.json")))
+    }
+}
+
+#[cfg(feature = "file-sessions")]
+impl SessionStore for FileSessionStore {
+    fn load(&self, id: &str) -> Result<Option<Session>, McpError> {
+        let Some(path) = self.path(id) else {
+            return Ok(None);
+        };
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let session: Session = serde_json::from_slice(&bytes)?;
+        if expired(self.idle_timeout, &session) {
+            self.remove(id)?;
+            return Ok(None);
+        }
+        Ok(Some(session))
+    }
+
+    fn save(&self, session: &Session) -> Result<(), McpError> {
+        let path = self.path(&session.id).ok_or_else(|| {
+            McpError::InvalidInput(format!("session id `{}` cannot name a file", session.id))
+        })?;
+        let staged = self
+            .dir
+            .join(format!(".{}.{}.tmp", session.id, generate_id()));
+        fs::write(&staged, serde_json::to_vec(session)?)?;
+        fs::rename(&staged, &path).inspect_err(|_| {
+            let _ = fs::remove_file(&staged);
+        })?;
+        Ok(())
+    }
+
+    fn remove(&self, id: &str) -> Result<(), McpError> {
+        let Some(path) = self.path(id) else {
+            return Ok(());
+        };
+        match fs::remove_file(path) {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Session store keeping each session as JSON under `<prefix><id>` in Redis,
+/// optionally expiring idle sessions.
+///
+/// Every save sets the key's TTL to the idle timeout, so Redis drops
+/// abandoned sessions itself. One connection is kept and reopened after an
+/// error; failures surface as [`McpError::Transient`].
+#[cfg(feature = "redis-sessions")]
+pub struct RedisSessionStore {
+    client: redis::Client,
+    connection: Mutex<Option<redis::Connection>>,
+    prefix: String,
+    idle_timeout: Option<Duration>,
+}
+
+#[cfg(feature = "redis-sessions")]
+impl RedisSessionStore {
+    /// Keep sessions in the Redis server at `url`, e.g. `redis://cache:6379/0`.
+    /// The server is not contacted until the first request.
+    pub fn new(url: &str) -> Result<Self, McpError> {
+        let client = redis::Client::open(url)
+            .map_err(|err| McpError::InvalidInput(format!("redis url `{url}`: {err}")))?;
+        Ok(Self {
+            client,
+            connection: Mutex::new(None),
+            prefix: "mcp-session:".into(),
+            idle_timeout: None,
+        })
+    }
+
+    /// Prefix of the session keys, `mcp-session:` by default.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Expire sessions that have been idle for longer than `timeout`.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    fn key(&self, id: &str) -> String {
+        format!("{}{id}", self.prefix)
+    }
+
+    /// Run `op` on the kept connection, opening one if needed.
+    fn with_connection<T>(
+        &self,
+        op: impl FnOnce(&mut redis::Connection) -> redis::RedisResult<T>,
+    ) -> Result<T, McpError> {
+        let mut slot = self
+            .connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let connection = match slot.as_mut() {
+            Some(connection) => connection,
+            None => slot.insert(self.client.get_connection().map_err(redis_error)?),
+        };
+        op(connection).map_err(|err| {
+            // The connection may be mid-reply; start afresh next time.
+            *slot = None;
+            redis_error(err)
+        })
+    }
+}
+
+#[cfg(feature = "redis-sessions")]
+impl SessionStore for RedisSessionStore {
+    fn load(&self, id: &str) -> Result<Option<Session>, McpError> {
+        use redis::Commands;
+
+        let key = self.key(id);
+        let Some(bytes) = self.with_connection(|conn| conn.get::<_, Option<Vec<u8>>>(&key))? else {
+            return Ok(None);
+        };
+        let session: Session = serde_json::from_slice(&bytes)?;
+        if expired(self.idle_timeout, &session) {
+            self.remove(id)?;
+            return Ok(None);
+        }
+        Ok(Some(session))
+    }
+
+    fn save(&self, session: &Session) -> Result<(), McpError> {
+        use redis::Commands;
+
+        let key = self.key(&session.id);
+        let bytes = serde_json::to_vec(session)?;
+        self.with_connection(|conn| match self.idle_timeout {
+            Some(timeout) => conn.set_ex(&key, bytes, timeout.as_secs().max(1)),
+            None => conn.set(&key, bytes),
+        })
+    }
+
+    fn remove(&self, id: &str) -> Result<(), McpError> {
+        use redis::Commands;
+
+        let key = self.key(id);
+        self.with_connection(|conn| conn.del(&key))
+    }
+}
+
+#[cfg(feature = "redis-sessions")]
+fn redis_error(err: redis::RedisError) -> McpError {
+    McpError::Transient("redis session store".into(), err.to_string())
+}
+
+/// Whether `session` has been idle for longer than `idle_timeout`.
+fn expired(idle_timeout: Option<Duration>, session: &Session) -> bool {
+    idle_timeout
+        .is_some_and(|timeout| now_secs().saturating_sub(session.last_seen) > timeout.as_secs())
+}
+
+pub(crate) fn generate_id() -> String {
+    let uniform = Uniform::new_inclusive(0u64, u64::MAX).expect("valid id bounds");
+    let mut rng = rand::rng();
+    format!(
+        "{:016x}{:016x}",
+        uniform.sample(&mut rng),
+        uniform.sample(&mut rng)
+    )
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn sessions_round_trip_and_expire() {
+        let store = InMemorySessionStore::new().with_idle_timeout(Duration::from_secs(60));
+        let mut session = Session::new(ProtocolRevision::V2025_06_18);
+        session.data.insert("cursor".into(), json!("abc"));
+        store.save(&session).expect("save");
+        assert_eq!(session.id.len(), 32);
+
+        let loaded = store.touch(&session.id).expect("touch").expect("live");
+        assert_eq!(loaded.data["cursor"], "abc");
+
+        let mut stale = Session::new(ProtocolRevision::V2025_03_26);
+        stale.last_seen -= 120;
+        store.save(&stale).expect("save");
+        assert!(store.load(&stale.id).expect("load").is_none());
+        assert_eq!(store.len(), 1);
+
+        store.remove(&session.id).expect("remove");
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn saves_sweep_out_abandoned_sessions() {
+        let store = InMemorySessionStore::new().with_idle_timeout(Duration::from_secs(60));
+        let mut abandoned = Session::new(ProtocolRevision::V2025_06_18);
+        abandoned.last_seen -= 120;
+        store.save(&abandoned).expect("save");
+        assert_eq!(store.len(), 1, "nothing to sweep before it was saved");
+
+        // Not a full timeout since the last sweep: the save does not walk.
+        store
+            .save(&Session::new(ProtocolRevision::V2025_06_18))
+            .expect("save");
+        assert_eq!(store.len(), 2);
+
+        store.lock().swept_at -= 60;
+        store
+            .save(&Session::new(ProtocolRevision::V2025_06_18))
+            .expect("save");
+        assert_eq!(store.len(), 2);
+        assert!(!store.lock().by_id.contains_key(&abandoned.id));
+    }
+
+    #[cfg(feature = "file-sessions")]
+    #[test]
+    fn file_sessions_outlive_the_store_and_expire() {
+        let dir = std::env::temp_dir().join(format!("mcp-sessions-{}", generate_id()));
+        let store = FileSessionStore::new(&dir).expect("store");
+        let mut session = Session::new(ProtocolRevision::V2025_06_18);
+        session.data.insert("cursor".into(), json!("abc"));
+        store.save(&session).expect("save");
+
+        // A second store over the same directory, as after a restart.
+        let reopened = FileSessionStore::new(&dir)
+            .expect("reopen")
+            .with_idle_timeout(Duration::from_secs(60));
+        let loaded = reopened.touch(&session.id).expect("touch").expect("live");
+        assert_eq!(loaded.data["cursor"], "abc");
+
+        let mut stale = Session::new(ProtocolRevision::V2025_03_26);
+        stale.last_seen -= 120;
+        reopened.save(&stale).expect("save");
+        assert!(reopened.load(&stale.id).expect("load").is_none());
+        assert!(!dir.join(format!("{}.json", stale.id)).exists());
+
+        assert!(reopened.load("../escape").expect("load").is_none());
+        stale.id = "../escape".into();
+        assert!(matches!(
+            reopened.save(&stale),
+            Err(McpError::InvalidInput(_))
+        ));
+
+        reopened.remove(&session.id).expect("remove");
+        assert!(store.load(&session.id).expect("load").is_none());
+        assert_eq!(fs::read_dir(&dir).expect("dir").count(), 0);
+        fs::remove_dir(&dir).expect("cleanup");
+    }
+
+    /// Serve the few Redis commands the store sends from a map, over RESP2,
+    /// and log them.
+    #[cfg(feature = "redis-sessions")]
+    fn fake_redis() -> (String, std::sync::Arc<Mutex<Vec<Vec<String>>>>) {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+
+        fn read_line(reader: &mut impl BufRead) -> Option<String> {
+            let mut line = String::new();
+            reader.read_line(&mut line).ok().filter(|&read| read > 0)?;
+            Some(line.trim_end().to_string())
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("redis://{}", listener.local_addr().expect("addr"));
+        let log = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let commands = std::sync::Arc::clone(&log);
+        std::thread::spawn(move || {
+            let mut values: HashMap<String, Vec<u8>> = HashMap::new();
+            for stream in listener.incoming() {
+                let mut stream = stream.expect("accept");
+                let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+                while let Some(header) = read_line(&mut reader) {
+                    let count: usize = header[1..].parse().expect("array length");
+                    let mut args = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        let len: usize = read_line(&mut reader).expect("bulk")[1..]
+                            .parse()
+                            .expect("bulk length");
+                        let mut arg = vec![0; len + 2];
+                        reader.read_exact(&mut arg).expect("bulk body");
+                        arg.truncate(len);
+                        args.push(arg);
+                    }
+                    let name = String::from_utf8_lossy(&args[0]).to_uppercase();
+                    let key = String::from_utf8_lossy(args.get(1).map_or(&[][..], |arg| arg))
+                        .into_owned();
+                    let reply = match name.as_str() {
+                        "GET" => match values.get(&key) {
+                            Some(value) => {
+                                let mut reply = format!("${}\r\n", value.len()).into_bytes();
+                                reply.extend_from_slice(value);
+                                reply.extend_from_slice(b"\r\n");
+                                reply
+                            }
+                            None => b"$-1\r\n".to_vec(),
+                        },
+                        "SET" | "SETEX" => {
+                            values.insert(key, args[args.len() - 1].clone());
+                            b"+OK\r\n".to_vec()
+                        }
+                        "DEL" => {
+                            format!(":{}\r\n", u8::from(values.remove(&key).is_some())).into_bytes()
+                        }
+                        _ => b"+OK\r\n".to_vec(),
+                    };
+                    commands.lock().expect("log").push(
+                        args.iter()
+                            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                            .collect(),
+                    );
+                    stream.write_all(&reply).expect("reply");
+                }
+            }
+        });
+        (url, log)
+    }
+
+    #[cfg(feature = "redis-sessions")]
+    #[test]
+    fn redis_sessions_carry_a_ttl_and_expire() {
+        let (url, log) = fake_redis();
+        let store = RedisSessionStore::new(&url)
+            .expect("store")
+            .with_prefix("test:")
+            .with_idle_timeout(Duration::from_secs(60));
+        let mut session = Session::new(ProtocolRevision::V2025_06_18);
+        session.data.insert("cursor".into(), json!("abc"));
+        store.save(&session).expect("save");
+        let loaded = store.touch(&session.id).expect("touch").expect("live");
+        assert_eq!(loaded.data["cursor"], "abc");
+
+        let mut stale = Session::new(ProtocolRevision::V2025_03_26);
+        stale.last_seen -= 120;
+        store.save(&stale).expect("save");
+        assert!(store.load(&stale.id).expect("load").is_none());
+        store.remove(&session.id).expect("remove");
+        assert!(store.load(&session.id).expect("load").is_none());
+
+        let log = log.lock().expect("log");
+        let set = log
+            .iter()
+            .find(|command| command[0] == "SETEX")
+            .expect("a SETEX");
+        assert_eq!(set[1], format!("test:{}", session.id));
+        assert_eq!(set[2], "60");
+        assert!(log.contains(&vec!["DEL".to_string(), format!("test:{}", stale.id)]));
+    }
+
+    #[cfg(feature = "redis-sessions")]
+    #[test]
+    fn unreachable_redis_is_a_transient_error() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("redis://{}", listener.local_addr().expect("addr"));
+        drop(listener);
+        let store = RedisSessionStore::new(&url).expect("store");
+        assert!(matches!(store.load("abc"), Err(McpError::Transient(..))));
+        assert!(matches!(
+            RedisSessionStore::new("not a url"),
+            Err(McpError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn sessions_serialize_for_shared_backends() {
+        let session = Session::new(ProtocolRevision::V2025_06_18);
+        let encoded = serde_json::to_value(&session).expect("encode");
+        assert_eq!(encoded["protocol"], "2025-06-18");
+        let decoded: Session = serde_json::from_value(encoded).expect("decode");
+        assert_eq!(decoded, session);
+    }
+}
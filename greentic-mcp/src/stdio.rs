@@ -0,0 +1,380 @@
+//! Transport for MCP servers spawned as local child processes.
+//!
+//! [`StdioTransport`] speaks newline-delimited JSON-RPC over the child's
+//! stdin/stdout. Locally spawned servers are often third-party binaries, so
+//! [`StdioSandbox`] can start them with a cleared environment, a fixed working
+//! directory, and (on Unix) CPU and address-space limits.
+
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::codec;
+use crate::remote::RemoteTransport;
+use crate::secret::Secret;
+use crate::types::McpError;
+
+/// Restrictions applied to a spawned server process.
+#[derive(Clone, Debug, Default)]
+pub struct StdioSandbox {
+    /// When set, the child starts with an empty environment plus only these
+    /// variables copied from the parent; otherwise it inherits everything.
+    pub env_allowlist: Option<Vec<String>>,
+    /// Variables set explicitly on the child, applied after the allowlist.
+    /// Values are [`Secret`]s since they commonly carry the server's API keys.
+    pub env: BTreeMap<String, Secret>,
+    /// Working directory for the child. This only sets the starting
+    /// directory; it is not a chroot.
+    pub working_dir: Option<PathBuf>,
+    /// CPU time limit in seconds (`RLIMIT_CPU`, Unix only).
+    pub max_cpu_seconds: Option<u64>,
+    /// Address-space limit in bytes (`RLIMIT_AS`, Unix only).
+    pub max_memory_bytes: Option<u64>,
+}
+
+impl StdioSandbox {
+    fn apply(&self, command: &mut Command) -> Result<(), McpError> {
+        if let Some(allowlist) = &self.env_allowlist {
+            command.env_clear();
+            for name in allowlist {
+                if let Some(value) = std::env::var_os(name) {
+                    command.env(name, value);
+                }
+            }
+        }
+        for (name, value) in &self.env {
+            command.env(name, value.expose());
+        }
+
+        if let Some(dir) = &self.working_dir {
+            if !dir.is_dir() {
+                return Err(McpError::InvalidInput(format!(
+                    "sandbox working directory `{}` does not exist",
+                    dir.display()
+                )));
+            }
+            command.current_dir(dir);
+        }
+
+        self.apply_limits(command)
+    }
+
+    #[cfg(unix)]
+    fn apply_limits(&self, command: &mut Command) -> Result<(), McpError> {
+        use std::os::unix::process::CommandExt;
+
+        let (cpu, memory) = (self.max_cpu_seconds, self.max_memory_bytes);
+        if cpu.is_none() && memory.is_none() {
+            return Ok(());
+        }
+        let rlimit = |value: u64| libc::rlimit {
+            rlim_cur: value as libc::rlim_t,
+            rlim_max: value as libc::rlim_t,
+        };
+        // SAFETY: the hook runs between fork and exec and only calls
+        // setrlimit, which is async-signal-safe, on stack-local values.
+        unsafe {
+            command.pre_exec(move || {
+                if let Some(seconds) = cpu
+                    && libc::setrlimit(libc::RLIMIT_CPU, &rlimit(seconds)) != 0
+                {
+                    return Err(std::io::Error::last_os_error());
+                }
+                if let Some(bytes) = memory
+                    && libc::setrlimit(libc::RLIMIT_AS, &rlimit(bytes)) != 0
+                {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn apply_limits(&self, _command: &mut Command) -> Result<(), McpError> {
+        if self.max_cpu_seconds.is_some() || self.max_memory_bytes.is_some() {
+            return Err(McpError::InvalidInput(
+                "process resource limits are only supported on Unix".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Newline-delimited JSON-RPC transport to a child process.
+pub struct StdioTransport {
+    program: String,
+    launch: Launch,
+    child: Mutex<Child>,
+    stdin: Mutex<Option<ChildStdin>>,
+    stdout: Mutex<BufReader<ChildStdout>>,
+    grace_period: Duration,
+}
+
+/// Everything needed to start (or restart) the server process.
+struct Launch {
+    program: OsString,
+    args: Vec<OsString>,
+    sandbox: StdioSandbox,
+}
+
+impl Launch {
+    fn spawn(&self, name: &str) -> Result<(Child, ChildStdin, ChildStdout), McpError> {
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit());
+        self.sandbox.apply(&mut command)?;
+
+        let mut child = command
+            .spawn()
+            .map_err(|err| McpError::ExecutionFailed(format!("failed to spawn `{name}`: {err}")))?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        Ok((child, stdin, stdout))
+    }
+}
+
+impl StdioTransport {
+    /// Spawn `program` with `args` under `sandbox`. The child's stderr is
+    /// inherited so server diagnostics stay visible.
+    pub fn spawn<S: AsRef<OsStr>>(
+        program: impl AsRef<OsStr>,
+        args: impl IntoIterator<Item = S>,
+        sandbox: &StdioSandbox,
+    ) -> Result<Self, McpError> {
+        let launch = Launch {
+            program: program.as_ref().to_os_string(),
+            args: args
+                .into_iter()
+                .map(|arg| arg.as_ref().to_os_string())
+                .collect(),
+            sandbox: sandbox.clone(),
+        };
+        let name = launch.program.to_string_lossy().into_owned();
+        let (child, stdin, stdout) = launch.spawn(&name)?;
+        Ok(Self {
+            program: name,
+            launch,
+            child: Mutex::new(child),
+            stdin: Mutex::new(Some(stdin)),
+            stdout: Mutex::new(BufReader::new(stdout)),
+            grace_period: Duration::from_secs(5),
+        })
+    }
+
+    /// How long [`RemoteTransport::close`] waits for the child to exit after
+    /// closing its stdin before killing it.
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// OS process id of the server.
+    pub fn pid(&self) -> u32 {
+        self.child.lock().unwrap().id()
+    }
+
+    fn exited(&self, reason: impl std::fmt::Display) -> McpError {
+        McpError::Transient(self.program.clone(), reason.to_string())
+    }
+}
+
+impl RemoteTransport for StdioTransport {
+    fn send(&self, message: &Value) -> Result<Option<Value>, McpError> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        // Holding the reader for the whole exchange keeps concurrent callers
+        // from consuming each other's responses.
+        let mut stdout = self.stdout.lock().unwrap();
+        {
+            let mut stdin = self.stdin.lock().unwrap();
+            let stdin = stdin
+                .as_mut()
+                .ok_or_else(|| self.exited("transport is closed"))?;
+            stdin
+                .write_all(line.as_bytes())
+                .and_then(|()| stdin.flush())
+                .map_err(|err| self.exited(format!("write failed: {err}")))?;
+        }
+
+        let Some(id) = message.get("id") else {
+            return Ok(None);
+        };
+        // Skip notifications and server-initiated requests until our response.
+        let mut buffer = String::new();
+        loop {
+            buffer.clear();
+            let read = stdout
+                .read_line(&mut buffer)
+                .map_err(|err| self.exited(format!("read failed: {err}")))?;
+            if read == 0 {
+                return Err(self.exited("server closed its output"));
+            }
+            let Ok(frame) = codec::from_str::<Value>(buffer.trim()) else {
+                continue;
+            };
+            if frame.get("id") == Some(id) && frame.get("method").is_none() {
+                return Ok(Some(frame));
+            }
+        }
+    }
+
+    /// Close the child's stdin, wait out the grace period, then terminate it:
+    /// `SIGTERM` first on Unix, then a kill.
+    fn close(&self) -> Result<(), McpError> {
+        self.stdin.lock().unwrap().take();
+        let mut child = self.child.lock().unwrap();
+        if wait_for_exit(&mut child, self.grace_period)? {
+            return Ok(());
+        }
+
+        #[cfg(unix)]
+        {
+            // SAFETY: signalling a child we spawned and have not yet reaped.
+            unsafe {
+                libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+            }
+            if wait_for_exit(&mut child, self.grace_period)? {
+                return Ok(());
+            }
+        }
+
+        child.kill()?;
+        child.wait()?;
+        Ok(())
+    }
+
+    /// Respawn the server with the original command and sandbox, killing the
+    /// old process if it is still running. Closed transports stay closed.
+    fn restart(&self) -> Result<bool, McpError> {
+        // Same lock order as `send` and `close`: reader, writer, process.
+        let mut stdout = self.stdout.lock().unwrap();
+        let mut stdin = self.stdin.lock().unwrap();
+        if stdin.is_none() {
+            // Closed on purpose; do not bring the server back.
+            return Ok(false);
+        }
+        let mut child = self.child.lock().unwrap();
+        if child.try_wait()?.is_none() {
+            child.kill()?;
+            child.wait()?;
+        }
+
+        let (new_child, new_stdin, new_stdout) = self.launch.spawn(&self.program)?;
+        *child = new_child;
+        *stdin = Some(new_stdin);
+        *stdout = BufReader::new(new_stdout);
+        Ok(true)
+    }
+}
+
+impl Drop for StdioTransport {
+    fn drop(&mut self) {
+        // Never leave an orphaned server behind, even without a clean close.
+        let child = self.child.get_mut().unwrap_or_else(|err| err.into_inner());
+        if let Ok(None) = child.try_wait() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Poll until the child exits or `timeout` passes; `true` once it has exited.
+fn wait_for_exit(child: &mut Child, timeout: Duration) -> Result<bool, McpError> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if child.try_wait()?.is_some() {
+            return Ok(true);
+        }
+        if Instant::now() >= deadline {
+            return Ok(false);
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A shell "server" that answers every line with a fixed response for id 1,
+    /// preceded by a notification.
+    fn echo_server(sandbox: &StdioSandbox) -> StdioTransport {
+        let script = r#"while read -r line; do
+            printf '%s\n' '{"jsonrpc":"2.0","method":"notifications/message"}'
+            printf '{"jsonrpc":"2.0","id":1,"result":{"home":"%s","pwd":"%s"}}\n' "${HOME:-}" "$(pwd)"
+        done"#;
+        StdioTransport::spawn("sh", ["-c", script], sandbox).expect("spawn")
+    }
+
+    #[test]
+    fn exchanges_lines_under_a_cleared_environment() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let sandbox = StdioSandbox {
+            env_allowlist: Some(vec!["PATH".into()]),
+            working_dir: Some(dir.path().to_path_buf()),
+            max_cpu_seconds: Some(10),
+            ..Default::default()
+        };
+        let transport = echo_server(&sandbox);
+
+        let response = transport
+            .send(&json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" }))
+            .expect("send")
+            .expect("response");
+        assert_eq!(response["result"]["home"], "", "HOME is not allowlisted");
+        let pwd = std::fs::canonicalize(dir.path()).expect("canonical dir");
+        assert_eq!(response["result"]["pwd"], pwd.to_string_lossy().as_ref());
+
+        transport.close().expect("close");
+        assert!(transport.send(&json!({ "id": 1 })).is_err());
+    }
+
+    #[test]
+    fn close_escalates_when_the_server_ignores_eof() {
+        let transport =
+            StdioTransport::spawn("sh", ["-c", "trap '' TERM; sleep 30"], &Default::default())
+                .expect("spawn")
+                .with_grace_period(Duration::from_millis(100));
+        let started = Instant::now();
+        transport.close().expect("close");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn restart_respawns_a_crashed_server() {
+        let transport = echo_server(&StdioSandbox::default());
+        let first = transport.pid();
+        // SAFETY: killing the child we spawned.
+        unsafe {
+            libc::kill(first as libc::pid_t, libc::SIGKILL);
+        }
+        assert!(
+            transport
+                .send(&json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" }))
+                .is_err()
+        );
+
+        assert!(transport.restart().expect("restart"));
+        assert_ne!(transport.pid(), first);
+        transport
+            .send(&json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" }))
+            .expect("send after restart")
+            .expect("response");
+
+        transport.close().expect("close");
+        assert!(!transport.restart().expect("restart after close"));
+    }
+}
@@ -0,0 +1,452 @@
+//! Flow-controlled consumption of Streamable HTTP responses.
+//!
+//! [`HttpTransport::stream`](crate::HttpTransport::stream) returns a
+//! [`MessageStream`]: an async [`Stream`] of the JSON-RPC messages in a
+//! response, i.e. the server's notifications (progress, logs) followed by the
+//! response itself. The body is read on a dedicated thread into a buffer
+//! bounded by a [`StreamBuffer`], in messages and in bytes. Once the buffer is
+//! full the thread stops reading, so a slow consumer holds the connection back
+//! through TCP flow control instead of the host buffering an unbounded body.
+//! A single message over [`StreamBuffer::max_message_bytes`] ends the stream
+//! with an error before it is read in full. Dropping the stream ends the read
+//! and closes the connection.
+
+use std::io::{self, BufRead, BufReader, Read};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+use std::thread;
+
+use futures_core::Stream;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::codec;
+use crate::types::McpError;
+
+/// How far a [`MessageStream`] reads ahead of its consumer.
+///
+/// Converts from a message count, keeping the default byte limits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamBuffer {
+    /// Messages held for the consumer.
+    pub messages: usize,
+    /// Bytes of encoded messages held for the consumer. A message larger than
+    /// this is still read once the buffer is empty.
+    pub bytes: usize,
+    /// Largest single message, in bytes.
+    pub max_message_bytes: usize,
+}
+
+impl Default for StreamBuffer {
+    fn default() -> Self {
+        Self {
+            messages: 16,
+            bytes: 8 * 1024 * 1024,
+            max_message_bytes: 16 * 1024 * 1024,
+        }
+    }
+}
+
+impl From<usize> for StreamBuffer {
+    fn from(messages: usize) -> Self {
+        Self {
+            messages,
+            ..Self::default()
+        }
+    }
+}
+
+/// Async stream of the JSON-RPC messages of one response.
+///
+/// Ends after the response matching the request id, or when the body ends.
+#[derive(Debug)]
+pub struct MessageStream {
+    messages: mpsc::Receiver<(Result<Value, McpError>, usize)>,
+    read_ahead: Arc<ReadAhead>,
+}
+
+impl MessageStream {
+    /// Read `body` on a new thread, reading at most `buffer` ahead.
+    ///
+    /// `event_stream` selects SSE framing; otherwise the body is a single JSON
+    /// message. `failure` turns read errors into the error yielded last.
+    pub(crate) fn spawn(
+        body: impl Read + Send + 'static,
+        event_stream: bool,
+        id: Option<Value>,
+        buffer: StreamBuffer,
+        failure: impl Fn(String) -> McpError + Send + 'static,
+    ) -> Self {
+        let (sender, messages) = mpsc::channel(buffer.messages.max(1));
+        let read_ahead = Arc::new(ReadAhead::default());
+        let sender = Sender {
+            messages: sender,
+            read_ahead: Arc::clone(&read_ahead),
+            max_bytes: buffer.bytes,
+        };
+        let max_message_bytes = buffer.max_message_bytes;
+        thread::spawn(move || {
+            let result = if event_stream {
+                pump_events(
+                    BufReader::new(body),
+                    id.as_ref(),
+                    max_message_bytes,
+                    &sender,
+                )
+            } else {
+                pump_json(body, max_message_bytes, &sender)
+            };
+            if let Err(err) = result {
+                sender.send(Err(failure(err.to_string())), 0);
+            }
+        });
+        Self {
+            messages,
+            read_ahead,
+        }
+    }
+
+    /// Wait for the next message from synchronous code.
+    ///
+    /// Must not be called from within an async runtime.
+    pub fn blocking_next(&mut self) -> Option<Result<Value, McpError>> {
+        let (message, size) = self.messages.blocking_recv()?;
+        self.read_ahead.release(size);
+        Some(message)
+    }
+}
+
+impl Stream for MessageStream {
+    type Item = Result<Value, McpError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.messages.poll_recv(cx).map(|received| {
+            let (message, size) = received?;
+            self.read_ahead.release(size);
+            Some(message)
+        })
+    }
+}
+
+impl Drop for MessageStream {
+    fn drop(&mut self) {
+        self.read_ahead.close();
+    }
+}
+
+/// Bytes of messages read but not yet consumed.
+#[derive(Debug, Default)]
+struct ReadAhead {
+    state: Mutex<ReadAheadState>,
+    changed: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct ReadAheadState {
+    bytes: usize,
+    closed: bool,
+}
+
+impl ReadAhead {
+    /// Wait until `size` more bytes fit under `max`, then count them. Returns
+    /// `false` once the stream was dropped.
+    fn reserve(&self, size: usize, max: usize) -> bool {
+        let mut state = self.lock();
+        while !state.closed && state.bytes > 0 && state.bytes + size > max {
+            state = self
+                .changed
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        state.bytes += size;
+        !state.closed
+    }
+
+    fn release(&self, size: usize) {
+        self.lock().bytes -= size;
+        self.changed.notify_all();
+    }
+
+    fn close(&self) {
+        self.lock().closed = true;
+        self.changed.notify_all();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ReadAheadState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+struct Sender {
+    messages: mpsc::Sender<(Result<Value, McpError>, usize)>,
+    read_ahead: Arc<ReadAhead>,
+    max_bytes: usize,
+}
+
+impl Sender {
+    /// Hand a message of `size` encoded bytes to the consumer, waiting while
+    /// the buffer is full. Returns `false` once the stream was dropped.
+    fn send(&self, message: Result<Value, McpError>, size: usize) -> bool {
+        self.read_ahead.reserve(size, self.max_bytes)
+            && self.messages.blocking_send((message, size)).is_ok()
+    }
+}
+
+/// Forward SSE `data` events until the response to `id` or the end of the body.
+fn pump_events(
+    mut body: impl BufRead,
+    id: Option<&Value>,
+    max_message_bytes: usize,
+    sender: &Sender,
+) -> io::Result<()> {
+    let mut data = String::new();
+    let mut line = Vec::new();
+    // Room for the `data:` field name around a maximal message.
+    while read_line(&mut body, &mut line, max_message_bytes + 6)? {
+        let line = std::str::from_utf8(&line)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        if line.is_empty() {
+            if !dispatch(&mut data, id, sender) {
+                return Ok(());
+            }
+        } else if let Some(chunk) = line.strip_prefix("data:") {
+            if !data.is_empty() {
+                data.push('\n');
+            }
+            data.push_str(chunk.strip_prefix(' ').unwrap_or(chunk));
+            if data.len() > max_message_bytes {
+                return Err(too_large(max_message_bytes));
+            }
+        }
+    }
+    dispatch(&mut data, id, sender);
+    Ok(())
+}
+
+/// Read one line into `line`, without its line ending, failing as soon as it
+/// grows past `max` bytes. Returns `false` at the end of the body.
+fn read_line(body: &mut impl BufRead, line: &mut Vec<u8>, max: usize) -> io::Result<bool> {
+    line.clear();
+    loop {
+        let available = body.fill_buf()?;
+        if available.is_empty() {
+            return Ok(!line.is_empty());
+        }
+        let end = available.iter().position(|&byte| byte == b'\n');
+        let chunk = &available[..end.unwrap_or(available.len())];
+        if line.len() + chunk.len() > max {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line exceeds the limit of {max} bytes"),
+            ));
+        }
+        line.extend_from_slice(chunk);
+        let consumed = end.map_or(chunk.len(), |end| end + 1);
+        body.consume(consumed);
+        if end.is_some() {
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            return Ok(true);
+        }
+    }
+}
+
+fn too_large(max: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("message exceeds the limit of {max} bytes"),
+    )
+}
+
+/// Send the buffered event, if it holds JSON. Returns whether to keep reading.
+fn dispatch(data: &mut String, id: Option<&Value>, sender: &Sender) -> bool {
+    let event = std::mem::take(data);
+    let Ok(message) = codec::from_str::<Value>(&event) else {
+        return true;
+    };
+    let last = id.is_some() && message.get("id") == id;
+    sender.send(Ok(message), event.len()) && !last
+}
+
+/// Forward a plain JSON body; empty bodies (accepted notifications) yield nothing.
+fn pump_json(body: impl Read, max_message_bytes: usize, sender: &Sender) -> io::Result<()> {
+    let mut raw = Vec::new();
+    body.take(max_message_bytes as u64 + 1)
+        .read_to_end(&mut raw)?;
+    if raw.len() > max_message_bytes {
+        return Err(too_large(max_message_bytes));
+    }
+    if !raw.trim_ascii().is_empty() {
+        sender.send(codec::from_slice(&raw).map_err(McpError::from), raw.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::VecDeque;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Body handing out one chunk per read, counting the reads.
+    struct Chunked {
+        chunks: VecDeque<Vec<u8>>,
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl Read for Chunked {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            let Some(chunk) = self.chunks.pop_front() else {
+                return Ok(0);
+            };
+            buf[..chunk.len()].copy_from_slice(&chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    fn event(message: Value) -> Vec<u8> {
+        format!("event: message\ndata: {message}\n\n").into_bytes()
+    }
+
+    fn next(stream: &mut MessageStream) -> impl Future<Output = Option<Result<Value, McpError>>> {
+        std::future::poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx))
+    }
+
+    #[tokio::test]
+    async fn streams_events_with_bounded_read_ahead() {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let mut chunks: VecDeque<Vec<u8>> = (0..20)
+            .map(|n| event(json!({"method": "notifications/progress", "params": {"progress": n}})))
+            .collect();
+        chunks.push_back(event(json!({"jsonrpc": "2.0", "id": 7, "result": {}})));
+        chunks.push_back(event(json!({"method": "after-the-response"})));
+        let body = Chunked {
+            chunks,
+            reads: Arc::clone(&reads),
+        };
+        let mut stream = MessageStream::spawn(body, true, Some(json!(7)), 2.into(), |message| {
+            McpError::Transient("test".into(), message)
+        });
+
+        // With nobody consuming, the reader stalls once the buffer is full.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(
+            reads.load(Ordering::SeqCst) <= 4,
+            "read ahead of the consumer"
+        );
+
+        let mut progress = 0;
+        let mut last = None;
+        while let Some(message) = next(&mut stream).await {
+            let message = message.expect("message");
+            if message.get("id").is_some() {
+                last = Some(message);
+            } else {
+                assert_eq!(message["params"]["progress"], progress);
+                progress += 1;
+            }
+        }
+        assert_eq!(progress, 20);
+        assert_eq!(last.expect("response")["id"], 7);
+    }
+
+    #[tokio::test]
+    async fn read_ahead_is_bounded_in_bytes() {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let chunks: VecDeque<Vec<u8>> = (0..20)
+            .map(|n| event(json!({"method": "notifications/message", "params": {"n": n}})))
+            .collect();
+        let body = Chunked {
+            chunks,
+            reads: Arc::clone(&reads),
+        };
+        let buffer = StreamBuffer {
+            messages: 100,
+            bytes: 100,
+            ..StreamBuffer::default()
+        };
+        let mut stream = MessageStream::spawn(body, true, None, buffer, |message| {
+            McpError::Transient("test".into(), message)
+        });
+
+        // Each message is about 50 bytes, so the reader stalls within two.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(
+            reads.load(Ordering::SeqCst) <= 4,
+            "read ahead of the consumer"
+        );
+        let mut received = 0;
+        while let Some(message) = next(&mut stream).await {
+            assert_eq!(message.expect("message")["params"]["n"], received);
+            received += 1;
+        }
+        assert_eq!(received, 20);
+    }
+
+    #[test]
+    fn oversized_messages_end_the_stream() {
+        let buffer = StreamBuffer {
+            max_message_bytes: 64,
+            ..StreamBuffer::default()
+        };
+        let failure = |message| McpError::Transient("test".into(), message);
+        let small = event(json!({"method": "notifications/message"}));
+        let large = event(json!({"method": "notifications/message", "params": "x".repeat(100)}));
+        let body = std::io::Cursor::new([small, large].concat());
+        let mut stream = MessageStream::spawn(body, true, None, buffer, failure);
+        stream.blocking_next().expect("small").expect("ok");
+        let err = stream
+            .blocking_next()
+            .expect("error")
+            .expect_err("too large");
+        assert!(err.to_string().contains("exceeds the limit"), "{err}");
+        assert!(stream.blocking_next().is_none());
+
+        let body = std::io::Cursor::new(format!(r#"{{"id":1,"result":"{}"}}"#, "x".repeat(100)));
+        let mut stream = MessageStream::spawn(body, false, Some(json!(1)), buffer, failure);
+        let err = stream
+            .blocking_next()
+            .expect("error")
+            .expect_err("too large");
+        assert!(
+            err.to_string().contains("exceeds the limit of 64 bytes"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn json_bodies_are_a_single_message_and_read_errors_end_the_stream() {
+        let body = std::io::Cursor::new(br#"{"jsonrpc":"2.0","id":1,"result":{}}"#.to_vec());
+        let mut stream = MessageStream::spawn(body, false, Some(json!(1)), 1.into(), |message| {
+            McpError::Transient("test".into(), message)
+        });
+        assert_eq!(
+            stream.blocking_next().expect("message").expect("ok")["id"],
+            1
+        );
+        assert!(stream.blocking_next().is_none());
+
+        struct Broken;
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("connection reset"))
+            }
+        }
+        let mut stream = MessageStream::spawn(Broken, true, None, 1.into(), |message| {
+            McpError::Transient("test".into(), message)
+        });
+        let err = stream
+            .blocking_next()
+            .expect("error")
+            .expect_err("read failed");
+        assert!(err.to_string().contains("connection reset"), "{err}");
+    }
+}
@@ -0,0 +1,249 @@
+//! Wire-level tap for remote MCP transports.
+//!
+//! [`TapTransport`] wraps any [`RemoteTransport`] and hands the raw JSON-RPC
+//! frames it exchanges to a sink: tracing by default, or a callback. Frames are
+//! truncated to a size limit, and a sampling ratio decides per exchange whether
+//! a request and its response are tapped, so the tap can stay enabled against
+//! busy third-party servers while debugging interop issues.
+
+use std::sync::Arc;
+
+use rand::distr::{Distribution, Uniform};
+use serde_json::Value;
+use tracing::debug;
+
+use crate::remote::RemoteTransport;
+use crate::secret::redact_json;
+use crate::types::McpError;
+
+/// Direction of a tapped frame, from the client's point of view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameDirection {
+    Outbound,
+    Inbound,
+}
+
+impl FrameDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FrameDirection::Outbound => "outbound",
+            FrameDirection::Inbound => "inbound",
+        }
+    }
+}
+
+/// A JSON-RPC frame observed on the wire.
+#[derive(Clone, Debug)]
+pub struct Frame {
+    pub direction: FrameDirection,
+    /// Method of the request this frame belongs to.
+    pub method: Option<String>,
+    /// JSON-RPC id, absent for notifications.
+    pub id: Option<Value>,
+    /// Serialized frame, cut to [`TapConfig::max_frame_bytes`].
+    pub body: String,
+    /// Size of the full serialized frame in bytes.
+    pub size: usize,
+    pub truncated: bool,
+}
+
+/// Callback receiving tapped frames.
+pub type FrameSink = Arc<dyn Fn(&Frame) + Send + Sync>;
+
+/// Limits applied by a [`TapTransport`].
+#[derive(Clone, Debug)]
+pub struct TapConfig {
+    /// Fraction of exchanges to tap, from `0.0` (none) to `1.0` (all).
+    pub sample_ratio: f64,
+    /// Frames larger than this are truncated before reaching the sink.
+    pub max_frame_bytes: usize,
+}
+
+impl Default for TapConfig {
+    fn default() -> Self {
+        Self {
+            sample_ratio: 1.0,
+            max_frame_bytes: 16 * 1024,
+        }
+    }
+}
+
+/// Transport wrapper that taps the frames exchanged by `inner`.
+pub struct TapTransport<T> {
+    inner: T,
+    config: TapConfig,
+    sink: FrameSink,
+}
+
+impl<T: RemoteTransport> TapTransport<T> {
+    /// Tap `inner`, logging frames at debug level under the `greentic_mcp::tap` target.
+    pub fn new(inner: T, config: TapConfig) -> Self {
+        Self {
+            inner,
+            config,
+            sink: Arc::new(log_frame),
+        }
+    }
+
+    /// Forward frames to `sink` instead of logging them.
+    pub fn with_sink(mut self, sink: impl Fn(&Frame) + Send + Sync + 'static) -> Self {
+        self.sink = Arc::new(sink);
+        self
+    }
+
+    /// The wrapped transport.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    fn sampled(&self) -> bool {
+        let ratio = self.config.sample_ratio;
+        if ratio >= 1.0 {
+            return true;
+        }
+        if ratio <= 0.0 || ratio.is_nan() {
+            return false;
+        }
+        let uniform = Uniform::new(0.0f64, 1.0f64).expect("valid sample bounds");
+        uniform.sample(&mut rand::rng()) < ratio
+    }
+
+    fn emit(&self, direction: FrameDirection, method: Option<&str>, frame: &Value) {
+        let mut redacted = frame.clone();
+        redact_json(&mut redacted);
+        let mut body = redacted.to_string();
+        let size = body.len();
+        let truncated = size > self.config.max_frame_bytes;
+        if truncated {
+            let mut cut = self.config.max_frame_bytes;
+            while !body.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            body.truncate(cut);
+        }
+        (self.sink)(&Frame {
+            direction,
+            method: method.map(str::to_owned),
+            id: frame.get("id").cloned(),
+            body,
+            size,
+            truncated,
+        });
+    }
+}
+
+impl<T: RemoteTransport> RemoteTransport for TapTransport<T> {
+    fn send(&self, message: &Value) -> Result<Option<Value>, McpError> {
+        if !self.sampled() {
+            return self.inner.send(message);
+        }
+
+        let method = message.get("method").and_then(Value::as_str);
+        self.emit(FrameDirection::Outbound, method, message);
+        let response = self.inner.send(message)?;
+        if let Some(response) = &response {
+            self.emit(FrameDirection::Inbound, method, response);
+        }
+        Ok(response)
+    }
+
+    fn close(&self) -> Result<(), McpError> {
+        self.inner.close()
+    }
+
+    fn restart(&self) -> Result<bool, McpError> {
+        self.inner.restart()
+    }
+}
+
+fn log_frame(frame: &Frame) {
+    debug!(
+        target: "greentic_mcp::tap",
+        direction = frame.direction.as_str(),
+        method = frame.method.as_deref().unwrap_or_default(),
+        size = frame.size,
+        truncated = frame.truncated,
+        "{}",
+        frame.body
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    struct Echo;
+
+    impl RemoteTransport for Echo {
+        fn send(&self, message: &Value) -> Result<Option<Value>, McpError> {
+            Ok(message.get("id").map(
+                |id| json!({ "jsonrpc": "2.0", "id": id, "result": { "echo": message["params"] } }),
+            ))
+        }
+    }
+
+    fn tapped(config: TapConfig) -> (TapTransport<Echo>, Arc<Mutex<Vec<Frame>>>) {
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let sink = frames.clone();
+        let transport = TapTransport::new(Echo, config)
+            .with_sink(move |frame: &Frame| sink.lock().unwrap().push(frame.clone()));
+        (transport, frames)
+    }
+
+    #[test]
+    fn taps_requests_responses_and_truncates() {
+        let (transport, frames) = tapped(TapConfig {
+            sample_ratio: 1.0,
+            max_frame_bytes: 40,
+        });
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "tools/call",
+            "params": { "name": "echo", "arguments": { "text": "é".repeat(40) } }
+        });
+        let response = transport.send(&request).expect("send").expect("response");
+        assert_eq!(response["id"], 7);
+
+        let frames = frames.lock().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].direction, FrameDirection::Outbound);
+        assert_eq!(frames[1].direction, FrameDirection::Inbound);
+        assert_eq!(frames[1].method.as_deref(), Some("tools/call"));
+        assert_eq!(frames[1].id, Some(json!(7)));
+        assert!(frames.iter().all(|frame| frame.truncated));
+        assert!(frames.iter().all(|frame| frame.body.len() <= 40));
+    }
+
+    #[test]
+    fn masks_credential_fields() {
+        let (transport, frames) = tapped(TapConfig::default());
+        transport
+            .send(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "tools/call",
+                "params": { "name": "login", "arguments": { "user": "ada", "password": "hunter2" } }
+            }))
+            .expect("send");
+
+        let frames = frames.lock().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert!(frames.iter().all(|frame| !frame.body.contains("hunter2")));
+        assert!(frames[0].body.contains("ada"));
+    }
+
+    #[test]
+    fn zero_ratio_taps_nothing() {
+        let (transport, frames) = tapped(TapConfig {
+            sample_ratio: 0.0,
+            ..Default::default()
+        });
+        transport
+            .send(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .expect("send");
+        assert!(frames.lock().unwrap().is_empty());
+    }
+}
@@ -80,6 +80,9 @@ pub enum McpError {
     Transient(String, String),
     #[error("internal error: {0}")]
     Internal(String),
+    #[cfg(feature = "native")]
+    #[error(transparent)]
+    PermissionDenied(#[from] greentic_mcp_exec::PermissionDenied),
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error(transparent)]
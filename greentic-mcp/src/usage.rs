@@ -0,0 +1,238 @@
+//! Per-conversation record of the tools an agent called.
+//!
+//! A [`UsageLedger`] keeps, for each session or conversation id, every tool
+//! call in order: which server and tool, a digest of the arguments, how large
+//! the result was, and whether it succeeded. Hosts query it to show users what
+//! the agent did on their behalf. Arguments are only stored as a SHA-256 of
+//! their canonical JSON, so the ledger can be displayed without leaking
+//! payloads, and identical calls share a digest.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::result_cache::write_canonical;
+
+/// One tool call in a session.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct UsageRecord {
+    /// Server (remote) or component (wasm) that served the call.
+    pub server: String,
+    pub tool: String,
+    /// Hex SHA-256 of the arguments with object keys sorted.
+    pub arguments_sha256: String,
+    /// Bytes of the JSON-encoded result or error.
+    pub result_bytes: usize,
+    pub ok: bool,
+    pub duration: Duration,
+    pub at: SystemTime,
+}
+
+impl UsageRecord {
+    /// Describe a finished call, hashing its arguments and measuring its result.
+    pub fn new(
+        server: &str,
+        tool: &str,
+        arguments: &Value,
+        result: &Value,
+        ok: bool,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            server: server.to_string(),
+            tool: tool.to_string(),
+            arguments_sha256: arguments_digest(arguments),
+            result_bytes: serde_json::to_vec(result).map_or(0, |bytes| bytes.len()),
+            ok,
+            duration,
+            at: SystemTime::now(),
+        }
+    }
+}
+
+/// Calls of one tool in a session, as returned by [`UsageLedger::summary`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ToolUsage {
+    pub server: String,
+    pub tool: String,
+    pub calls: u64,
+    pub errors: u64,
+    /// Distinct argument digests seen.
+    pub distinct_arguments: usize,
+    pub result_bytes: u64,
+}
+
+/// Tool calls recorded per session or conversation id.
+///
+/// Each session keeps at most `max_records` calls, dropping the oldest first.
+/// Share one ledger (behind an `Arc`) between every router serving the same
+/// conversations.
+pub struct UsageLedger {
+    max_records: usize,
+    sessions: Mutex<HashMap<String, VecDeque<UsageRecord>>>,
+}
+
+impl Default for UsageLedger {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+impl UsageLedger {
+    pub fn new(max_records: usize) -> Self {
+        Self {
+            max_records: max_records.max(1),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Append a call made for `session`.
+    pub fn record(&self, session: &str, record: UsageRecord) {
+        let mut sessions = self.lock();
+        let records = sessions.entry(session.to_string()).or_default();
+        if records.len() == self.max_records {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// Calls recorded for `session`, oldest first.
+    pub fn session(&self, session: &str) -> Vec<UsageRecord> {
+        self.lock()
+            .get(session)
+            .map(|records| records.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Per-tool totals for `session`, ordered by server and tool.
+    pub fn summary(&self, session: &str) -> Vec<ToolUsage> {
+        let sessions = self.lock();
+        let Some(records) = sessions.get(session) else {
+            return Vec::new();
+        };
+        let mut totals: BTreeMap<(&str, &str), (ToolUsage, Vec<&str>)> = BTreeMap::new();
+        for record in records {
+            let (usage, digests) = totals
+                .entry((record.server.as_str(), record.tool.as_str()))
+                .or_insert_with(|| {
+                    let usage = ToolUsage {
+                        server: record.server.clone(),
+                        tool: record.tool.clone(),
+                        ..ToolUsage::default()
+                    };
+                    (usage, Vec::new())
+                });
+            usage.calls += 1;
+            usage.errors += u64::from(!record.ok);
+            usage.result_bytes += record.result_bytes as u64;
+            if !digests.contains(&record.arguments_sha256.as_str()) {
+                digests.push(&record.arguments_sha256);
+            }
+        }
+        totals
+            .into_values()
+            .map(|(usage, digests)| ToolUsage {
+                distinct_arguments: digests.len(),
+                ..usage
+            })
+            .collect()
+    }
+
+    /// Ids of every session with recorded calls.
+    pub fn sessions(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.lock().keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Drop the records of `session`, e.g. when the conversation is deleted.
+    pub fn forget(&self, session: &str) {
+        self.lock().remove(session);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, VecDeque<UsageRecord>>> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Hex SHA-256 of `arguments` in canonical form.
+pub fn arguments_digest(arguments: &Value) -> String {
+    let mut canonical = String::new();
+    write_canonical(arguments, &mut canonical);
+    hex::encode(Sha256::digest(canonical.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn records_and_summarizes_per_session() {
+        let ledger = UsageLedger::new(3);
+        let took = Duration::from_millis(5);
+        ledger.record(
+            "conv-1",
+            UsageRecord::new(
+                "files",
+                "read",
+                &json!({"path": "a", "n": 1}),
+                &json!({"ok": true}),
+                true,
+                took,
+            ),
+        );
+        ledger.record(
+            "conv-1",
+            UsageRecord::new(
+                "files",
+                "read",
+                &json!({"n": 1, "path": "a"}),
+                &json!({"ok": true}),
+                true,
+                took,
+            ),
+        );
+        ledger.record(
+            "conv-1",
+            UsageRecord::new(
+                "files",
+                "delete",
+                &json!({"path": "a"}),
+                &json!({"ok": false}),
+                false,
+                took,
+            ),
+        );
+        ledger.record(
+            "conv-2",
+            UsageRecord::new("crm", "lookup", &json!({}), &json!(null), true, took),
+        );
+
+        let records = ledger.session("conv-1");
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].arguments_sha256, records[1].arguments_sha256);
+        assert_eq!(records[0].result_bytes, r#"{"ok":true}"#.len());
+
+        let summary = ledger.summary("conv-1");
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0].tool, "delete");
+        assert_eq!((summary[0].calls, summary[0].errors), (1, 1));
+        assert_eq!((summary[1].calls, summary[1].distinct_arguments), (2, 1));
+
+        ledger.record(
+            "conv-1",
+            UsageRecord::new("files", "list", &json!({}), &json!([]), true, took),
+        );
+        assert_eq!(ledger.session("conv-1")[0].tool, "read", "oldest dropped");
+        assert_eq!(ledger.sessions(), vec!["conv-1", "conv-2"]);
+        ledger.forget("conv-2");
+        assert!(ledger.session("conv-2").is_empty());
+    }
+}
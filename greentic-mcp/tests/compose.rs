@@ -1,4 +1,4 @@
-use greentic_mcp::compose::compose_router_with_bundled_adapter;
+use greentic_mcp::compose::{AdapterVariant, compose_router, compose_router_with_bundled_adapter};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -30,6 +30,21 @@ fn compose_invokes_wasm_tools() {
     );
     assert!(output.exists(), "output file not created");
 
+    let slim = temp.path().join("adapter_slim.component.wasm");
+    fs::write(&slim, b"slim adapter").expect("slim adapter write");
+    let variant = AdapterVariant::Slim(Some(slim.clone()));
+    compose_router(&router, &output, &variant, Some(&wasm_tools)).expect("compose slim ok");
+    let args = fs::read_to_string(&args_log).expect("args log");
+    assert!(
+        args.contains("adapter_slim.component.wasm"),
+        "missing slim adapter path in args: {args}"
+    );
+
+    let missing = AdapterVariant::Slim(Some(temp.path().join("missing.wasm")));
+    let err = compose_router(&router, &output, &missing, Some(&wasm_tools))
+        .expect_err("missing slim adapter");
+    assert!(err.to_string().contains("missing.wasm"), "{err}");
+
     // Safety: cleanup mirrors the test's own env mutation.
     unsafe {
         std::env::remove_var("GREENTIC_MCP_TEST_ARGS");
@@ -21,6 +21,9 @@ fn test_exec_config(runtime: RuntimePolicy) -> (ExecConfig, tempfile::TempDir) {
         runtime,
         http_enabled: false,
         secrets_store: None,
+        cost_sink: None,
+        transforms: None,
+        hooks: None,
     };
     (cfg, dir)
 }
@@ -68,7 +71,10 @@ async fn echo_transient_retries() {
         component: "echo-flaky".into(),
         action: "tool-invoke".into(),
         args: json!({"flaky": true, "message": "hello"}),
+        args_raw: None,
         tenant: None,
+        expected_digest: None,
+        priority: Default::default(),
     };
 
     let result = exec_with_retries_backend(req, &cfg, |req, cfg| {
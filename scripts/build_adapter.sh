@@ -13,6 +13,24 @@ TARGET_TRIPLE="wasm32-wasip2"
 OUT_DIR=""
 BIN_WASM=""
 COMP_WASM=""
+# --slim builds without the default rendering features (see the adapter README).
+SLIM=0
+FEATURE_ARGS=()
+COMP_SUFFIX=""
+
+for arg in "$@"; do
+  case "${arg}" in
+    --slim)
+      SLIM=1
+      FEATURE_ARGS=(--no-default-features)
+      COMP_SUFFIX="_slim"
+      ;;
+    *)
+      echo "error: unknown argument: ${arg} (expected --slim)" >&2
+      exit 2
+      ;;
+  esac
+done
 
 if [ "${DEBUG:-0}" = "1" ]; then
   set -x
@@ -47,7 +65,7 @@ echo "==> wasm32-wasip2 target confirmed; continuing"
 
 OUT_DIR="${ADAPTER_TARGET_DIR}/${TARGET_TRIPLE}/release"
 BIN_WASM="$OUT_DIR/greentic_mcp_adapter.wasm"
-COMP_WASM="$OUT_DIR/mcp_adapter_25_06_18.component.wasm"
+COMP_WASM="$OUT_DIR/mcp_adapter_25_06_18${COMP_SUFFIX}.component.wasm"
 LEGACY_OUT_DIR="${ROOT_DIR}/target/${TARGET_TRIPLE}/release"
 
 ensure_bindings() {
@@ -110,7 +128,10 @@ ensure_bindings
 
 echo "==> Step: build adapter crate"
 echo "==> Using target: ${TARGET_TRIPLE}"
-CARGO_TARGET_DIR="${ADAPTER_TARGET_DIR}" "cargo" "+${ACTIVE_TOOLCHAIN}" build --release --locked --target "${TARGET_TRIPLE}" -p greentic-mcp-adapter
+if [ "${SLIM}" = "1" ]; then
+  echo "==> Building the slim variant"
+fi
+CARGO_TARGET_DIR="${ADAPTER_TARGET_DIR}" "cargo" "+${ACTIVE_TOOLCHAIN}" build --release --locked --target "${TARGET_TRIPLE}" -p greentic-mcp-adapter ${FEATURE_ARGS[@]+"${FEATURE_ARGS[@]}"}
 
 echo "==> Step: componentize"
 if ! wasm-tools component new "$BIN_WASM" -o "$COMP_WASM" 2>"/tmp/componentize.err.$$"; then